@@ -0,0 +1,664 @@
+//! Expansion of the top-level `include` and `profile` nodes.
+//!
+//! These are handled on the config text before it is decoded: every `include` node is replaced
+//! with the contents of the files it matches, and every `profile` node is replaced with its
+//! children when it is the selected profile, or removed otherwise.
+
+use std::path::{Path, PathBuf};
+use std::{env, fmt};
+
+use miette::{
+    miette, Context, Diagnostic, IntoDiagnostic, LabeledSpan, NamedSource, Severity, SourceCode,
+};
+
+/// Maximum nesting of included files, to catch include cycles.
+const MAX_DEPTH: usize = 16;
+
+#[derive(knuffel::Decode)]
+struct Directives {
+    #[knuffel(children(name = "include"))]
+    include: Vec<Include>,
+    #[knuffel(children(name = "profile"))]
+    profile: Vec<Profile>,
+}
+
+#[derive(knuffel::Decode)]
+struct Include {
+    #[knuffel(argument)]
+    path: String,
+}
+
+#[derive(knuffel::Decode)]
+struct Profile {
+    #[knuffel(argument)]
+    name: String,
+}
+
+/// A top-level node in the config text.
+pub(crate) struct Node<'a> {
+    /// Full text of the node.
+    pub text: &'a str,
+    /// Node name, if it starts with a plain identifier.
+    pub name: &'a str,
+    /// Text of the children block without the braces, if any.
+    pub children: Option<&'a str>,
+    /// Text of the node before the children block.
+    pub header: &'a str,
+}
+
+/// Config text with its `include` and `profile` nodes expanded.
+#[derive(Debug, Default)]
+pub struct Expanded {
+    pub text: String,
+    /// Problems that don't prevent loading the config.
+    pub warnings: Vec<String>,
+    /// Contents of every file that was read.
+    sources: Vec<(PathBuf, String)>,
+    /// Ranges of `text` copied verbatim from the sources.
+    segments: Vec<Segment>,
+    /// Names of all profiles found in the config.
+    profiles: Vec<String>,
+}
+
+#[derive(Debug)]
+struct Segment {
+    /// Start of the segment in the expanded text.
+    start: usize,
+    len: usize,
+    /// Index into `sources`.
+    source: usize,
+    /// Start of the segment in the source text.
+    source_start: usize,
+}
+
+impl Expanded {
+    /// Finds the file, line and column corresponding to a byte offset in the expanded text.
+    ///
+    /// Line and column start from 1, and the column counts characters.
+    pub fn locate(&self, offset: usize) -> Option<(&Path, usize, usize)> {
+        let (source, offset) = self.source_offset(offset)?;
+        let (path, source) = &self.sources[source];
+
+        let before = source.get(..offset)?;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line = before.matches('\n').count() + 1;
+        let column = before[line_start..].chars().count() + 1;
+        Some((path, line, column))
+    }
+
+    /// Returns the path and the text of a source file, `0` being the main config file.
+    pub(crate) fn source(&self, source: usize) -> (&Path, &str) {
+        let (path, text) = &self.sources[source];
+        (path, text)
+    }
+
+    /// Finds the source and the byte offset in it corresponding to an offset in the expanded
+    /// text.
+    pub(crate) fn source_offset(&self, offset: usize) -> Option<(usize, usize)> {
+        let segment = self
+            .segments
+            .iter()
+            .rev()
+            .find(|s| s.start <= offset && offset <= s.start + s.len)?;
+        Some((
+            segment.source,
+            segment.source_start + (offset - segment.start),
+        ))
+    }
+
+    /// Points an error from parsing the expanded text at the files the problems come from.
+    pub fn relocate_error(&self, err: knuffel::Error) -> miette::Report {
+        // knuffel reports every problem as a related diagnostic.
+        let related: Vec<&dyn Diagnostic> =
+            err.related().map(Iterator::collect).unwrap_or_default();
+        let related = if related.is_empty() {
+            vec![&err as &dyn Diagnostic]
+        } else {
+            related
+        };
+
+        let related = related.into_iter().map(|diag| self.relocate(diag)).collect();
+        miette::Report::new(RelocatedError {
+            message: err.to_string(),
+            related,
+        })
+    }
+
+    fn relocate(&self, diag: &dyn Diagnostic) -> RelocatedDiagnostic {
+        let mut source = None;
+        let mut labels = Vec::new();
+        for label in diag.labels().into_iter().flatten() {
+            let Some((idx, offset)) = self.source_offset(label.offset()) else {
+                continue;
+            };
+            // A diagnostic can only show one source, so drop labels in other files.
+            if *source.get_or_insert(idx) != idx {
+                continue;
+            }
+
+            let (_, text) = &self.sources[idx];
+            let len = label.len().min(text.len().saturating_sub(offset));
+            labels.push(LabeledSpan::new(
+                label.label().map(String::from),
+                offset,
+                len,
+            ));
+        }
+
+        RelocatedDiagnostic {
+            message: diag.to_string(),
+            help: diag.help().map(|help| help.to_string()),
+            severity: diag.severity(),
+            source_code: source.map(|idx| {
+                let (path, text) = &self.sources[idx];
+                NamedSource::new(path.to_string_lossy(), text.clone())
+            }),
+            labels,
+        }
+    }
+
+    fn push_source(&mut self, source: usize, source_text: &str, range: &str) {
+        if range.is_empty() {
+            return;
+        }
+
+        let source_start = range.as_ptr() as usize - source_text.as_ptr() as usize;
+        self.segments.push(Segment {
+            start: self.text.len(),
+            len: range.len(),
+            source,
+            source_start,
+        });
+        self.text.push_str(range);
+    }
+}
+
+/// Error from parsing the expanded config text, with locations in the original files.
+#[derive(Debug)]
+pub struct RelocatedError {
+    message: String,
+    related: Vec<RelocatedDiagnostic>,
+}
+
+#[derive(Debug)]
+struct RelocatedDiagnostic {
+    message: String,
+    help: Option<String>,
+    severity: Option<Severity>,
+    source_code: Option<NamedSource>,
+    labels: Vec<LabeledSpan>,
+}
+
+impl fmt::Display for RelocatedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for RelocatedError {}
+
+impl Diagnostic for RelocatedError {
+    fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn Diagnostic> + 'a>> {
+        Some(Box::new(
+            self.related.iter().map(|diag| diag as &dyn Diagnostic),
+        ))
+    }
+}
+
+impl fmt::Display for RelocatedDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for RelocatedDiagnostic {}
+
+impl Diagnostic for RelocatedDiagnostic {
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        self.help
+            .as_ref()
+            .map(|help| Box::new(help) as Box<dyn fmt::Display + 'a>)
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        self.severity
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        self.source_code
+            .as_ref()
+            .map(|source| source as &dyn SourceCode)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        if self.labels.is_empty() {
+            return None;
+        }
+        Some(Box::new(self.labels.iter().cloned()))
+    }
+}
+
+/// Reads the config file at `path` and expands its `include` and `profile` nodes.
+///
+/// All files and directories that were read are appended to `files`, even when this returns an
+/// error, so that it's possible to watch them for changes.
+pub fn expand(
+    path: &Path,
+    profile: Option<&str>,
+    files: &mut Vec<PathBuf>,
+) -> miette::Result<Expanded> {
+    let mut expanded = Expanded::default();
+    expand_file(path, profile, 0, files, &mut expanded)?;
+
+    if let Some(profile) = profile {
+        if !expanded.profiles.iter().any(|p| p == profile) {
+            expanded
+                .warnings
+                .push(format!("profile {profile:?} is not defined in the config"));
+        }
+    }
+
+    Ok(expanded)
+}
+
+fn expand_file(
+    path: &Path,
+    profile: Option<&str>,
+    depth: usize,
+    files: &mut Vec<PathBuf>,
+    out: &mut Expanded,
+) -> miette::Result<()> {
+    if depth > MAX_DEPTH {
+        return Err(miette!(
+            "includes are nested too deeply at {path:?}, is there a cycle?"
+        ));
+    }
+
+    files.push(path.to_owned());
+
+    let text = std::fs::read_to_string(path)
+        .into_diagnostic()
+        .with_context(|| format!("error reading {path:?}"))?;
+
+    let source = out.sources.len();
+    out.sources.push((path.to_owned(), String::new()));
+    expand_text(&text, &text, source, path, profile, depth, files, out)?;
+    out.sources[source].1 = text;
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn expand_text(
+    text: &str,
+    source_text: &str,
+    source: usize,
+    path: &Path,
+    profile: Option<&str>,
+    depth: usize,
+    files: &mut Vec<PathBuf>,
+    out: &mut Expanded,
+) -> miette::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let filename = path.to_string_lossy();
+
+    let mut last = 0;
+    for node in top_level_nodes(text) {
+        if node.name != "include" && node.name != "profile" {
+            continue;
+        }
+
+        // Copy everything since the previous directive verbatim.
+        let start = node.text.as_ptr() as usize - text.as_ptr() as usize;
+        out.push_source(source, source_text, &text[last..start]);
+        last = start + node.text.len();
+
+        if node.name == "include" {
+            let directives: Directives = knuffel::parse(&filename, node.text)?;
+            for include in directives.include {
+                let included = resolve_include(dir, &include.path, files)?;
+                if included.is_empty() {
+                    out.warnings.push(format!(
+                        "include {:?} in {path:?} matches no files",
+                        include.path
+                    ));
+                }
+
+                for included in included {
+                    expand_file(&included, profile, depth + 1, files, out)?;
+                    out.text.push('\n');
+                }
+            }
+        } else {
+            let directives: Directives = knuffel::parse(&filename, node.header)?;
+            let mut selected = false;
+            for p in directives.profile {
+                selected |= Some(p.name.as_str()) == profile;
+                out.profiles.push(p.name);
+            }
+
+            if selected {
+                let children = node.children.unwrap_or_default();
+                expand_text(
+                    children,
+                    source_text,
+                    source,
+                    path,
+                    profile,
+                    depth,
+                    files,
+                    out,
+                )?;
+                out.text.push('\n');
+            }
+        }
+    }
+    out.push_source(source, source_text, &text[last..]);
+
+    Ok(())
+}
+
+/// Returns the files matching an include path, sorted by name.
+fn resolve_include(
+    dir: &Path,
+    include: &str,
+    files: &mut Vec<PathBuf>,
+) -> miette::Result<Vec<PathBuf>> {
+    let path = Path::new(include);
+    let path = match path.strip_prefix("~") {
+        Ok(rest) => {
+            let home = env::var_os("HOME").ok_or_else(|| miette!("HOME is not set"))?;
+            Path::new(&home).join(rest)
+        }
+        Err(_) => dir.join(path),
+    };
+
+    let pattern = path.to_string_lossy();
+    if !pattern.contains(['*', '?', '[']) {
+        return Ok(vec![path]);
+    }
+
+    // Watch the directory too, so that newly matching files are picked up.
+    if let Some(parent) = path.parent() {
+        files.push(parent.to_owned());
+    }
+
+    let paths = glob::glob(&pattern)
+        .into_diagnostic()
+        .with_context(|| format!("invalid include pattern {include:?}"))?;
+    let mut matches = paths
+        .filter_map(|path| match path {
+            Ok(path) => path.is_file().then_some(path),
+            Err(err) => {
+                warn!("error matching include pattern {include:?}: {err:?}");
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    matches.sort();
+    Ok(matches)
+}
+
+/// Splits the config text into its top-level nodes.
+///
+/// This only understands enough of KDL to find where the nodes start and end: strings, comments
+/// and children blocks. Any actual syntax errors are left for the decoder to report.
+pub(crate) fn top_level_nodes(text: &str) -> Vec<Node<'_>> {
+    let bytes = text.as_bytes();
+    let mut nodes = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        // Skip whitespace and comments between nodes.
+        match bytes[i] {
+            b if b.is_ascii_whitespace() || b == b';' => {
+                i += 1;
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                i = skip_line_comment(bytes, i);
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = skip_block_comment(bytes, i);
+                continue;
+            }
+            _ => (),
+        }
+
+        let start = i;
+        let mut depth = 0usize;
+        let mut children_start = None;
+        let mut children_end = None;
+
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\n' | b';' if depth == 0 => break,
+                b'"' => i = skip_string(bytes, i + 1, 0),
+                b'r' if matches!(bytes.get(i + 1), Some(b'"' | b'#'))
+                    && (i == start || !is_ident_byte(bytes[i - 1])) =>
+                {
+                    let hashes = bytes[i + 1..].iter().take_while(|b| **b == b'#').count();
+                    if bytes.get(i + 1 + hashes) == Some(&b'"') {
+                        i = skip_string(bytes, i + 2 + hashes, hashes);
+                    } else {
+                        i += 1;
+                    }
+                }
+                b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                    i = skip_line_comment(bytes, i);
+                }
+                b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                    i = skip_block_comment(bytes, i);
+                }
+                // Line continuation.
+                b'\\' if depth == 0 => {
+                    i += 1;
+                    while i < bytes.len() && bytes[i] != b'\n' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                b'{' => {
+                    if depth == 0 && children_start.is_none() {
+                        children_start = Some(i);
+                    }
+                    depth += 1;
+                    i += 1;
+                }
+                b'}' => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 && children_end.is_none() {
+                        children_end = Some(i);
+                    }
+                    i += 1;
+                }
+                _ => i += 1,
+            }
+        }
+
+        let end = i.min(bytes.len());
+        let node_text = &text[start..end];
+        let name_len = node_text.bytes().take_while(|b| is_ident_byte(*b)).count();
+
+        let (header, children) = match (children_start, children_end) {
+            (Some(cs), Some(ce)) => (&text[start..cs], Some(&text[cs + 1..ce])),
+            (Some(cs), None) => (&text[start..cs], Some(&text[cs + 1..end])),
+            _ => (node_text, None),
+        };
+
+        nodes.push(Node {
+            text: node_text,
+            name: &node_text[..name_len],
+            children,
+            header,
+        });
+    }
+
+    nodes
+}
+
+fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'-' || b == b'_'
+}
+
+/// Skips a string starting right after its opening quote, returning the index past its end.
+fn skip_string(bytes: &[u8], mut i: usize, hashes: usize) -> usize {
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if hashes == 0 => i += 2,
+            b'"' => {
+                let closing = bytes[i + 1..]
+                    .iter()
+                    .take(hashes)
+                    .take_while(|b| **b == b'#')
+                    .count();
+                i += 1;
+                if closing == hashes {
+                    return i + hashes;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+fn skip_line_comment(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() && bytes[i] != b'\n' {
+        i += 1;
+    }
+    i
+}
+
+fn skip_block_comment(bytes: &[u8], mut i: usize) -> usize {
+    let mut depth = 0usize;
+    while i < bytes.len() {
+        if bytes[i] == b'/' && bytes.get(i + 1) == Some(&b'*') {
+            depth += 1;
+            i += 2;
+        } else if bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/') {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    bytes.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(text: &str) -> Vec<&str> {
+        top_level_nodes(text).into_iter().map(|n| n.name).collect()
+    }
+
+    #[test]
+    fn splits_top_level_nodes() {
+        let text = r##"
+            // include "commented.kdl"
+            include "a.kdl"
+            layout { gaps 16; }
+            /* profile "x" { } */
+            profile "docked" {
+                output "eDP-1" { off; }
+            }
+            spawn-at-startup "sh" "-c" "echo }"; include r#"b"}.kdl"#
+        "##;
+        assert_eq!(
+            names(text),
+            [
+                "include",
+                "layout",
+                "profile",
+                "spawn-at-startup",
+                "include"
+            ]
+        );
+
+        let nodes = top_level_nodes(text);
+        assert_eq!(nodes[2].header.trim(), r#"profile "docked""#);
+        assert_eq!(
+            nodes[2].children.unwrap().trim(),
+            r#"output "eDP-1" { off; }"#
+        );
+        assert_eq!(nodes[4].text, r##"include r#"b"}.kdl"#"##);
+    }
+
+    #[test]
+    fn expands_selected_profile_only() {
+        let text = r#"
+prefer-no-csd
+profile "docked" {
+    screenshot-path null
+}
+profile "laptop" {
+    hotkey-overlay { skip-at-startup; }
+}
+"#;
+        let mut out = Expanded::default();
+        let mut files = Vec::new();
+        expand_text(
+            text,
+            text,
+            0,
+            Path::new("config.kdl"),
+            Some("docked"),
+            0,
+            &mut files,
+            &mut out,
+        )
+        .unwrap();
+        out.sources
+            .push((PathBuf::from("config.kdl"), text.to_owned()));
+
+        assert!(out.text.contains("screenshot-path null"));
+        assert!(!out.text.contains("profile"));
+        assert!(!out.text.contains("hotkey-overlay"));
+        assert_eq!(out.profiles, ["docked", "laptop"]);
+
+        // Locations in the expanded text map back to the original text.
+        let offset = out.text.find("screenshot-path").unwrap();
+        assert_eq!(out.locate(offset), Some((Path::new("config.kdl"), 4, 5)));
+    }
+
+    #[test]
+    fn relocates_parse_errors() {
+        let text = r#"
+prefer-no-csd
+profile "docked" {
+    screenshot-path null
+    unknown-node
+}
+"#;
+        let mut out = Expanded::default();
+        let mut files = Vec::new();
+        expand_text(
+            text,
+            text,
+            0,
+            Path::new("config.kdl"),
+            Some("docked"),
+            0,
+            &mut files,
+            &mut out,
+        )
+        .unwrap();
+        out.sources
+            .push((PathBuf::from("config.kdl"), text.to_owned()));
+
+        let err = crate::Config::parse("config.kdl", &out.text).unwrap_err();
+        let report = out.relocate_error(err);
+        let err = report.downcast_ref::<RelocatedError>().unwrap();
+
+        let diag = &err.related[0];
+        assert!(diag.source_code.is_some());
+        let offset = diag.labels[0].offset();
+        assert!(text[offset..].starts_with("unknown-node"));
+    }
+}
@@ -1,10 +1,12 @@
 #[macro_use]
 extern crate tracing;
 
+use std::cell::RefCell;
 use std::collections::HashSet;
 use std::ffi::OsStr;
 use std::ops::{Mul, MulAssign};
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use std::str::FromStr;
 use std::time::Duration;
 
@@ -12,10 +14,10 @@ use bitflags::bitflags;
 use knuffel::errors::DecodeError;
 use knuffel::Decode as _;
 use layer_rule::LayerRule;
-use miette::{miette, Context, IntoDiagnostic};
+use miette::{miette, Context, Diagnostic, IntoDiagnostic, Severity};
 use niri_ipc::{
-    ColumnDisplay, ConfiguredMode, LayoutSwitchTarget, PositionChange, SizeChange, Transform,
-    WorkspaceReferenceArg,
+    ColumnDisplay, ConfigDiagnostic, ConfiguredMode, DiagnosticSeverity, LayoutSwitchTarget,
+    OpacityChange, OsdKind, PositionChange, SizeChange, Transform, WorkspaceReferenceArg,
 };
 use smithay::backend::renderer::Color32F;
 use smithay::input::keyboard::keysyms::KEY_NoSymbol;
@@ -26,10 +28,14 @@ use smithay::reexports::input;
 pub const DEFAULT_BACKGROUND_COLOR: Color = Color::from_array_unpremul([0.25, 0.25, 0.25, 1.]);
 pub const DEFAULT_BACKDROP_COLOR: Color = Color::from_array_unpremul([0.15, 0.15, 0.15, 1.]);
 
+mod include;
 pub mod layer_rule;
+pub mod schema;
 
 mod utils;
 pub use utils::RegexEq;
+mod value;
+pub use value::{persist_value, value_names};
 
 #[derive(knuffel::Decode, Debug, PartialEq)]
 pub struct Config {
@@ -37,6 +43,8 @@ pub struct Config {
     pub input: Input,
     #[knuffel(children(name = "output"))]
     pub outputs: Outputs,
+    #[knuffel(children(name = "output-profile"))]
+    pub output_profiles: Vec<OutputProfile>,
     #[knuffel(children(name = "spawn-at-startup"))]
     pub spawn_at_startup: Vec<SpawnAtStartup>,
     #[knuffel(child, default)]
@@ -58,6 +66,8 @@ pub struct Config {
     #[knuffel(child, default)]
     pub hotkey_overlay: HotkeyOverlay,
     #[knuffel(child, default)]
+    pub power_menu: PowerMenu,
+    #[knuffel(child, default)]
     pub animations: Animations,
     #[knuffel(child, default)]
     pub gestures: Gestures,
@@ -73,9 +83,31 @@ pub struct Config {
     pub layer_rules: Vec<LayerRule>,
     #[knuffel(child, default)]
     pub binds: Binds,
+    #[knuffel(children(name = "binds-mode"))]
+    pub binds_modes: Vec<BindsMode>,
     #[knuffel(child, default)]
     pub switch_events: SwitchBinds,
     #[knuffel(child, default)]
+    pub lid_switch: LidSwitch,
+    #[knuffel(child, default)]
+    pub tablet_mode: TabletMode,
+    #[knuffel(child, default)]
+    pub idle: Idle,
+    #[knuffel(child, default)]
+    pub lock_screen: LockScreen,
+    #[knuffel(child, default)]
+    pub hooks: Hooks,
+    #[knuffel(child, default)]
+    pub notifications: Notifications,
+    #[knuffel(child, default)]
+    pub osd: Osd,
+    #[knuffel(child, default)]
+    pub power_saving: PowerSaving,
+    #[knuffel(child, default)]
+    pub hidden_windows: HiddenWindows,
+    #[knuffel(child, default)]
+    pub security: Security,
+    #[knuffel(child, default)]
     pub debug: DebugConfig,
     #[knuffel(children(name = "workspace"))]
     pub workspaces: Vec<Workspace>,
@@ -97,18 +129,26 @@ pub struct Input {
     pub tablet: Tablet,
     #[knuffel(child, default)]
     pub touch: Touch,
+    #[knuffel(children(name = "device"))]
+    pub devices: Vec<InputDevice>,
     #[knuffel(child)]
     pub disable_power_key_handling: bool,
     #[knuffel(child)]
+    pub mpris_media_keys: bool,
+    #[knuffel(child)]
     pub warp_mouse_to_focus: Option<WarpMouseToFocus>,
     #[knuffel(child)]
     pub focus_follows_mouse: Option<FocusFollowsMouse>,
     #[knuffel(child)]
+    pub output_barriers: Option<OutputBarriers>,
+    #[knuffel(child)]
     pub workspace_auto_back_and_forth: bool,
     #[knuffel(child, unwrap(argument, str))]
     pub mod_key: Option<ModKey>,
     #[knuffel(child, unwrap(argument, str))]
     pub mod_key_nested: Option<ModKey>,
+    #[knuffel(child, unwrap(argument), default)]
+    pub activation_policy: ActivationPolicy,
 }
 
 #[derive(knuffel::Decode, Debug, PartialEq, Eq)]
@@ -124,6 +164,16 @@ pub struct Keyboard {
     pub track_layout: TrackLayout,
     #[knuffel(child)]
     pub numlock: bool,
+    #[knuffel(child)]
+    pub sticky_keys: bool,
+    /// How long a key must be held before the press is accepted, in milliseconds.
+    #[knuffel(child, unwrap(argument), default)]
+    pub slow_keys_delay: u16,
+    /// Presses of the same key within this many milliseconds of its release are ignored.
+    #[knuffel(child, unwrap(argument), default)]
+    pub bounce_keys_delay: u16,
+    #[knuffel(child)]
+    pub layout_indicator: bool,
 }
 
 impl Default for Keyboard {
@@ -134,6 +184,10 @@ impl Default for Keyboard {
             repeat_rate: 25,
             track_layout: Default::default(),
             numlock: Default::default(),
+            sticky_keys: Default::default(),
+            slow_keys_delay: Default::default(),
+            bounce_keys_delay: Default::default(),
+            layout_indicator: Default::default(),
         }
     }
 }
@@ -155,6 +209,11 @@ pub struct Xkb {
 }
 
 impl Xkb {
+    /// Returns the comma-separated layouts from the `layout` option, like `us`.
+    pub fn layouts(&self) -> impl Iterator<Item = &str> {
+        self.layout.split(',').map(str::trim)
+    }
+
     pub fn to_xkb_config(&self) -> XkbConfig {
         XkbConfig {
             rules: &self.rules,
@@ -185,6 +244,8 @@ pub enum TrackLayout {
     Global,
     /// The layout change is window local.
     Window,
+    /// The layout change is workspace local.
+    Workspace,
 }
 
 #[derive(knuffel::Decode, Debug, Default, PartialEq)]
@@ -302,6 +363,51 @@ impl From<ClickMethod> for input::ClickMethod {
     }
 }
 
+/// Settings for input devices matched by name or USB id.
+///
+/// These are applied on top of the settings for the device type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputDevice {
+    pub name: Option<String>,
+    pub vid: Option<u32>,
+    pub pid: Option<u32>,
+    pub settings: InputDeviceSettings,
+}
+
+#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq)]
+pub struct InputDeviceSettings {
+    #[knuffel(child, unwrap(argument))]
+    pub off: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub tap: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub dwt: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub dwtp: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub drag: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub drag_lock: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub natural_scroll: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub accel_speed: Option<FloatOrInt<-1, 1>>,
+    #[knuffel(child, unwrap(argument, str))]
+    pub accel_profile: Option<AccelProfile>,
+    #[knuffel(child, unwrap(argument, str))]
+    pub scroll_method: Option<ScrollMethod>,
+    #[knuffel(child, unwrap(argument))]
+    pub scroll_button: Option<u32>,
+    #[knuffel(child, unwrap(argument, str))]
+    pub click_method: Option<ClickMethod>,
+    #[knuffel(child, unwrap(argument, str))]
+    pub tap_button_map: Option<TapButtonMap>,
+    #[knuffel(child, unwrap(argument))]
+    pub left_handed: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub middle_emulation: Option<bool>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AccelProfile {
     Adaptive,
@@ -360,9 +466,26 @@ pub struct Tablet {
     #[knuffel(child, unwrap(argument))]
     pub map_to_output: Option<String>,
     #[knuffel(child)]
+    pub map_to_focused_window: bool,
+    #[knuffel(child)]
+    pub active_area: Option<TabletArea>,
+    #[knuffel(child)]
     pub left_handed: bool,
 }
 
+/// Part of the tablet surface that is mapped to the target, as fractions of the full surface.
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
+pub struct TabletArea {
+    #[knuffel(property, default)]
+    pub x: FloatOrInt<0, 1>,
+    #[knuffel(property, default)]
+    pub y: FloatOrInt<0, 1>,
+    #[knuffel(property, default = FloatOrInt(1.))]
+    pub width: FloatOrInt<0, 1>,
+    #[knuffel(property, default = FloatOrInt(1.))]
+    pub height: FloatOrInt<0, 1>,
+}
+
 #[derive(knuffel::Decode, Debug, Default, PartialEq)]
 pub struct Touch {
     #[knuffel(child)]
@@ -375,6 +498,56 @@ pub struct Touch {
 pub struct FocusFollowsMouse {
     #[knuffel(property, str)]
     pub max_scroll_amount: Option<Percent>,
+    /// How long the pointer must stay over a window before it's focused.
+    #[knuffel(property)]
+    pub delay_ms: Option<u16>,
+    /// Don't change focus while the view is scrolling.
+    #[knuffel(property, default)]
+    pub ignore_while_scrolling: bool,
+    /// Only focus when the pointer enters the window contents, not the borders or the top bar.
+    #[knuffel(property, default)]
+    pub content_only: bool,
+}
+
+/// Resistance for the pointer moving between outputs.
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
+pub struct OutputBarriers {
+    /// How far past the output edge the pointer must be pushed to cross it, in logical pixels.
+    #[knuffel(child, unwrap(argument), default = Self::default().push_through)]
+    pub push_through: FloatOrInt<0, 65535>,
+    /// Never let the pointer cross near output corners.
+    #[knuffel(child)]
+    pub block_corners: bool,
+}
+
+impl Default for OutputBarriers {
+    fn default() -> Self {
+        Self {
+            push_through: FloatOrInt(50.),
+            block_corners: false,
+        }
+    }
+}
+
+/// What to do with activation requests that don't come from recent user interaction.
+#[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ActivationPolicy {
+    /// Focus the window anyway.
+    Focus,
+    /// Mark the window as urgent.
+    #[default]
+    MarkUrgent,
+    /// Do nothing.
+    Ignore,
+}
+
+/// Decoration mode to force on a window regardless of what it asks for.
+#[derive(knuffel::DecodeScalar, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecorationMode {
+    /// The compositor draws the decorations; the client must not draw its own title bar.
+    ServerSide,
+    /// The client draws its own decorations.
+    ClientSide,
 }
 
 #[derive(knuffel::Decode, Debug, PartialEq, Eq, Clone, Copy)]
@@ -454,6 +627,14 @@ pub struct Output {
     pub background_color: Option<Color>,
     #[knuffel(child)]
     pub backdrop_color: Option<Color>,
+    #[knuffel(child, unwrap(argument))]
+    pub mirror: Option<String>,
+    #[knuffel(child)]
+    pub modeline: Option<Modeline>,
+    #[knuffel(child)]
+    pub custom_mode: bool,
+    #[knuffel(child)]
+    pub wallpaper: Option<Wallpaper>,
 }
 
 impl Output {
@@ -483,10 +664,24 @@ impl Default for Output {
             variable_refresh_rate: None,
             background_color: None,
             backdrop_color: None,
+            mirror: None,
+            modeline: None,
+            custom_mode: false,
+            wallpaper: None,
         }
     }
 }
 
+#[derive(knuffel::Decode, Debug, Clone, PartialEq)]
+pub struct OutputProfile {
+    #[knuffel(argument)]
+    pub name: String,
+    #[knuffel(children(name = "output"))]
+    pub outputs: Outputs,
+    #[knuffel(children(name = "workspace"))]
+    pub workspaces: Vec<Workspace>,
+}
+
 #[derive(Debug, Clone)]
 pub struct OutputName {
     pub connector: String,
@@ -503,6 +698,65 @@ pub struct Position {
     pub y: i32,
 }
 
+/// Custom mode timings in the same format as X11 modelines.
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
+pub struct Modeline {
+    /// Pixel clock in MHz.
+    #[knuffel(argument)]
+    pub clock: FloatOrInt<0, 10000>,
+    #[knuffel(argument)]
+    pub hdisplay: u16,
+    #[knuffel(argument)]
+    pub hsync_start: u16,
+    #[knuffel(argument)]
+    pub hsync_end: u16,
+    #[knuffel(argument)]
+    pub htotal: u16,
+    #[knuffel(argument)]
+    pub vdisplay: u16,
+    #[knuffel(argument)]
+    pub vsync_start: u16,
+    #[knuffel(argument)]
+    pub vsync_end: u16,
+    #[knuffel(argument)]
+    pub vtotal: u16,
+    #[knuffel(argument, str)]
+    pub hsync_polarity: HSyncPolarity,
+    #[knuffel(argument, str)]
+    pub vsync_polarity: VSyncPolarity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HSyncPolarity {
+    Positive,
+    Negative,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VSyncPolarity {
+    Positive,
+    Negative,
+}
+
+#[derive(knuffel::Decode, Debug, Clone, PartialEq)]
+pub struct Wallpaper {
+    #[knuffel(argument)]
+    pub path: String,
+    #[knuffel(property, default)]
+    pub mode: WallpaperMode,
+}
+
+#[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WallpaperMode {
+    /// Scale the image to cover the whole output, cropping the overflow.
+    #[default]
+    Fill,
+    /// Scale the image to fit inside the output, filling the rest with the background color.
+    Fit,
+    /// Repeat the image at its original size.
+    Tile,
+}
+
 #[derive(knuffel::Decode, Debug, Clone, PartialEq, Default)]
 pub struct Vrr {
     #[knuffel(property, default = false)]
@@ -525,6 +779,10 @@ pub struct Layout {
     pub tab_indicator: TabIndicator,
     #[knuffel(child, default)]
     pub insert_hint: InsertHint,
+    #[knuffel(child, default)]
+    pub offscreen_column_indicator: OffscreenColumnIndicator,
+    #[knuffel(child, default)]
+    pub top_bar: TopBar,
     #[knuffel(child, unwrap(children), default)]
     pub preset_column_widths: Vec<PresetSize>,
     #[knuffel(child)]
@@ -543,6 +801,10 @@ pub struct Layout {
     pub gaps: FloatOrInt<0, 65535>,
     #[knuffel(child, default)]
     pub struts: Struts,
+    #[knuffel(child)]
+    pub smart_gaps: Option<SmartGaps>,
+    #[knuffel(child, unwrap(argument), default = Self::default().reading_mode_max_width)]
+    pub reading_mode_max_width: FloatOrInt<0, 65535>,
     #[knuffel(child, default = DEFAULT_BACKGROUND_COLOR)]
     pub background_color: Color,
 }
@@ -555,6 +817,8 @@ impl Default for Layout {
             shadow: Default::default(),
             tab_indicator: Default::default(),
             insert_hint: Default::default(),
+            offscreen_column_indicator: Default::default(),
+            top_bar: Default::default(),
             preset_column_widths: Default::default(),
             default_column_width: Default::default(),
             center_focused_column: Default::default(),
@@ -563,6 +827,8 @@ impl Default for Layout {
             default_column_display: ColumnDisplay::Normal,
             gaps: FloatOrInt(16.),
             struts: Default::default(),
+            smart_gaps: None,
+            reading_mode_max_width: FloatOrInt(1200.),
             preset_window_heights: Default::default(),
             background_color: DEFAULT_BACKGROUND_COLOR,
         }
@@ -573,12 +839,36 @@ impl Default for Layout {
 pub struct SpawnAtStartup {
     #[knuffel(arguments)]
     pub command: Vec<String>,
+    /// Name to refer to the command over IPC.
+    #[knuffel(property)]
+    pub name: Option<String>,
+    /// Working directory for the command.
+    #[knuffel(property)]
+    pub working_dir: Option<String>,
+    #[knuffel(property, default)]
+    pub restart: RestartPolicy,
+    /// Extra environment variables for the command, on top of the global `environment`.
+    #[knuffel(child, default)]
+    pub environment: Environment,
+}
+
+/// When to restart a command spawned at startup after it exits.
+#[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RestartPolicy {
+    #[default]
+    Never,
+    /// Restart when the command exits unsuccessfully or gets killed.
+    OnFailure,
+    /// Restart whenever the command exits.
+    Always,
 }
 
 #[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
 pub struct FocusRing {
     #[knuffel(child)]
     pub off: bool,
+    #[knuffel(child)]
+    pub hide_when_single_window: bool,
     #[knuffel(child, unwrap(argument), default = Self::default().width)]
     pub width: FloatOrInt<0, 65535>,
     #[knuffel(child, default = Self::default().active_color)]
@@ -599,6 +889,7 @@ impl Default for FocusRing {
     fn default() -> Self {
         Self {
             off: false,
+            hide_when_single_window: false,
             width: FloatOrInt(4.),
             active_color: Color::from_rgba8_unpremul(127, 200, 255, 255),
             inactive_color: Color::from_rgba8_unpremul(80, 80, 80, 255),
@@ -671,6 +962,8 @@ pub enum HueInterpolation {
 pub struct Border {
     #[knuffel(child)]
     pub off: bool,
+    #[knuffel(child)]
+    pub hide_when_single_window: bool,
     #[knuffel(child, unwrap(argument), default = Self::default().width)]
     pub width: FloatOrInt<0, 65535>,
     #[knuffel(child, default = Self::default().active_color)]
@@ -691,6 +984,7 @@ impl Default for Border {
     fn default() -> Self {
         Self {
             off: true,
+            hide_when_single_window: false,
             width: FloatOrInt(4.),
             active_color: Color::from_rgba8_unpremul(255, 200, 127, 255),
             inactive_color: Color::from_rgba8_unpremul(80, 80, 80, 255),
@@ -706,6 +1000,7 @@ impl From<Border> for FocusRing {
     fn from(value: Border) -> Self {
         Self {
             off: value.off,
+            hide_when_single_window: value.hide_when_single_window,
             width: value.width,
             active_color: value.active_color,
             inactive_color: value.inactive_color,
@@ -721,6 +1016,7 @@ impl From<FocusRing> for Border {
     fn from(value: FocusRing) -> Self {
         Self {
             off: value.off,
+            hide_when_single_window: value.hide_when_single_window,
             width: value.width,
             active_color: value.active_color,
             inactive_color: value.inactive_color,
@@ -910,6 +1206,56 @@ impl Default for InsertHint {
     }
 }
 
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
+pub struct OffscreenColumnIndicator {
+    #[knuffel(child)]
+    pub on: bool,
+    #[knuffel(child, unwrap(argument), default = Self::default().style)]
+    pub style: OffscreenColumnIndicatorStyle,
+    #[knuffel(child, unwrap(argument), default = Self::default().width)]
+    pub width: FloatOrInt<0, 65535>,
+    #[knuffel(child, default = Self::default().color)]
+    pub color: Color,
+    /// How long the indicator stays visible once the view stops scrolling.
+    #[knuffel(child, unwrap(argument), default = Self::default().hide_after_ms)]
+    pub hide_after_ms: u16,
+}
+
+impl Default for OffscreenColumnIndicator {
+    fn default() -> Self {
+        Self {
+            on: false,
+            style: OffscreenColumnIndicatorStyle::Dots,
+            width: FloatOrInt(6.),
+            color: Color::from_rgba8_unpremul(255, 255, 255, 128),
+            hide_after_ms: 500,
+        }
+    }
+}
+
+#[derive(knuffel::DecodeScalar, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OffscreenColumnIndicatorStyle {
+    /// A bar along the edge, shown while there are columns past it.
+    Bar,
+    /// A dot along the edge for every column past it.
+    Dots,
+}
+
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
+pub struct TopBar {
+    /// Background color of the top bar of urgent windows.
+    #[knuffel(child, default = Self::default().urgent_color)]
+    pub urgent_color: Color,
+}
+
+impl Default for TopBar {
+    fn default() -> Self {
+        Self {
+            urgent_color: Color::from_rgba8_unpremul(155, 0, 0, 230),
+        }
+    }
+}
+
 /// RGB color in [0, 1] with unpremultiplied alpha.
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub struct Color {
@@ -1029,6 +1375,14 @@ pub struct Struts {
     pub bottom: FloatOrInt<-65535, 65535>,
 }
 
+/// Removing the gaps on workspaces with a single column.
+#[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SmartGaps {
+    /// Also remove the border when the workspace has a single window.
+    #[knuffel(child)]
+    pub remove_borders: bool,
+}
+
 #[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct HotkeyOverlay {
     #[knuffel(child)]
@@ -1037,6 +1391,34 @@ pub struct HotkeyOverlay {
     pub hide_not_bound: bool,
 }
 
+#[derive(knuffel::Decode, Debug, Clone, PartialEq, Eq)]
+pub struct PowerMenu {
+    #[knuffel(children)]
+    pub entries: Vec<PowerMenuEntry>,
+}
+
+impl Default for PowerMenu {
+    fn default() -> Self {
+        Self {
+            entries: vec![
+                PowerMenuEntry::Logout,
+                PowerMenuEntry::Suspend,
+                PowerMenuEntry::Reboot,
+                PowerMenuEntry::PowerOff,
+            ],
+        }
+    }
+}
+
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerMenuEntry {
+    Logout,
+    Suspend,
+    Hibernate,
+    Reboot,
+    PowerOff,
+}
+
 #[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct Clipboard {
     #[knuffel(child)]
@@ -1286,7 +1668,13 @@ pub struct Gestures {
     #[knuffel(child, default)]
     pub dnd_edge_workspace_switch: DndEdgeWorkspaceSwitch,
     #[knuffel(child, default)]
+    pub wheel_view_scroll: WheelViewScroll,
+    #[knuffel(child, default)]
     pub hot_corners: HotCorners,
+    #[knuffel(child, default)]
+    pub touchpad: TouchpadGestures,
+    #[knuffel(child, default)]
+    pub touchscreen: TouchscreenGestures,
 }
 
 #[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
@@ -1329,12 +1717,89 @@ impl Default for DndEdgeWorkspaceSwitch {
     }
 }
 
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
+pub struct WheelViewScroll {
+    #[knuffel(child)]
+    pub on: bool,
+    #[knuffel(child, unwrap(argument, str), default = Self::default().modifiers)]
+    pub modifiers: Modifiers,
+    /// How far one wheel detent scrolls the view, in logical pixels.
+    #[knuffel(child, unwrap(argument), default = Self::default().distance)]
+    pub distance: FloatOrInt<0, 65535>,
+}
+
+impl Default for WheelViewScroll {
+    fn default() -> Self {
+        Self {
+            on: false,
+            modifiers: Modifiers::COMPOSITOR,
+            distance: FloatOrInt(200.),
+        }
+    }
+}
+
 #[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq)]
 pub struct HotCorners {
     #[knuffel(child)]
     pub off: bool,
 }
 
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
+pub struct TouchpadGestures {
+    #[knuffel(child, unwrap(argument), default = Self::default().three_finger_swipe)]
+    pub three_finger_swipe: TouchpadSwipeAction,
+    #[knuffel(child, unwrap(argument), default = Self::default().four_finger_swipe)]
+    pub four_finger_swipe: TouchpadSwipeAction,
+    #[knuffel(child, unwrap(argument), default)]
+    pub three_finger_pinch: TouchpadPinchAction,
+    #[knuffel(child, unwrap(argument), default)]
+    pub four_finger_pinch: TouchpadPinchAction,
+    #[knuffel(child, unwrap(argument), default = Self::default().swipe_threshold)]
+    pub swipe_threshold: FloatOrInt<0, 65535>,
+}
+
+impl Default for TouchpadGestures {
+    fn default() -> Self {
+        Self {
+            three_finger_swipe: TouchpadSwipeAction::WorkspaceOrColumn,
+            four_finger_swipe: TouchpadSwipeAction::Overview,
+            three_finger_pinch: TouchpadPinchAction::Off,
+            four_finger_pinch: TouchpadPinchAction::Off,
+            // Taken from GNOME Shell.
+            swipe_threshold: FloatOrInt(16.),
+        }
+    }
+}
+
+#[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq)]
+pub struct TouchscreenGestures {
+    #[knuffel(child)]
+    pub off: bool,
+}
+
+#[derive(knuffel::DecodeScalar, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TouchpadSwipeAction {
+    /// Switch workspaces on vertical swipes and scroll columns on horizontal swipes.
+    WorkspaceOrColumn,
+    /// Switch workspaces.
+    WorkspaceSwitch,
+    /// Scroll columns.
+    ColumnScroll,
+    /// Open and close the overview.
+    Overview,
+    /// Send the gesture to the client under the cursor.
+    Off,
+}
+
+#[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TouchpadPinchAction {
+    /// Pinch in to open the overview, pinch out to close it.
+    Overview,
+    /// Send the gesture to the client under the cursor.
+    #[default]
+    Off,
+}
+
 #[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
 pub struct Overview {
     #[knuffel(child, unwrap(argument), default = Self::default().zoom)]
@@ -1383,41 +1848,328 @@ impl Default for XwaylandSatellite {
     }
 }
 
-#[derive(knuffel::Decode, Debug, Clone, PartialEq, Eq)]
-pub struct Workspace {
-    #[knuffel(argument)]
-    pub name: WorkspaceName,
-    #[knuffel(child, unwrap(argument))]
-    pub open_on_output: Option<String>,
+#[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LidSwitch {
+    #[knuffel(child, unwrap(argument), default)]
+    pub internal_output: LidInternalOutput,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct WorkspaceName(pub String);
-
-#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq)]
-pub struct WindowRule {
-    #[knuffel(children(name = "match"))]
-    pub matches: Vec<Match>,
-    #[knuffel(children(name = "exclude"))]
-    pub excludes: Vec<Match>,
+#[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LidInternalOutput {
+    /// Turn the internal panel off when the lid is closed, if an external monitor is connected.
+    #[default]
+    OffWithExternal,
+    /// Always turn the internal panel off when the lid is closed.
+    Off,
+    /// Keep the internal panel on when the lid is closed.
+    On,
+}
 
-    // Rules applied at initial configure.
+#[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq)]
+pub struct TabletMode {
     #[knuffel(child)]
-    pub default_column_width: Option<DefaultPresetSize>,
+    pub maximize_new_windows: bool,
+    #[knuffel(child, unwrap(argument), default)]
+    pub hit_target_padding: FloatOrInt<0, 64>,
+}
+
+#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq, Eq)]
+pub struct Idle {
     #[knuffel(child)]
-    pub default_window_height: Option<DefaultPresetSize>,
-    #[knuffel(child, unwrap(argument))]
-    pub open_on_output: Option<String>,
+    pub inhibit_when_fullscreen: bool,
+    /// Dim the outputs after this much inactivity.
     #[knuffel(child, unwrap(argument))]
-    pub open_on_workspace: Option<String>,
-    #[knuffel(child, unwrap(argument))]
-    pub open_maximized: Option<bool>,
-    #[knuffel(child, unwrap(argument))]
-    pub open_fullscreen: Option<bool>,
+    pub dim_timeout_ms: Option<u32>,
+    /// Run the lock command after this much inactivity.
     #[knuffel(child, unwrap(argument))]
-    pub open_floating: Option<bool>,
+    pub lock_timeout_ms: Option<u32>,
+    #[knuffel(child, unwrap(arguments))]
+    pub lock_command: Option<Vec<String>>,
+    /// Power off the monitors after this much inactivity.
     #[knuffel(child, unwrap(argument))]
-    pub open_focused: Option<bool>,
+    pub power_off_monitors_timeout_ms: Option<u32>,
+}
+
+/// The built-in lock screen, shown while the session is locked without a lock screen client.
+#[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LockScreen {
+    /// Keep the outputs blank instead.
+    #[knuffel(child)]
+    pub off: bool,
+    /// Show what the MPRIS media players are playing.
+    #[knuffel(child)]
+    pub show_media: bool,
+}
+
+/// Commands to spawn on compositor events.
+#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq)]
+pub struct Hooks {
+    #[knuffel(children(name = "window-open"))]
+    pub window_open: Vec<WindowOpenHook>,
+    #[knuffel(children(name = "workspace-switch"))]
+    pub workspace_switch: Vec<Hook>,
+    #[knuffel(children(name = "output-connect"))]
+    pub output_connect: Vec<Hook>,
+    #[knuffel(children(name = "idle"))]
+    pub idle: Vec<IdleHook>,
+    #[knuffel(children(name = "lock"))]
+    pub lock: Vec<Hook>,
+}
+
+#[derive(knuffel::Decode, Debug, Clone, PartialEq, Eq)]
+pub struct Hook {
+    #[knuffel(arguments)]
+    pub command: Vec<String>,
+}
+
+/// Hook for new windows, optionally limited to the windows matching the regexes.
+#[derive(knuffel::Decode, Debug, Clone, PartialEq)]
+pub struct WindowOpenHook {
+    #[knuffel(property, str)]
+    pub app_id: Option<RegexEq>,
+    #[knuffel(property, str)]
+    pub title: Option<RegexEq>,
+    #[knuffel(arguments)]
+    pub command: Vec<String>,
+}
+
+/// Hook for when there was no user activity for the timeout.
+#[derive(knuffel::Decode, Debug, Clone, PartialEq, Eq)]
+pub struct IdleHook {
+    #[knuffel(property)]
+    pub timeout_ms: u32,
+    #[knuffel(arguments)]
+    pub command: Vec<String>,
+}
+
+/// Effects to degrade while saving power.
+#[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq)]
+pub struct PowerSaving {
+    /// Save power while running on battery, as reported by UPower.
+    #[knuffel(child)]
+    pub on_battery: bool,
+    /// Save power while the power-saver profile is active, as reported by power-profiles-daemon.
+    #[knuffel(child)]
+    pub power_saver_profile: bool,
+    /// Save power while rendering on the CPU, when there's no usable GPU.
+    #[knuffel(child)]
+    pub software_rendering: bool,
+    #[knuffel(child)]
+    pub disable_animations: bool,
+    /// Multiplier for the animation slowdown, below 1 for shorter animations.
+    #[knuffel(child, unwrap(argument))]
+    pub animation_slowdown: Option<FloatOrInt<0, { i32::MAX }>>,
+    #[knuffel(child)]
+    pub disable_shadows: bool,
+    #[knuffel(child, unwrap(argument))]
+    pub max_fps: Option<u16>,
+}
+
+/// Handling of windows that aren't visible on any output.
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HiddenWindows {
+    /// How many frame callbacks per second hidden windows get, or 0 for none.
+    #[knuffel(child, unwrap(argument), default = Self::default().max_fps)]
+    pub max_fps: u16,
+}
+
+impl Default for HiddenWindows {
+    fn default() -> Self {
+        Self { max_fps: 1 }
+    }
+}
+
+/// Built-in notification daemon.
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Notifications {
+    #[knuffel(child)]
+    pub on: bool,
+    #[knuffel(child, unwrap(argument), default)]
+    pub position: NotificationPosition,
+    /// Timeout for notifications that don't set one.
+    #[knuffel(child, unwrap(argument), default = Self::default().timeout_ms)]
+    pub timeout_ms: u32,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Self {
+            on: false,
+            position: NotificationPosition::default(),
+            timeout_ms: 5000,
+        }
+    }
+}
+
+/// Screen corner to show the notification popups in.
+#[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationPosition {
+    TopLeft,
+    #[default]
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// On-screen display of brightness and volume changes.
+#[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
+pub struct Osd {
+    #[knuffel(child)]
+    pub off: bool,
+    #[knuffel(child, unwrap(argument), default)]
+    pub position: OsdPosition,
+    #[knuffel(child, unwrap(argument), default = Self::default().width)]
+    pub width: FloatOrInt<1, 65535>,
+    #[knuffel(child, default = Self::default().bar_color)]
+    pub bar_color: Color,
+    #[knuffel(child, unwrap(argument), default = Self::default().hide_after_ms)]
+    pub hide_after_ms: u16,
+}
+
+impl Default for Osd {
+    fn default() -> Self {
+        Self {
+            off: false,
+            position: OsdPosition::default(),
+            width: FloatOrInt(300.),
+            bar_color: Color::from_rgba8_unpremul(127, 200, 255, 255),
+            hide_after_ms: 1500,
+        }
+    }
+}
+
+/// Where on the output to show the on-screen display.
+#[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OsdPosition {
+    Top,
+    Center,
+    #[default]
+    Bottom,
+}
+
+#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq, Eq)]
+pub struct Security {
+    /// Deny the permissions with an empty allow list, rather than allowing them to everyone.
+    #[knuffel(child)]
+    pub deny_by_default: bool,
+    /// Only log the clients that would be denied, without denying them.
+    #[knuffel(child)]
+    pub log_only: bool,
+    #[knuffel(child, default)]
+    pub virtual_keyboard: ClientPermission,
+    #[knuffel(child, default)]
+    pub virtual_pointer: ClientPermission,
+    #[knuffel(child, default)]
+    pub data_control: ClientPermission,
+    #[knuffel(child, default)]
+    pub screencopy: ClientPermission,
+    #[knuffel(child, default)]
+    pub output_management: ClientPermission,
+}
+
+#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq, Eq)]
+pub struct ClientPermission {
+    #[knuffel(child)]
+    pub off: bool,
+    #[knuffel(children(name = "allow"))]
+    pub allow: Vec<AllowedClient>,
+}
+
+/// Entry of an allow list, with at least one of the executable and the unit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllowedClient {
+    pub executable: Option<String>,
+    /// Regex for the systemd unit that the client runs in.
+    pub unit: Option<RegexEq>,
+}
+
+impl ClientPermission {
+    /// Returns whether a client running the given executable in the given systemd unit is
+    /// permitted.
+    ///
+    /// With an empty allow list, all clients are permitted, unless denying by default. Otherwise,
+    /// the client must match one of the entries. An entry matches the executable by its full path
+    /// or by its file name, and the unit by the regex; when it has both, both must match.
+    ///
+    /// Only full paths of executables that the client can't replace make this a security boundary,
+    /// since any program can have a given file name or start a unit with a given name.
+    pub fn allows(
+        &self,
+        executable: Option<&Path>,
+        unit: Option<&str>,
+        deny_by_default: bool,
+    ) -> bool {
+        if self.off {
+            return false;
+        }
+
+        if self.allow.is_empty() {
+            return !deny_by_default;
+        }
+
+        self.allow.iter().any(|allowed| {
+            let executable_matches = allowed.executable.as_ref().map_or(true, |allowed| {
+                let Some(executable) = executable else {
+                    return false;
+                };
+
+                let allowed = Path::new(allowed);
+                if allowed.is_absolute() {
+                    executable == allowed
+                } else {
+                    executable.file_name() == Some(allowed.as_os_str())
+                }
+            });
+
+            let unit_matches = allowed
+                .unit
+                .as_ref()
+                .map_or(true, |re| unit.is_some_and(|unit| re.0.is_match(unit)));
+
+            executable_matches && unit_matches
+        })
+    }
+}
+
+#[derive(knuffel::Decode, Debug, Clone, PartialEq, Eq)]
+pub struct Workspace {
+    #[knuffel(argument)]
+    pub name: WorkspaceName,
+    #[knuffel(child, unwrap(argument))]
+    pub open_on_output: Option<String>,
+    #[knuffel(child)]
+    pub wallpaper: Option<Wallpaper>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceName(pub String);
+
+#[derive(knuffel::Decode, Debug, Default, Clone, PartialEq)]
+pub struct WindowRule {
+    #[knuffel(children(name = "match"))]
+    pub matches: Vec<Match>,
+    #[knuffel(children(name = "exclude"))]
+    pub excludes: Vec<Match>,
+
+    // Rules applied at initial configure.
+    #[knuffel(child)]
+    pub default_column_width: Option<DefaultPresetSize>,
+    #[knuffel(child)]
+    pub default_window_height: Option<DefaultPresetSize>,
+    #[knuffel(child, unwrap(arguments))]
+    pub open_on_output: Option<Vec<String>>,
+    #[knuffel(child, unwrap(argument))]
+    pub open_on_workspace: Option<String>,
+    #[knuffel(child, unwrap(argument))]
+    pub open_maximized: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub open_fullscreen: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub open_floating: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub open_focused: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub open_as_backdrop: Option<bool>,
 
     // Rules applied dynamically.
     #[knuffel(child, unwrap(argument))]
@@ -1445,6 +2197,10 @@ pub struct WindowRule {
     pub geometry_corner_radius: Option<CornerRadius>,
     #[knuffel(child, unwrap(argument))]
     pub clip_to_geometry: Option<bool>,
+    #[knuffel(child)]
+    pub csd_margins: Option<CsdMargins>,
+    #[knuffel(child)]
+    pub background_color: Option<Color>,
     #[knuffel(child, unwrap(argument))]
     pub baba_is_float: Option<bool>,
     #[knuffel(child, unwrap(argument))]
@@ -1459,6 +2215,20 @@ pub struct WindowRule {
     pub scroll_factor: Option<FloatOrInt<0, 100>>,
     #[knuffel(child, unwrap(argument))]
     pub tiled_state: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub activation_policy: Option<ActivationPolicy>,
+    #[knuffel(child, unwrap(argument))]
+    pub decoration_mode: Option<DecorationMode>,
+    #[knuffel(child)]
+    pub window_movement_animation: Option<WindowMovementAnim>,
+    #[knuffel(child)]
+    pub window_resize_animation: Option<WindowResizeAnim>,
+    #[knuffel(child, unwrap(argument))]
+    pub always_on_top: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub force_windowed_fullscreen: Option<bool>,
+    #[knuffel(child, unwrap(argument))]
+    pub inhibit_shortcuts_when_fullscreen: Option<bool>,
 }
 
 #[derive(knuffel::Decode, Debug, Default, Clone, PartialEq)]
@@ -1480,6 +2250,16 @@ pub struct Match {
     #[knuffel(property)]
     pub is_urgent: Option<bool>,
     #[knuffel(property)]
+    pub is_xwayland: Option<bool>,
+    #[knuffel(property)]
+    pub has_parent: Option<bool>,
+    #[knuffel(property)]
+    pub is_modal: Option<bool>,
+    #[knuffel(property, str)]
+    pub sandbox_app_id: Option<RegexEq>,
+    #[knuffel(property, str)]
+    pub activation_app_id: Option<RegexEq>,
+    #[knuffel(property)]
     pub at_startup: Option<bool>,
 }
 
@@ -1577,6 +2357,19 @@ pub struct TabIndicatorRule {
     pub urgent_gradient: Option<Gradient>,
 }
 
+/// Margins that a window draws around its contents without including them in its geometry.
+#[derive(knuffel::Decode, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CsdMargins {
+    #[knuffel(property, default)]
+    pub top: u16,
+    #[knuffel(property, default)]
+    pub bottom: u16,
+    #[knuffel(property, default)]
+    pub left: u16,
+    #[knuffel(property, default)]
+    pub right: u16,
+}
+
 #[derive(knuffel::Decode, Debug, Clone, Copy, PartialEq)]
 pub struct FloatingPosition {
     #[knuffel(property)]
@@ -1603,6 +2396,13 @@ pub enum RelativeTo {
 #[derive(Debug, Default, PartialEq)]
 pub struct Binds(pub Vec<Bind>);
 
+/// Named set of binds that replaces the regular binds while the mode is active.
+#[derive(Debug, PartialEq)]
+pub struct BindsMode {
+    pub name: String,
+    pub binds: Binds,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Bind {
     pub key: Key,
@@ -1612,6 +2412,32 @@ pub struct Bind {
     pub allow_when_locked: bool,
     pub allow_inhibiting: bool,
     pub hotkey_overlay_title: Option<Option<String>>,
+    pub area: BindArea,
+    pub activation: BindActivation,
+}
+
+/// Where the pointer must be for a mouse or wheel bind to trigger.
+#[derive(knuffel::DecodeScalar, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BindArea {
+    /// Anywhere on the screen.
+    #[default]
+    Anywhere,
+    /// Over the workspace background, outside of any window.
+    Background,
+    /// Over a window's top bar.
+    TopBar,
+}
+
+/// Which key presses trigger a keyboard bind.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BindActivation {
+    /// Pressing the key.
+    #[default]
+    Press,
+    /// Releasing the key with no other key pressed since it was pressed.
+    Release,
+    /// Tapping the key twice in a row.
+    DoubleTap,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
@@ -1678,9 +2504,19 @@ pub enum Action {
     Suspend,
     PowerOffMonitors,
     PowerOnMonitors,
+    ToggleMonitorPower(#[knuffel(argument)] Option<String>),
+    #[knuffel(skip)]
+    ShowOsd {
+        kind: OsdKind,
+        percent: u8,
+        muted: bool,
+    },
+    AdjustBrightness(#[knuffel(argument)] i8),
     ToggleDebugTint,
     DebugToggleOpaqueRegions,
     DebugToggleDamage,
+    DebugAddNestedOutput,
+    DebugRemoveNestedOutput,
     Spawn(#[knuffel(arguments)] Vec<String>),
     DoScreenTransition(#[knuffel(property(name = "delay-ms"))] Option<u16>),
     #[knuffel(skip)]
@@ -1703,6 +2539,7 @@ pub enum Action {
         write_to_disk: bool,
     },
     ToggleKeyboardShortcutsInhibit,
+    ReleasePointerConstraint,
     CloseWindow,
     #[knuffel(skip)]
     CloseWindowById(u64),
@@ -1716,6 +2553,7 @@ pub enum Action {
     FocusWindow(u64),
     FocusWindowInColumn(#[knuffel(argument)] u8),
     FocusWindowPrevious,
+    FocusUrgent,
     FocusColumnLeft,
     #[knuffel(skip)]
     FocusColumnLeftUnderMouse,
@@ -1727,6 +2565,7 @@ pub enum Action {
     FocusColumnRightOrFirst,
     FocusColumnLeftOrLast,
     FocusColumn(#[knuffel(argument)] usize),
+    FocusColumnPrevious,
     FocusWindowOrMonitorUp,
     FocusWindowOrMonitorDown,
     FocusColumnOrMonitorLeft,
@@ -1750,6 +2589,11 @@ pub enum Action {
     MoveColumnLeftOrToMonitorLeft,
     MoveColumnRightOrToMonitorRight,
     MoveColumnToIndex(#[knuffel(argument)] usize),
+    #[knuffel(skip)]
+    MoveColumnToIndexById {
+        id: u64,
+        index: usize,
+    },
     MoveWindowDown,
     MoveWindowUp,
     MoveWindowDownOrToWorkspaceDown,
@@ -1869,10 +2713,14 @@ pub enum Action {
     #[knuffel(skip)]
     SwitchPresetWindowHeightById(u64),
     MaximizeColumn,
+    FullscreenColumn,
+    ToggleReadingMode,
     SetColumnWidth(#[knuffel(argument, str)] SizeChange),
     ExpandColumnToAvailableWidth,
     SwitchLayout(#[knuffel(argument, str)] LayoutSwitchTarget),
     ShowHotkeyOverlay,
+    ShowCheatsheet,
+    ShowWindowSwitcher,
     MoveWorkspaceToMonitorLeft,
     MoveWorkspaceToMonitorRight,
     MoveWorkspaceToMonitorDown,
@@ -1900,6 +2748,17 @@ pub enum Action {
     ToggleWindowRuleOpacity,
     #[knuffel(skip)]
     ToggleWindowRuleOpacityById(u64),
+    ToggleAlwaysOnTop,
+    #[knuffel(skip)]
+    ToggleAlwaysOnTopById(u64),
+    SetWindowOpacity(#[knuffel(argument, str)] OpacityChange),
+    #[knuffel(skip)]
+    SetWindowOpacityById {
+        id: u64,
+        change: OpacityChange,
+    },
+    MovePipToNextCorner,
+    SwitchPresetPipWidth,
     SetDynamicCastWindow,
     #[knuffel(skip)]
     SetDynamicCastWindowById(u64),
@@ -1914,6 +2773,10 @@ pub enum Action {
     SetWindowUrgent(u64),
     #[knuffel(skip)]
     UnsetWindowUrgent(u64),
+    EnterMode(#[knuffel(argument)] String),
+    ExitMode,
+    ToggleDoNotDisturb,
+    DismissNotifications,
 }
 
 impl From<niri_ipc::Action> for Action {
@@ -1922,6 +2785,17 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::Quit { skip_confirmation } => Self::Quit(skip_confirmation),
             niri_ipc::Action::PowerOffMonitors {} => Self::PowerOffMonitors,
             niri_ipc::Action::PowerOnMonitors {} => Self::PowerOnMonitors,
+            niri_ipc::Action::ToggleMonitorPower { output } => Self::ToggleMonitorPower(output),
+            niri_ipc::Action::ShowOsd {
+                kind,
+                percent,
+                muted,
+            } => Self::ShowOsd {
+                kind,
+                percent,
+                muted,
+            },
+            niri_ipc::Action::AdjustBrightness { step } => Self::AdjustBrightness(step),
             niri_ipc::Action::Spawn { command } => Self::Spawn(command),
             niri_ipc::Action::DoScreenTransition { delay_ms } => Self::DoScreenTransition(delay_ms),
             niri_ipc::Action::Screenshot { show_pointer } => Self::Screenshot(show_pointer),
@@ -1940,6 +2814,7 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::ToggleKeyboardShortcutsInhibit {} => {
                 Self::ToggleKeyboardShortcutsInhibit
             }
+            niri_ipc::Action::ReleasePointerConstraint {} => Self::ReleasePointerConstraint,
             niri_ipc::Action::CloseWindow { id: None } => Self::CloseWindow,
             niri_ipc::Action::CloseWindow { id: Some(id) } => Self::CloseWindowById(id),
             niri_ipc::Action::FullscreenWindow { id: None } => Self::FullscreenWindow,
@@ -1953,6 +2828,7 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::FocusWindow { id } => Self::FocusWindow(id),
             niri_ipc::Action::FocusWindowInColumn { index } => Self::FocusWindowInColumn(index),
             niri_ipc::Action::FocusWindowPrevious {} => Self::FocusWindowPrevious,
+            niri_ipc::Action::FocusUrgent {} => Self::FocusUrgent,
             niri_ipc::Action::FocusColumnLeft {} => Self::FocusColumnLeft,
             niri_ipc::Action::FocusColumnRight {} => Self::FocusColumnRight,
             niri_ipc::Action::FocusColumnFirst {} => Self::FocusColumnFirst,
@@ -1960,6 +2836,7 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::FocusColumnRightOrFirst {} => Self::FocusColumnRightOrFirst,
             niri_ipc::Action::FocusColumnLeftOrLast {} => Self::FocusColumnLeftOrLast,
             niri_ipc::Action::FocusColumn { index } => Self::FocusColumn(index),
+            niri_ipc::Action::FocusColumnPrevious {} => Self::FocusColumnPrevious,
             niri_ipc::Action::FocusWindowOrMonitorUp {} => Self::FocusWindowOrMonitorUp,
             niri_ipc::Action::FocusWindowOrMonitorDown {} => Self::FocusWindowOrMonitorDown,
             niri_ipc::Action::FocusColumnOrMonitorLeft {} => Self::FocusColumnOrMonitorLeft,
@@ -1980,7 +2857,13 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::MoveColumnRight {} => Self::MoveColumnRight,
             niri_ipc::Action::MoveColumnToFirst {} => Self::MoveColumnToFirst,
             niri_ipc::Action::MoveColumnToLast {} => Self::MoveColumnToLast,
-            niri_ipc::Action::MoveColumnToIndex { index } => Self::MoveColumnToIndex(index),
+            niri_ipc::Action::MoveColumnToIndex { id: None, index } => {
+                Self::MoveColumnToIndex(index)
+            }
+            niri_ipc::Action::MoveColumnToIndex {
+                id: Some(id),
+                index,
+            } => Self::MoveColumnToIndexById { id, index },
             niri_ipc::Action::MoveColumnLeftOrToMonitorLeft {} => {
                 Self::MoveColumnLeftOrToMonitorLeft
             }
@@ -2114,10 +2997,14 @@ impl From<niri_ipc::Action> for Action {
                 Self::SwitchPresetWindowHeightById(id)
             }
             niri_ipc::Action::MaximizeColumn {} => Self::MaximizeColumn,
+            niri_ipc::Action::FullscreenColumn {} => Self::FullscreenColumn,
+            niri_ipc::Action::ToggleReadingMode {} => Self::ToggleReadingMode,
             niri_ipc::Action::SetColumnWidth { change } => Self::SetColumnWidth(change),
             niri_ipc::Action::ExpandColumnToAvailableWidth {} => Self::ExpandColumnToAvailableWidth,
             niri_ipc::Action::SwitchLayout { layout } => Self::SwitchLayout(layout),
             niri_ipc::Action::ShowHotkeyOverlay {} => Self::ShowHotkeyOverlay,
+            niri_ipc::Action::ShowCheatsheet {} => Self::ShowCheatsheet,
+            niri_ipc::Action::ShowWindowSwitcher {} => Self::ShowWindowSwitcher,
             niri_ipc::Action::MoveWorkspaceToMonitorLeft {} => Self::MoveWorkspaceToMonitorLeft,
             niri_ipc::Action::MoveWorkspaceToMonitorRight {} => Self::MoveWorkspaceToMonitorRight,
             niri_ipc::Action::MoveWorkspaceToMonitorDown {} => Self::MoveWorkspaceToMonitorDown,
@@ -2151,6 +3038,8 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::ToggleDebugTint {} => Self::ToggleDebugTint,
             niri_ipc::Action::DebugToggleOpaqueRegions {} => Self::DebugToggleOpaqueRegions,
             niri_ipc::Action::DebugToggleDamage {} => Self::DebugToggleDamage,
+            niri_ipc::Action::DebugAddNestedOutput {} => Self::DebugAddNestedOutput,
+            niri_ipc::Action::DebugRemoveNestedOutput {} => Self::DebugRemoveNestedOutput,
             niri_ipc::Action::ToggleWindowFloating { id: None } => Self::ToggleWindowFloating,
             niri_ipc::Action::ToggleWindowFloating { id: Some(id) } => {
                 Self::ToggleWindowFloatingById(id)
@@ -2175,6 +3064,17 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::ToggleWindowRuleOpacity { id: Some(id) } => {
                 Self::ToggleWindowRuleOpacityById(id)
             }
+            niri_ipc::Action::ToggleAlwaysOnTop { id: None } => Self::ToggleAlwaysOnTop,
+            niri_ipc::Action::ToggleAlwaysOnTop { id: Some(id) } => Self::ToggleAlwaysOnTopById(id),
+            niri_ipc::Action::SetWindowOpacity { id: None, change } => {
+                Self::SetWindowOpacity(change)
+            }
+            niri_ipc::Action::SetWindowOpacity {
+                id: Some(id),
+                change,
+            } => Self::SetWindowOpacityById { id, change },
+            niri_ipc::Action::MovePipToNextCorner {} => Self::MovePipToNextCorner,
+            niri_ipc::Action::SwitchPresetPipWidth {} => Self::SwitchPresetPipWidth,
             niri_ipc::Action::SetDynamicCastWindow { id: None } => Self::SetDynamicCastWindow,
             niri_ipc::Action::SetDynamicCastWindow { id: Some(id) } => {
                 Self::SetDynamicCastWindowById(id)
@@ -2189,6 +3089,10 @@ impl From<niri_ipc::Action> for Action {
             niri_ipc::Action::ToggleWindowUrgent { id } => Self::ToggleWindowUrgent(id),
             niri_ipc::Action::SetWindowUrgent { id } => Self::SetWindowUrgent(id),
             niri_ipc::Action::UnsetWindowUrgent { id } => Self::UnsetWindowUrgent(id),
+            niri_ipc::Action::EnterMode { name } => Self::EnterMode(name),
+            niri_ipc::Action::ExitMode {} => Self::ExitMode,
+            niri_ipc::Action::ToggleDoNotDisturb {} => Self::ToggleDoNotDisturb,
+            niri_ipc::Action::DismissNotifications {} => Self::DismissNotifications,
         }
     }
 }
@@ -2332,6 +3236,8 @@ pub struct DebugConfig {
     pub disable_direct_scanout: bool,
     #[knuffel(child)]
     pub restrict_primary_scanout_to_matching_format: bool,
+    #[knuffel(child)]
+    pub scanout_video_and_games_in_any_format: bool,
     #[knuffel(child, unwrap(argument))]
     pub render_drm_device: Option<PathBuf>,
     #[knuffel(child)]
@@ -2354,6 +3260,10 @@ pub struct DebugConfig {
     pub deactivate_unfocused_windows: bool,
     #[knuffel(child)]
     pub skip_cursor_only_updates_during_vrr: bool,
+    #[knuffel(child)]
+    pub restart_on_crash: bool,
+    #[knuffel(child, unwrap(argument))]
+    pub nested_outputs: Option<u8>,
 }
 
 #[derive(knuffel::DecodeScalar, Debug, Clone, Copy, PartialEq, Eq)]
@@ -2363,33 +3273,147 @@ pub enum PreviewRender {
 }
 
 impl Config {
-    pub fn load(path: &Path) -> miette::Result<Self> {
+    /// Loads the config file at `path`, expanding includes and the selected profile.
+    ///
+    /// All files read while loading are appended to `files`, including on errors.
+    pub fn load(
+        path: &Path,
+        profile: Option<&str>,
+        files: &mut Vec<PathBuf>,
+    ) -> miette::Result<Self> {
         let _span = tracy_client::span!("Config::load");
-        Self::load_internal(path).context("error loading config")
+        Self::load_internal(path, profile, files).context("error loading config")
     }
 
-    fn load_internal(path: &Path) -> miette::Result<Self> {
-        let contents = std::fs::read_to_string(path)
-            .into_diagnostic()
-            .with_context(|| format!("error reading {path:?}"))?;
+    fn load_internal(
+        path: &Path,
+        profile: Option<&str>,
+        files: &mut Vec<PathBuf>,
+    ) -> miette::Result<Self> {
+        let expanded = include::expand(path, profile, files)?;
+        for warning in &expanded.warnings {
+            warn!("{warning}");
+        }
 
-        let config = Self::parse(
-            path.file_name()
-                .and_then(OsStr::to_str)
-                .unwrap_or("config.kdl"),
-            &contents,
-        )
-        .context("error parsing")?;
+        let config = Self::parse(config_filename(path), &expanded.text)
+            .map_err(|err| expanded.relocate_error(err))
+            .context("error parsing")?;
         debug!("loaded config from {path:?}");
         Ok(config)
     }
 
+    /// Loads the config file at `path` and returns all problems found in it.
+    pub fn validate(path: &Path, profile: Option<&str>) -> Vec<ConfigDiagnostic> {
+        let _span = tracy_client::span!("Config::validate");
+
+        let expanded = match include::expand(path, profile, &mut Vec::new()) {
+            Ok(expanded) => expanded,
+            Err(err) => {
+                let message = err.chain().map(|e| e.to_string()).collect::<Vec<_>>();
+                return vec![ConfigDiagnostic {
+                    severity: DiagnosticSeverity::Error,
+                    message: message.join(": "),
+                    label: None,
+                    help: err.help().map(|help| help.to_string()),
+                    file: Some(path.to_string_lossy().into_owned()),
+                    line: None,
+                    column: None,
+                }];
+            }
+        };
+
+        let mut diagnostics: Vec<_> = expanded
+            .warnings
+            .iter()
+            .map(|warning| ConfigDiagnostic {
+                severity: DiagnosticSeverity::Warning,
+                message: warning.clone(),
+                label: None,
+                help: None,
+                file: None,
+                line: None,
+                column: None,
+            })
+            .collect();
+
+        let deprecations = Deprecations::default();
+        let res = knuffel::parse_with_context::<Self, knuffel::span::Span, _>(
+            config_filename(path),
+            &expanded.text,
+            |ctx| ctx.set(deprecations.clone()),
+        );
+
+        for (span, deprecation) in deprecations.0.take() {
+            let location = expanded.locate(span.offset());
+            diagnostics.push(ConfigDiagnostic {
+                severity: DiagnosticSeverity::Warning,
+                message: format!("deprecated syntax: {}", deprecation.syntax),
+                label: None,
+                help: Some(format!("use {} instead", deprecation.replacement)),
+                file: location.map(|(path, _, _)| path.to_string_lossy().into_owned()),
+                line: location.map(|(_, line, _)| line),
+                column: location.map(|(_, _, column)| column),
+            });
+        }
+
+        let Err(err) = res else {
+            return diagnostics;
+        };
+
+        // knuffel reports every problem as a related diagnostic.
+        let related: Vec<&dyn Diagnostic> =
+            err.related().map(Iterator::collect).unwrap_or_default();
+        let related = if related.is_empty() {
+            vec![&err as &dyn Diagnostic]
+        } else {
+            related
+        };
+
+        for diag in related {
+            let label = diag.labels().and_then(|mut labels| labels.next());
+            let location = label
+                .as_ref()
+                .and_then(|label| expanded.locate(label.offset()));
+
+            diagnostics.push(ConfigDiagnostic {
+                severity: match diag.severity() {
+                    Some(Severity::Warning | Severity::Advice) => DiagnosticSeverity::Warning,
+                    _ => DiagnosticSeverity::Error,
+                },
+                message: diag.to_string(),
+                label: label.and_then(|label| label.label().map(String::from)),
+                help: diag.help().map(|help| help.to_string()),
+                file: location.map(|(path, _, _)| path.to_string_lossy().into_owned()),
+                line: location.map(|(_, line, _)| line),
+                column: location.map(|(_, _, column)| column),
+            });
+        }
+
+        diagnostics
+    }
+
     pub fn parse(filename: &str, text: &str) -> Result<Self, knuffel::Error> {
         let _span = tracy_client::span!("Config::parse");
         knuffel::parse(filename, text)
     }
 }
 
+/// Uses of deprecated syntax, recorded through the knuffel decode context.
+#[derive(Debug, Default, Clone)]
+struct Deprecations(Rc<RefCell<Vec<(miette::SourceSpan, schema::Deprecation)>>>);
+
+impl Deprecations {
+    fn push(&self, span: impl Into<miette::SourceSpan>, deprecation: schema::Deprecation) {
+        self.0.borrow_mut().push((span.into(), deprecation));
+    }
+}
+
+fn config_filename(path: &Path) -> &str {
+    path.file_name()
+        .and_then(OsStr::to_str)
+        .unwrap_or("config.kdl")
+}
+
 impl Default for Config {
     fn default() -> Self {
         Config::parse(
@@ -2744,8 +3768,13 @@ where
             knuffel::ast::Literal::String(ref s) => {
                 Color::from_str(s).map_err(|e| DecodeError::conversion(&val.literal, e))
             }
-            // Otherwise, fall back to the 4-argument RGBA form.
-            _ => return ColorRgba::decode_node(node, ctx).map(Color::from),
+            // Otherwise, fall back to the deprecated 4-argument RGBA form.
+            _ => {
+                if let Some(deprecations) = ctx.get::<Deprecations>() {
+                    deprecations.push(node.span().clone(), schema::RGBA_COLOR_DEPRECATION);
+                }
+                return ColorRgba::decode_node(node, ctx).map(Color::from);
+            }
         }?;
 
         // Check for unexpected following arguments.
@@ -2824,6 +3853,21 @@ impl Outputs {
     }
 }
 
+impl OutputProfile {
+    /// Returns whether this profile matches exactly the given set of connected outputs.
+    pub fn matches(&self, connected: &[OutputName]) -> bool {
+        !connected.is_empty()
+            && connected
+                .iter()
+                .all(|name| self.outputs.find(name).is_some())
+            && self
+                .outputs
+                .0
+                .iter()
+                .all(|output| connected.iter().any(|name| name.matches(&output.name)))
+    }
+}
+
 impl OutputName {
     pub fn from_ipc_output(output: &niri_ipc::Output) -> Self {
         Self {
@@ -3558,7 +4602,7 @@ where
     }
 }
 
-impl<S> knuffel::Decode<S> for Binds
+impl<S> knuffel::Decode<S> for InputDevice
 where
     S: knuffel::traits::ErrorSpan,
 {
@@ -3566,60 +4610,247 @@ where
         node: &knuffel::ast::SpannedNode<S>,
         ctx: &mut knuffel::decode::Context<S>,
     ) -> Result<Self, DecodeError<S>> {
-        expect_only_children(node, ctx);
+        if let Some(type_name) = &node.type_name {
+            ctx.emit_error(DecodeError::unexpected(
+                type_name,
+                "type name",
+                "no type name expected for this node",
+            ));
+        }
 
-        let mut seen_keys = HashSet::new();
+        let mut iter_args = node.arguments.iter();
+        let name = iter_args
+            .next()
+            .map(|val| knuffel::traits::DecodeScalar::decode(val, ctx))
+            .transpose()?;
 
-        let mut binds = Vec::new();
+        if let Some(val) = iter_args.next() {
+            ctx.emit_error(DecodeError::unexpected(
+                &val.literal,
+                "argument",
+                "unexpected argument",
+            ));
+        }
 
-        for child in node.children() {
-            match Bind::decode_node(child, ctx) {
-                Err(e) => {
-                    ctx.emit_error(e);
+        let mut vid = None;
+        let mut pid = None;
+        for (prop_name, val) in &node.properties {
+            match &***prop_name {
+                "vid" => vid = Some(knuffel::traits::DecodeScalar::decode(val, ctx)?),
+                "pid" => pid = Some(knuffel::traits::DecodeScalar::decode(val, ctx)?),
+                name_str => {
+                    ctx.emit_error(DecodeError::unexpected(
+                        prop_name,
+                        "property",
+                        format!("unexpected property `{}`", name_str.escape_default()),
+                    ));
                 }
-                Ok(bind) => {
-                    if seen_keys.insert(bind.key) {
-                        binds.push(bind);
-                    } else {
-                        // ideally, this error should point to the previous instance of this keybind
-                        //
-                        // i (sodiboo) have tried to implement this in various ways:
-                        // miette!(), #[derive(Diagnostic)]
-                        // DecodeError::Custom, DecodeError::Conversion
-                        // nothing seems to work, and i suspect it's not possible.
-                        //
-                        // DecodeError is fairly restrictive.
-                        // even DecodeError::Custom just wraps a std::error::Error
-                        // and this erases all rich information from miette. (why???)
-                        //
-                        // why does knuffel do this?
-                        // from what i can tell, it doesn't even use DecodeError for much.
-                        // it only ever converts them to a Report anyways!
-                        // https://github.com/tailhook/knuffel/blob/c44c6b0c0f31ea6d1174d5d2ed41064922ea44ca/src/wrappers.rs#L55-L58
-                        //
-                        // besides like, allowing downstream users (such as us!)
-                        // to match on parse failure, i don't understand why
-                        // it doesn't just use a generic error type
-                        //
-                        // even the matching isn't consistent,
-                        // because errors can also be omitted as ctx.emit_error.
-                        // why does *that one* especially, require a DecodeError?
-                        //
-                        // anyways if you can make it format nicely, definitely do fix this
-                        ctx.emit_error(DecodeError::unexpected(
-                            &child.node_name,
-                            "keybind",
-                            "duplicate keybind",
-                        ));
-                    }
+            }
+        }
+
+        // A device section without anything to match would apply to every device, which is
+        // almost certainly a mistake.
+        if name.is_none() && vid.is_none() && pid.is_none() {
+            return Err(DecodeError::missing(
+                node,
+                "a device name, `vid` or `pid` is required to match the device",
+            ));
+        }
+
+        let children = node.children.as_ref().map_or(&[][..], |children| &children[..]);
+        let settings = knuffel::traits::DecodeChildren::decode_children(children, ctx)?;
+
+        Ok(Self {
+            name,
+            vid,
+            pid,
+            settings,
+        })
+    }
+}
+
+impl<S> knuffel::Decode<S> for AllowedClient
+where
+    S: knuffel::traits::ErrorSpan,
+{
+    fn decode_node(
+        node: &knuffel::ast::SpannedNode<S>,
+        ctx: &mut knuffel::decode::Context<S>,
+    ) -> Result<Self, DecodeError<S>> {
+        if let Some(type_name) = &node.type_name {
+            ctx.emit_error(DecodeError::unexpected(
+                type_name,
+                "type name",
+                "no type name expected for this node",
+            ));
+        }
+
+        let mut iter_args = node.arguments.iter();
+        let executable = iter_args
+            .next()
+            .map(|val| knuffel::traits::DecodeScalar::decode(val, ctx))
+            .transpose()?;
+
+        if let Some(val) = iter_args.next() {
+            ctx.emit_error(DecodeError::unexpected(
+                &val.literal,
+                "argument",
+                "unexpected argument",
+            ));
+        }
+
+        let mut unit = None;
+        for (prop_name, val) in &node.properties {
+            match &***prop_name {
+                "unit" => {
+                    let regex: String = knuffel::traits::DecodeScalar::decode(val, ctx)?;
+                    let regex = RegexEq::from_str(&regex)
+                        .map_err(|err| DecodeError::conversion(&val.literal, err))?;
+                    unit = Some(regex);
+                }
+                name_str => {
+                    ctx.emit_error(DecodeError::unexpected(
+                        prop_name,
+                        "property",
+                        format!("unexpected property `{}`", name_str.escape_default()),
+                    ));
                 }
             }
         }
 
-        Ok(Self(binds))
+        for child in node.children.as_ref().map(|lst| &lst[..]).unwrap_or(&[]) {
+            ctx.emit_error(DecodeError::unexpected(
+                child,
+                "node",
+                format!("unexpected node `{}`", child.node_name.escape_default()),
+            ));
+        }
+
+        // An entry with neither would never match any client.
+        if executable.is_none() && unit.is_none() {
+            return Err(DecodeError::missing(
+                node,
+                "an executable or a `unit` is required to match the client",
+            ));
+        }
+
+        Ok(Self { executable, unit })
     }
 }
 
+impl<S> knuffel::Decode<S> for Binds
+where
+    S: knuffel::traits::ErrorSpan,
+{
+    fn decode_node(
+        node: &knuffel::ast::SpannedNode<S>,
+        ctx: &mut knuffel::decode::Context<S>,
+    ) -> Result<Self, DecodeError<S>> {
+        expect_only_children(node, ctx);
+        Ok(decode_binds(node, ctx))
+    }
+}
+
+impl<S> knuffel::Decode<S> for BindsMode
+where
+    S: knuffel::traits::ErrorSpan,
+{
+    fn decode_node(
+        node: &knuffel::ast::SpannedNode<S>,
+        ctx: &mut knuffel::decode::Context<S>,
+    ) -> Result<Self, DecodeError<S>> {
+        if let Some(type_name) = &node.type_name {
+            ctx.emit_error(DecodeError::unexpected(
+                type_name,
+                "type name",
+                "no type name expected for this node",
+            ));
+        }
+
+        let mut iter_args = node.arguments.iter();
+        let val = iter_args
+            .next()
+            .ok_or_else(|| DecodeError::missing(node, "additional argument `name` is required"))?;
+        let name = knuffel::traits::DecodeScalar::decode(val, ctx)?;
+
+        if let Some(val) = iter_args.next() {
+            ctx.emit_error(DecodeError::unexpected(
+                &val.literal,
+                "argument",
+                "unexpected argument",
+            ));
+        }
+        for name in node.properties.keys() {
+            ctx.emit_error(DecodeError::unexpected(
+                name,
+                "property",
+                format!("unexpected property `{}`", name.escape_default()),
+            ));
+        }
+
+        let binds = decode_binds(node, ctx);
+        Ok(Self { name, binds })
+    }
+}
+
+fn decode_binds<S>(
+    node: &knuffel::ast::SpannedNode<S>,
+    ctx: &mut knuffel::decode::Context<S>,
+) -> Binds
+where
+    S: knuffel::traits::ErrorSpan,
+{
+    let mut seen_keys = HashSet::new();
+
+    let mut binds = Vec::new();
+
+    for child in node.children() {
+        match Bind::decode_node(child, ctx) {
+            Err(e) => {
+                ctx.emit_error(e);
+            }
+            Ok(bind) => {
+                if seen_keys.insert((bind.key, bind.area)) {
+                    binds.push(bind);
+                } else {
+                    // ideally, this error should point to the previous instance of this keybind
+                    //
+                    // i (sodiboo) have tried to implement this in various ways:
+                    // miette!(), #[derive(Diagnostic)]
+                    // DecodeError::Custom, DecodeError::Conversion
+                    // nothing seems to work, and i suspect it's not possible.
+                    //
+                    // DecodeError is fairly restrictive.
+                    // even DecodeError::Custom just wraps a std::error::Error
+                    // and this erases all rich information from miette. (why???)
+                    //
+                    // why does knuffel do this?
+                    // from what i can tell, it doesn't even use DecodeError for much.
+                    // it only ever converts them to a Report anyways!
+                    // https://github.com/tailhook/knuffel/blob/c44c6b0c0f31ea6d1174d5d2ed41064922ea44ca/src/wrappers.rs#L55-L58
+                    //
+                    // besides like, allowing downstream users (such as us!)
+                    // to match on parse failure, i don't understand why
+                    // it doesn't just use a generic error type
+                    //
+                    // even the matching isn't consistent,
+                    // because errors can also be omitted as ctx.emit_error.
+                    // why does *that one* especially, require a DecodeError?
+                    //
+                    // anyways if you can make it format nicely, definitely do fix this
+                    ctx.emit_error(DecodeError::unexpected(
+                        &child.node_name,
+                        "keybind",
+                        "duplicate keybind",
+                    ));
+                }
+            }
+        }
+    }
+
+    Binds(binds)
+}
+
 impl<S> knuffel::Decode<S> for Bind
 where
     S: knuffel::traits::ErrorSpan,
@@ -3655,6 +4886,11 @@ where
         let mut allow_when_locked_node = None;
         let mut allow_inhibiting = true;
         let mut hotkey_overlay_title = None;
+        let mut area = BindArea::Anywhere;
+        let mut area_node = None;
+        let mut on_release = false;
+        let mut double_tap = false;
+        let mut activation_node = None;
         for (name, val) in &node.properties {
             match &***name {
                 "repeat" => {
@@ -3675,6 +4911,18 @@ where
                 "hotkey-overlay-title" => {
                     hotkey_overlay_title = Some(knuffel::traits::DecodeScalar::decode(val, ctx)?);
                 }
+                "area" => {
+                    area = knuffel::traits::DecodeScalar::decode(val, ctx)?;
+                    area_node = Some(name);
+                }
+                "on-release" => {
+                    on_release = knuffel::traits::DecodeScalar::decode(val, ctx)?;
+                    activation_node = Some(name);
+                }
+                "double-tap" => {
+                    double_tap = knuffel::traits::DecodeScalar::decode(val, ctx)?;
+                    activation_node = Some(name);
+                }
                 name_str => {
                     ctx.emit_error(DecodeError::unexpected(
                         name,
@@ -3685,6 +4933,36 @@ where
             }
         }
 
+        if matches!(key.trigger, Trigger::Keysym(_)) {
+            if let Some(node) = area_node {
+                ctx.emit_error(DecodeError::unexpected(
+                    node,
+                    "property",
+                    "area can only be set on mouse, wheel and touchpad scroll binds",
+                ));
+                area = BindArea::Anywhere;
+            }
+        }
+
+        let mut activation = if double_tap {
+            BindActivation::DoubleTap
+        } else if on_release {
+            BindActivation::Release
+        } else {
+            BindActivation::Press
+        };
+
+        if !matches!(key.trigger, Trigger::Keysym(_)) {
+            if let Some(node) = activation_node {
+                ctx.emit_error(DecodeError::unexpected(
+                    node,
+                    "property",
+                    "on-release and double-tap can only be set on keyboard binds",
+                ));
+                activation = BindActivation::Press;
+            }
+        }
+
         let mut children = node.children();
 
         // If the action is invalid but the key is fine, we still want to return something.
@@ -3698,6 +4976,8 @@ where
             allow_when_locked: false,
             allow_inhibiting: true,
             hotkey_overlay_title: None,
+            area,
+            activation,
         };
 
         if let Some(child) = children.next() {
@@ -3734,6 +5014,8 @@ where
                         allow_when_locked,
                         allow_inhibiting,
                         hotkey_overlay_title,
+                        area,
+                        activation,
                     })
                 }
                 Err(e) => {
@@ -3767,6 +5049,39 @@ impl FromStr for ModKey {
     }
 }
 
+impl FromStr for Modifiers {
+    type Err = miette::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = Modifiers::empty();
+        for part in s.split('+') {
+            modifiers |= parse_modifier(part)?;
+        }
+        Ok(modifiers)
+    }
+}
+
+fn parse_modifier(part: &str) -> miette::Result<Modifiers> {
+    let part = part.trim();
+    if part.eq_ignore_ascii_case("mod") {
+        Ok(Modifiers::COMPOSITOR)
+    } else if part.eq_ignore_ascii_case("ctrl") || part.eq_ignore_ascii_case("control") {
+        Ok(Modifiers::CTRL)
+    } else if part.eq_ignore_ascii_case("shift") {
+        Ok(Modifiers::SHIFT)
+    } else if part.eq_ignore_ascii_case("alt") {
+        Ok(Modifiers::ALT)
+    } else if part.eq_ignore_ascii_case("super") || part.eq_ignore_ascii_case("win") {
+        Ok(Modifiers::SUPER)
+    } else if part.eq_ignore_ascii_case("iso_level3_shift") || part.eq_ignore_ascii_case("mod5") {
+        Ok(Modifiers::ISO_LEVEL3_SHIFT)
+    } else if part.eq_ignore_ascii_case("iso_level5_shift") || part.eq_ignore_ascii_case("mod3") {
+        Ok(Modifiers::ISO_LEVEL5_SHIFT)
+    } else {
+        Err(miette!("invalid modifier: {part}"))
+    }
+}
+
 impl FromStr for Key {
     type Err = miette::Error;
 
@@ -3777,28 +5092,7 @@ impl FromStr for Key {
         let key = split.next_back().unwrap();
 
         for part in split {
-            let part = part.trim();
-            if part.eq_ignore_ascii_case("mod") {
-                modifiers |= Modifiers::COMPOSITOR
-            } else if part.eq_ignore_ascii_case("ctrl") || part.eq_ignore_ascii_case("control") {
-                modifiers |= Modifiers::CTRL;
-            } else if part.eq_ignore_ascii_case("shift") {
-                modifiers |= Modifiers::SHIFT;
-            } else if part.eq_ignore_ascii_case("alt") {
-                modifiers |= Modifiers::ALT;
-            } else if part.eq_ignore_ascii_case("super") || part.eq_ignore_ascii_case("win") {
-                modifiers |= Modifiers::SUPER;
-            } else if part.eq_ignore_ascii_case("iso_level3_shift")
-                || part.eq_ignore_ascii_case("mod5")
-            {
-                modifiers |= Modifiers::ISO_LEVEL3_SHIFT;
-            } else if part.eq_ignore_ascii_case("iso_level5_shift")
-                || part.eq_ignore_ascii_case("mod3")
-            {
-                modifiers |= Modifiers::ISO_LEVEL5_SHIFT;
-            } else {
-                return Err(miette!("invalid modifier: {part}"));
-            }
+            modifiers |= parse_modifier(part)?;
         }
 
         let trigger = if key.eq_ignore_ascii_case("MouseLeft") {
@@ -3839,6 +5133,34 @@ impl FromStr for Key {
     }
 }
 
+impl FromStr for HSyncPolarity {
+    type Err = miette::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "+hsync" => Ok(Self::Positive),
+            "-hsync" => Ok(Self::Negative),
+            _ => Err(miette!(
+                r#"invalid horizontal sync polarity, can be "+hsync" or "-hsync""#
+            )),
+        }
+    }
+}
+
+impl FromStr for VSyncPolarity {
+    type Err = miette::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "+vsync" => Ok(Self::Positive),
+            "-vsync" => Ok(Self::Negative),
+            _ => Err(miette!(
+                r#"invalid vertical sync polarity, can be "+vsync" or "-vsync""#
+            )),
+        }
+    }
+}
+
 impl FromStr for ClickMethod {
     type Err = miette::Error;
 
@@ -3938,6 +5260,9 @@ mod tests {
                     repeat-delay 600
                     repeat-rate 25
                     track-layout "window"
+                    sticky-keys
+                    bounce-keys-delay 50
+                    layout-indicator
                     xkb {
                         layout "us,ru"
                         options "grp:win_space_toggle"
@@ -3991,6 +5316,7 @@ mod tests {
 
                 tablet {
                     map-to-output "eDP-1"
+                    active-area x=0.25 width=0.5
                     calibration-matrix 1.0 2.0 3.0 \
                                        4.0 5.0 6.0
                 }
@@ -3999,14 +5325,28 @@ mod tests {
                     map-to-output "eDP-1"
                 }
 
+                device "AT Translated Set 2 keyboard" {
+                    off true
+                }
+
+                device vid=0x046d pid=0xc52b {
+                    accel-profile "adaptive"
+                    natural-scroll false
+                }
+
                 disable-power-key-handling
+                mpris-media-keys
 
                 warp-mouse-to-focus
-                focus-follows-mouse
+                focus-follows-mouse delay-ms=150 content-only=true
+                output-barriers {
+                    block-corners
+                }
                 workspace-auto-back-and-forth
 
                 mod-key "Mod5"
                 mod-key-nested "Super"
+                activation-policy "ignore"
             }
 
             output "eDP-1" {
@@ -4017,6 +5357,24 @@ mod tests {
                 mode "1920x1080@144"
                 variable-refresh-rate on-demand=true
                 background-color "rgba(25, 25, 102, 1.0)"
+                mirror "HDMI-A-1"
+                modeline 173.0 1920 2048 2248 2576 1080 1083 1088 1120 "-hsync" "+vsync"
+                custom-mode
+                wallpaper "~/Pictures/wallpaper.png" mode="fit"
+            }
+
+            output-profile "docked" {
+                output "eDP-1" {
+                    off
+                }
+
+                output "HDMI-A-1" {
+                    scale 1.5
+                }
+
+                workspace "workspace-1" {
+                    open-on-output "HDMI-A-1"
+                }
             }
 
             layout {
@@ -4030,6 +5388,7 @@ mod tests {
                 border {
                     width 3
                     inactive-color "rgba(255, 200, 100, 0.0)"
+                    hide-when-single-window
                 }
 
                 shadow {
@@ -4065,6 +5424,12 @@ mod tests {
                     top 3
                 }
 
+                smart-gaps {
+                    remove-borders
+                }
+
+                reading-mode-max-width 1600
+
                 center-focused-column "on-overflow"
 
                 default-column-display "tabbed"
@@ -4073,9 +5438,24 @@ mod tests {
                     color "rgb(255, 200, 127)"
                     gradient from="rgba(10, 20, 30, 1.0)" to="#0080ffff" relative-to="workspace-view"
                 }
+
+                offscreen-column-indicator {
+                    on
+                    style "bar"
+                    hide-after-ms 1000
+                }
+
+                top-bar {
+                    urgent-color "#ff0000"
+                }
             }
 
             spawn-at-startup "alacritty" "-e" "fish"
+            spawn-at-startup "waybar" name="bar" working-dir="~" restart="on-failure" {
+                environment {
+                    WAYBAR_DEBUG "1"
+                }
+            }
 
             prefer-no-csd
 
@@ -4096,6 +5476,11 @@ mod tests {
                 skip-at-startup
             }
 
+            power-menu {
+                suspend
+                power-off
+            }
+
             animations {
                 slowdown 2.0
 
@@ -4116,6 +5501,17 @@ mod tests {
                     trigger-width 10
                     max-speed 50
                 }
+
+                wheel-view-scroll {
+                    on
+                    modifiers "Mod+Shift"
+                }
+
+                touchpad {
+                    three-finger-swipe "workspace-switch"
+                    four-finger-pinch "overview"
+                    swipe-threshold 24
+                }
             }
 
             environment {
@@ -4127,15 +5523,28 @@ mod tests {
                 match app-id=".*alacritty"
                 exclude title="~"
                 exclude is-active=true is-focused=false
+                exclude has-parent=true is-modal=true sandbox-app-id="^org\\.gimp" activation-app-id="gimp"
 
-                open-on-output "eDP-1"
+                open-on-output "eDP-1" "HDMI-A-1"
                 open-maximized true
                 open-fullscreen false
                 open-floating false
                 open-focused true
+                open-as-backdrop false
                 default-window-height { fixed 500; }
                 default-column-display "tabbed"
                 default-floating-position x=100 y=-200 relative-to="bottom-left"
+                decoration-mode "server-side"
+                window-movement-animation { off; }
+                window-resize-animation {
+                    duration-ms 100
+                    curve "ease-out-quad"
+                }
+                always-on-top true
+                force-windowed-fullscreen true
+                inhibit-shortcuts-when-fullscreen true
+                csd-margins top=10 bottom=30 left=20 right=20
+                background-color "#000"
 
                 focus-ring {
                     off
@@ -4172,6 +5581,13 @@ mod tests {
                 Mod+Shift+1 { focus-workspace "workspace-1"; }
                 Mod+Shift+E allow-inhibiting=false { quit skip-confirmation=true; }
                 Mod+WheelScrollDown cooldown-ms=150 { focus-workspace-down; }
+                Mod+MouseMiddle area="top-bar" { close-window; }
+                Mod+Space on-release=true { toggle-overview; }
+            }
+
+            binds-mode "resize" {
+                Mod+Left { set-column-width "-10%"; }
+                Return { exit-mode; }
             }
 
             switch-events {
@@ -4179,6 +5595,73 @@ mod tests {
                 tablet-mode-off { spawn "bash" "-c" "gsettings set org.gnome.desktop.a11y.applications screen-keyboard-enabled false"; }
             }
 
+            lid-switch {
+                internal-output "off"
+            }
+
+            tablet-mode {
+                maximize-new-windows
+                hit-target-padding 8
+            }
+
+            idle {
+                inhibit-when-fullscreen
+                dim-timeout-ms 240000
+                lock-timeout-ms 300000
+                lock-command "swaylock" "-f"
+                power-off-monitors-timeout-ms 600000
+            }
+
+            lock-screen {
+                show-media
+            }
+
+            hooks {
+                window-open app-id="^firefox$" "notify-send" "Firefox opened"
+                workspace-switch "~/bin/on-workspace-switch"
+                idle timeout-ms=300000 "swaylock"
+            }
+
+            notifications {
+                on
+                position "bottom-right"
+                timeout-ms 8000
+            }
+
+            osd {
+                position "top"
+                bar-color "#ffc87f"
+            }
+
+            power-saving {
+                on-battery
+                disable-shadows
+                animation-slowdown 0.5
+                max-fps 30
+            }
+
+            hidden-windows {
+                max-fps 0
+            }
+
+            security {
+                virtual-keyboard {
+                    allow "wtype"
+                    allow "/usr/bin/ydotool"
+                }
+                virtual-pointer {
+                    off
+                }
+                data-control {
+                    allow "cliphist"
+                    allow "wl-paste"
+                }
+                screencopy {
+                    allow unit="^app-com\\.obsproject\\.Studio"
+                    allow "grim"
+                }
+            }
+
             debug {
                 render-drm-device "/dev/dri/renderD129"
             }
@@ -4186,7 +5669,9 @@ mod tests {
             workspace "workspace-1" {
                 open-on-output "eDP-1"
             }
-            workspace "workspace-2"
+            workspace "workspace-2" {
+                wallpaper "~/Pictures/workspace-2.png"
+            }
             workspace "workspace-3"
             "##,
         );
@@ -4209,6 +5694,10 @@ mod tests {
                     repeat_rate: 25,
                     track_layout: Window,
                     numlock: false,
+                    sticky_keys: true,
+                    slow_keys_delay: 0,
+                    bounce_keys_delay: 50,
+                    layout_indicator: true,
                 },
                 touchpad: Touchpad {
                     off: false,
@@ -4321,6 +5810,23 @@ mod tests {
                     map_to_output: Some(
                         "eDP-1",
                     ),
+                    map_to_focused_window: false,
+                    active_area: Some(
+                        TabletArea {
+                            x: FloatOrInt(
+                                0.25,
+                            ),
+                            y: FloatOrInt(
+                                0.0,
+                            ),
+                            width: FloatOrInt(
+                                0.5,
+                            ),
+                            height: FloatOrInt(
+                                1.0,
+                            ),
+                        },
+                    ),
                     left_handed: false,
                 },
                 touch: Touch {
@@ -4329,7 +5835,66 @@ mod tests {
                         "eDP-1",
                     ),
                 },
+                devices: [
+                    InputDevice {
+                        name: Some(
+                            "AT Translated Set 2 keyboard",
+                        ),
+                        vid: None,
+                        pid: None,
+                        settings: InputDeviceSettings {
+                            off: Some(
+                                true,
+                            ),
+                            tap: None,
+                            dwt: None,
+                            dwtp: None,
+                            drag: None,
+                            drag_lock: None,
+                            natural_scroll: None,
+                            accel_speed: None,
+                            accel_profile: None,
+                            scroll_method: None,
+                            scroll_button: None,
+                            click_method: None,
+                            tap_button_map: None,
+                            left_handed: None,
+                            middle_emulation: None,
+                        },
+                    },
+                    InputDevice {
+                        name: None,
+                        vid: Some(
+                            1133,
+                        ),
+                        pid: Some(
+                            50475,
+                        ),
+                        settings: InputDeviceSettings {
+                            off: None,
+                            tap: None,
+                            dwt: None,
+                            dwtp: None,
+                            drag: None,
+                            drag_lock: None,
+                            natural_scroll: Some(
+                                false,
+                            ),
+                            accel_speed: None,
+                            accel_profile: Some(
+                                Adaptive,
+                            ),
+                            scroll_method: None,
+                            scroll_button: None,
+                            click_method: None,
+                            tap_button_map: None,
+                            left_handed: None,
+                            middle_emulation: None,
+                        },
+                    },
+                ],
                 disable_power_key_handling: true,
+                mpris_media_keys: true,
                 warp_mouse_to_focus: Some(
                     WarpMouseToFocus {
                         mode: None,
@@ -4338,6 +5903,19 @@ mod tests {
                 focus_follows_mouse: Some(
                     FocusFollowsMouse {
                         max_scroll_amount: None,
+                        delay_ms: Some(
+                            150,
+                        ),
+                        ignore_while_scrolling: false,
+                        content_only: true,
+                    },
+                ),
+                output_barriers: Some(
+                    OutputBarriers {
+                        push_through: FloatOrInt(
+                            50.0,
+                        ),
+                        block_corners: true,
                     },
                 ),
                 workspace_auto_back_and_forth: true,
@@ -4347,6 +5925,7 @@ mod tests {
                 mod_key_nested: Some(
                     Super,
                 ),
+                activation_policy: Ignore,
             },
             outputs: Outputs(
                 [
@@ -4389,9 +5968,92 @@ mod tests {
                             },
                         ),
                         backdrop_color: None,
+                        mirror: Some(
+                            "HDMI-A-1",
+                        ),
+                        modeline: Some(
+                            Modeline {
+                                clock: FloatOrInt(
+                                    173.0,
+                                ),
+                                hdisplay: 1920,
+                                hsync_start: 2048,
+                                hsync_end: 2248,
+                                htotal: 2576,
+                                vdisplay: 1080,
+                                vsync_start: 1083,
+                                vsync_end: 1088,
+                                vtotal: 1120,
+                                hsync_polarity: Negative,
+                                vsync_polarity: Positive,
+                            },
+                        ),
+                        custom_mode: true,
+                        wallpaper: Some(
+                            Wallpaper {
+                                path: "~/Pictures/wallpaper.png",
+                                mode: Fit,
+                            },
+                        ),
                     },
                 ],
             ),
+            output_profiles: [
+                OutputProfile {
+                    name: "docked",
+                    outputs: Outputs(
+                        [
+                            Output {
+                                off: true,
+                                name: "eDP-1",
+                                scale: None,
+                                transform: Normal,
+                                position: None,
+                                mode: None,
+                                variable_refresh_rate: None,
+                                focus_at_startup: false,
+                                background_color: None,
+                                backdrop_color: None,
+                                mirror: None,
+                                modeline: None,
+                                custom_mode: false,
+                                wallpaper: None,
+                            },
+                            Output {
+                                off: false,
+                                name: "HDMI-A-1",
+                                scale: Some(
+                                    FloatOrInt(
+                                        1.5,
+                                    ),
+                                ),
+                                transform: Normal,
+                                position: None,
+                                mode: None,
+                                variable_refresh_rate: None,
+                                focus_at_startup: false,
+                                background_color: None,
+                                backdrop_color: None,
+                                mirror: None,
+                                modeline: None,
+                                custom_mode: false,
+                                wallpaper: None,
+                            },
+                        ],
+                    ),
+                    workspaces: [
+                        Workspace {
+                            name: WorkspaceName(
+                                "workspace-1",
+                            ),
+                            open_on_output: Some(
+                                "HDMI-A-1",
+                            ),
+                            wallpaper: None,
+                        },
+                    ],
+                },
+            ],
             spawn_at_startup: [
                 SpawnAtStartup {
                     command: [
@@ -4399,11 +6061,40 @@ mod tests {
                         "-e",
                         "fish",
                     ],
+                    name: None,
+                    working_dir: None,
+                    restart: Never,
+                    environment: Environment(
+                        [],
+                    ),
+                },
+                SpawnAtStartup {
+                    command: [
+                        "waybar",
+                    ],
+                    name: Some(
+                        "bar",
+                    ),
+                    working_dir: Some(
+                        "~",
+                    ),
+                    restart: OnFailure,
+                    environment: Environment(
+                        [
+                            EnvironmentVariable {
+                                name: "WAYBAR_DEBUG",
+                                value: Some(
+                                    "1",
+                                ),
+                            },
+                        ],
+                    ),
                 },
             ],
             layout: Layout {
                 focus_ring: FocusRing {
                     off: false,
+                    hide_when_single_window: false,
                     width: FloatOrInt(
                         5.0,
                     ),
@@ -4452,6 +6143,7 @@ mod tests {
                 },
                 border: Border {
                     off: false,
+                    hide_when_single_window: true,
                     width: FloatOrInt(
                         3.0,
                     ),
@@ -4562,6 +6254,28 @@ mod tests {
                         },
                     ),
                 },
+                offscreen_column_indicator: OffscreenColumnIndicator {
+                    on: true,
+                    style: Bar,
+                    width: FloatOrInt(
+                        6.0,
+                    ),
+                    color: Color {
+                        r: 1.0,
+                        g: 1.0,
+                        b: 1.0,
+                        a: 0.5019608,
+                    },
+                    hide_after_ms: 1000,
+                },
+                top_bar: TopBar {
+                    urgent_color: Color {
+                        r: 1.0,
+                        g: 0.0,
+                        b: 0.0,
+                        a: 1.0,
+                    },
+                },
                 preset_column_widths: [
                     Proportion(
                         0.25,
@@ -4620,6 +6334,14 @@ mod tests {
                         0.0,
                     ),
                 },
+                smart_gaps: Some(
+                    SmartGaps {
+                        remove_borders: true,
+                    },
+                ),
+                reading_mode_max_width: FloatOrInt(
+                    1600.0,
+                ),
                 background_color: Color {
                     r: 0.25,
                     g: 0.25,
@@ -4646,6 +6368,12 @@ mod tests {
                 skip_at_startup: true,
                 hide_not_bound: false,
             },
+            power_menu: PowerMenu {
+                entries: [
+                    Suspend,
+                    PowerOff,
+                ],
+            },
             animations: Animations {
                 off: false,
                 slowdown: FloatOrInt(
@@ -4778,9 +6506,30 @@ mod tests {
                         1500.0,
                     ),
                 },
+                wheel_view_scroll: WheelViewScroll {
+                    on: true,
+                    modifiers: Modifiers(
+                        SHIFT | COMPOSITOR,
+                    ),
+                    distance: FloatOrInt(
+                        200.0,
+                    ),
+                },
                 hot_corners: HotCorners {
                     off: false,
                 },
+                touchpad: TouchpadGestures {
+                    three_finger_swipe: WorkspaceSwitch,
+                    four_finger_swipe: Overview,
+                    three_finger_pinch: Off,
+                    four_finger_pinch: Overview,
+                    swipe_threshold: FloatOrInt(
+                        24.0,
+                    ),
+                },
+                touchscreen: TouchscreenGestures {
+                    off: false,
+                },
             },
             overview: Overview {
                 zoom: FloatOrInt(
@@ -4852,6 +6601,11 @@ mod tests {
                             is_floating: None,
                             is_window_cast_target: None,
                             is_urgent: None,
+                            is_xwayland: None,
+                            has_parent: None,
+                            is_modal: None,
+                            sandbox_app_id: None,
+                            activation_app_id: None,
                             at_startup: None,
                         },
                     ],
@@ -4871,6 +6625,11 @@ mod tests {
                             is_floating: None,
                             is_window_cast_target: None,
                             is_urgent: None,
+                            is_xwayland: None,
+                            has_parent: None,
+                            is_modal: None,
+                            sandbox_app_id: None,
+                            activation_app_id: None,
                             at_startup: None,
                         },
                         Match {
@@ -4886,6 +6645,43 @@ mod tests {
                             is_floating: None,
                             is_window_cast_target: None,
                             is_urgent: None,
+                            is_xwayland: None,
+                            has_parent: None,
+                            is_modal: None,
+                            sandbox_app_id: None,
+                            activation_app_id: None,
+                            at_startup: None,
+                        },
+                        Match {
+                            app_id: None,
+                            title: None,
+                            is_active: None,
+                            is_focused: None,
+                            is_active_in_column: None,
+                            is_floating: None,
+                            is_window_cast_target: None,
+                            is_urgent: None,
+                            is_xwayland: None,
+                            has_parent: Some(
+                                true,
+                            ),
+                            is_modal: Some(
+                                true,
+                            ),
+                            sandbox_app_id: Some(
+                                RegexEq(
+                                    Regex(
+                                        "^org\\.gimp",
+                                    ),
+                                ),
+                            ),
+                            activation_app_id: Some(
+                                RegexEq(
+                                    Regex(
+                                        "gimp",
+                                    ),
+                                ),
+                            ),
                             at_startup: None,
                         },
                     ],
@@ -4900,7 +6696,10 @@ mod tests {
                         ),
                     ),
                     open_on_output: Some(
-                        "eDP-1",
+                        [
+                            "eDP-1",
+                            "HDMI-A-1",
+                        ],
                     ),
                     open_on_workspace: None,
                     open_maximized: Some(
@@ -4915,6 +6714,9 @@ mod tests {
                     open_focused: Some(
                         true,
                     ),
+                    open_as_backdrop: Some(
+                        false,
+                    ),
                     min_width: None,
                     min_height: None,
                     max_width: None,
@@ -4978,6 +6780,22 @@ mod tests {
                     opacity: None,
                     geometry_corner_radius: None,
                     clip_to_geometry: None,
+                    csd_margins: Some(
+                        CsdMargins {
+                            top: 10,
+                            bottom: 30,
+                            left: 20,
+                            right: 20,
+                        },
+                    ),
+                    background_color: Some(
+                        Color {
+                            r: 0.0,
+                            g: 0.0,
+                            b: 0.0,
+                            a: 1.0,
+                        },
+                    ),
                     baba_is_float: None,
                     block_out_from: None,
                     variable_refresh_rate: None,
@@ -4997,6 +6815,47 @@ mod tests {
                     ),
                     scroll_factor: None,
                     tiled_state: None,
+                    activation_policy: None,
+                    decoration_mode: Some(
+                        ServerSide,
+                    ),
+                    window_movement_animation: Some(
+                        WindowMovementAnim(
+                            Animation {
+                                off: true,
+                                kind: Spring(
+                                    SpringParams {
+                                        damping_ratio: 1.0,
+                                        stiffness: 800,
+                                        epsilon: 0.0001,
+                                    },
+                                ),
+                            },
+                        ),
+                    ),
+                    window_resize_animation: Some(
+                        WindowResizeAnim {
+                            anim: Animation {
+                                off: false,
+                                kind: Easing(
+                                    EasingParams {
+                                        duration_ms: 100,
+                                        curve: EaseOutQuad,
+                                    },
+                                ),
+                            },
+                            custom_shader: None,
+                        },
+                    ),
+                    always_on_top: Some(
+                        true,
+                    ),
+                    force_windowed_fullscreen: Some(
+                        true,
+                    ),
+                    inhibit_shortcuts_when_fullscreen: Some(
+                        true,
+                    ),
                 },
             ],
             layer_rules: [
@@ -5054,6 +6913,8 @@ mod tests {
                                 "Inhibit",
                             ),
                         ),
+                        area: Anywhere,
+                        activation: Press,
                     },
                     Bind {
                         key: Key {
@@ -5070,6 +6931,8 @@ mod tests {
                         allow_when_locked: false,
                         allow_inhibiting: false,
                         hotkey_overlay_title: None,
+                        area: Anywhere,
+                        activation: Press,
                     },
                     Bind {
                         key: Key {
@@ -5090,6 +6953,8 @@ mod tests {
                         allow_when_locked: true,
                         allow_inhibiting: true,
                         hotkey_overlay_title: None,
+                        area: Anywhere,
+                        activation: Press,
                     },
                     Bind {
                         key: Key {
@@ -5108,6 +6973,8 @@ mod tests {
                         hotkey_overlay_title: Some(
                             None,
                         ),
+                        area: Anywhere,
+                        activation: Press,
                     },
                     Bind {
                         key: Key {
@@ -5124,6 +6991,8 @@ mod tests {
                         allow_when_locked: false,
                         allow_inhibiting: true,
                         hotkey_overlay_title: None,
+                        area: Anywhere,
+                        activation: Press,
                     },
                     Bind {
                         key: Key {
@@ -5142,6 +7011,8 @@ mod tests {
                         allow_when_locked: false,
                         allow_inhibiting: true,
                         hotkey_overlay_title: None,
+                        area: Anywhere,
+                        activation: Press,
                     },
                     Bind {
                         key: Key {
@@ -5158,6 +7029,8 @@ mod tests {
                         allow_when_locked: false,
                         allow_inhibiting: true,
                         hotkey_overlay_title: None,
+                        area: Anywhere,
+                        activation: Press,
                     },
                     Bind {
                         key: Key {
@@ -5176,6 +7049,8 @@ mod tests {
                         allow_when_locked: false,
                         allow_inhibiting: true,
                         hotkey_overlay_title: None,
+                        area: Anywhere,
+                        activation: Press,
                     },
                     Bind {
                         key: Key {
@@ -5194,6 +7069,8 @@ mod tests {
                         allow_when_locked: false,
                         allow_inhibiting: true,
                         hotkey_overlay_title: None,
+                        area: Anywhere,
+                        activation: Press,
                     },
                     Bind {
                         key: Key {
@@ -5210,6 +7087,8 @@ mod tests {
                         allow_when_locked: false,
                         allow_inhibiting: true,
                         hotkey_overlay_title: None,
+                        area: Anywhere,
+                        activation: Press,
                     },
                     Bind {
                         key: Key {
@@ -5230,6 +7109,8 @@ mod tests {
                         allow_when_locked: false,
                         allow_inhibiting: true,
                         hotkey_overlay_title: None,
+                        area: Anywhere,
+                        activation: Press,
                     },
                     Bind {
                         key: Key {
@@ -5250,6 +7131,8 @@ mod tests {
                         allow_when_locked: false,
                         allow_inhibiting: true,
                         hotkey_overlay_title: None,
+                        area: Anywhere,
+                        activation: Press,
                     },
                     Bind {
                         key: Key {
@@ -5268,6 +7151,8 @@ mod tests {
                         allow_when_locked: false,
                         allow_inhibiting: false,
                         hotkey_overlay_title: None,
+                        area: Anywhere,
+                        activation: Press,
                     },
                     Bind {
                         key: Key {
@@ -5284,9 +7169,94 @@ mod tests {
                         allow_when_locked: false,
                         allow_inhibiting: true,
                         hotkey_overlay_title: None,
+                        area: Anywhere,
+                        activation: Press,
+                    },
+                    Bind {
+                        key: Key {
+                            trigger: MouseMiddle,
+                            modifiers: Modifiers(
+                                COMPOSITOR,
+                            ),
+                        },
+                        action: CloseWindow,
+                        repeat: true,
+                        cooldown: None,
+                        allow_when_locked: false,
+                        allow_inhibiting: true,
+                        hotkey_overlay_title: None,
+                        area: TopBar,
+                        activation: Press,
+                    },
+                    Bind {
+                        key: Key {
+                            trigger: Keysym(
+                                XK_space,
+                            ),
+                            modifiers: Modifiers(
+                                COMPOSITOR,
+                            ),
+                        },
+                        action: ToggleOverview,
+                        repeat: true,
+                        cooldown: None,
+                        allow_when_locked: false,
+                        allow_inhibiting: true,
+                        hotkey_overlay_title: None,
+                        area: Anywhere,
+                        activation: Release,
                     },
                 ],
             ),
+            binds_modes: [
+                BindsMode {
+                    name: "resize",
+                    binds: Binds(
+                        [
+                            Bind {
+                                key: Key {
+                                    trigger: Keysym(
+                                        XK_Left,
+                                    ),
+                                    modifiers: Modifiers(
+                                        COMPOSITOR,
+                                    ),
+                                },
+                                action: SetColumnWidth(
+                                    AdjustProportion(
+                                        -10.0,
+                                    ),
+                                ),
+                                repeat: true,
+                                cooldown: None,
+                                allow_when_locked: false,
+                                allow_inhibiting: true,
+                                hotkey_overlay_title: None,
+                                area: Anywhere,
+                                activation: Press,
+                            },
+                            Bind {
+                                key: Key {
+                                    trigger: Keysym(
+                                        XK_Return,
+                                    ),
+                                    modifiers: Modifiers(
+                                        0x0,
+                                    ),
+                                },
+                                action: ExitMode,
+                                repeat: true,
+                                cooldown: None,
+                                allow_when_locked: false,
+                                allow_inhibiting: true,
+                                hotkey_overlay_title: None,
+                                area: Anywhere,
+                                activation: Press,
+                            },
+                        ],
+                    ),
+                },
+            ],
             switch_events: SwitchBinds {
                 lid_open: None,
                 lid_close: None,
@@ -5309,6 +7279,176 @@ mod tests {
                     },
                 ),
             },
+            lid_switch: LidSwitch {
+                internal_output: Off,
+            },
+            tablet_mode: TabletMode {
+                maximize_new_windows: true,
+                hit_target_padding: FloatOrInt(
+                    8.0,
+                ),
+            },
+            idle: Idle {
+                inhibit_when_fullscreen: true,
+                dim_timeout_ms: Some(
+                    240000,
+                ),
+                lock_timeout_ms: Some(
+                    300000,
+                ),
+                lock_command: Some(
+                    [
+                        "swaylock",
+                        "-f",
+                    ],
+                ),
+                power_off_monitors_timeout_ms: Some(
+                    600000,
+                ),
+            },
+            lock_screen: LockScreen {
+                off: false,
+                show_media: true,
+            },
+            hooks: Hooks {
+                window_open: [
+                    WindowOpenHook {
+                        app_id: Some(
+                            RegexEq(
+                                Regex(
+                                    "^firefox$",
+                                ),
+                            ),
+                        ),
+                        title: None,
+                        command: [
+                            "notify-send",
+                            "Firefox opened",
+                        ],
+                    },
+                ],
+                workspace_switch: [
+                    Hook {
+                        command: [
+                            "~/bin/on-workspace-switch",
+                        ],
+                    },
+                ],
+                output_connect: [],
+                idle: [
+                    IdleHook {
+                        timeout_ms: 300000,
+                        command: [
+                            "swaylock",
+                        ],
+                    },
+                ],
+                lock: [],
+            },
+            notifications: Notifications {
+                on: true,
+                position: BottomRight,
+                timeout_ms: 8000,
+            },
+            osd: Osd {
+                off: false,
+                position: Top,
+                width: FloatOrInt(
+                    300.0,
+                ),
+                bar_color: Color {
+                    r: 1.0,
+                    g: 0.78431374,
+                    b: 0.49803922,
+                    a: 1.0,
+                },
+                hide_after_ms: 1500,
+            },
+            power_saving: PowerSaving {
+                on_battery: true,
+                power_saver_profile: false,
+                software_rendering: false,
+                disable_animations: false,
+                animation_slowdown: Some(
+                    FloatOrInt(
+                        0.5,
+                    ),
+                ),
+                disable_shadows: true,
+                max_fps: Some(
+                    30,
+                ),
+            },
+            hidden_windows: HiddenWindows {
+                max_fps: 0,
+            },
+            security: Security {
+                deny_by_default: false,
+                log_only: false,
+                virtual_keyboard: ClientPermission {
+                    off: false,
+                    allow: [
+                        AllowedClient {
+                            executable: Some(
+                                "wtype",
+                            ),
+                            unit: None,
+                        },
+                        AllowedClient {
+                            executable: Some(
+                                "/usr/bin/ydotool",
+                            ),
+                            unit: None,
+                        },
+                    ],
+                },
+                virtual_pointer: ClientPermission {
+                    off: true,
+                    allow: [],
+                },
+                data_control: ClientPermission {
+                    off: false,
+                    allow: [
+                        AllowedClient {
+                            executable: Some(
+                                "cliphist",
+                            ),
+                            unit: None,
+                        },
+                        AllowedClient {
+                            executable: Some(
+                                "wl-paste",
+                            ),
+                            unit: None,
+                        },
+                    ],
+                },
+                screencopy: ClientPermission {
+                    off: false,
+                    allow: [
+                        AllowedClient {
+                            executable: None,
+                            unit: Some(
+                                RegexEq(
+                                    Regex(
+                                        "^app-com\\.obsproject\\.Studio",
+                                    ),
+                                ),
+                            ),
+                        },
+                        AllowedClient {
+                            executable: Some(
+                                "grim",
+                            ),
+                            unit: None,
+                        },
+                    ],
+                },
+                output_management: ClientPermission {
+                    off: false,
+                    allow: [],
+                },
+            },
             debug: DebugConfig {
                 preview_render: None,
                 dbus_interfaces_in_non_session_instances: false,
@@ -5318,6 +7458,7 @@ mod tests {
                 disable_cursor_plane: false,
                 disable_direct_scanout: false,
                 restrict_primary_scanout_to_matching_format: false,
+                scanout_video_and_games_in_any_format: false,
                 render_drm_device: Some(
                     "/dev/dri/renderD129",
                 ),
@@ -5331,6 +7472,8 @@ mod tests {
                 honor_xdg_activation_with_invalid_serial: false,
                 deactivate_unfocused_windows: false,
                 skip_cursor_only_updates_during_vrr: false,
+                restart_on_crash: false,
+                nested_outputs: None,
             },
             workspaces: [
                 Workspace {
@@ -5340,18 +7483,26 @@ mod tests {
                     open_on_output: Some(
                         "eDP-1",
                     ),
+                    wallpaper: None,
                 },
                 Workspace {
                     name: WorkspaceName(
                         "workspace-2",
                     ),
                     open_on_output: None,
+                    wallpaper: Some(
+                        Wallpaper {
+                            path: "~/Pictures/workspace-2.png",
+                            mode: Fill,
+                        },
+                    ),
                 },
                 Workspace {
                     name: WorkspaceName(
                         "workspace-3",
                     ),
                     open_on_output: None,
+                    wallpaper: None,
                 },
             ],
         }
@@ -5415,8 +7566,24 @@ mod tests {
             "-10%".parse::<SizeChange>().unwrap(),
             SizeChange::AdjustProportion(-10.),
         );
+        assert_eq!(
+            "1000px".parse::<SizeChange>().unwrap(),
+            SizeChange::SetFixed(1000),
+        );
+        assert_eq!(
+            "+120px".parse::<SizeChange>().unwrap(),
+            SizeChange::AdjustFixed(120),
+        );
+        assert_eq!(
+            "-120px".parse::<SizeChange>().unwrap(),
+            SizeChange::AdjustFixed(-120),
+        );
 
         assert!("-".parse::<SizeChange>().is_err());
+        assert!("px".parse::<SizeChange>().is_err());
+        assert!("+px".parse::<SizeChange>().is_err());
+        assert!("10%px".parse::<SizeChange>().is_err());
+        assert!("10px%".parse::<SizeChange>().is_err());
         assert!("10% ".parse::<SizeChange>().is_err());
     }
 
@@ -5734,4 +7901,224 @@ mod tests {
         assert_snapshot!(is_on("on", &["on", "off"]), @"off");
         assert_snapshot!(is_on("on", &["on", "on"]), @"on");
     }
+
+    #[test]
+    fn validate_reports_all_errors_with_locations() {
+        let dir = std::env::temp_dir().join(format!("niri-validate-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let main = dir.join("config.kdl");
+        let included = dir.join("included.kdl");
+        std::fs::write(
+            &main,
+            "prefer-no-csd\ninclude \"included.kdl\"\nunknown-node\n",
+        )
+        .unwrap();
+        std::fs::write(&included, "layout {\n    gaps \"big\"\n}\n").unwrap();
+
+        let diagnostics = Config::validate(&main, Some("docked"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let locations: Vec<_> = diagnostics
+            .iter()
+            .map(|d| {
+                let file = d.file.as_deref().map(|f| Path::new(f).file_name().unwrap());
+                (d.severity, file.and_then(OsStr::to_str), d.line)
+            })
+            .collect();
+        assert_eq!(
+            locations,
+            [
+                // The profile doesn't exist.
+                (DiagnosticSeverity::Warning, None, None),
+                (DiagnosticSeverity::Error, Some("included.kdl"), Some(2)),
+                (DiagnosticSeverity::Error, Some("config.kdl"), Some(3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_warns_about_deprecated_syntax() {
+        let dir = std::env::temp_dir().join(format!(
+            "niri-validate-deprecated-test-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let main = dir.join("config.kdl");
+        std::fs::write(
+            &main,
+            "layout {\n    focus-ring {\n        active-color 127 200 255 255\n    }\n}\n",
+        )
+        .unwrap();
+
+        let diagnostics = Config::validate(&main, None);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(diagnostics.len(), 1);
+        let diagnostic = &diagnostics[0];
+        assert_eq!(diagnostic.severity, DiagnosticSeverity::Warning);
+        let message = &diagnostic.message;
+        assert!(message.starts_with("deprecated syntax: color as four numbers"));
+        assert_eq!((diagnostic.line, diagnostic.column), (Some(3), Some(9)));
+    }
+
+    #[test]
+    fn parse_input_device_requires_matcher() {
+        let parsed = do_parse(
+            r#"
+            input {
+                device "Some Mouse" {
+                    off
+                }
+                device pid=0xc52b
+            }
+            "#,
+        );
+        assert_eq!(parsed.input.devices.len(), 2);
+        assert_eq!(parsed.input.devices[0].settings.off, Some(true));
+        assert_eq!(parsed.input.devices[1].pid, Some(0xc52b));
+
+        let res = Config::parse(
+            "test.kdl",
+            r#"
+            input {
+                device {
+                    off
+                }
+            }
+            "#,
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_output_profile_matches() {
+        let profile = OutputProfile {
+            name: String::from("docked"),
+            outputs: Outputs(vec![
+                Output {
+                    name: String::from("eDP-1"),
+                    ..Default::default()
+                },
+                Output {
+                    name: String::from("Some Company Some Monitor 1234"),
+                    ..Default::default()
+                },
+            ]),
+            workspaces: vec![],
+        };
+
+        let laptop = make_output_name("eDP-1", None, None, None);
+        let monitor = make_output_name(
+            "DP-2",
+            Some("Some Company"),
+            Some("Some Monitor"),
+            Some("1234"),
+        );
+        let other = make_output_name("DP-3", None, None, None);
+
+        assert!(profile.matches(&[laptop.clone(), monitor.clone()]));
+        assert!(profile.matches(&[monitor.clone(), laptop.clone()]));
+        assert!(!profile.matches(&[laptop.clone()]));
+        assert!(!profile.matches(&[laptop, monitor, other]));
+        assert!(!profile.matches(&[]));
+    }
+
+    #[test]
+    fn test_client_permission_allows() {
+        let permission = ClientPermission {
+            off: false,
+            allow: vec![
+                AllowedClient {
+                    executable: Some(String::from("wtype")),
+                    unit: None,
+                },
+                AllowedClient {
+                    executable: Some(String::from("/usr/bin/ydotool")),
+                    unit: None,
+                },
+            ],
+        };
+
+        assert!(permission.allows(Some(Path::new("/usr/bin/wtype")), None, false));
+        assert!(permission.allows(Some(Path::new("/usr/bin/ydotool")), None, false));
+        assert!(!permission.allows(Some(Path::new("/usr/local/bin/ydotool")), None, false));
+        assert!(!permission.allows(Some(Path::new("/usr/bin/wtyper")), None, false));
+        assert!(!permission.allows(None, None, false));
+
+        assert!(ClientPermission::default().allows(None, None, false));
+        assert!(!ClientPermission::default().allows(None, None, true));
+
+        let off = ClientPermission {
+            off: true,
+            ..Default::default()
+        };
+        assert!(!off.allows(Some(Path::new("/usr/bin/wtype")), None, false));
+    }
+
+    #[test]
+    fn test_client_permission_allows_unit() {
+        let permission = ClientPermission {
+            off: false,
+            allow: vec![
+                AllowedClient {
+                    executable: None,
+                    unit: Some(RegexEq::from_str(r"^app-obs-\d+\.scope$").unwrap()),
+                },
+                AllowedClient {
+                    executable: Some(String::from("grim")),
+                    unit: Some(RegexEq::from_str(r"^app-").unwrap()),
+                },
+            ],
+        };
+
+        let exe = Some(Path::new("/usr/bin/obs"));
+        assert!(permission.allows(exe, Some("app-obs-1234.scope"), true));
+        assert!(!permission.allows(exe, Some("app-obs.scope"), true));
+        assert!(!permission.allows(exe, None, true));
+
+        let grim = Some(Path::new("/usr/bin/grim"));
+        assert!(permission.allows(grim, Some("app-foot-1.scope"), true));
+        assert!(!permission.allows(grim, Some("session-2.scope"), true));
+        assert!(!permission.allows(grim, None, true));
+    }
+
+    #[test]
+    fn parse_allowed_client_requires_matcher() {
+        let parsed = do_parse(
+            r#"
+            security {
+                screencopy {
+                    allow "grim" unit="^app-"
+                }
+            }
+            "#,
+        );
+        let allow = &parsed.security.screencopy.allow;
+        assert_eq!(allow[0].executable.as_deref(), Some("grim"));
+        assert!(allow[0].unit.is_some());
+
+        let res = Config::parse(
+            "test.kdl",
+            r#"
+            security {
+                screencopy {
+                    allow
+                }
+            }
+            "#,
+        );
+        assert!(res.is_err());
+
+        let res = Config::parse(
+            "test.kdl",
+            r#"
+            security {
+                screencopy {
+                    allow unit="("
+                }
+            }
+            "#,
+        );
+        assert!(res.is_err());
+    }
 }
@@ -0,0 +1,1723 @@
+//! Machine-readable description of the config file structure, for editor integrations.
+//!
+//! The schema lists every node with its arguments, properties and children. Default values are
+//! taken from the `Default` impls of the config structs, so they stay in sync with the parser.
+
+use std::path::PathBuf;
+
+use niri_ipc::{
+    ColumnDisplay, ConfiguredMode, LayoutSwitchTarget, OpacityChange, SizeChange, Transform,
+};
+use serde::Serialize;
+
+use crate::{
+    AccelProfile, ActivationPolicy, AnimationCurve, Animations, BindArea, BlockOutFrom, Border,
+    CenterFocusedColumn, ClickMethod, Color, CsdMargins, Cursor, DecorationMode, DndEdgeViewScroll,
+    DndEdgeWorkspaceSwitch, FloatOrInt, FocusRing, GradientInterpolation, GradientRelativeTo,
+    HSyncPolarity, HiddenWindows, InsertHint, Keyboard, Layout, LidInternalOutput, LidSwitch,
+    ModKey, Modifiers, NotificationPosition, Notifications, OffscreenColumnIndicator,
+    OffscreenColumnIndicatorStyle, Osd, OsdPosition, OutputBarriers, Overview, Percent,
+    PreviewRender, RegexEq, RelativeTo, RestartPolicy, ScrollMethod, Shadow, ShadowOffset,
+    TabIndicator, TabIndicatorPosition, TabletMode, TapButtonMap, TopBar, TouchpadGestures,
+    TouchpadPinchAction, TouchpadSwipeAction, TrackLayout, VSyncPolarity, WallpaperMode,
+    WarpMouseToFocusMode, WheelViewScroll, WorkspaceReference, WorkspaceShadow, XwaylandSatellite,
+};
+
+/// Structure of the config file.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct ConfigSchema {
+    /// Version of niri that the schema describes.
+    pub version: String,
+    /// Nodes allowed at the top level of the config.
+    pub nodes: Vec<SchemaNode>,
+    /// Syntax that is still accepted, but shouldn't be used in new configs.
+    pub deprecations: Vec<Deprecation>,
+}
+
+/// Node of the config.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct SchemaNode {
+    /// Name of the node, or `*` when any name is allowed, like for key binds.
+    pub name: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<&'static str>,
+    /// Whether the node can appear several times in its parent.
+    #[serde(skip_serializing_if = "is_false")]
+    pub repeated: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub arguments: Vec<SchemaArgument>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub properties: Vec<SchemaProperty>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<SchemaNode>,
+}
+
+/// Argument of a node.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct SchemaArgument {
+    #[serde(flatten)]
+    pub ty: SchemaType,
+    pub required: bool,
+    /// Whether this argument can repeat any number of times.
+    #[serde(skip_serializing_if = "is_false")]
+    pub variadic: bool,
+    /// Value used when the whole node is missing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<SchemaValue>,
+}
+
+/// Property of a node.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct SchemaProperty {
+    pub name: &'static str,
+    #[serde(flatten)]
+    pub ty: SchemaType,
+    /// Value used when the property is missing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<SchemaValue>,
+}
+
+/// Type of an argument or a property.
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum SchemaType {
+    Boolean,
+    Integer {
+        min: i64,
+        max: i64,
+    },
+    /// Integer or floating point number.
+    Number {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        min: Option<f64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        max: Option<f64>,
+    },
+    String,
+    /// CSS color, like `"#7fc8ff"` or `"rgb(255 127 0 / 50%)"`.
+    Color,
+    /// Regular expression.
+    Regex,
+    /// One of the listed strings.
+    Enum {
+        values: &'static [&'static str],
+    },
+    /// Integer index or string name.
+    IndexOrName,
+}
+
+/// Value of an argument or a property.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum SchemaValue {
+    Boolean(bool),
+    Integer(i64),
+    Number(f64),
+    String(String),
+}
+
+/// Syntax that is still accepted, but shouldn't be used in new configs.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct Deprecation {
+    /// Description of the deprecated syntax.
+    pub syntax: &'static str,
+    /// What to use instead.
+    pub replacement: &'static str,
+}
+
+/// Colors written as four numbers, reported by [`Config::validate`](crate::Config::validate) too.
+pub(crate) const RGBA_COLOR_DEPRECATION: Deprecation = Deprecation {
+    syntax: "color as four numbers from 0 to 255, like `active-color 127 200 255 255`",
+    replacement: "color as a CSS string, like `active-color \"#7fc8ffff\"`",
+};
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+/// Type that can be an argument or a property in the config.
+trait Scalar {
+    fn schema_type() -> SchemaType;
+}
+
+/// Type whose values can be written in the schema as defaults.
+trait DefaultValue: Scalar {
+    fn schema_value(&self) -> SchemaValue;
+}
+
+impl Scalar for bool {
+    fn schema_type() -> SchemaType {
+        SchemaType::Boolean
+    }
+}
+
+impl DefaultValue for bool {
+    fn schema_value(&self) -> SchemaValue {
+        SchemaValue::Boolean(*self)
+    }
+}
+
+macro_rules! integer {
+    ($($ty:ty),+) => {
+        $(
+            impl Scalar for $ty {
+                fn schema_type() -> SchemaType {
+                    SchemaType::Integer {
+                        min: i64::from(<$ty>::MIN),
+                        max: i64::from(<$ty>::MAX),
+                    }
+                }
+            }
+
+            impl DefaultValue for $ty {
+                fn schema_value(&self) -> SchemaValue {
+                    SchemaValue::Integer(i64::from(*self))
+                }
+            }
+        )+
+    };
+}
+
+integer!(u8, u16, u32, i8, i16, i32);
+
+macro_rules! large_integer {
+    ($($ty:ty),+) => {
+        $(
+            impl Scalar for $ty {
+                fn schema_type() -> SchemaType {
+                    SchemaType::Integer {
+                        min: 0,
+                        max: i64::MAX,
+                    }
+                }
+            }
+        )+
+    };
+}
+
+// JSON numbers beyond i64 aren't portable, and such large values make no sense in the config.
+large_integer!(u64, usize);
+
+impl Scalar for f32 {
+    fn schema_type() -> SchemaType {
+        SchemaType::Number {
+            min: None,
+            max: None,
+        }
+    }
+}
+
+impl Scalar for f64 {
+    fn schema_type() -> SchemaType {
+        SchemaType::Number {
+            min: None,
+            max: None,
+        }
+    }
+}
+
+impl DefaultValue for f64 {
+    fn schema_value(&self) -> SchemaValue {
+        SchemaValue::Number(*self)
+    }
+}
+
+impl<const MIN: i32, const MAX: i32> Scalar for FloatOrInt<MIN, MAX> {
+    fn schema_type() -> SchemaType {
+        SchemaType::Number {
+            min: Some(f64::from(MIN)),
+            max: Some(f64::from(MAX)),
+        }
+    }
+}
+
+impl<const MIN: i32, const MAX: i32> DefaultValue for FloatOrInt<MIN, MAX> {
+    fn schema_value(&self) -> SchemaValue {
+        SchemaValue::Number(self.0)
+    }
+}
+
+impl DefaultValue for String {
+    fn schema_value(&self) -> SchemaValue {
+        SchemaValue::String(self.clone())
+    }
+}
+
+macro_rules! string {
+    ($($ty:ty),+) => {
+        $(
+            impl Scalar for $ty {
+                fn schema_type() -> SchemaType {
+                    SchemaType::String
+                }
+            }
+        )+
+    };
+}
+
+// These are parsed from strings with their own syntax, like `"Mod+Shift"` or `"+10%"`.
+string!(
+    String,
+    PathBuf,
+    Percent,
+    Modifiers,
+    ConfiguredMode,
+    GradientInterpolation,
+    SizeChange,
+    OpacityChange,
+    LayoutSwitchTarget
+);
+
+impl Scalar for Color {
+    fn schema_type() -> SchemaType {
+        SchemaType::Color
+    }
+}
+
+impl DefaultValue for Color {
+    fn schema_value(&self) -> SchemaValue {
+        let [r, g, b, a] = self
+            .to_array_unpremul()
+            .map(|x| (x.clamp(0., 1.) * 255.).round() as u8);
+        SchemaValue::String(format!("#{r:02x}{g:02x}{b:02x}{a:02x}"))
+    }
+}
+
+impl Scalar for RegexEq {
+    fn schema_type() -> SchemaType {
+        SchemaType::Regex
+    }
+}
+
+impl Scalar for WorkspaceReference {
+    fn schema_type() -> SchemaType {
+        SchemaType::IndexOrName
+    }
+}
+
+macro_rules! enumeration {
+    ($ty:ident { $($variant:ident => $value:literal),+ $(,)? }) => {
+        impl Scalar for $ty {
+            fn schema_type() -> SchemaType {
+                SchemaType::Enum {
+                    values: &[$($value),+],
+                }
+            }
+        }
+
+        impl DefaultValue for $ty {
+            fn schema_value(&self) -> SchemaValue {
+                let value = match self {
+                    $($ty::$variant => $value),+
+                };
+                SchemaValue::String(String::from(value))
+            }
+        }
+    };
+}
+
+enumeration!(CenterFocusedColumn {
+    Never => "never",
+    Always => "always",
+    OnOverflow => "on-overflow",
+});
+enumeration!(TrackLayout {
+    Global => "global",
+    Window => "window",
+    Workspace => "workspace",
+});
+enumeration!(ClickMethod {
+    Clickfinger => "clickfinger",
+    ButtonAreas => "button-areas",
+});
+enumeration!(AccelProfile {
+    Adaptive => "adaptive",
+    Flat => "flat",
+});
+enumeration!(ScrollMethod {
+    NoScroll => "no-scroll",
+    TwoFinger => "two-finger",
+    Edge => "edge",
+    OnButtonDown => "on-button-down",
+});
+enumeration!(TapButtonMap {
+    LeftRightMiddle => "left-right-middle",
+    LeftMiddleRight => "left-middle-right",
+});
+enumeration!(ActivationPolicy {
+    Focus => "focus",
+    MarkUrgent => "mark-urgent",
+    Ignore => "ignore",
+});
+enumeration!(DecorationMode {
+    ServerSide => "server-side",
+    ClientSide => "client-side",
+});
+enumeration!(WarpMouseToFocusMode {
+    CenterXy => "center-xy",
+    CenterXyAlways => "center-xy-always",
+});
+enumeration!(ModKey {
+    Ctrl => "Ctrl",
+    Shift => "Shift",
+    Alt => "Alt",
+    Super => "Super",
+    IsoLevel3Shift => "ISO_Level3_Shift",
+    IsoLevel5Shift => "ISO_Level5_Shift",
+});
+enumeration!(Transform {
+    Normal => "normal",
+    _90 => "90",
+    _180 => "180",
+    _270 => "270",
+    Flipped => "flipped",
+    Flipped90 => "flipped-90",
+    Flipped180 => "flipped-180",
+    Flipped270 => "flipped-270",
+});
+enumeration!(HSyncPolarity {
+    Positive => "+hsync",
+    Negative => "-hsync",
+});
+enumeration!(VSyncPolarity {
+    Positive => "+vsync",
+    Negative => "-vsync",
+});
+enumeration!(WallpaperMode {
+    Fill => "fill",
+    Fit => "fit",
+    Tile => "tile",
+});
+enumeration!(RestartPolicy {
+    Never => "never",
+    OnFailure => "on-failure",
+    Always => "always",
+});
+enumeration!(GradientRelativeTo {
+    Window => "window",
+    WorkspaceView => "workspace-view",
+});
+enumeration!(TabIndicatorPosition {
+    Left => "left",
+    Right => "right",
+    Top => "top",
+    Bottom => "bottom",
+});
+enumeration!(OffscreenColumnIndicatorStyle {
+    Bar => "bar",
+    Dots => "dots",
+});
+enumeration!(ColumnDisplay {
+    Normal => "normal",
+    Tabbed => "tabbed",
+});
+enumeration!(AnimationCurve {
+    Linear => "linear",
+    EaseOutQuad => "ease-out-quad",
+    EaseOutCubic => "ease-out-cubic",
+    EaseOutExpo => "ease-out-expo",
+});
+enumeration!(TouchpadSwipeAction {
+    WorkspaceOrColumn => "workspace-or-column",
+    WorkspaceSwitch => "workspace-switch",
+    ColumnScroll => "column-scroll",
+    Overview => "overview",
+    Off => "off",
+});
+enumeration!(TouchpadPinchAction {
+    Overview => "overview",
+    Off => "off",
+});
+enumeration!(LidInternalOutput {
+    OffWithExternal => "off-with-external",
+    Off => "off",
+    On => "on",
+});
+enumeration!(NotificationPosition {
+    TopLeft => "top-left",
+    TopRight => "top-right",
+    BottomLeft => "bottom-left",
+    BottomRight => "bottom-right",
+});
+enumeration!(OsdPosition {
+    Top => "top",
+    Center => "center",
+    Bottom => "bottom",
+});
+enumeration!(BlockOutFrom {
+    Screencast => "screencast",
+    ScreenCapture => "screen-capture",
+});
+enumeration!(RelativeTo {
+    TopLeft => "top-left",
+    TopRight => "top-right",
+    BottomLeft => "bottom-left",
+    BottomRight => "bottom-right",
+    Top => "top",
+    Bottom => "bottom",
+    Left => "left",
+    Right => "right",
+});
+enumeration!(BindArea {
+    Anywhere => "anywhere",
+    Background => "background",
+    TopBar => "top-bar",
+});
+enumeration!(PreviewRender {
+    Screencast => "screencast",
+    ScreenCapture => "screen-capture",
+});
+
+impl SchemaNode {
+    fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            description: None,
+            repeated: false,
+            arguments: Vec::new(),
+            properties: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    fn describe(mut self, description: &'static str) -> Self {
+        self.description = Some(description);
+        self
+    }
+
+    fn repeated(mut self) -> Self {
+        self.repeated = true;
+        self
+    }
+
+    fn push_arg<T: Scalar>(mut self, required: bool, variadic: bool) -> Self {
+        self.arguments.push(SchemaArgument {
+            ty: T::schema_type(),
+            required,
+            variadic,
+            default: None,
+        });
+        self
+    }
+
+    fn arg<T: Scalar>(self) -> Self {
+        self.push_arg::<T>(true, false)
+    }
+
+    fn optional_arg<T: Scalar>(self) -> Self {
+        self.push_arg::<T>(false, false)
+    }
+
+    fn args<T: Scalar>(self) -> Self {
+        self.push_arg::<T>(false, true)
+    }
+
+    fn default<T: DefaultValue>(mut self, value: &T) -> Self {
+        let arg = self
+            .arguments
+            .last_mut()
+            .expect("default must follow an argument");
+        arg.default = Some(value.schema_value());
+        self
+    }
+
+    fn prop<T: Scalar>(mut self, name: &'static str) -> Self {
+        self.properties.push(SchemaProperty {
+            name,
+            ty: T::schema_type(),
+            default: None,
+        });
+        self
+    }
+
+    fn prop_default<T: DefaultValue>(mut self, name: &'static str, value: &T) -> Self {
+        self.properties.push(SchemaProperty {
+            name,
+            ty: T::schema_type(),
+            default: Some(value.schema_value()),
+        });
+        self
+    }
+
+    fn children(mut self, children: Vec<SchemaNode>) -> Self {
+        self.children.extend(children);
+        self
+    }
+}
+
+/// Node that is either present or not.
+fn flag(name: &'static str) -> SchemaNode {
+    SchemaNode::new(name)
+}
+
+/// Node with a single argument that has a default value.
+fn value<T: DefaultValue>(name: &'static str, default: &T) -> SchemaNode {
+    SchemaNode::new(name).arg::<T>().default(default)
+}
+
+/// Node with a single argument that is unset when the node is missing.
+fn option<T: Scalar>(name: &'static str) -> SchemaNode {
+    SchemaNode::new(name).arg::<T>()
+}
+
+fn section(name: &'static str, description: &'static str, children: Vec<SchemaNode>) -> SchemaNode {
+    SchemaNode::new(name)
+        .describe(description)
+        .children(children)
+}
+
+fn action(name: &'static str) -> SchemaNode {
+    SchemaNode::new(name)
+}
+
+fn gradient(name: &'static str) -> SchemaNode {
+    SchemaNode::new(name)
+        .prop::<Color>("from")
+        .prop::<Color>("to")
+        .prop_default("angle", &180i16)
+        .prop_default("relative-to", &GradientRelativeTo::Window)
+        .prop::<GradientInterpolation>("in")
+}
+
+fn shadow_offset(default: Option<ShadowOffset>) -> SchemaNode {
+    let node = SchemaNode::new("offset");
+    match default {
+        Some(offset) => node
+            .prop_default("x", &offset.x)
+            .prop_default("y", &offset.y),
+        None => node
+            .prop::<FloatOrInt<-65535, 65535>>("x")
+            .prop::<FloatOrInt<-65535, 65535>>("y"),
+    }
+}
+
+/// Pointer device section with the options shared by all pointer devices, and `extra` ones.
+fn pointer_device(name: &'static str, extra: Vec<SchemaNode>) -> SchemaNode {
+    SchemaNode::new(name)
+        .children(vec![
+            flag("off"),
+            flag("natural-scroll"),
+            value("accel-speed", &FloatOrInt::<-1, 1>(0.)),
+            option::<AccelProfile>("accel-profile"),
+            option::<ScrollMethod>("scroll-method"),
+            option::<u32>("scroll-button"),
+            flag("left-handed"),
+            flag("middle-emulation"),
+        ])
+        .children(extra)
+}
+
+fn input() -> SchemaNode {
+    let keyboard = Keyboard::default();
+    let output_barriers = OutputBarriers::default();
+
+    let device_options = [
+        "off",
+        "tap",
+        "dwt",
+        "dwtp",
+        "drag",
+        "drag-lock",
+        "natural-scroll",
+        "left-handed",
+        "middle-emulation",
+    ]
+    .into_iter()
+    .map(option::<bool>)
+    .chain([
+        option::<FloatOrInt<-1, 1>>("accel-speed"),
+        option::<AccelProfile>("accel-profile"),
+        option::<ScrollMethod>("scroll-method"),
+        option::<u32>("scroll-button"),
+        option::<ClickMethod>("click-method"),
+        option::<TapButtonMap>("tap-button-map"),
+    ])
+    .collect();
+
+    section(
+        "input",
+        "Settings for keyboards, pointers, tablets and touchscreens.",
+        vec![
+            SchemaNode::new("keyboard").children(vec![
+                SchemaNode::new("xkb").children(vec![
+                    value("rules", &keyboard.xkb.rules),
+                    value("model", &keyboard.xkb.model),
+                    value("layout", &keyboard.xkb.layout),
+                    value("variant", &keyboard.xkb.variant),
+                    option::<String>("options"),
+                    option::<String>("file"),
+                ]),
+                value("repeat-delay", &keyboard.repeat_delay),
+                value("repeat-rate", &keyboard.repeat_rate),
+                value("track-layout", &keyboard.track_layout),
+                flag("numlock"),
+                flag("sticky-keys"),
+                value("slow-keys-delay", &keyboard.slow_keys_delay),
+                value("bounce-keys-delay", &keyboard.bounce_keys_delay),
+                flag("layout-indicator"),
+            ]),
+            pointer_device(
+                "touchpad",
+                vec![
+                    flag("tap"),
+                    flag("dwt"),
+                    flag("dwtp"),
+                    option::<bool>("drag"),
+                    flag("drag-lock"),
+                    option::<ClickMethod>("click-method"),
+                    option::<TapButtonMap>("tap-button-map"),
+                    flag("disabled-on-external-mouse"),
+                    option::<FloatOrInt<0, 100>>("scroll-factor"),
+                ],
+            ),
+            pointer_device("mouse", vec![option::<FloatOrInt<0, 100>>("scroll-factor")]),
+            pointer_device("trackpoint", vec![]),
+            pointer_device("trackball", vec![]),
+            SchemaNode::new("tablet").children(vec![
+                flag("off"),
+                SchemaNode::new("calibration-matrix").args::<f32>(),
+                option::<String>("map-to-output"),
+                flag("map-to-focused-window"),
+                SchemaNode::new("active-area")
+                    .prop_default("x", &FloatOrInt::<0, 1>(0.))
+                    .prop_default("y", &FloatOrInt::<0, 1>(0.))
+                    .prop_default("width", &FloatOrInt::<0, 1>(1.))
+                    .prop_default("height", &FloatOrInt::<0, 1>(1.)),
+                flag("left-handed"),
+            ]),
+            SchemaNode::new("touch").children(vec![flag("off"), option::<String>("map-to-output")]),
+            SchemaNode::new("device")
+                .describe("Settings for a single input device, matched by name and/or USB ids.")
+                .repeated()
+                .optional_arg::<String>()
+                .prop::<u32>("vid")
+                .prop::<u32>("pid")
+                .children(device_options),
+            flag("disable-power-key-handling"),
+            flag("mpris-media-keys"),
+            SchemaNode::new("warp-mouse-to-focus").prop::<WarpMouseToFocusMode>("mode"),
+            SchemaNode::new("focus-follows-mouse")
+                .prop::<Percent>("max-scroll-amount")
+                .prop::<u16>("delay-ms")
+                .prop_default("ignore-while-scrolling", &false)
+                .prop_default("content-only", &false),
+            SchemaNode::new("output-barriers").children(vec![
+                value("push-through", &output_barriers.push_through),
+                flag("block-corners"),
+            ]),
+            flag("workspace-auto-back-and-forth"),
+            option::<ModKey>("mod-key"),
+            option::<ModKey>("mod-key-nested"),
+            value("activation-policy", &ActivationPolicy::default()),
+        ],
+    )
+}
+
+fn wallpaper() -> SchemaNode {
+    SchemaNode::new("wallpaper")
+        .arg::<String>()
+        .prop_default("mode", &WallpaperMode::default())
+}
+
+fn output() -> SchemaNode {
+    SchemaNode::new("output")
+        .describe("Settings for a monitor, matched by connector or by make, model and serial.")
+        .repeated()
+        .arg::<String>()
+        .children(vec![
+            flag("off"),
+            option::<FloatOrInt<0, 10>>("scale"),
+            value("transform", &Transform::Normal),
+            SchemaNode::new("position")
+                .prop::<i32>("x")
+                .prop::<i32>("y"),
+            option::<ConfiguredMode>("mode"),
+            SchemaNode::new("variable-refresh-rate").prop_default("on-demand", &false),
+            flag("focus-at-startup"),
+            option::<Color>("background-color"),
+            option::<Color>("backdrop-color"),
+            option::<String>("mirror"),
+            SchemaNode::new("modeline")
+                .arg::<FloatOrInt<0, 10000>>()
+                .arg::<u16>()
+                .arg::<u16>()
+                .arg::<u16>()
+                .arg::<u16>()
+                .arg::<u16>()
+                .arg::<u16>()
+                .arg::<u16>()
+                .arg::<u16>()
+                .arg::<HSyncPolarity>()
+                .arg::<VSyncPolarity>(),
+            flag("custom-mode"),
+            wallpaper(),
+        ])
+}
+
+fn workspace() -> SchemaNode {
+    SchemaNode::new("workspace")
+        .describe("Named workspace that always exists.")
+        .repeated()
+        .arg::<String>()
+        .children(vec![option::<String>("open-on-output"), wallpaper()])
+}
+
+fn environment() -> SchemaNode {
+    SchemaNode::new("environment")
+        .describe("Environment variables, set with a string or unset with null.")
+        .children(vec![SchemaNode::new("*").repeated().arg::<String>()])
+}
+
+fn preset_sizes(name: &'static str) -> SchemaNode {
+    SchemaNode::new(name).children(vec![
+        option::<f64>("proportion").repeated(),
+        option::<i32>("fixed").repeated(),
+    ])
+}
+
+fn ring(
+    name: &'static str,
+    description: &'static str,
+    width: FloatOrInt<0, 65535>,
+    [active, inactive, urgent]: [Color; 3],
+) -> SchemaNode {
+    section(
+        name,
+        description,
+        vec![
+            flag("off"),
+            flag("hide-when-single-window"),
+            value("width", &width),
+            value("active-color", &active),
+            value("inactive-color", &inactive),
+            value("urgent-color", &urgent),
+            gradient("active-gradient"),
+            gradient("inactive-gradient"),
+            gradient("urgent-gradient"),
+        ],
+    )
+}
+
+fn layout() -> SchemaNode {
+    let layout = Layout::default();
+    let focus_ring = FocusRing::default();
+    let border = Border::default();
+    let shadow = Shadow::default();
+    let tab_indicator = TabIndicator::default();
+    let insert_hint = InsertHint::default();
+    let offscreen = OffscreenColumnIndicator::default();
+
+    section(
+        "layout",
+        "Settings for the scrollable tiling layout.",
+        vec![
+            ring(
+                "focus-ring",
+                "Ring drawn around the active window.",
+                focus_ring.width,
+                [
+                    focus_ring.active_color,
+                    focus_ring.inactive_color,
+                    focus_ring.urgent_color,
+                ],
+            ),
+            ring(
+                "border",
+                "Border drawn around every window.",
+                border.width,
+                [
+                    border.active_color,
+                    border.inactive_color,
+                    border.urgent_color,
+                ],
+            ),
+            section(
+                "shadow",
+                "Shadow drawn behind windows.",
+                vec![
+                    flag("on"),
+                    shadow_offset(Some(shadow.offset)),
+                    value("softness", &shadow.softness),
+                    value("spread", &shadow.spread),
+                    value("draw-behind-window", &shadow.draw_behind_window),
+                    value("color", &shadow.color),
+                    option::<Color>("inactive-color"),
+                ],
+            ),
+            section(
+                "tab-indicator",
+                "Indicator of the tabs in tabbed columns.",
+                vec![
+                    flag("off"),
+                    flag("hide-when-single-tab"),
+                    flag("place-within-column"),
+                    value("gap", &tab_indicator.gap),
+                    value("width", &tab_indicator.width),
+                    match tab_indicator.length.total_proportion {
+                        Some(proportion) => {
+                            SchemaNode::new("length").prop_default("total-proportion", &proportion)
+                        }
+                        None => SchemaNode::new("length").prop::<f64>("total-proportion"),
+                    },
+                    value("position", &tab_indicator.position),
+                    value("gaps-between-tabs", &tab_indicator.gaps_between_tabs),
+                    value("corner-radius", &tab_indicator.corner_radius),
+                    option::<Color>("active-color"),
+                    option::<Color>("inactive-color"),
+                    option::<Color>("urgent-color"),
+                    gradient("active-gradient"),
+                    gradient("inactive-gradient"),
+                    gradient("urgent-gradient"),
+                ],
+            ),
+            section(
+                "insert-hint",
+                "Hint shown where a dragged window will be inserted.",
+                vec![
+                    flag("off"),
+                    value("color", &insert_hint.color),
+                    gradient("gradient"),
+                ],
+            ),
+            section(
+                "offscreen-column-indicator",
+                "Indicator of columns outside the view.",
+                vec![
+                    flag("on"),
+                    value("style", &offscreen.style),
+                    value("width", &offscreen.width),
+                    value("color", &offscreen.color),
+                    value("hide-after-ms", &offscreen.hide_after_ms),
+                ],
+            ),
+            SchemaNode::new("top-bar")
+                .children(vec![value("urgent-color", &TopBar::default().urgent_color)]),
+            preset_sizes("preset-column-widths"),
+            SchemaNode::new("default-column-width")
+                .children(vec![option::<f64>("proportion"), option::<i32>("fixed")]),
+            preset_sizes("preset-window-heights"),
+            value("center-focused-column", &layout.center_focused_column),
+            flag("always-center-single-column"),
+            flag("empty-workspace-above-first"),
+            value("default-column-display", &layout.default_column_display),
+            value("gaps", &layout.gaps),
+            SchemaNode::new("struts").children(vec![
+                value("left", &layout.struts.left),
+                value("right", &layout.struts.right),
+                value("top", &layout.struts.top),
+                value("bottom", &layout.struts.bottom),
+            ]),
+            SchemaNode::new("smart-gaps").children(vec![flag("remove-borders")]),
+            value("reading-mode-max-width", &layout.reading_mode_max_width),
+            value("background-color", &layout.background_color),
+        ],
+    )
+}
+
+fn animation(name: &'static str, custom_shader: bool) -> SchemaNode {
+    let mut children = vec![
+        flag("off"),
+        SchemaNode::new("spring")
+            .prop::<f64>("damping-ratio")
+            .prop::<u32>("stiffness")
+            .prop::<f64>("epsilon"),
+        option::<u32>("duration-ms"),
+        option::<AnimationCurve>("curve"),
+    ];
+    if custom_shader {
+        children.push(option::<String>("custom-shader"));
+    }
+    SchemaNode::new(name).children(children)
+}
+
+fn animations() -> SchemaNode {
+    let animations = Animations::default();
+
+    section(
+        "animations",
+        "Animation settings. Each animation takes either spring or easing parameters.",
+        vec![
+            flag("off"),
+            value("slowdown", &animations.slowdown),
+            animation("workspace-switch", false),
+            animation("window-open", true),
+            animation("window-close", true),
+            animation("horizontal-view-movement", false),
+            animation("window-movement", false),
+            animation("window-resize", true),
+            animation("config-notification-open-close", false),
+            animation("screenshot-ui-open", false),
+            animation("overview-open-close", false),
+        ],
+    )
+}
+
+fn gestures() -> SchemaNode {
+    let view_scroll = DndEdgeViewScroll::default();
+    let workspace_switch = DndEdgeWorkspaceSwitch::default();
+    let wheel = WheelViewScroll::default();
+    let touchpad = TouchpadGestures::default();
+
+    section(
+        "gestures",
+        "Settings for mouse and touchpad gestures.",
+        vec![
+            SchemaNode::new("dnd-edge-view-scroll").children(vec![
+                value("trigger-width", &view_scroll.trigger_width),
+                value("delay-ms", &view_scroll.delay_ms),
+                value("max-speed", &view_scroll.max_speed),
+            ]),
+            SchemaNode::new("dnd-edge-workspace-switch").children(vec![
+                value("trigger-height", &workspace_switch.trigger_height),
+                value("delay-ms", &workspace_switch.delay_ms),
+                value("max-speed", &workspace_switch.max_speed),
+            ]),
+            SchemaNode::new("wheel-view-scroll").children(vec![
+                flag("on"),
+                option::<Modifiers>("modifiers"),
+                value("distance", &wheel.distance),
+            ]),
+            SchemaNode::new("hot-corners").children(vec![flag("off")]),
+            SchemaNode::new("touchpad").children(vec![
+                value("three-finger-swipe", &touchpad.three_finger_swipe),
+                value("four-finger-swipe", &touchpad.four_finger_swipe),
+                value("three-finger-pinch", &touchpad.three_finger_pinch),
+                value("four-finger-pinch", &touchpad.four_finger_pinch),
+                value("swipe-threshold", &touchpad.swipe_threshold),
+            ]),
+            SchemaNode::new("touchscreen").children(vec![flag("off")]),
+        ],
+    )
+}
+
+fn overview() -> SchemaNode {
+    let overview = Overview::default();
+    let shadow = WorkspaceShadow::default();
+
+    section(
+        "overview",
+        "Settings for the zoomed-out overview of all workspaces.",
+        vec![
+            value("zoom", &overview.zoom),
+            value("backdrop-color", &overview.backdrop_color),
+            SchemaNode::new("workspace-shadow").children(vec![
+                flag("off"),
+                shadow_offset(Some(shadow.offset)),
+                value("softness", &shadow.softness),
+                value("spread", &shadow.spread),
+                value("color", &shadow.color),
+            ]),
+        ],
+    )
+}
+
+fn border_rule(name: &'static str) -> SchemaNode {
+    SchemaNode::new(name).children(vec![
+        flag("off"),
+        flag("on"),
+        option::<FloatOrInt<0, 65535>>("width"),
+        option::<Color>("active-color"),
+        option::<Color>("inactive-color"),
+        option::<Color>("urgent-color"),
+        gradient("active-gradient"),
+        gradient("inactive-gradient"),
+        gradient("urgent-gradient"),
+    ])
+}
+
+fn shadow_rule() -> SchemaNode {
+    SchemaNode::new("shadow").children(vec![
+        flag("off"),
+        flag("on"),
+        shadow_offset(None),
+        option::<FloatOrInt<0, 1024>>("softness"),
+        option::<FloatOrInt<-1024, 1024>>("spread"),
+        option::<bool>("draw-behind-window"),
+        option::<Color>("color"),
+        option::<Color>("inactive-color"),
+    ])
+}
+
+fn window_rule() -> SchemaNode {
+    let csd_margins = CsdMargins::default();
+    let window_match = |name| {
+        SchemaNode::new(name)
+            .repeated()
+            .prop::<RegexEq>("app-id")
+            .prop::<RegexEq>("title")
+            .prop::<bool>("is-active")
+            .prop::<bool>("is-focused")
+            .prop::<bool>("is-active-in-column")
+            .prop::<bool>("is-floating")
+            .prop::<bool>("is-window-cast-target")
+            .prop::<bool>("is-urgent")
+            .prop::<bool>("is-xwayland")
+            .prop::<bool>("has-parent")
+            .prop::<bool>("is-modal")
+            .prop::<RegexEq>("sandbox-app-id")
+            .prop::<RegexEq>("activation-app-id")
+            .prop::<bool>("at-startup")
+    };
+
+    let mut children = vec![
+        window_match("match"),
+        window_match("exclude"),
+        SchemaNode::new("default-column-width")
+            .children(vec![option::<f64>("proportion"), option::<i32>("fixed")]),
+        SchemaNode::new("default-window-height")
+            .children(vec![option::<f64>("proportion"), option::<i32>("fixed")]),
+        SchemaNode::new("open-on-output").args::<String>(),
+        option::<String>("open-on-workspace"),
+    ];
+    children.extend(
+        [
+            "open-maximized",
+            "open-fullscreen",
+            "open-floating",
+            "open-focused",
+            "open-as-backdrop",
+        ]
+        .into_iter()
+        .map(option::<bool>),
+    );
+    children.extend(
+        ["min-width", "min-height", "max-width", "max-height"]
+            .into_iter()
+            .map(option::<u16>),
+    );
+    children.extend([
+        border_rule("focus-ring"),
+        border_rule("border"),
+        shadow_rule(),
+        SchemaNode::new("tab-indicator").children(vec![
+            option::<Color>("active-color"),
+            option::<Color>("inactive-color"),
+            option::<Color>("urgent-color"),
+            gradient("active-gradient"),
+            gradient("inactive-gradient"),
+            gradient("urgent-gradient"),
+        ]),
+        option::<bool>("draw-border-with-background"),
+        option::<f32>("opacity"),
+        SchemaNode::new("geometry-corner-radius").args::<f32>(),
+        option::<bool>("clip-to-geometry"),
+        SchemaNode::new("csd-margins")
+            .prop_default("top", &csd_margins.top)
+            .prop_default("bottom", &csd_margins.bottom)
+            .prop_default("left", &csd_margins.left)
+            .prop_default("right", &csd_margins.right),
+        option::<Color>("background-color"),
+        option::<bool>("baba-is-float"),
+        option::<BlockOutFrom>("block-out-from"),
+        option::<bool>("variable-refresh-rate"),
+        option::<ColumnDisplay>("default-column-display"),
+        SchemaNode::new("default-floating-position")
+            .prop::<FloatOrInt<-65535, 65535>>("x")
+            .prop::<FloatOrInt<-65535, 65535>>("y")
+            .prop_default("relative-to", &RelativeTo::default()),
+        option::<FloatOrInt<0, 100>>("scroll-factor"),
+        option::<bool>("tiled-state"),
+        option::<ActivationPolicy>("activation-policy"),
+        option::<DecorationMode>("decoration-mode"),
+        animation("window-movement-animation", false),
+        animation("window-resize-animation", true),
+        option::<bool>("always-on-top"),
+        option::<bool>("force-windowed-fullscreen"),
+        option::<bool>("inhibit-shortcuts-when-fullscreen"),
+    ]);
+
+    SchemaNode::new("window-rule")
+        .describe("Settings for the windows that match any `match` and no `exclude`.")
+        .repeated()
+        .children(children)
+}
+
+fn layer_rule() -> SchemaNode {
+    let layer_match = |name| {
+        SchemaNode::new(name)
+            .repeated()
+            .prop::<RegexEq>("namespace")
+            .prop::<bool>("at-startup")
+    };
+
+    SchemaNode::new("layer-rule")
+        .describe("Settings for the layer-shell surfaces that match any `match` and no `exclude`.")
+        .repeated()
+        .children(vec![
+            layer_match("match"),
+            layer_match("exclude"),
+            option::<f32>("opacity"),
+            option::<BlockOutFrom>("block-out-from"),
+            shadow_rule(),
+            SchemaNode::new("geometry-corner-radius").args::<f32>(),
+            option::<bool>("place-within-backdrop"),
+            option::<bool>("baba-is-float"),
+        ])
+}
+
+fn actions() -> Vec<SchemaNode> {
+    vec![
+        action("quit").prop_default("skip-confirmation", &false),
+        action("suspend"),
+        action("power-off-monitors"),
+        action("power-on-monitors"),
+        action("toggle-monitor-power").optional_arg::<String>(),
+        action("adjust-brightness").arg::<i8>(),
+        action("toggle-debug-tint"),
+        action("debug-toggle-opaque-regions"),
+        action("debug-toggle-damage"),
+        action("debug-add-nested-output"),
+        action("debug-remove-nested-output"),
+        action("spawn").args::<String>(),
+        action("do-screen-transition").prop::<u16>("delay-ms"),
+        action("screenshot").prop_default("show-pointer", &true),
+        action("screenshot-screen")
+            .prop_default("write-to-disk", &true)
+            .prop_default("show-pointer", &true),
+        action("screenshot-window").prop_default("write-to-disk", &true),
+        action("toggle-keyboard-shortcuts-inhibit"),
+        action("release-pointer-constraint"),
+        action("close-window"),
+        action("fullscreen-window"),
+        action("toggle-windowed-fullscreen"),
+        action("focus-window-in-column").arg::<u8>(),
+        action("focus-window-previous"),
+        action("focus-urgent"),
+        action("focus-column-left"),
+        action("focus-column-right"),
+        action("focus-column-first"),
+        action("focus-column-last"),
+        action("focus-column-right-or-first"),
+        action("focus-column-left-or-last"),
+        action("focus-column").arg::<usize>(),
+        action("focus-column-previous"),
+        action("focus-window-or-monitor-up"),
+        action("focus-window-or-monitor-down"),
+        action("focus-column-or-monitor-left"),
+        action("focus-column-or-monitor-right"),
+        action("focus-window-down"),
+        action("focus-window-up"),
+        action("focus-window-down-or-column-left"),
+        action("focus-window-down-or-column-right"),
+        action("focus-window-up-or-column-left"),
+        action("focus-window-up-or-column-right"),
+        action("focus-window-or-workspace-down"),
+        action("focus-window-or-workspace-up"),
+        action("focus-window-top"),
+        action("focus-window-bottom"),
+        action("focus-window-down-or-top"),
+        action("focus-window-up-or-bottom"),
+        action("move-column-left"),
+        action("move-column-right"),
+        action("move-column-to-first"),
+        action("move-column-to-last"),
+        action("move-column-left-or-to-monitor-left"),
+        action("move-column-right-or-to-monitor-right"),
+        action("move-column-to-index").arg::<usize>(),
+        action("move-window-down"),
+        action("move-window-up"),
+        action("move-window-down-or-to-workspace-down"),
+        action("move-window-up-or-to-workspace-up"),
+        action("consume-or-expel-window-left"),
+        action("consume-or-expel-window-right"),
+        action("consume-window-into-column"),
+        action("expel-window-from-column"),
+        action("swap-window-left"),
+        action("swap-window-right"),
+        action("toggle-column-tabbed-display"),
+        action("set-column-display").arg::<ColumnDisplay>(),
+        action("center-column"),
+        action("center-window"),
+        action("center-visible-columns"),
+        action("focus-workspace-down"),
+        action("focus-workspace-up"),
+        action("focus-workspace").arg::<WorkspaceReference>(),
+        action("focus-workspace-previous"),
+        action("move-window-to-workspace-down"),
+        action("move-window-to-workspace-up"),
+        action("move-window-to-workspace")
+            .arg::<WorkspaceReference>()
+            .prop_default("focus", &true),
+        action("move-column-to-workspace-down").prop_default("focus", &true),
+        action("move-column-to-workspace-up").prop_default("focus", &true),
+        action("move-column-to-workspace")
+            .arg::<WorkspaceReference>()
+            .prop_default("focus", &true),
+        action("move-workspace-down"),
+        action("move-workspace-up"),
+        action("move-workspace-to-index").arg::<usize>(),
+        action("move-workspace-to-monitor").arg::<String>(),
+        action("set-workspace-name").arg::<String>(),
+        action("unset-workspace-name"),
+        action("focus-monitor-left"),
+        action("focus-monitor-right"),
+        action("focus-monitor-down"),
+        action("focus-monitor-up"),
+        action("focus-monitor-previous"),
+        action("focus-monitor-next"),
+        action("focus-monitor").arg::<String>(),
+        action("move-window-to-monitor-left"),
+        action("move-window-to-monitor-right"),
+        action("move-window-to-monitor-down"),
+        action("move-window-to-monitor-up"),
+        action("move-window-to-monitor-previous"),
+        action("move-window-to-monitor-next"),
+        action("move-window-to-monitor").arg::<String>(),
+        action("move-column-to-monitor-left"),
+        action("move-column-to-monitor-right"),
+        action("move-column-to-monitor-down"),
+        action("move-column-to-monitor-up"),
+        action("move-column-to-monitor-previous"),
+        action("move-column-to-monitor-next"),
+        action("move-column-to-monitor").arg::<String>(),
+        action("set-window-width").arg::<SizeChange>(),
+        action("set-window-height").arg::<SizeChange>(),
+        action("reset-window-height"),
+        action("switch-preset-column-width"),
+        action("switch-preset-window-width"),
+        action("switch-preset-window-height"),
+        action("maximize-column"),
+        action("fullscreen-column"),
+        action("toggle-reading-mode"),
+        action("set-column-width").arg::<SizeChange>(),
+        action("expand-column-to-available-width"),
+        action("switch-layout").arg::<LayoutSwitchTarget>(),
+        action("show-hotkey-overlay"),
+        action("show-cheatsheet"),
+        action("show-window-switcher"),
+        action("move-workspace-to-monitor-left"),
+        action("move-workspace-to-monitor-right"),
+        action("move-workspace-to-monitor-down"),
+        action("move-workspace-to-monitor-up"),
+        action("move-workspace-to-monitor-previous"),
+        action("move-workspace-to-monitor-next"),
+        action("toggle-window-floating"),
+        action("move-window-to-floating"),
+        action("move-window-to-tiling"),
+        action("focus-floating"),
+        action("focus-tiling"),
+        action("switch-focus-between-floating-and-tiling"),
+        action("toggle-window-rule-opacity"),
+        action("toggle-always-on-top"),
+        action("set-window-opacity").arg::<OpacityChange>(),
+        action("move-pip-to-next-corner"),
+        action("switch-preset-pip-width"),
+        action("set-dynamic-cast-window"),
+        action("set-dynamic-cast-monitor").optional_arg::<String>(),
+        action("clear-dynamic-cast-target"),
+        action("toggle-overview"),
+        action("open-overview"),
+        action("close-overview"),
+        action("enter-mode").arg::<String>(),
+        action("exit-mode"),
+        action("toggle-do-not-disturb"),
+        action("dismiss-notifications"),
+    ]
+}
+
+fn bind() -> SchemaNode {
+    SchemaNode::new("*")
+        .describe("Key combination like `Mod+T`, with exactly one action as its child.")
+        .repeated()
+        .prop_default("repeat", &true)
+        .prop::<u64>("cooldown-ms")
+        .prop_default("allow-when-locked", &false)
+        .prop_default("allow-inhibiting", &true)
+        .prop::<String>("hotkey-overlay-title")
+        .prop_default("area", &BindArea::Anywhere)
+        .prop_default("on-release", &false)
+        .prop_default("double-tap", &false)
+        .children(actions())
+}
+
+fn switch_action(name: &'static str) -> SchemaNode {
+    SchemaNode::new(name).children(vec![SchemaNode::new("spawn").args::<String>()])
+}
+
+fn client_permission(name: &'static str) -> SchemaNode {
+    SchemaNode::new(name).children(vec![
+        flag("off"),
+        SchemaNode::new("allow")
+            .repeated()
+            .optional_arg::<String>()
+            .prop::<RegexEq>("unit"),
+    ])
+}
+
+fn hook(name: &'static str) -> SchemaNode {
+    SchemaNode::new(name).repeated().args::<String>()
+}
+
+/// Returns the schema of the config file.
+pub fn config_schema() -> ConfigSchema {
+    let cursor = Cursor::default();
+    let xwayland_satellite = XwaylandSatellite::default();
+    let notifications = Notifications::default();
+    let osd = Osd::default();
+
+    let nodes = vec![
+        SchemaNode::new("include")
+            .describe("Includes other config files; the path can contain `*` wildcards.")
+            .repeated()
+            .arg::<String>(),
+        SchemaNode::new("profile")
+            .describe("Config nodes used only when niri runs with this profile.")
+            .repeated()
+            .arg::<String>(),
+        input(),
+        output(),
+        SchemaNode::new("output-profile")
+            .describe("Monitor and workspace settings used when all its outputs are connected.")
+            .repeated()
+            .arg::<String>()
+            .children(vec![output(), workspace()]),
+        SchemaNode::new("spawn-at-startup")
+            .describe("Command to run when niri starts.")
+            .repeated()
+            .args::<String>()
+            .prop::<String>("name")
+            .prop::<String>("working-dir")
+            .prop_default("restart", &RestartPolicy::default())
+            .children(vec![environment()]),
+        layout(),
+        flag("prefer-no-csd").describe("Ask clients to omit their client-side decorations."),
+        SchemaNode::new("screenshot-path")
+            .arg::<String>()
+            .default(&String::from(
+                "~/Pictures/Screenshots/Screenshot from %Y-%m-%d %H-%M-%S.png",
+            )),
+        section(
+            "cursor",
+            "Settings for the mouse cursor.",
+            vec![
+                value("xcursor-theme", &cursor.xcursor_theme),
+                value("xcursor-size", &cursor.xcursor_size),
+                flag("hide-when-typing"),
+                option::<u32>("hide-after-inactive-ms"),
+            ],
+        ),
+        SchemaNode::new("clipboard").children(vec![flag("disable-primary")]),
+        SchemaNode::new("hotkey-overlay")
+            .children(vec![flag("skip-at-startup"), flag("hide-not-bound")]),
+        SchemaNode::new("power-menu").children(vec![
+            flag("logout"),
+            flag("suspend"),
+            flag("hibernate"),
+            flag("reboot"),
+            flag("power-off"),
+        ]),
+        animations(),
+        gestures(),
+        overview(),
+        environment(),
+        SchemaNode::new("xwayland-satellite")
+            .children(vec![flag("off"), value("path", &xwayland_satellite.path)]),
+        window_rule(),
+        layer_rule(),
+        SchemaNode::new("binds")
+            .describe("Key bindings.")
+            .children(vec![bind()]),
+        SchemaNode::new("binds-mode")
+            .describe("Key bindings active after the `enter-mode` action.")
+            .repeated()
+            .arg::<String>()
+            .children(vec![bind()]),
+        SchemaNode::new("switch-events").children(vec![
+            switch_action("lid-open"),
+            switch_action("lid-close"),
+            switch_action("tablet-mode-on"),
+            switch_action("tablet-mode-off"),
+        ]),
+        SchemaNode::new("lid-switch").children(vec![value(
+            "internal-output",
+            &LidSwitch::default().internal_output,
+        )]),
+        SchemaNode::new("tablet-mode").children(vec![
+            flag("maximize-new-windows"),
+            value(
+                "hit-target-padding",
+                &TabletMode::default().hit_target_padding,
+            ),
+        ]),
+        SchemaNode::new("idle").children(vec![
+            flag("inhibit-when-fullscreen"),
+            option::<u32>("dim-timeout-ms"),
+            option::<u32>("lock-timeout-ms"),
+            SchemaNode::new("lock-command").args::<String>(),
+            option::<u32>("power-off-monitors-timeout-ms"),
+        ]),
+        SchemaNode::new("lock-screen").children(vec![flag("off"), flag("show-media")]),
+        section(
+            "hooks",
+            "Commands to run when something happens.",
+            vec![
+                hook("window-open")
+                    .prop::<RegexEq>("app-id")
+                    .prop::<RegexEq>("title"),
+                hook("workspace-switch"),
+                hook("output-connect"),
+                hook("idle").prop::<u32>("timeout-ms"),
+                hook("lock"),
+            ],
+        ),
+        SchemaNode::new("notifications").children(vec![
+            flag("on"),
+            value("position", &notifications.position),
+            value("timeout-ms", &notifications.timeout_ms),
+        ]),
+        SchemaNode::new("osd").children(vec![
+            flag("off"),
+            value("position", &osd.position),
+            value("width", &osd.width),
+            value("bar-color", &osd.bar_color),
+            value("hide-after-ms", &osd.hide_after_ms),
+        ]),
+        SchemaNode::new("power-saving").children(vec![
+            flag("on-battery"),
+            flag("power-saver-profile"),
+            flag("software-rendering"),
+            flag("disable-animations"),
+            option::<FloatOrInt<0, { i32::MAX }>>("animation-slowdown"),
+            flag("disable-shadows"),
+            option::<u16>("max-fps"),
+        ]),
+        SchemaNode::new("hidden-windows")
+            .children(vec![value("max-fps", &HiddenWindows::default().max_fps)]),
+        section(
+            "security",
+            "Which clients can use privileged protocols.",
+            vec![
+                flag("deny-by-default"),
+                flag("log-only"),
+                client_permission("virtual-keyboard"),
+                client_permission("virtual-pointer"),
+                client_permission("data-control"),
+                client_permission("screencopy"),
+                client_permission("output-management"),
+            ],
+        ),
+        section(
+            "debug",
+            "Debugging options that can change or go away at any time.",
+            vec![
+                option::<PreviewRender>("preview-render"),
+                flag("dbus-interfaces-in-non-session-instances"),
+                flag("wait-for-frame-completion-before-queueing"),
+                flag("wait-for-frame-completion-in-pipewire"),
+                flag("enable-overlay-planes"),
+                flag("disable-cursor-plane"),
+                flag("disable-direct-scanout"),
+                flag("restrict-primary-scanout-to-matching-format"),
+                flag("scanout-video-and-games-in-any-format"),
+                option::<PathBuf>("render-drm-device"),
+                flag("force-pipewire-invalid-modifier"),
+                flag("emulate-zero-presentation-time"),
+                flag("disable-resize-throttling"),
+                flag("disable-transactions"),
+                flag("keep-laptop-panel-on-when-lid-is-closed"),
+                flag("disable-monitor-names"),
+                flag("strict-new-window-focus-policy"),
+                flag("honor-xdg-activation-with-invalid-serial"),
+                flag("deactivate-unfocused-windows"),
+                flag("skip-cursor-only-updates-during-vrr"),
+                flag("restart-on-crash"),
+                option::<u8>("nested-outputs"),
+            ],
+        ),
+        workspace(),
+    ];
+
+    ConfigSchema {
+        version: String::from(env!("CARGO_PKG_VERSION")),
+        nodes,
+        deprecations: vec![RGBA_COLOR_DEPRECATION],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashSet};
+    use std::path::Path;
+
+    use super::*;
+    use crate::Config;
+
+    /// Names used anywhere in the schema.
+    #[derive(Default)]
+    struct SchemaNames<'a> {
+        nodes: HashSet<&'a str>,
+        properties: HashSet<&'a str>,
+        /// Nodes whose children can have any name, like `binds`.
+        any_children: HashSet<&'a str>,
+    }
+
+    fn collect_names<'a>(node: &'a SchemaNode, names: &mut SchemaNames<'a>) {
+        names.nodes.insert(node.name);
+        names
+            .properties
+            .extend(node.properties.iter().map(|prop| prop.name));
+        if node.children.iter().any(|child| child.name == "*") {
+            names.any_children.insert(node.name);
+        }
+        for child in &node.children {
+            collect_names(child, names);
+        }
+    }
+
+    /// Returns the KDL code blocks of the wiki pages that are expected to parse.
+    fn wiki_snippets() -> Vec<(String, String)> {
+        let wiki = Path::new(env!("CARGO_MANIFEST_DIR")).join("../wiki");
+
+        let mut snippets = Vec::new();
+        for entry in std::fs::read_dir(wiki).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().map_or(true, |ext| ext != "md") {
+                continue;
+            }
+
+            let text = std::fs::read_to_string(&path).unwrap();
+            // Code blocks can be inside callouts.
+            let mut lines = text.lines().map(|line| {
+                let line = line.trim();
+                line.strip_prefix('>').map_or(line, str::trim_start)
+            });
+            while let Some(line) = lines.next() {
+                let Some(info) = line.strip_prefix("```kdl") else {
+                    continue;
+                };
+                let code: String = lines
+                    .by_ref()
+                    .take_while(|line| !line.starts_with("```"))
+                    .map(|line| format!("{line}\n"))
+                    .collect();
+                if !info.contains("must-fail") {
+                    snippets.push((path.display().to_string(), code));
+                }
+            }
+        }
+        snippets
+    }
+
+    fn find_missing(
+        nodes: &[knuffel::ast::SpannedNode<knuffel::span::Span>],
+        any_name: bool,
+        names: &SchemaNames,
+        missing: &mut BTreeSet<String>,
+    ) {
+        for node in nodes {
+            let name = &**node.node_name;
+            if !any_name && !names.nodes.contains(name) {
+                missing.insert(format!("node `{name}`"));
+            }
+            for prop in node.properties.keys() {
+                let prop = &***prop;
+                if !names.properties.contains(prop) {
+                    missing.insert(format!("property `{prop}` of `{name}`"));
+                }
+            }
+
+            let children = node
+                .children
+                .as_ref()
+                .map_or(&[][..], |children| &children[..]);
+            find_missing(children, names.any_children.contains(name), names, missing);
+        }
+    }
+
+    fn kdl_value(value: &SchemaValue) -> String {
+        match value {
+            SchemaValue::Boolean(x) => x.to_string(),
+            SchemaValue::Integer(x) => x.to_string(),
+            SchemaValue::Number(x) => x.to_string(),
+            SchemaValue::String(x) => format!("{x:?}"),
+        }
+    }
+
+    /// Writes every default argument value as a node, skipping colors which don't round-trip
+    /// exactly through 8-bit hex.
+    fn write_defaults(node: &SchemaNode, path: &str, out: &mut String) {
+        // The presence of these nodes changes the config by itself.
+        const SKIP: &[&str] = &["input.output-barriers"];
+
+        if node.repeated || node.name == "*" || SKIP.contains(&path) {
+            return;
+        }
+
+        if let Some(arg) = node.arguments.first() {
+            if let Some(default) = &arg.default {
+                if !matches!(arg.ty, SchemaType::Color) {
+                    out.push_str(&format!("{} {}\n", node.name, kdl_value(default)));
+                }
+            }
+            return;
+        }
+
+        let mut children = String::new();
+        for child in &node.children {
+            write_defaults(child, &format!("{path}.{}", child.name), &mut children);
+        }
+
+        // Empty sections can change the config too, like `smart-gaps {}`.
+        if !children.is_empty() {
+            out.push_str(&format!("{} {{\n{children}}}\n", node.name));
+        }
+    }
+
+    #[test]
+    fn defaults_match_empty_config() {
+        let schema = config_schema();
+
+        let mut text = String::new();
+        for node in &schema.nodes {
+            write_defaults(node, node.name, &mut text);
+        }
+
+        let empty = Config::parse("empty.kdl", "").unwrap();
+        let defaults = Config::parse("defaults.kdl", &text).unwrap();
+        assert_eq!(empty, defaults, "{text}");
+    }
+
+    /// Checks the nodes and the properties of the default config and of the wiki examples
+    /// against the schema, so that options documented in the wiki can't be left out of it.
+    #[test]
+    fn every_documented_config_node_is_in_schema() {
+        let schema = config_schema();
+        let mut names = SchemaNames::default();
+        for node in &schema.nodes {
+            collect_names(node, &mut names);
+        }
+
+        let mut configs = wiki_snippets();
+        configs.push((
+            String::from("default-config.kdl"),
+            String::from(include_str!("../../resources/default-config.kdl")),
+        ));
+        // Make sure that the code blocks are found at all.
+        assert!(configs.len() > 100, "{}", configs.len());
+
+        let mut missing = BTreeSet::new();
+        for (filename, text) in &configs {
+            // Only look at configs that niri accepts, the others are reported by the wiki test.
+            if Config::parse(filename, text).is_err() {
+                continue;
+            }
+
+            let document = knuffel::parse_ast(filename, text).unwrap();
+            find_missing(&document.nodes, false, &names, &mut missing);
+        }
+        assert!(missing.is_empty(), "missing from the schema: {missing:?}");
+    }
+
+    #[test]
+    fn color_default_is_hex() {
+        assert_eq!(
+            Color::from_rgba8_unpremul(0x7f, 0xc8, 0xff, 0xff).schema_value(),
+            SchemaValue::String(String::from("#7fc8ffff"))
+        );
+    }
+}
@@ -0,0 +1,477 @@
+//! Reading and changing individual config values by name.
+//!
+//! The names are the dot-separated paths to the config nodes, like `layout.gaps`.
+
+use std::fs;
+use std::path::Path;
+
+use crate::include::{self, top_level_nodes};
+use crate::{Config, FloatOrInt, FocusFollowsMouse, WarpMouseToFocus};
+
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    /// Node that is either present or not, `true` or `false`.
+    Flag,
+    /// Node with a number argument.
+    Number { max: f64 },
+    /// Node with an optional millisecond argument, `null` when unset.
+    OptionalMs,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Value {
+    Flag(bool),
+    Number(f64),
+    OptionalMs(Option<u32>),
+}
+
+const VALUES: &[(&str, Kind)] = &[
+    ("prefer-no-csd", Kind::Flag),
+    ("input.focus-follows-mouse", Kind::Flag),
+    ("input.warp-mouse-to-focus", Kind::Flag),
+    ("input.workspace-auto-back-and-forth", Kind::Flag),
+    ("layout.gaps", Kind::Number { max: 65535. }),
+    ("layout.always-center-single-column", Kind::Flag),
+    ("layout.focus-ring.off", Kind::Flag),
+    ("layout.focus-ring.width", Kind::Number { max: 65535. }),
+    ("layout.border.off", Kind::Flag),
+    ("layout.border.width", Kind::Number { max: 65535. }),
+    ("cursor.hide-when-typing", Kind::Flag),
+    ("cursor.hide-after-inactive-ms", Kind::OptionalMs),
+    ("animations.off", Kind::Flag),
+    (
+        "animations.slowdown",
+        Kind::Number {
+            max: i32::MAX as f64,
+        },
+    ),
+];
+
+/// Returns the names of all values that can be read and changed.
+pub fn value_names() -> impl Iterator<Item = &'static str> {
+    VALUES.iter().map(|(name, _)| *name)
+}
+
+fn kind(name: &str) -> Result<Kind, String> {
+    VALUES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, kind)| *kind)
+        .ok_or_else(|| {
+            let names = value_names().collect::<Vec<_>>().join(", ");
+            format!("unknown config value {name:?}, supported values are: {names}")
+        })
+}
+
+fn parse(name: &str, value: &str) -> Result<Value, String> {
+    match kind(name)? {
+        Kind::Flag => match value {
+            "true" | "on" => Ok(Value::Flag(true)),
+            "false" | "off" => Ok(Value::Flag(false)),
+            _ => Err(format!("{name} must be true or false, got {value:?}")),
+        },
+        Kind::Number { max } => {
+            let number = value
+                .parse::<f64>()
+                .ok()
+                .filter(|x| (0. ..=max).contains(x))
+                .ok_or_else(|| format!("{name} must be a number from 0 to {max}, got {value:?}"))?;
+            Ok(Value::Number(number))
+        }
+        Kind::OptionalMs => {
+            if value == "null" {
+                return Ok(Value::OptionalMs(None));
+            }
+
+            let ms = value
+                .parse::<u32>()
+                .map_err(|_| format!("{name} must be a number of milliseconds or null"))?;
+            Ok(Value::OptionalMs(Some(ms)))
+        }
+    }
+}
+
+impl Config {
+    /// Returns the current value with the given name, formatted as text.
+    pub fn get_value(&self, name: &str) -> Result<String, String> {
+        kind(name)?;
+
+        let value = match name {
+            "prefer-no-csd" => Value::Flag(self.prefer_no_csd),
+            "input.focus-follows-mouse" => Value::Flag(self.input.focus_follows_mouse.is_some()),
+            "input.warp-mouse-to-focus" => Value::Flag(self.input.warp_mouse_to_focus.is_some()),
+            "input.workspace-auto-back-and-forth" => {
+                Value::Flag(self.input.workspace_auto_back_and_forth)
+            }
+            "layout.gaps" => Value::Number(self.layout.gaps.0),
+            "layout.always-center-single-column" => {
+                Value::Flag(self.layout.always_center_single_column)
+            }
+            "layout.focus-ring.off" => Value::Flag(self.layout.focus_ring.off),
+            "layout.focus-ring.width" => Value::Number(self.layout.focus_ring.width.0),
+            "layout.border.off" => Value::Flag(self.layout.border.off),
+            "layout.border.width" => Value::Number(self.layout.border.width.0),
+            "cursor.hide-when-typing" => Value::Flag(self.cursor.hide_when_typing),
+            "cursor.hide-after-inactive-ms" => {
+                Value::OptionalMs(self.cursor.hide_after_inactive_ms)
+            }
+            "animations.off" => Value::Flag(self.animations.off),
+            "animations.slowdown" => Value::Number(self.animations.slowdown.0),
+            _ => unreachable!("all names in VALUES must be handled"),
+        };
+
+        Ok(match value {
+            Value::Flag(flag) => flag.to_string(),
+            Value::Number(number) => number.to_string(),
+            Value::OptionalMs(Some(ms)) => ms.to_string(),
+            Value::OptionalMs(None) => String::from("null"),
+        })
+    }
+
+    /// Changes the value with the given name, parsing it from text.
+    pub fn set_value(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match (name, parse(name, value)?) {
+            ("prefer-no-csd", Value::Flag(flag)) => self.prefer_no_csd = flag,
+            ("input.focus-follows-mouse", Value::Flag(flag)) => {
+                let ffm = &mut self.input.focus_follows_mouse;
+                if !flag {
+                    *ffm = None;
+                } else if ffm.is_none() {
+                    *ffm = Some(FocusFollowsMouse {
+                        max_scroll_amount: None,
+                        delay_ms: None,
+                        ignore_while_scrolling: false,
+                        content_only: false,
+                    });
+                }
+            }
+            ("input.warp-mouse-to-focus", Value::Flag(flag)) => {
+                let warp = &mut self.input.warp_mouse_to_focus;
+                if !flag {
+                    *warp = None;
+                } else if warp.is_none() {
+                    *warp = Some(WarpMouseToFocus { mode: None });
+                }
+            }
+            ("input.workspace-auto-back-and-forth", Value::Flag(flag)) => {
+                self.input.workspace_auto_back_and_forth = flag;
+            }
+            ("layout.gaps", Value::Number(number)) => self.layout.gaps = FloatOrInt(number),
+            ("layout.always-center-single-column", Value::Flag(flag)) => {
+                self.layout.always_center_single_column = flag;
+            }
+            ("layout.focus-ring.off", Value::Flag(flag)) => self.layout.focus_ring.off = flag,
+            ("layout.focus-ring.width", Value::Number(number)) => {
+                self.layout.focus_ring.width = FloatOrInt(number);
+            }
+            ("layout.border.off", Value::Flag(flag)) => self.layout.border.off = flag,
+            ("layout.border.width", Value::Number(number)) => {
+                self.layout.border.width = FloatOrInt(number);
+            }
+            ("cursor.hide-when-typing", Value::Flag(flag)) => self.cursor.hide_when_typing = flag,
+            ("cursor.hide-after-inactive-ms", Value::OptionalMs(ms)) => {
+                self.cursor.hide_after_inactive_ms = ms;
+            }
+            ("animations.off", Value::Flag(flag)) => self.animations.off = flag,
+            ("animations.slowdown", Value::Number(number)) => {
+                self.animations.slowdown = FloatOrInt(number);
+            }
+            _ => unreachable!("all names in VALUES must be handled"),
+        }
+
+        Ok(())
+    }
+}
+
+/// Changes the value with the given name in the config file at `path`.
+///
+/// The change goes into the file that already sets the value or contains its section, which can
+/// be an included file, and into the main file otherwise. Values set inside a `profile` block
+/// are not changed and return an error instead, since the change would only apply to that
+/// profile.
+pub fn persist_value(
+    path: &Path,
+    profile: Option<&str>,
+    name: &str,
+    value: &str,
+) -> Result<(), String> {
+    let expanded = include::expand(path, profile, &mut Vec::new())
+        .map_err(|err| format!("error reading the config: {err}"))?;
+    let nodes: Vec<&str> = name.split('.').collect();
+
+    let (file, text) = match find_deepest_node(&expanded.text, &nodes) {
+        Some(node) => {
+            let offset = node.as_ptr() as usize - expanded.text.as_ptr() as usize;
+            let (source, source_offset) = expanded
+                .source_offset(offset)
+                .ok_or_else(|| format!("error finding where {name} is set in the config"))?;
+            let (file, text) = expanded.source(source);
+
+            // Editing the file only finds the nodes outside of profiles.
+            let found = find_deepest_node(text, &nodes)
+                .map(|node| node.as_ptr() as usize - text.as_ptr() as usize);
+            if found != Some(source_offset) {
+                return Err(format!(
+                    "{name} is set inside a profile in {file:?}, change it there instead"
+                ));
+            }
+
+            (file, text)
+        }
+        None => expanded.source(0),
+    };
+
+    let text = persist_value_in_text(text, name, value)?;
+    fs::write(file, text).map_err(|err| format!("error writing {file:?}: {err}"))
+}
+
+/// Finds the deepest node along the dot-separated path that is present in the text.
+fn find_deepest_node<'a>(text: &'a str, path: &[&str]) -> Option<&'a str> {
+    let (name, rest) = path.split_first()?;
+    let node = top_level_nodes(text)
+        .into_iter()
+        .rev()
+        .find(|node| node.name == *name)?;
+
+    let deeper = match node.children {
+        Some(children) if !rest.is_empty() => find_deepest_node(children, rest),
+        _ => None,
+    };
+    Some(deeper.unwrap_or(node.text))
+}
+
+/// Changes the value with the given name in the config file text.
+///
+/// The rest of the text, including comments and formatting, is kept as is.
+fn persist_value_in_text(text: &str, name: &str, value: &str) -> Result<String, String> {
+    let mut path: Vec<&str> = name.split('.').collect();
+    let leaf = path.pop().unwrap();
+
+    let edit = match parse(name, value)? {
+        Value::Flag(true) => Edit::Keep(leaf.to_owned()),
+        Value::Flag(false) | Value::OptionalMs(None) => Edit::Remove,
+        Value::Number(number) => Edit::Replace(format!("{leaf} {number}")),
+        Value::OptionalMs(Some(ms)) => Edit::Replace(format!("{leaf} {ms}")),
+    };
+
+    Ok(edit_node(text, &path, leaf, &edit, 0))
+}
+
+enum Edit {
+    /// Add the node if it's missing, keeping the existing one otherwise.
+    Keep(String),
+    /// Replace the node, or add it if it's missing.
+    Replace(String),
+    Remove,
+}
+
+fn edit_node(text: &str, sections: &[&str], leaf: &str, edit: &Edit, depth: usize) -> String {
+    let nodes = top_level_nodes(text);
+    let offset = |s: &str| s.as_ptr() as usize - text.as_ptr() as usize;
+
+    if let Some((section, rest)) = sections.split_first() {
+        let children = nodes
+            .iter()
+            .rev()
+            .find(|node| node.name == *section)
+            .and_then(|node| node.children);
+        if let Some(children) = children {
+            let start = offset(children);
+            let edited = edit_node(children, rest, leaf, edit, depth + 1);
+            return format!(
+                "{}{edited}{}",
+                &text[..start],
+                &text[start + children.len()..]
+            );
+        }
+    } else if let Some(node) = nodes.iter().rev().find(|node| node.name == leaf) {
+        let start = offset(node.text);
+        let end = start + node.text.len();
+        return match edit {
+            Edit::Keep(_) => text.to_owned(),
+            Edit::Replace(new) => format!("{}{new}{}", &text[..start], &text[end..]),
+            Edit::Remove => {
+                // Remove the whole line if the node was alone on it.
+                let line_start = text[..start].trim_end_matches([' ', '\t']).len();
+                let after = text[end..].trim_start_matches([' ', '\t', ';']);
+                let alone = (line_start == 0 || text[..line_start].ends_with('\n'))
+                    && (after.is_empty() || after.starts_with('\n'));
+                if alone {
+                    let after = after.strip_prefix('\n').unwrap_or(after);
+                    format!("{}{after}", &text[..line_start])
+                } else {
+                    format!("{}{}", &text[..start], &text[end..])
+                }
+            }
+        };
+    }
+
+    // The node is missing, add it along with any missing sections.
+    let new = match edit {
+        Edit::Keep(new) | Edit::Replace(new) => new,
+        Edit::Remove => return text.to_owned(),
+    };
+    let block = new_block(sections, new, depth);
+
+    match nodes.last() {
+        Some(last) => {
+            let end = offset(last.text) + last.text.len();
+            format!("{}\n{}{}", &text[..end], block.trim_end(), &text[end..])
+        }
+        None if depth == 0 => format!("{text}{block}"),
+        None => format!("\n{block}{}", "    ".repeat(depth - 1)),
+    }
+}
+
+fn new_block(sections: &[&str], leaf: &str, depth: usize) -> String {
+    let indent = "    ".repeat(depth);
+    match sections.split_first() {
+        None => format!("{indent}{leaf}\n"),
+        Some((section, rest)) => {
+            let inner = new_block(rest, leaf, depth + 1);
+            format!("{indent}{section} {{\n{inner}{indent}}}\n")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn get_and_set_values() {
+        let mut config = Config::default();
+        for name in value_names() {
+            let value = config.get_value(name).unwrap();
+            config.set_value(name, &value).unwrap();
+            assert_eq!(config.get_value(name).unwrap(), value);
+        }
+
+        config.set_value("layout.gaps", "8").unwrap();
+        assert_eq!(config.layout.gaps, FloatOrInt(8.));
+
+        config
+            .set_value("cursor.hide-after-inactive-ms", "null")
+            .unwrap();
+        assert_eq!(config.cursor.hide_after_inactive_ms, None);
+
+        assert!(config.set_value("layout.gaps", "-1").is_err());
+        assert!(config.set_value("animations.off", "yes").is_err());
+        assert!(config.get_value("layout.unknown").is_err());
+    }
+
+    #[test]
+    fn persist_replaces_existing_node() {
+        let text = "layout {\n    // Gaps.\n    gaps 16\n}\n";
+        assert_eq!(
+            persist_value_in_text(text, "layout.gaps", "8").unwrap(),
+            "layout {\n    // Gaps.\n    gaps 8\n}\n"
+        );
+    }
+
+    #[test]
+    fn persist_adds_missing_nodes() {
+        let text = "layout {\n    gaps 16\n}\n";
+        assert_eq!(
+            persist_value_in_text(text, "layout.border.width", "2").unwrap(),
+            "layout {\n    gaps 16\n    border {\n        width 2\n    }\n}\n"
+        );
+        assert_eq!(
+            persist_value_in_text(text, "animations.slowdown", "2").unwrap(),
+            "layout {\n    gaps 16\n}\nanimations {\n    slowdown 2\n}\n"
+        );
+    }
+
+    #[test]
+    fn persist_flags() {
+        let text = "input {\n    focus-follows-mouse max-scroll-amount=\"10%\"\n}\n";
+        assert_eq!(
+            persist_value_in_text(text, "input.focus-follows-mouse", "true").unwrap(),
+            text
+        );
+        assert_eq!(
+            persist_value_in_text(text, "input.focus-follows-mouse", "false").unwrap(),
+            "input {\n}\n"
+        );
+    }
+
+    fn with_config_files(
+        test: &str,
+        main: &str,
+        included: &str,
+        f: impl FnOnce(&Path) -> Result<(), String>,
+    ) -> (Result<(), String>, String, String) {
+        let dir = std::env::temp_dir().join(format!(
+            "niri-persist-test-{test}-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let main_path = dir.join("config.kdl");
+        let included_path = dir.join("included.kdl");
+        fs::write(&main_path, main).unwrap();
+        fs::write(&included_path, included).unwrap();
+
+        let res = f(&main_path);
+        let main = fs::read_to_string(&main_path).unwrap();
+        let included = fs::read_to_string(&included_path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        (res, main, included)
+    }
+
+    #[test]
+    fn persist_edits_included_file() {
+        let main = "include \"included.kdl\"\n";
+        let included = "layout {\n    gaps 16\n}\n";
+        let (res, main_after, included_after) =
+            with_config_files("included", main, included, |path| {
+                persist_value(path, None, "layout.gaps", "8")
+            });
+        res.unwrap();
+        assert_eq!(main_after, main);
+        assert_eq!(included_after, "layout {\n    gaps 8\n}\n");
+    }
+
+    #[test]
+    fn persist_adds_to_included_section() {
+        let main = "include \"included.kdl\"\n";
+        let included = "layout {\n    gaps 16\n}\n";
+        let (res, main_after, included_after) =
+            with_config_files("section", main, included, |path| {
+                persist_value(path, None, "layout.border.width", "2")
+            });
+        res.unwrap();
+        assert_eq!(main_after, main);
+        assert_eq!(
+            included_after,
+            "layout {\n    gaps 16\n    border {\n        width 2\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn persist_adds_missing_value_to_main_file() {
+        let main = "include \"included.kdl\"\n";
+        let included = "layout {\n    gaps 16\n}\n";
+        let (res, main_after, included_after) =
+            with_config_files("main", main, included, |path| {
+                persist_value(path, None, "animations.slowdown", "2")
+            });
+        res.unwrap();
+        assert_eq!(
+            main_after,
+            "include \"included.kdl\"\nanimations {\n    slowdown 2\n}\n"
+        );
+        assert_eq!(included_after, included);
+    }
+
+    #[test]
+    fn persist_refuses_values_in_profiles() {
+        let main = "profile \"docked\" {\n    layout {\n        gaps 16\n    }\n}\n";
+        let (res, main_after, _) = with_config_files("profile", main, "", |path| {
+            persist_value(path, Some("docked"), "layout.gaps", "8")
+        });
+        assert!(res.is_err());
+        assert_eq!(main_after, main);
+    }
+}
@@ -86,6 +86,12 @@ pub enum Request {
     PickColor,
     /// Perform an action.
     Action(Action),
+    /// Perform several actions in order.
+    ///
+    /// The actions run back-to-back without niri redrawing or handling other requests in between,
+    /// so that intermediate states never show up on screen. If an action fails to apply, for
+    /// example because its target window doesn't exist, the remaining actions still run.
+    Actions(Vec<Action>),
     /// Change output configuration temporarily.
     ///
     /// The configuration is changed temporarily and not saved into the config file. If the output
@@ -116,6 +122,74 @@ pub enum Request {
     ReturnError,
     /// Request information about the overview.
     OverviewState,
+    /// Request the full layout tree: workspaces, their columns, and the windows in them.
+    Layout,
+    /// Validate the config file of the running niri instance.
+    ///
+    /// This loads the config file from disk, with the same config path and profile as the running
+    /// instance, and reports any problems with it. It doesn't apply the config.
+    ValidateConfig,
+    /// Request the current value of a config option.
+    GetConfigValue {
+        /// Dot-separated path to the option, for example `layout.gaps`.
+        name: String,
+    },
+    /// Change a config option.
+    ///
+    /// The change applies right away. Unless persisted, it's temporary: it's forgotten when niri
+    /// restarts, or when the same option changes in the config file.
+    SetConfigValue {
+        /// Dot-separated path to the option, for example `layout.gaps`.
+        name: String,
+        /// New value, for example `16`, `true` or `null`.
+        value: String,
+        /// Also write the new value into the config file.
+        persist: bool,
+    },
+    /// Request information about the commands from `spawn-at-startup`.
+    Autostart,
+    /// Restart a command from `spawn-at-startup`.
+    ///
+    /// If the command is running, it's terminated first.
+    RestartAutostart {
+        /// Id of the autostart entry.
+        id: u64,
+    },
+    /// Create a virtual output.
+    ///
+    /// Virtual outputs aren't shown on any monitor, but otherwise work like connected outputs. For
+    /// example, they can be screencast to stream them elsewhere. They can be configured by their
+    /// name in the config, like other outputs.
+    ///
+    /// The compositor replies with the name of the new output.
+    CreateVirtualOutput {
+        /// Width in physical pixels.
+        width: u16,
+        /// Height in physical pixels.
+        height: u16,
+        /// Refresh rate in millihertz, 60 Hz by default.
+        refresh_rate: Option<u32>,
+        /// Scale, unless the output config sets one.
+        scale: Option<f64>,
+    },
+    /// Destroy a virtual output.
+    DestroyVirtualOutput {
+        /// Name of the virtual output.
+        output: String,
+    },
+    /// Request information about the GPUs and the outputs connected to them.
+    Gpus,
+    /// Request the recent log messages.
+    Logs,
+    /// Request the current log filter.
+    LogFilter,
+    /// Change the log filter.
+    SetLogFilter {
+        /// New filter in the `RUST_LOG` syntax, for example `niri=trace,smithay=debug`.
+        ///
+        /// `None` resets the filter to the one niri started with.
+        filter: Option<String>,
+    },
 }
 
 /// Reply from niri to client.
@@ -160,6 +234,25 @@ pub enum Response {
     OutputConfigChanged(OutputConfigChanged),
     /// Information about the overview.
     OverviewState(Overview),
+    /// The layout tree, one entry per workspace.
+    Layout(Vec<WorkspaceLayout>),
+    /// Problems found in the config file.
+    ///
+    /// The config is valid if there are no problems with the [`Error`](DiagnosticSeverity::Error)
+    /// severity.
+    ConfigDiagnostics(Vec<ConfigDiagnostic>),
+    /// Current value of a config option.
+    ConfigValue(String),
+    /// Information about the commands from `spawn-at-startup`.
+    Autostart(Vec<AutostartEntry>),
+    /// Name of the created virtual output.
+    VirtualOutputCreated(String),
+    /// Information about the GPUs.
+    Gpus(Vec<Gpu>),
+    /// Recent log messages, oldest first.
+    Logs(Vec<LogEntry>),
+    /// Current log filter in the `RUST_LOG` syntax.
+    LogFilter(String),
 }
 
 /// Overview information.
@@ -170,6 +263,176 @@ pub struct Overview {
     pub is_open: bool,
 }
 
+/// Layout of a workspace.
+///
+/// All positions are in logical pixels relative to the top-left corner of the workspace view. For
+/// the active workspace of an output, outside of the overview, this is the top-left corner of the
+/// output. Positions include ongoing animations, so they match what is on screen.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct WorkspaceLayout {
+    /// Id of the workspace.
+    pub id: u64,
+    /// Name of the output that the workspace is on.
+    ///
+    /// Can be `None` if no outputs are currently connected.
+    pub output: Option<String>,
+    /// Whether the workspace is currently active on its output.
+    pub is_active: bool,
+    /// Whether the workspace is currently focused.
+    pub is_focused: bool,
+    /// Size of the workspace view as (width, height).
+    pub view_size: (f64, f64),
+    /// Columns of the scrolling layout, from left to right.
+    pub columns: Vec<ColumnLayout>,
+    /// Index of the active column in `columns`, if there are any columns.
+    pub active_column_idx: Option<usize>,
+    /// Floating windows, from top to bottom.
+    pub floating: Vec<TileLayout>,
+    /// Whether the floating layout has focus rather than the scrolling layout.
+    pub floating_is_active: bool,
+}
+
+/// Layout of a column in the scrolling layout.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct ColumnLayout {
+    /// X position of the left edge of the column.
+    pub x: f64,
+    /// Width of the column.
+    pub width: f64,
+    /// Whether the column is full width.
+    pub is_full_width: bool,
+    /// How the windows in the column are displayed.
+    pub display: ColumnDisplay,
+    /// Windows in the column, from top to bottom.
+    pub tiles: Vec<TileLayout>,
+    /// Index of the active window in `tiles`.
+    pub active_tile_idx: usize,
+}
+
+/// Layout of a window tile.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct TileLayout {
+    /// Id of the window.
+    pub window_id: u64,
+    /// Position of the tile as (x, y).
+    ///
+    /// The tile includes the window, its border and its top bar, if any.
+    pub pos: (f64, f64),
+    /// Size of the tile as (width, height).
+    pub tile_size: (f64, f64),
+    /// Size of the window as (width, height).
+    pub window_size: (f64, f64),
+    /// Whether the window is fullscreen.
+    pub is_fullscreen: bool,
+}
+
+/// Problem found in the config.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct ConfigDiagnostic {
+    /// Severity of the problem.
+    pub severity: DiagnosticSeverity,
+    /// Description of the problem.
+    pub message: String,
+    /// Text attached to the source location, if any.
+    pub label: Option<String>,
+    /// Suggestion on how to fix the problem, if any.
+    pub help: Option<String>,
+    /// Path to the config file where the problem is, if known.
+    ///
+    /// This can be the main config file or one of the included files.
+    pub file: Option<String>,
+    /// Line of the problem in the file, starting from 1, if known.
+    pub line: Option<usize>,
+    /// Column of the problem in the line, starting from 1, if known.
+    ///
+    /// The column counts characters rather than bytes.
+    pub column: Option<usize>,
+}
+
+/// Severity of a config problem.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum DiagnosticSeverity {
+    /// The config fails to load.
+    Error,
+    /// The config loads, but something in it is likely a mistake.
+    Warning,
+}
+
+/// Command from `spawn-at-startup` managed by niri.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct AutostartEntry {
+    /// Id of the entry.
+    ///
+    /// This is the index of the entry among the `spawn-at-startup` commands, starting from 0.
+    pub id: u64,
+    /// Name of the entry, if set in the config.
+    pub name: Option<String>,
+    /// The command with its arguments.
+    pub command: Vec<String>,
+    /// Process id of the command, if it's currently running.
+    pub pid: Option<u32>,
+    /// Exit code from the last time the command exited.
+    ///
+    /// `None` if the command didn't exit yet, or if it was killed by a signal.
+    pub exit_code: Option<i32>,
+    /// How many times the command was restarted.
+    pub restarts: u32,
+}
+
+/// GPU used by niri.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct Gpu {
+    /// Path to the DRM device, for example `/dev/dri/card0`.
+    pub path: String,
+    /// Path to the render node of the device, for example `/dev/dri/renderD128`.
+    pub render_node: Option<String>,
+    /// Whether niri renders with this GPU.
+    ///
+    /// There's one primary GPU. Outputs connected to other GPUs show frames rendered on the
+    /// primary GPU and copied over, and client buffers from other GPUs are copied to the primary
+    /// GPU to render them.
+    pub is_primary: bool,
+    /// Names of the connected outputs, like `eDP-1`.
+    pub outputs: Vec<String>,
+}
+
+/// Log message from niri.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct LogEntry {
+    /// Time since niri started in milliseconds.
+    pub time_ms: u64,
+    /// Level of the message.
+    pub level: LogLevel,
+    /// Module that logged the message, for example `niri::backend::tty`.
+    pub target: String,
+    /// The message, followed by its fields as `name=value`.
+    pub message: String,
+}
+
+/// Level of a log message.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum LogLevel {
+    /// Recoverable bug.
+    Error,
+    /// Something bad but possible happened.
+    Warn,
+    /// Important message about normal operation.
+    Info,
+    /// Less important message about normal operation.
+    Debug,
+    /// Spammy message for debugging.
+    Trace,
+}
+
 /// Color picked from the screen.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
@@ -197,6 +460,40 @@ pub enum Action {
     PowerOffMonitors {},
     /// Power on all monitors via DPMS.
     PowerOnMonitors {},
+    /// Toggle the power of a single monitor via DPMS.
+    #[cfg_attr(
+        feature = "clap",
+        clap(about = "Toggle the power of the focused monitor via DPMS")
+    )]
+    ToggleMonitorPower {
+        /// Name of the output to toggle.
+        ///
+        /// If `None`, uses the focused output.
+        #[cfg_attr(feature = "clap", arg())]
+        output: Option<String>,
+    },
+    /// Show a brightness or volume level in the on-screen display.
+    ///
+    /// Meant for scripts and tools that change the brightness or the volume.
+    ShowOsd {
+        /// What the level is of.
+        #[cfg_attr(feature = "clap", arg())]
+        kind: OsdKind,
+
+        /// Level in percent.
+        #[cfg_attr(feature = "clap", arg(value_parser = clap::value_parser!(u8).range(0..=100)))]
+        percent: u8,
+
+        /// Show the level as muted.
+        #[cfg_attr(feature = "clap", arg(long))]
+        muted: bool,
+    },
+    /// Change the backlight brightness and show it in the on-screen display.
+    AdjustBrightness {
+        /// Step in percent of the maximum brightness, negative to dim.
+        #[cfg_attr(feature = "clap", arg(allow_hyphen_values = true))]
+        step: i8,
+    },
     /// Spawn a command.
     Spawn {
         /// Command to spawn.
@@ -243,6 +540,10 @@ pub enum Action {
     },
     /// Enable or disable the keyboard shortcuts inhibitor (if any) for the focused surface.
     ToggleKeyboardShortcutsInhibit {},
+    /// Release the pointer lock or confinement (if any) of the surface under the pointer.
+    ///
+    /// The constraint is re-enabled the next time you click on the surface.
+    ReleasePointerConstraint {},
     /// Close a window.
     #[cfg_attr(feature = "clap", clap(about = "Close the focused window"))]
     CloseWindow {
@@ -292,6 +593,8 @@ pub enum Action {
     },
     /// Focus the previously focused window.
     FocusWindowPrevious {},
+    /// Focus the window that most recently became urgent.
+    FocusUrgent {},
     /// Focus the column to the left.
     FocusColumnLeft {},
     /// Focus the column to the right.
@@ -312,6 +615,10 @@ pub enum Action {
         #[cfg_attr(feature = "clap", arg())]
         index: usize,
     },
+    /// Focus the previously focused column.
+    ///
+    /// Repeating it bounces between the two most recently focused columns.
+    FocusColumnPrevious {},
     /// Focus the window or the monitor above.
     FocusWindowOrMonitorUp {},
     /// Focus the window or the monitor below.
@@ -356,8 +663,18 @@ pub enum Action {
     MoveColumnLeftOrToMonitorLeft {},
     /// Move the focused column to the right or to the monitor to the right.
     MoveColumnRightOrToMonitorRight {},
-    /// Move the focused column to a specific index on its workspace.
+    /// Move a column to a specific index on its workspace.
+    #[cfg_attr(
+        feature = "clap",
+        clap(about = "Move the focused column to a specific index on its workspace")
+    )]
     MoveColumnToIndex {
+        /// Id of a window in the column to move.
+        ///
+        /// If `None`, uses the focused column.
+        #[cfg_attr(feature = "clap", arg(long))]
+        id: Option<u64>,
+
         /// New index for the column.
         ///
         /// The index starts from 1 for the first column.
@@ -675,6 +992,14 @@ pub enum Action {
     },
     /// Toggle the maximized state of the focused column.
     MaximizeColumn {},
+    /// Toggle fullscreen on the focused column.
+    ///
+    /// The windows of the column span the whole output, keeping their relative heights.
+    FullscreenColumn {},
+    /// Toggle the reading mode on the focused workspace.
+    ///
+    /// In reading mode, a single column is centered and limited to the reading mode max width.
+    ToggleReadingMode {},
     /// Change the width of the focused column.
     SetColumnWidth {
         /// How to change the width.
@@ -685,12 +1010,16 @@ pub enum Action {
     ExpandColumnToAvailableWidth {},
     /// Switch between keyboard layouts.
     SwitchLayout {
-        /// Layout to switch to.
+        /// Layout to switch to: `next`, `prev`, a layout index, or a layout name.
         #[cfg_attr(feature = "clap", arg())]
         layout: LayoutSwitchTarget,
     },
     /// Show the hotkey overlay.
     ShowHotkeyOverlay {},
+    /// Show the cheatsheet with all binds, grouped by category.
+    ShowCheatsheet {},
+    /// Show the window switcher to pick a window from all workspaces by its title or app ID.
+    ShowWindowSwitcher {},
     /// Move the focused workspace to the monitor to the left.
     MoveWorkspaceToMonitorLeft {},
     /// Move the focused workspace to the monitor to the right.
@@ -725,6 +1054,10 @@ pub enum Action {
     DebugToggleOpaqueRegions {},
     /// Toggle visualization of output damage.
     DebugToggleDamage {},
+    /// Add an output to the nested window.
+    DebugAddNestedOutput {},
+    /// Remove the last added output from the nested window.
+    DebugRemoveNestedOutput {},
     /// Move the focused window between the floating and the tiling layout.
     ToggleWindowFloating {
         /// Id of the window to move.
@@ -790,6 +1123,50 @@ pub enum Action {
         #[cfg_attr(feature = "clap", arg(long))]
         id: Option<u64>,
     },
+    /// Toggle whether a floating window stays above all other windows.
+    #[cfg_attr(
+        feature = "clap",
+        clap(about = "Toggle whether the focused floating window stays on top")
+    )]
+    ToggleAlwaysOnTop {
+        /// Id of the window.
+        ///
+        /// If `None`, uses the focused window.
+        #[cfg_attr(feature = "clap", arg(long))]
+        id: Option<u64>,
+    },
+    /// Change the opacity of a window.
+    ///
+    /// The opacity stays until the window closes, and takes priority over the opacity window rule.
+    #[cfg_attr(
+        feature = "clap",
+        clap(about = "Change the opacity of the focused window")
+    )]
+    SetWindowOpacity {
+        /// Id of the window whose opacity to set.
+        ///
+        /// If `None`, uses the focused window.
+        #[cfg_attr(feature = "clap", arg(long))]
+        id: Option<u64>,
+
+        /// How to change the opacity.
+        #[cfg_attr(feature = "clap", arg(allow_hyphen_values = true))]
+        change: OpacityChange,
+    },
+    /// Move the picture-in-picture window to the next corner of the screen, going clockwise.
+    ///
+    /// The picture-in-picture window is the topmost always-on-top floating window.
+    #[cfg_attr(
+        feature = "clap",
+        clap(about = "Move the picture-in-picture window to the next corner of the screen")
+    )]
+    MovePipToNextCorner {},
+    /// Switch between preset widths for the picture-in-picture window.
+    #[cfg_attr(
+        feature = "clap",
+        clap(about = "Switch between preset widths for the picture-in-picture window")
+    )]
+    SwitchPresetPipWidth {},
     /// Set the dynamic cast target to a window.
     #[cfg_attr(
         feature = "clap",
@@ -840,6 +1217,18 @@ pub enum Action {
         #[cfg_attr(feature = "clap", arg(long))]
         id: u64,
     },
+    /// Enter a binds mode, replacing the regular key bindings with the mode's ones.
+    EnterMode {
+        /// Name of the binds mode to enter.
+        #[cfg_attr(feature = "clap", arg())]
+        name: String,
+    },
+    /// Exit the current binds mode, returning to the regular key bindings.
+    ExitMode {},
+    /// Toggle do-not-disturb for the built-in notifications.
+    ToggleDoNotDisturb {},
+    /// Close all popups of the built-in notifications.
+    DismissNotifications {},
 }
 
 /// Change in window or column size.
@@ -866,6 +1255,16 @@ pub enum PositionChange {
     AdjustFixed(f64),
 }
 
+/// Change in window opacity.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum OpacityChange {
+    /// Set the opacity, from 0 to 1.
+    SetOpacity(f64),
+    /// Add or subtract to the current opacity.
+    AdjustOpacity(f64),
+}
+
 /// Workspace reference (id, index or name) to operate on.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
@@ -879,7 +1278,7 @@ pub enum WorkspaceReferenceArg {
 }
 
 /// Layout to switch to.
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub enum LayoutSwitchTarget {
     /// The next configured layout.
@@ -888,6 +1287,11 @@ pub enum LayoutSwitchTarget {
     Prev,
     /// The specific layout by index.
     Index(u8),
+    /// The specific layout by name.
+    ///
+    /// Matches either the full layout name, like `English (US)`, or the layout as written in the
+    /// xkb config, like `us`.
+    Name(String),
 }
 
 /// How windows display in a column.
@@ -900,6 +1304,17 @@ pub enum ColumnDisplay {
     Tabbed,
 }
 
+/// What an on-screen display level is of.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum OsdKind {
+    /// Screen brightness.
+    Brightness,
+    /// Audio volume.
+    Volume,
+}
+
 /// Output actions that niri can perform.
 // Variants in this enum should match the spelling of the ones in niri-config. Most thigs from
 // niri-config should be present here.
@@ -945,6 +1360,12 @@ pub enum OutputAction {
         #[cfg_attr(feature = "clap", command(flatten))]
         vrr: VrrToSet,
     },
+    /// Mirror another output onto this output.
+    Mirror {
+        /// Name of the output to mirror, or "off" to stop mirroring.
+        #[cfg_attr(feature = "clap", arg())]
+        mirror: MirrorToSet,
+    },
 }
 
 /// Output mode to set.
@@ -1005,6 +1426,16 @@ pub struct ConfiguredPosition {
     pub y: i32,
 }
 
+/// Output mirroring to set.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub enum MirrorToSet {
+    /// Stop mirroring and show the output's own workspaces.
+    Off,
+    /// Mirror the output with this name.
+    Output(String),
+}
+
 /// Output VRR to set.
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 #[cfg_attr(feature = "clap", derive(clap::Args))]
@@ -1150,6 +1581,37 @@ pub struct Window {
     pub is_floating: bool,
     /// Whether this window requests your attention.
     pub is_urgent: bool,
+    /// Whether the client of this window doesn't respond to pings.
+    ///
+    /// Unresponsive clients are usually hung and may need to be killed.
+    pub is_unresponsive: bool,
+    /// Whether this window is out of view, either on a workspace off screen, scrolled away, or
+    /// behind another tab.
+    ///
+    /// Such windows are told that they are suspended, so they can stop rendering.
+    pub is_suspended: bool,
+    /// Opacity of this window, from 0 to 1.
+    ///
+    /// Comes from the opacity window rule, or from the `SetWindowOpacity` action.
+    pub opacity: f64,
+    /// Position and state of this window in the layout.
+    pub layout: WindowLayout,
+}
+
+/// Position and state of a toplevel window in the layout.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct WindowLayout {
+    /// Location of the window in the scrolling layout.
+    ///
+    /// The first index is 1-based index of the column, and the second index is 1-based index of
+    /// the tile within that column.
+    ///
+    /// `None` if the window is floating, or isn't in the layout (for example, while it's being
+    /// moved interactively).
+    pub pos_in_scrolling_layout: Option<(usize, usize)>,
+    /// Whether this window is fullscreen.
+    pub is_fullscreen: bool,
 }
 
 /// Output configuration change result.
@@ -1326,6 +1788,24 @@ pub enum Event {
         /// The new urgency state of the window.
         urgent: bool,
     },
+    /// Window responsiveness changed.
+    ///
+    /// A window becomes unresponsive when its client stops answering pings, and responsive again
+    /// once the client answers.
+    WindowResponsivenessChanged {
+        /// Id of the window.
+        id: u64,
+        /// Whether the window is now unresponsive.
+        unresponsive: bool,
+    },
+    /// The layout of one or more windows changed.
+    ///
+    /// Sent when windows move between columns or within a column, when columns are added, removed
+    /// or reordered, and when windows enter or leave fullscreen.
+    WindowLayoutsChanged {
+        /// Pairs consisting of a window id and the new layout of the window.
+        changes: Vec<(u64, WindowLayout)>,
+    },
     /// The configured keyboard layouts have changed.
     KeyboardLayoutsChanged {
         /// The new keyboard layout configuration.
@@ -1341,6 +1821,18 @@ pub enum Event {
         /// The new state of the overview.
         is_open: bool,
     },
+    /// The output configuration has changed.
+    ///
+    /// Sent when outputs are connected or disconnected, and when their mode, position, scale,
+    /// transform or other properties change, including changes made through
+    /// wlr-output-management.
+    OutputsChanged {
+        /// The new output configuration.
+        ///
+        /// This configuration completely replaces the previous configuration. I.e. if any outputs
+        /// are missing from here, then they were disconnected.
+        outputs: HashMap<String, Output>,
+    },
 }
 
 impl FromStr for WorkspaceReferenceArg {
@@ -1384,7 +1876,8 @@ impl FromStr for SizeChange {
                 }
             }
             None => {
-                let value = s;
+                // Logical pixels are the default unit, but they can be spelled out too.
+                let value = s.strip_suffix("px").unwrap_or(s);
                 match value.bytes().next() {
                     Some(b'-' | b'+') => {
                         let value = value.parse().map_err(|_| "error parsing value")?;
@@ -1420,6 +1913,25 @@ impl FromStr for PositionChange {
     }
 }
 
+impl FromStr for OpacityChange {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s;
+        match value.bytes().next() {
+            Some(b'-' | b'+') => {
+                let value = value.parse().map_err(|_| "error parsing value")?;
+                Ok(Self::AdjustOpacity(value))
+            }
+            Some(_) => {
+                let value = value.parse().map_err(|_| "error parsing value")?;
+                Ok(Self::SetOpacity(value))
+            }
+            None => Err("value is missing"),
+        }
+    }
+}
+
 impl FromStr for LayoutSwitchTarget {
     type Err = &'static str;
 
@@ -1427,9 +1939,10 @@ impl FromStr for LayoutSwitchTarget {
         match s {
             "next" => Ok(Self::Next),
             "prev" => Ok(Self::Prev),
+            "" => Err(r#"invalid layout action, can be "next", "prev", a layout index or name"#),
             other => match other.parse() {
                 Ok(layout) => Ok(Self::Index(layout)),
-                _ => Err(r#"invalid layout action, can be "next", "prev" or a layout index"#),
+                _ => Ok(Self::Name(other.to_owned())),
             },
         }
     }
@@ -1509,6 +2022,22 @@ impl FromStr for ConfiguredMode {
     }
 }
 
+impl FromStr for MirrorToSet {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("off") {
+            return Ok(Self::Off);
+        }
+
+        if s.is_empty() {
+            return Err("output name cannot be empty");
+        }
+
+        Ok(Self::Output(s.to_owned()))
+    }
+}
+
 impl FromStr for ScaleToSet {
     type Err = &'static str;
 
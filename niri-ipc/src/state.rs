@@ -9,7 +9,7 @@
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 
-use crate::{Event, KeyboardLayouts, Window, Workspace};
+use crate::{Event, KeyboardLayouts, Output, Window, Workspace};
 
 /// Part of the state communicated via the event stream.
 pub trait EventStreamStatePart {
@@ -43,6 +43,9 @@ pub struct EventStreamState {
 
     /// State of the overview.
     pub overview: OverviewState,
+
+    /// State of the outputs.
+    pub outputs: OutputsState,
 }
 
 /// The workspaces state communicated over the event stream.
@@ -73,6 +76,13 @@ pub struct OverviewState {
     pub is_open: bool,
 }
 
+/// The outputs state communicated over the event stream.
+#[derive(Debug, Default)]
+pub struct OutputsState {
+    /// Map from an output name to the output.
+    pub outputs: HashMap<String, Output>,
+}
+
 impl EventStreamStatePart for EventStreamState {
     fn replicate(&self) -> Vec<Event> {
         let mut events = Vec::new();
@@ -80,6 +90,7 @@ impl EventStreamStatePart for EventStreamState {
         events.extend(self.windows.replicate());
         events.extend(self.keyboard_layouts.replicate());
         events.extend(self.overview.replicate());
+        events.extend(self.outputs.replicate());
         events
     }
 
@@ -88,6 +99,7 @@ impl EventStreamStatePart for EventStreamState {
         let event = self.windows.apply(event)?;
         let event = self.keyboard_layouts.apply(event)?;
         let event = self.overview.apply(event)?;
+        let event = self.outputs.apply(event)?;
         Some(event)
     }
 }
@@ -189,6 +201,18 @@ impl EventStreamStatePart for WindowsState {
                     }
                 }
             }
+            Event::WindowResponsivenessChanged { id, unresponsive } => {
+                if let Some(win) = self.windows.get_mut(&id) {
+                    win.is_unresponsive = unresponsive;
+                }
+            }
+            Event::WindowLayoutsChanged { changes } => {
+                for (id, layout) in changes {
+                    if let Some(win) = self.windows.get_mut(&id) {
+                        win.layout = layout;
+                    }
+                }
+            }
             event => return Some(event),
         }
         None
@@ -237,3 +261,20 @@ impl EventStreamStatePart for OverviewState {
         None
     }
 }
+
+impl EventStreamStatePart for OutputsState {
+    fn replicate(&self) -> Vec<Event> {
+        let outputs = self.outputs.clone();
+        vec![Event::OutputsChanged { outputs }]
+    }
+
+    fn apply(&mut self, event: Event) -> Option<Event> {
+        match event {
+            Event::OutputsChanged { outputs } => {
+                self.outputs = outputs;
+            }
+            event => return Some(event),
+        }
+        None
+    }
+}
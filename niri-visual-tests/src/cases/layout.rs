@@ -57,6 +57,7 @@ impl Layout {
             },
             border: niri_config::Border {
                 off: false,
+                hide_when_single_window: false,
                 width: FloatOrInt(4.),
                 active_color: Color::from_rgba8_unpremul(255, 163, 72, 255),
                 inactive_color: Color::from_rgba8_unpremul(50, 50, 50, 255),
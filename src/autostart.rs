@@ -0,0 +1,470 @@
+//! Commands from `spawn-at-startup`, kept track of to restart them.
+
+use std::io::{self, ErrorKind};
+use std::mem;
+use std::process::{Child, ExitStatus};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use niri_config::{RestartPolicy, SpawnAtStartup};
+use smithay::reexports::calloop::channel::{self, Event, Sender};
+use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
+use smithay::reexports::calloop::{LoopHandle, RegistrationToken};
+
+use crate::niri::State;
+use crate::utils::spawning::spawn_child;
+
+/// Delay before restarting a command that exited.
+const RESTART_DELAY: Duration = Duration::from_secs(1);
+/// Exits sooner than this after the start count as quick exits.
+const QUICK_EXIT_DURATION: Duration = Duration::from_secs(10);
+/// Number of quick exits in a row after which a command is no longer restarted.
+const MAX_QUICK_EXITS: u32 = 5;
+
+pub struct Autostart {
+    entries: Vec<Entry>,
+    sender: Sender<Message>,
+}
+
+struct Entry {
+    config: SpawnAtStartup,
+    /// Incremented on every run to tell apart messages from earlier runs.
+    generation: u64,
+    /// Whether the command is currently being spawned or running.
+    running: bool,
+    child: Option<ChildHandle>,
+    started_at: Instant,
+    exit_code: Option<i32>,
+    restarts: u32,
+    quick_exits: u32,
+    /// Whether to run the command again right after it exits.
+    restart_requested: bool,
+    restart_timer: Option<RegistrationToken>,
+}
+
+/// Running command that can be signaled without the risk of hitting a reused pid.
+#[derive(Clone)]
+struct ChildHandle {
+    pid: u32,
+    /// Set by the waiter once the process exits, before the process is reaped.
+    exited: Arc<Mutex<bool>>,
+}
+
+enum Message {
+    Started {
+        idx: usize,
+        generation: u64,
+        child: ChildHandle,
+    },
+    /// The command exited, or failed to spawn if there's no status.
+    Exited {
+        idx: usize,
+        generation: u64,
+        status: Option<ExitStatus>,
+    },
+}
+
+impl Autostart {
+    pub fn new(event_loop: &LoopHandle<'static, State>) -> Self {
+        let (sender, rx) = channel::channel();
+        event_loop
+            .insert_source(rx, |event, _, state| {
+                if let Event::Msg(msg) = event {
+                    state.niri.autostart.on_message(&state.niri.event_loop, msg);
+                }
+            })
+            .unwrap();
+
+        Self {
+            entries: Vec::new(),
+            sender,
+        }
+    }
+
+    /// Spawns the commands from `spawn-at-startup`.
+    pub fn start(&mut self, configs: Vec<SpawnAtStartup>) {
+        self.entries = configs.into_iter().map(Entry::new).collect();
+
+        for idx in 0..self.entries.len() {
+            self.run(idx);
+        }
+    }
+
+    /// Restarts the command, terminating it first if it's running.
+    pub fn restart(
+        &mut self,
+        event_loop: &LoopHandle<'static, State>,
+        id: u64,
+    ) -> Result<(), String> {
+        let idx = usize::try_from(id)
+            .ok()
+            .filter(|idx| *idx < self.entries.len());
+        let Some(idx) = idx else {
+            return Err(format!("no autostart entry with id {id}"));
+        };
+
+        let entry = &mut self.entries[idx];
+        if let Some(token) = entry.restart_timer.take() {
+            event_loop.remove(token);
+        }
+
+        entry.quick_exits = 0;
+
+        if entry.running {
+            entry.restart_requested = true;
+            if let Some(child) = &entry.child {
+                child.terminate();
+            }
+            // Otherwise, it will be terminated once it starts.
+        } else {
+            entry.restarts += 1;
+            self.run(idx);
+        }
+
+        Ok(())
+    }
+
+    pub fn ipc_entries(&self) -> Vec<niri_ipc::AutostartEntry> {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(idx, entry)| niri_ipc::AutostartEntry {
+                id: idx as u64,
+                name: entry.config.name.clone(),
+                command: entry.config.command.clone(),
+                pid: entry.child.as_ref().map(|child| child.pid),
+                exit_code: entry.exit_code,
+                restarts: entry.restarts,
+            })
+            .collect()
+    }
+
+    fn run(&mut self, idx: usize) {
+        let entry = &mut self.entries[idx];
+        entry.generation += 1;
+        entry.running = true;
+        entry.started_at = Instant::now();
+
+        let generation = entry.generation;
+        let config = entry.config.clone();
+        let sender = self.sender.clone();
+
+        // Spawning takes some milliseconds, and waiting blocks, so do it in a thread.
+        let res = thread::Builder::new()
+            .name("Autostart Waiter".to_owned())
+            .spawn(move || {
+                let working_dir = config.working_dir.as_deref();
+                let Some(mut child) =
+                    spawn_child(&config.command, &config.environment, working_dir)
+                else {
+                    let _ = sender.send(Message::Exited {
+                        idx,
+                        generation,
+                        status: None,
+                    });
+                    return;
+                };
+
+                let handle = ChildHandle {
+                    pid: child.id(),
+                    exited: Arc::new(Mutex::new(false)),
+                };
+                let _ = sender.send(Message::Started {
+                    idx,
+                    generation,
+                    child: handle.clone(),
+                });
+
+                let status = handle.wait(&mut child);
+                let _ = sender.send(Message::Exited {
+                    idx,
+                    generation,
+                    status,
+                });
+            });
+
+        if let Err(err) = res {
+            warn!("error spawning a thread to spawn the command: {err:?}");
+            entry.running = false;
+        }
+    }
+
+    fn on_message(&mut self, event_loop: &LoopHandle<'static, State>, msg: Message) {
+        match msg {
+            Message::Started {
+                idx,
+                generation,
+                child,
+            } => {
+                let entry = &mut self.entries[idx];
+                if entry.generation != generation {
+                    return;
+                }
+
+                if entry.restart_requested {
+                    child.terminate();
+                }
+                entry.child = Some(child);
+            }
+            Message::Exited {
+                idx,
+                generation,
+                status,
+            } => {
+                let entry = &mut self.entries[idx];
+                if entry.generation != generation {
+                    return;
+                }
+
+                entry.running = false;
+                entry.child = None;
+
+                if entry.restart_requested {
+                    entry.restart_requested = false;
+                    entry.restarts += 1;
+                    self.run(idx);
+                    return;
+                }
+
+                // The command failed to spawn, restarting won't help.
+                let Some(status) = status else {
+                    return;
+                };
+
+                let ran_for = entry.started_at.elapsed();
+                if !entry.on_exit(status, ran_for) {
+                    return;
+                }
+
+                let timer = Timer::from_duration(RESTART_DELAY);
+                let token = event_loop
+                    .insert_source(timer, move |_, _, state| {
+                        let autostart = &mut state.niri.autostart;
+                        let entry = &mut autostart.entries[idx];
+                        entry.restart_timer = None;
+                        entry.restarts += 1;
+                        autostart.run(idx);
+                        TimeoutAction::Drop
+                    })
+                    .unwrap();
+                entry.restart_timer = Some(token);
+            }
+        }
+    }
+}
+
+impl Entry {
+    fn new(config: SpawnAtStartup) -> Self {
+        Self {
+            config,
+            generation: 0,
+            running: false,
+            child: None,
+            started_at: Instant::now(),
+            exit_code: None,
+            restarts: 0,
+            quick_exits: 0,
+            restart_requested: false,
+            restart_timer: None,
+        }
+    }
+
+    /// Records that the command exited on its own and returns whether to restart it.
+    fn on_exit(&mut self, status: ExitStatus, ran_for: Duration) -> bool {
+        self.exit_code = status.code();
+
+        let restart = match self.config.restart {
+            RestartPolicy::Never => false,
+            RestartPolicy::OnFailure => !status.success(),
+            RestartPolicy::Always => true,
+        };
+        if !restart {
+            return false;
+        }
+
+        if ran_for < QUICK_EXIT_DURATION {
+            self.quick_exits += 1;
+        } else {
+            self.quick_exits = 0;
+        }
+
+        if self.quick_exits >= MAX_QUICK_EXITS {
+            warn!(
+                "{:?} exited {MAX_QUICK_EXITS} times in a row soon after starting, \
+                 not restarting it anymore",
+                self.config.command
+            );
+            return false;
+        }
+
+        true
+    }
+}
+
+impl ChildHandle {
+    /// Waits for the child to exit and reaps it.
+    fn wait(&self, child: &mut Child) -> Option<ExitStatus> {
+        // Wait without reaping first: until the child is reaped, its pid can't be reused, so
+        // marking it as exited in between makes terminate() safe.
+        wait_for_exit(self.pid);
+        *self.exited.lock().unwrap() = true;
+
+        match child.wait() {
+            Ok(status) => Some(status),
+            Err(err) => {
+                warn!("error waiting for child: {err:?}");
+                None
+            }
+        }
+    }
+
+    /// Sends SIGTERM to the child, unless it already exited.
+    ///
+    /// Returns whether the signal was sent.
+    fn terminate(&self) -> bool {
+        let Ok(pid) = i32::try_from(self.pid) else {
+            return false;
+        };
+
+        // Hold the lock while signaling, so that the waiter can't reap the child meanwhile.
+        let exited = self.exited.lock().unwrap();
+        if *exited {
+            return false;
+        }
+
+        if unsafe { libc::kill(pid, libc::SIGTERM) } != 0 {
+            let err = io::Error::last_os_error();
+            warn!("error terminating process {pid}: {err:?}");
+            return false;
+        }
+
+        true
+    }
+}
+
+/// Waits for the process to exit, leaving it unreaped.
+fn wait_for_exit(pid: u32) {
+    let mut info: libc::siginfo_t = unsafe { mem::zeroed() };
+    loop {
+        let res = unsafe {
+            libc::waitid(
+                libc::P_PID,
+                pid as libc::id_t,
+                &mut info,
+                libc::WEXITED | libc::WNOWAIT,
+            )
+        };
+        if res == 0 {
+            return;
+        }
+
+        let err = io::Error::last_os_error();
+        if err.kind() != ErrorKind::Interrupted {
+            warn!("error waiting for process {pid}: {err:?}");
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::process::ExitStatusExt as _;
+    use std::process::Command;
+
+    use super::*;
+
+    const QUICK: Duration = Duration::from_secs(1);
+    const SLOW: Duration = Duration::from_secs(60);
+
+    fn entry(restart: RestartPolicy) -> Entry {
+        Entry::new(SpawnAtStartup {
+            command: vec![String::from("true")],
+            name: None,
+            working_dir: None,
+            restart,
+            environment: Default::default(),
+        })
+    }
+
+    fn exit_code(code: i32) -> ExitStatus {
+        ExitStatus::from_raw(code << 8)
+    }
+
+    fn killed(signal: i32) -> ExitStatus {
+        ExitStatus::from_raw(signal)
+    }
+
+    #[test]
+    fn never_restarts() {
+        let mut entry = entry(RestartPolicy::Never);
+        assert!(!entry.on_exit(exit_code(0), SLOW));
+        assert!(!entry.on_exit(exit_code(1), SLOW));
+        assert!(!entry.on_exit(killed(libc::SIGKILL), SLOW));
+        assert_eq!(entry.exit_code, None);
+    }
+
+    #[test]
+    fn on_failure_restarts_failed_and_killed() {
+        let mut entry = entry(RestartPolicy::OnFailure);
+        assert!(!entry.on_exit(exit_code(0), SLOW));
+        assert_eq!(entry.exit_code, Some(0));
+        assert!(entry.on_exit(exit_code(1), SLOW));
+        assert_eq!(entry.exit_code, Some(1));
+        assert!(entry.on_exit(killed(libc::SIGKILL), SLOW));
+        assert_eq!(entry.exit_code, None);
+    }
+
+    #[test]
+    fn always_restarts() {
+        let mut entry = entry(RestartPolicy::Always);
+        assert!(entry.on_exit(exit_code(0), SLOW));
+        assert!(entry.on_exit(exit_code(1), SLOW));
+    }
+
+    #[test]
+    fn gives_up_after_quick_exits() {
+        let mut entry = entry(RestartPolicy::Always);
+        for _ in 1..MAX_QUICK_EXITS {
+            assert!(entry.on_exit(exit_code(1), QUICK));
+        }
+        assert!(!entry.on_exit(exit_code(1), QUICK));
+    }
+
+    #[test]
+    fn slow_exit_resets_quick_exits() {
+        let mut entry = entry(RestartPolicy::Always);
+        for _ in 1..MAX_QUICK_EXITS {
+            assert!(entry.on_exit(exit_code(1), QUICK));
+        }
+        assert!(entry.on_exit(exit_code(1), SLOW));
+        assert_eq!(entry.quick_exits, 0);
+        assert!(entry.on_exit(exit_code(1), QUICK));
+    }
+
+    #[test]
+    fn terminate_running_child() {
+        let mut child = Command::new("sleep").arg("100").spawn().unwrap();
+        let handle = ChildHandle {
+            pid: child.id(),
+            exited: Arc::new(Mutex::new(false)),
+        };
+
+        assert!(handle.terminate());
+        let status = handle.wait(&mut child).unwrap();
+        assert_eq!(status.signal(), Some(libc::SIGTERM));
+    }
+
+    #[test]
+    fn terminate_exited_child_does_nothing() {
+        let mut child = Command::new("true").spawn().unwrap();
+        let handle = ChildHandle {
+            pid: child.id(),
+            exited: Arc::new(Mutex::new(false)),
+        };
+
+        let status = handle.wait(&mut child).unwrap();
+        assert!(status.success());
+        assert!(!handle.terminate());
+    }
+}
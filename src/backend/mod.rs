@@ -20,6 +20,9 @@ pub use winit::Winit;
 pub mod headless;
 pub use headless::Headless;
 
+pub mod virtual_output;
+use virtual_output::VirtualOutput;
+
 #[allow(clippy::large_enum_variant)]
 pub enum Backend {
     Tty(Tty),
@@ -88,6 +91,10 @@ impl Backend {
         output: &Output,
         target_presentation_time: Duration,
     ) -> RenderResult {
+        if output.user_data().get::<VirtualOutput>().is_some() {
+            return virtual_output::render(niri, output);
+        }
+
         match self {
             Backend::Tty(tty) => tty.render(niri, output, target_presentation_time),
             Backend::Winit(winit) => winit.render(niri, output),
@@ -132,6 +139,22 @@ impl Backend {
         }
     }
 
+    pub fn add_nested_output(&mut self, niri: &mut Niri) {
+        match self {
+            Backend::Tty(_) => (),
+            Backend::Winit(winit) => winit.add_output(niri),
+            Backend::Headless(_) => (),
+        }
+    }
+
+    pub fn remove_nested_output(&mut self, niri: &mut Niri) {
+        match self {
+            Backend::Tty(_) => (),
+            Backend::Winit(winit) => winit.remove_output(niri),
+            Backend::Headless(_) => (),
+        }
+    }
+
     pub fn import_dmabuf(&mut self, dmabuf: &Dmabuf) -> bool {
         match self {
             Backend::Tty(tty) => tty.import_dmabuf(dmabuf),
@@ -176,7 +199,27 @@ impl Backend {
         }
     }
 
+    pub fn ipc_gpus(&self) -> Vec<niri_ipc::Gpu> {
+        match self {
+            Backend::Tty(tty) => tty.ipc_gpus(),
+            Backend::Winit(_) => Vec::new(),
+            Backend::Headless(_) => Vec::new(),
+        }
+    }
+
+    pub fn set_output_active(&mut self, output: &Output, active: bool) {
+        match self {
+            Backend::Tty(tty) => tty.set_output_active(output, active),
+            Backend::Winit(_) => (),
+            Backend::Headless(_) => (),
+        }
+    }
+
     pub fn set_output_on_demand_vrr(&mut self, niri: &mut Niri, output: &Output, enable_vrr: bool) {
+        if output.user_data().get::<VirtualOutput>().is_some() {
+            return;
+        }
+
         match self {
             Backend::Tty(tty) => tty.set_output_on_demand_vrr(niri, output, enable_vrr),
             Backend::Winit(_) => (),
@@ -4,7 +4,7 @@ use std::fmt::Write;
 use std::iter::zip;
 use std::num::NonZeroU64;
 use std::os::fd::AsFd;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
@@ -13,7 +13,7 @@ use std::{io, mem};
 use anyhow::{anyhow, bail, ensure, Context};
 use bytemuck::cast_slice_mut;
 use libc::dev_t;
-use niri_config::{Config, OutputName};
+use niri_config::{Config, LidInternalOutput, OutputName};
 use smithay::backend::allocator::dmabuf::Dmabuf;
 use smithay::backend::allocator::format::FormatSet;
 use smithay::backend::allocator::gbm::{GbmAllocator, GbmBufferFlags, GbmDevice};
@@ -26,7 +26,7 @@ use smithay::backend::drm::{
 use smithay::backend::egl::context::ContextPriority;
 use smithay::backend::egl::{EGLDevice, EGLDisplay};
 use smithay::backend::libinput::{LibinputInputBackend, LibinputSessionInterface};
-use smithay::backend::renderer::gles::GlesRenderer;
+use smithay::backend::renderer::gles::{GlesError, GlesRenderer};
 use smithay::backend::renderer::multigpu::gbm::GbmGlesBackend;
 use smithay::backend::renderer::multigpu::{GpuManager, MultiFrame, MultiRenderer};
 use smithay::backend::renderer::{DebugFlags, ImportDma, ImportEgl, RendererSuper};
@@ -63,7 +63,7 @@ use crate::niri::{Niri, RedrawState, State};
 use crate::render_helpers::debug::draw_damage;
 use crate::render_helpers::renderer::AsGlesRenderer;
 use crate::render_helpers::{resources, shaders, RenderTarget};
-use crate::utils::{get_monotonic_time, is_laptop_panel, logical_output};
+use crate::utils::{get_monotonic_time, is_laptop_panel, is_software_rendering, logical_output};
 
 const SUPPORTED_COLOR_FORMATS: [Fourcc; 4] = [
     Fourcc::Xrgb8888,
@@ -72,6 +72,12 @@ const SUPPORTED_COLOR_FORMATS: [Fourcc; 4] = [
     Fourcc::Abgr8888,
 ];
 
+/// Delay before the first attempt to recreate a device after a GPU reset, doubled on every
+/// following attempt.
+const GPU_RECOVERY_BASE_DELAY: Duration = Duration::from_millis(100);
+/// Attempts to recreate a device after a GPU reset before giving up.
+const GPU_RECOVERY_MAX_ATTEMPTS: u32 = 5;
+
 pub struct Tty {
     config: Rc<RefCell<Config>>,
     session: LibSeatSession,
@@ -93,6 +99,17 @@ pub struct Tty {
     // Whether the debug tinting is enabled.
     debug_tint: bool,
     ipc_outputs: Arc<Mutex<IpcOutputMap>>,
+    // Devices that lost their renderer context after a GPU reset.
+    gpu_recovery: HashMap<DrmNode, GpuRecovery>,
+}
+
+/// Recreating a device after its GPU got reset.
+#[derive(Debug, Default)]
+struct GpuRecovery {
+    /// Number of times the device was recreated without rendering a frame since.
+    attempts: u32,
+    /// Whether recreating the device is scheduled.
+    scheduled: bool,
 }
 
 pub type TtyRenderer<'render> = MultiRenderer<
@@ -122,6 +139,8 @@ type GbmDrmCompositor = DrmCompositor<
 
 pub struct OutputDevice {
     token: RegistrationToken,
+    /// Path to the device, to open it again after a GPU reset.
+    path: PathBuf,
     render_node: DrmNode,
     drm_scanner: DrmScanner,
     surfaces: HashMap<crtc::Handle, Surface>,
@@ -335,6 +354,7 @@ impl Tty {
             update_output_config_on_resume: false,
             debug_tint: false,
             ipc_outputs: Arc::new(Mutex::new(HashMap::new())),
+            gpu_recovery: HashMap::new(),
         })
     }
 
@@ -342,6 +362,13 @@ impl Tty {
         for (device_id, path) in self.udev_dispatcher.clone().as_source_ref().device_list() {
             if let Err(err) = self.device_added(device_id, path, niri) {
                 warn!("error adding device: {err:?}");
+
+                // Without rendering on the primary GPU, nothing shows up.
+                let is_primary =
+                    DrmNode::from_dev_id(device_id).is_ok_and(|node| node == self.primary_node);
+                if is_primary && !is_software_rendering() {
+                    warn!("if the GPU can't render, try running niri with --software-rendering");
+                }
             }
         }
     }
@@ -605,6 +632,7 @@ impl Tty {
 
         let device = OutputDevice {
             token,
+            path: path.to_owned(),
             render_node,
             drm,
             gbm,
@@ -840,9 +868,14 @@ impl Tty {
             trace!("{m:?}");
         }
 
-        let (mode, fallback) =
+        let custom_mode = configured_custom_mode(&config).unwrap_or_else(|err| {
+            warn!("invalid custom mode for {connector_name}, ignoring: {err:?}");
+            None
+        });
+
+        let (mut mode, fallback) =
             pick_mode(&connector, config.mode).ok_or_else(|| anyhow!("no mode"))?;
-        if fallback {
+        if fallback && custom_mode.is_none() {
             let target = config.mode.unwrap();
             warn!(
                 "configured mode {}x{}{} could not be found, falling back to preferred",
@@ -1012,6 +1045,27 @@ impl Tty {
             }
         };
 
+        // The driver validates custom modes when we try to use them, so start with a mode the
+        // connector advertises and switch afterwards, keeping the former if the switch fails.
+        if let Some(custom_mode) = custom_mode {
+            debug!("trying custom mode: {custom_mode:?}");
+            match compositor.use_mode(custom_mode) {
+                Ok(()) => {
+                    mode = custom_mode;
+                    let wl_mode = Mode::from(mode);
+                    output.change_current_state(Some(wl_mode), None, None, None);
+                    output.set_preferred(wl_mode);
+                }
+                Err(err) => {
+                    let (width, height) = mode.size();
+                    warn!(
+                        "custom mode for {connector_name} was rejected by the driver, \
+                         falling back to {width}x{height}: {err:?}"
+                    );
+                }
+            }
+        }
+
         if self.debug_tint {
             compositor.set_debug_flags(DebugFlags::TINT);
         }
@@ -1121,11 +1175,11 @@ impl Tty {
         debug!("disconnecting connector: {:?}", surface.name.connector);
 
         let output = niri
-            .global_space
-            .outputs()
+            .output_state
+            .keys()
             .find(|output| {
-                let tty_state: &TtyOutputState = output.user_data().get().unwrap();
-                tty_state.node == node && tty_state.crtc == crtc
+                let tty_state = output.user_data().get::<TtyOutputState>();
+                tty_state.is_some_and(|s| s.node == node && s.crtc == crtc)
             })
             .cloned();
         if let Some(output) = output {
@@ -1201,15 +1255,15 @@ impl Tty {
             .message(&message, 0);
 
         let Some(output) = niri
-            .global_space
-            .outputs()
+            .output_state
+            .keys()
             .find(|output| {
-                let tty_state: &TtyOutputState = output.user_data().get().unwrap();
-                tty_state.node == node && tty_state.crtc == crtc
+                let tty_state = output.user_data().get::<TtyOutputState>();
+                tty_state.is_some_and(|s| s.node == node && s.crtc == crtc)
             })
             .cloned()
         else {
-            error!("missing output in global space for {name}");
+            error!("missing output for {name}");
             return;
         };
 
@@ -1405,7 +1459,10 @@ impl Tty {
         let flags = {
             let debug = &self.config.borrow().debug;
 
-            let primary_scanout_flag = if debug.restrict_primary_scanout_to_matching_format {
+            let restrict_primary_scanout = debug.restrict_primary_scanout_to_matching_format
+                && !(debug.scanout_video_and_games_in_any_format
+                    && niri.has_fullscreen_video_or_game(output));
+            let primary_scanout_flag = if restrict_primary_scanout {
                 FrameFlags::ALLOW_PRIMARY_PLANE_SCANOUT
             } else {
                 FrameFlags::ALLOW_PRIMARY_PLANE_SCANOUT_ANY
@@ -1436,6 +1493,8 @@ impl Tty {
         let drm_compositor = &mut surface.compositor;
         match drm_compositor.render_frame::<_, _>(&mut renderer, &elements, [0.; 4], flags) {
             Ok(res) => {
+                self.gpu_recovery.remove(&tty_state.node);
+
                 let needs_sync = res.needs_sync()
                     || self
                         .config
@@ -1496,6 +1555,38 @@ impl Tty {
             Err(err) => {
                 // Can fail if we switched to a different TTY.
                 warn!("error rendering frame: {err}");
+
+                // After a GPU reset, the renderer can't be used anymore and the device needs to
+                // be recreated.
+                if is_context_lost(&err) {
+                    let node = tty_state.node;
+                    let recovery = self.gpu_recovery.entry(node).or_default();
+                    if !recovery.scheduled {
+                        recovery.scheduled = true;
+
+                        if recovery.attempts < GPU_RECOVERY_MAX_ATTEMPTS {
+                            let delay = GPU_RECOVERY_BASE_DELAY * 2u32.pow(recovery.attempts);
+                            recovery.attempts += 1;
+
+                            let timer = Timer::from_duration(delay);
+                            niri.event_loop
+                                .insert_source(timer, move |_, _, state| {
+                                    state
+                                        .backend
+                                        .tty()
+                                        .recover_from_gpu_reset(node, &mut state.niri);
+                                    TimeoutAction::Drop
+                                })
+                                .unwrap();
+                        } else {
+                            // Stays scheduled so that this is only logged once.
+                            error!(
+                                "giving up on recreating {node} after {} attempts",
+                                recovery.attempts
+                            );
+                        }
+                    }
+                }
             }
         }
 
@@ -1508,6 +1599,39 @@ impl Tty {
         rv
     }
 
+    /// Recreates a device with its renderer, which also imports the client buffers again.
+    ///
+    /// This is also done for the primary GPU, which does the rendering for all outputs.
+    fn recover_from_gpu_reset(&mut self, node: DrmNode, niri: &mut Niri) {
+        if let Some(recovery) = self.gpu_recovery.get_mut(&node) {
+            recovery.scheduled = false;
+        }
+
+        if !self.session.is_active() {
+            return;
+        }
+
+        let mut nodes = vec![node];
+        if node != self.primary_node && self.devices.contains_key(&self.primary_node) {
+            nodes.push(self.primary_node);
+        }
+
+        for node in nodes {
+            let Some(device) = self.devices.get(&node) else {
+                continue;
+            };
+
+            warn!("renderer context lost on {node}, recreating the device");
+            let path = device.path.clone();
+            self.device_removed(node.dev_id(), niri);
+            if let Err(err) = self.device_added(node.dev_id(), &path, niri) {
+                warn!("error adding device after a GPU reset: {err:?}");
+            }
+        }
+
+        niri.queue_redraw_all();
+    }
+
     pub fn change_vt(&mut self, vt: i32) {
         if let Err(err) = self.session.change_vt(vt) {
             warn!("error changing VT: {err}");
@@ -1567,7 +1691,10 @@ impl Tty {
     }
 
     pub fn get_gamma_size(&self, output: &Output) -> anyhow::Result<u32> {
-        let tty_state = output.user_data().get::<TtyOutputState>().unwrap();
+        let tty_state = output
+            .user_data()
+            .get::<TtyOutputState>()
+            .context("not a TTY output")?;
         let crtc = tty_state.crtc;
 
         let device = self
@@ -1588,7 +1715,10 @@ impl Tty {
     }
 
     pub fn set_gamma(&mut self, output: &Output, ramp: Option<Vec<u16>>) -> anyhow::Result<()> {
-        let tty_state = output.user_data().get::<TtyOutputState>().unwrap();
+        let tty_state = output
+            .user_data()
+            .get::<TtyOutputState>()
+            .context("not a TTY output")?;
         let crtc = tty_state.crtc;
 
         let device = self
@@ -1627,7 +1757,7 @@ impl Tty {
                 let current_crtc_mode = surface.map(|surface| surface.compositor.pending_mode());
                 let mut current_mode = None;
 
-                let modes = connector
+                let mut modes: Vec<_> = connector
                     .modes()
                     .iter()
                     .filter(|m| !m.flags().contains(ModeFlags::INTERLACE))
@@ -1647,6 +1777,19 @@ impl Tty {
                     .collect();
 
                 if let Some(crtc_mode) = current_crtc_mode {
+                    // Custom modes from the config aren't in the connector mode list.
+                    if current_mode.is_none()
+                        && crtc_mode.mode_type().contains(ModeTypeFlags::USERDEF)
+                    {
+                        current_mode = Some(modes.len());
+                        modes.push(niri_ipc::Mode {
+                            width: crtc_mode.size().0,
+                            height: crtc_mode.size().1,
+                            refresh_rate: Mode::from(crtc_mode).refresh as u32,
+                            is_preferred: false,
+                        });
+                    }
+
                     if current_mode.is_none() {
                         if crtc_mode.flags().contains(ModeFlags::INTERLACE) {
                             warn!("connector mode list missing current mode (interlaced)");
@@ -1672,8 +1815,8 @@ impl Tty {
                     .global_space
                     .outputs()
                     .find(|output| {
-                        let tty_state: &TtyOutputState = output.user_data().get().unwrap();
-                        tty_state.node == *node && tty_state.crtc == crtc
+                        let tty_state = output.user_data().get::<TtyOutputState>();
+                        tty_state.is_some_and(|s| s.node == *node && s.crtc == crtc)
                     })
                     .map(logical_output);
 
@@ -1701,8 +1844,11 @@ impl Tty {
         }
 
         let mut guard = self.ipc_outputs.lock().unwrap();
+        // Virtual outputs aren't tied to the devices, keep them.
+        let virtual_ids = niri.virtual_output_ids();
+        ipc_outputs.extend(guard.drain().filter(|(id, _)| virtual_ids.contains(id)));
         *guard = ipc_outputs;
-        niri.ipc_outputs_changed = true;
+        niri.ipc_outputs_dirty = true;
     }
 
     pub fn ipc_outputs(&self) -> Arc<Mutex<IpcOutputMap>> {
@@ -1741,6 +1887,68 @@ impl Tty {
         }
     }
 
+    pub fn ipc_gpus(&self) -> Vec<niri_ipc::Gpu> {
+        let path = |node: &DrmNode| {
+            node.dev_path().map_or_else(
+                || node.to_string(),
+                |path| path.to_string_lossy().into_owned(),
+            )
+        };
+
+        let mut gpus: Vec<_> = self
+            .devices
+            .iter()
+            .map(|(node, device)| {
+                let mut outputs: Vec<_> = device
+                    .known_crtcs
+                    .values()
+                    .map(|crtc| crtc.name.connector.clone())
+                    .collect();
+                outputs.sort_unstable();
+
+                niri_ipc::Gpu {
+                    path: path(node),
+                    render_node: Some(path(&device.render_node)),
+                    is_primary: *node == self.primary_node
+                        || device.render_node == self.primary_render_node,
+                    outputs,
+                }
+            })
+            .collect();
+
+        // The primary GPU may have no display controller.
+        if !gpus.iter().any(|gpu| gpu.is_primary) {
+            gpus.push(niri_ipc::Gpu {
+                path: path(&self.primary_node),
+                render_node: Some(path(&self.primary_render_node)),
+                is_primary: true,
+                outputs: Vec::new(),
+            });
+        }
+
+        gpus.sort_by(|a, b| a.path.cmp(&b.path));
+        gpus
+    }
+
+    pub fn set_output_active(&mut self, output: &Output, active: bool) {
+        // Same as set_monitors_active(), but for a single output.
+        if active {
+            return;
+        }
+
+        let Some(tty_state): Option<&TtyOutputState> = output.user_data().get() else {
+            return;
+        };
+        let Some(device) = self.devices.get_mut(&tty_state.node) else {
+            return;
+        };
+        if let Some(surface) = device.surfaces.get_mut(&tty_state.crtc) {
+            if let Err(err) = surface.compositor.clear() {
+                warn!("error clearing drm surface: {err:?}");
+            }
+        }
+    }
+
     pub fn set_output_on_demand_vrr(&mut self, niri: &mut Niri, output: &Output, enable_vrr: bool) {
         let _span = tracy_client::span!("Tty::set_output_on_demand_vrr");
 
@@ -1785,16 +1993,25 @@ impl Tty {
         let mut disable_laptop_panels = false;
         if niri.is_lid_closed {
             let config = self.config.borrow();
-            if !config.debug.keep_laptop_panel_on_when_lid_is_closed {
-                // Check if any external monitor is connected.
-                'outer: for device in self.devices.values() {
-                    for (connector, _crtc) in device.drm_scanner.crtcs() {
-                        if !is_laptop_panel(&format_connector_name(connector)) {
-                            disable_laptop_panels = true;
-                            break 'outer;
+            let mut internal_output = config.lid_switch.internal_output;
+            if config.debug.keep_laptop_panel_on_when_lid_is_closed {
+                internal_output = LidInternalOutput::On;
+            }
+
+            match internal_output {
+                LidInternalOutput::OffWithExternal => {
+                    // Check if any external monitor is connected.
+                    'outer: for device in self.devices.values() {
+                        for (connector, _crtc) in device.drm_scanner.crtcs() {
+                            if !is_laptop_panel(&format_connector_name(connector)) {
+                                disable_laptop_panels = true;
+                                break 'outer;
+                            }
                         }
                     }
                 }
+                LidInternalOutput::Off => disable_laptop_panels = true,
+                LidInternalOutput::On => (),
             }
         }
         let should_disable = |connector: &str| disable_laptop_panels && is_laptop_panel(connector);
@@ -1823,10 +2040,22 @@ impl Tty {
                     continue;
                 };
 
+                let custom_mode = configured_custom_mode(&config).unwrap_or_else(|err| {
+                    warn!(
+                        "output {:?}: invalid custom mode, ignoring: {err:?}",
+                        surface.name.connector
+                    );
+                    None
+                });
+
                 let Some((mode, fallback)) = pick_mode(connector, config.mode) else {
                     warn!("couldn't pick mode for enabled connector");
                     continue;
                 };
+                let (mode, fallback) = match custom_mode {
+                    Some(custom_mode) => (custom_mode, false),
+                    None => (mode, fallback),
+                };
 
                 let change_mode = surface.compositor.pending_mode() != mode;
 
@@ -1839,11 +2068,11 @@ impl Tty {
                 }
 
                 let output = niri
-                    .global_space
-                    .outputs()
+                    .output_state
+                    .keys()
                     .find(|output| {
-                        let tty_state: &TtyOutputState = output.user_data().get().unwrap();
-                        tty_state.node == node && tty_state.crtc == crtc
+                        let tty_state = output.user_data().get::<TtyOutputState>();
+                        tty_state.is_some_and(|s| s.node == node && s.crtc == crtc)
                     })
                     .cloned();
                 let Some(output) = output else {
@@ -1893,7 +2122,14 @@ impl Tty {
                         surface.name.connector
                     );
                     if let Err(err) = surface.compositor.use_mode(mode) {
-                        warn!("error changing mode: {err:?}");
+                        if custom_mode.is_some() {
+                            warn!(
+                                "output {:?}: custom mode was rejected by the driver: {err:?}",
+                                surface.name.connector
+                            );
+                        } else {
+                            warn!("error changing mode: {err:?}");
+                        }
                         continue;
                     }
 
@@ -2314,6 +2550,18 @@ fn suspend() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Returns whether the error comes from the renderer losing its context after a GPU reset.
+fn is_context_lost(err: &(dyn std::error::Error + 'static)) -> bool {
+    let mut source = Some(err);
+    while let Some(err) = source {
+        if matches!(err.downcast_ref::<GlesError>(), Some(GlesError::ContextLost)) {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
+
 fn queue_estimated_vblank_timer(
     niri: &mut Niri,
     output: Output,
@@ -2426,6 +2674,164 @@ fn pick_mode(
     mode.map(|m| (*m, fallback))
 }
 
+/// Computes the custom mode requested in the output config, if any.
+fn configured_custom_mode(config: &niri_config::Output) -> anyhow::Result<Option<DrmMode>> {
+    if let Some(modeline) = &config.modeline {
+        return modeline_to_drm_mode(modeline).map(Some);
+    }
+
+    if config.custom_mode {
+        let target = config.mode.context("custom-mode requires mode to be set")?;
+        let refresh = target.refresh.unwrap_or(60.);
+        return cvt_rb_mode(target.width, target.height, refresh).map(Some);
+    }
+
+    Ok(None)
+}
+
+fn modeline_to_drm_mode(modeline: &niri_config::Modeline) -> anyhow::Result<DrmMode> {
+    let niri_config::Modeline {
+        clock,
+        hdisplay,
+        hsync_start,
+        hsync_end,
+        htotal,
+        vdisplay,
+        vsync_start,
+        vsync_end,
+        vtotal,
+        hsync_polarity,
+        vsync_polarity,
+    } = *modeline;
+
+    let clock = (clock.0 * 1000.).round() as u32;
+    ensure!(clock > 0, "pixel clock must be positive");
+    ensure!(
+        0 < hdisplay && hdisplay <= hsync_start && hsync_start <= hsync_end && hsync_end < htotal,
+        "horizontal timings must satisfy 0 < hdisplay <= hsync-start <= hsync-end < htotal"
+    );
+    ensure!(
+        0 < vdisplay && vdisplay <= vsync_start && vsync_start <= vsync_end && vsync_end < vtotal,
+        "vertical timings must satisfy 0 < vdisplay <= vsync-start <= vsync-end < vtotal"
+    );
+
+    let mut flags = ModeFlags::empty();
+    flags |= match hsync_polarity {
+        niri_config::HSyncPolarity::Positive => ModeFlags::PHSYNC,
+        niri_config::HSyncPolarity::Negative => ModeFlags::NHSYNC,
+    };
+    flags |= match vsync_polarity {
+        niri_config::VSyncPolarity::Positive => ModeFlags::PVSYNC,
+        niri_config::VSyncPolarity::Negative => ModeFlags::NVSYNC,
+    };
+
+    Ok(custom_drm_mode(
+        clock,
+        (hdisplay, hsync_start, hsync_end, htotal),
+        (vdisplay, vsync_start, vsync_end, vtotal),
+        flags,
+    ))
+}
+
+/// Generates a mode with CVT reduced blanking (v1) timings.
+///
+/// This follows the same computation as the `cvt -r` tool.
+fn cvt_rb_mode(width: u16, height: u16, refresh: f64) -> anyhow::Result<DrmMode> {
+    // Minimum vertical blanking interval time in µs.
+    const MIN_V_BLANK: f64 = 460.;
+    const H_BLANK: u16 = 160;
+    const H_SYNC: u16 = 32;
+    const V_FRONT_PORCH: u16 = 3;
+    const MIN_V_BACK_PORCH: u16 = 6;
+    // Pixel clock granularity in MHz.
+    const CLOCK_STEP: f64 = 0.25;
+
+    ensure!(refresh > 0., "refresh rate must be positive");
+
+    // Horizontal resolution must be a multiple of the character cell.
+    let hdisplay = width - width % 8;
+    let vdisplay = height;
+    ensure!(hdisplay > 0 && vdisplay > 0, "mode size must be positive");
+
+    // The vertical sync width encodes the aspect ratio.
+    let (w, h) = (u32::from(hdisplay), u32::from(vdisplay));
+    let v_sync = if h % 3 == 0 && h * 4 / 3 == w {
+        4
+    } else if h % 9 == 0 && h * 16 / 9 == w {
+        5
+    } else if h % 10 == 0 && h * 16 / 10 == w {
+        6
+    } else if (h % 4 == 0 && h * 5 / 4 == w) || (h % 9 == 0 && h * 15 / 9 == w) {
+        7
+    } else {
+        10
+    };
+
+    let h_period_estimate = (1_000_000. / refresh - MIN_V_BLANK) / f64::from(vdisplay);
+    ensure!(h_period_estimate > 0., "refresh rate is too high");
+
+    // Convert through a checked float, since extreme modes don't fit into the timing fields.
+    let vbi_lines = (MIN_V_BLANK / h_period_estimate).floor() + 1.;
+    ensure!(vbi_lines <= f64::from(u16::MAX), "refresh rate is too high");
+    let vbi_lines = vbi_lines as u16;
+    let min_vbi_lines = V_FRONT_PORCH + v_sync + MIN_V_BACK_PORCH;
+    let vtotal = vdisplay
+        .checked_add(vbi_lines.max(min_vbi_lines))
+        .context("mode is too large")?;
+    let htotal = hdisplay.checked_add(H_BLANK).context("mode is too large")?;
+
+    let clock = refresh * f64::from(vtotal) * f64::from(htotal) / 1_000_000.;
+    let clock = (clock / CLOCK_STEP).floor() * CLOCK_STEP;
+    let clock = (clock * 1000.).round();
+    ensure!(clock > 0., "refresh rate is too low");
+    ensure!(clock <= f64::from(u32::MAX), "mode is too large");
+    let clock = clock as u32;
+
+    let hsync_start = hdisplay + H_BLANK / 2 - H_SYNC;
+    let vsync_start = vdisplay + V_FRONT_PORCH;
+
+    Ok(custom_drm_mode(
+        clock,
+        (hdisplay, hsync_start, hsync_start + H_SYNC, htotal),
+        (vdisplay, vsync_start, vsync_start + v_sync, vtotal),
+        ModeFlags::PHSYNC | ModeFlags::NVSYNC,
+    ))
+}
+
+fn custom_drm_mode(
+    clock: u32,
+    (hdisplay, hsync_start, hsync_end, htotal): (u16, u16, u16, u16),
+    (vdisplay, vsync_start, vsync_end, vtotal): (u16, u16, u16, u16),
+    flags: ModeFlags,
+) -> DrmMode {
+    let total = u64::from(htotal) * u64::from(vtotal);
+    let vrefresh = (u64::from(clock) * 1000 + total / 2) / total;
+
+    let mut name = [0; 32];
+    let formatted = format!("{hdisplay}x{vdisplay}");
+    for (c, b) in zip(&mut name[..31], formatted.bytes()) {
+        *c = b as _;
+    }
+
+    DrmMode::from(drm_ffi::drm_mode_modeinfo {
+        clock,
+        hdisplay,
+        hsync_start,
+        hsync_end,
+        htotal,
+        hskew: 0,
+        vdisplay,
+        vsync_start,
+        vsync_end,
+        vtotal,
+        vscan: 0,
+        vrefresh: vrefresh as u32,
+        flags: flags.bits(),
+        type_: ModeTypeFlags::USERDEF.bits(),
+        name,
+    })
+}
+
 fn get_edid_info(
     device: &DrmDevice,
     connector: connector::Handle,
@@ -2551,3 +2957,51 @@ fn make_output_name(
         serial: info.as_ref().and_then(|info| info.serial()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[track_caller]
+    fn check_cvt_rb(
+        (width, height, refresh): (u16, u16, f64),
+        clock: u32,
+        hsync: (u16, u16, u16),
+        vsync: (u16, u16, u16),
+    ) {
+        let mode = cvt_rb_mode(width, height, refresh).unwrap();
+        assert_eq!(mode.clock(), clock);
+        assert_eq!(mode.size(), (width, height));
+        assert_eq!(mode.hsync(), hsync);
+        assert_eq!(mode.vsync(), vsync);
+    }
+
+    #[test]
+    fn cvt_rb_matches_known_timings() {
+        // Values from `cvt -r`.
+        check_cvt_rb(
+            (1920, 1080, 60.),
+            138_500,
+            (1968, 2000, 2080),
+            (1083, 1088, 1111),
+        );
+        check_cvt_rb(
+            (2560, 1440, 60.),
+            241_500,
+            (2608, 2640, 2720),
+            (1443, 1448, 1481),
+        );
+    }
+
+    #[test]
+    fn cvt_rb_rejects_extreme_modes() {
+        assert!(cvt_rb_mode(1920, 1080, 0.).is_err());
+        assert!(cvt_rb_mode(1920, 1080, -60.).is_err());
+        assert!(cvt_rb_mode(1920, 1080, f64::NAN).is_err());
+        assert!(cvt_rb_mode(1920, 1080, f64::INFINITY).is_err());
+        assert!(cvt_rb_mode(1920, u16::MAX, 2170.).is_err());
+        assert!(cvt_rb_mode(1920, 1080, 1e-9).is_err());
+        assert!(cvt_rb_mode(u16::MAX, u16::MAX, 60.).is_err());
+        assert!(cvt_rb_mode(4, 1080, 60.).is_err());
+    }
+}
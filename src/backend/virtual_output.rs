@@ -0,0 +1,233 @@
+//! Virtual outputs created at runtime over IPC.
+//!
+//! Virtual outputs aren't shown on any physical monitor. They work like any other output
+//! otherwise, so they can be screencast, for example to stream a dedicated monitor over VNC, RDP
+//! or to a game-streaming client.
+
+use std::mem;
+use std::time::Duration;
+
+use niri_config::OutputName;
+use smithay::backend::renderer::element::RenderElementStates;
+use smithay::output::{Mode, Output, PhysicalProperties, Subpixel};
+use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
+use smithay::reexports::wayland_protocols::wp::presentation_time::server::wp_presentation_feedback;
+use smithay::utils::Size;
+use smithay::wayland::presentation::Refresh;
+
+use super::{OutputId, RenderResult};
+use crate::niri::{Niri, RedrawState, State};
+use crate::utils::{get_monotonic_time, logical_output};
+
+/// Refresh rate of virtual outputs, unless requested otherwise, in millihertz.
+const DEFAULT_REFRESH_RATE: u32 = 60_000;
+
+/// Marks a virtual output, stored in the output user data.
+#[derive(Debug)]
+pub struct VirtualOutput {
+    pub id: OutputId,
+    /// Requested scale, used unless the output config sets one.
+    pub scale: Option<f64>,
+}
+
+impl Niri {
+    /// Returns the IPC ids of the current virtual outputs.
+    pub fn virtual_output_ids(&self) -> Vec<OutputId> {
+        self.virtual_outputs
+            .iter()
+            .map(|output| output.user_data().get::<VirtualOutput>().unwrap().id)
+            .collect()
+    }
+}
+
+impl State {
+    /// Creates a new virtual output and returns its name.
+    pub fn create_virtual_output(
+        &mut self,
+        width: u16,
+        height: u16,
+        refresh_rate: Option<u32>,
+        scale: Option<f64>,
+    ) -> Result<String, String> {
+        if width == 0 || height == 0 {
+            return Err(String::from("output size must be positive"));
+        }
+
+        let refresh_rate = refresh_rate.unwrap_or(DEFAULT_REFRESH_RATE);
+        if refresh_rate == 0 {
+            return Err(String::from("refresh rate must be positive"));
+        }
+
+        if let Some(scale) = scale {
+            if !(scale.is_finite() && scale > 0.) {
+                return Err(String::from("scale must be positive"));
+            }
+        }
+
+        let n = (1..)
+            .find(|n| {
+                let connector = format!("virtual-{n}");
+                !self.niri.output_state.keys().any(|o| o.name() == connector)
+            })
+            .unwrap();
+        let connector = format!("virtual-{n}");
+        let make = String::from("niri");
+        let model = String::from("virtual");
+
+        let output = Output::new(
+            connector.clone(),
+            PhysicalProperties {
+                size: (0, 0).into(),
+                subpixel: Subpixel::Unknown,
+                make: make.clone(),
+                model: model.clone(),
+            },
+        );
+
+        let mode = Mode {
+            size: Size::from((i32::from(width), i32::from(height))),
+            refresh: i32::try_from(refresh_rate).unwrap_or(i32::MAX),
+        };
+        output.change_current_state(Some(mode), None, None, None);
+        output.set_preferred(mode);
+
+        let id = OutputId::next();
+        output.user_data().insert_if_missing(|| OutputName {
+            connector: connector.clone(),
+            make: Some(make.clone()),
+            model: Some(model.clone()),
+            serial: None,
+        });
+        output
+            .user_data()
+            .insert_if_missing(|| VirtualOutput { id, scale });
+
+        let refresh_interval = Duration::from_secs_f64(1000. / f64::from(refresh_rate));
+        self.niri
+            .add_output(output.clone(), Some(refresh_interval), false);
+        self.niri.virtual_outputs.push(output.clone());
+
+        self.backend.ipc_outputs().lock().unwrap().insert(
+            id,
+            niri_ipc::Output {
+                name: connector.clone(),
+                make,
+                model,
+                serial: None,
+                physical_size: None,
+                modes: vec![niri_ipc::Mode {
+                    width,
+                    height,
+                    refresh_rate,
+                    is_preferred: true,
+                }],
+                current_mode: Some(0),
+                vrr_supported: false,
+                vrr_enabled: false,
+                logical: Some(logical_output(&output)),
+            },
+        );
+        self.niri.ipc_outputs_dirty = true;
+
+        Ok(connector)
+    }
+
+    /// Destroys a virtual output by its name.
+    pub fn destroy_virtual_output(&mut self, name: &str) -> Result<(), String> {
+        let Some(idx) = self
+            .niri
+            .virtual_outputs
+            .iter()
+            .position(|o| o.name() == name)
+        else {
+            return Err(format!("no virtual output named {name}"));
+        };
+
+        let output = self.niri.virtual_outputs.remove(idx);
+        let id = output.user_data().get::<VirtualOutput>().unwrap().id;
+        self.niri.remove_output(&output);
+
+        self.backend.ipc_outputs().lock().unwrap().remove(&id);
+        self.niri.ipc_outputs_dirty = true;
+
+        Ok(())
+    }
+}
+
+/// "Presents" a frame on a virtual output.
+///
+/// There's nothing to display the frame on, so this pretends that the frame was presented right
+/// away and paces the next one with a timer at the output refresh rate. Screencasts of the output
+/// are rendered separately.
+pub fn render(niri: &mut Niri, output: &Output) -> RenderResult {
+    let states = RenderElementStates::default();
+    let mut presentation_feedbacks = niri.take_presentation_feedbacks(output, &states);
+    let now = get_monotonic_time();
+    presentation_feedbacks.presented::<_, smithay::utils::Monotonic>(
+        now,
+        Refresh::Unknown,
+        0,
+        wp_presentation_feedback::Kind::empty(),
+    );
+
+    let output_state = niri.output_state.get_mut(output).unwrap();
+    output_state.frame_clock.presented(now);
+    let refresh_interval = output_state
+        .frame_clock
+        .refresh_interval()
+        .unwrap_or(Duration::from_micros(16_667));
+
+    match mem::replace(&mut output_state.redraw_state, RedrawState::Idle) {
+        RedrawState::Idle => unreachable!(),
+        RedrawState::Queued => (),
+        RedrawState::WaitingForVBlank { .. } => unreachable!(),
+        RedrawState::WaitingForEstimatedVBlank(_) => unreachable!(),
+        // Keep pacing with the timer that is already running.
+        RedrawState::WaitingForEstimatedVBlankAndQueued(token) => {
+            output_state.redraw_state = RedrawState::WaitingForEstimatedVBlank(token);
+            return RenderResult::Submitted;
+        }
+    }
+
+    let output_ = output.clone();
+    let timer = Timer::from_duration(refresh_interval);
+    let token = niri
+        .event_loop
+        .insert_source(timer, move |_, _, state| {
+            on_vblank_timer(&mut state.niri, &output_);
+            TimeoutAction::Drop
+        })
+        .unwrap();
+
+    let output_state = niri.output_state.get_mut(output).unwrap();
+    output_state.redraw_state = RedrawState::WaitingForEstimatedVBlank(token);
+
+    RenderResult::Submitted
+}
+
+fn on_vblank_timer(niri: &mut Niri, output: &Output) {
+    let Some(output_state) = niri.output_state.get_mut(output) else {
+        return;
+    };
+
+    // We waited for the timer, now we can send frame callbacks again.
+    output_state.frame_callback_sequence = output_state.frame_callback_sequence.wrapping_add(1);
+
+    match mem::replace(&mut output_state.redraw_state, RedrawState::Idle) {
+        RedrawState::Idle => unreachable!(),
+        RedrawState::Queued => unreachable!(),
+        RedrawState::WaitingForVBlank { .. } => unreachable!(),
+        RedrawState::WaitingForEstimatedVBlank(_) => (),
+        // The timer fired just in front of a redraw.
+        RedrawState::WaitingForEstimatedVBlankAndQueued(_) => {
+            output_state.redraw_state = RedrawState::Queued;
+            return;
+        }
+    }
+
+    if output_state.unfinished_animations_remain {
+        niri.queue_redraw(output);
+    } else {
+        niri.send_frame_callbacks(output);
+    }
+}
@@ -6,15 +6,21 @@ use std::sync::{Arc, Mutex};
 
 use niri_config::{Config, OutputName};
 use smithay::backend::allocator::dmabuf::Dmabuf;
+use smithay::backend::allocator::Fourcc;
 use smithay::backend::renderer::damage::OutputDamageTracker;
-use smithay::backend::renderer::gles::GlesRenderer;
-use smithay::backend::renderer::{DebugFlags, ImportDma, ImportEgl, Renderer};
+use smithay::backend::renderer::gles::{GlesError, GlesRenderer, GlesTarget, GlesTexture};
+use smithay::backend::renderer::sync::SyncPoint;
+use smithay::backend::renderer::{
+    Bind as _, Color32F, DebugFlags, Frame as _, ImportDma, ImportEgl, Offscreen as _, Renderer,
+    Texture as _,
+};
 use smithay::backend::winit::{self, WinitEvent, WinitGraphicsBackend};
 use smithay::output::{Mode, Output, PhysicalProperties, Subpixel};
 use smithay::reexports::calloop::LoopHandle;
 use smithay::reexports::wayland_protocols::wp::presentation_time::server::wp_presentation_feedback;
 use smithay::reexports::winit::dpi::LogicalSize;
 use smithay::reexports::winit::window::Window;
+use smithay::utils::{Physical, Point, Rectangle, Size, Transform};
 use smithay::wayland::presentation::Refresh;
 
 use super::{IpcOutputMap, OutputId, RenderResult};
@@ -25,10 +31,24 @@ use crate::utils::{get_monotonic_time, logical_output};
 
 pub struct Winit {
     config: Rc<RefCell<Config>>,
-    output: Output,
+    /// Outputs shown side by side in the host window.
+    outputs: Vec<WinitOutput>,
     backend: WinitGraphicsBackend<GlesRenderer>,
-    damage_tracker: OutputDamageTracker,
     ipc_outputs: Arc<Mutex<IpcOutputMap>>,
+    /// Whether drawing the outputs into the host window is queued.
+    composite_queued: bool,
+}
+
+struct WinitOutput {
+    output: Output,
+    id: OutputId,
+    damage_tracker: OutputDamageTracker,
+    /// Last frame of the output.
+    ///
+    /// With a single output, it renders straight into the host window and this is `None`. With
+    /// several outputs, each renders into its own texture, and the textures are then drawn next
+    /// to each other into the host window.
+    texture: Option<GlesTexture>,
 }
 
 impl Winit {
@@ -42,96 +62,38 @@ impl Winit {
             .with_title("niri");
         let (backend, winit) = winit::init_from_attributes(builder)?;
 
-        let output = Output::new(
-            "winit".to_string(),
-            PhysicalProperties {
-                size: (0, 0).into(),
-                subpixel: Subpixel::Unknown,
-                make: "Smithay".into(),
-                model: "Winit".into(),
-            },
-        );
-
-        let mode = Mode {
-            size: backend.window_size(),
-            refresh: 60_000,
-        };
-        output.change_current_state(Some(mode), None, None, None);
-        output.set_preferred(mode);
-
-        output.user_data().insert_if_missing(|| OutputName {
-            connector: "winit".to_string(),
-            make: Some("Smithay".to_string()),
-            model: Some("Winit".to_string()),
-            serial: None,
-        });
+        let count = config.borrow().debug.nested_outputs.unwrap_or(1).max(1);
+        let size = output_size(backend.window_size(), usize::from(count));
 
-        let physical_properties = output.physical_properties();
-        let ipc_outputs = Arc::new(Mutex::new(HashMap::from([(
-            OutputId::next(),
-            niri_ipc::Output {
-                name: output.name(),
-                make: physical_properties.make,
-                model: physical_properties.model,
-                serial: None,
-                physical_size: None,
-                modes: vec![niri_ipc::Mode {
-                    width: backend.window_size().w.clamp(0, u16::MAX as i32) as u16,
-                    height: backend.window_size().h.clamp(0, u16::MAX as i32) as u16,
-                    refresh_rate: 60_000,
-                    is_preferred: true,
-                }],
-                current_mode: Some(0),
-                vrr_supported: false,
-                vrr_enabled: false,
-                logical: Some(logical_output(&output)),
-            },
-        )])));
-
-        let damage_tracker = OutputDamageTracker::from_output(&output);
+        let mut outputs = Vec::new();
+        let mut ipc_outputs = HashMap::new();
+        for idx in 0..usize::from(count) {
+            let (output, ipc_output) = WinitOutput::new(idx, size);
+            ipc_outputs.insert(output.id, ipc_output);
+            outputs.push(output);
+        }
+        let ipc_outputs = Arc::new(Mutex::new(ipc_outputs));
 
         event_loop
             .insert_source(winit, move |event, _, state| match event {
-                WinitEvent::Resized { size, .. } => {
-                    let winit = state.backend.winit();
-                    winit.output.change_current_state(
-                        Some(Mode {
-                            size,
-                            refresh: 60_000,
-                        }),
-                        None,
-                        None,
-                        None,
-                    );
-
-                    {
-                        let mut ipc_outputs = winit.ipc_outputs.lock().unwrap();
-                        let output = ipc_outputs.values_mut().next().unwrap();
-                        let mode = &mut output.modes[0];
-                        mode.width = size.w.clamp(0, u16::MAX as i32) as u16;
-                        mode.height = size.h.clamp(0, u16::MAX as i32) as u16;
-                        if let Some(logical) = output.logical.as_mut() {
-                            logical.width = size.w as u32;
-                            logical.height = size.h as u32;
-                        }
-                        state.niri.ipc_outputs_changed = true;
-                    }
-
-                    state.niri.output_resized(&winit.output);
-                }
+                WinitEvent::Resized { .. } => state.backend.winit().resize_outputs(&mut state.niri),
                 WinitEvent::Input(event) => state.process_input_event(event),
                 WinitEvent::Focus(_) => (),
-                WinitEvent::Redraw => state.niri.queue_redraw(&state.backend.winit().output),
+                WinitEvent::Redraw => {
+                    for output in &state.backend.winit().outputs {
+                        state.niri.queue_redraw(&output.output);
+                    }
+                }
                 WinitEvent::CloseRequested => state.niri.stop_signal.stop(),
             })
             .unwrap();
 
         Ok(Self {
             config,
-            output,
+            outputs,
             backend,
-            damage_tracker,
             ipc_outputs,
+            composite_queued: false,
         })
     }
 
@@ -158,7 +120,82 @@ impl Winit {
 
         niri.update_shaders();
 
-        niri.add_output(self.output.clone(), None, false);
+        for output in &self.outputs {
+            niri.add_output(output.output.clone(), None, false);
+        }
+    }
+
+    /// Adds an output to the host window, simulating a monitor hotplug.
+    pub fn add_output(&mut self, niri: &mut Niri) {
+        let size = output_size(self.backend.window_size(), self.outputs.len() + 1);
+        let (output, ipc_output) = WinitOutput::new(self.outputs.len(), size);
+        self.ipc_outputs
+            .lock()
+            .unwrap()
+            .insert(output.id, ipc_output);
+
+        let new_output = output.output.clone();
+        self.outputs.push(output);
+
+        self.resize_outputs(niri);
+        niri.add_output(new_output, None, false);
+        niri.ipc_outputs_dirty = true;
+    }
+
+    /// Removes the last added output from the host window, simulating a monitor unplug.
+    ///
+    /// The first output always stays.
+    pub fn remove_output(&mut self, niri: &mut Niri) {
+        if self.outputs.len() == 1 {
+            return;
+        }
+
+        let output = self.outputs.pop().unwrap();
+        niri.remove_output(&output.output);
+        self.ipc_outputs.lock().unwrap().remove(&output.id);
+
+        if self.outputs.len() == 1 {
+            // The texture would be stale the next time it's needed.
+            self.outputs[0].texture = None;
+        }
+
+        self.resize_outputs(niri);
+        niri.ipc_outputs_dirty = true;
+    }
+
+    /// Splits the host window between the outputs after a change to its size or to the outputs.
+    fn resize_outputs(&mut self, niri: &mut Niri) {
+        let size = output_size(self.backend.window_size(), self.outputs.len());
+        let mode = Mode {
+            size,
+            refresh: 60_000,
+        };
+
+        for output in &self.outputs {
+            if output.output.current_mode() == Some(mode) {
+                continue;
+            }
+
+            output
+                .output
+                .change_current_state(Some(mode), None, None, None);
+            output.output.set_preferred(mode);
+
+            {
+                let mut ipc_outputs = self.ipc_outputs.lock().unwrap();
+                let ipc_output = ipc_outputs.get_mut(&output.id).unwrap();
+                let mode = &mut ipc_output.modes[0];
+                mode.width = size.w.clamp(0, u16::MAX as i32) as u16;
+                mode.height = size.h.clamp(0, u16::MAX as i32) as u16;
+                if let Some(logical) = ipc_output.logical.as_mut() {
+                    logical.width = size.w as u32;
+                    logical.height = size.h as u32;
+                }
+                niri.ipc_outputs_dirty = true;
+            }
+
+            niri.output_resized(&output.output);
+        }
     }
 
     pub fn seat_name(&self) -> String {
@@ -175,6 +212,13 @@ impl Winit {
     pub fn render(&mut self, niri: &mut Niri, output: &Output) -> RenderResult {
         let _span = tracy_client::span!("Winit::render");
 
+        let idx = self
+            .outputs
+            .iter()
+            .position(|o| o.output == *output)
+            .unwrap();
+        let is_single = self.outputs.len() == 1;
+
         // Render the elements.
         let mut elements = niri.render::<GlesRenderer>(
             self.backend.renderer(),
@@ -189,16 +233,44 @@ impl Winit {
             draw_damage(&mut output_state.debug_damage_tracker, &mut elements);
         }
 
-        // Hand them over to winit.
-        let res = {
+        let winit_output = &mut self.outputs[idx];
+        let res = if is_single {
+            // Hand them over to winit.
             let (renderer, mut framebuffer) = self.backend.bind().unwrap();
             // FIXME: currently impossible to call due to a mutable borrow.
             //
             // let age = self.backend.buffer_age().unwrap();
             let age = 0;
-            self.damage_tracker
+            winit_output
+                .damage_tracker
                 .render_output(renderer, &mut framebuffer, age, &elements, [0.; 4])
                 .unwrap()
+        } else {
+            // Render into the output texture, to be drawn into the host window afterwards.
+            let renderer = self.backend.renderer();
+            let size = output.current_mode().unwrap().size;
+            let buffer_size = size.to_logical(1).to_buffer(1, Transform::Normal);
+
+            // The texture keeps the previous frame, unless it's new.
+            let mut age = 1;
+            if winit_output
+                .texture
+                .as_ref()
+                .map_or(true, |texture| texture.size() != buffer_size)
+            {
+                let texture = renderer
+                    .create_buffer(Fourcc::Abgr8888, buffer_size)
+                    .unwrap();
+                winit_output.texture = Some(texture);
+                age = 0;
+            }
+
+            let texture = winit_output.texture.as_mut().unwrap();
+            let mut target = renderer.bind(texture).unwrap();
+            winit_output
+                .damage_tracker
+                .render_output(renderer, &mut target, age, &elements, [0.; 4])
+                .unwrap()
         };
 
         niri.update_primary_scanout_output(output, &res.states);
@@ -217,7 +289,11 @@ impl Winit {
                 }
             }
 
-            self.backend.submit(Some(damage)).unwrap();
+            if is_single {
+                self.backend.submit(Some(damage)).unwrap();
+            } else {
+                self.queue_composite(niri);
+            }
 
             let mut presentation_feedbacks = niri.take_presentation_feedbacks(output, &res.states);
             presentation_feedbacks.presented::<_, smithay::utils::Monotonic>(
@@ -252,6 +328,70 @@ impl Winit {
         rv
     }
 
+    /// Queues drawing the output textures into the host window.
+    ///
+    /// This waits for an idle, so that all outputs redrawn at once are submitted together.
+    fn queue_composite(&mut self, niri: &Niri) {
+        if mem::replace(&mut self.composite_queued, true) {
+            return;
+        }
+
+        niri.event_loop.insert_idle(|state| {
+            let winit = state.backend.winit();
+            winit.composite_queued = false;
+            winit.composite(&state.niri);
+        });
+    }
+
+    fn composite(&mut self, niri: &Niri) {
+        let _span = tracy_client::span!("Winit::composite");
+
+        let window_size = self.backend.window_size();
+        let width = output_size(window_size, self.outputs.len()).w;
+
+        // Lay out the outputs in the window in the same order as in the global space, since the
+        // pointer position maps from the window to the global space as a whole.
+        let mut outputs = self.outputs.iter().collect::<Vec<_>>();
+        outputs.sort_by_key(|o| {
+            niri.global_space
+                .output_geometry(&o.output)
+                .map(|geo| (geo.loc.x, geo.loc.y))
+        });
+
+        let textures = outputs
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, o)| {
+                let loc = Point::from((width * i as i32, 0));
+                Some((loc, o.texture.as_ref()?))
+            })
+            .collect::<Vec<_>>();
+
+        let res = {
+            let (renderer, mut framebuffer) = self.backend.bind().unwrap();
+            draw_textures(renderer, &mut framebuffer, window_size, &textures)
+        };
+
+        match res {
+            Ok(sync) => {
+                if self
+                    .config
+                    .borrow()
+                    .debug
+                    .wait_for_frame_completion_before_queueing
+                {
+                    let _span = tracy_client::span!("wait for completion");
+                    if let Err(err) = sync.wait() {
+                        warn!("error waiting for frame completion: {err:?}");
+                    }
+                }
+
+                self.backend.submit(None).unwrap();
+            }
+            Err(err) => warn!("error drawing the outputs into the window: {err:?}"),
+        }
+    }
+
     pub fn toggle_debug_tint(&mut self) {
         let renderer = self.backend.renderer();
         renderer.set_debug_flags(renderer.debug_flags() ^ DebugFlags::TINT);
@@ -271,3 +411,104 @@ impl Winit {
         self.ipc_outputs.clone()
     }
 }
+
+impl WinitOutput {
+    fn new(idx: usize, size: Size<i32, Physical>) -> (Self, niri_ipc::Output) {
+        // Keep the first output name the same as with a single output.
+        let connector = if idx == 0 {
+            String::from("winit")
+        } else {
+            format!("winit-{}", idx + 1)
+        };
+
+        let output = Output::new(
+            connector.clone(),
+            PhysicalProperties {
+                size: (0, 0).into(),
+                subpixel: Subpixel::Unknown,
+                make: "Smithay".into(),
+                model: "Winit".into(),
+            },
+        );
+
+        let mode = Mode {
+            size,
+            refresh: 60_000,
+        };
+        output.change_current_state(Some(mode), None, None, None);
+        output.set_preferred(mode);
+
+        output.user_data().insert_if_missing(|| OutputName {
+            connector: connector.clone(),
+            make: Some("Smithay".to_string()),
+            model: Some("Winit".to_string()),
+            serial: None,
+        });
+
+        let physical_properties = output.physical_properties();
+        let ipc_output = niri_ipc::Output {
+            name: output.name(),
+            make: physical_properties.make,
+            model: physical_properties.model,
+            serial: None,
+            physical_size: None,
+            modes: vec![niri_ipc::Mode {
+                width: size.w.clamp(0, u16::MAX as i32) as u16,
+                height: size.h.clamp(0, u16::MAX as i32) as u16,
+                refresh_rate: 60_000,
+                is_preferred: true,
+            }],
+            current_mode: Some(0),
+            vrr_supported: false,
+            vrr_enabled: false,
+            logical: Some(logical_output(&output)),
+        };
+
+        let damage_tracker = OutputDamageTracker::from_output(&output);
+
+        let winit_output = Self {
+            output,
+            id: OutputId::next(),
+            damage_tracker,
+            texture: None,
+        };
+        (winit_output, ipc_output)
+    }
+}
+
+/// Returns the size of each of `count` outputs splitting the host window horizontally.
+fn output_size(window_size: Size<i32, Physical>, count: usize) -> Size<i32, Physical> {
+    let count = i32::try_from(count).unwrap_or(i32::MAX).max(1);
+    Size::from(((window_size.w / count).max(1), window_size.h))
+}
+
+fn draw_textures(
+    renderer: &mut GlesRenderer,
+    framebuffer: &mut GlesTarget<'_>,
+    size: Size<i32, Physical>,
+    textures: &[(Point<i32, Physical>, &GlesTexture)],
+) -> Result<SyncPoint, GlesError> {
+    let mut frame = renderer.render(framebuffer, size, Transform::Normal)?;
+    frame.clear(Color32F::TRANSPARENT, &[Rectangle::from_size(size)])?;
+
+    for (loc, texture) in textures {
+        let texture_size = texture.size();
+        let dst = Rectangle::new(
+            *loc,
+            texture_size.to_logical(1, Transform::Normal).to_physical(1),
+        );
+        frame.render_texture_from_to(
+            texture,
+            Rectangle::from_size(texture_size.to_f64()),
+            dst,
+            &[Rectangle::from_size(dst.size)],
+            &[],
+            Transform::Normal,
+            1.,
+            None,
+            &[],
+        )?;
+    }
+
+    frame.finish()
+}
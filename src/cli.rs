@@ -5,6 +5,7 @@ use clap::{Parser, Subcommand};
 use clap_complete::Shell;
 use niri_ipc::{Action, OutputAction};
 
+use crate::import_config::ImportSource;
 use crate::utils::version;
 
 #[derive(Parser)]
@@ -19,6 +20,12 @@ pub struct Cli {
     /// command line argument takes precedence.
     #[arg(short, long)]
     pub config: Option<PathBuf>,
+    /// Config profile to use.
+    ///
+    /// The contents of the matching `profile` sections in the config are used, and all other
+    /// profiles are ignored. This can also be set with the `NIRI_PROFILE` environment variable.
+    #[arg(short, long)]
+    pub profile: Option<String>,
     /// Import environment globally to systemd and D-Bus, run D-Bus services.
     ///
     /// Set this flag in a systemd service started by your display manager, or when running
@@ -26,6 +33,24 @@ pub struct Cli {
     /// on a TTY as your non-main compositor instance, to avoid messing up the global environment.
     #[arg(long)]
     pub session: bool,
+    /// Render on the CPU with the Mesa software renderer instead of the GPU.
+    ///
+    /// niri also switches to software rendering by itself on a TTY when no GPU can render, for
+    /// example in virtual machines without 3D acceleration.
+    #[arg(long)]
+    pub software_rendering: bool,
+    /// Record keyboard, pointer and gesture input to a file, for reproducing bugs.
+    ///
+    /// The file must not exist yet. The recording contains everything you type outside the lock
+    /// screen, including passwords typed into other programs.
+    #[arg(long, value_name = "PATH")]
+    pub record_input: Option<PathBuf>,
+    /// Replay input recorded with `--record-input` with its original timing.
+    ///
+    /// The replay only matches the recording if niri starts with the same config, outputs and
+    /// windows.
+    #[arg(long, value_name = "PATH")]
+    pub replay_input: Option<PathBuf>,
     /// Command to run upon compositor startup.
     #[arg(last = true)]
     pub command: Vec<OsString>,
@@ -43,6 +68,12 @@ pub enum Sub {
         /// Format output as JSON.
         #[arg(short, long)]
         json: bool,
+        /// Format lists of outputs, workspaces, windows, layers and autostart entries as tables.
+        #[arg(short, long, conflicts_with = "json")]
+        table: bool,
+        /// Print the response again every time the compositor state changes.
+        #[arg(short, long)]
+        watch: bool,
     },
     /// Validate the config file.
     Validate {
@@ -52,6 +83,28 @@ pub enum Sub {
         /// command line argument takes precedence.
         #[arg(short, long)]
         config: Option<PathBuf>,
+        /// Config profile to validate.
+        #[arg(short, long)]
+        profile: Option<String>,
+        /// Print all problems as JSON, for editor integrations.
+        #[arg(short, long)]
+        json: bool,
+    },
+    /// Print the structure of the config file as JSON, for editor integrations.
+    ///
+    /// The output lists every config node with its arguments, properties, children and default
+    /// values, and the deprecated syntax.
+    ConfigSchema,
+    /// Convert a sway or i3 config into a niri config.
+    ///
+    /// The niri config is printed to stdout. Directives that can't be converted are kept as
+    /// comments.
+    ImportConfig {
+        /// Compositor that the config is for.
+        #[arg(long, value_enum)]
+        from: ImportSource,
+        /// Path to the config file to convert.
+        path: PathBuf,
     },
     /// Cause a panic to check if the backtraces are good.
     Panic,
@@ -59,7 +112,7 @@ pub enum Sub {
     Completions { shell: Shell },
 }
 
-#[derive(Subcommand)]
+#[derive(Subcommand, Clone)]
 pub enum Msg {
     /// List connected outputs.
     Outputs,
@@ -84,6 +137,15 @@ pub enum Msg {
         #[command(subcommand)]
         action: Action,
     },
+    /// Perform several actions in order, without redrawing in between.
+    ///
+    /// Separate the actions with a standalone `;` argument, quoted to keep the shell from
+    /// interpreting it. For example: `niri msg actions focus-workspace 2 ';' maximize-column`.
+    Actions {
+        /// Actions with their arguments, separated by `;`.
+        #[arg(required = true, num_args = 1.., trailing_var_arg = true, allow_hyphen_values = true)]
+        actions: Vec<String>,
+    },
     /// Change output configuration temporarily.
     ///
     /// The configuration is changed temporarily and not saved into the config file. If the output
@@ -107,4 +169,89 @@ pub enum Msg {
     RequestError,
     /// Print the overview state.
     OverviewState,
+    /// Print the layout tree with window geometries.
+    Layout,
+    /// Validate the config file of the running niri instance.
+    ///
+    /// This uses the same config path and profile as the running instance.
+    ValidateConfig,
+    /// Print the current value of a config option.
+    GetConfig {
+        /// Dot-separated path to the option, for example `layout.gaps`.
+        name: String,
+    },
+    /// Change a config option.
+    ///
+    /// The change is temporary and not saved into the config file, unless `--persist` is set. If
+    /// the option subsequently changes in the config file, the temporary change is forgotten.
+    SetConfig {
+        /// Dot-separated path to the option, for example `layout.gaps`.
+        name: String,
+        /// New value, for example `16`, `true` or `null`.
+        value: String,
+        /// Also write the new value into the config file.
+        #[arg(long)]
+        persist: bool,
+    },
+    /// List the commands from `spawn-at-startup`.
+    Autostart,
+    /// Restart a command from `spawn-at-startup`.
+    RestartAutostart {
+        /// Id of the autostart entry, from `niri msg autostart`.
+        id: u64,
+    },
+    /// Create a virtual output and print its name.
+    ///
+    /// The output isn't shown on any monitor, but it can be screencast, for example to stream it
+    /// over VNC or RDP.
+    CreateVirtualOutput {
+        /// Width in physical pixels.
+        width: u16,
+        /// Height in physical pixels.
+        height: u16,
+        /// Refresh rate in Hz.
+        #[arg(long, default_value_t = 60.)]
+        refresh_rate: f64,
+        /// Scale, unless the output config sets one.
+        #[arg(long)]
+        scale: Option<f64>,
+    },
+    /// Destroy a virtual output.
+    DestroyVirtualOutput {
+        /// Name of the virtual output, like `virtual-1`.
+        output: String,
+    },
+    /// List the GPUs and the outputs connected to them.
+    Gpus,
+    /// Print the recent log messages.
+    ///
+    /// niri keeps the last 1000 messages that passed the log filter.
+    Logs,
+    /// Print the current log filter.
+    LogFilter,
+    /// Change the log filter.
+    ///
+    /// The change lasts until niri exits.
+    SetLogFilter {
+        /// New filter in the `RUST_LOG` syntax, for example `niri=trace,smithay=debug`.
+        ///
+        /// Leave empty to reset to the filter niri started with.
+        filter: Option<String>,
+    },
+}
+
+/// Single action parsed out of the `niri msg actions` arguments.
+#[derive(Parser)]
+#[command(no_binary_name = true)]
+struct ActionArgs {
+    #[command(subcommand)]
+    action: Action,
+}
+
+/// Parses `;`-separated actions for `niri msg actions`.
+pub fn parse_actions(args: &[String]) -> Result<Vec<Action>, clap::Error> {
+    args.split(|arg| arg == ";")
+        .filter(|args| !args.is_empty())
+        .map(|args| ActionArgs::try_parse_from(args).map(|args| args.action))
+        .collect()
 }
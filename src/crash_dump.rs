@@ -0,0 +1,261 @@
+//! Crash dumps and restarting after a crash.
+//!
+//! When the compositor panics, we write the layout state, the most recent input events and a hash
+//! of the config to a file, so that crash reports carry enough information to track the bug down.
+
+use std::any::Any;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::hash::{Hash, Hasher};
+use std::os::unix::process::CommandExt;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{env, fs};
+
+use directories::BaseDirs;
+use smithay::backend::input::{InputBackend, InputEvent};
+
+use crate::niri::State;
+use crate::utils::{get_monotonic_time, version};
+
+/// Environment variable counting the restarts after a crash.
+const RESTART_COUNT_ENV: &str = "NIRI_CRASH_RESTART_COUNT";
+
+/// Restarting stops after this many crashes, to avoid a crash loop.
+const MAX_RESTARTS: u32 = 3;
+
+/// Number of input event entries kept for the crash dump.
+const INPUT_HISTORY_LEN: usize = 64;
+
+/// Kinds of the most recent input events.
+///
+/// Only the kinds are stored, not the contents, so that the dump doesn't leak what was typed.
+#[derive(Debug, Default)]
+pub struct InputHistory {
+    entries: VecDeque<InputHistoryEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct InputHistoryEntry {
+    /// Time of the last event of this run.
+    time: Duration,
+    kind: &'static str,
+    /// Number of consecutive events of this kind.
+    count: u32,
+}
+
+impl InputHistory {
+    pub fn push(&mut self, kind: &'static str) {
+        self.push_at(get_monotonic_time(), kind);
+    }
+
+    fn push_at(&mut self, time: Duration, kind: &'static str) {
+        // Fold runs of the same event, like pointer motion, into one entry.
+        if let Some(last) = self.entries.back_mut() {
+            if last.kind == kind {
+                last.time = time;
+                last.count = last.count.saturating_add(1);
+                return;
+            }
+        }
+
+        if self.entries.len() == INPUT_HISTORY_LEN {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(InputHistoryEntry {
+            time,
+            kind,
+            count: 1,
+        });
+    }
+}
+
+/// Returns the kind of the input event for the input history.
+pub fn input_event_kind<I: InputBackend>(event: &InputEvent<I>) -> &'static str {
+    use InputEvent::*;
+    match event {
+        DeviceAdded { .. } => "DeviceAdded",
+        DeviceRemoved { .. } => "DeviceRemoved",
+        Keyboard { .. } => "Keyboard",
+        PointerMotion { .. } => "PointerMotion",
+        PointerMotionAbsolute { .. } => "PointerMotionAbsolute",
+        PointerButton { .. } => "PointerButton",
+        PointerAxis { .. } => "PointerAxis",
+        TabletToolAxis { .. } => "TabletToolAxis",
+        TabletToolTip { .. } => "TabletToolTip",
+        TabletToolProximity { .. } => "TabletToolProximity",
+        TabletToolButton { .. } => "TabletToolButton",
+        GestureSwipeBegin { .. } => "GestureSwipeBegin",
+        GestureSwipeUpdate { .. } => "GestureSwipeUpdate",
+        GestureSwipeEnd { .. } => "GestureSwipeEnd",
+        GesturePinchBegin { .. } => "GesturePinchBegin",
+        GesturePinchUpdate { .. } => "GesturePinchUpdate",
+        GesturePinchEnd { .. } => "GesturePinchEnd",
+        GestureHoldBegin { .. } => "GestureHoldBegin",
+        GestureHoldEnd { .. } => "GestureHoldEnd",
+        TouchDown { .. } => "TouchDown",
+        TouchMotion { .. } => "TouchMotion",
+        TouchUp { .. } => "TouchUp",
+        TouchCancel { .. } => "TouchCancel",
+        TouchFrame { .. } => "TouchFrame",
+        SwitchToggle { .. } => "SwitchToggle",
+        Special(_) => "Special",
+    }
+}
+
+/// Handles a panic that unwound out of the event loop.
+///
+/// Writes a crash dump, then restarts niri if configured, otherwise continues unwinding.
+pub fn handle_crash(state: &State, payload: Box<dyn Any + Send>) -> ! {
+    // Don't let a panic while dumping the state get in the way of the restart.
+    let res = panic::catch_unwind(AssertUnwindSafe(|| write_crash_dump(state, &*payload)));
+    match res {
+        Ok(Ok(path)) => error!("niri crashed, wrote crash dump to {path:?}"),
+        Ok(Err(err)) => error!("niri crashed, error writing crash dump: {err:?}"),
+        Err(_) => error!("niri crashed, panicked while writing crash dump"),
+    }
+
+    let restart = panic::catch_unwind(AssertUnwindSafe(|| {
+        state.niri.config.borrow().debug.restart_on_crash
+    }))
+    .unwrap_or(false);
+    if restart {
+        restart_after_crash();
+    }
+
+    panic::resume_unwind(payload)
+}
+
+fn write_crash_dump(state: &State, payload: &(dyn Any + Send)) -> anyhow::Result<PathBuf> {
+    let dump = crash_dump(state, payload);
+
+    let dir = BaseDirs::new()
+        .and_then(|dirs| dirs.state_dir().map(|dir| dir.to_owned()))
+        .unwrap_or_else(env::temp_dir)
+        .join("niri");
+    fs::create_dir_all(&dir)?;
+
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let path = dir.join(format!("crash-{secs}.txt"));
+    fs::write(&path, dump)?;
+
+    Ok(path)
+}
+
+fn crash_dump(state: &State, payload: &(dyn Any + Send)) -> String {
+    let niri = &state.niri;
+    let mut buf = String::new();
+
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.as_str()
+    } else {
+        "<unknown>"
+    };
+
+    let config_hash = {
+        let mut hasher = DefaultHasher::new();
+        format!("{:?}", niri.config.borrow()).hash(&mut hasher);
+        hasher.finish()
+    };
+
+    let now = get_monotonic_time();
+
+    writeln!(buf, "niri {}", version()).unwrap();
+    writeln!(buf, "panic: {message}").unwrap();
+    writeln!(buf, "config hash: {config_hash:016x}").unwrap();
+    writeln!(buf).unwrap();
+
+    writeln!(buf, "recent input events, oldest first:").unwrap();
+    for entry in &niri.input_history.entries {
+        let ago = now.saturating_sub(entry.time).as_secs_f64();
+        write!(buf, "  {ago:.3}s ago: {}", entry.kind).unwrap();
+        if entry.count > 1 {
+            write!(buf, " (x{})", entry.count).unwrap();
+        }
+        writeln!(buf).unwrap();
+    }
+    writeln!(buf).unwrap();
+
+    writeln!(buf, "layout:").unwrap();
+    writeln!(buf, "{:#?}", niri.layout).unwrap();
+
+    buf
+}
+
+/// Replaces the current process with a fresh niri with the same arguments.
+///
+/// Only returns if that was not possible.
+fn restart_after_crash() {
+    let count = env::var(RESTART_COUNT_ENV)
+        .ok()
+        .and_then(|x| x.parse::<u32>().ok())
+        .unwrap_or(0);
+    if count >= MAX_RESTARTS {
+        error!("niri crashed {count} times after restarting, not restarting again");
+        return;
+    }
+
+    let exe = match env::current_exe() {
+        Ok(exe) => exe,
+        Err(err) => {
+            error!("error getting the niri executable path: {err:?}");
+            return;
+        }
+    };
+
+    info!("restarting niri after the crash");
+    let err = Command::new(exe)
+        .args(env::args_os().skip(1))
+        .env(RESTART_COUNT_ENV, (count + 1).to_string())
+        .exec();
+    error!("error restarting niri: {err:?}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn input_history_folds_repeats() {
+        let mut history = InputHistory::default();
+        let t = Duration::from_millis;
+        history.push_at(t(1), "PointerMotion");
+        history.push_at(t(2), "PointerMotion");
+        history.push_at(t(3), "PointerButton");
+        history.push_at(t(4), "PointerMotion");
+
+        let entries: Vec<_> = history.entries.iter().map(|e| (e.kind, e.count)).collect();
+        assert_eq!(
+            entries,
+            [
+                ("PointerMotion", 2),
+                ("PointerButton", 1),
+                ("PointerMotion", 1)
+            ]
+        );
+        assert_eq!(history.entries[0].time, t(2));
+    }
+
+    #[test]
+    fn input_history_is_bounded() {
+        let mut history = InputHistory::default();
+        for i in 0..INPUT_HISTORY_LEN * 2 {
+            let kind = if i % 2 == 0 {
+                "Keyboard"
+            } else {
+                "PointerButton"
+            };
+            history.push_at(Duration::from_millis(i as u64), kind);
+        }
+        assert_eq!(history.entries.len(), INPUT_HISTORY_LEN);
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use zbus::fdo::{self, RequestNameFlags};
+use zbus::interface;
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::OwnedValue;
+
+use super::Start;
+use crate::utils::version;
+
+/// Minimal notification daemon showing the notifications as popups.
+pub struct Notifications {
+    to_niri: calloop::channel::Sender<NotificationsToNiri>,
+    counter: u32,
+}
+
+pub enum NotificationsToNiri {
+    Notify {
+        id: u32,
+        summary: String,
+        body: String,
+        is_critical: bool,
+        /// Timeout in milliseconds, -1 for the default one, 0 to never time out.
+        expire_timeout: i32,
+    },
+    Close(u32),
+}
+
+#[interface(name = "org.freedesktop.Notifications")]
+impl Notifications {
+    async fn get_capabilities(&self) -> Vec<String> {
+        vec![String::from("body")]
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn notify(
+        &mut self,
+        app_name: &str,
+        replaces_id: u32,
+        _app_icon: &str,
+        summary: String,
+        body: String,
+        _actions: Vec<String>,
+        hints: HashMap<String, OwnedValue>,
+        expire_timeout: i32,
+    ) -> fdo::Result<u32> {
+        trace!("notify from {app_name}: {summary}");
+
+        let id = if replaces_id != 0 {
+            replaces_id
+        } else {
+            // Start from 1 because 0 means no notification to replace.
+            self.counter = self.counter.wrapping_add(1);
+            if self.counter == 0 {
+                self.counter += 1;
+            }
+            self.counter
+        };
+
+        let urgency = hints.get("urgency").and_then(|v| u8::try_from(v).ok());
+        let msg = NotificationsToNiri::Notify {
+            id,
+            summary,
+            body,
+            is_critical: urgency == Some(2),
+            expire_timeout,
+        };
+        if let Err(err) = self.to_niri.send(msg) {
+            warn!("error sending message to niri: {err:?}");
+            return Err(fdo::Error::Failed("internal error".to_owned()));
+        }
+
+        Ok(id)
+    }
+
+    async fn close_notification(&self, id: u32) -> fdo::Result<()> {
+        if let Err(err) = self.to_niri.send(NotificationsToNiri::Close(id)) {
+            warn!("error sending message to niri: {err:?}");
+            return Err(fdo::Error::Failed("internal error".to_owned()));
+        }
+
+        Ok(())
+    }
+
+    async fn get_server_information(&self) -> (String, String, String, String) {
+        (
+            String::from("niri"),
+            String::from("niri"),
+            version(),
+            String::from("1.2"),
+        )
+    }
+
+    #[zbus(signal)]
+    pub async fn notification_closed(
+        ctxt: &SignalEmitter<'_>,
+        id: u32,
+        reason: u32,
+    ) -> zbus::Result<()>;
+}
+
+impl Notifications {
+    pub fn new(to_niri: calloop::channel::Sender<NotificationsToNiri>) -> Self {
+        Self {
+            to_niri,
+            counter: 0,
+        }
+    }
+}
+
+impl Start for Notifications {
+    fn start(self) -> anyhow::Result<zbus::blocking::Connection> {
+        let conn = zbus::blocking::Connection::session()?;
+        // Don't replace an already running notification daemon.
+        let flags = RequestNameFlags::DoNotQueue;
+
+        conn.object_server()
+            .at("/org/freedesktop/Notifications", self)?;
+        conn.request_name_with_flags("org.freedesktop.Notifications", flags)?;
+
+        Ok(conn)
+    }
+}
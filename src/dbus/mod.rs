@@ -2,18 +2,25 @@ use zbus::blocking::Connection;
 use zbus::object_server::Interface;
 
 use crate::niri::State;
+use crate::power_saving::watch_power_status;
 
+pub mod freedesktop_notifications;
 pub mod freedesktop_screensaver;
 pub mod gnome_shell_introspect;
 pub mod gnome_shell_screenshot;
 pub mod mutter_display_config;
 pub mod mutter_service_channel;
 
+#[cfg(feature = "xdp-gnome-screencast")]
+pub mod mutter_remote_desktop;
 #[cfg(feature = "xdp-gnome-screencast")]
 pub mod mutter_screen_cast;
 #[cfg(feature = "xdp-gnome-screencast")]
+use mutter_remote_desktop::{LinkedScreenCasts, RemoteDesktop};
+#[cfg(feature = "xdp-gnome-screencast")]
 use mutter_screen_cast::ScreenCast;
 
+use self::freedesktop_notifications::Notifications;
 use self::freedesktop_screensaver::ScreenSaver;
 use self::gnome_shell_introspect::Introspect;
 use self::mutter_display_config::DisplayConfig;
@@ -30,8 +37,11 @@ pub struct DBusServers {
     pub conn_screen_saver: Option<Connection>,
     pub conn_screen_shot: Option<Connection>,
     pub conn_introspect: Option<Connection>,
+    pub conn_notifications: Option<Connection>,
     #[cfg(feature = "xdp-gnome-screencast")]
     pub conn_screen_cast: Option<Connection>,
+    #[cfg(feature = "xdp-gnome-screencast")]
+    pub conn_remote_desktop: Option<Connection>,
 }
 
 impl DBusServers {
@@ -56,6 +66,15 @@ impl DBusServers {
                 })
                 .unwrap();
             dbus.conn_service_channel = try_start(service_channel);
+
+            let (to_niri, from_power_status) = calloop::channel::channel();
+            niri.event_loop
+                .insert_source(from_power_status, move |event, _, state| match event {
+                    calloop::channel::Event::Msg(change) => state.on_power_status_change(change),
+                    calloop::channel::Event::Closed => (),
+                })
+                .unwrap();
+            watch_power_status(to_niri);
         }
 
         if is_session_instance || config.debug.dbus_interfaces_in_non_session_instances {
@@ -109,6 +128,18 @@ impl DBusServers {
             let introspect = Introspect::new(to_niri, from_niri);
             dbus.conn_introspect = try_start(introspect);
 
+            if config.notifications.on {
+                let (to_niri, from_notifications) = calloop::channel::channel();
+                niri.event_loop
+                    .insert_source(from_notifications, move |event, _, state| match event {
+                        calloop::channel::Event::Msg(msg) => state.on_notifications_msg(msg),
+                        calloop::channel::Event::Closed => (),
+                    })
+                    .unwrap();
+                let notifications = Notifications::new(to_niri);
+                dbus.conn_notifications = try_start(notifications);
+            }
+
             #[cfg(feature = "xdp-gnome-screencast")]
             {
                 let (to_niri, from_screen_cast) = calloop::channel::channel();
@@ -120,8 +151,22 @@ impl DBusServers {
                         }
                     })
                     .unwrap();
-                let screen_cast = ScreenCast::new(backend.ipc_outputs(), to_niri);
+                let linked_screen_casts = LinkedScreenCasts::default();
+                let screen_cast =
+                    ScreenCast::new(backend.ipc_outputs(), to_niri, linked_screen_casts.clone());
                 dbus.conn_screen_cast = try_start(screen_cast);
+
+                let (to_niri, from_remote_desktop) = calloop::channel::channel();
+                niri.event_loop
+                    .insert_source(from_remote_desktop, {
+                        move |event, _, state| match event {
+                            calloop::channel::Event::Msg(msg) => state.on_remote_desktop_msg(msg),
+                            calloop::channel::Event::Closed => (),
+                        }
+                    })
+                    .unwrap();
+                let remote_desktop = RemoteDesktop::new(to_niri, linked_screen_casts);
+                dbus.conn_remote_desktop = try_start(remote_desktop);
             }
         }
 
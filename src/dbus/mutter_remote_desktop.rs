@@ -0,0 +1,627 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use anyhow::Context;
+use futures_util::StreamExt;
+use smithay::backend::input::{Axis, AxisSource};
+use zbus::fdo::{self, DBusProxy, RequestNameFlags};
+use zbus::message::Header;
+use zbus::names::{BusName, OwnedUniqueName, UniqueName};
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{NoneValue, OwnedObjectPath};
+use zbus::{interface, Connection, ObjectServer, Task};
+
+use super::mutter_screen_cast::{self, StreamTargetId};
+use super::Start;
+
+/// Keyboard device type bit in `SupportedDeviceTypes`.
+const DEVICE_TYPE_KEYBOARD: u32 = 1;
+/// Pointer device type bit in `SupportedDeviceTypes`.
+const DEVICE_TYPE_POINTER: u32 = 2;
+
+/// Bits of the `NotifyPointerAxis` flags.
+const AXIS_FLAG_FINISH: u32 = 1;
+const AXIS_FLAG_SOURCE_WHEEL: u32 = 1 << 1;
+const AXIS_FLAG_SOURCE_FINGER: u32 = 1 << 2;
+
+/// Bus names of the clients allowed to create remote desktop sessions.
+///
+/// The portal asks the user before starting a session, so it's the only client that may do it.
+const ALLOWED_BUS_NAMES: &[&str] = &["org.freedesktop.impl.portal.desktop.gnome"];
+
+/// Screencast sessions linked to remote desktop sessions, by the remote desktop session id.
+pub type LinkedScreenCasts = Arc<Mutex<HashMap<String, Option<mutter_screen_cast::Session>>>>;
+
+/// Owners of the running sessions, by the session object path.
+type SessionOwners = Arc<Mutex<HashMap<String, OwnedUniqueName>>>;
+
+pub struct RemoteDesktop {
+    to_niri: calloop::channel::Sender<RemoteDesktopToNiri>,
+    linked_screen_casts: LinkedScreenCasts,
+    session_owners: SessionOwners,
+    monitor_task: Arc<OnceLock<Task<()>>>,
+}
+
+pub struct Session {
+    id: String,
+    /// Client that created the session, the only one allowed to use it.
+    owner: OwnedUniqueName,
+    /// Devices allowed by the security policy.
+    permissions: DevicePermissions,
+    to_niri: calloop::channel::Sender<RemoteDesktopToNiri>,
+    linked_screen_casts: LinkedScreenCasts,
+    session_owners: SessionOwners,
+    started: AtomicBool,
+    stopped: AtomicBool,
+    /// Keys and buttons held by this session, to release them when it stops.
+    pressed_keycodes: Mutex<HashSet<u32>>,
+    pressed_buttons: Mutex<HashSet<u32>>,
+}
+
+/// Virtual devices that a remote desktop client may use.
+#[derive(Debug, Clone, Copy)]
+pub struct DevicePermissions {
+    pub keyboard: bool,
+    pub pointer: bool,
+}
+
+pub enum RemoteDesktopToNiri {
+    /// Checks the security policy for the portal process.
+    CheckPermissions {
+        pid: Option<i32>,
+        reply: async_channel::Sender<DevicePermissions>,
+    },
+    Keycode {
+        /// Evdev keycode.
+        keycode: u32,
+        pressed: bool,
+    },
+    Keysym {
+        keysym: u32,
+        pressed: bool,
+        /// Evdev keycode that the keysym resolved to, if any key produces it.
+        reply: async_channel::Sender<Option<u32>>,
+    },
+    PointerMotion {
+        dx: f64,
+        dy: f64,
+    },
+    PointerMotionAbsolute {
+        target: StreamTargetId,
+        /// Position within the stream in logical coordinates.
+        x: f64,
+        y: f64,
+    },
+    PointerButton {
+        /// Evdev button code.
+        button: u32,
+        pressed: bool,
+    },
+    PointerAxis {
+        dx: f64,
+        dy: f64,
+        source: AxisSource,
+        finish: bool,
+    },
+    PointerAxisDiscrete {
+        axis: Axis,
+        steps: i32,
+    },
+}
+
+#[interface(name = "org.gnome.Mutter.RemoteDesktop")]
+impl RemoteDesktop {
+    async fn create_session(
+        &self,
+        #[zbus(header)] hdr: Header<'_>,
+        #[zbus(connection)] conn: &Connection,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> fdo::Result<OwnedObjectPath> {
+        let owner = check_allowed_sender(conn, &hdr).await?;
+        let permissions = self.check_permissions(conn, &owner).await?;
+        if !permissions.keyboard && !permissions.pointer {
+            return Err(fdo::Error::AccessDenied(
+                "denied by the security policy".to_owned(),
+            ));
+        }
+
+        static NUMBER: AtomicUsize = AtomicUsize::new(0);
+        let number = NUMBER.fetch_add(1, Ordering::SeqCst);
+        let path = format!("/org/gnome/Mutter/RemoteDesktop/Session/u{number}");
+        let path = OwnedObjectPath::try_from(path).unwrap();
+
+        let id = format!("niri-remote-desktop-{number}");
+        let session = Session {
+            id: id.clone(),
+            owner,
+            permissions,
+            to_niri: self.to_niri.clone(),
+            linked_screen_casts: self.linked_screen_casts.clone(),
+            session_owners: self.session_owners.clone(),
+            started: AtomicBool::new(false),
+            stopped: AtomicBool::new(false),
+            pressed_keycodes: Mutex::new(HashSet::new()),
+            pressed_buttons: Mutex::new(HashSet::new()),
+        };
+
+        let owner = session.owner.clone();
+        match server.at(&path, session).await {
+            Ok(true) => {
+                self.linked_screen_casts.lock().unwrap().insert(id, None);
+                let mut owners = self.session_owners.lock().unwrap();
+                owners.insert(path.as_str().to_owned(), owner);
+            }
+            Ok(false) => return Err(fdo::Error::Failed("session path already exists".to_owned())),
+            Err(err) => {
+                return Err(fdo::Error::Failed(format!(
+                    "error creating session object: {err:?}"
+                )))
+            }
+        }
+
+        Ok(path)
+    }
+
+    // Reports all device types, since the permissions depend on the client.
+    #[zbus(property)]
+    async fn supported_device_types(&self) -> u32 {
+        DEVICE_TYPE_KEYBOARD | DEVICE_TYPE_POINTER
+    }
+
+    #[zbus(property)]
+    async fn version(&self) -> i32 {
+        1
+    }
+}
+
+#[interface(name = "org.gnome.Mutter.RemoteDesktop.Session")]
+impl Session {
+    async fn start(&self, #[zbus(header)] hdr: Header<'_>) -> fdo::Result<()> {
+        debug!("start");
+        self.check_owner(&hdr)?;
+
+        if self.started.swap(true, Ordering::SeqCst) {
+            return Err(fdo::Error::Failed("session already started".to_owned()));
+        }
+
+        let screen_cast = self.linked_screen_cast();
+        if let Some(screen_cast) = screen_cast {
+            screen_cast.start_streams();
+        }
+
+        Ok(())
+    }
+
+    async fn stop(
+        &self,
+        #[zbus(header)] hdr: Header<'_>,
+        #[zbus(object_server)] server: &ObjectServer,
+        #[zbus(signal_context)] ctxt: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        debug!("stop");
+        self.check_owner(&hdr)?;
+        self.stop_session(server, &ctxt).await;
+        Ok(())
+    }
+
+    #[zbus(property)]
+    async fn session_id(&self) -> String {
+        self.id.clone()
+    }
+
+    async fn notify_keyboard_keycode(
+        &self,
+        #[zbus(header)] hdr: Header<'_>,
+        keycode: u32,
+        state: bool,
+    ) -> fdo::Result<()> {
+        self.check_keyboard(&hdr)?;
+        track(&self.pressed_keycodes, keycode, state);
+        self.send(RemoteDesktopToNiri::Keycode {
+            keycode,
+            pressed: state,
+        });
+        Ok(())
+    }
+
+    async fn notify_keyboard_keysym(
+        &self,
+        #[zbus(header)] hdr: Header<'_>,
+        keysym: u32,
+        state: bool,
+    ) -> fdo::Result<()> {
+        self.check_keyboard(&hdr)?;
+
+        // Niri resolves the keysym against the current layout, so track the key that it pressed.
+        let (tx, rx) = async_channel::bounded(1);
+        self.send(RemoteDesktopToNiri::Keysym {
+            keysym,
+            pressed: state,
+            reply: tx,
+        });
+        let keycode = rx.recv().await.map_err(|err| {
+            warn!("error receiving message from niri: {err:?}");
+            fdo::Error::Failed("internal error".to_owned())
+        })?;
+        if let Some(keycode) = keycode {
+            track(&self.pressed_keycodes, keycode, state);
+        }
+
+        Ok(())
+    }
+
+    async fn notify_pointer_button(
+        &self,
+        #[zbus(header)] hdr: Header<'_>,
+        button: i32,
+        state: bool,
+    ) -> fdo::Result<()> {
+        self.check_pointer(&hdr)?;
+        let button = u32::try_from(button)
+            .map_err(|_| fdo::Error::InvalidArgs("invalid button".to_owned()))?;
+        track(&self.pressed_buttons, button, state);
+        self.send(RemoteDesktopToNiri::PointerButton {
+            button,
+            pressed: state,
+        });
+        Ok(())
+    }
+
+    async fn notify_pointer_axis(
+        &self,
+        #[zbus(header)] hdr: Header<'_>,
+        dx: f64,
+        dy: f64,
+        flags: u32,
+    ) -> fdo::Result<()> {
+        self.check_pointer(&hdr)?;
+        let source = if flags & AXIS_FLAG_SOURCE_WHEEL != 0 {
+            AxisSource::Wheel
+        } else if flags & AXIS_FLAG_SOURCE_FINGER != 0 {
+            AxisSource::Finger
+        } else {
+            AxisSource::Continuous
+        };
+        self.send(RemoteDesktopToNiri::PointerAxis {
+            dx,
+            dy,
+            source,
+            finish: flags & AXIS_FLAG_FINISH != 0,
+        });
+        Ok(())
+    }
+
+    async fn notify_pointer_axis_discrete(
+        &self,
+        #[zbus(header)] hdr: Header<'_>,
+        axis: u32,
+        steps: i32,
+    ) -> fdo::Result<()> {
+        self.check_pointer(&hdr)?;
+        let axis = match axis {
+            0 => Axis::Vertical,
+            1 => Axis::Horizontal,
+            _ => return Err(fdo::Error::InvalidArgs("invalid axis".to_owned())),
+        };
+        self.send(RemoteDesktopToNiri::PointerAxisDiscrete { axis, steps });
+        Ok(())
+    }
+
+    async fn notify_pointer_motion_relative(
+        &self,
+        #[zbus(header)] hdr: Header<'_>,
+        dx: f64,
+        dy: f64,
+    ) -> fdo::Result<()> {
+        self.check_pointer(&hdr)?;
+        self.send(RemoteDesktopToNiri::PointerMotion { dx, dy });
+        Ok(())
+    }
+
+    async fn notify_pointer_motion_absolute(
+        &self,
+        #[zbus(header)] hdr: Header<'_>,
+        stream: &str,
+        x: f64,
+        y: f64,
+    ) -> fdo::Result<()> {
+        self.check_pointer(&hdr)?;
+
+        let screen_cast = self.linked_screen_cast();
+        let target = screen_cast.and_then(|screen_cast| screen_cast.stream_target(stream));
+        let Some(target) = target else {
+            return Err(fdo::Error::InvalidArgs("no such stream".to_owned()));
+        };
+
+        self.send(RemoteDesktopToNiri::PointerMotionAbsolute { target, x, y });
+        Ok(())
+    }
+
+    async fn notify_touch_down(
+        &self,
+        _stream: &str,
+        _slot: u32,
+        _x: f64,
+        _y: f64,
+    ) -> fdo::Result<()> {
+        Err(fdo::Error::NotSupported(
+            "touch is not supported".to_owned(),
+        ))
+    }
+
+    async fn notify_touch_motion(
+        &self,
+        _stream: &str,
+        _slot: u32,
+        _x: f64,
+        _y: f64,
+    ) -> fdo::Result<()> {
+        Err(fdo::Error::NotSupported(
+            "touch is not supported".to_owned(),
+        ))
+    }
+
+    async fn notify_touch_up(&self, _slot: u32) -> fdo::Result<()> {
+        Err(fdo::Error::NotSupported(
+            "touch is not supported".to_owned(),
+        ))
+    }
+
+    #[zbus(signal)]
+    async fn closed(ctxt: &SignalEmitter<'_>) -> zbus::Result<()>;
+}
+
+impl RemoteDesktop {
+    pub fn new(
+        to_niri: calloop::channel::Sender<RemoteDesktopToNiri>,
+        linked_screen_casts: LinkedScreenCasts,
+    ) -> Self {
+        Self {
+            to_niri,
+            linked_screen_casts,
+            session_owners: Arc::new(Mutex::new(HashMap::new())),
+            monitor_task: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// Asks niri to check the security policy for the client.
+    ///
+    /// The client is always the portal, so this checks the portal process rather than the app
+    /// that asked the portal for the session.
+    async fn check_permissions(
+        &self,
+        conn: &Connection,
+        owner: &OwnedUniqueName,
+    ) -> fdo::Result<DevicePermissions> {
+        let dbus = DBusProxy::new(conn).await?;
+        let pid = match dbus
+            .get_connection_unix_process_id(BusName::Unique(owner.inner().clone()))
+            .await
+        {
+            Ok(pid) => i32::try_from(pid).ok(),
+            Err(err) => {
+                warn!("error getting the pid of {owner}: {err:?}");
+                None
+            }
+        };
+
+        let (tx, rx) = async_channel::bounded(1);
+        if let Err(err) = self
+            .to_niri
+            .send(RemoteDesktopToNiri::CheckPermissions { pid, reply: tx })
+        {
+            warn!("error sending message to niri: {err:?}");
+            return Err(fdo::Error::Failed("internal error".to_owned()));
+        }
+
+        rx.recv().await.map_err(|err| {
+            warn!("error receiving message from niri: {err:?}");
+            fdo::Error::Failed("internal error".to_owned())
+        })
+    }
+}
+
+/// Checks that the message comes from one of the allowed clients, returning its unique name.
+async fn check_allowed_sender(conn: &Connection, hdr: &Header<'_>) -> fdo::Result<OwnedUniqueName> {
+    let Some(sender) = hdr.sender() else {
+        return Err(fdo::Error::AccessDenied("no sender".to_owned()));
+    };
+
+    let dbus = DBusProxy::new(conn).await?;
+    for name in ALLOWED_BUS_NAMES {
+        let name = BusName::try_from(*name).unwrap();
+        if let Ok(owner) = dbus.get_name_owner(name).await {
+            if owner.as_str() == sender.as_str() {
+                return Ok(owner);
+            }
+        }
+    }
+
+    warn!("denying remote desktop session for {sender}, which is not the portal");
+    Err(fdo::Error::AccessDenied(
+        "only the portal can create remote desktop sessions".to_owned(),
+    ))
+}
+
+/// Stops the sessions of clients that disconnect without stopping them, for example when the
+/// portal crashes.
+async fn monitor_disappeared_owners(
+    conn: &Connection,
+    session_owners: SessionOwners,
+) -> anyhow::Result<()> {
+    let proxy = DBusProxy::new(conn)
+        .await
+        .context("error creating a DBusProxy")?;
+
+    let mut stream = proxy
+        .receive_name_owner_changed_with_args(&[(2, UniqueName::null_value())])
+        .await
+        .context("error creating a NameOwnerChanged stream")?;
+
+    while let Some(signal) = stream.next().await {
+        let args = signal
+            .args()
+            .context("error retrieving NameOwnerChanged args")?;
+
+        let Some(name) = &**args.old_owner() else {
+            continue;
+        };
+
+        if args.new_owner().is_some() {
+            error!("non-null new_owner should've been filtered out");
+            continue;
+        }
+
+        let paths: Vec<String> = session_owners
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|&(_, owner)| owner == name)
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        for path in paths {
+            debug!("remote desktop session owner disappeared: {name}");
+
+            let server = conn.object_server();
+            let Ok(iface) = server.interface::<_, Session>(path.as_str()).await else {
+                continue;
+            };
+            iface
+                .get()
+                .await
+                .stop_session(server, iface.signal_emitter())
+                .await;
+        }
+    }
+
+    Ok(())
+}
+
+impl Start for RemoteDesktop {
+    fn start(self) -> anyhow::Result<zbus::blocking::Connection> {
+        let session_owners = self.session_owners.clone();
+        let monitor_task = self.monitor_task.clone();
+
+        let conn = zbus::blocking::Connection::session()?;
+        let flags = RequestNameFlags::AllowReplacement
+            | RequestNameFlags::ReplaceExisting
+            | RequestNameFlags::DoNotQueue;
+
+        conn.object_server()
+            .at("/org/gnome/Mutter/RemoteDesktop", self)?;
+        conn.request_name_with_flags("org.gnome.Mutter.RemoteDesktop", flags)?;
+
+        let async_conn = conn.inner();
+        let future = {
+            let conn = async_conn.clone();
+            async move {
+                if let Err(err) = monitor_disappeared_owners(&conn, session_owners).await {
+                    warn!("error monitoring org.gnome.Mutter.RemoteDesktop clients: {err:?}");
+                }
+            }
+        };
+        let task = async_conn
+            .executor()
+            .spawn(future, "monitor disappearing clients");
+        monitor_task.set(task).unwrap();
+
+        Ok(conn)
+    }
+}
+
+impl Session {
+    /// Stops the session, releasing anything that it left held down.
+    async fn stop_session(&self, server: &ObjectServer, ctxt: &SignalEmitter<'_>) {
+        if self.stopped.swap(true, Ordering::SeqCst) {
+            // Already stopped.
+            return;
+        }
+
+        Session::closed(ctxt).await.unwrap();
+
+        // Don't leave anything held down.
+        for keycode in self.pressed_keycodes.lock().unwrap().drain() {
+            self.send(RemoteDesktopToNiri::Keycode {
+                keycode,
+                pressed: false,
+            });
+        }
+        for button in self.pressed_buttons.lock().unwrap().drain() {
+            self.send(RemoteDesktopToNiri::PointerButton {
+                button,
+                pressed: false,
+            });
+        }
+
+        let screen_cast = self.linked_screen_casts.lock().unwrap().remove(&self.id);
+        if let Some(Some(screen_cast)) = screen_cast {
+            screen_cast.stop_casts();
+        }
+
+        let path = ctxt.path();
+        self.session_owners.lock().unwrap().remove(path.as_str());
+        server.remove::<Session, _>(path).await.unwrap();
+    }
+
+    fn check_owner(&self, hdr: &Header<'_>) -> fdo::Result<()> {
+        let is_owner = hdr
+            .sender()
+            .is_some_and(|sender| sender.as_str() == self.owner.as_str());
+        if !is_owner {
+            return Err(fdo::Error::AccessDenied(
+                "session belongs to another client".to_owned(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_running(&self, hdr: &Header<'_>) -> fdo::Result<()> {
+        self.check_owner(hdr)?;
+        if !self.started.load(Ordering::SeqCst) || self.stopped.load(Ordering::SeqCst) {
+            return Err(fdo::Error::Failed("session is not running".to_owned()));
+        }
+        Ok(())
+    }
+
+    fn check_keyboard(&self, hdr: &Header<'_>) -> fdo::Result<()> {
+        self.check_running(hdr)?;
+        if !self.permissions.keyboard {
+            return Err(fdo::Error::AccessDenied(
+                "virtual keyboard denied by the security policy".to_owned(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_pointer(&self, hdr: &Header<'_>) -> fdo::Result<()> {
+        self.check_running(hdr)?;
+        if !self.permissions.pointer {
+            return Err(fdo::Error::AccessDenied(
+                "virtual pointer denied by the security policy".to_owned(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn linked_screen_cast(&self) -> Option<mutter_screen_cast::Session> {
+        let linked = self.linked_screen_casts.lock().unwrap();
+        linked.get(&self.id).cloned().flatten()
+    }
+
+    fn send(&self, msg: RemoteDesktopToNiri) {
+        if let Err(err) = self.to_niri.send(msg) {
+            warn!("error sending message to niri: {err:?}");
+        }
+    }
+}
+
+fn track(pressed: &Mutex<HashSet<u32>>, code: u32, state: bool) {
+    let mut pressed = pressed.lock().unwrap();
+    if state {
+        pressed.insert(code);
+    } else {
+        pressed.remove(&code);
+    }
+}
@@ -9,6 +9,7 @@ use zbus::object_server::{InterfaceRef, SignalEmitter};
 use zbus::zvariant::{DeserializeDict, OwnedObjectPath, SerializeDict, Type, Value};
 use zbus::{fdo, interface, ObjectServer};
 
+use super::mutter_remote_desktop::LinkedScreenCasts;
 use super::Start;
 use crate::backend::IpcOutputMap;
 
@@ -18,6 +19,7 @@ pub struct ScreenCast {
     to_niri: calloop::channel::Sender<ScreenCastToNiri>,
     #[allow(clippy::type_complexity)]
     sessions: Arc<Mutex<Vec<(Session, InterfaceRef<Session>)>>>,
+    linked_screen_casts: LinkedScreenCasts,
 }
 
 #[derive(Clone)]
@@ -112,11 +114,17 @@ impl ScreenCast {
         #[zbus(object_server)] server: &ObjectServer,
         properties: HashMap<&str, Value<'_>>,
     ) -> fdo::Result<OwnedObjectPath> {
-        if properties.contains_key("remote-desktop-session-id") {
-            return Err(fdo::Error::Failed(
-                "there are no remote desktop sessions".to_owned(),
-            ));
-        }
+        let remote_desktop_session_id = match properties.get("remote-desktop-session-id") {
+            Some(value) => match <&str>::try_from(value) {
+                Ok(id) => Some(id.to_owned()),
+                Err(_) => {
+                    return Err(fdo::Error::InvalidArgs(
+                        "invalid remote desktop session id".to_owned(),
+                    ))
+                }
+            },
+            None => None,
+        };
 
         static NUMBER: AtomicUsize = AtomicUsize::new(0);
         let session_id = NUMBER.fetch_add(1, Ordering::SeqCst);
@@ -127,7 +135,7 @@ impl ScreenCast {
         match server.at(&path, session.clone()).await {
             Ok(true) => {
                 let iface = server.interface(&path).await.unwrap();
-                self.sessions.lock().unwrap().push((session, iface));
+                self.sessions.lock().unwrap().push((session.clone(), iface));
             }
             Ok(false) => return Err(fdo::Error::Failed("session path already exists".to_owned())),
             Err(err) => {
@@ -137,6 +145,25 @@ impl ScreenCast {
             }
         }
 
+        // Remote desktop sessions start and stop their screencast sessions, and use their streams
+        // for absolute pointer motion.
+        if let Some(id) = remote_desktop_session_id {
+            let mut linked = self.linked_screen_casts.lock().unwrap();
+            match linked.get_mut(&id) {
+                Some(linked @ None) => *linked = Some(session),
+                Some(Some(_)) => {
+                    return Err(fdo::Error::Failed(
+                        "remote desktop session already has a screencast session".to_owned(),
+                    ))
+                }
+                None => {
+                    return Err(fdo::Error::Failed(
+                        "no such remote desktop session".to_owned(),
+                    ))
+                }
+            }
+        }
+
         Ok(path)
     }
 
@@ -151,9 +178,7 @@ impl Session {
     async fn start(&self) {
         debug!("start");
 
-        for (stream, iface) in &*self.streams.lock().unwrap() {
-            stream.start(iface.signal_emitter().clone());
-        }
+        self.start_streams();
     }
 
     pub async fn stop(
@@ -311,11 +336,13 @@ impl ScreenCast {
     pub fn new(
         ipc_outputs: Arc<Mutex<IpcOutputMap>>,
         to_niri: calloop::channel::Sender<ScreenCastToNiri>,
+        linked_screen_casts: LinkedScreenCasts,
     ) -> Self {
         Self {
             ipc_outputs,
             to_niri,
             sessions: Arc::new(Mutex::new(vec![])),
+            linked_screen_casts,
         }
     }
 }
@@ -349,6 +376,34 @@ impl Session {
             stopped: Arc::new(AtomicBool::new(false)),
         }
     }
+
+    pub fn start_streams(&self) {
+        for (stream, iface) in &*self.streams.lock().unwrap() {
+            stream.start(iface.signal_emitter().clone());
+        }
+    }
+
+    /// Stops the casts of this session, for when its remote desktop session stops.
+    pub fn stop_casts(&self) {
+        if self.stopped.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        if let Err(err) = self.to_niri.send(ScreenCastToNiri::StopCast {
+            session_id: self.id,
+        }) {
+            warn!("error sending StopCast to niri: {err:?}");
+        }
+    }
+
+    /// Returns the target of the stream with this object path.
+    pub fn stream_target(&self, path: &str) -> Option<StreamTargetId> {
+        let streams = self.streams.lock().unwrap();
+        streams
+            .iter()
+            .find(|(_, iface)| iface.signal_emitter().path().as_str() == path)
+            .map(|(stream, _)| stream.target.make_id())
+    }
 }
 
 impl Drop for Session {
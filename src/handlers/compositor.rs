@@ -4,6 +4,7 @@ use niri_ipc::PositionChange;
 use smithay::backend::renderer::utils::on_commit_buffer_handler;
 use smithay::input::pointer::{CursorImageStatus, CursorImageSurfaceData};
 use smithay::reexports::calloop::Interest;
+use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel;
 use smithay::reexports::wayland_server::protocol::wl_buffer;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::reexports::wayland_server::{Client, Resource};
@@ -20,8 +21,8 @@ use smithay::{delegate_compositor, delegate_shm};
 
 use super::xdg_shell::add_mapped_toplevel_pre_commit_hook;
 use crate::handlers::XDG_ACTIVATION_TOKEN_TIMEOUT;
-use crate::layout::{ActivateWindow, AddWindowTarget};
-use crate::niri::{CastTarget, ClientState, LockState, State};
+use crate::layout::{ActivateWindow, AddWindowTarget, LayoutElement};
+use crate::niri::{BackdropWindow, CastTarget, ClientState, LockState, State};
 use crate::utils::transaction::Transaction;
 use crate::utils::{is_mapped, send_scale_transform};
 use crate::window::{InitialConfigureState, Mapped, ResolvedWindowRules, Unmapped};
@@ -118,6 +119,17 @@ impl CompositorHandler for State {
                             (ResolvedWindowRules::empty(), None, None, false, None, None)
                         };
 
+                    if rules.open_as_backdrop == Some(true) {
+                        let output = output.or_else(|| self.niri.layout.active_output().cloned());
+                        let backdrop = BackdropWindow::new(window, output.clone());
+                        self.niri.backdrop_windows.push(backdrop);
+
+                        if let Some(output) = output {
+                            self.niri.queue_redraw(&output);
+                        }
+                        return;
+                    }
+
                     // The GTK about dialog sets min/max size after the initial configure but
                     // before mapping, so we need to compute open_floating at the last possible
                     // moment, that is here.
@@ -168,9 +180,23 @@ impl CompositorHandler for State {
                     // The mapped pre-commit hook deals with dma-bufs on its own.
                     self.remove_default_dmabuf_pre_commit_hook(toplevel.wl_surface());
                     let hook = add_mapped_toplevel_pre_commit_hook(toplevel);
-                    let mapped = Mapped::new(window, rules, hook);
+                    let force_windowed = rules.force_windowed_fullscreen == Some(true);
+                    let mut mapped = Mapped::new(window, rules, hook);
                     let window = mapped.window.clone();
 
+                    // The client may have stopped responding before opening this window.
+                    let client = toplevel.wl_surface().client();
+                    if client.is_some_and(|client| self.niri.is_client_unresponsive(&client.id())) {
+                        mapped.set_unresponsive(true);
+                    }
+
+                    // The initial configure told the window that it's fullscreen, keep it that
+                    // way while opening it as a regular tile.
+                    if force_windowed && mapped.is_pending_fullscreen() {
+                        mapped.request_windowed_fullscreen(true);
+                    }
+                    self.niri.run_window_open_hooks(&mapped);
+
                     let target = if let Some(p) = &parent {
                         // Open dialogs next to their parent window.
                         AddWindowTarget::NextTo(p)
@@ -216,6 +242,29 @@ impl CompositorHandler for State {
             }
 
             // This is a commit of a previously-mapped root or a non-toplevel root.
+            if let Some(idx) = self.niri.backdrop_window_idx(surface) {
+                let backdrop = &self.niri.backdrop_windows[idx];
+                let window = backdrop.window.clone();
+                let output = backdrop.output.clone();
+
+                window.on_commit();
+
+                if !is_mapped(surface) {
+                    // The backdrop window got unmapped.
+                    self.niri.backdrop_windows.remove(idx);
+
+                    // Newly-unmapped toplevels must perform the initial commit-configure sequence
+                    // afresh.
+                    let unmapped = Unmapped::new(window);
+                    self.niri.unmapped_windows.insert(surface.clone(), unmapped);
+                }
+
+                if let Some(output) = output {
+                    self.niri.queue_redraw(&output);
+                }
+                return;
+            }
+
             if let Some((mapped, output)) = self.niri.layout.find_window_and_output(surface) {
                 let window = mapped.window.clone();
                 let output = output.cloned();
@@ -295,6 +344,16 @@ impl CompositorHandler for State {
                 // The toplevel remains mapped.
                 self.niri.layout.update_window(&window, serial);
 
+                // A window woken up for a screenshot has drawn again.
+                let toplevel = window.toplevel().expect("no X11 support");
+                if !toplevel
+                    .current_state()
+                    .states
+                    .contains(xdg_toplevel::State::Suspended)
+                {
+                    self.take_pending_window_screenshot(surface);
+                }
+
                 // Move the toplevel according to the attach offset.
                 if let Some(delta) = buffer_delta {
                     if delta.x != 0 || delta.y != 0 {
@@ -321,6 +380,15 @@ impl CompositorHandler for State {
         }
 
         // This is a commit of a non-root or a non-toplevel root.
+        if let Some(idx) = self.niri.backdrop_window_idx(&root_surface) {
+            let backdrop = &self.niri.backdrop_windows[idx];
+            backdrop.window.on_commit();
+            if let Some(output) = backdrop.output.clone() {
+                self.niri.queue_redraw(&output);
+            }
+            return;
+        }
+
         let root_window_output = self.niri.layout.find_window_and_output(&root_surface);
         if let Some((mapped, output)) = root_window_output {
             let window = mapped.window.clone();
@@ -11,7 +11,7 @@ use smithay::wayland::shell::wlr_layer::{
 use smithay::wayland::shell::xdg::PopupSurface;
 
 use crate::layer::{MappedLayer, ResolvedLayerRules};
-use crate::niri::State;
+use crate::niri::{KeyboardFocus, State};
 use crate::utils::{is_mapped, output_size, send_scale_transform};
 
 impl WlrLayerShellHandler for State {
@@ -173,6 +173,17 @@ impl State {
                         // little annoying.
                         self.niri.layer_shell_on_demand_focus = Some(layer.clone());
                     }
+
+                    // When a focused exclusive surface switches to on-demand (e.g. a bar that
+                    // grabbed the keyboard for its search field), keep the focus on it rather
+                    // than yanking it away mid-interaction.
+                    let has_focus = matches!(
+                        &self.niri.keyboard_focus,
+                        KeyboardFocus::LayerShell { surface: focus } if focus == surface
+                    );
+                    if !was_unmapped && on_demand && has_focus {
+                        self.niri.layer_shell_on_demand_focus = Some(layer.clone());
+                    }
                 } else {
                     let was_mapped = self.niri.mapped_layer_surfaces.remove(layer).is_some();
                     self.niri.unmapped_layer_surfaces.insert(surface.clone());
@@ -9,6 +9,7 @@ use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
+use niri_config::ActivationPolicy;
 use smithay::backend::allocator::dmabuf::Dmabuf;
 use smithay::backend::drm::DrmNode;
 use smithay::backend::input::{InputEvent, TabletToolDescriptor};
@@ -21,6 +22,7 @@ use smithay::output::Output;
 use smithay::reexports::rustix::fs::{fcntl_setfl, OFlags};
 use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel;
 use smithay::reexports::wayland_protocols_wlr::screencopy::v1::server::zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1;
+use smithay::reexports::wayland_server::protocol::wl_data_device_manager::DndAction;
 use smithay::reexports::wayland_server::protocol::wl_data_source::WlDataSource;
 use smithay::reexports::wayland_server::protocol::wl_output::WlOutput;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
@@ -31,6 +33,9 @@ use smithay::wayland::dmabuf::{DmabufGlobal, DmabufHandler, DmabufState, ImportN
 use smithay::wayland::drm_lease::{
     DrmLease, DrmLeaseBuilder, DrmLeaseHandler, DrmLeaseRequest, DrmLeaseState, LeaseRejected,
 };
+use smithay::wayland::foreign_toplevel_list::{
+    ForeignToplevelListHandler, ForeignToplevelListState,
+};
 use smithay::wayland::fractional_scale::FractionalScaleHandler;
 use smithay::wayland::idle_inhibit::IdleInhibitHandler;
 use smithay::wayland::idle_notify::{IdleNotifierHandler, IdleNotifierState};
@@ -44,8 +49,8 @@ use smithay::wayland::security_context::{
     SecurityContext, SecurityContextHandler, SecurityContextListenerSource,
 };
 use smithay::wayland::selection::data_device::{
-    set_data_device_focus, ClientDndGrabHandler, DataDeviceHandler, DataDeviceState,
-    ServerDndGrabHandler,
+    default_action_chooser, set_data_device_focus, ClientDndGrabHandler, DataDeviceHandler,
+    DataDeviceState, ServerDndGrabHandler,
 };
 use smithay::wayland::selection::ext_data_control::{
     DataControlHandler as ExtDataControlHandler, DataControlState as ExtDataControlState,
@@ -65,18 +70,19 @@ use smithay::wayland::xdg_activation::{
     XdgActivationHandler, XdgActivationState, XdgActivationToken, XdgActivationTokenData,
 };
 use smithay::{
-    delegate_cursor_shape, delegate_data_control, delegate_data_device, delegate_dmabuf,
-    delegate_drm_lease, delegate_ext_data_control, delegate_fractional_scale,
-    delegate_idle_inhibit, delegate_idle_notify, delegate_input_method_manager,
-    delegate_keyboard_shortcuts_inhibit, delegate_output, delegate_pointer_constraints,
-    delegate_pointer_gestures, delegate_presentation, delegate_primary_selection,
-    delegate_relative_pointer, delegate_seat, delegate_security_context, delegate_session_lock,
-    delegate_single_pixel_buffer, delegate_tablet_manager, delegate_text_input_manager,
-    delegate_viewporter, delegate_virtual_keyboard_manager, delegate_xdg_activation,
+    delegate_content_type, delegate_cursor_shape, delegate_data_control, delegate_data_device,
+    delegate_dmabuf, delegate_drm_lease, delegate_ext_data_control, delegate_foreign_toplevel_list,
+    delegate_fractional_scale, delegate_idle_inhibit, delegate_idle_notify,
+    delegate_input_method_manager, delegate_keyboard_shortcuts_inhibit, delegate_output,
+    delegate_pointer_constraints, delegate_pointer_gestures, delegate_presentation,
+    delegate_primary_selection, delegate_relative_pointer, delegate_seat,
+    delegate_security_context, delegate_session_lock, delegate_single_pixel_buffer,
+    delegate_tablet_manager, delegate_text_input_manager, delegate_viewporter,
+    delegate_virtual_keyboard_manager, delegate_xdg_activation,
 };
 
 pub use crate::handlers::xdg_shell::KdeDecorationsModeState;
-use crate::layout::ActivateWindow;
+use crate::layout::{ActivateWindow, LayoutElement as _};
 use crate::niri::{DndIcon, NewClient, State};
 use crate::protocols::foreign_toplevel::{
     self, ForeignToplevelHandler, ForeignToplevelManagerState,
@@ -91,6 +97,7 @@ use crate::protocols::virtual_pointer::{
     VirtualPointerMotionEvent,
 };
 use crate::utils::{output_size, send_scale_transform, with_toplevel_role};
+use crate::window::{ActivationAppId, InitialConfigureState};
 use crate::{
     delegate_foreign_toplevel, delegate_gamma_control, delegate_mutter_x11_interop,
     delegate_output_management, delegate_screencopy, delegate_virtual_pointer,
@@ -122,7 +129,15 @@ impl SeatHandler for State {
         let dh = &self.niri.display_handle;
         let client = focused.and_then(|s| dh.get_client(s.id()).ok());
         set_data_device_focus(dh, seat, client.clone());
-        set_primary_focus(dh, seat, client);
+
+        // Withholding primary selection focus blocks clients that had bound the global before
+        // the primary selection was disabled.
+        let primary_client = if self.niri.config.borrow().clipboard.disable_primary {
+            None
+        } else {
+            client
+        };
+        set_primary_focus(dh, seat, primary_client);
     }
 
     fn led_state_changed(&mut self, _seat: &Seat<Self>, led_state: keyboard::LedState) {
@@ -312,6 +327,24 @@ impl DataDeviceHandler for State {
     fn data_device_state(&self) -> &DataDeviceState {
         &self.niri.data_device_state
     }
+
+    fn action_choice(&mut self, available: DndAction, preferred: DndAction) -> DndAction {
+        // Let the held modifiers pick the action, like in other desktops: Shift moves, Ctrl
+        // copies, and Alt asks the target.
+        let mods = self.niri.seat.get_keyboard().unwrap().modifier_state();
+        let requested = match (mods.shift, mods.ctrl, mods.alt) {
+            (true, false, false) => Some(DndAction::Move),
+            (false, true, false) => Some(DndAction::Copy),
+            (false, false, true) => Some(DndAction::Ask),
+            _ => None,
+        };
+
+        if let Some(action) = requested.filter(|action| available.contains(*action)) {
+            return action;
+        }
+
+        default_action_chooser(available, preferred)
+    }
 }
 
 impl ClientDndGrabHandler for State {
@@ -491,6 +524,7 @@ impl SecurityContextHandler for State {
                     client,
                     restricted: true,
                     credentials_unknown: false,
+                    sandbox_app_id: context.app_id.clone(),
                 });
             })
             .unwrap();
@@ -574,6 +608,13 @@ impl ForeignToplevelHandler for State {
 }
 delegate_foreign_toplevel!(State);
 
+impl ForeignToplevelListHandler for State {
+    fn foreign_toplevel_list_state(&mut self) -> &mut ForeignToplevelListState {
+        &mut self.niri.foreign_toplevel_list_state
+    }
+}
+delegate_foreign_toplevel_list!(State);
+
 impl ScreencopyHandler for State {
     fn frame(&mut self, manager: &ZwlrScreencopyManagerV1, screencopy: Screencopy) {
         // If with_damage then push it onto the queue for redraw of the output,
@@ -676,6 +717,8 @@ delegate_drm_lease!(State);
 
 delegate_viewporter!(State);
 
+delegate_content_type!(State);
+
 impl GammaControlHandler for State {
     fn gamma_control_manager_state(&mut self) -> &mut GammaControlManagerState {
         &mut self.niri.gamma_control_manager_state
@@ -707,7 +750,8 @@ impl GammaControlHandler for State {
 }
 delegate_gamma_control!(State);
 
-struct UrgentOnlyMarker;
+/// Marks tokens that weren't created in response to recent user interaction.
+struct NoInteractionMarker;
 
 impl XdgActivationHandler for State {
     fn activation_state(&mut self) -> &mut XdgActivationState {
@@ -715,12 +759,13 @@ impl XdgActivationHandler for State {
     }
 
     fn token_created(&mut self, _token: XdgActivationToken, data: XdgActivationTokenData) -> bool {
-        // Tokens without a serial are urgency-only. This is not specified, but it seems to be the
-        // common client behavior.
+        // Tokens without a serial don't come from user interaction, and are subject to the
+        // activation policy. Usually the clients want urgency from such tokens. This is not
+        // specified, but it seems to be the common client behavior.
         //
         // See also: https://gitlab.freedesktop.org/wayland/wayland-protocols/-/issues/150
         let Some((serial, seat)) = data.serial else {
-            data.user_data.insert_if_missing(|| UrgentOnlyMarker);
+            data.user_data.insert_if_missing(|| NoInteractionMarker);
             return true;
         };
         let Some(seat) = Seat::<State>::from_resource(&seat) else {
@@ -761,18 +806,57 @@ impl XdgActivationHandler for State {
         surface: WlSurface,
     ) {
         if token_data.timestamp.elapsed() < XDG_ACTIVATION_TOKEN_TIMEOUT {
+            let no_interaction = token_data.user_data.get::<NoInteractionMarker>().is_some();
+            let default_policy = self.niri.config.borrow().input.activation_policy;
+
             if let Some((mapped, _)) = self.niri.layout.find_window_and_output_mut(&surface) {
-                let window = mapped.window.clone();
-                if token_data.user_data.get::<UrgentOnlyMarker>().is_some() {
-                    mapped.set_urgent(true);
-                    self.niri.queue_redraw_all();
+                let policy = if no_interaction {
+                    mapped.rules().activation_policy.unwrap_or(default_policy)
                 } else {
-                    self.niri.layout.activate_window(&window);
-                    self.niri.layer_shell_on_demand_focus = None;
-                    self.niri.queue_redraw_all();
+                    ActivationPolicy::Focus
+                };
+
+                match policy {
+                    ActivationPolicy::Focus => {
+                        let window = mapped.window.clone();
+                        self.niri.layout.activate_window(&window);
+                        self.niri.layer_shell_on_demand_focus = None;
+                        self.niri.queue_redraw_all();
+                    }
+                    ActivationPolicy::MarkUrgent => {
+                        mapped.set_urgent(true);
+                        self.niri.queue_redraw_all();
+                    }
+                    ActivationPolicy::Ignore => (),
+                }
+            } else if self.niri.unmapped_windows.contains_key(&surface) {
+                // Remember which app the token was made for, for the activation-app-id matcher.
+                if let Some(app_id) = token_data.app_id.clone() {
+                    with_states(&surface, |states| {
+                        states
+                            .data_map
+                            .insert_if_missing(|| ActivationAppId(app_id));
+                    });
+                    let toplevel = self.niri.unmapped_windows[&surface].toplevel().clone();
+                    self.update_window_rules(&toplevel);
+                }
+
+                let unmapped = self.niri.unmapped_windows.get_mut(&surface).unwrap();
+
+                // New windows can't be urgent, so only keep the token if it should focus.
+                let policy = if no_interaction {
+                    let rule = match &unmapped.state {
+                        InitialConfigureState::Configured { rules, .. } => rules.activation_policy,
+                        InitialConfigureState::NotConfigured { .. } => None,
+                    };
+                    rule.unwrap_or(default_policy)
+                } else {
+                    ActivationPolicy::Focus
+                };
+
+                if policy == ActivationPolicy::Focus {
+                    unmapped.activation_token_data = Some(token_data);
                 }
-            } else if let Some(unmapped) = self.niri.unmapped_windows.get_mut(&surface) {
-                unmapped.activation_token_data = Some(token_data);
             }
         }
 
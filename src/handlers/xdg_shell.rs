@@ -1,7 +1,7 @@
 use std::cell::Cell;
 
 use calloop::Interest;
-use niri_config::PresetSize;
+use niri_config::{DecorationMode, PresetSize};
 use smithay::desktop::{
     find_popup_root_surface, get_popup_toplevel_coords, layer_map_for_output, utils, LayerSurface,
     PopupKeyboardGrab, PopupKind, PopupManager, PopupPointerGrab, PopupUngrabStrategy, Window,
@@ -28,13 +28,15 @@ use smithay::wayland::selection::data_device::DnDGrab;
 use smithay::wayland::shell::kde::decoration::{KdeDecorationHandler, KdeDecorationState};
 use smithay::wayland::shell::wlr_layer::{self, Layer};
 use smithay::wayland::shell::xdg::decoration::XdgDecorationHandler;
+use smithay::wayland::shell::xdg::dialog::XdgDialogHandler;
 use smithay::wayland::shell::xdg::{
-    PopupSurface, PositionerState, ToplevelSurface, XdgShellHandler, XdgShellState,
+    PopupSurface, PositionerState, ShellClient, ToplevelSurface, XdgShellHandler, XdgShellState,
     XdgToplevelSurfaceData,
 };
 use smithay::wayland::xdg_foreign::{XdgForeignHandler, XdgForeignState};
 use smithay::{
-    delegate_kde_decoration, delegate_xdg_decoration, delegate_xdg_foreign, delegate_xdg_shell,
+    delegate_kde_decoration, delegate_xdg_decoration, delegate_xdg_dialog, delegate_xdg_foreign,
+    delegate_xdg_shell,
 };
 use tracing::field::Empty;
 
@@ -43,19 +45,29 @@ use crate::input::resize_grab::ResizeGrab;
 use crate::input::touch_move_grab::TouchMoveGrab;
 use crate::input::touch_resize_grab::TouchResizeGrab;
 use crate::input::{PointerOrTouchStartData, DOUBLE_CLICK_TIME};
-use crate::layout::ActivateWindow;
-use crate::niri::{CastTarget, PopupGrabState, State};
+use crate::layout::{ActivateWindow, LayoutElement};
+use crate::niri::{set_backdrop_window_pending_state, CastTarget, PopupGrabState, State};
+use crate::unresponsive::PingedClient;
 use crate::utils::transaction::Transaction;
 use crate::utils::{
-    get_monotonic_time, output_matches_name, send_scale_transform, update_tiled_state, ResizeEdge,
+    get_monotonic_time, output_matches_name, send_scale_transform, update_decoration_mode,
+    update_tiled_state, xdg_decoration_mode, ResizeEdge,
 };
-use crate::window::{InitialConfigureState, ResolvedWindowRules, Unmapped, WindowRef};
+use crate::window::{InitialConfigureState, ModalState, ResolvedWindowRules, Unmapped, WindowRef};
 
 impl XdgShellHandler for State {
     fn xdg_shell_state(&mut self) -> &mut XdgShellState {
         &mut self.niri.xdg_shell_state
     }
 
+    fn new_client(&mut self, client: ShellClient) {
+        self.niri.shell_clients.push(PingedClient::new(client));
+    }
+
+    fn client_pong(&mut self, client: ShellClient) {
+        self.shell_client_pong(&client);
+    }
+
     fn new_toplevel(&mut self, surface: ToplevelSurface) {
         let wl_surface = surface.wl_surface().clone();
         let unmapped = Unmapped::new(Window::new_wayland_window(surface));
@@ -455,6 +467,8 @@ impl XdgShellHandler for State {
             mapped.set_needs_configure();
 
             let window = mapped.window.clone();
+            let force_windowed = mapped.rules().force_windowed_fullscreen == Some(true);
+            let is_windowed = mapped.is_pending_windowed_fullscreen();
 
             if let Some(requested_output) = requested_output {
                 if Some(&requested_output) != current_output {
@@ -467,7 +481,21 @@ impl XdgShellHandler for State {
                 }
             }
 
-            self.niri.layout.set_fullscreen(&window, true);
+            if force_windowed {
+                // Keep the window in its tile, only telling it that it's fullscreen.
+                if !is_windowed {
+                    self.niri.layout.toggle_windowed_fullscreen(&window);
+                }
+            } else {
+                self.niri.layout.set_fullscreen(&window, true);
+            }
+        } else if self
+            .niri
+            .backdrop_window_idx(toplevel.wl_surface())
+            .is_some()
+        {
+            // Backdrop windows always span their output.
+            toplevel.send_configure();
         } else if let Some(unmapped) = self.niri.unmapped_windows.get_mut(toplevel.wl_surface()) {
             match &mut unmapped.state {
                 InitialConfigureState::NotConfigured { wants_fullscreen } => {
@@ -475,6 +503,12 @@ impl XdgShellHandler for State {
 
                     // The required configure will be the initial configure.
                 }
+                InitialConfigureState::Configured { rules, .. }
+                    if rules.open_as_backdrop == Some(true) =>
+                {
+                    // Backdrop windows always span their output.
+                    toplevel.send_configure();
+                }
                 InitialConfigureState::Configured { rules, output, .. } => {
                     // Figure out the monitor following a similar logic to initial configure.
                     // FIXME: deduplicate.
@@ -539,6 +573,13 @@ impl XdgShellHandler for State {
 
             let window = mapped.window.clone();
             self.niri.layout.set_fullscreen(&window, false);
+        } else if self
+            .niri
+            .backdrop_window_idx(toplevel.wl_surface())
+            .is_some()
+        {
+            // Backdrop windows always span their output.
+            toplevel.send_configure();
         } else if let Some(unmapped) = self.niri.unmapped_windows.get_mut(toplevel.wl_surface()) {
             match &mut unmapped.state {
                 InitialConfigureState::NotConfigured { wants_fullscreen } => {
@@ -546,6 +587,12 @@ impl XdgShellHandler for State {
 
                     // The required configure will be the initial configure.
                 }
+                InitialConfigureState::Configured { rules, .. }
+                    if rules.open_as_backdrop == Some(true) =>
+                {
+                    // Backdrop windows always span their output.
+                    toplevel.send_configure();
+                }
                 InitialConfigureState::Configured {
                     rules,
                     width,
@@ -649,6 +696,15 @@ impl XdgShellHandler for State {
             return;
         }
 
+        if let Some(idx) = self.niri.backdrop_window_idx(surface.wl_surface()) {
+            // A backdrop window got destroyed.
+            let backdrop = self.niri.backdrop_windows.remove(idx);
+            if let Some(output) = backdrop.output {
+                self.niri.queue_redraw(&output);
+            }
+            return;
+        }
+
         let win_out = self
             .niri
             .layout
@@ -715,6 +771,9 @@ impl XdgShellHandler for State {
     }
 
     fn parent_changed(&mut self, toplevel: ToplevelSurface) {
+        // For the has-parent matcher.
+        self.update_window_rules(&toplevel);
+
         let Some(parent) = toplevel.parent() else {
             return;
         };
@@ -735,9 +794,9 @@ delegate_xdg_shell!(State);
 
 impl XdgDecorationHandler for State {
     fn new_decoration(&mut self, toplevel: ToplevelSurface) {
-        // If we want CSD, we hide this global altogether.
+        let mode = self.default_decoration_mode(&toplevel);
         toplevel.with_pending_state(|state| {
-            state.decoration_mode = Some(zxdg_toplevel_decoration_v1::Mode::ServerSide);
+            state.decoration_mode = Some(mode);
         });
     }
 
@@ -750,6 +809,12 @@ impl XdgDecorationHandler for State {
         //
         // The bug has been fixed, but there's a ton of apps which will use the buggy version for a
         // long while...
+        //
+        // The only exception is a mode explicitly forced by the window rules.
+        let mode = self
+            .decoration_mode_rule(&toplevel)
+            .map(xdg_decoration_mode)
+            .unwrap_or(mode);
         toplevel.with_pending_state(|state| {
             state.decoration_mode = Some(mode);
         });
@@ -768,9 +833,9 @@ impl XdgDecorationHandler for State {
     }
 
     fn unset_mode(&mut self, toplevel: ToplevelSurface) {
-        // If we want CSD, we hide this global altogether.
+        let mode = self.default_decoration_mode(&toplevel);
         toplevel.with_pending_state(|state| {
-            state.decoration_mode = Some(zxdg_toplevel_decoration_v1::Mode::ServerSide);
+            state.decoration_mode = Some(mode);
         });
 
         // A configure is required in response to this event. However, if an initial configure
@@ -836,7 +901,51 @@ impl XdgForeignHandler for State {
 }
 delegate_xdg_foreign!(State);
 
+impl XdgDialogHandler for State {
+    fn modal_changed(&mut self, toplevel: ToplevelSurface, is_modal: bool) {
+        with_states(toplevel.wl_surface(), |states| {
+            let state = states.data_map.get_or_insert(ModalState::default);
+            state.0.set(is_modal);
+        });
+
+        // For the is-modal matcher.
+        self.update_window_rules(&toplevel);
+    }
+}
+delegate_xdg_dialog!(State);
+
 impl State {
+    /// Returns the decoration mode forced by the window rules, if any.
+    fn decoration_mode_rule(&self, toplevel: &ToplevelSurface) -> Option<DecorationMode> {
+        let surface = toplevel.wl_surface();
+        if let Some((mapped, _)) = self.niri.layout.find_window_and_output(surface) {
+            return mapped.rules().decoration_mode;
+        }
+
+        // Before the initial configure, the rules aren't computed yet, and the initial configure
+        // will apply them later.
+        match &self.niri.unmapped_windows.get(surface)?.state {
+            InitialConfigureState::Configured { rules, .. } => rules.decoration_mode,
+            InitialConfigureState::NotConfigured { .. } => None,
+        }
+    }
+
+    /// Returns the decoration mode to use when the client has no preference.
+    fn default_decoration_mode(
+        &self,
+        toplevel: &ToplevelSurface,
+    ) -> zxdg_toplevel_decoration_v1::Mode {
+        if let Some(mode) = self.decoration_mode_rule(toplevel) {
+            return xdg_decoration_mode(mode);
+        }
+
+        if self.niri.config.borrow().prefer_no_csd {
+            zxdg_toplevel_decoration_v1::Mode::ServerSide
+        } else {
+            zxdg_toplevel_decoration_v1::Mode::ClientSide
+        }
+    }
+
     pub fn send_initial_configure(&mut self, toplevel: &ToplevelSurface) {
         let _span = tracy_client::span!("State::send_initial_configure");
 
@@ -859,18 +968,51 @@ impl State {
             return;
         };
 
+        // Backdrop windows span their output instead of going into the layout.
+        if rules.open_as_backdrop == Some(true) {
+            let output = rules
+                .open_on_output
+                .iter()
+                .find_map(|name| {
+                    self.niri
+                        .global_space
+                        .outputs()
+                        .find(|output| output_matches_name(output, name))
+                })
+                .or_else(|| self.niri.layout.active_output())
+                .cloned();
+
+            set_backdrop_window_pending_state(toplevel, output.as_ref());
+            update_decoration_mode(toplevel, rules.decoration_mode);
+
+            *state = InitialConfigureState::Configured {
+                rules,
+                width: None,
+                height: None,
+                floating_width: None,
+                floating_height: None,
+                is_full_width: false,
+                output,
+                workspace_name: None,
+            };
+
+            toplevel.send_configure();
+            return;
+        }
+
         // Pick the target monitor. First, check if we had a workspace set in the window rules.
         let mon = rules
             .open_on_workspace
             .as_deref()
             .and_then(|name| self.niri.layout.monitor_for_workspace(name));
 
-        // If not, check if we had an output set in the window rules.
+        // If not, check if we had an output set in the window rules. Take the first one that is
+        // connected.
         let mon = mon.or_else(|| {
             rules
                 .open_on_output
-                .as_deref()
-                .and_then(|name| {
+                .iter()
+                .find_map(|name| {
                     self.niri
                         .global_space
                         .outputs()
@@ -917,7 +1059,9 @@ impl State {
         let mut floating_width = None;
         let mut height = None;
         let mut floating_height = None;
-        let is_full_width = rules.open_maximized.unwrap_or(false);
+        let is_full_width = rules
+            .open_maximized
+            .unwrap_or(self.niri.is_tablet_mode && config.tablet_mode.maximize_new_windows);
         let is_floating = rules.compute_open_floating(toplevel);
 
         // Tell the surface the preferred size and bounds for its likely output.
@@ -962,7 +1106,9 @@ impl State {
             );
         }
 
-        // Set the tiled state for the initial configure.
+        // Set the decoration mode and the tiled state, which depends on it, for the initial
+        // configure.
+        update_decoration_mode(toplevel, rules.decoration_mode);
         update_tiled_state(toplevel, config.prefer_no_csd, rules.tiled_state);
 
         // Set the configured settings.
@@ -235,6 +235,21 @@ impl Window {
         self.viewport.set_destination(i32::from(w), i32::from(h));
     }
 
+    /// Acks the last configure and commits a new buffer of the size that it requested.
+    ///
+    /// Like a real client, this picks its own size of 100 for any dimension left at zero.
+    pub fn ack_last_and_commit_configured(&self) {
+        let (w, h) = self.configures_received.last().unwrap().1.size;
+        let pick = |size: i32| match size {
+            0 => 100,
+            size => u16::try_from(size).unwrap(),
+        };
+
+        self.attach_new_buffer();
+        self.set_size(pick(w), pick(h));
+        self.ack_last_and_commit();
+    }
+
     pub fn set_fullscreen(&self, output: Option<&WlOutput>) {
         self.xdg_toplevel.set_fullscreen(output);
     }
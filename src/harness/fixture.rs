@@ -0,0 +1,286 @@
+use std::os::fd::AsFd as _;
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use calloop::generic::Generic;
+use calloop::{EventLoop, Interest, LoopHandle, Mode, PostAction};
+use niri_config::Config;
+use niri_ipc::{Action, Reply, Request, Response};
+use smithay::backend::input::{KeyState, Keycode};
+use smithay::output::Output;
+use smithay::utils::Point;
+use wayland_client::protocol::wl_surface::WlSurface;
+
+use super::client::{Client, ClientId};
+use super::server::Server;
+use crate::ipc::server::process_request;
+use crate::layout::HitType;
+use crate::niri::{NewClient, Niri};
+use crate::utils::get_monotonic_time;
+
+pub struct Fixture {
+    pub event_loop: EventLoop<'static, State>,
+    pub handle: LoopHandle<'static, State>,
+    pub state: State,
+}
+
+pub struct State {
+    pub server: Server,
+    pub clients: Vec<Client>,
+}
+
+impl Fixture {
+    pub fn new() -> Self {
+        Self::with_config(Config::default())
+    }
+
+    pub fn with_config(config: Config) -> Self {
+        let event_loop = EventLoop::try_new().unwrap();
+        let handle = event_loop.handle();
+
+        let server = Server::new(config);
+        let fd = server.event_loop.as_fd().try_clone_to_owned().unwrap();
+        let source = Generic::new(fd, Interest::READ, Mode::Level);
+        handle
+            .insert_source(source, |_, _, state: &mut State| {
+                state.server.dispatch();
+                Ok(PostAction::Continue)
+            })
+            .unwrap();
+
+        let state = State {
+            server,
+            clients: Vec::new(),
+        };
+
+        Self {
+            event_loop,
+            handle,
+            state,
+        }
+    }
+
+    pub fn dispatch(&mut self) {
+        self.event_loop
+            .dispatch(Duration::ZERO, &mut self.state)
+            .unwrap();
+    }
+
+    /// Dispatches the compositor event loop, for example to run its expired timers.
+    pub fn dispatch_server(&mut self) {
+        self.state.server.dispatch();
+    }
+
+    pub fn niri_state(&mut self) -> &mut crate::niri::State {
+        &mut self.state.server.state
+    }
+
+    pub fn niri(&mut self) -> &mut Niri {
+        &mut self.niri_state().niri
+    }
+
+    pub fn niri_output(&self, n: u8) -> Output {
+        let niri = &self.state.server.state.niri;
+        let idx = usize::from(n - 1);
+        let output = niri.global_space.outputs().nth(idx).unwrap();
+        output.clone()
+    }
+
+    pub fn niri_focus_output(&mut self, n: u8) {
+        let niri = &mut self.state.server.state.niri;
+        let idx = usize::from(n - 1);
+        let output = niri.global_space.outputs().nth(idx).unwrap();
+        niri.layout.focus_output(output);
+    }
+
+    pub fn add_output(&mut self, n: u8, size: (u16, u16)) {
+        let state = self.niri_state();
+        let niri = &mut state.niri;
+        state.backend.headless().add_output(niri, n, size);
+    }
+
+    pub fn add_client(&mut self) -> ClientId {
+        let (sock1, sock2) = UnixStream::pair().unwrap();
+        self.niri().insert_client(NewClient {
+            client: sock1,
+            restricted: false,
+            credentials_unknown: false,
+            sandbox_app_id: None,
+        });
+
+        let client = Client::new(sock2);
+        let id = client.id;
+
+        let fd = client.event_loop.as_fd().try_clone_to_owned().unwrap();
+        let source = Generic::new(fd, Interest::READ, Mode::Level);
+        self.handle
+            .insert_source(source, move |_, _, state: &mut State| {
+                state.client(id).dispatch();
+                Ok(PostAction::Continue)
+            })
+            .unwrap();
+
+        self.state.clients.push(client);
+        self.roundtrip(id);
+        id
+    }
+
+    pub fn client(&mut self, id: ClientId) -> &mut Client {
+        self.state.client(id)
+    }
+
+    pub fn roundtrip(&mut self, id: ClientId) {
+        let client = self.state.client(id);
+        let data = client.send_sync();
+        while !data.done.load(Ordering::Relaxed) {
+            self.dispatch();
+        }
+    }
+
+    /// Roundtrip twice in a row.
+    ///
+    /// For some reason, when running tests on many threads at once, a single roundtrip is
+    /// sometimes not sufficient to get the configure events to the client.
+    ///
+    /// I suspect that this is because these configure events are sent from the niri loop callback,
+    /// so they arrive after the sync done event and don't get processed in that client dispatch
+    /// cycle. I'm not sure why this would be dependent on multithreading. But if this is indeed
+    /// the issue, then a double roundtrip fixes it.
+    pub fn double_roundtrip(&mut self, id: ClientId) {
+        self.roundtrip(id);
+        self.roundtrip(id);
+    }
+
+    /// Opens a window on the client and waits until it's mapped at the size niri asked for.
+    pub fn open_window(&mut self, id: ClientId) -> WlSurface {
+        let window = self.client(id).create_window();
+        let surface = window.surface.clone();
+        window.commit();
+
+        self.commit_configured(id, &surface);
+        surface
+    }
+
+    /// Makes the window follow its last configure, like a well-behaved client would.
+    pub fn commit_configured(&mut self, id: ClientId, surface: &WlSurface) {
+        self.roundtrip(id);
+        let window = self.client(id).window(surface);
+        window.ack_last_and_commit_configured();
+        self.double_roundtrip(id);
+    }
+
+    /// Sends an IPC request and waits for the reply, like `niri msg` does.
+    pub fn ipc(&mut self, request: Request) -> Reply {
+        let rx = process_request(self.niri_state(), request);
+        loop {
+            self.dispatch();
+            if let Ok(reply) = rx.try_recv() {
+                return reply;
+            }
+        }
+    }
+
+    /// Does an action over IPC, like `niri msg action` does.
+    pub fn action(&mut self, action: Action) {
+        let reply = self.ipc(Request::Action(action));
+        assert!(
+            matches!(reply, Ok(Response::Handled)),
+            "unexpected reply to action: {reply:?}"
+        );
+    }
+
+    /// Presses or releases a key, like a physical keyboard would.
+    ///
+    /// The key code is an XKB key code, i.e. the evdev key code plus 8.
+    pub fn key(&mut self, key_code: u32, pressed: bool) {
+        let state = if pressed {
+            KeyState::Pressed
+        } else {
+            KeyState::Released
+        };
+        let time = get_monotonic_time().as_millis() as u32;
+        self.niri_state().handle_key(Keycode::new(key_code), state, time);
+        self.dispatch();
+    }
+
+    /// Formats what a pointer would hit all over an output, one character per cell.
+    ///
+    /// The characters are:
+    ///
+    /// - `a`, `b`, `c` and so on for the input region of windows, in the order they opened,
+    /// - digits for the top bar buttons, by button index,
+    /// - `=` for the rest of the top bar,
+    /// - `~` for the tab indicator,
+    /// - `#` for the rest of the tile, like the window border,
+    /// - `L` for layer-shell surfaces,
+    /// - `?` for other surfaces,
+    /// - `.` for nothing.
+    ///
+    /// Each cell is hit-tested at its center. Nothing is rendered, so the map shows where input
+    /// goes rather than what's drawn there.
+    pub fn format_hit_map(&mut self, n: u8, cell_size: u16) -> String {
+        let output = self.niri_output(n);
+        let niri = self.niri();
+        let geo = niri.global_space.output_geometry(&output).unwrap();
+        let cell_size = i32::from(cell_size);
+        let half = f64::from(cell_size) / 2.;
+
+        let mut windows = niri
+            .layout
+            .windows()
+            .map(|(_, mapped)| (mapped.id().get(), mapped.window.clone()))
+            .collect::<Vec<_>>();
+        windows.sort_unstable_by_key(|(id, _)| *id);
+
+        let mut buf = String::new();
+        for y in 0..geo.size.h / cell_size {
+            for x in 0..geo.size.w / cell_size {
+                let cell = Point::from((x * cell_size, y * cell_size)) + geo.loc;
+                let pos = cell.to_f64() + Point::from((half, half));
+                let contents = niri.contents_under(pos);
+
+                let c = if let Some((window, hit)) = &contents.window {
+                    match hit {
+                        HitType::Input { .. } => {
+                            let idx = windows.iter().position(|(_, w)| w == window).unwrap();
+                            char::from(b'a' + (idx % 26) as u8)
+                        }
+                        HitType::Activate {
+                            is_tab_indicator: true,
+                            ..
+                        } => '~',
+                        HitType::Activate {
+                            is_top_bar: true, ..
+                        } => '=',
+                        HitType::Activate { .. } => '#',
+                        HitType::TopBarButton { button_idx } => {
+                            char::from_digit(*button_idx as u32, 10).unwrap_or('+')
+                        }
+                    }
+                } else if contents.layer.is_some() {
+                    'L'
+                } else if contents.surface.is_some() {
+                    '?'
+                } else {
+                    '.'
+                };
+                buf.push(c);
+            }
+            buf.push('\n');
+        }
+        buf
+    }
+}
+
+impl Default for Fixture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl State {
+    pub fn client(&mut self, id: ClientId) -> &mut Client {
+        self.clients.iter_mut().find(|c| c.id == id).unwrap()
+    }
+}
@@ -0,0 +1,15 @@
+//! Harness for testing niri end to end without a real session.
+//!
+//! [`Fixture`] runs niri with the headless backend together with fake Wayland clients, all on the
+//! current thread. Tests add outputs and clients, drive niri over IPC like `niri msg` would, and
+//! check the result with text snapshots, such as [`Fixture::format_hit_map`] which shows what a
+//! pointer would hit on an output.
+//!
+//! The harness is built for niri's own tests, and for other crates with the `test-harness`
+//! feature.
+
+pub use fixture::Fixture;
+
+pub mod client;
+pub mod fixture;
+pub mod server;
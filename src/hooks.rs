@@ -0,0 +1,95 @@
+//! Commands from the config spawned on compositor events.
+
+use smithay::output::Output;
+
+use crate::niri::Niri;
+use crate::utils::spawning::spawn_with_env;
+use crate::utils::with_toplevel_role;
+use crate::window::Mapped;
+
+impl Niri {
+    pub fn run_window_open_hooks(&self, mapped: &Mapped) {
+        let config = self.config.borrow();
+        if config.hooks.window_open.is_empty() {
+            return;
+        }
+
+        let (app_id, title) = with_toplevel_role(mapped.toplevel(), |role| {
+            (role.app_id.clone(), role.title.clone())
+        });
+
+        for hook in &config.hooks.window_open {
+            if let Some(re) = &hook.app_id {
+                if !app_id.as_ref().is_some_and(|app_id| re.0.is_match(app_id)) {
+                    continue;
+                }
+            }
+            if let Some(re) = &hook.title {
+                if !title.as_ref().is_some_and(|title| re.0.is_match(title)) {
+                    continue;
+                }
+            }
+
+            let mut env = vec![("NIRI_WINDOW_ID", mapped.id().get().to_string())];
+            if let Some(app_id) = &app_id {
+                env.push(("NIRI_WINDOW_APP_ID", app_id.clone()));
+            }
+            if let Some(title) = &title {
+                env.push(("NIRI_WINDOW_TITLE", title.clone()));
+            }
+            spawn_with_env(hook.command.clone(), env);
+        }
+    }
+
+    /// Runs the workspace switch hooks if the focused workspace changed since the last call.
+    pub fn refresh_workspace_switch_hooks(&mut self) {
+        let Some(mon) = self.layout.active_monitor_ref() else {
+            return;
+        };
+        let ws = mon.active_workspace_ref();
+
+        let id = ws.id();
+        let previous = self.hooks_focused_workspace.replace(id);
+        // Don't count the first workspace at startup as a switch.
+        if previous.map_or(true, |previous| previous == id) {
+            return;
+        }
+
+        let config = self.config.borrow();
+        if config.hooks.workspace_switch.is_empty() {
+            return;
+        }
+
+        let mut env = vec![
+            ("NIRI_WORKSPACE_ID", id.get().to_string()),
+            (
+                "NIRI_WORKSPACE_IDX",
+                (mon.active_workspace_idx() + 1).to_string(),
+            ),
+            ("NIRI_OUTPUT", mon.output_name().clone()),
+        ];
+        if let Some(name) = ws.name() {
+            env.push(("NIRI_WORKSPACE_NAME", name.clone()));
+        }
+
+        for hook in &config.hooks.workspace_switch {
+            spawn_with_env(hook.command.clone(), env.clone());
+        }
+    }
+
+    pub fn run_output_connect_hooks(&self, output: &Output) {
+        let config = self.config.borrow();
+        for hook in &config.hooks.output_connect {
+            let env = vec![("NIRI_OUTPUT", output.name())];
+            spawn_with_env(hook.command.clone(), env);
+        }
+    }
+
+    pub fn run_lock_hooks(&self) {
+        let config = self.config.borrow();
+        for hook in &config.hooks.lock {
+            spawn_with_env(hook.command.clone(), Vec::new());
+        }
+    }
+
+}
@@ -0,0 +1,196 @@
+//! Built-in idle management: dimming, locking and powering off the monitors on inactivity, and
+//! running the idle hooks.
+
+use std::time::Duration;
+
+use niri_config::Config;
+use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
+
+use crate::niri::{Niri, State};
+use crate::utils::spawning::{spawn, spawn_with_env};
+
+/// Opacity of the black overlay on dimmed outputs.
+pub const DIM_ALPHA: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IdleStage {
+    Dim,
+    Lock,
+    PowerOffMonitors,
+    /// Idle hook from the config, by index.
+    Hook(usize),
+}
+
+/// Returns the idle stages enabled in the config with their timeouts, in the order they run.
+///
+/// Stages with the same timeout run in a fixed order: dim, lock, power off the monitors, then
+/// the idle hooks in the config order.
+fn idle_stages(config: &Config) -> Vec<(u32, IdleStage)> {
+    let idle = &config.idle;
+    let lock_timeout_ms = idle.lock_command.as_ref().and(idle.lock_timeout_ms);
+    let builtin = [
+        (idle.dim_timeout_ms, IdleStage::Dim),
+        (lock_timeout_ms, IdleStage::Lock),
+        (idle.power_off_monitors_timeout_ms, IdleStage::PowerOffMonitors),
+    ];
+
+    let mut stages: Vec<_> = builtin
+        .into_iter()
+        .filter_map(|(timeout_ms, stage)| Some((timeout_ms?, stage)))
+        .collect();
+    let hooks = config.hooks.idle.iter().enumerate();
+    stages.extend(hooks.map(|(idx, hook)| (hook.timeout_ms, IdleStage::Hook(idx))));
+
+    // The sort is stable, so it keeps the order above for equal timeouts.
+    stages.sort_by_key(|(timeout_ms, _)| *timeout_ms);
+    stages
+}
+
+impl Niri {
+    /// Restarts the idle timers and undims the outputs, for example after user activity.
+    pub fn reset_idle_timers(&mut self) {
+        for token in self.idle_timers.drain(..).flatten() {
+            self.event_loop.remove(token);
+        }
+
+        if self.is_idle_dimmed {
+            self.is_idle_dimmed = false;
+            self.queue_redraw_all();
+        }
+
+        let stages = idle_stages(&self.config.borrow());
+        for (timeout_ms, stage) in stages {
+            let idx = self.idle_timers.len();
+            let timer = Timer::from_duration(Duration::from_millis(u64::from(timeout_ms)));
+            let token = self
+                .event_loop
+                .insert_source(timer, move |_, _, state| {
+                    state.niri.idle_timers[idx] = None;
+                    // The stages skipped here are re-armed once the inhibitor goes away.
+                    if !state.niri.is_idle_inhibited() {
+                        state.run_idle_stage(stage);
+                    }
+                    TimeoutAction::Drop
+                })
+                .unwrap();
+            self.idle_timers.push(Some(token));
+        }
+    }
+}
+
+impl State {
+    fn run_idle_stage(&mut self, stage: IdleStage) {
+        debug!("reached idle stage {stage:?}");
+
+        match stage {
+            IdleStage::Dim => {
+                // Don't dim videos and games, which are watched without touching the input.
+                if self.niri.has_visible_video_or_game() {
+                    return;
+                }
+
+                self.niri.is_idle_dimmed = true;
+                self.niri.queue_redraw_all();
+            }
+            IdleStage::Lock => {
+                if self.niri.is_locked() {
+                    return;
+                }
+
+                let config = self.niri.config.borrow();
+                if let Some(command) = &config.idle.lock_command {
+                    spawn(command.clone(), None);
+                }
+            }
+            IdleStage::PowerOffMonitors => {
+                self.niri.deactivate_monitors(&mut self.backend);
+            }
+            IdleStage::Hook(idx) => {
+                let config = self.niri.config.borrow();
+                if let Some(hook) = config.hooks.idle.get(idx) {
+                    spawn_with_env(hook.command.clone(), Vec::new());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stages(config: &str) -> Vec<(u32, IdleStage)> {
+        let config = Config::parse("test.kdl", config).unwrap();
+        idle_stages(&config)
+    }
+
+    #[test]
+    fn no_stages_by_default() {
+        assert!(stages("").is_empty());
+    }
+
+    #[test]
+    fn stages_sorted_by_timeout() {
+        let config = r#"
+            idle {
+                dim-timeout-ms 300
+                lock-timeout-ms 200
+                lock-command "swaylock"
+                power-off-monitors-timeout-ms 100
+            }
+
+            hooks {
+                idle timeout-ms=250 "first"
+                idle timeout-ms=50 "second"
+            }
+        "#;
+        assert_eq!(
+            stages(config),
+            [
+                (50, IdleStage::Hook(1)),
+                (100, IdleStage::PowerOffMonitors),
+                (200, IdleStage::Lock),
+                (250, IdleStage::Hook(0)),
+                (300, IdleStage::Dim),
+            ]
+        );
+    }
+
+    #[test]
+    fn equal_timeouts_keep_fixed_order() {
+        let config = r#"
+            idle {
+                dim-timeout-ms 100
+                lock-timeout-ms 100
+                lock-command "swaylock"
+                power-off-monitors-timeout-ms 100
+            }
+
+            hooks {
+                idle timeout-ms=100 "first"
+                idle timeout-ms=100 "second"
+            }
+        "#;
+        assert_eq!(
+            stages(config),
+            [
+                (100, IdleStage::Dim),
+                (100, IdleStage::Lock),
+                (100, IdleStage::PowerOffMonitors),
+                (100, IdleStage::Hook(0)),
+                (100, IdleStage::Hook(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn lock_needs_command() {
+        let config = r#"
+            idle {
+                dim-timeout-ms 100
+                lock-timeout-ms 200
+            }
+        "#;
+        assert_eq!(stages(config), [(100, IdleStage::Dim)]);
+    }
+}
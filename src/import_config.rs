@@ -0,0 +1,1374 @@
+//! Conversion of sway and i3 configs into niri configs.
+//!
+//! Only the directives with a close niri equivalent are converted: key binds and modes, startup
+//! commands, outputs, gaps, borders, simple window rules and the common input settings. Everything
+//! else ends up in the output as a comment, so that nothing is dropped silently.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use clap::ValueEnum;
+
+/// Compositor whose config is converted.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportSource {
+    Sway,
+    I3,
+}
+
+impl ImportSource {
+    fn name(self) -> &'static str {
+        match self {
+            ImportSource::Sway => "sway",
+            ImportSource::I3 => "i3",
+        }
+    }
+}
+
+/// Converts the text of a sway or i3 config into the text of a niri config.
+pub fn convert(source: ImportSource, text: &str) -> String {
+    let mut converter = Converter::new(source);
+    for line in logical_lines(text) {
+        converter.line(&line);
+    }
+    converter.finish()
+}
+
+/// Joins continued lines and drops comments and empty lines.
+fn logical_lines(text: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+        if current.is_empty() && (line.is_empty() || line.starts_with('#')) {
+            continue;
+        }
+
+        if let Some(line) = line.strip_suffix('\\') {
+            current.push_str(line);
+            current.push(' ');
+            continue;
+        }
+
+        current.push_str(line);
+        lines.push(std::mem::take(&mut current));
+    }
+
+    if !current.trim().is_empty() {
+        lines.push(current);
+    }
+
+    lines
+}
+
+/// Token of a config line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Token {
+    /// Token text with the quotes removed.
+    text: String,
+    /// Byte offset of the token in the line.
+    start: usize,
+}
+
+/// Splits a line into tokens, keeping quoted strings and `[criteria]` together.
+fn tokenize(line: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = line.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let mut text = String::new();
+        let mut quote = None;
+        // Criteria are kept verbatim with their quotes, they are tokenized again separately.
+        let in_criteria = c == '[';
+        while let Some((_, c)) = chars.next() {
+            if in_criteria {
+                text.push(c);
+                match quote {
+                    Some(q) if c == q => quote = None,
+                    Some(_) => (),
+                    None if c == '"' || c == '\'' => quote = Some(c),
+                    None if c == ']' => break,
+                    None => (),
+                }
+                continue;
+            }
+
+            match quote {
+                Some(q) if c == q => quote = None,
+                Some(_) if c == '\\' => {
+                    if let Some((_, escaped)) = chars.next() {
+                        text.push(escaped);
+                    }
+                }
+                Some(_) => text.push(c),
+                None if c == '"' || c == '\'' => quote = Some(c),
+                None if c.is_whitespace() => break,
+                None => text.push(c),
+            }
+        }
+
+        tokens.push(Token { text, start });
+    }
+
+    tokens
+}
+
+/// Quotes a string for KDL.
+fn quote(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Converts a command run through the shell into a `spawn` action.
+fn spawn(command: &str) -> String {
+    let command = command.trim();
+    let needs_shell = command
+        .chars()
+        .any(|c| "$|&;<>()`'\"\\*?~{}[]#=".contains(c));
+
+    let args: Vec<String> = if needs_shell {
+        vec![quote("sh"), quote("-c"), quote(command)]
+    } else {
+        command.split_whitespace().map(quote).collect()
+    };
+
+    format!("spawn {}", args.join(" "))
+}
+
+/// Workspace reference as a `focus-workspace`-like argument.
+///
+/// niri only refers to workspaces by index or by the name of a named workspace, so other names
+/// are returned as `None`.
+fn workspace_index(name: &str) -> Option<u8> {
+    name.parse().ok()
+}
+
+fn is_enabled(value: &str) -> Option<bool> {
+    match value {
+        "enabled" | "enable" | "yes" | "on" | "true" => Some(true),
+        "disabled" | "disable" | "no" | "off" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// Key binds of the default mode or of a named mode.
+#[derive(Debug, Default)]
+struct Binds {
+    /// Normalized key and the bind, or `None` and a comment.
+    entries: Vec<(Option<String>, String)>,
+}
+
+impl Binds {
+    fn push(&mut self, key: String, bind: String) {
+        // As in sway, a later bind of the same key overrides the earlier one.
+        let normalized = normalize_key(&key);
+        self.entries
+            .retain(|(other, _)| other.as_ref() != Some(&normalized));
+        self.entries.push((Some(normalized), bind));
+    }
+
+    fn comment(&mut self, line: &str) {
+        self.entries
+            .push((None, format!("// Not converted: {line}")));
+    }
+
+    fn write(&self, out: &mut String, indent: &str) {
+        for (_, entry) in &self.entries {
+            let _ = writeln!(out, "{indent}{entry}");
+        }
+    }
+}
+
+/// Normalizes a niri key for finding duplicate binds.
+fn normalize_key(key: &str) -> String {
+    let mut parts: Vec<String> = key.split('+').map(str::to_ascii_lowercase).collect();
+    let trigger = parts.pop().unwrap_or_default();
+    parts.sort();
+    parts.push(trigger);
+    parts.join("+")
+}
+
+/// Converts a sway key combination like `Mod4+Shift+q` to niri syntax.
+fn convert_key(key: &str) -> Option<String> {
+    let mut parts: Vec<&str> = key.split('+').collect();
+    let trigger = parts.pop()?;
+
+    // Mouse buttons have different names in niri and are usually bound with extra flags.
+    if trigger.to_ascii_lowercase().starts_with("button") || trigger.starts_with("BTN_") {
+        return None;
+    }
+
+    let mut converted = Vec::new();
+    for part in parts {
+        let modifier = match &*part.to_ascii_lowercase() {
+            "mod4" | "super" => "Super",
+            "mod1" | "alt" => "Alt",
+            "shift" => "Shift",
+            "control" | "ctrl" => "Ctrl",
+            "mod5" => "ISO_Level3_Shift",
+            "mod3" => "ISO_Level5_Shift",
+            _ => return None,
+        };
+        converted.push(modifier);
+    }
+    converted.push(trigger);
+
+    Some(converted.join("+"))
+}
+
+/// Parsed criteria of `for_window` and `assign`, as properties of a niri `match`.
+fn convert_criteria(criteria: &str) -> Option<String> {
+    let inner = criteria.strip_prefix('[')?.strip_suffix(']')?;
+
+    let mut props = Vec::new();
+    for token in tokenize(inner) {
+        let (key, value) = match token.text.split_once('=') {
+            Some((key, value)) => (key, Some(value.trim_matches('"').trim_matches('\''))),
+            None => (&*token.text, None),
+        };
+
+        let prop = match (key, value) {
+            // X11 windows get their class as the app id in niri.
+            ("app_id" | "class", Some(value)) if value != "__focused__" => {
+                format!("app-id={}", quote(value))
+            }
+            ("title", Some(value)) if value != "__focused__" => format!("title={}", quote(value)),
+            ("floating", None) => String::from("is-floating=true"),
+            ("tiling", None) => String::from("is-floating=false"),
+            ("urgent", Some("latest" | "oldest")) => String::from("is-urgent=true"),
+            _ => return None,
+        };
+        props.push(prop);
+    }
+
+    if props.is_empty() {
+        return None;
+    }
+
+    Some(props.join(" "))
+}
+
+/// Splits command tokens into separate commands at `,` and `;`.
+fn split_commands(tokens: &[Token]) -> Vec<Vec<Token>> {
+    let mut commands = vec![Vec::new()];
+    for token in tokens {
+        let (text, ends_command) = match token.text.strip_suffix([',', ';']) {
+            Some(text) => (text, true),
+            None => (&*token.text, false),
+        };
+        if !text.is_empty() {
+            commands.last_mut().unwrap().push(Token {
+                text: text.to_owned(),
+                start: token.start,
+            });
+        }
+        if ends_command {
+            commands.push(Vec::new());
+        }
+    }
+    commands.retain(|command| !command.is_empty());
+    commands
+}
+
+/// Settings that niri supports in the `mouse` section.
+const MOUSE_SETTINGS: &[&str] = &[
+    "off",
+    "natural-scroll",
+    "accel-speed",
+    "accel-profile",
+    "scroll-method",
+    "scroll-button",
+    "left-handed",
+    "middle-emulation",
+    "scroll-factor",
+];
+
+/// Direction suffix for niri action names.
+fn direction(dir: &str) -> Option<&'static str> {
+    match dir {
+        "left" => Some("left"),
+        "right" => Some("right"),
+        "up" => Some("up"),
+        "down" => Some("down"),
+        _ => None,
+    }
+}
+
+/// Converts a `resize grow|shrink` command to a `set-column-width` or `set-window-height` action.
+fn convert_resize(args: &[&str]) -> Option<String> {
+    let [how, what, rest @ ..] = args else {
+        return None;
+    };
+
+    let sign = match *how {
+        "grow" => "+",
+        "shrink" => "-",
+        _ => return None,
+    };
+    let action = match *what {
+        "width" | "left" | "right" => "set-column-width",
+        "height" | "up" | "down" => "set-window-height",
+        _ => return None,
+    };
+
+    // Tiled windows resize by the `ppt` amount, so prefer it when it's given.
+    let amount = match rest {
+        [] => String::from("10"),
+        [amount] | [amount, "px"] => amount.trim_end_matches("px").to_owned(),
+        [amount, "ppt"] | [_, "px", "or", amount, "ppt"] => format!("{amount}%"),
+        _ => return None,
+    };
+    let number = amount.trim_end_matches('%');
+    if number.parse::<f64>().is_err() {
+        return None;
+    }
+
+    Some(format!("{action} {}", quote(&format!("{sign}{amount}"))))
+}
+
+#[derive(Debug)]
+struct Output {
+    name: String,
+    nodes: Vec<String>,
+}
+
+struct Converter {
+    source: ImportSource,
+    variables: Vec<(String, String)>,
+    /// Name of the mode block being read, if any.
+    mode: Option<String>,
+    /// Header of the generic block being read, if any, like `output eDP-1`.
+    block: Option<String>,
+    /// Depth of the ignored block being read, like `bar {}`.
+    skip_depth: usize,
+    keyboard: Vec<String>,
+    touchpad: Vec<String>,
+    mouse: Vec<String>,
+    input: Vec<String>,
+    cursor: Vec<String>,
+    outputs: Vec<Output>,
+    layout: Vec<String>,
+    struts: HashMap<&'static str, String>,
+    spawn_at_startup: Vec<String>,
+    window_rules: Vec<String>,
+    binds: Binds,
+    modes: Vec<(String, Binds)>,
+    workspaces: Vec<(String, Option<String>)>,
+    unconverted: Vec<String>,
+}
+
+impl Converter {
+    fn new(source: ImportSource) -> Self {
+        Self {
+            source,
+            variables: Vec::new(),
+            mode: None,
+            block: None,
+            skip_depth: 0,
+            keyboard: Vec::new(),
+            touchpad: Vec::new(),
+            mouse: Vec::new(),
+            input: Vec::new(),
+            cursor: Vec::new(),
+            outputs: Vec::new(),
+            layout: Vec::new(),
+            struts: HashMap::new(),
+            spawn_at_startup: Vec::new(),
+            window_rules: Vec::new(),
+            binds: Binds::default(),
+            modes: Vec::new(),
+            workspaces: Vec::new(),
+            unconverted: Vec::new(),
+        }
+    }
+
+    fn substitute(&self, line: &str) -> String {
+        let mut line = line.to_owned();
+        // Longest names first, so that `$mod` doesn't replace the start of `$mod_alt`.
+        let mut variables: Vec<_> = self.variables.iter().collect();
+        variables.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+        for (name, value) in variables {
+            line = line.replace(name.as_str(), value);
+        }
+        line
+    }
+
+    fn unconverted(&mut self, line: &str) {
+        if self.mode.is_some() {
+            self.mode_binds().comment(line);
+        } else {
+            self.unconverted.push(line.to_owned());
+        }
+    }
+
+    /// Returns the binds of the mode being read.
+    fn mode_binds(&mut self) -> &mut Binds {
+        let Some(mode) = self.mode.clone() else {
+            return &mut self.binds;
+        };
+
+        match self.modes.iter().position(|(name, _)| *name == mode) {
+            Some(index) => &mut self.modes[index].1,
+            None => {
+                self.modes.push((mode, Binds::default()));
+                &mut self.modes.last_mut().unwrap().1
+            }
+        }
+    }
+
+    fn line(&mut self, line: &str) {
+        if self.skip_depth > 0 {
+            if line.ends_with('{') {
+                self.skip_depth += 1;
+            } else if line == "}" {
+                self.skip_depth -= 1;
+            }
+            self.unconverted.push(line.to_owned());
+            return;
+        }
+
+        if line == "}" {
+            if self.block.take().is_none() {
+                self.mode = None;
+            }
+            return;
+        }
+
+        if let Some(header) = line.strip_suffix('{') {
+            let header = self.substitute(header.trim());
+            let tokens = tokenize(&header);
+            match tokens.first().map(|t| &*t.text) {
+                Some("mode") if tokens.len() >= 2 => {
+                    // Flags like --pango_markup come before the name.
+                    let name = &tokens.last().unwrap().text;
+                    self.mode = Some(name.clone());
+                    self.mode_binds();
+                }
+                Some("bar") => {
+                    self.unconverted.push(line.to_owned());
+                    self.skip_depth = 1;
+                }
+                _ => self.block = Some(header),
+            }
+            return;
+        }
+
+        let line = match &self.block {
+            Some(header) => format!("{header} {line}"),
+            None => line.to_owned(),
+        };
+
+        let tokens = tokenize(&line);
+        let Some(first) = tokens.first() else {
+            return;
+        };
+
+        // Variables are substituted before anything else, like in sway.
+        if first.text == "set" {
+            if let [_, name, value, ..] = &tokens[..] {
+                let value = line[value.start..].trim().trim_matches('"').to_owned();
+                let value = self.substitute(&value);
+                self.variables.push((name.text.clone(), value));
+            }
+            return;
+        }
+
+        let line = self.substitute(&line);
+        let tokens = tokenize(&line);
+        if !self.directive(&line, &tokens) {
+            self.unconverted(&line);
+        }
+    }
+
+    /// Converts a directive, returns `false` if it can't be converted.
+    fn directive(&mut self, line: &str, tokens: &[Token]) -> bool {
+        let args: Vec<&str> = tokens.iter().map(|t| &*t.text).collect();
+
+        match &args[..] {
+            ["bindsym", ..] => self.bindsym(line, tokens),
+            ["exec" | "exec_always", rest @ ..] if !rest.is_empty() => {
+                let mut start = tokens[1].start;
+                if rest[0] == "--no-startup-id" {
+                    let Some(token) = tokens.get(2) else {
+                        return false;
+                    };
+                    start = token.start;
+                }
+                let command = unquote_rest(&line[start..]);
+                // spawn-at-startup takes the arguments of the spawn action.
+                let spawn = spawn(&command);
+                let args = spawn.strip_prefix("spawn ").unwrap();
+                self.spawn_at_startup
+                    .push(format!("spawn-at-startup {args}"));
+                true
+            }
+            ["output", name, rest @ ..] if self.source == ImportSource::Sway => {
+                self.output(name, rest)
+            }
+            ["input", identifier, rest @ ..] if self.source == ImportSource::Sway => {
+                self.input(identifier, rest)
+            }
+            ["seat", _, rest @ ..] if self.source == ImportSource::Sway => self.seat(rest),
+            ["gaps", rest @ ..] => self.gaps(rest),
+            ["smart_gaps", value] => match is_enabled(value) {
+                Some(true) => {
+                    self.layout.push(String::from("smart-gaps"));
+                    true
+                }
+                Some(false) => true,
+                None => false,
+            },
+            ["default_border" | "new_window", style, rest @ ..] => {
+                let width = match (*style, rest) {
+                    ("none", []) => None,
+                    ("pixel" | "normal", []) => Some("2"),
+                    ("pixel" | "normal", [width]) if width.parse::<f64>().is_ok() => Some(*width),
+                    _ => return false,
+                };
+                if let Some(width) = width {
+                    self.layout
+                        .push(format!("border {{\n        width {width}\n    }}"));
+                }
+                // sway shows focus with the border color instead of a separate ring.
+                self.layout
+                    .push(String::from("focus-ring {\n        off\n    }"));
+                true
+            }
+            ["focus_follows_mouse", value] => match *value {
+                "yes" | "always" => {
+                    self.input.push(String::from("focus-follows-mouse"));
+                    true
+                }
+                "no" => true,
+                _ => false,
+            },
+            ["mouse_warping", value] => match *value {
+                "container" => {
+                    self.input.push(String::from("warp-mouse-to-focus"));
+                    true
+                }
+                "none" => true,
+                _ => false,
+            },
+            ["workspace_auto_back_and_forth", value] => match is_enabled(value) {
+                Some(true) => {
+                    self.input
+                        .push(String::from("workspace-auto-back-and-forth"));
+                    true
+                }
+                Some(false) => true,
+                None => false,
+            },
+            ["workspace", name, "output", output, ..] => {
+                // Numbered workspaces are dynamic in niri, only named ones can be configured.
+                if workspace_index(name).is_some() {
+                    return false;
+                }
+                self.declare_workspace(name, Some(output));
+                true
+            }
+            ["for_window", criteria, ..] => {
+                let Some(matcher) = convert_criteria(criteria) else {
+                    return false;
+                };
+                self.window_rule(&matcher, &tokens[2..])
+            }
+            ["assign", criteria, rest @ ..] => {
+                let Some(matcher) = convert_criteria(criteria) else {
+                    return false;
+                };
+                let rest = match rest {
+                    ["→", rest @ ..] => rest,
+                    rest => rest,
+                };
+                let name = match rest {
+                    ["workspace", "number", name] | ["workspace", name] | [name] => *name,
+                    _ => return false,
+                };
+                if workspace_index(name).is_some() {
+                    return false;
+                }
+                self.declare_workspace(name, None);
+                self.window_rules.push(format!(
+                    "window-rule {{\n    match {matcher}\n    open-on-workspace {}\n}}",
+                    quote(name)
+                ));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn declare_workspace(&mut self, name: &str, output: Option<&str>) {
+        match self.workspaces.iter_mut().find(|(other, _)| other == name) {
+            Some((_, existing)) => {
+                if let Some(output) = output {
+                    *existing = Some(output.to_owned());
+                }
+            }
+            None => self
+                .workspaces
+                .push((name.to_owned(), output.map(String::from))),
+        }
+    }
+
+    fn bindsym(&mut self, line: &str, tokens: &[Token]) -> bool {
+        let mut props = Vec::new();
+        let mut locked = false;
+        let mut index = 1;
+        while let Some(flag) = tokens.get(index).and_then(|t| t.text.strip_prefix("--")) {
+            match flag {
+                "to-code" | "no-warn" => (),
+                "locked" => locked = true,
+                "release" => props.push("on-release=true"),
+                "no-repeat" => props.push("repeat=false"),
+                "inhibited" => props.push("allow-inhibiting=false"),
+                _ => {
+                    self.mode_binds().comment(line);
+                    return true;
+                }
+            }
+            index += 1;
+        }
+
+        let Some(key) = tokens.get(index).and_then(|t| convert_key(&t.text)) else {
+            self.mode_binds().comment(line);
+            return true;
+        };
+
+        let command = &tokens[index + 1..];
+        let action = match &split_commands(command)[..] {
+            [command] => self.action(line, command),
+            _ => None,
+        };
+        let Some(action) = action else {
+            self.mode_binds().comment(line);
+            return true;
+        };
+
+        // niri allows only spawn binds on the lock screen.
+        if locked && action.starts_with("spawn ") {
+            props.push("allow-when-locked=true");
+        }
+
+        let mut bind = key.clone();
+        for prop in props {
+            bind.push(' ');
+            bind.push_str(prop);
+        }
+        let _ = write!(bind, " {{ {action}; }}");
+
+        self.mode_binds().push(key, bind);
+        true
+    }
+
+    /// Converts a bind command to a niri action.
+    fn action(&mut self, line: &str, command: &[Token]) -> Option<String> {
+        let args: Vec<&str> = command.iter().map(|t| &*t.text).collect();
+
+        let action = match &args[..] {
+            ["exec", rest @ ..] if !rest.is_empty() => {
+                let mut start = command[1].start;
+                if rest[0] == "--no-startup-id" {
+                    start = command.get(2)?.start;
+                }
+                spawn(&unquote_rest(&line[start..]))
+            }
+            ["kill"] => String::from("close-window"),
+            ["exit"] => String::from("quit"),
+            ["fullscreen"] | ["fullscreen", "toggle"] => String::from("fullscreen-window"),
+            ["floating", "toggle"] => String::from("toggle-window-floating"),
+            ["focus", "mode_toggle"] => String::from("switch-focus-between-floating-and-tiling"),
+            ["focus", "left" | "right"] => format!("focus-column-{}", args[1]),
+            ["focus", "up" | "down"] => format!("focus-window-{}", args[1]),
+            ["focus", "output", dir] => match direction(dir) {
+                Some(dir) => format!("focus-monitor-{dir}"),
+                None => format!("focus-monitor {}", quote(dir)),
+            },
+            ["move", "left" | "right"] => format!("move-column-{}", args[1]),
+            ["move", "up" | "down"] => format!("move-window-{}", args[1]),
+            ["move", "workspace", "to", "output", dir] | ["move", "workspace", "to", dir] => {
+                match direction(dir) {
+                    Some(dir) => format!("move-workspace-to-monitor-{dir}"),
+                    None => format!("move-workspace-to-monitor {}", quote(dir)),
+                }
+            }
+            ["move", "container" | "window", "to", "output", dir] => match direction(dir) {
+                Some(dir) => format!("move-window-to-monitor-{dir}"),
+                None => format!("move-window-to-monitor {}", quote(dir)),
+            },
+            ["move", rest @ ..] => {
+                let rest = match rest {
+                    ["container" | "window", "to", rest @ ..] | ["to", rest @ ..] => rest,
+                    rest => rest,
+                };
+                let name = match rest {
+                    ["workspace", "number", name] | ["workspace", name] => *name,
+                    _ => return None,
+                };
+                match name {
+                    "next" | "next_on_output" => String::from("move-window-to-workspace-down"),
+                    "prev" | "prev_on_output" => String::from("move-window-to-workspace-up"),
+                    name => format!("move-window-to-workspace {}", self.workspace_arg(name)?),
+                }
+            }
+            ["workspace", rest @ ..] => {
+                let rest = match rest {
+                    ["--no-auto-back-and-forth", rest @ ..] => rest,
+                    rest => rest,
+                };
+                let name = match rest {
+                    ["number", name] | [name] => *name,
+                    _ => return None,
+                };
+                match name {
+                    "next" | "next_on_output" => String::from("focus-workspace-down"),
+                    "prev" | "prev_on_output" => String::from("focus-workspace-up"),
+                    "back_and_forth" => String::from("focus-workspace-previous"),
+                    name => format!("focus-workspace {}", self.workspace_arg(name)?),
+                }
+            }
+            ["layout", "tabbed" | "stacking"] => String::from("set-column-display \"tabbed\""),
+            ["layout", "splith" | "splitv" | "default"] => {
+                String::from("set-column-display \"normal\"")
+            }
+            ["layout", "toggle", rest @ ..]
+                if rest.iter().any(|x| *x == "tabbed" || *x == "stacking") =>
+            {
+                String::from("toggle-column-tabbed-display")
+            }
+            ["mode", name] => match *name {
+                "default" => String::from("exit-mode"),
+                name => format!("enter-mode {}", quote(name)),
+            },
+            ["resize", rest @ ..] => convert_resize(rest)?,
+            _ => return None,
+        };
+
+        Some(action)
+    }
+
+    /// Returns the argument for a workspace action, declaring named workspaces.
+    fn workspace_arg(&mut self, name: &str) -> Option<String> {
+        if let Some(index) = workspace_index(name) {
+            return Some(index.to_string());
+        }
+        if name == "back_and_forth" || name.starts_with("next") || name.starts_with("prev") {
+            return None;
+        }
+        self.declare_workspace(name, None);
+        Some(quote(name))
+    }
+
+    fn window_rule(&mut self, matcher: &str, command: &[Token]) -> bool {
+        let mut nodes = Vec::new();
+        for command in split_commands(command) {
+            let args: Vec<&str> = command.iter().map(|t| &*t.text).collect();
+            let node = match &args[..] {
+                ["floating", "enable"] => String::from("open-floating true"),
+                ["floating", "disable"] => String::from("open-floating false"),
+                ["fullscreen"] | ["fullscreen", "enable"] => String::from("open-fullscreen true"),
+                ["opacity", "set", value] | ["opacity", value] if value.parse::<f64>().is_ok() => {
+                    format!("opacity {value}")
+                }
+                ["border", "none"] => String::from("border {\n        off\n    }"),
+                ["border", "pixel" | "normal", width] if width.parse::<f64>().is_ok() => {
+                    format!("border {{\n        on\n        width {width}\n    }}")
+                }
+                ["move", rest @ ..] => {
+                    let rest = match rest {
+                        ["container" | "window", "to", rest @ ..] | ["to", rest @ ..] => rest,
+                        rest => rest,
+                    };
+                    let name = match rest {
+                        ["workspace", "number", name] | ["workspace", name] => *name,
+                        _ => return false,
+                    };
+                    // Only named workspaces can be opened on.
+                    if workspace_index(name).is_some() {
+                        return false;
+                    }
+                    self.declare_workspace(name, None);
+                    format!("open-on-workspace {}", quote(name))
+                }
+                _ => return false,
+            };
+            nodes.push(node);
+        }
+
+        if nodes.is_empty() {
+            return false;
+        }
+
+        let mut rule = format!("window-rule {{\n    match {matcher}\n");
+        for node in nodes {
+            let _ = writeln!(rule, "    {node}");
+        }
+        rule.push('}');
+        self.window_rules.push(rule);
+        true
+    }
+
+    fn output(&mut self, name: &str, args: &[&str]) -> bool {
+        // niri matches outputs by their name, there's no catch-all output section.
+        if name == "*" {
+            return false;
+        }
+
+        let mut nodes = Vec::new();
+        let mut rest = args;
+        while !rest.is_empty() {
+            let (node, tail) = match rest {
+                ["mode" | "resolution" | "res", mode, tail @ ..] if !mode.starts_with("--") => {
+                    let mode = mode.trim_end_matches("Hz");
+                    (format!("mode {}", quote(mode)), tail)
+                }
+                ["pos" | "position", x, y, tail @ ..]
+                    if x.parse::<i32>().is_ok() && y.parse::<i32>().is_ok() =>
+                {
+                    (format!("position x={x} y={y}"), tail)
+                }
+                ["scale", scale, tail @ ..] if scale.parse::<f64>().is_ok() => {
+                    (format!("scale {scale}"), tail)
+                }
+                ["transform", transform, tail @ ..]
+                    if matches!(
+                        *transform,
+                        "normal"
+                            | "90"
+                            | "180"
+                            | "270"
+                            | "flipped"
+                            | "flipped-90"
+                            | "flipped-180"
+                            | "flipped-270"
+                    ) && !matches!(tail.first(), Some(&"clockwise" | &"anticlockwise")) =>
+                {
+                    (format!("transform {}", quote(transform)), tail)
+                }
+                ["disable", tail @ ..] => (String::from("off"), tail),
+                ["enable", tail @ ..] => (String::new(), tail),
+                ["adaptive_sync", value, tail @ ..] => match is_enabled(value) {
+                    Some(true) => (String::from("variable-refresh-rate"), tail),
+                    Some(false) => (String::new(), tail),
+                    None => return false,
+                },
+                ["bg" | "background", path, mode, tail @ ..]
+                    if matches!(*mode, "fill" | "fit" | "tile") =>
+                {
+                    (
+                        format!("wallpaper {} mode={}", quote(path), quote(mode)),
+                        tail,
+                    )
+                }
+                _ => return false,
+            };
+            if !node.is_empty() {
+                nodes.push(node);
+            }
+            rest = tail;
+        }
+
+        match self.outputs.iter_mut().find(|output| output.name == name) {
+            Some(output) => output.nodes.extend(nodes),
+            None => self.outputs.push(Output {
+                name: name.to_owned(),
+                nodes,
+            }),
+        }
+        true
+    }
+
+    fn input(&mut self, identifier: &str, args: &[&str]) -> bool {
+        let node = match args {
+            ["xkb_layout", value] => Some(("keyboard", format!("layout {}", quote(value)))),
+            ["xkb_variant", value] => Some(("keyboard", format!("variant {}", quote(value)))),
+            ["xkb_options", value] => Some(("keyboard", format!("options {}", quote(value)))),
+            ["xkb_model", value] => Some(("keyboard", format!("model {}", quote(value)))),
+            ["xkb_rules", value] => Some(("keyboard", format!("rules {}", quote(value)))),
+            ["xkb_file", value] => Some(("keyboard", format!("file {}", quote(value)))),
+            ["repeat_delay", value] if value.parse::<u16>().is_ok() => {
+                Some(("keyboard", format!("repeat-delay {value}")))
+            }
+            ["repeat_rate", value] if value.parse::<u8>().is_ok() => {
+                Some(("keyboard", format!("repeat-rate {value}")))
+            }
+            ["xkb_numlock", value] => match is_enabled(value) {
+                Some(true) => Some(("keyboard", String::from("numlock"))),
+                Some(false) => None,
+                None => return false,
+            },
+            _ => {
+                let node = match args {
+                    ["events", "disabled"] => String::from("off"),
+                    ["events", "enabled"] => return true,
+                    ["tap" | "natural_scroll" | "dwt" | "dwtp" | "drag_lock" | "left_handed"
+                    | "middle_emulation", value] => match is_enabled(value) {
+                        Some(true) => args[0].replace('_', "-"),
+                        Some(false) => return true,
+                        None => return false,
+                    },
+                    ["drag", value] => match is_enabled(value) {
+                        Some(enabled) => format!("drag {enabled}"),
+                        None => return false,
+                    },
+                    ["pointer_accel", value] if value.parse::<f64>().is_ok() => {
+                        format!("accel-speed {value}")
+                    }
+                    ["accel_profile", value @ ("flat" | "adaptive")] => {
+                        format!("accel-profile {}", quote(value))
+                    }
+                    ["scroll_method", value] => {
+                        let method = match *value {
+                            "none" => "no-scroll",
+                            "two_finger" => "two-finger",
+                            "edge" => "edge",
+                            "on_button_down" => "on-button-down",
+                            _ => return false,
+                        };
+                        format!("scroll-method {}", quote(method))
+                    }
+                    ["scroll_button", value] if value.parse::<u32>().is_ok() => {
+                        format!("scroll-button {value}")
+                    }
+                    ["scroll_factor", value] if value.parse::<f64>().is_ok() => {
+                        format!("scroll-factor {value}")
+                    }
+                    ["click_method", value] => {
+                        let method = match *value {
+                            "clickfinger" => "clickfinger",
+                            "button_areas" => "button-areas",
+                            _ => return false,
+                        };
+                        format!("click-method {}", quote(method))
+                    }
+                    ["tap_button_map", value] => {
+                        let map = match *value {
+                            "lrm" => "left-right-middle",
+                            "lmr" => "left-middle-right",
+                            _ => return false,
+                        };
+                        format!("tap-button-map {}", quote(map))
+                    }
+                    _ => return false,
+                };
+
+                let section = match identifier {
+                    "type:touchpad" => "touchpad",
+                    "type:pointer" => "mouse",
+                    _ => return false,
+                };
+                // Mice don't support the touchpad-specific settings.
+                let name = node.split(' ').next().unwrap();
+                if section == "mouse" && !MOUSE_SETTINGS.contains(&name) {
+                    return false;
+                }
+                Some((section, node))
+            }
+        };
+
+        let Some((section, node)) = node else {
+            return true;
+        };
+
+        // Keyboard settings are global in niri, so they only convert for all keyboards.
+        if section == "keyboard" && identifier != "*" && identifier != "type:keyboard" {
+            return false;
+        }
+
+        match section {
+            "keyboard" => self.keyboard.push(node),
+            "touchpad" => self.touchpad.push(node),
+            _ => self.mouse.push(node),
+        }
+        true
+    }
+
+    fn seat(&mut self, args: &[&str]) -> bool {
+        match args {
+            ["xcursor_theme", theme, rest @ ..] => {
+                self.cursor.push(format!("xcursor-theme {}", quote(theme)));
+                match rest {
+                    [] => (),
+                    [size] if size.parse::<u8>().is_ok() => {
+                        self.cursor.push(format!("xcursor-size {size}"));
+                    }
+                    _ => return false,
+                }
+                true
+            }
+            ["hide_cursor", "when-typing", value] => match is_enabled(value) {
+                Some(true) => {
+                    self.cursor.push(String::from("hide-when-typing"));
+                    true
+                }
+                Some(false) => true,
+                None => false,
+            },
+            ["hide_cursor", ms] if ms.parse::<u32>().is_ok() => {
+                if *ms != "0" {
+                    self.cursor.push(format!("hide-after-inactive-ms {ms}"));
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn gaps(&mut self, args: &[&str]) -> bool {
+        let [kind, amount] = args else {
+            // Runtime forms like `gaps inner all set 10` don't apply to the config.
+            return false;
+        };
+        if amount.parse::<f64>().is_err() {
+            return false;
+        }
+
+        let sides: &[&'static str] = match *kind {
+            "inner" => {
+                self.layout.push(format!("gaps {amount}"));
+                return true;
+            }
+            "outer" => &["left", "right", "top", "bottom"],
+            "horizontal" => &["left", "right"],
+            "vertical" => &["top", "bottom"],
+            "left" => &["left"],
+            "right" => &["right"],
+            "top" => &["top"],
+            "bottom" => &["bottom"],
+            _ => return false,
+        };
+        for &side in sides {
+            self.struts.insert(side, (*amount).to_owned());
+        }
+        true
+    }
+
+    fn finish(mut self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "// Converted from a {} config by `niri import-config`.",
+            self.source.name()
+        );
+        out.push_str("// Check the result with `niri validate`.\n");
+
+        if !self.keyboard.is_empty()
+            || !self.touchpad.is_empty()
+            || !self.mouse.is_empty()
+            || !self.input.is_empty()
+        {
+            out.push_str("\ninput {\n");
+
+            let (xkb, keyboard): (Vec<_>, Vec<_>) = self.keyboard.iter().partition(|node| {
+                [
+                    "layout ", "variant ", "options ", "model ", "rules ", "file ",
+                ]
+                .iter()
+                .any(|prefix| node.starts_with(*prefix))
+            });
+            if !xkb.is_empty() || !keyboard.is_empty() {
+                out.push_str("    keyboard {\n");
+                if !xkb.is_empty() {
+                    out.push_str("        xkb {\n");
+                    for node in xkb {
+                        let _ = writeln!(out, "            {node}");
+                    }
+                    out.push_str("        }\n");
+                }
+                for node in keyboard {
+                    let _ = writeln!(out, "        {node}");
+                }
+                out.push_str("    }\n");
+            }
+
+            for (name, nodes) in [("touchpad", &self.touchpad), ("mouse", &self.mouse)] {
+                if nodes.is_empty() {
+                    continue;
+                }
+                let _ = writeln!(out, "    {name} {{");
+                for node in nodes {
+                    let _ = writeln!(out, "        {node}");
+                }
+                out.push_str("    }\n");
+            }
+
+            for node in &self.input {
+                let _ = writeln!(out, "    {node}");
+            }
+            out.push_str("}\n");
+        }
+
+        for output in &self.outputs {
+            let _ = writeln!(out, "\noutput {} {{", quote(&output.name));
+            for node in &output.nodes {
+                let _ = writeln!(out, "    {node}");
+            }
+            out.push_str("}\n");
+        }
+
+        if !self.struts.is_empty() {
+            let mut struts = String::from("struts {\n");
+            for side in ["left", "right", "top", "bottom"] {
+                if let Some(amount) = self.struts.get(side) {
+                    let _ = writeln!(struts, "        {side} {amount}");
+                }
+            }
+            struts.push_str("    }");
+            self.layout.push(struts);
+        }
+
+        if !self.layout.is_empty() {
+            out.push_str("\nlayout {\n");
+            for node in &self.layout {
+                let _ = writeln!(out, "    {node}");
+            }
+            out.push_str("}\n");
+        }
+
+        if !self.cursor.is_empty() {
+            out.push_str("\ncursor {\n");
+            for node in &self.cursor {
+                let _ = writeln!(out, "    {node}");
+            }
+            out.push_str("}\n");
+        }
+
+        for (name, output) in &self.workspaces {
+            match output {
+                Some(output) => {
+                    let _ = writeln!(
+                        out,
+                        "\nworkspace {} {{\n    open-on-output {}\n}}",
+                        quote(name),
+                        quote(output)
+                    );
+                }
+                None => {
+                    let _ = writeln!(out, "\nworkspace {}", quote(name));
+                }
+            }
+        }
+
+        if !self.spawn_at_startup.is_empty() {
+            out.push('\n');
+            for spawn in &self.spawn_at_startup {
+                let _ = writeln!(out, "{spawn}");
+            }
+        }
+
+        for rule in &self.window_rules {
+            let _ = writeln!(out, "\n{rule}");
+        }
+
+        out.push_str("\nbinds {\n");
+        self.binds.write(&mut out, "    ");
+        out.push_str("}\n");
+
+        for (name, binds) in &self.modes {
+            let _ = writeln!(out, "\nbinds-mode {} {{", quote(name));
+            binds.write(&mut out, "    ");
+            out.push_str("}\n");
+        }
+
+        if !self.unconverted.is_empty() {
+            out.push_str("\n// Not converted:\n");
+            for line in &self.unconverted {
+                let _ = writeln!(out, "// {line}");
+            }
+        }
+
+        out
+    }
+}
+
+/// Returns the rest of a line, without the quotes if it's a single quoted string.
+fn unquote_rest(rest: &str) -> String {
+    let rest = rest.trim();
+    let tokens = tokenize(rest);
+    match &tokens[..] {
+        [token] if rest.starts_with(['"', '\'']) => token.text.clone(),
+        _ => rest.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use niri_config::{Action, Config, Key, WorkspaceReference};
+
+    use super::*;
+
+    const SWAY_CONFIG: &str = r#"
+# Logo key.
+set $mod Mod4
+set $term foot
+set $left h
+
+output eDP-1 mode 1920x1080@60Hz pos 0 0 scale 1.5
+output HDMI-A-1 {
+    mode 2560x1440@144.000Hz
+    transform 90
+    adaptive_sync on
+}
+output * bg ~/wallpaper.png fill
+
+input type:touchpad {
+    tap enabled
+    natural_scroll enabled
+    click_method clickfinger
+}
+input * xkb_layout "us,ru"
+input * xkb_options grp:win_space_toggle
+
+seat seat0 xcursor_theme Adwaita 32
+focus_follows_mouse yes
+
+gaps inner 10
+gaps outer 5
+default_border pixel 2
+
+exec mako
+exec_always --no-startup-id "swayidle -w timeout 300 'swaylock -f'"
+
+for_window [app_id="pavucontrol"] floating enable
+for_window [class="^Steam$" title="Friends"] floating enable, border none
+for_window [shell="xwayland"] title_format "[X] %title"
+assign [app_id="firefox"] web
+workspace web output HDMI-A-1
+
+bindsym $mod+Return exec $term
+bindsym $mod+Shift+q kill
+bindsym $mod+$left focus left
+bindsym $mod+Shift+Right move right
+bindsym $mod+1 workspace number 1
+bindsym $mod+Shift+1 move container to workspace number 1
+bindsym $mod+w workspace web
+bindsym $mod+f fullscreen
+bindsym --locked XF86AudioMute exec pactl set-sink-mute @DEFAULT_SINK@ toggle
+bindsym $mod+Shift+c reload
+bindsym $mod+minus scratchpad show
+bindsym $mod+q kill
+bindsym $mod+Shift+q exec swaynag -t warning -m 'Exit?' -B 'Yes' 'swaymsg exit'
+bindsym $mod+r mode "resize"
+
+mode "resize" {
+    bindsym $left resize shrink width 10px
+    bindsym Right resize grow width 10 px or 10 ppt
+    bindsym Return mode "default"
+}
+
+bar {
+    position top
+    colors {
+        statusline #ffffff
+    }
+}
+"#;
+
+    fn key(key: &str) -> Key {
+        key.parse().unwrap()
+    }
+
+    #[test]
+    fn sway_config_converts_to_valid_config() {
+        let text = convert(ImportSource::Sway, SWAY_CONFIG);
+        let config = Config::parse("imported.kdl", &text).unwrap();
+
+        assert_eq!(config.layout.gaps.0, 10.);
+        assert_eq!(config.layout.struts.top.0, 5.);
+        assert!(!config.layout.border.off);
+        assert!(config.layout.focus_ring.off);
+        assert_eq!(config.input.keyboard.xkb.layout, "us,ru");
+        assert!(config.input.touchpad.tap);
+        assert!(config.input.focus_follows_mouse.is_some());
+        assert_eq!(config.cursor.xcursor_size, 32);
+        assert_eq!(config.outputs.0.len(), 2);
+        assert_eq!(config.spawn_at_startup.len(), 2);
+        assert_eq!(config.window_rules.len(), 3);
+        assert_eq!(config.workspaces.len(), 1);
+        assert_eq!(config.binds_modes.len(), 1);
+
+        let action = |k: &str| {
+            config
+                .binds
+                .0
+                .iter()
+                .find(|bind| bind.key == key(k))
+                .map(|bind| bind.action.clone())
+        };
+        assert_eq!(
+            action("Super+Return"),
+            Some(Action::Spawn(vec![String::from("foot")]))
+        );
+        assert_eq!(action("Super+H"), Some(Action::FocusColumnLeft));
+        assert_eq!(
+            action("Super+1"),
+            Some(Action::FocusWorkspace(WorkspaceReference::Index(1)))
+        );
+        assert_eq!(
+            action("Super+W"),
+            Some(Action::FocusWorkspace(WorkspaceReference::Name(
+                String::from("web")
+            )))
+        );
+        assert_eq!(action("Super+Minus"), None);
+        // The later bind of the same key wins, like in sway.
+        assert!(matches!(action("Super+Shift+Q"), Some(Action::Spawn(_))));
+    }
+
+    #[test]
+    fn unconvertible_directives_become_comments() {
+        let text = convert(ImportSource::Sway, SWAY_CONFIG);
+        assert!(text.contains("// Not converted: bindsym Mod4+Shift+c reload"));
+        assert!(text.contains("// Not converted: bindsym Mod4+minus scratchpad show"));
+        assert!(text.contains("// output * bg ~/wallpaper.png fill"));
+        assert!(text.contains("// for_window [shell=\"xwayland\"]"));
+        assert!(text.contains("// bar {"));
+        assert!(text.contains("// statusline #ffffff"));
+    }
+
+    #[test]
+    fn i3_has_no_output_directives() {
+        let text = convert(ImportSource::I3, "output eDP-1 scale 2\ngaps inner 4\n");
+        assert!(text.contains("// output eDP-1 scale 2"));
+        assert!(text.contains("gaps 4"));
+    }
+
+    #[test]
+    fn spawn_uses_shell_only_when_needed() {
+        assert_eq!(spawn("foot -e htop"), r#"spawn "foot" "-e" "htop""#);
+        assert_eq!(
+            spawn("grim - | wl-copy"),
+            r#"spawn "sh" "-c" "grim - | wl-copy""#
+        );
+    }
+
+    #[test]
+    fn resize_prefers_ppt() {
+        assert_eq!(
+            convert_resize(&["grow", "width", "10", "px", "or", "5", "ppt"]).as_deref(),
+            Some(r#"set-column-width "+5%""#)
+        );
+        assert_eq!(
+            convert_resize(&["shrink", "height", "20px"]).as_deref(),
+            Some(r#"set-window-height "-20""#)
+        );
+        assert_eq!(convert_resize(&["grow", "sideways", "20"]), None);
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+use calloop::RegistrationToken;
+use smithay::backend::input::{KeyState, Keycode};
+
+/// State of the keyboard accessibility features: sticky, slow and bounce keys.
+#[derive(Debug, Default)]
+pub struct KeyboardAccessibility {
+    /// Modifier that was pressed with no other key since, and will latch on release.
+    sticky_tap: Option<Keycode>,
+    /// Modifiers latched until the next non-modifier key press.
+    sticky_latched: Vec<Keycode>,
+    /// Held non-modifier keys along with the latched modifiers pressed for them.
+    sticky_held: Vec<(Keycode, Vec<Keycode>)>,
+    /// Keys held for less than the slow keys delay so far, with their timers.
+    slow_pending: HashMap<Keycode, RegistrationToken>,
+    /// Last release time of every key, for bounce keys.
+    last_release: HashMap<Keycode, u32>,
+    /// Keys whose press was ignored by bounce keys, so their release must be ignored too.
+    bounced: HashSet<Keycode>,
+}
+
+impl KeyboardAccessibility {
+    /// Returns whether the key event should be ignored because of bounce keys.
+    pub fn debounce(&mut self, key_code: Keycode, state: KeyState, time: u32, delay: u16) -> bool {
+        match state {
+            KeyState::Pressed => {
+                if delay == 0 {
+                    return false;
+                }
+
+                let Some(last) = self.last_release.get(&key_code) else {
+                    return false;
+                };
+                if time.wrapping_sub(*last) >= u32::from(delay) {
+                    return false;
+                }
+
+                self.bounced.insert(key_code);
+                true
+            }
+            KeyState::Released => {
+                if self.bounced.remove(&key_code) {
+                    return true;
+                }
+
+                self.last_release.insert(key_code, time);
+                false
+            }
+        }
+    }
+
+    /// Records a key held for the slow keys delay, returning the previous timer if any.
+    pub fn insert_slow_key(
+        &mut self,
+        key_code: Keycode,
+        token: RegistrationToken,
+    ) -> Option<RegistrationToken> {
+        self.slow_pending.insert(key_code, token)
+    }
+
+    /// Removes a key held for the slow keys delay, returning its timer.
+    pub fn take_slow_key(&mut self, key_code: Keycode) -> Option<RegistrationToken> {
+        self.slow_pending.remove(&key_code)
+    }
+
+    /// Handles a key press for sticky keys.
+    ///
+    /// Returns the latched modifiers that must be pressed before the key.
+    pub fn sticky_press(&mut self, key_code: Keycode, is_modifier: bool) -> Vec<Keycode> {
+        if is_modifier {
+            self.sticky_tap = Some(key_code);
+            return Vec::new();
+        }
+
+        self.sticky_tap = None;
+
+        let latched = mem::take(&mut self.sticky_latched);
+        if !latched.is_empty() {
+            self.sticky_held.push((key_code, latched.clone()));
+        }
+        latched
+    }
+
+    /// Handles a key release for sticky keys.
+    ///
+    /// Returns the previously latched modifiers that must be released after the key.
+    pub fn sticky_release(&mut self, key_code: Keycode) -> Vec<Keycode> {
+        if self.sticky_tap == Some(key_code) {
+            self.sticky_tap = None;
+
+            // Tapping a latched modifier again unlatches it.
+            if let Some(idx) = self.sticky_latched.iter().position(|k| *k == key_code) {
+                self.sticky_latched.remove(idx);
+            } else {
+                self.sticky_latched.push(key_code);
+            }
+
+            return Vec::new();
+        }
+
+        if let Some(idx) = self.sticky_held.iter().position(|(k, _)| *k == key_code) {
+            return self.sticky_held.remove(idx).1;
+        }
+
+        Vec::new()
+    }
+
+    /// Unlatches all modifiers, for when sticky keys get disabled.
+    pub fn clear_sticky_latched(&mut self) {
+        self.sticky_tap = None;
+        self.sticky_latched.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHIFT: u32 = 50;
+    const CTRL: u32 = 37;
+    const A: u32 = 38;
+
+    #[test]
+    fn sticky_modifier_latches_until_next_key() {
+        let mut a11y = KeyboardAccessibility::default();
+
+        assert!(a11y.sticky_press(SHIFT.into(), true).is_empty());
+        assert!(a11y.sticky_release(SHIFT.into()).is_empty());
+
+        assert_eq!(a11y.sticky_press(A.into(), false), vec![SHIFT.into()]);
+        assert_eq!(a11y.sticky_release(A.into()), vec![SHIFT.into()]);
+
+        // The latch was consumed.
+        assert!(a11y.sticky_press(A.into(), false).is_empty());
+        assert!(a11y.sticky_release(A.into()).is_empty());
+    }
+
+    #[test]
+    fn sticky_chord_does_not_latch() {
+        let mut a11y = KeyboardAccessibility::default();
+
+        a11y.sticky_press(CTRL.into(), true);
+        a11y.sticky_press(A.into(), false);
+        a11y.sticky_release(A.into());
+        a11y.sticky_release(CTRL.into());
+
+        assert!(a11y.sticky_press(A.into(), false).is_empty());
+    }
+
+    #[test]
+    fn sticky_double_tap_unlatches() {
+        let mut a11y = KeyboardAccessibility::default();
+
+        a11y.sticky_press(SHIFT.into(), true);
+        a11y.sticky_release(SHIFT.into());
+        a11y.sticky_press(SHIFT.into(), true);
+        a11y.sticky_release(SHIFT.into());
+
+        assert!(a11y.sticky_press(A.into(), false).is_empty());
+    }
+
+    #[test]
+    fn bounce_keys_ignore_quick_repress() {
+        let mut a11y = KeyboardAccessibility::default();
+        let key = Keycode::from(A);
+
+        assert!(!a11y.debounce(key, KeyState::Pressed, 0, 100));
+        assert!(!a11y.debounce(key, KeyState::Released, 50, 100));
+
+        // Bounced press and its release are both ignored.
+        assert!(a11y.debounce(key, KeyState::Pressed, 100, 100));
+        assert!(a11y.debounce(key, KeyState::Released, 120, 100));
+
+        // The bounced release doesn't restart the delay.
+        assert!(!a11y.debounce(key, KeyState::Pressed, 160, 100));
+    }
+}
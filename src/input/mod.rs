@@ -6,7 +6,10 @@ use std::time::Duration;
 
 use calloop::timer::{TimeoutAction, Timer};
 use input::event::gesture::GestureEventCoordinates as _;
-use niri_config::{Action, Bind, Binds, Key, ModKey, Modifiers, SwitchBinds, Trigger};
+use niri_config::{
+    Action, Bind, BindActivation, BindArea, Binds, Key, ModKey, Modifiers, OutputBarriers,
+    SwitchBinds, TouchpadPinchAction, TouchpadSwipeAction, Trigger,
+};
 use niri_ipc::LayoutSwitchTarget;
 use smithay::backend::input::{
     AbsolutePositionEvent, Axis, AxisSource, ButtonState, Device, DeviceCapability, Event,
@@ -33,37 +36,60 @@ use smithay::utils::{Logical, Point, Rectangle, Size, Transform, SERIAL_COUNTER}
 use smithay::wayland::keyboard_shortcuts_inhibit::KeyboardShortcutsInhibitor;
 use smithay::wayland::pointer_constraints::{with_pointer_constraint, PointerConstraint};
 use smithay::wayland::selection::data_device::DnDGrab;
+use smithay::wayland::shell::wlr_layer::Layer;
 use smithay::wayland::tablet_manager::{TabletDescriptor, TabletSeatTrait};
 use touch_move_grab::TouchMoveGrab;
 use touch_overview_grab::TouchOverviewGrab;
+use touch_swipe_grab::TouchSwipeGrab;
 
 use self::move_grab::MoveGrab;
 use self::resize_grab::ResizeGrab;
 use self::spatial_movement_grab::SpatialMovementGrab;
+use crate::crash_dump::input_event_kind;
 use crate::layout::scrolling::ScrollDirection;
-use crate::layout::{ActivateWindow, LayoutElement as _};
+use crate::layout::{ActivateWindow, HitType, LayoutElement as _};
 use crate::niri::{CastTarget, PointerVisibility, State};
 use crate::ui::screenshot_ui::ScreenshotUi;
+use crate::ui::window_switcher::WindowSwitcherEntry;
+#[cfg(feature = "dbus")]
+use crate::utils::mpris::MediaKey;
 use crate::utils::spawning::spawn;
-use crate::utils::{center, get_monotonic_time, ResizeEdge};
+use crate::utils::{center, get_monotonic_time, with_toplevel_role, ResizeEdge};
 
+pub mod accessibility;
 pub mod backend_ext;
 pub mod move_grab;
 pub mod pick_color_grab;
 pub mod pick_window_grab;
+#[cfg(feature = "xdp-gnome-screencast")]
+pub mod remote_desktop;
+pub mod replay;
 pub mod resize_grab;
 pub mod scroll_swipe_gesture;
 pub mod scroll_tracker;
 pub mod spatial_movement_grab;
 pub mod swipe_tracker;
+pub mod tap_tracker;
 pub mod touch_move_grab;
 pub mod touch_overview_grab;
 pub mod touch_resize_grab;
+pub mod touch_swipe_grab;
 
 use backend_ext::{NiriInputBackend as InputBackend, NiriInputDevice as _};
 
 pub const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(400);
 
+/// Gesture movement for a change in touchpad pinch scale of 1, when controlling the overview.
+///
+/// With this value, pinching in to half the initial finger spread fully opens the overview.
+const PINCH_OVERVIEW_MOVEMENT: f64 = 600.;
+
+/// Size of the output corners where output barriers don't let the pointer through.
+const OUTPUT_BARRIER_CORNER_SIZE: f64 = 16.;
+
+/// How long the wheel must stay still for the kinetic wheel view scroll to end.
+const WHEEL_VIEW_SCROLL_TIMEOUT: Duration = Duration::from_millis(100);
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct TabletData {
     pub aspect_ratio: f64,
@@ -90,6 +116,9 @@ impl State {
     {
         let _span = tracy_client::span!("process_input_event");
 
+        self.niri.input_history.push(input_event_kind(&event));
+        self.record_input_event(&event);
+
         // Make sure some logic like workspace clean-up has a chance to run before doing actions.
         self.niri.advance_animations();
 
@@ -98,6 +127,11 @@ impl State {
             if should_notify_activity(&event) {
                 self.niri.notify_activity();
             }
+
+            // Power on monitors that were powered off on their own.
+            if should_activate_monitors(&event) {
+                self.niri.activate_monitors(&mut self.backend);
+            }
         } else {
             // Power on monitors if they were off.
             if should_activate_monitors(&event) {
@@ -121,7 +155,10 @@ impl State {
             .exit_confirm_dialog
             .as_ref()
             .is_some_and(|d| d.is_open())
-            && should_hide_exit_confirm_dialog(&event);
+            && should_hide_dialog(&event);
+
+        let hide_window_switcher =
+            self.niri.window_switcher.is_open() && should_hide_dialog(&event);
 
         use InputEvent::*;
         match event {
@@ -164,6 +201,10 @@ impl State {
                 self.niri.queue_redraw_all();
             }
         }
+
+        if hide_window_switcher && self.niri.window_switcher.hide() {
+            self.niri.queue_redraw_all();
+        }
     }
 
     pub fn process_libinput_event(&mut self, event: &mut InputEvent<LibinputInputBackend>) {
@@ -267,29 +308,43 @@ impl State {
     {
         let device_output = event.device().output(self);
         let device_output = device_output.as_ref();
-        let (target_geo, keep_ratio, px, transform) =
-            if let Some(output) = device_output.or_else(|| self.niri.output_for_tablet()) {
-                (
-                    self.niri.global_space.output_geometry(output).unwrap(),
-                    true,
-                    1. / output.current_scale().fractional_scale(),
-                    output.current_transform(),
-                )
-            } else {
-                let geo = self.global_bounding_rectangle()?;
+        let target = self.niri.focused_tile_for_tablet().or_else(|| {
+            let output = device_output.or_else(|| self.niri.output_for_tablet())?;
+            Some((self.niri.global_space.output_geometry(output)?, output))
+        });
+        let (target_geo, keep_ratio, px, transform) = if let Some((geo, output)) = target {
+            (
+                geo,
+                true,
+                1. / output.current_scale().fractional_scale(),
+                output.current_transform(),
+            )
+        } else {
+            let geo = self.global_bounding_rectangle()?;
 
-                // FIXME: this 1 px size should ideally somehow be computed for the rightmost output
-                // corresponding to the position on the right when clamping.
-                let output = self.niri.global_space.outputs().next().unwrap();
-                let scale = output.current_scale().fractional_scale();
+            // FIXME: this 1 px size should ideally somehow be computed for the rightmost output
+            // corresponding to the position on the right when clamping.
+            let output = self.niri.global_space.outputs().next().unwrap();
+            let scale = output.current_scale().fractional_scale();
 
-                // Do not keep ratio for the unified mode as this is what OpenTabletDriver expects.
-                (geo, false, 1. / scale, Transform::Normal)
-            };
+            // Do not keep ratio for the unified mode as this is what OpenTabletDriver expects.
+            (geo, false, 1. / scale, Transform::Normal)
+        };
+
+        let active_area = self.niri.config.borrow().input.tablet.active_area;
 
         let mut pos = {
             let size = transform.invert().transform_size(target_geo.size);
-            transform.transform_point_in(event.position_transformed(size), &size.to_f64())
+            let mut pos = event.position_transformed(size);
+
+            // Stretch the active area of the tablet over the whole target.
+            if let Some(area) = active_area {
+                let (w, h) = (f64::from(size.w), f64::from(size.h));
+                pos.x = (pos.x / w - area.x.0) / area.width.0.max(0.01) * w;
+                pos.y = (pos.y / h - area.y.0) / area.height.0.max(0.01) * h;
+            }
+
+            transform.transform_point_in(pos, &size.to_f64())
         };
 
         if keep_ratio {
@@ -302,7 +357,11 @@ impl State {
                     // This code does the same thing as mutter with "keep aspect ratio" enabled.
                     let size = transform.invert().transform_size(target_geo.size);
                     let output_aspect_ratio = size.w as f64 / size.h as f64;
-                    let ratio = data.aspect_ratio / output_aspect_ratio;
+                    let mut aspect_ratio = data.aspect_ratio;
+                    if let Some(area) = active_area {
+                        aspect_ratio *= area.width.0.max(0.01) / area.height.0.max(0.01);
+                    }
+                    let ratio = aspect_ratio / output_aspect_ratio;
 
                     if ratio > 1. {
                         pos.x *= ratio;
@@ -322,6 +381,15 @@ impl State {
     }
 
     fn is_inhibiting_shortcuts(&self) -> bool {
+        let is_inhibited_by_rule = self.niri.keyboard_focus.is_layout()
+            && self.niri.layout.focus().is_some_and(|mapped| {
+                mapped.rules().inhibit_shortcuts_when_fullscreen == Some(true)
+                    && mapped.is_fullscreen()
+            });
+        if is_inhibited_by_rule {
+            return true;
+        }
+
         self.niri
             .keyboard_focus
             .surface()
@@ -334,53 +402,196 @@ impl State {
     }
 
     fn on_keyboard<I: InputBackend>(&mut self, event: I::KeyboardKeyEvent) {
+        let key_code = event.key_code();
+        let state = event.state();
+        let time = Event::time_msec(&event);
+
+        let (slow_keys_delay, bounce_keys_delay) = {
+            let config = &self.niri.config.borrow().input.keyboard;
+            (config.slow_keys_delay, config.bounce_keys_delay)
+        };
+
+        let a11y = &mut self.niri.keyboard_accessibility;
+        if a11y.debounce(key_code, state, time, bounce_keys_delay) {
+            return;
+        }
+
+        // With slow keys, a press is only accepted once the key was held long enough.
+        match state {
+            KeyState::Pressed if slow_keys_delay > 0 => {
+                let delay = Duration::from_millis(u64::from(slow_keys_delay));
+                let token = self
+                    .niri
+                    .event_loop
+                    .insert_source(Timer::from_duration(delay), move |_, _, state| {
+                        state.niri.keyboard_accessibility.take_slow_key(key_code);
+                        let time = get_monotonic_time().as_millis() as u32;
+                        state.on_key(key_code, KeyState::Pressed, time);
+                        TimeoutAction::Drop
+                    })
+                    .unwrap();
+                let a11y = &mut self.niri.keyboard_accessibility;
+                if let Some(token) = a11y.insert_slow_key(key_code, token) {
+                    self.niri.event_loop.remove(token);
+                }
+                return;
+            }
+            KeyState::Released => {
+                let a11y = &mut self.niri.keyboard_accessibility;
+                if let Some(token) = a11y.take_slow_key(key_code) {
+                    // Released before the delay passed, so the press was never accepted.
+                    self.niri.event_loop.remove(token);
+                    return;
+                }
+            }
+            _ => (),
+        }
+
+        self.on_key(key_code, state, time);
+    }
+
+    /// Handles a key event that passed the slow and bounce keys filters.
+    fn on_key(&mut self, key_code: Keycode, state: KeyState, time: u32) {
+        let sticky_keys = self.niri.config.borrow().input.keyboard.sticky_keys;
+
+        match state {
+            KeyState::Pressed => {
+                if !sticky_keys {
+                    self.niri.keyboard_accessibility.clear_sticky_latched();
+                    self.handle_key(key_code, state, time);
+                    return;
+                }
+
+                let is_modifier = self.is_modifier_key(key_code);
+                let a11y = &mut self.niri.keyboard_accessibility;
+                for modifier in a11y.sticky_press(key_code, is_modifier) {
+                    self.handle_key(modifier, KeyState::Pressed, time);
+                }
+                self.handle_key(key_code, state, time);
+            }
+            KeyState::Released => {
+                self.handle_key(key_code, state, time);
+
+                let a11y = &mut self.niri.keyboard_accessibility;
+                let modifiers = a11y.sticky_release(key_code);
+                if !sticky_keys {
+                    a11y.clear_sticky_latched();
+                }
+                for modifier in modifiers {
+                    self.handle_key(modifier, KeyState::Released, time);
+                }
+            }
+        }
+    }
+
+    fn is_modifier_key(&mut self, key_code: Keycode) -> bool {
+        let keyboard = self.niri.seat.get_keyboard().unwrap();
+        keyboard.with_xkb_state(self, |context| {
+            let xkb = context.xkb().lock().unwrap();
+            let layout = xkb.active_layout();
+            xkb.raw_syms_for_key_in_layout(key_code, layout)
+                .iter()
+                .any(|sym| sym.is_modifier_key())
+        })
+    }
+
+    /// Returns the modifiers that the key sets in the current layout.
+    fn key_modifiers(&mut self, key_code: Keycode) -> Modifiers {
+        let keyboard = self.niri.seat.get_keyboard().unwrap();
+        keyboard.with_xkb_state(self, |context| {
+            let xkb = context.xkb().lock().unwrap();
+            let layout = xkb.active_layout();
+            xkb.raw_syms_for_key_in_layout(key_code, layout)
+                .iter()
+                .fold(Modifiers::empty(), |mods, sym| mods | keysym_modifier(*sym))
+        })
+    }
+
+    pub(crate) fn handle_key(&mut self, key_code: Keycode, state: KeyState, time: u32) {
         let mod_key = self.backend.mod_key(&self.niri.config.borrow());
 
         let serial = SERIAL_COUNTER.next_serial();
-        let time = Event::time_msec(&event);
-        let pressed = event.state() == KeyState::Pressed;
-
-        // Stop bind key repeat on any release. This won't work 100% correctly in cases like:
-        // 1. Press Mod
-        // 2. Press Left (repeat starts)
-        // 3. Press PgDown (new repeat starts)
-        // 4. Release Left (PgDown repeat stops)
-        // But it's good enough for now.
-        // FIXME: handle this properly.
-        if !pressed {
-            if let Some(token) = self.niri.bind_repeat_timer.take() {
-                self.niri.event_loop.remove(token);
+        let pressed = state == KeyState::Pressed;
+
+        if let Some((repeat_key, repeat_mods, _)) = self.niri.bind_repeat_timer {
+            let key_mods = if pressed {
+                Modifiers::empty()
+            } else {
+                self.key_modifiers(key_code)
+            };
+
+            if stops_bind_repeat(repeat_key, repeat_mods, key_code, key_mods, pressed) {
+                self.stop_key_repeat();
             }
         }
 
         if pressed {
-            self.hide_cursor_if_needed();
+            self.hide_cursor_if_needed(key_code);
+        }
+
+        // Tap binds only trigger if no other key was pressed in between.
+        if pressed {
+            self.niri.tap_tracker.press(key_code);
         }
 
         let is_inhibiting_shortcuts = self.is_inhibiting_shortcuts();
 
-        let Some(Some(bind)) = self.niri.seat.get_keyboard().unwrap().input(
+        let mut tap_bind = None;
+        let mut exit_binds_mode = false;
+        #[cfg(feature = "dbus")]
+        let mut media_key = None;
+        let mut switcher_pick = None;
+        let res = self.niri.seat.get_keyboard().unwrap().input(
             self,
-            event.key_code(),
-            event.state(),
+            key_code,
+            state,
             serial,
             time,
             |this, mods, keysym| {
-                let key_code = event.key_code();
                 let modified = keysym.modified_sym();
                 let raw = keysym.raw_latin_sym_or_raw_current_sym();
 
-                if let Some(dialog) = &this.niri.exit_confirm_dialog {
-                    if dialog.is_open() && pressed && raw == Some(Keysym::Return) {
-                        info!("quitting after confirming exit dialog");
-                        this.niri.stop_signal.stop();
+                if let Some(dialog) = &mut this.niri.exit_confirm_dialog {
+                    if dialog.is_open() && pressed {
+                        match raw {
+                            Some(Keysym::Return) => {
+                                let entry = dialog.selected();
+                                dialog.hide();
+                                this.niri.queue_redraw_all();
+                                this.activate_power_menu_entry(entry);
+
+                                // Don't send this Enter press to any clients.
+                                this.niri.suppressed_keys.insert(key_code);
+                                return FilterResult::Intercept(None);
+                            }
+                            Some(Keysym::Up | Keysym::Down) if dialog.has_entries() => {
+                                if raw == Some(Keysym::Up) {
+                                    dialog.select_prev();
+                                } else {
+                                    dialog.select_next();
+                                }
+                                this.niri.queue_redraw_all();
 
-                        // Don't send this Enter press to any clients.
-                        this.niri.suppressed_keys.insert(key_code);
-                        return FilterResult::Intercept(None);
+                                this.niri.suppressed_keys.insert(key_code);
+                                return FilterResult::Intercept(None);
+                            }
+                            // Any other key closes the dialog and goes through as usual.
+                            _ => {
+                                dialog.hide();
+                                this.niri.queue_redraw_all();
+                            }
+                        }
                     }
                 }
 
+                if this.niri.window_switcher.is_open() && pressed {
+                    switcher_pick = this.handle_window_switcher_key(raw, modified, *mods);
+
+                    // While the switcher is open, all key presses go to it.
+                    this.niri.suppressed_keys.insert(key_code);
+                    return FilterResult::Intercept(None);
+                }
+
                 if pressed
                     && raw == Some(Keysym::Escape)
                     && (this.niri.pick_window.is_some() || this.niri.pick_color.is_some())
@@ -400,7 +611,40 @@ impl State {
                     this.niri.screenshot_ui.set_space_down(pressed);
                 }
 
-                let bindings = &this.niri.config.borrow().binds;
+                // While in a binds mode, its binds replace the regular ones.
+                let config = this.niri.config.borrow();
+                let modes = &config.binds_modes;
+                let active_mode = this.niri.binds_mode.as_deref();
+                let binds_mode = active_mode.and_then(|name| modes.iter().find(|m| m.name == name));
+                let bindings = binds_mode.map_or(&config.binds, |mode| &mode.binds);
+
+                if !pressed && this.niri.tap_tracker.release(key_code) {
+                    let bind = find_bind(
+                        bindings,
+                        mod_key,
+                        modified,
+                        raw,
+                        *mods,
+                        this.niri.config.borrow().input.disable_power_key_handling,
+                    );
+                    let bind = bind.filter(|bind| {
+                        !this.niri.screenshot_ui.is_open()
+                            && !(is_inhibiting_shortcuts && bind.allow_inhibiting)
+                    });
+
+                    match bind {
+                        Some(bind) if bind.activation == BindActivation::Release => {
+                            tap_bind = Some(bind);
+                        }
+                        Some(bind) if bind.activation == BindActivation::DoubleTap => {
+                            if this.niri.tap_tracker.double_tap(key_code, time) {
+                                tap_bind = Some(bind);
+                            }
+                        }
+                        _ => (),
+                    }
+                }
+
                 let res = should_intercept_key(
                     &mut this.niri.suppressed_keys,
                     bindings,
@@ -415,7 +659,27 @@ impl State {
                     is_inhibiting_shortcuts,
                 );
 
+                // Tap binds don't do anything on press.
+                if let FilterResult::Intercept(Some(bind)) = &res {
+                    if bind.activation != BindActivation::Press {
+                        if modified.is_modifier_key() {
+                            // Modifiers must still reach the clients since they're also used in
+                            // combination with other keys.
+                            this.niri.suppressed_keys.remove(&key_code);
+                            return FilterResult::Forward;
+                        }
+                        return FilterResult::Intercept(None);
+                    }
+                }
+
                 if matches!(res, FilterResult::Forward) {
+                    // Escape leaves the binds mode unless the mode binds it to something else.
+                    if binds_mode.is_some() && pressed && raw == Some(Keysym::Escape) {
+                        exit_binds_mode = true;
+                        this.niri.suppressed_keys.insert(key_code);
+                        return FilterResult::Intercept(None);
+                    }
+
                     // If we didn't find any bind, try other hardcoded keys.
                     if this.niri.keyboard_focus.is_overview() && pressed {
                         if let Some(bind) = raw.and_then(|raw| hardcoded_overview_bind(raw, *mods))
@@ -424,11 +688,49 @@ impl State {
                             return FilterResult::Intercept(Some(bind));
                         }
                     }
+
+                    // Unbound media keys go to an MPRIS player if configured, or if the
+                    // built-in lock screen shows the media controls.
+                    #[cfg(feature = "dbus")]
+                    if pressed
+                        && !is_inhibiting_shortcuts
+                        && (this.niri.config.borrow().input.mpris_media_keys
+                            || this.niri.builtin_lock_screen_shows_media())
+                    {
+                        if let Some(key) = raw.and_then(MediaKey::from_keysym) {
+                            media_key = Some(key);
+                            this.niri.suppressed_keys.insert(key_code);
+                            return FilterResult::Intercept(None);
+                        }
+                    }
                 }
 
                 res
             },
-        ) else {
+        );
+
+        if exit_binds_mode {
+            self.niri.set_binds_mode(None);
+            return;
+        }
+
+        if let Some((id, bring_here)) = switcher_pick {
+            self.pick_window_from_switcher(id, bring_here);
+            return;
+        }
+
+        #[cfg(feature = "dbus")]
+        if let Some(key) = media_key {
+            self.niri.mpris.send_key(key);
+            return;
+        }
+
+        if let Some(bind) = tap_bind {
+            self.handle_bind(bind);
+            return;
+        }
+
+        let Some(Some(bind)) = res else {
             return;
         };
 
@@ -438,17 +740,80 @@ impl State {
 
         self.handle_bind(bind.clone());
 
-        self.start_key_repeat(bind);
+        self.start_key_repeat(key_code, bind);
     }
 
-    fn start_key_repeat(&mut self, bind: Bind) {
-        if !bind.repeat {
+    /// Handles a key press while the window switcher is open.
+    ///
+    /// Returns the picked window ID and whether to bring the window to the current workspace.
+    fn handle_window_switcher_key(
+        &mut self,
+        raw: Option<Keysym>,
+        modified: Keysym,
+        mods: ModifiersState,
+    ) -> Option<(u64, bool)> {
+        let switcher = &mut self.niri.window_switcher;
+        let mut pick = None;
+        match raw {
+            Some(Keysym::Escape) => {
+                switcher.hide();
+            }
+            Some(Keysym::Return | Keysym::KP_Enter) => {
+                pick = switcher.selected().map(|id| (id, mods.shift));
+                switcher.hide();
+            }
+            Some(Keysym::Up) => switcher.select_prev(),
+            Some(Keysym::Down) => switcher.select_next(),
+            Some(Keysym::BackSpace) => switcher.pop_char(),
+            _ => {
+                let c = modified.key_char().filter(|c| !c.is_control());
+                if let Some(c) = c.filter(|_| !(mods.ctrl || mods.alt || mods.logo)) {
+                    switcher.push_char(c);
+                }
+            }
+        }
+
+        self.niri.queue_redraw_all();
+        pick
+    }
+
+    /// Focuses the window picked in the window switcher, optionally bringing it to the current
+    /// workspace first.
+    fn pick_window_from_switcher(&mut self, id: u64, bring_here: bool) {
+        let window = self.niri.layout.windows().find(|(_, m)| m.id().get() == id);
+        let Some(window) = window.map(|(_, m)| m.window.clone()) else {
             return;
+        };
+
+        if bring_here {
+            if let Some(mon) = self.niri.layout.active_monitor_ref() {
+                let output = mon.output().clone();
+                let idx = mon.active_workspace_idx();
+                self.niri.layout.move_to_output(
+                    Some(&window),
+                    &output,
+                    Some(idx),
+                    ActivateWindow::Yes,
+                );
+            }
         }
 
+        self.focus_window(&window);
+    }
+
+    fn start_key_repeat(&mut self, key_code: Keycode, bind: Bind) {
         // Stop the previous key repeat if any.
-        if let Some(token) = self.niri.bind_repeat_timer.take() {
-            self.niri.event_loop.remove(token);
+        self.stop_key_repeat();
+
+        if !bind.repeat {
+            return;
+        }
+
+        let mod_key = self.backend.mod_key(&self.niri.config.borrow());
+        let mut mods = bind.key.modifiers;
+        if mods.contains(Modifiers::COMPOSITOR) {
+            mods.remove(Modifiers::COMPOSITOR);
+            mods.insert(mod_key.to_modifiers());
         }
 
         let config = self.niri.config.borrow();
@@ -472,10 +837,16 @@ impl State {
             })
             .unwrap();
 
-        self.niri.bind_repeat_timer = Some(token);
+        self.niri.bind_repeat_timer = Some((key_code, mods, token));
     }
 
-    fn hide_cursor_if_needed(&mut self) {
+    fn stop_key_repeat(&mut self) {
+        if let Some((_, _, token)) = self.niri.bind_repeat_timer.take() {
+            self.niri.event_loop.remove(token);
+        }
+    }
+
+    fn hide_cursor_if_needed(&mut self, key_code: Keycode) {
         // If the pointer is already invisible, don't reset it back to Hidden causing one frame
         // of hover.
         if !self.niri.pointer_visibility.is_visible() {
@@ -486,6 +857,12 @@ impl State {
             return;
         }
 
+        // Modifiers are commonly held together with the mouse, for example for Mod+drag, so they
+        // don't count as typing.
+        if self.is_modifier_key(key_code) {
+            return;
+        }
+
         // niri keeps this set only while actively using a tablet, which means the cursor position
         // is likely to change almost immediately, causing pointer_visibility to just flicker back
         // and forth.
@@ -569,6 +946,29 @@ impl State {
             Action::PowerOnMonitors => {
                 self.niri.activate_monitors(&mut self.backend);
             }
+            Action::ToggleMonitorPower(output) => {
+                let output = match output {
+                    None => self.niri.layout.active_output(),
+                    Some(name) => self.niri.output_by_name_match(&name),
+                };
+                if let Some(output) = output.cloned() {
+                    let state = self.niri.output_state.get(&output);
+                    let is_powered_off = state.is_some_and(|state| state.is_powered_off);
+                    self.niri
+                        .set_output_powered(&mut self.backend, &output, is_powered_off);
+                }
+            }
+            Action::ShowOsd {
+                kind,
+                percent,
+                muted,
+            } => {
+                self.niri.show_osd(kind, f64::from(percent) / 100., muted);
+            }
+            Action::AdjustBrightness(step) => {
+                // The OSD shows up once the worker reports the new brightness.
+                self.niri.backlight.adjust(step);
+            }
             Action::ToggleDebugTint => {
                 self.backend.toggle_debug_tint();
                 self.niri.queue_redraw_all();
@@ -580,6 +980,12 @@ impl State {
             Action::DebugToggleDamage => {
                 self.niri.debug_toggle_damage();
             }
+            Action::DebugAddNestedOutput => {
+                self.backend.add_nested_output(&mut self.niri);
+            }
+            Action::DebugRemoveNestedOutput => {
+                self.backend.remove_nested_output(&mut self.niri);
+            }
             Action::Spawn(command) => {
                 let (token, _) = self.niri.activation_state.create_external_token(None);
                 spawn(command, Some(token.clone()));
@@ -640,15 +1046,21 @@ impl State {
                 let mut windows = self.niri.layout.windows();
                 let window = windows.find(|(_, m)| m.id().get() == id);
                 if let Some((Some(monitor), mapped)) = window {
-                    let output = monitor.output();
-                    self.backend.with_primary_renderer(|renderer| {
-                        if let Err(err) =
-                            self.niri
-                                .screenshot_window(renderer, output, mapped, write_to_disk)
-                        {
-                            warn!("error taking screenshot: {err:?}");
-                        }
-                    });
+                    if mapped.is_suspended() {
+                        let surface = mapped.toplevel().wl_surface().clone();
+                        drop(windows);
+                        self.request_window_screenshot(&surface, write_to_disk);
+                    } else {
+                        let output = monitor.output();
+                        self.backend.with_primary_renderer(|renderer| {
+                            if let Err(err) =
+                                self.niri
+                                    .screenshot_window(renderer, output, mapped, write_to_disk)
+                            {
+                                warn!("error taking screenshot: {err:?}");
+                            }
+                        });
+                    }
                 }
             }
             Action::ToggleKeyboardShortcutsInhibit => {
@@ -664,6 +1076,9 @@ impl State {
                     }
                 }
             }
+            Action::ReleasePointerConstraint => {
+                self.niri.release_pointer_constraint();
+            }
             Action::CloseWindow => {
                 if let Some(mapped) = self.niri.layout.focus() {
                     mapped.toplevel().send_close();
@@ -728,7 +1143,30 @@ impl State {
                     self.focus_window(&window);
                 }
             }
+            Action::FocusUrgent => {
+                let window = self
+                    .niri
+                    .layout
+                    .windows()
+                    .filter_map(|(_, m)| m.urgent_since().map(|since| (since, m)))
+                    .max_by_key(|(since, _)| *since)
+                    .map(|(_, m)| m.window.clone());
+                if let Some(window) = window {
+                    self.focus_window(&window);
+                }
+            }
             Action::SwitchLayout(action) => {
+                let action = match action {
+                    LayoutSwitchTarget::Name(name) => match self.keyboard_layout_by_name(&name) {
+                        Some(idx) => LayoutSwitchTarget::Index(idx),
+                        None => {
+                            warn!("requested layout doesn't exist");
+                            return;
+                        }
+                    },
+                    action => action,
+                };
+
                 let keyboard = &self.niri.seat.get_keyboard().unwrap();
                 keyboard.with_xkb_state(self, |mut state| match action {
                     LayoutSwitchTarget::Next => state.cycle_next_layout(),
@@ -741,6 +1179,8 @@ impl State {
                             state.set_layout(Layout(layout.into()))
                         }
                     }
+                    // Resolved to an index above.
+                    LayoutSwitchTarget::Name(_) => unreachable!(),
                 });
             }
             Action::MoveColumnLeft => {
@@ -966,6 +1406,13 @@ impl State {
                 // FIXME: granular
                 self.niri.queue_redraw_all();
             }
+            Action::FocusColumnPrevious => {
+                self.niri.layout.focus_column_previous();
+                self.maybe_warp_cursor_to_focus();
+                self.niri.layer_shell_on_demand_focus = None;
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
             Action::FocusWindowOrMonitorUp => {
                 if let Some(output) = self.niri.output_up() {
                     if self.niri.layout.focus_window_up_or_output(&output)
@@ -1271,11 +1718,20 @@ impl State {
                 }
             }
             Action::MoveColumnToIndex(idx) => {
-                self.niri.layout.move_column_to_index(idx);
+                self.niri.layout.move_column_to_index(None, idx);
                 self.maybe_warp_cursor_to_focus();
                 // FIXME: granular
                 self.niri.queue_redraw_all();
             }
+            Action::MoveColumnToIndexById { id, index } => {
+                let window = self.niri.layout.windows().find(|(_, m)| m.id().get() == id);
+                let window = window.map(|(_, m)| m.window.clone());
+                if let Some(window) = window {
+                    self.niri.layout.move_column_to_index(Some(&window), index);
+                    // FIXME: granular
+                    self.niri.queue_redraw_all();
+                }
+            }
             Action::FocusWorkspaceDown => {
                 self.niri.layout.switch_workspace_down();
                 self.maybe_warp_cursor_to_focus();
@@ -1475,6 +1931,16 @@ impl State {
             Action::MaximizeColumn => {
                 self.niri.layout.toggle_full_width();
             }
+            Action::FullscreenColumn => {
+                self.niri.layout.toggle_column_fullscreen();
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::ToggleReadingMode => {
+                self.niri.layout.toggle_reading_mode();
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
             Action::FocusMonitorLeft => {
                 if let Some(output) = self.niri.output_left() {
                     self.niri.layout.focus_output(&output);
@@ -1839,6 +2305,28 @@ impl State {
                     self.niri.queue_redraw_all();
                 }
             }
+            Action::ShowCheatsheet => {
+                if self.niri.hotkey_overlay.show_all() {
+                    self.niri.queue_redraw_all();
+                }
+            }
+            Action::ShowWindowSwitcher => {
+                let entries = self
+                    .niri
+                    .layout
+                    .windows()
+                    .map(|(_, mapped)| {
+                        with_toplevel_role(mapped.toplevel(), |role| WindowSwitcherEntry {
+                            id: mapped.id().get(),
+                            title: role.title.clone().unwrap_or_default(),
+                            app_id: role.app_id.clone().unwrap_or_default(),
+                        })
+                    })
+                    .collect();
+                if self.niri.window_switcher.show(entries) {
+                    self.niri.queue_redraw_all();
+                }
+            }
             Action::MoveWorkspaceToMonitorLeft => {
                 if let Some(output) = self.niri.output_left() {
                     self.niri.layout.move_workspace_to_output(&output);
@@ -2024,6 +2512,64 @@ impl State {
                     }
                 }
             }
+            Action::ToggleAlwaysOnTop => {
+                let active_window = self
+                    .niri
+                    .layout
+                    .active_workspace_mut()
+                    .and_then(|ws| ws.active_window_mut());
+                if let Some(window) = active_window {
+                    window.toggle_always_on_top();
+                    // FIXME: granular
+                    self.niri.queue_redraw_all();
+                }
+            }
+            Action::ToggleAlwaysOnTopById(id) => {
+                let window = self
+                    .niri
+                    .layout
+                    .workspaces_mut()
+                    .find_map(|ws| ws.windows_mut().find(|w| w.id().get() == id));
+                if let Some(window) = window {
+                    window.toggle_always_on_top();
+                    // FIXME: granular
+                    self.niri.queue_redraw_all();
+                }
+            }
+            Action::SetWindowOpacity(change) => {
+                let active_window = self
+                    .niri
+                    .layout
+                    .active_workspace_mut()
+                    .and_then(|ws| ws.active_window_mut());
+                if let Some(window) = active_window {
+                    window.set_opacity(change);
+                    // FIXME: granular
+                    self.niri.queue_redraw_all();
+                }
+            }
+            Action::SetWindowOpacityById { id, change } => {
+                let window = self
+                    .niri
+                    .layout
+                    .workspaces_mut()
+                    .find_map(|ws| ws.windows_mut().find(|w| w.id().get() == id));
+                if let Some(window) = window {
+                    window.set_opacity(change);
+                    // FIXME: granular
+                    self.niri.queue_redraw_all();
+                }
+            }
+            Action::MovePipToNextCorner => {
+                self.niri.layout.move_pip_to_next_corner();
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
+            Action::SwitchPresetPipWidth => {
+                self.niri.layout.switch_preset_pip_width();
+                // FIXME: granular
+                self.niri.queue_redraw_all();
+            }
             Action::SetDynamicCastWindow => {
                 let id = self
                     .niri
@@ -2102,7 +2648,76 @@ impl State {
                 }
                 self.niri.queue_redraw_all();
             }
+            Action::EnterMode(name) => {
+                let config = self.niri.config.borrow();
+                if config.binds_modes.iter().any(|mode| mode.name == name) {
+                    drop(config);
+                    self.niri.set_binds_mode(Some(name));
+                } else {
+                    warn!("binds mode {name:?} doesn't exist");
+                }
+            }
+            Action::ExitMode => {
+                self.niri.set_binds_mode(None);
+            }
+            Action::ToggleDoNotDisturb => {
+                let popups = &mut self.niri.notification_popups;
+                popups.set_do_not_disturb(!popups.do_not_disturb());
+            }
+            Action::DismissNotifications => {
+                self.niri.dismiss_notifications();
+            }
+        }
+    }
+
+    /// Holds the pointer at the edge of its output until it's pushed far enough past the edge.
+    fn apply_output_barriers(
+        &mut self,
+        barriers: OutputBarriers,
+        pos: Point<f64, Logical>,
+        new_pos: Point<f64, Logical>,
+    ) -> Point<f64, Logical> {
+        let space = &self.niri.global_space;
+        let Some(output) = space.output_under(pos).next() else {
+            return new_pos;
+        };
+        let geom = space.output_geometry(output).unwrap();
+
+        if geom.to_f64().contains(new_pos) {
+            self.niri.output_barrier_push = 0.;
+            return new_pos;
+        }
+
+        let clamped = Point::from((
+            new_pos
+                .x
+                .clamp(geom.loc.x as f64, (geom.loc.x + geom.size.w - 1) as f64),
+            new_pos
+                .y
+                .clamp(geom.loc.y as f64, (geom.loc.y + geom.size.h - 1) as f64),
+        ));
+
+        if barriers.block_corners {
+            let near_edge = |value: f64, start: i32, size: i32| {
+                value - f64::from(start) < OUTPUT_BARRIER_CORNER_SIZE
+                    || f64::from(start + size) - value <= OUTPUT_BARRIER_CORNER_SIZE
+            };
+            if near_edge(clamped.x, geom.loc.x, geom.size.w)
+                && near_edge(clamped.y, geom.loc.y, geom.size.h)
+            {
+                self.niri.output_barrier_push = 0.;
+                return clamped;
+            }
         }
+
+        let overshoot = new_pos - clamped;
+        self.niri.output_barrier_push += overshoot.x.abs() + overshoot.y.abs();
+        if self.niri.output_barrier_push < barriers.push_through.0 {
+            return clamped;
+        }
+
+        self.niri.output_barrier_push = 0.;
+        new_pos
     }
 
     fn on_pointer_motion<I: InputBackend>(&mut self, event: I::PointerMotionEvent) {
@@ -2209,6 +2824,11 @@ impl State {
             }
         }
 
+        let output_barriers = self.niri.config.borrow().input.output_barriers;
+        if let Some(barriers) = output_barriers {
+            new_pos = self.apply_output_barriers(barriers, pos, new_pos);
+        }
+
         if let Some(output) = self.niri.screenshot_ui.selection_output() {
             let geom = self.niri.global_space.output_geometry(output).unwrap();
             let mut point = (new_pos - geom.loc.to_f64())
@@ -2410,13 +3030,54 @@ impl State {
                 self.niri.layout.dnd_update(output, pos_within_output);
             }
         }
-
-        // Redraw to update the cursor position.
-        // FIXME: redraw only outputs overlapping the cursor.
-        self.niri.queue_redraw_all();
+
+        // Redraw to update the cursor position.
+        // FIXME: redraw only outputs overlapping the cursor.
+        self.niri.queue_redraw_all();
+    }
+
+    /// Handles a mouse or wheel bind.
+    ///
+    /// Top bar binds act on the window whose top bar is under the pointer, so it is focused first.
+    fn handle_pointer_bind(&mut self, bind: Bind) {
+        if bind.area == BindArea::TopBar {
+            if let Some((window, _)) = self.niri.pointer_contents.window.clone() {
+                self.focus_window(&window);
+            }
+        }
+
+        self.handle_bind(bind);
+    }
+
+    /// Returns the area under the pointer for the purposes of pointer binds.
+    fn pointer_bind_area(&self) -> BindArea {
+        let contents = &self.niri.pointer_contents;
+        if let Some((_, hit)) = &contents.window {
+            return match hit {
+                HitType::Activate {
+                    is_top_bar: true, ..
+                }
+                | HitType::TopBarButton { .. } => BindArea::TopBar,
+                _ => BindArea::Anywhere,
+            };
+        }
+
+        let is_background = match &contents.layer {
+            Some(layer) => layer.layer() == Layer::Background,
+            // No surface means that the pointer is over the bare workspace.
+            None => contents.output.is_some() && contents.surface.is_none(),
+        };
+        if is_background {
+            BindArea::Background
+        } else {
+            BindArea::Anywhere
+        }
     }
 
     fn on_pointer_button<I: InputBackend>(&mut self, event: I::PointerButtonEvent) {
+        // Releasing Mod after Mod+click or Mod+drag isn't a tap.
+        self.niri.tap_tracker.cancel();
+
         let pointer = self.niri.seat.get_pointer().unwrap();
 
         let serial = SERIAL_COUNTER.next_serial();
@@ -2435,6 +3096,16 @@ impl State {
         }
 
         if ButtonState::Pressed == button_state {
+            // Clicks on the built-in lock screen media controls go to the MPRIS player.
+            #[cfg(feature = "dbus")]
+            if button == Some(MouseButton::Left) {
+                if let Some(key) = self.niri.lock_screen_media_key_under_pointer() {
+                    self.niri.suppressed_buttons.insert(button_code);
+                    self.niri.mpris.send_key(key);
+                    return;
+                }
+            }
+
             let mods = self.niri.seat.get_keyboard().unwrap().modifier_state();
             let modifiers = modifiers_from_state(mods);
 
@@ -2450,10 +3121,11 @@ impl State {
                 .and_then(|trigger| {
                     let config = self.niri.config.borrow();
                     let bindings = &config.binds;
-                    find_configured_bind(bindings, mod_key, trigger, mods)
+                    let area = self.pointer_bind_area();
+                    find_configured_pointer_bind(bindings, mod_key, trigger, mods, area)
                 }) {
                     self.niri.suppressed_buttons.insert(button_code);
-                    self.handle_bind(bind.clone());
+                    self.handle_pointer_bind(bind.clone());
                     return;
                 };
             }
@@ -2462,6 +3134,11 @@ impl State {
             self.niri.pointer_visibility = PointerVisibility::Visible;
             self.niri.tablet_cursor_location = None;
 
+            // Clicking re-enables a released pointer constraint.
+            if self.niri.pointer_constraint_released.take().is_some() {
+                self.niri.maybe_activate_pointer_constraint();
+            }
+
             let is_overview_open = self.niri.layout.is_overview_open();
 
             if is_overview_open && !pointer.is_grabbed() && button == Some(MouseButton::Right) {
@@ -2546,6 +3223,13 @@ impl State {
                     // So we can use that reference directly
                     if let Some((tile, hit)) = self.niri.layout.find_tile_for_window(mapped, location) {
                         if let Some(button_idx) = tile.hit_top_bar(hit) {
+                            // The close button force kills unresponsive windows, which can't
+                            // handle a regular close request.
+                            if button_idx == 2 && mapped.is_unresponsive() {
+                                self.force_kill_window(&window);
+                                return;
+                            }
+
                             // Run the appropriate command based on the button index
                             let command = match button_idx {
                                 0 => "niri msg action maximize-column",
@@ -2744,6 +3428,9 @@ impl State {
     }
 
     fn on_pointer_axis<I: InputBackend>(&mut self, event: I::PointerAxisEvent) {
+        // Releasing Mod after Mod+scroll isn't a tap.
+        self.niri.tap_tracker.cancel();
+
         let pointer = &self.niri.seat.get_pointer().unwrap();
 
         let source = event.source();
@@ -2762,6 +3449,7 @@ impl State {
         let vertical_amount_v120 = event.amount_v120(Axis::Vertical);
 
         let is_overview_open = self.niri.layout.is_overview_open();
+        let area = self.pointer_bind_area();
 
         // We should only handle scrolling in the overview if the pointer is not over a (top or
         // overlay) layer surface.
@@ -2783,6 +3471,58 @@ impl State {
             false
         };
 
+        // Handle the kinetic wheel view scroll, which takes precedence over the wheel bindings.
+        if source == AxisSource::Wheel && !is_overview_open {
+            let config = self.niri.config.borrow().gestures.wheel_view_scroll;
+            let mods = self.niri.seat.get_keyboard().unwrap().modifier_state();
+            let modifiers = modifiers_from_state(mods);
+
+            let mut trigger_mods = config.modifiers;
+            if trigger_mods.contains(Modifiers::COMPOSITOR) {
+                trigger_mods.remove(Modifiers::COMPOSITOR);
+                trigger_mods.insert(mod_key.to_modifiers());
+            }
+
+            if config.on && modifiers == trigger_mods {
+                if let Some((output, ws)) = self.niri.workspace_under_cursor(true) {
+                    let ws_id = ws.id();
+                    let ws_idx = self.niri.layout.find_workspace_by_id(ws_id).unwrap().0;
+                    self.niri.layout.wheel_scroll_gesture_begin(&output, ws_idx);
+                }
+
+                // Both wheel axes scroll the view horizontally.
+                let v120 =
+                    horizontal_amount_v120.unwrap_or(0.) + vertical_amount_v120.unwrap_or(0.);
+                let delta = v120 / 120. * config.distance.0;
+                self.niri
+                    .layout
+                    .wheel_scroll_gesture_update(delta, timestamp);
+
+                // End the gesture once the wheel stops, letting the view coast to a column.
+                if let Some(token) = self.niri.wheel_view_scroll_timer.take() {
+                    self.niri.event_loop.remove(token);
+                }
+                let timer = Timer::from_duration(WHEEL_VIEW_SCROLL_TIMEOUT);
+                let token = self
+                    .niri
+                    .event_loop
+                    .insert_source(timer, |_, _, state| {
+                        state.niri.wheel_view_scroll_timer = None;
+                        if state.niri.layout.wheel_scroll_gesture_end() {
+                            // FIXME: granular.
+                            state.niri.queue_redraw_all();
+                        }
+                        TimeoutAction::Drop
+                    })
+                    .unwrap();
+                self.niri.wheel_view_scroll_timer = Some(token);
+
+                // FIXME: granular.
+                self.niri.queue_redraw_all();
+                return;
+            }
+        }
+
         // Handle wheel scroll bindings.
         if source == AxisSource::Wheel {
             // If we have a scroll bind with current modifiers, then accumulate and don't pass to
@@ -2795,56 +3535,56 @@ impl State {
                 let horizontal = horizontal_amount_v120.unwrap_or(0.);
                 let ticks = self.niri.horizontal_wheel_tracker.accumulate(horizontal);
                 if ticks != 0 {
-                    let (bind_left, bind_right) = if should_handle_in_overview
-                        && modifiers.is_empty()
-                    {
-                        let bind_left = Some(Bind {
-                            key: Key {
-                                trigger: Trigger::WheelScrollLeft,
-                                modifiers: Modifiers::empty(),
-                            },
-                            action: Action::FocusColumnLeftUnderMouse,
-                            repeat: true,
-                            cooldown: None,
-                            allow_when_locked: false,
-                            allow_inhibiting: false,
-                            hotkey_overlay_title: None,
-                        });
-                        let bind_right = Some(Bind {
-                            key: Key {
-                                trigger: Trigger::WheelScrollRight,
-                                modifiers: Modifiers::empty(),
-                            },
-                            action: Action::FocusColumnRightUnderMouse,
-                            repeat: true,
-                            cooldown: None,
-                            allow_when_locked: false,
-                            allow_inhibiting: false,
-                            hotkey_overlay_title: None,
-                        });
-                        (bind_left, bind_right)
-                    } else {
-                        let config = self.niri.config.borrow();
-                        let bindings = &config.binds;
-                        let bind_left =
-                            find_configured_bind(bindings, mod_key, Trigger::WheelScrollLeft, mods);
-                        let bind_right = find_configured_bind(
-                            bindings,
-                            mod_key,
-                            Trigger::WheelScrollRight,
-                            mods,
-                        );
-                        (bind_left, bind_right)
-                    };
+                    let (bind_left, bind_right) =
+                        if should_handle_in_overview && modifiers.is_empty() {
+                            let bind_left = Some(Bind {
+                                key: Key {
+                                    trigger: Trigger::WheelScrollLeft,
+                                    modifiers: Modifiers::empty(),
+                                },
+                                action: Action::FocusColumnLeftUnderMouse,
+                                repeat: true,
+                                cooldown: None,
+                                allow_when_locked: false,
+                                allow_inhibiting: false,
+                                hotkey_overlay_title: None,
+                                area: BindArea::Anywhere,
+                                activation: BindActivation::Press,
+                            });
+                            let bind_right = Some(Bind {
+                                key: Key {
+                                    trigger: Trigger::WheelScrollRight,
+                                    modifiers: Modifiers::empty(),
+                                },
+                                action: Action::FocusColumnRightUnderMouse,
+                                repeat: true,
+                                cooldown: None,
+                                allow_when_locked: false,
+                                allow_inhibiting: false,
+                                hotkey_overlay_title: None,
+                                area: BindArea::Anywhere,
+                                activation: BindActivation::Press,
+                            });
+                            (bind_left, bind_right)
+                        } else {
+                            let config = self.niri.config.borrow();
+                            let bindings = &config.binds;
+                            let find = |trigger| {
+                                find_configured_pointer_bind(bindings, mod_key, trigger, mods, area)
+                            };
+                            let bind_left = find(Trigger::WheelScrollLeft);
+                            let bind_right = find(Trigger::WheelScrollRight);
+                            (bind_left, bind_right)
+                        };
 
                     if let Some(right) = bind_right {
                         for _ in 0..ticks {
-                            self.handle_bind(right.clone());
+                            self.handle_pointer_bind(right.clone());
                         }
                     }
                     if let Some(left) = bind_left {
                         for _ in ticks..0 {
-                            self.handle_bind(left.clone());
+                            self.handle_pointer_bind(left.clone());
                         }
                     }
                 }
@@ -2865,6 +3605,8 @@ impl State {
                             allow_when_locked: false,
                             allow_inhibiting: false,
                             hotkey_overlay_title: None,
+                            area: BindArea::Anywhere,
+                            activation: BindActivation::Press,
                         });
                         let bind_down = Some(Bind {
                             key: Key {
@@ -2877,6 +3619,8 @@ impl State {
                             allow_when_locked: false,
                             allow_inhibiting: false,
                             hotkey_overlay_title: None,
+                            area: BindArea::Anywhere,
+                            activation: BindActivation::Press,
                         });
                         (bind_up, bind_down)
                     } else if should_handle_in_overview && modifiers == Modifiers::SHIFT {
@@ -2891,6 +3635,8 @@ impl State {
                             allow_when_locked: false,
                             allow_inhibiting: false,
                             hotkey_overlay_title: None,
+                            area: BindArea::Anywhere,
+                            activation: BindActivation::Press,
                         });
                         let bind_down = Some(Bind {
                             key: Key {
@@ -2903,26 +3649,29 @@ impl State {
                             allow_when_locked: false,
                             allow_inhibiting: false,
                             hotkey_overlay_title: None,
+                            area: BindArea::Anywhere,
+                            activation: BindActivation::Press,
                         });
                         (bind_up, bind_down)
                     } else {
                         let config = self.niri.config.borrow();
                         let bindings = &config.binds;
-                        let bind_up =
-                            find_configured_bind(bindings, mod_key, Trigger::WheelScrollUp, mods);
-                        let bind_down =
-                            find_configured_bind(bindings, mod_key, Trigger::WheelScrollDown, mods);
+                        let find = |trigger| {
+                            find_configured_pointer_bind(bindings, mod_key, trigger, mods, area)
+                        };
+                        let bind_up = find(Trigger::WheelScrollUp);
+                        let bind_down = find(Trigger::WheelScrollDown);
                         (bind_up, bind_down)
                     };
 
                     if let Some(down) = bind_down {
                         for _ in 0..ticks {
-                            self.handle_bind(down.clone());
+                            self.handle_pointer_bind(down.clone());
                         }
                     }
                     if let Some(up) = bind_up {
                         for _ in ticks..0 {
-                            self.handle_bind(up.clone());
+                            self.handle_pointer_bind(up.clone());
                         }
                     }
                 }
@@ -3048,20 +3797,21 @@ impl State {
                 if ticks != 0 {
                     let config = self.niri.config.borrow();
                     let bindings = &config.binds;
-                    let bind_left =
-                        find_configured_bind(bindings, mod_key, Trigger::TouchpadScrollLeft, mods);
-                    let bind_right =
-                        find_configured_bind(bindings, mod_key, Trigger::TouchpadScrollRight, mods);
+                    let find = |trigger| {
+                        find_configured_pointer_bind(bindings, mod_key, trigger, mods, area)
+                    };
+                    let bind_left = find(Trigger::TouchpadScrollLeft);
+                    let bind_right = find(Trigger::TouchpadScrollRight);
                     drop(config);
 
                     if let Some(right) = bind_right {
                         for _ in 0..ticks {
-                            self.handle_bind(right.clone());
+                            self.handle_pointer_bind(right.clone());
                         }
                     }
                     if let Some(left) = bind_left {
                         for _ in ticks..0 {
-                            self.handle_bind(left.clone());
+                            self.handle_pointer_bind(left.clone());
                         }
                     }
                 }
@@ -3073,20 +3823,21 @@ impl State {
                 if ticks != 0 {
                     let config = self.niri.config.borrow();
                     let bindings = &config.binds;
-                    let bind_up =
-                        find_configured_bind(bindings, mod_key, Trigger::TouchpadScrollUp, mods);
-                    let bind_down =
-                        find_configured_bind(bindings, mod_key, Trigger::TouchpadScrollDown, mods);
+                    let find = |trigger| {
+                        find_configured_pointer_bind(bindings, mod_key, trigger, mods, area)
+                    };
+                    let bind_up = find(Trigger::TouchpadScrollUp);
+                    let bind_down = find(Trigger::TouchpadScrollDown);
                     drop(config);
 
                     if let Some(down) = bind_down {
                         for _ in 0..ticks {
-                            self.handle_bind(down.clone());
+                            self.handle_pointer_bind(down.clone());
                         }
                     }
                     if let Some(up) = bind_up {
                         for _ in ticks..0 {
-                            self.handle_bind(up.clone());
+                            self.handle_pointer_bind(up.clone());
                         }
                     }
                 }
@@ -3371,17 +4122,32 @@ impl State {
     }
 
     fn on_gesture_swipe_begin<I: InputBackend>(&mut self, event: I::GestureSwipeBeginEvent) {
-        if event.fingers() == 3 {
-            self.niri.gesture_swipe_3f_cumulative = Some((0., 0.));
+        self.niri.tap_tracker.cancel();
 
-            // We handled this event.
-            return;
-        } else if event.fingers() == 4 {
-            self.niri.layout.overview_gesture_begin();
-            self.niri.queue_redraw_all();
+        let action = {
+            let gestures = &self.niri.config.borrow().gestures.touchpad;
+            match event.fingers() {
+                3 => gestures.three_finger_swipe,
+                4 => gestures.four_finger_swipe,
+                _ => TouchpadSwipeAction::Off,
+            }
+        };
 
-            // We handled this event.
-            return;
+        match action {
+            TouchpadSwipeAction::Overview => {
+                self.niri.layout.overview_gesture_begin();
+                self.niri.queue_redraw_all();
+
+                // We handled this event.
+                return;
+            }
+            TouchpadSwipeAction::Off => (),
+            action => {
+                self.niri.gesture_swipe_cumulative = Some((action, 0., 0.));
+
+                // We handled this event.
+                return;
+            }
         }
 
         let serial = SERIAL_COUNTER.next_serial();
@@ -3429,17 +4195,27 @@ impl State {
 
         let is_overview_open = self.niri.layout.is_overview_open();
 
-        if let Some((cx, cy)) = &mut self.niri.gesture_swipe_3f_cumulative {
+        let threshold = {
+            let gestures = &self.niri.config.borrow().gestures;
+            gestures.touchpad.swipe_threshold.0
+        };
+        if let Some((action, cx, cy)) = &mut self.niri.gesture_swipe_cumulative {
             *cx += delta_x;
             *cy += delta_y;
 
-            // Check if the gesture moved far enough to decide. Threshold copied from GNOME Shell.
-            let (cx, cy) = (*cx, *cy);
-            if cx * cx + cy * cy >= 16. * 16. {
-                self.niri.gesture_swipe_3f_cumulative = None;
+            // Check if the gesture moved far enough to decide.
+            let (action, cx, cy) = (*action, *cx, *cy);
+            if cx * cx + cy * cy >= threshold * threshold {
+                self.niri.gesture_swipe_cumulative = None;
+
+                let is_horizontal = match action {
+                    TouchpadSwipeAction::WorkspaceSwitch => false,
+                    TouchpadSwipeAction::ColumnScroll => true,
+                    _ => cx.abs() > cy.abs(),
+                };
 
                 if let Some(output) = self.niri.output_under_cursor() {
-                    if cx.abs() > cy.abs() {
+                    if is_horizontal {
                         let output_ws = if is_overview_open {
                             self.niri.workspace_under_cursor(true)
                         } else {
@@ -3523,7 +4299,7 @@ impl State {
     }
 
     fn on_gesture_swipe_end<I: InputBackend>(&mut self, event: I::GestureSwipeEndEvent) {
-        self.niri.gesture_swipe_3f_cumulative = None;
+        self.niri.gesture_swipe_cumulative = None;
 
         let mut handled = false;
         let res = self.niri.layout.workspace_switch_gesture_end(Some(true));
@@ -3567,6 +4343,26 @@ impl State {
     }
 
     fn on_gesture_pinch_begin<I: InputBackend>(&mut self, event: I::GesturePinchBeginEvent) {
+        self.niri.tap_tracker.cancel();
+
+        let action = {
+            let gestures = &self.niri.config.borrow().gestures.touchpad;
+            match event.fingers() {
+                3 => gestures.three_finger_pinch,
+                4 => gestures.four_finger_pinch,
+                _ => TouchpadPinchAction::Off,
+            }
+        };
+
+        if action == TouchpadPinchAction::Overview {
+            self.niri.gesture_pinch_last_scale = Some(1.);
+            self.niri.layout.overview_gesture_begin();
+            self.niri.queue_redraw_all();
+
+            // We handled this event.
+            return;
+        }
+
         let serial = SERIAL_COUNTER.next_serial();
         let pointer = self.niri.seat.get_pointer().unwrap();
 
@@ -3585,6 +4381,22 @@ impl State {
     }
 
     fn on_gesture_pinch_update<I: InputBackend>(&mut self, event: I::GesturePinchUpdateEvent) {
+        if let Some(last_scale) = self.niri.gesture_pinch_last_scale {
+            // The scale is relative to the start of the gesture, so pinching in makes it go down.
+            let scale = event.scale();
+            self.niri.gesture_pinch_last_scale = Some(scale);
+
+            let delta = (last_scale - scale) * PINCH_OVERVIEW_MOVEMENT;
+            let timestamp = Duration::from_micros(event.time());
+            let res = self.niri.layout.overview_gesture_update(delta, timestamp);
+            if res == Some(true) {
+                self.niri.queue_redraw_all();
+            }
+
+            // We handled this event.
+            return;
+        }
+
         let pointer = self.niri.seat.get_pointer().unwrap();
 
         if self.update_pointer_contents() {
@@ -3603,6 +4415,15 @@ impl State {
     }
 
     fn on_gesture_pinch_end<I: InputBackend>(&mut self, event: I::GesturePinchEndEvent) {
+        if self.niri.gesture_pinch_last_scale.take().is_some() {
+            if self.niri.layout.overview_gesture_end() {
+                self.niri.queue_redraw_all();
+            }
+
+            // We handled this event.
+            return;
+        }
+
         let serial = SERIAL_COUNTER.next_serial();
         let pointer = self.niri.seat.get_pointer().unwrap();
 
@@ -3786,6 +4607,21 @@ impl State {
             } else if let Some(output) = under.output {
                 self.niri.layout.focus_output(&output);
 
+                // Swipes on the workspace background scroll the layout.
+                let is_background = match &under.layer {
+                    Some(layer) => layer.layer() == Layer::Background,
+                    None => true,
+                };
+                if is_background && !self.niri.config.borrow().gestures.touchscreen.off {
+                    let start_data = TouchGrabStartData {
+                        focus: None,
+                        slot,
+                        location: pos,
+                    };
+                    let grab = TouchSwipeGrab::new(start_data, output);
+                    handle.set_grab(self, grab, serial);
+                }
+
                 // FIXME: granular.
                 self.niri.queue_redraw_all();
             }
@@ -3903,6 +4739,15 @@ impl State {
             self.backend.on_output_config_changed(&mut self.niri);
         }
 
+        if switch == Switch::TabletMode {
+            let is_tablet_mode = evt.state() == SwitchState::On;
+            debug!("tablet mode {}", if is_tablet_mode { "on" } else { "off" });
+            self.niri.is_tablet_mode = is_tablet_mode;
+
+            let config = self.niri.config.borrow();
+            self.niri.layout.set_tablet_mode(&config, is_tablet_mode);
+        }
+
         let action = {
             let bindings = &self.niri.config.borrow().switch_events;
             find_configured_switch_action(bindings, switch, evt.state())
@@ -3975,6 +4820,8 @@ fn should_intercept_key(
                     // inhibited.
                     allow_inhibiting: false,
                     hotkey_overlay_title: None,
+                    area: BindArea::Anywhere,
+                    activation: BindActivation::Press,
                 });
             }
         }
@@ -4041,6 +4888,8 @@ fn find_bind(
             // Hardcoded binds must never be inhibited.
             allow_inhibiting: false,
             hotkey_overlay_title: None,
+            area: BindArea::Anywhere,
+            activation: BindActivation::Press,
         });
     }
 
@@ -4053,6 +4902,34 @@ fn find_configured_bind(
     mod_key: ModKey,
     trigger: Trigger,
     mods: ModifiersState,
+) -> Option<Bind> {
+    find_configured_bind_in_area(bindings, mod_key, trigger, mods, BindArea::Anywhere)
+}
+
+/// Finds a pointer bind, preferring binds restricted to the area under the pointer.
+fn find_configured_pointer_bind(
+    bindings: &Binds,
+    mod_key: ModKey,
+    trigger: Trigger,
+    mods: ModifiersState,
+    area: BindArea,
+) -> Option<Bind> {
+    if area != BindArea::Anywhere {
+        let bind = find_configured_bind_in_area(bindings, mod_key, trigger, mods, area);
+        if bind.is_some() {
+            return bind;
+        }
+    }
+
+    find_configured_bind(bindings, mod_key, trigger, mods)
+}
+
+fn find_configured_bind_in_area(
+    bindings: &Binds,
+    mod_key: ModKey,
+    trigger: Trigger,
+    mods: ModifiersState,
+    area: BindArea,
 ) -> Option<Bind> {
     // Handle configured binds.
     let mut modifiers = modifiers_from_state(mods);
@@ -4063,7 +4940,7 @@ fn find_configured_bind(
     }
 
     for bind in &bindings.0 {
-        if bind.key.trigger != trigger {
+        if bind.key.trigger != trigger || bind.area != area {
             continue;
         }
 
@@ -4122,6 +4999,38 @@ fn modifiers_from_state(mods: ModifiersState) -> Modifiers {
     modifiers
 }
 
+/// Returns the modifier that a modifier keysym sets, if any.
+fn keysym_modifier(sym: Keysym) -> Modifiers {
+    match sym {
+        Keysym::Control_L | Keysym::Control_R => Modifiers::CTRL,
+        Keysym::Shift_L | Keysym::Shift_R => Modifiers::SHIFT,
+        Keysym::Alt_L | Keysym::Alt_R | Keysym::Meta_L | Keysym::Meta_R => Modifiers::ALT,
+        Keysym::Super_L | Keysym::Super_R | Keysym::Hyper_L | Keysym::Hyper_R => Modifiers::SUPER,
+        Keysym::ISO_Level3_Shift => Modifiers::ISO_LEVEL3_SHIFT,
+        Keysym::ISO_Level5_Shift => Modifiers::ISO_LEVEL5_SHIFT,
+        _ => Modifiers::empty(),
+    }
+}
+
+/// Returns whether a key event lets go of the repeating bind.
+///
+/// The repeat stops when any other key is pressed, or when the repeating key or one of the bind's
+/// own modifiers is released, since the bind is no longer held then. `key_mods` are the modifiers
+/// that the key sets.
+fn stops_bind_repeat(
+    repeat_key: Keycode,
+    repeat_mods: Modifiers,
+    key_code: Keycode,
+    key_mods: Modifiers,
+    pressed: bool,
+) -> bool {
+    if pressed {
+        repeat_key != key_code
+    } else {
+        repeat_key == key_code || repeat_mods.intersects(key_mods)
+    }
+}
+
 fn should_activate_monitors<I: InputBackend>(event: &InputEvent<I>) -> bool {
     match event {
         InputEvent::Keyboard { event } if event.state() == KeyState::Pressed => true,
@@ -4158,9 +5067,9 @@ fn should_hide_hotkey_overlay<I: InputBackend>(event: &InputEvent<I>) -> bool {
     }
 }
 
-fn should_hide_exit_confirm_dialog<I: InputBackend>(event: &InputEvent<I>) -> bool {
+fn should_hide_dialog<I: InputBackend>(event: &InputEvent<I>) -> bool {
+    // Key presses are handled in on_keyboard() since some of them navigate the dialogs.
     match event {
-        InputEvent::Keyboard { event } if event.state() == KeyState::Pressed => true,
         InputEvent::PointerButton { event } if event.state() == ButtonState::Pressed => true,
         InputEvent::PointerAxis { .. }
         | InputEvent::GestureSwipeBegin { .. }
@@ -4202,6 +5111,8 @@ fn allowed_when_locked(action: &Action) -> bool {
             | Action::Suspend
             | Action::PowerOffMonitors
             | Action::PowerOnMonitors
+            | Action::ToggleMonitorPower(_)
+            | Action::AdjustBrightness(_)
             | Action::SwitchLayout(_)
             | Action::ToggleKeyboardShortcutsInhibit
     )
@@ -4215,6 +5126,7 @@ fn allowed_during_screenshot(action: &Action) -> bool {
             | Action::Suspend
             | Action::PowerOffMonitors
             | Action::PowerOnMonitors
+            | Action::ToggleMonitorPower(_)
             // The screenshot UI can handle these.
             | Action::MoveColumnLeft
             | Action::MoveColumnLeftOrToMonitorLeft
@@ -4276,6 +5188,8 @@ fn hardcoded_overview_bind(raw: Keysym, mods: ModifiersState) -> Option<Bind> {
         allow_when_locked: false,
         allow_inhibiting: false,
         hotkey_overlay_title: None,
+        area: BindArea::Anywhere,
+        activation: BindActivation::Press,
     })
 }
 
@@ -4284,13 +5198,6 @@ pub fn apply_libinput_settings(config: &niri_config::Input, device: &mut input::
     let is_touchpad = device.config_tap_finger_count() > 0;
     if is_touchpad {
         let c = &config.touchpad;
-        let _ = device.config_send_events_set_mode(if c.off {
-            input::SendEventsMode::DISABLED
-        } else if c.disabled_on_external_mouse {
-            input::SendEventsMode::DISABLED_ON_EXTERNAL_MOUSE
-        } else {
-            input::SendEventsMode::ENABLED
-        });
         let _ = device.config_tap_set_enabled(c.tap);
         let _ = device.config_dwt_set_enabled(c.dwt);
         let _ = device.config_dwtp_set_enabled(c.dwtp);
@@ -4365,11 +5272,6 @@ pub fn apply_libinput_settings(config: &niri_config::Input, device: &mut input::
         && !is_trackpoint;
     if is_mouse {
         let c = &config.mouse;
-        let _ = device.config_send_events_set_mode(if c.off {
-            input::SendEventsMode::DISABLED
-        } else {
-            input::SendEventsMode::ENABLED
-        });
         let _ = device.config_scroll_set_natural_scroll_enabled(c.natural_scroll);
         let _ = device.config_accel_set_speed(c.accel_speed.0);
         let _ = device.config_left_handed_set(c.left_handed);
@@ -4402,11 +5304,6 @@ pub fn apply_libinput_settings(config: &niri_config::Input, device: &mut input::
 
     if is_trackball {
         let c = &config.trackball;
-        let _ = device.config_send_events_set_mode(if c.off {
-            input::SendEventsMode::DISABLED
-        } else {
-            input::SendEventsMode::ENABLED
-        });
         let _ = device.config_scroll_set_natural_scroll_enabled(c.natural_scroll);
         let _ = device.config_accel_set_speed(c.accel_speed.0);
         let _ = device.config_middle_emulation_set_enabled(c.middle_emulation);
@@ -4439,11 +5336,6 @@ pub fn apply_libinput_settings(config: &niri_config::Input, device: &mut input::
 
     if is_trackpoint {
         let c = &config.trackpoint;
-        let _ = device.config_send_events_set_mode(if c.off {
-            input::SendEventsMode::DISABLED
-        } else {
-            input::SendEventsMode::ENABLED
-        });
         let _ = device.config_scroll_set_natural_scroll_enabled(c.natural_scroll);
         let _ = device.config_accel_set_speed(c.accel_speed.0);
         let _ = device.config_left_handed_set(c.left_handed);
@@ -4477,11 +5369,6 @@ pub fn apply_libinput_settings(config: &niri_config::Input, device: &mut input::
     let is_tablet = device.has_capability(input::DeviceCapability::TabletTool);
     if is_tablet {
         let c = &config.tablet;
-        let _ = device.config_send_events_set_mode(if c.off {
-            input::SendEventsMode::DISABLED
-        } else {
-            input::SendEventsMode::ENABLED
-        });
 
         #[rustfmt::skip]
         const IDENTITY_MATRIX: [f32; 6] = [
@@ -4501,13 +5388,141 @@ pub fn apply_libinput_settings(config: &niri_config::Input, device: &mut input::
     }
 
     let is_touch = device.has_capability(input::DeviceCapability::Touch);
-    if is_touch {
-        let c = &config.touch;
-        let _ = device.config_send_events_set_mode(if c.off {
+
+    let kinds = InputDeviceKinds {
+        touchpad: is_touchpad,
+        mouse: is_mouse,
+        trackball: is_trackball,
+        trackpoint: is_trackpoint,
+        tablet: is_tablet,
+        touch: is_touch,
+    };
+    let devices = config
+        .devices
+        .iter()
+        .filter(|c| input_device_matches(c, device))
+        .map(|c| &c.settings);
+    let mode = send_events_mode(config, kinds, devices);
+    let _ = device.config_send_events_set_mode(mode);
+
+    for c in &config.devices {
+        if input_device_matches(c, device) {
+            apply_input_device_settings(&c.settings, device);
+        }
+    }
+}
+
+/// Kinds of input devices that have their own section in the input config.
+#[derive(Debug, Default, Clone, Copy)]
+struct InputDeviceKinds {
+    touchpad: bool,
+    mouse: bool,
+    trackball: bool,
+    trackpoint: bool,
+    tablet: bool,
+    touch: bool,
+}
+
+/// Returns the send-events mode for a device of the given kinds and matching device sections.
+///
+/// This starts from enabled rather than from the device's current mode, so that removing an `off`
+/// from the config and reloading turns the device back on.
+fn send_events_mode<'a>(
+    config: &niri_config::Input,
+    kinds: InputDeviceKinds,
+    devices: impl IntoIterator<Item = &'a niri_config::InputDeviceSettings>,
+) -> input::SendEventsMode {
+    let off_mode = |off| {
+        if off {
             input::SendEventsMode::DISABLED
         } else {
             input::SendEventsMode::ENABLED
-        });
+        }
+    };
+
+    let mut mode = input::SendEventsMode::ENABLED;
+    if kinds.touchpad {
+        let c = &config.touchpad;
+        mode = if !c.off && c.disabled_on_external_mouse {
+            input::SendEventsMode::DISABLED_ON_EXTERNAL_MOUSE
+        } else {
+            off_mode(c.off)
+        };
+    }
+    if kinds.mouse {
+        mode = off_mode(config.mouse.off);
+    }
+    if kinds.trackball {
+        mode = off_mode(config.trackball.off);
+    }
+    if kinds.trackpoint {
+        mode = off_mode(config.trackpoint.off);
+    }
+    if kinds.tablet {
+        mode = off_mode(config.tablet.off);
+    }
+    if kinds.touch {
+        mode = off_mode(config.touch.off);
+    }
+
+    for c in devices {
+        if let Some(off) = c.off {
+            mode = off_mode(off);
+        }
+    }
+
+    mode
+}
+
+fn input_device_matches(c: &niri_config::InputDevice, device: &input::Device) -> bool {
+    c.name.as_deref().map_or(true, |name| name == device.name())
+        && c.vid.map_or(true, |vid| vid == device.id_vendor())
+        && c.pid.map_or(true, |pid| pid == device.id_product())
+}
+
+fn apply_input_device_settings(c: &niri_config::InputDeviceSettings, device: &mut input::Device) {
+    // The send-events mode is set together with the per-type one in apply_libinput_settings().
+    if let Some(tap) = c.tap {
+        let _ = device.config_tap_set_enabled(tap);
+    }
+    if let Some(dwt) = c.dwt {
+        let _ = device.config_dwt_set_enabled(dwt);
+    }
+    if let Some(dwtp) = c.dwtp {
+        let _ = device.config_dwtp_set_enabled(dwtp);
+    }
+    if let Some(drag) = c.drag {
+        let _ = device.config_tap_set_drag_enabled(drag);
+    }
+    if let Some(drag_lock) = c.drag_lock {
+        let _ = device.config_tap_set_drag_lock_enabled(drag_lock);
+    }
+    if let Some(natural_scroll) = c.natural_scroll {
+        let _ = device.config_scroll_set_natural_scroll_enabled(natural_scroll);
+    }
+    if let Some(accel_speed) = c.accel_speed {
+        let _ = device.config_accel_set_speed(accel_speed.0);
+    }
+    if let Some(accel_profile) = c.accel_profile {
+        let _ = device.config_accel_set_profile(accel_profile.into());
+    }
+    if let Some(method) = c.scroll_method {
+        let _ = device.config_scroll_set_method(method.into());
+    }
+    if let Some(button) = c.scroll_button {
+        let _ = device.config_scroll_set_button(button);
+    }
+    if let Some(method) = c.click_method {
+        let _ = device.config_click_set_method(method.into());
+    }
+    if let Some(tap_button_map) = c.tap_button_map {
+        let _ = device.config_tap_set_button_map(tap_button_map.into());
+    }
+    if let Some(left_handed) = c.left_handed {
+        let _ = device.config_left_handed_set(left_handed);
+    }
+    if let Some(middle_emulation) = c.middle_emulation {
+        let _ = device.config_middle_emulation_set_enabled(middle_emulation);
     }
 }
 
@@ -4591,6 +5606,8 @@ mod tests {
             allow_when_locked: false,
             allow_inhibiting: true,
             hotkey_overlay_title: None,
+            area: BindArea::Anywhere,
+            activation: BindActivation::Press,
         }]);
 
         let comp_mod = ModKey::Super;
@@ -4777,6 +5794,8 @@ mod tests {
                 allow_when_locked: false,
                 allow_inhibiting: true,
                 hotkey_overlay_title: None,
+                area: BindArea::Anywhere,
+                activation: BindActivation::Press,
             },
             Bind {
                 key: Key {
@@ -4789,6 +5808,8 @@ mod tests {
                 allow_when_locked: false,
                 allow_inhibiting: true,
                 hotkey_overlay_title: None,
+                area: BindArea::Anywhere,
+                activation: BindActivation::Press,
             },
             Bind {
                 key: Key {
@@ -4801,6 +5822,8 @@ mod tests {
                 allow_when_locked: false,
                 allow_inhibiting: true,
                 hotkey_overlay_title: None,
+                area: BindArea::Anywhere,
+                activation: BindActivation::Press,
             },
             Bind {
                 key: Key {
@@ -4813,6 +5836,8 @@ mod tests {
                 allow_when_locked: false,
                 allow_inhibiting: true,
                 hotkey_overlay_title: None,
+                area: BindArea::Anywhere,
+                activation: BindActivation::Press,
             },
             Bind {
                 key: Key {
@@ -4825,6 +5850,8 @@ mod tests {
                 allow_when_locked: false,
                 allow_inhibiting: true,
                 hotkey_overlay_title: None,
+                area: BindArea::Anywhere,
+                activation: BindActivation::Press,
             },
         ]);
 
@@ -4947,4 +5974,99 @@ mod tests {
             None,
         );
     }
+
+    #[test]
+    fn pointer_bind_area_handling() {
+        let bind = |area| Bind {
+            key: Key {
+                trigger: Trigger::MouseMiddle,
+                modifiers: Modifiers::COMPOSITOR,
+            },
+            action: Action::CloseWindow,
+            repeat: true,
+            cooldown: None,
+            allow_when_locked: false,
+            allow_inhibiting: true,
+            hotkey_overlay_title: None,
+            area,
+            activation: BindActivation::Press,
+        };
+        let bindings = Binds(vec![bind(BindArea::TopBar), bind(BindArea::Anywhere)]);
+        let mods = ModifiersState {
+            logo: true,
+            ..Default::default()
+        };
+
+        let find = |area| {
+            find_configured_pointer_bind(&bindings, ModKey::Super, Trigger::MouseMiddle, mods, area)
+        };
+        assert_eq!(find(BindArea::TopBar).as_ref(), Some(&bindings.0[0]));
+        assert_eq!(find(BindArea::Anywhere).as_ref(), Some(&bindings.0[1]));
+        assert_eq!(find(BindArea::Background).as_ref(), Some(&bindings.0[1]));
+
+        // Area binds don't trigger outside of their area.
+        let bindings = Binds(vec![bind(BindArea::TopBar)]);
+        let find = |area| {
+            find_configured_pointer_bind(&bindings, ModKey::Super, Trigger::MouseMiddle, mods, area)
+        };
+        assert_eq!(find(BindArea::TopBar).as_ref(), Some(&bindings.0[0]));
+        assert_eq!(find(BindArea::Background), None);
+        assert_eq!(
+            find_configured_bind(&bindings, ModKey::Super, Trigger::MouseMiddle, mods),
+            None
+        );
+    }
+
+    #[test]
+    fn send_events_mode_resets_on_reload() {
+        fn mode(config: &str, kinds: InputDeviceKinds) -> input::SendEventsMode {
+            let config = niri_config::Config::parse("test.kdl", config).unwrap();
+            let devices = config.input.devices.iter().map(|c| &c.settings);
+            send_events_mode(&config.input, kinds, devices)
+        }
+
+        // Keyboards have no section of their own, only device sections turn them off.
+        let keyboard = InputDeviceKinds::default();
+        let off = r#"input { device "Keyboard" { off true; }; }"#;
+        assert_eq!(mode(off, keyboard), input::SendEventsMode::DISABLED);
+        assert_eq!(mode("", keyboard), input::SendEventsMode::ENABLED);
+
+        let touchpad = InputDeviceKinds {
+            touchpad: true,
+            ..Default::default()
+        };
+        let external_mouse = "input { touchpad { disabled-on-external-mouse; }; }";
+        assert_eq!(
+            mode(external_mouse, touchpad),
+            input::SendEventsMode::DISABLED_ON_EXTERNAL_MOUSE
+        );
+        assert_eq!(mode("", touchpad), input::SendEventsMode::ENABLED);
+
+        // Device sections take precedence over the type sections.
+        let on = r#"input { touchpad { off; }; device "Touchpad" { off false; }; }"#;
+        assert_eq!(mode(on, touchpad), input::SendEventsMode::ENABLED);
+    }
+
+    #[test]
+    fn bind_repeat_stops_on_own_modifiers() {
+        let x = Keycode::new(53);
+        let y = Keycode::new(29);
+        let super_l = Keycode::new(133);
+        let shift_l = Keycode::new(50);
+        let mods = Modifiers::SUPER;
+
+        let stops = |key_code, sym, pressed| {
+            stops_bind_repeat(x, mods, key_code, keysym_modifier(sym), pressed)
+        };
+
+        // Mod+X repeats while X is held.
+        assert!(!stops(x, Keysym::x, true));
+        // Pressing another key or letting go of X or Mod stops it.
+        assert!(stops(y, Keysym::y, true));
+        assert!(stops(x, Keysym::x, false));
+        assert!(stops(super_l, Keysym::Super_L, false));
+        // Releasing a modifier that isn't part of the bind doesn't.
+        assert!(!stops(shift_l, Keysym::Shift_L, false));
+        assert!(!stops(y, Keysym::y, false));
+    }
 }
@@ -0,0 +1,470 @@
+//! Input injected by remote desktop sessions.
+
+use niri_config::ClientPermission;
+use smithay::backend::input::{
+    AbsolutePositionEvent, Axis, AxisRelativeDirection, AxisSource, ButtonState, Device,
+    DeviceCapability, Event, InputBackend, InputEvent, KeyState, KeyboardKeyEvent, Keycode,
+    PointerAxisEvent, PointerButtonEvent, PointerMotionAbsoluteEvent, PointerMotionEvent,
+    UnusedEvent,
+};
+use smithay::input::keyboard::Keysym;
+use smithay::output::Output;
+use smithay::utils::{Logical, Point};
+
+use super::backend_ext::NiriInputDevice;
+use crate::dbus::mutter_remote_desktop::{DevicePermissions, RemoteDesktopToNiri};
+use crate::dbus::mutter_screen_cast::StreamTargetId;
+use crate::niri::State;
+use crate::utils::{get_executable, get_monotonic_time, get_systemd_unit};
+
+/// Offset between evdev keycodes and xkb keycodes.
+const EVDEV_OFFSET: u32 = 8;
+
+pub struct RemoteDesktopInputBackend;
+
+/// The single device that all remote desktop sessions inject input through.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RemoteDesktopDevice;
+
+impl Device for RemoteDesktopDevice {
+    fn id(&self) -> String {
+        String::from("remote desktop")
+    }
+
+    fn name(&self) -> String {
+        String::from("remote desktop")
+    }
+
+    fn has_capability(&self, capability: DeviceCapability) -> bool {
+        matches!(
+            capability,
+            DeviceCapability::Keyboard | DeviceCapability::Pointer
+        )
+    }
+
+    fn usb_id(&self) -> Option<(u32, u32)> {
+        None
+    }
+
+    fn syspath(&self) -> Option<std::path::PathBuf> {
+        None
+    }
+}
+
+impl NiriInputDevice for RemoteDesktopDevice {
+    fn output(&self, _state: &State) -> Option<Output> {
+        None
+    }
+}
+
+pub struct RemoteDesktopKeyboardKeyEvent {
+    time: u64,
+    key_code: Keycode,
+    state: KeyState,
+}
+
+impl Event<RemoteDesktopInputBackend> for RemoteDesktopKeyboardKeyEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> RemoteDesktopDevice {
+        RemoteDesktopDevice
+    }
+}
+
+impl KeyboardKeyEvent<RemoteDesktopInputBackend> for RemoteDesktopKeyboardKeyEvent {
+    fn key_code(&self) -> Keycode {
+        self.key_code
+    }
+
+    fn state(&self) -> KeyState {
+        self.state
+    }
+
+    fn count(&self) -> u32 {
+        u32::from(self.state == KeyState::Pressed)
+    }
+}
+
+pub struct RemoteDesktopPointerMotionEvent {
+    time: u64,
+    dx: f64,
+    dy: f64,
+}
+
+impl Event<RemoteDesktopInputBackend> for RemoteDesktopPointerMotionEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> RemoteDesktopDevice {
+        RemoteDesktopDevice
+    }
+}
+
+impl PointerMotionEvent<RemoteDesktopInputBackend> for RemoteDesktopPointerMotionEvent {
+    fn delta_x(&self) -> f64 {
+        self.dx
+    }
+
+    fn delta_y(&self) -> f64 {
+        self.dy
+    }
+
+    fn delta_x_unaccel(&self) -> f64 {
+        self.dx
+    }
+
+    fn delta_y_unaccel(&self) -> f64 {
+        self.dy
+    }
+}
+
+/// Absolute motion, relative to the bounding rectangle of all outputs.
+pub struct RemoteDesktopPointerMotionAbsoluteEvent {
+    time: u64,
+    x: f64,
+    y: f64,
+}
+
+impl Event<RemoteDesktopInputBackend> for RemoteDesktopPointerMotionAbsoluteEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> RemoteDesktopDevice {
+        RemoteDesktopDevice
+    }
+}
+
+impl AbsolutePositionEvent<RemoteDesktopInputBackend> for RemoteDesktopPointerMotionAbsoluteEvent {
+    fn x(&self) -> f64 {
+        self.x
+    }
+
+    fn y(&self) -> f64 {
+        self.y
+    }
+
+    fn x_transformed(&self, width: i32) -> f64 {
+        self.x * f64::from(width)
+    }
+
+    fn y_transformed(&self, height: i32) -> f64 {
+        self.y * f64::from(height)
+    }
+}
+
+impl PointerMotionAbsoluteEvent<RemoteDesktopInputBackend>
+    for RemoteDesktopPointerMotionAbsoluteEvent
+{
+}
+
+pub struct RemoteDesktopPointerButtonEvent {
+    time: u64,
+    button: u32,
+    state: ButtonState,
+}
+
+impl Event<RemoteDesktopInputBackend> for RemoteDesktopPointerButtonEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> RemoteDesktopDevice {
+        RemoteDesktopDevice
+    }
+}
+
+impl PointerButtonEvent<RemoteDesktopInputBackend> for RemoteDesktopPointerButtonEvent {
+    fn button_code(&self) -> u32 {
+        self.button
+    }
+
+    fn state(&self) -> ButtonState {
+        self.state
+    }
+}
+
+pub struct RemoteDesktopPointerAxisEvent {
+    time: u64,
+    /// Amounts along the horizontal and the vertical axes.
+    amount: (f64, f64),
+    amount_v120: Option<(f64, f64)>,
+    source: AxisSource,
+}
+
+impl Event<RemoteDesktopInputBackend> for RemoteDesktopPointerAxisEvent {
+    fn time(&self) -> u64 {
+        self.time
+    }
+
+    fn device(&self) -> RemoteDesktopDevice {
+        RemoteDesktopDevice
+    }
+}
+
+fn tuple_axis<T>(tuple: (T, T), axis: Axis) -> T {
+    match axis {
+        Axis::Horizontal => tuple.0,
+        Axis::Vertical => tuple.1,
+    }
+}
+
+impl PointerAxisEvent<RemoteDesktopInputBackend> for RemoteDesktopPointerAxisEvent {
+    fn amount(&self, axis: Axis) -> Option<f64> {
+        Some(tuple_axis(self.amount, axis))
+    }
+
+    fn amount_v120(&self, axis: Axis) -> Option<f64> {
+        self.amount_v120.map(|v120| tuple_axis(v120, axis))
+    }
+
+    fn source(&self) -> AxisSource {
+        self.source
+    }
+
+    fn relative_direction(&self, _axis: Axis) -> AxisRelativeDirection {
+        AxisRelativeDirection::Identical
+    }
+}
+
+impl InputBackend for RemoteDesktopInputBackend {
+    type Device = RemoteDesktopDevice;
+
+    type KeyboardKeyEvent = RemoteDesktopKeyboardKeyEvent;
+    type PointerAxisEvent = RemoteDesktopPointerAxisEvent;
+    type PointerButtonEvent = RemoteDesktopPointerButtonEvent;
+    type PointerMotionEvent = RemoteDesktopPointerMotionEvent;
+    type PointerMotionAbsoluteEvent = RemoteDesktopPointerMotionAbsoluteEvent;
+
+    type GestureSwipeBeginEvent = UnusedEvent;
+    type GestureSwipeUpdateEvent = UnusedEvent;
+    type GestureSwipeEndEvent = UnusedEvent;
+    type GesturePinchBeginEvent = UnusedEvent;
+    type GesturePinchUpdateEvent = UnusedEvent;
+    type GesturePinchEndEvent = UnusedEvent;
+    type GestureHoldBeginEvent = UnusedEvent;
+    type GestureHoldEndEvent = UnusedEvent;
+
+    type TouchDownEvent = UnusedEvent;
+    type TouchUpEvent = UnusedEvent;
+    type TouchMotionEvent = UnusedEvent;
+    type TouchCancelEvent = UnusedEvent;
+    type TouchFrameEvent = UnusedEvent;
+    type TabletToolAxisEvent = UnusedEvent;
+    type TabletToolProximityEvent = UnusedEvent;
+    type TabletToolTipEvent = UnusedEvent;
+    type TabletToolButtonEvent = UnusedEvent;
+
+    type SwitchToggleEvent = UnusedEvent;
+
+    type SpecialEvent = UnusedEvent;
+}
+
+type Backend = RemoteDesktopInputBackend;
+
+impl State {
+    pub fn on_remote_desktop_msg(&mut self, msg: RemoteDesktopToNiri) {
+        let time = get_monotonic_time().as_micros() as u64;
+
+        let event = match msg {
+            RemoteDesktopToNiri::CheckPermissions { pid, reply } => {
+                let permissions = self.remote_desktop_permissions(pid);
+                if let Err(err) = reply.send_blocking(permissions) {
+                    warn!("error sending remote desktop permissions: {err:?}");
+                }
+                return;
+            }
+            RemoteDesktopToNiri::Keycode { keycode, pressed } => {
+                let key_code = Keycode::new(keycode + EVDEV_OFFSET);
+                InputEvent::<Backend>::Keyboard {
+                    event: RemoteDesktopKeyboardKeyEvent {
+                        time,
+                        key_code,
+                        state: key_state(pressed),
+                    },
+                }
+            }
+            RemoteDesktopToNiri::Keysym {
+                keysym,
+                pressed,
+                reply,
+            } => {
+                let key_code = self.keycode_for_keysym(Keysym::new(keysym));
+                let keycode = key_code.map(|key_code| key_code.raw() - EVDEV_OFFSET);
+                if let Err(err) = reply.send_blocking(keycode) {
+                    warn!("error sending remote desktop keycode: {err:?}");
+                }
+
+                let Some(key_code) = key_code else {
+                    debug!("no key produces keysym {keysym:#x} in the current layout");
+                    return;
+                };
+                InputEvent::<Backend>::Keyboard {
+                    event: RemoteDesktopKeyboardKeyEvent {
+                        time,
+                        key_code,
+                        state: key_state(pressed),
+                    },
+                }
+            }
+            RemoteDesktopToNiri::PointerMotion { dx, dy } => InputEvent::<Backend>::PointerMotion {
+                event: RemoteDesktopPointerMotionEvent { time, dx, dy },
+            },
+            RemoteDesktopToNiri::PointerMotionAbsolute { target, x, y } => {
+                let Some(pos) = self.remote_desktop_stream_position(&target, (x, y).into()) else {
+                    return;
+                };
+                let Some(bounds) = self.global_bounding_rectangle() else {
+                    return;
+                };
+                let pos = pos - bounds.loc.to_f64();
+                let size = bounds.size.to_f64();
+                InputEvent::<Backend>::PointerMotionAbsolute {
+                    event: RemoteDesktopPointerMotionAbsoluteEvent {
+                        time,
+                        x: pos.x / size.w,
+                        y: pos.y / size.h,
+                    },
+                }
+            }
+            RemoteDesktopToNiri::PointerButton { button, pressed } => {
+                let state = if pressed {
+                    ButtonState::Pressed
+                } else {
+                    ButtonState::Released
+                };
+                InputEvent::<Backend>::PointerButton {
+                    event: RemoteDesktopPointerButtonEvent {
+                        time,
+                        button,
+                        state,
+                    },
+                }
+            }
+            RemoteDesktopToNiri::PointerAxis {
+                dx,
+                dy,
+                source,
+                finish,
+            } => {
+                // Finishing a scroll sequence is signaled with zero amounts.
+                let amount = if finish { (0., 0.) } else { (dx, dy) };
+                InputEvent::<Backend>::PointerAxis {
+                    event: RemoteDesktopPointerAxisEvent {
+                        time,
+                        amount,
+                        amount_v120: None,
+                        source,
+                    },
+                }
+            }
+            RemoteDesktopToNiri::PointerAxisDiscrete { axis, steps } => {
+                let v120 = f64::from(steps) * 120.;
+                let amount = f64::from(steps) * 15.;
+                let (amount, amount_v120) = match axis {
+                    Axis::Horizontal => ((amount, 0.), (v120, 0.)),
+                    Axis::Vertical => ((0., amount), (0., v120)),
+                };
+                InputEvent::<Backend>::PointerAxis {
+                    event: RemoteDesktopPointerAxisEvent {
+                        time,
+                        amount,
+                        amount_v120: Some(amount_v120),
+                        source: AxisSource::Wheel,
+                    },
+                }
+            }
+        };
+
+        self.process_input_event(event);
+    }
+
+    /// Checks the virtual keyboard and pointer permissions of a remote desktop client, the same
+    /// way as for Wayland clients.
+    fn remote_desktop_permissions(&self, pid: Option<i32>) -> DevicePermissions {
+        let executable = pid.and_then(get_executable);
+        let unit = pid.and_then(get_systemd_unit);
+
+        let config = self.niri.config.borrow();
+        let security = &config.security;
+        let allows = |permission: &ClientPermission| {
+            permission.allows(
+                executable.as_deref(),
+                unit.as_deref(),
+                security.deny_by_default,
+            )
+        };
+        let permissions = DevicePermissions {
+            keyboard: allows(&security.virtual_keyboard),
+            pointer: allows(&security.virtual_pointer),
+        };
+
+        let denied: Vec<_> = [
+            (permissions.keyboard, "virtual keyboard"),
+            (permissions.pointer, "virtual pointer"),
+        ]
+        .into_iter()
+        .filter(|(allowed, _)| !allowed)
+        .map(|(_, name)| name)
+        .collect();
+        if denied.is_empty() {
+            return permissions;
+        }
+
+        let denied = denied.join(", ");
+        if security.log_only {
+            info!("would deny remote desktop client {executable:?} in unit {unit:?}: {denied}");
+            DevicePermissions {
+                keyboard: true,
+                pointer: true,
+            }
+        } else {
+            info!("denying remote desktop client {executable:?} in unit {unit:?}: {denied}");
+            permissions
+        }
+    }
+
+    /// Returns a key that produces the keysym without modifiers in the active layout.
+    fn keycode_for_keysym(&mut self, keysym: Keysym) -> Option<Keycode> {
+        let keyboard = self.niri.seat.get_keyboard().unwrap();
+        keyboard.with_xkb_state(self, |context| {
+            let xkb = context.xkb().lock().unwrap();
+            let layout = xkb.active_layout();
+            (EVDEV_OFFSET..=255).map(Keycode::new).find(|key_code| {
+                xkb.raw_syms_for_key_in_layout(*key_code, layout)
+                    .first()
+                    .is_some_and(|sym| *sym == keysym)
+            })
+        })
+    }
+
+    /// Converts a position within a screencast stream to the global position.
+    fn remote_desktop_stream_position(
+        &self,
+        target: &StreamTargetId,
+        pos: Point<f64, Logical>,
+    ) -> Option<Point<f64, Logical>> {
+        match target {
+            StreamTargetId::Output { name } => {
+                let output = self.niri.output_by_name_match(name)?;
+                let geo = self.niri.global_space.output_geometry(output)?;
+                Some(geo.loc.to_f64() + pos)
+            }
+            StreamTargetId::Window { .. } => {
+                debug!("absolute pointer motion is unsupported on window streams");
+                None
+            }
+        }
+    }
+}
+
+fn key_state(pressed: bool) -> KeyState {
+    if pressed {
+        KeyState::Pressed
+    } else {
+        KeyState::Released
+    }
+}
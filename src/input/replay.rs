@@ -0,0 +1,752 @@
+//! Recording input events to a file and replaying them with the same timing.
+//!
+//! Recordings help reproduce bugs that depend on the exact sequence and timing of input, like
+//! focus or gesture bugs. They are JSON lines, one event per line, with the time in microseconds
+//! since the start of the recording.
+//!
+//! Touch, tablet and switch events are not recorded. Recordings contain every typed key,
+//! including passwords typed into other programs, so they are only readable by the user.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{LineWriter, Write as _};
+use std::os::unix::fs::OpenOptionsExt as _;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use smithay::backend::input::{
+    AbsolutePositionEvent, Axis, AxisRelativeDirection, AxisSource, ButtonState, Device,
+    DeviceCapability, Event, GestureBeginEvent, GestureEndEvent, GestureHoldBeginEvent,
+    GestureHoldEndEvent, GesturePinchBeginEvent, GesturePinchEndEvent, GesturePinchUpdateEvent,
+    GestureSwipeBeginEvent, GestureSwipeEndEvent, GestureSwipeUpdateEvent, InputEvent, KeyState,
+    KeyboardKeyEvent, Keycode, PointerAxisEvent, PointerButtonEvent, PointerMotionAbsoluteEvent,
+    PointerMotionEvent, UnusedEvent,
+};
+use smithay::output::Output;
+use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
+
+use super::backend_ext::{NiriInputBackend as InputBackend, NiriInputDevice};
+use crate::niri::State;
+use crate::utils::get_monotonic_time;
+
+/// Writes the input events to a file as they come.
+pub struct InputRecorder {
+    file: LineWriter<File>,
+    /// Monotonic time of the start of the recording.
+    start: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct Entry {
+    /// Time since the start of the recording in microseconds.
+    time: u64,
+    #[serde(flatten)]
+    event: RecordedEvent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+enum RecordedEvent {
+    Key {
+        /// XKB keycode.
+        keycode: u32,
+        pressed: bool,
+    },
+    PointerMotion {
+        dx: f64,
+        dy: f64,
+        dx_unaccel: f64,
+        dy_unaccel: f64,
+    },
+    /// Absolute motion, relative to the bounding rectangle of all outputs.
+    PointerMotionAbsolute {
+        x: f64,
+        y: f64,
+    },
+    PointerButton {
+        button: u32,
+        pressed: bool,
+    },
+    PointerAxis {
+        source: ScrollSource,
+        horizontal: Option<f64>,
+        vertical: Option<f64>,
+        horizontal_v120: Option<f64>,
+        vertical_v120: Option<f64>,
+    },
+    SwipeBegin {
+        fingers: u32,
+    },
+    SwipeUpdate {
+        dx: f64,
+        dy: f64,
+    },
+    SwipeEnd {
+        cancelled: bool,
+    },
+    PinchBegin {
+        fingers: u32,
+    },
+    PinchUpdate {
+        dx: f64,
+        dy: f64,
+        scale: f64,
+        rotation: f64,
+    },
+    PinchEnd {
+        cancelled: bool,
+    },
+    HoldBegin {
+        fingers: u32,
+    },
+    HoldEnd {
+        cancelled: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum ScrollSource {
+    Finger,
+    Continuous,
+    Wheel,
+    WheelTilt,
+}
+
+impl From<AxisSource> for ScrollSource {
+    fn from(source: AxisSource) -> Self {
+        match source {
+            AxisSource::Finger => Self::Finger,
+            AxisSource::Continuous => Self::Continuous,
+            AxisSource::Wheel => Self::Wheel,
+            AxisSource::WheelTilt => Self::WheelTilt,
+        }
+    }
+}
+
+impl From<ScrollSource> for AxisSource {
+    fn from(source: ScrollSource) -> Self {
+        match source {
+            ScrollSource::Finger => Self::Finger,
+            ScrollSource::Continuous => Self::Continuous,
+            ScrollSource::Wheel => Self::Wheel,
+            ScrollSource::WheelTilt => Self::WheelTilt,
+        }
+    }
+}
+
+impl InputRecorder {
+    pub fn create(path: &Path) -> anyhow::Result<Self> {
+        // Don't follow or reuse an existing file, whose permissions could let others read it.
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(path)
+            .with_context(|| format!("error creating {path:?}"))?;
+        Ok(Self {
+            file: LineWriter::new(file),
+            start: get_monotonic_time(),
+        })
+    }
+
+    fn write(&mut self, event: RecordedEvent) -> anyhow::Result<()> {
+        let time = get_monotonic_time().saturating_sub(self.start);
+        let entry = Entry {
+            time: time.as_micros() as u64,
+            event,
+        };
+
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+}
+
+impl State {
+    /// Writes the input event to the input recording, if one is running.
+    pub fn record_input_event<I: InputBackend>(&mut self, event: &InputEvent<I>) {
+        if self.niri.input_recorder.is_none() {
+            return;
+        }
+
+        // Keep passwords out of the recording.
+        if self.niri.is_locking_or_locked() {
+            return;
+        }
+
+        let recorded = match event {
+            InputEvent::Keyboard { event } => RecordedEvent::Key {
+                keycode: event.key_code().raw(),
+                pressed: event.state() == KeyState::Pressed,
+            },
+            InputEvent::PointerMotion { event } => RecordedEvent::PointerMotion {
+                dx: event.delta_x(),
+                dy: event.delta_y(),
+                dx_unaccel: event.delta_x_unaccel(),
+                dy_unaccel: event.delta_y_unaccel(),
+            },
+            InputEvent::PointerMotionAbsolute { event } => {
+                let Some(bounds) = self.global_bounding_rectangle() else {
+                    return;
+                };
+                let pos = self
+                    .compute_absolute_location(event, None)
+                    .unwrap_or_else(|| {
+                        event.position_transformed(bounds.size) + bounds.loc.to_f64()
+                    });
+                let pos = pos - bounds.loc.to_f64();
+                let size = bounds.size.to_f64();
+                RecordedEvent::PointerMotionAbsolute {
+                    x: pos.x / size.w,
+                    y: pos.y / size.h,
+                }
+            }
+            InputEvent::PointerButton { event } => RecordedEvent::PointerButton {
+                button: event.button_code(),
+                pressed: event.state() == ButtonState::Pressed,
+            },
+            InputEvent::PointerAxis { event } => RecordedEvent::PointerAxis {
+                source: event.source().into(),
+                horizontal: event.amount(Axis::Horizontal),
+                vertical: event.amount(Axis::Vertical),
+                horizontal_v120: event.amount_v120(Axis::Horizontal),
+                vertical_v120: event.amount_v120(Axis::Vertical),
+            },
+            InputEvent::GestureSwipeBegin { event } => RecordedEvent::SwipeBegin {
+                fingers: event.fingers(),
+            },
+            InputEvent::GestureSwipeUpdate { event } => RecordedEvent::SwipeUpdate {
+                dx: GestureSwipeUpdateEvent::delta_x(event),
+                dy: GestureSwipeUpdateEvent::delta_y(event),
+            },
+            InputEvent::GestureSwipeEnd { event } => RecordedEvent::SwipeEnd {
+                cancelled: event.cancelled(),
+            },
+            InputEvent::GesturePinchBegin { event } => RecordedEvent::PinchBegin {
+                fingers: event.fingers(),
+            },
+            InputEvent::GesturePinchUpdate { event } => RecordedEvent::PinchUpdate {
+                dx: GesturePinchUpdateEvent::delta_x(event),
+                dy: GesturePinchUpdateEvent::delta_y(event),
+                scale: event.scale(),
+                rotation: event.rotation(),
+            },
+            InputEvent::GesturePinchEnd { event } => RecordedEvent::PinchEnd {
+                cancelled: event.cancelled(),
+            },
+            InputEvent::GestureHoldBegin { event } => RecordedEvent::HoldBegin {
+                fingers: event.fingers(),
+            },
+            InputEvent::GestureHoldEnd { event } => RecordedEvent::HoldEnd {
+                cancelled: event.cancelled(),
+            },
+            _ => return,
+        };
+
+        let recorder = self.niri.input_recorder.as_mut().unwrap();
+        if let Err(err) = recorder.write(recorded) {
+            warn!("error writing the input recording, stopping it: {err:?}");
+            self.niri.input_recorder = None;
+        }
+    }
+
+    /// Starts replaying an input recording with its original timing.
+    pub fn start_input_replay(&mut self, path: &Path) -> anyhow::Result<()> {
+        let entries = read_recording(path)?;
+        info!("replaying {} input events from {path:?}", entries.len());
+
+        let Some(first) = entries.first() else {
+            return Ok(());
+        };
+        let timer = Timer::from_duration(Duration::from_micros(first.time));
+
+        let start = get_monotonic_time();
+        let mut entries = entries.into_iter().peekable();
+        self.niri
+            .event_loop
+            .insert_source(timer, move |_, _, state| {
+                let elapsed = get_monotonic_time().saturating_sub(start);
+                while let Some(entry) =
+                    entries.next_if(|entry| Duration::from_micros(entry.time) <= elapsed)
+                {
+                    state.replay_input_event(entry.event);
+                }
+
+                match entries.peek() {
+                    Some(next) => {
+                        TimeoutAction::ToDuration(Duration::from_micros(next.time) - elapsed)
+                    }
+                    None => {
+                        info!("finished replaying input");
+                        TimeoutAction::Drop
+                    }
+                }
+            })
+            .unwrap();
+
+        Ok(())
+    }
+
+    fn replay_input_event(&mut self, recorded: RecordedEvent) {
+        let time = get_monotonic_time().as_micros() as u64;
+
+        let event = match recorded {
+            RecordedEvent::Key { keycode, pressed } => InputEvent::<Backend>::Keyboard {
+                event: ReplayKeyboardKeyEvent {
+                    time,
+                    key_code: Keycode::new(keycode),
+                    state: if pressed {
+                        KeyState::Pressed
+                    } else {
+                        KeyState::Released
+                    },
+                },
+            },
+            RecordedEvent::PointerMotion {
+                dx,
+                dy,
+                dx_unaccel,
+                dy_unaccel,
+            } => InputEvent::<Backend>::PointerMotion {
+                event: ReplayPointerMotionEvent {
+                    time,
+                    delta: (dx, dy),
+                    delta_unaccel: (dx_unaccel, dy_unaccel),
+                },
+            },
+            RecordedEvent::PointerMotionAbsolute { x, y } => {
+                InputEvent::<Backend>::PointerMotionAbsolute {
+                    event: ReplayPointerMotionAbsoluteEvent { time, x, y },
+                }
+            }
+            RecordedEvent::PointerButton { button, pressed } => {
+                InputEvent::<Backend>::PointerButton {
+                    event: ReplayPointerButtonEvent {
+                        time,
+                        button,
+                        state: if pressed {
+                            ButtonState::Pressed
+                        } else {
+                            ButtonState::Released
+                        },
+                    },
+                }
+            }
+            RecordedEvent::PointerAxis {
+                source,
+                horizontal,
+                vertical,
+                horizontal_v120,
+                vertical_v120,
+            } => InputEvent::<Backend>::PointerAxis {
+                event: ReplayPointerAxisEvent {
+                    time,
+                    amount: (horizontal, vertical),
+                    amount_v120: (horizontal_v120, vertical_v120),
+                    source: source.into(),
+                },
+            },
+            RecordedEvent::SwipeBegin { fingers } => InputEvent::<Backend>::GestureSwipeBegin {
+                event: ReplayGestureBeginEvent { time, fingers },
+            },
+            RecordedEvent::SwipeUpdate { dx, dy } => InputEvent::<Backend>::GestureSwipeUpdate {
+                event: ReplayGestureUpdateEvent {
+                    time,
+                    delta: (dx, dy),
+                    scale: 1.,
+                    rotation: 0.,
+                },
+            },
+            RecordedEvent::SwipeEnd { cancelled } => InputEvent::<Backend>::GestureSwipeEnd {
+                event: ReplayGestureEndEvent { time, cancelled },
+            },
+            RecordedEvent::PinchBegin { fingers } => InputEvent::<Backend>::GesturePinchBegin {
+                event: ReplayGestureBeginEvent { time, fingers },
+            },
+            RecordedEvent::PinchUpdate {
+                dx,
+                dy,
+                scale,
+                rotation,
+            } => InputEvent::<Backend>::GesturePinchUpdate {
+                event: ReplayGestureUpdateEvent {
+                    time,
+                    delta: (dx, dy),
+                    scale,
+                    rotation,
+                },
+            },
+            RecordedEvent::PinchEnd { cancelled } => InputEvent::<Backend>::GesturePinchEnd {
+                event: ReplayGestureEndEvent { time, cancelled },
+            },
+            RecordedEvent::HoldBegin { fingers } => InputEvent::<Backend>::GestureHoldBegin {
+                event: ReplayGestureBeginEvent { time, fingers },
+            },
+            RecordedEvent::HoldEnd { cancelled } => InputEvent::<Backend>::GestureHoldEnd {
+                event: ReplayGestureEndEvent { time, cancelled },
+            },
+        };
+
+        self.process_input_event(event);
+    }
+}
+
+fn read_recording(path: &Path) -> anyhow::Result<Vec<Entry>> {
+    let contents = fs::read_to_string(path).with_context(|| format!("error reading {path:?}"))?;
+    parse_recording(&contents)
+}
+
+fn parse_recording(contents: &str) -> anyhow::Result<Vec<Entry>> {
+    let mut entries = contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            serde_json::from_str(line).with_context(|| format!("error parsing line {}", idx + 1))
+        })
+        .collect::<anyhow::Result<Vec<Entry>>>()?;
+
+    // Hand-edited recordings may be out of order.
+    entries.sort_by_key(|entry| entry.time);
+    Ok(entries)
+}
+
+pub struct ReplayInputBackend;
+
+type Backend = ReplayInputBackend;
+
+/// The single device that replays all recorded input.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ReplayDevice;
+
+impl Device for ReplayDevice {
+    fn id(&self) -> String {
+        String::from("input replay")
+    }
+
+    fn name(&self) -> String {
+        String::from("input replay")
+    }
+
+    fn has_capability(&self, capability: DeviceCapability) -> bool {
+        matches!(
+            capability,
+            DeviceCapability::Keyboard | DeviceCapability::Pointer | DeviceCapability::Gesture
+        )
+    }
+
+    fn usb_id(&self) -> Option<(u32, u32)> {
+        None
+    }
+
+    fn syspath(&self) -> Option<std::path::PathBuf> {
+        None
+    }
+}
+
+impl NiriInputDevice for ReplayDevice {
+    fn output(&self, _state: &State) -> Option<Output> {
+        None
+    }
+}
+
+macro_rules! impl_replay_event {
+    ($($ty:ty),+ $(,)?) => {
+        $(
+            impl Event<ReplayInputBackend> for $ty {
+                fn time(&self) -> u64 {
+                    self.time
+                }
+
+                fn device(&self) -> ReplayDevice {
+                    ReplayDevice
+                }
+            }
+        )+
+    };
+}
+
+impl_replay_event!(
+    ReplayKeyboardKeyEvent,
+    ReplayPointerMotionEvent,
+    ReplayPointerMotionAbsoluteEvent,
+    ReplayPointerButtonEvent,
+    ReplayPointerAxisEvent,
+    ReplayGestureBeginEvent,
+    ReplayGestureUpdateEvent,
+    ReplayGestureEndEvent,
+);
+
+pub struct ReplayKeyboardKeyEvent {
+    time: u64,
+    key_code: Keycode,
+    state: KeyState,
+}
+
+impl KeyboardKeyEvent<ReplayInputBackend> for ReplayKeyboardKeyEvent {
+    fn key_code(&self) -> Keycode {
+        self.key_code
+    }
+
+    fn state(&self) -> KeyState {
+        self.state
+    }
+
+    fn count(&self) -> u32 {
+        u32::from(self.state == KeyState::Pressed)
+    }
+}
+
+pub struct ReplayPointerMotionEvent {
+    time: u64,
+    delta: (f64, f64),
+    delta_unaccel: (f64, f64),
+}
+
+impl PointerMotionEvent<ReplayInputBackend> for ReplayPointerMotionEvent {
+    fn delta_x(&self) -> f64 {
+        self.delta.0
+    }
+
+    fn delta_y(&self) -> f64 {
+        self.delta.1
+    }
+
+    fn delta_x_unaccel(&self) -> f64 {
+        self.delta_unaccel.0
+    }
+
+    fn delta_y_unaccel(&self) -> f64 {
+        self.delta_unaccel.1
+    }
+}
+
+/// Absolute motion, relative to the bounding rectangle of all outputs.
+pub struct ReplayPointerMotionAbsoluteEvent {
+    time: u64,
+    x: f64,
+    y: f64,
+}
+
+impl AbsolutePositionEvent<ReplayInputBackend> for ReplayPointerMotionAbsoluteEvent {
+    fn x(&self) -> f64 {
+        self.x
+    }
+
+    fn y(&self) -> f64 {
+        self.y
+    }
+
+    fn x_transformed(&self, width: i32) -> f64 {
+        self.x * f64::from(width)
+    }
+
+    fn y_transformed(&self, height: i32) -> f64 {
+        self.y * f64::from(height)
+    }
+}
+
+impl PointerMotionAbsoluteEvent<ReplayInputBackend> for ReplayPointerMotionAbsoluteEvent {}
+
+pub struct ReplayPointerButtonEvent {
+    time: u64,
+    button: u32,
+    state: ButtonState,
+}
+
+impl PointerButtonEvent<ReplayInputBackend> for ReplayPointerButtonEvent {
+    fn button_code(&self) -> u32 {
+        self.button
+    }
+
+    fn state(&self) -> ButtonState {
+        self.state
+    }
+}
+
+pub struct ReplayPointerAxisEvent {
+    time: u64,
+    /// Amounts along the horizontal and the vertical axes.
+    amount: (Option<f64>, Option<f64>),
+    amount_v120: (Option<f64>, Option<f64>),
+    source: AxisSource,
+}
+
+fn tuple_axis<T>(tuple: (T, T), axis: Axis) -> T {
+    match axis {
+        Axis::Horizontal => tuple.0,
+        Axis::Vertical => tuple.1,
+    }
+}
+
+impl PointerAxisEvent<ReplayInputBackend> for ReplayPointerAxisEvent {
+    fn amount(&self, axis: Axis) -> Option<f64> {
+        tuple_axis(self.amount, axis)
+    }
+
+    fn amount_v120(&self, axis: Axis) -> Option<f64> {
+        tuple_axis(self.amount_v120, axis)
+    }
+
+    fn source(&self) -> AxisSource {
+        self.source
+    }
+
+    fn relative_direction(&self, _axis: Axis) -> AxisRelativeDirection {
+        AxisRelativeDirection::Identical
+    }
+}
+
+/// Beginning of a swipe, pinch or hold gesture.
+pub struct ReplayGestureBeginEvent {
+    time: u64,
+    fingers: u32,
+}
+
+impl GestureBeginEvent<ReplayInputBackend> for ReplayGestureBeginEvent {
+    fn fingers(&self) -> u32 {
+        self.fingers
+    }
+}
+
+impl GestureSwipeBeginEvent<ReplayInputBackend> for ReplayGestureBeginEvent {}
+impl GesturePinchBeginEvent<ReplayInputBackend> for ReplayGestureBeginEvent {}
+impl GestureHoldBeginEvent<ReplayInputBackend> for ReplayGestureBeginEvent {}
+
+/// Update of a swipe or pinch gesture.
+///
+/// Swipes keep the scale at 1 and the rotation at 0.
+pub struct ReplayGestureUpdateEvent {
+    time: u64,
+    delta: (f64, f64),
+    scale: f64,
+    rotation: f64,
+}
+
+impl GestureSwipeUpdateEvent<ReplayInputBackend> for ReplayGestureUpdateEvent {
+    fn delta_x(&self) -> f64 {
+        self.delta.0
+    }
+
+    fn delta_y(&self) -> f64 {
+        self.delta.1
+    }
+}
+
+impl GesturePinchUpdateEvent<ReplayInputBackend> for ReplayGestureUpdateEvent {
+    fn delta_x(&self) -> f64 {
+        self.delta.0
+    }
+
+    fn delta_y(&self) -> f64 {
+        self.delta.1
+    }
+
+    fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    fn rotation(&self) -> f64 {
+        self.rotation
+    }
+}
+
+/// End of a swipe, pinch or hold gesture.
+pub struct ReplayGestureEndEvent {
+    time: u64,
+    cancelled: bool,
+}
+
+impl GestureEndEvent<ReplayInputBackend> for ReplayGestureEndEvent {
+    fn cancelled(&self) -> bool {
+        self.cancelled
+    }
+}
+
+impl GestureSwipeEndEvent<ReplayInputBackend> for ReplayGestureEndEvent {}
+impl GesturePinchEndEvent<ReplayInputBackend> for ReplayGestureEndEvent {}
+impl GestureHoldEndEvent<ReplayInputBackend> for ReplayGestureEndEvent {}
+
+impl smithay::backend::input::InputBackend for ReplayInputBackend {
+    type Device = ReplayDevice;
+
+    type KeyboardKeyEvent = ReplayKeyboardKeyEvent;
+    type PointerAxisEvent = ReplayPointerAxisEvent;
+    type PointerButtonEvent = ReplayPointerButtonEvent;
+    type PointerMotionEvent = ReplayPointerMotionEvent;
+    type PointerMotionAbsoluteEvent = ReplayPointerMotionAbsoluteEvent;
+
+    type GestureSwipeBeginEvent = ReplayGestureBeginEvent;
+    type GestureSwipeUpdateEvent = ReplayGestureUpdateEvent;
+    type GestureSwipeEndEvent = ReplayGestureEndEvent;
+    type GesturePinchBeginEvent = ReplayGestureBeginEvent;
+    type GesturePinchUpdateEvent = ReplayGestureUpdateEvent;
+    type GesturePinchEndEvent = ReplayGestureEndEvent;
+    type GestureHoldBeginEvent = ReplayGestureBeginEvent;
+    type GestureHoldEndEvent = ReplayGestureEndEvent;
+
+    type TouchDownEvent = UnusedEvent;
+    type TouchUpEvent = UnusedEvent;
+    type TouchMotionEvent = UnusedEvent;
+    type TouchCancelEvent = UnusedEvent;
+    type TouchFrameEvent = UnusedEvent;
+    type TabletToolAxisEvent = UnusedEvent;
+    type TabletToolProximityEvent = UnusedEvent;
+    type TabletToolTipEvent = UnusedEvent;
+    type TabletToolButtonEvent = UnusedEvent;
+
+    type SwitchToggleEvent = UnusedEvent;
+
+    type SpecialEvent = UnusedEvent;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_recording_sorts_and_skips_blank_lines() {
+        let contents = r#"
+{"time":2000,"kind":"key","keycode":38,"pressed":false}
+
+{"time":1000,"kind":"key","keycode":38,"pressed":true}
+{"time":3000,"kind":"pointer-axis","source":"wheel","horizontal":null,"vertical":15.0,"horizontal_v120":null,"vertical_v120":120.0}
+"#;
+        let entries = parse_recording(contents).unwrap();
+        assert_eq!(
+            entries.iter().map(|entry| entry.time).collect::<Vec<_>>(),
+            [1000, 2000, 3000]
+        );
+        assert_eq!(
+            entries[0].event,
+            RecordedEvent::Key {
+                keycode: 38,
+                pressed: true
+            }
+        );
+
+        assert!(parse_recording(r#"{"time":0,"kind":"unknown"}"#).is_err());
+    }
+
+    #[test]
+    fn entries_round_trip() {
+        let entry = Entry {
+            time: 1234,
+            event: RecordedEvent::PinchUpdate {
+                dx: 1.5,
+                dy: -2.,
+                scale: 0.9,
+                rotation: 3.,
+            },
+        };
+        let line = serde_json::to_string(&entry).unwrap();
+        assert_eq!(
+            line,
+            r#"{"time":1234,"kind":"pinch-update","dx":1.5,"dy":-2.0,"scale":0.9,"rotation":3.0}"#
+        );
+        assert_eq!(serde_json::from_str::<Entry>(&line).unwrap(), entry);
+    }
+}
@@ -0,0 +1,184 @@
+use smithay::backend::input::Keycode;
+
+use super::DOUBLE_CLICK_TIME;
+
+/// Tracks key taps for on-release and double-tap binds.
+#[derive(Debug, Default)]
+pub struct TapTracker {
+    /// Last pressed key, if no other key was pressed since.
+    tapped_key: Option<Keycode>,
+    /// Key and release time of the first tap of a double-tap bind.
+    last_tap: Option<(Keycode, u32)>,
+}
+
+impl TapTracker {
+    /// Handles a key press.
+    ///
+    /// Taps only count if no other key was pressed in between.
+    pub fn press(&mut self, key_code: Keycode) {
+        self.tapped_key = Some(key_code);
+        if self.last_tap.is_some_and(|(key, _)| key != key_code) {
+            self.last_tap = None;
+        }
+    }
+
+    /// Handles a key release, returning whether it completes a tap of the key.
+    pub fn release(&mut self, key_code: Keycode) -> bool {
+        if self.tapped_key == Some(key_code) {
+            self.tapped_key = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Cancels the taps in progress, for pointer input in between the key press and release.
+    pub fn cancel(&mut self) {
+        self.tapped_key = None;
+        self.last_tap = None;
+    }
+
+    /// Handles a tap of a double-tap bind, returning whether it completes the double tap.
+    pub fn double_tap(&mut self, key_code: Keycode, time: u32) -> bool {
+        let max_delay = DOUBLE_CLICK_TIME.as_millis() as u32;
+        match self.last_tap.take() {
+            Some((_, last)) if time.wrapping_sub(last) <= max_delay => true,
+            _ => {
+                self.last_tap = Some((key_code, time));
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUPER: u32 = 133;
+    const A: u32 = 38;
+
+    #[test]
+    fn tap_completes_on_release() {
+        let mut taps = TapTracker::default();
+
+        taps.press(SUPER.into());
+        assert!(taps.release(SUPER.into()));
+
+        // A release without a press is not a tap.
+        assert!(!taps.release(SUPER.into()));
+    }
+
+    #[test]
+    fn other_key_cancels_tap() {
+        let mut taps = TapTracker::default();
+
+        // Super+A, releasing Super first.
+        taps.press(SUPER.into());
+        taps.press(A.into());
+        assert!(!taps.release(SUPER.into()));
+        assert!(taps.release(A.into()));
+
+        // Super+A, releasing A first.
+        taps.press(SUPER.into());
+        taps.press(A.into());
+        assert!(taps.release(A.into()));
+        assert!(!taps.release(SUPER.into()));
+    }
+
+    #[test]
+    fn pointer_input_cancels_tap() {
+        let mut taps = TapTracker::default();
+
+        // Mod+click, then releasing Mod.
+        taps.press(SUPER.into());
+        taps.cancel();
+        assert!(!taps.release(SUPER.into()));
+
+        // The next tap after that counts again.
+        taps.press(SUPER.into());
+        assert!(taps.release(SUPER.into()));
+    }
+
+    #[test]
+    fn pointer_input_cancels_double_tap() {
+        let mut taps = TapTracker::default();
+
+        taps.press(SUPER.into());
+        assert!(taps.release(SUPER.into()));
+        assert!(!taps.double_tap(SUPER.into(), 1000));
+
+        // Mod+scroll as the second press.
+        taps.press(SUPER.into());
+        taps.cancel();
+        assert!(!taps.release(SUPER.into()));
+
+        taps.press(SUPER.into());
+        assert!(taps.release(SUPER.into()));
+        assert!(!taps.double_tap(SUPER.into(), 1200));
+    }
+
+    #[test]
+    fn repeated_presses_are_one_tap() {
+        let mut taps = TapTracker::default();
+
+        // Key repeat sends more presses of the held key without releases.
+        taps.press(SUPER.into());
+        taps.press(SUPER.into());
+        taps.press(SUPER.into());
+        assert!(taps.release(SUPER.into()));
+        assert!(!taps.release(SUPER.into()));
+    }
+
+    #[test]
+    fn double_tap_within_time() {
+        let mut taps = TapTracker::default();
+
+        taps.press(SUPER.into());
+        assert!(taps.release(SUPER.into()));
+        assert!(!taps.double_tap(SUPER.into(), 1000));
+
+        taps.press(SUPER.into());
+        assert!(taps.release(SUPER.into()));
+        assert!(taps.double_tap(SUPER.into(), 1000 + 400));
+
+        // The double tap was consumed, so a third tap starts over.
+        taps.press(SUPER.into());
+        assert!(taps.release(SUPER.into()));
+        assert!(!taps.double_tap(SUPER.into(), 1500));
+    }
+
+    #[test]
+    fn double_tap_outside_time() {
+        let mut taps = TapTracker::default();
+
+        assert!(!taps.double_tap(SUPER.into(), 1000));
+        // Too late, so this becomes the new first tap.
+        assert!(!taps.double_tap(SUPER.into(), 1000 + 401));
+        assert!(taps.double_tap(SUPER.into(), 1000 + 401 + 300));
+    }
+
+    #[test]
+    fn double_tap_survives_timestamp_wraparound() {
+        let mut taps = TapTracker::default();
+
+        assert!(!taps.double_tap(SUPER.into(), u32::MAX - 100));
+        assert!(taps.double_tap(SUPER.into(), 100));
+    }
+
+    #[test]
+    fn other_key_cancels_double_tap() {
+        let mut taps = TapTracker::default();
+
+        taps.press(SUPER.into());
+        assert!(taps.release(SUPER.into()));
+        assert!(!taps.double_tap(SUPER.into(), 1000));
+
+        taps.press(A.into());
+        assert!(taps.release(A.into()));
+
+        taps.press(SUPER.into());
+        assert!(taps.release(SUPER.into()));
+        assert!(!taps.double_tap(SUPER.into(), 1100));
+    }
+}
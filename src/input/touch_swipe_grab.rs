@@ -0,0 +1,214 @@
+use std::time::Duration;
+
+use smithay::backend::input::TouchSlot;
+use smithay::input::touch::{
+    DownEvent, GrabStartData as TouchGrabStartData, MotionEvent, OrientationEvent, ShapeEvent,
+    TouchGrab, TouchInnerHandle, UpEvent,
+};
+use smithay::input::SeatHandler;
+use smithay::output::Output;
+use smithay::utils::{Logical, Point, Serial};
+
+use crate::niri::State;
+
+/// Touch grab for swipes on the workspace background.
+///
+/// One-finger horizontal swipes scroll the columns, and two-finger vertical swipes switch
+/// workspaces.
+pub struct TouchSwipeGrab {
+    start_data: TouchGrabStartData<State>,
+    output: Output,
+    /// Current locations of the fingers taking part in the gesture.
+    points: Vec<(TouchSlot, Point<f64, Logical>)>,
+    start_centroid: Point<f64, Logical>,
+    last_centroid: Point<f64, Logical>,
+    gesture: GestureState,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum GestureState {
+    Recognizing,
+    ViewOffset,
+    WorkspaceSwitch,
+    /// The swipe doesn't match any gesture.
+    Ignored,
+}
+
+impl TouchSwipeGrab {
+    pub fn new(start_data: TouchGrabStartData<State>, output: Output) -> Self {
+        let location = start_data.location;
+        Self {
+            points: vec![(start_data.slot, location)],
+            start_centroid: location,
+            last_centroid: location,
+            start_data,
+            output,
+            gesture: GestureState::Recognizing,
+        }
+    }
+
+    fn centroid(&self) -> Point<f64, Logical> {
+        let sum = self
+            .points
+            .iter()
+            .fold(Point::default(), |acc, (_, loc)| acc + *loc);
+        sum.downscale(self.points.len().max(1) as f64)
+    }
+
+    fn on_ungrab(&mut self, state: &mut State) {
+        let layout = &mut state.niri.layout;
+        match self.gesture {
+            GestureState::Recognizing | GestureState::Ignored => (),
+            GestureState::ViewOffset => {
+                layout.view_offset_gesture_end(Some(false));
+            }
+            GestureState::WorkspaceSwitch => {
+                layout.workspace_switch_gesture_end(Some(false));
+            }
+        }
+
+        state.niri.queue_redraw_all();
+    }
+}
+
+impl TouchGrab<State> for TouchSwipeGrab {
+    fn down(
+        &mut self,
+        data: &mut State,
+        handle: &mut TouchInnerHandle<'_, State>,
+        _focus: Option<(<State as SeatHandler>::TouchFocus, Point<f64, Logical>)>,
+        event: &DownEvent,
+        seq: Serial,
+    ) {
+        handle.down(data, None, event, seq);
+
+        // Extra fingers can only join while the gesture is still being recognized.
+        if matches!(self.gesture, GestureState::Recognizing)
+            && self.points.len() < 2
+            && !self.points.iter().any(|(slot, _)| *slot == event.slot)
+        {
+            self.points.push((event.slot, event.location));
+            self.start_centroid = self.centroid();
+            self.last_centroid = self.start_centroid;
+        }
+    }
+
+    fn up(
+        &mut self,
+        data: &mut State,
+        handle: &mut TouchInnerHandle<'_, State>,
+        event: &UpEvent,
+        seq: Serial,
+    ) {
+        handle.up(data, event, seq);
+
+        let Some(idx) = self.points.iter().position(|(slot, _)| *slot == event.slot) else {
+            return;
+        };
+        self.points.remove(idx);
+
+        if self.points.is_empty() {
+            handle.unset_grab(self, data);
+            return;
+        }
+
+        // Keep the centroid from jumping when one of the fingers lifts.
+        self.last_centroid = self.centroid();
+    }
+
+    fn motion(
+        &mut self,
+        data: &mut State,
+        handle: &mut TouchInnerHandle<'_, State>,
+        _focus: Option<(<State as SeatHandler>::TouchFocus, Point<f64, Logical>)>,
+        event: &MotionEvent,
+        seq: Serial,
+    ) {
+        handle.motion(data, None, event, seq);
+
+        let Some(point) = self.points.iter_mut().find(|(slot, _)| *slot == event.slot) else {
+            return;
+        };
+        point.1 = event.location;
+
+        let timestamp = Duration::from_millis(u64::from(event.time));
+        let centroid = self.centroid();
+        let layout = &mut data.niri.layout;
+
+        if matches!(self.gesture, GestureState::Recognizing) {
+            let c = centroid - self.start_centroid;
+
+            // Check if the gesture moved far enough to decide. Threshold copied from libadwaita.
+            if c.x * c.x + c.y * c.y >= 16. * 16. {
+                let horizontal = c.x.abs() > c.y.abs();
+                self.gesture = match (self.points.len(), horizontal) {
+                    (1, true) => {
+                        layout.view_offset_gesture_begin(&self.output, None, false);
+                        GestureState::ViewOffset
+                    }
+                    (2, false) => {
+                        layout.workspace_switch_gesture_begin(&self.output, false);
+                        GestureState::WorkspaceSwitch
+                    }
+                    _ => GestureState::Ignored,
+                };
+            }
+        }
+
+        let delta = centroid - self.last_centroid;
+        self.last_centroid = centroid;
+
+        let ongoing = match self.gesture {
+            GestureState::Recognizing | GestureState::Ignored => return,
+            GestureState::ViewOffset => layout
+                .view_offset_gesture_update(-delta.x, timestamp, false)
+                .is_some(),
+            GestureState::WorkspaceSwitch => layout
+                .workspace_switch_gesture_update(-delta.y, timestamp, false)
+                .is_some(),
+        };
+
+        if ongoing {
+            data.niri.queue_redraw_all();
+        } else {
+            handle.unset_grab(self, data);
+        }
+    }
+
+    fn frame(&mut self, data: &mut State, handle: &mut TouchInnerHandle<'_, State>, seq: Serial) {
+        handle.frame(data, seq);
+    }
+
+    fn cancel(&mut self, data: &mut State, handle: &mut TouchInnerHandle<'_, State>, seq: Serial) {
+        handle.cancel(data, seq);
+        handle.unset_grab(self, data);
+    }
+
+    fn shape(
+        &mut self,
+        data: &mut State,
+        handle: &mut TouchInnerHandle<'_, State>,
+        event: &ShapeEvent,
+        seq: Serial,
+    ) {
+        handle.shape(data, event, seq);
+    }
+
+    fn orientation(
+        &mut self,
+        data: &mut State,
+        handle: &mut TouchInnerHandle<'_, State>,
+        event: &OrientationEvent,
+        seq: Serial,
+    ) {
+        handle.orientation(data, event, seq);
+    }
+
+    fn start_data(&self) -> &TouchGrabStartData<State> {
+        &self.start_data
+    }
+
+    fn unset(&mut self, data: &mut State) {
+        self.on_ungrab(data);
+    }
+}
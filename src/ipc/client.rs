@@ -1,20 +1,89 @@
-use std::io::ErrorKind;
+use std::io::{self, ErrorKind, IsTerminal as _};
 use std::iter::Peekable;
-use std::slice;
+use std::sync::mpsc;
+use std::time::Duration;
+use std::{slice, thread};
 
 use anyhow::{anyhow, bail, Context};
 use niri_config::OutputName;
 use niri_ipc::socket::Socket;
 use niri_ipc::{
-    Event, KeyboardLayouts, LogicalOutput, Mode, Output, OutputConfigChanged, Overview, Request,
-    Response, Transform, Window,
+    AutostartEntry, ColumnDisplay, ConfigDiagnostic, DiagnosticSeverity, Event, Gpu,
+    KeyboardLayouts, LogEntry, LogLevel, LogicalOutput, Mode, Output, OutputConfigChanged,
+    Overview, Request, Response, TileLayout, Transform, Window, WorkspaceLayout,
 };
 use serde_json::json;
 
-use crate::cli::Msg;
+use crate::cli::{parse_actions, Msg};
 use crate::utils::version;
 
-pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
+/// How long to wait for more events before printing the response again in `--watch`.
+const WATCH_COALESCE_DELAY: Duration = Duration::from_millis(50);
+
+pub fn handle_msg(msg: Msg, json: bool, table: bool, watch: bool) -> anyhow::Result<()> {
+    if watch {
+        watch_msg(msg, json, table)
+    } else {
+        run_msg(msg, json, table)
+    }
+}
+
+/// Prints the response to the message, and then again every time an event arrives.
+fn watch_msg(msg: Msg, json: bool, table: bool) -> anyhow::Result<()> {
+    // Only these requests have their state covered by the event stream.
+    let can_watch = matches!(
+        msg,
+        Msg::Outputs
+            | Msg::Workspaces
+            | Msg::Windows
+            | Msg::KeyboardLayouts
+            | Msg::FocusedOutput
+            | Msg::FocusedWindow
+            | Msg::OverviewState
+            | Msg::Layout
+    );
+    if !can_watch {
+        bail!("--watch is not supported for this request");
+    }
+
+    let mut socket = Socket::connect().context("error connecting to the niri socket")?;
+    let reply = socket
+        .send(Request::EventStream)
+        .context("error communicating with niri")?;
+    match reply {
+        Ok(Response::Handled) => (),
+        Ok(response) => bail!("unexpected response: expected Handled, got {response:?}"),
+        Err(err_msg) => return Err(anyhow!(err_msg).context("niri returned an error")),
+    }
+
+    // Read the events on a thread to be able to coalesce bursts of them into one print.
+    let (tx, rx) = mpsc::channel();
+    let mut read_event = socket.read_events();
+    thread::spawn(move || {
+        while read_event().is_ok() {
+            if tx.send(()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let clear = !json && io::stdout().is_terminal();
+    loop {
+        // This also waits out the initial state that the event stream starts with.
+        while rx.recv_timeout(WATCH_COALESCE_DELAY).is_ok() {}
+
+        if clear {
+            print!("\x1b[2J\x1b[H");
+        }
+        run_msg(msg.clone(), json, table)?;
+
+        if rx.recv().is_err() {
+            bail!("niri closed the event stream");
+        }
+    }
+}
+
+fn run_msg(msg: Msg, json: bool, table: bool) -> anyhow::Result<()> {
     let request = match &msg {
         Msg::Version => Request::Version,
         Msg::Outputs => Request::Outputs,
@@ -23,6 +92,9 @@ pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
         Msg::PickWindow => Request::PickWindow,
         Msg::PickColor => Request::PickColor,
         Msg::Action { action } => Request::Action(action.clone()),
+        Msg::Actions { actions } => {
+            Request::Actions(parse_actions(actions).context("error parsing actions")?)
+        }
         Msg::Output { output, action } => Request::Output {
             output: output.clone(),
             action: action.clone(),
@@ -34,6 +106,40 @@ pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
         Msg::EventStream => Request::EventStream,
         Msg::RequestError => Request::ReturnError,
         Msg::OverviewState => Request::OverviewState,
+        Msg::Layout => Request::Layout,
+        Msg::ValidateConfig => Request::ValidateConfig,
+        Msg::GetConfig { name } => Request::GetConfigValue { name: name.clone() },
+        Msg::SetConfig {
+            name,
+            value,
+            persist,
+        } => Request::SetConfigValue {
+            name: name.clone(),
+            value: value.clone(),
+            persist: *persist,
+        },
+        Msg::Autostart => Request::Autostart,
+        Msg::RestartAutostart { id } => Request::RestartAutostart { id: *id },
+        Msg::CreateVirtualOutput {
+            width,
+            height,
+            refresh_rate,
+            scale,
+        } => Request::CreateVirtualOutput {
+            width: *width,
+            height: *height,
+            refresh_rate: Some((refresh_rate * 1000.).round() as u32),
+            scale: *scale,
+        },
+        Msg::DestroyVirtualOutput { output } => Request::DestroyVirtualOutput {
+            output: output.clone(),
+        },
+        Msg::Gpus => Request::Gpus,
+        Msg::Logs => Request::Logs,
+        Msg::LogFilter => Request::LogFilter,
+        Msg::SetLogFilter { filter } => Request::SetLogFilter {
+            filter: filter.clone(),
+        },
     };
 
     let mut socket = Socket::connect().context("error connecting to the niri socket")?;
@@ -144,6 +250,11 @@ pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
                 .collect::<Vec<_>>();
             outputs.sort_unstable_by(|a, b| a.0.compare(&b.0));
 
+            if table {
+                print_outputs_table(outputs.into_iter().map(|(_name, output)| output))?;
+                return Ok(());
+            }
+
             for (_name, output) in outputs.into_iter() {
                 print_output(output)?;
                 println!();
@@ -180,6 +291,11 @@ pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
 
             windows.sort_unstable_by(|a, b| a.id.cmp(&b.id));
 
+            if table {
+                print_windows_table(&windows);
+                return Ok(());
+            }
+
             for window in windows {
                 print_window(&window);
                 println!();
@@ -201,6 +317,12 @@ pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
                     .then_with(|| Ord::cmp(&a.layer, &b.layer))
                     .then_with(|| Ord::cmp(&a.namespace, &b.namespace))
             });
+
+            if table {
+                print_layers_table(&layers);
+                return Ok(());
+            }
+
             let mut iter = layers.iter().peekable();
 
             let print = |surface: &niri_ipc::LayerSurface| {
@@ -302,7 +424,12 @@ pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
                 println!("No color was picked.");
             }
         }
-        Msg::Action { .. } => {
+        Msg::Action { .. }
+        | Msg::Actions { .. }
+        | Msg::SetConfig { .. }
+        | Msg::RestartAutostart { .. }
+        | Msg::DestroyVirtualOutput { .. }
+        | Msg::SetLogFilter { .. } => {
             let Response::Handled = response else {
                 bail!("unexpected response: expected Handled, got {response:?}");
             };
@@ -344,6 +471,11 @@ pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
             response.sort_by_key(|ws| ws.idx);
             response.sort_by(|a, b| a.output.cmp(&b.output));
 
+            if table {
+                print_workspaces_table(&response);
+                return Ok(());
+            }
+
             let mut current_output = if let Some(output) = response[0].output.as_deref() {
                 println!("Output \"{output}\":");
                 Some(output)
@@ -447,6 +579,12 @@ pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
                     Event::WindowUrgencyChanged { id, urgent } => {
                         println!("Window {id}: urgency changed to {urgent}");
                     }
+                    Event::WindowResponsivenessChanged { id, unresponsive } => {
+                        println!("Window {id}: unresponsive changed to {unresponsive}");
+                    }
+                    Event::WindowLayoutsChanged { changes } => {
+                        println!("Window layouts changed: {changes:?}");
+                    }
                     Event::KeyboardLayoutsChanged { keyboard_layouts } => {
                         println!("Keyboard layouts changed: {keyboard_layouts:?}");
                     }
@@ -456,6 +594,11 @@ pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
                     Event::OverviewOpenedOrClosed { is_open: opened } => {
                         println!("Overview toggled: {opened}");
                     }
+                    Event::OutputsChanged { outputs } => {
+                        let mut names: Vec<_> = outputs.keys().collect();
+                        names.sort_unstable();
+                        println!("Outputs changed: {names:?}");
+                    }
                 }
             }
         }
@@ -478,11 +621,307 @@ pub fn handle_msg(msg: Msg, json: bool) -> anyhow::Result<()> {
                 println!("Overview is closed.");
             }
         }
+        Msg::Layout => {
+            let Response::Layout(mut workspaces) = response else {
+                bail!("unexpected response: expected Layout, got {response:?}");
+            };
+
+            if json {
+                let response =
+                    serde_json::to_string(&workspaces).context("error formatting response")?;
+                println!("{response}");
+                return Ok(());
+            }
+
+            workspaces.sort_by(|a, b| a.output.cmp(&b.output));
+
+            for workspace in workspaces {
+                print_workspace_layout(&workspace);
+                println!();
+            }
+        }
+        Msg::ValidateConfig => {
+            let Response::ConfigDiagnostics(diagnostics) = response else {
+                bail!("unexpected response: expected ConfigDiagnostics, got {response:?}");
+            };
+
+            let is_valid = diagnostics
+                .iter()
+                .all(|d| d.severity != DiagnosticSeverity::Error);
+
+            if json {
+                let response =
+                    serde_json::to_string(&diagnostics).context("error formatting response")?;
+                println!("{response}");
+            } else {
+                for diagnostic in &diagnostics {
+                    print_config_diagnostic(diagnostic);
+                }
+
+                if is_valid {
+                    println!("Config is valid.");
+                }
+            }
+
+            if !is_valid {
+                bail!("config is invalid");
+            }
+        }
+        Msg::GetConfig { .. } => {
+            let Response::ConfigValue(value) = response else {
+                bail!("unexpected response: expected ConfigValue, got {response:?}");
+            };
+
+            if json {
+                let value = serde_json::to_string(&value).context("error formatting response")?;
+                println!("{value}");
+                return Ok(());
+            }
+
+            println!("{value}");
+        }
+        Msg::Autostart => {
+            let Response::Autostart(entries) = response else {
+                bail!("unexpected response: expected Autostart, got {response:?}");
+            };
+
+            if json {
+                let response =
+                    serde_json::to_string(&entries).context("error formatting response")?;
+                println!("{response}");
+                return Ok(());
+            }
+
+            if table {
+                print_autostart_table(&entries);
+                return Ok(());
+            }
+
+            for entry in entries {
+                print_autostart_entry(&entry);
+                println!();
+            }
+        }
+        Msg::CreateVirtualOutput { .. } => {
+            let Response::VirtualOutputCreated(name) = response else {
+                bail!("unexpected response: expected VirtualOutputCreated, got {response:?}");
+            };
+
+            if json {
+                let name = serde_json::to_string(&name).context("error formatting response")?;
+                println!("{name}");
+                return Ok(());
+            }
+
+            println!("{name}");
+        }
+        Msg::Gpus => {
+            let Response::Gpus(gpus) = response else {
+                bail!("unexpected response: expected Gpus, got {response:?}");
+            };
+
+            if json {
+                let response = serde_json::to_string(&gpus).context("error formatting response")?;
+                println!("{response}");
+                return Ok(());
+            }
+
+            if table {
+                print_gpu_table(&gpus);
+                return Ok(());
+            }
+
+            for gpu in gpus {
+                print_gpu(&gpu);
+                println!();
+            }
+        }
+        Msg::Logs => {
+            let Response::Logs(entries) = response else {
+                bail!("unexpected response: expected Logs, got {response:?}");
+            };
+
+            if json {
+                let response =
+                    serde_json::to_string(&entries).context("error formatting response")?;
+                println!("{response}");
+                return Ok(());
+            }
+
+            for entry in &entries {
+                print_log_entry(entry);
+            }
+        }
+        Msg::LogFilter => {
+            let Response::LogFilter(filter) = response else {
+                bail!("unexpected response: expected LogFilter, got {response:?}");
+            };
+
+            if json {
+                let filter = serde_json::to_string(&filter).context("error formatting response")?;
+                println!("{filter}");
+                return Ok(());
+            }
+
+            println!("{filter}");
+        }
     }
 
     Ok(())
 }
 
+fn print_log_entry(entry: &LogEntry) {
+    let level = match entry.level {
+        LogLevel::Error => "ERROR",
+        LogLevel::Warn => "WARN",
+        LogLevel::Info => "INFO",
+        LogLevel::Debug => "DEBUG",
+        LogLevel::Trace => "TRACE",
+    };
+    let secs = entry.time_ms as f64 / 1000.;
+    let LogEntry {
+        target, message, ..
+    } = entry;
+    println!("{secs:>10.3} {level:>5} {target}: {message}");
+}
+
+fn print_gpu(gpu: &Gpu) {
+    let primary = if gpu.is_primary { " (primary)" } else { "" };
+    println!("GPU {}{primary}", gpu.path);
+
+    if let Some(render_node) = &gpu.render_node {
+        println!("  Render node: {render_node}");
+    }
+
+    if gpu.outputs.is_empty() {
+        println!("  No connected outputs");
+    } else {
+        println!("  Outputs: {}", gpu.outputs.join(", "));
+    }
+}
+
+fn print_autostart_entry(entry: &AutostartEntry) {
+    match &entry.name {
+        Some(name) => println!("Autostart entry {}: {name}", entry.id),
+        None => println!("Autostart entry {}:", entry.id),
+    }
+
+    println!("  Command: {:?}", entry.command);
+
+    if let Some(pid) = entry.pid {
+        println!("  Running, PID: {pid}");
+    } else if let Some(code) = entry.exit_code {
+        println!("  Exited with code {code}");
+    } else {
+        println!("  Not running");
+    }
+
+    println!("  Restarts: {}", entry.restarts);
+}
+
+fn print_config_diagnostic(diagnostic: &ConfigDiagnostic) {
+    let severity = match diagnostic.severity {
+        DiagnosticSeverity::Error => "error",
+        DiagnosticSeverity::Warning => "warning",
+    };
+
+    let mut location = String::new();
+    if let Some(file) = &diagnostic.file {
+        location.push_str(file);
+        if let Some(line) = diagnostic.line {
+            location.push_str(&format!(":{line}"));
+            if let Some(column) = diagnostic.column {
+                location.push_str(&format!(":{column}"));
+            }
+        }
+        location.push_str(": ");
+    }
+
+    println!("{location}{severity}: {}", diagnostic.message);
+    if let Some(label) = &diagnostic.label {
+        println!("  {label}");
+    }
+    if let Some(help) = &diagnostic.help {
+        println!("  help: {help}");
+    }
+}
+
+fn print_workspace_layout(workspace: &WorkspaceLayout) {
+    let state = if workspace.is_focused {
+        " (focused)"
+    } else if workspace.is_active {
+        " (active)"
+    } else {
+        ""
+    };
+    if let Some(output) = &workspace.output {
+        println!("Workspace ID {} on \"{output}\":{state}", workspace.id);
+    } else {
+        println!("Workspace ID {}:{state}", workspace.id);
+    }
+
+    let (w, h) = workspace.view_size;
+    println!("  View size: {w} × {h}");
+
+    for (idx, column) in workspace.columns.iter().enumerate() {
+        let active = if workspace.active_column_idx == Some(idx) {
+            " (active)"
+        } else {
+            ""
+        };
+        let display = match column.display {
+            ColumnDisplay::Normal => "normal",
+            ColumnDisplay::Tabbed => "tabbed",
+        };
+        let full_width = if column.is_full_width {
+            ", full width"
+        } else {
+            ""
+        };
+        println!(
+            "  Column {} at x {}: width {}, {display}{full_width}{active}",
+            idx + 1,
+            column.x,
+            column.width,
+        );
+
+        for (tile_idx, tile) in column.tiles.iter().enumerate() {
+            let active = tile_idx == column.active_tile_idx;
+            print_tile_layout(tile, active, "    ");
+        }
+    }
+
+    if !workspace.floating.is_empty() {
+        let active = if workspace.floating_is_active {
+            " (active)"
+        } else {
+            ""
+        };
+        println!("  Floating:{active}");
+
+        for tile in &workspace.floating {
+            print_tile_layout(tile, false, "    ");
+        }
+    }
+}
+
+fn print_tile_layout(tile: &TileLayout, active: bool, indent: &str) {
+    let active = if active { " (active)" } else { "" };
+    let fullscreen = if tile.is_fullscreen {
+        ", fullscreen"
+    } else {
+        ""
+    };
+    let (x, y) = tile.pos;
+    let (tile_w, tile_h) = tile.tile_size;
+    let (window_w, window_h) = tile.window_size;
+    println!(
+        "{indent}Window ID {} at {x}, {y}: tile {tile_w} × {tile_h}, \
+         window {window_w} × {window_h}{fullscreen}{active}",
+        tile.window_id,
+    );
+}
+
 fn print_output(output: Output) -> anyhow::Result<()> {
     let Output {
         name,
@@ -582,7 +1021,20 @@ fn print_output(output: Output) -> anyhow::Result<()> {
 fn print_window(window: &Window) {
     let focused = if window.is_focused { " (focused)" } else { "" };
     let urgent = if window.is_urgent { " (urgent)" } else { "" };
-    println!("Window ID {}:{focused}{urgent}", window.id);
+    let unresponsive = if window.is_unresponsive {
+        " (unresponsive)"
+    } else {
+        ""
+    };
+    let suspended = if window.is_suspended {
+        " (suspended)"
+    } else {
+        ""
+    };
+    println!(
+        "Window ID {}:{focused}{urgent}{unresponsive}{suspended}",
+        window.id
+    );
 
     if let Some(title) = &window.title {
         println!("  Title: \"{title}\"");
@@ -601,6 +1053,23 @@ fn print_window(window: &Window) {
         if window.is_floating { "yes" } else { "no" }
     );
 
+    if window.opacity != 1. {
+        println!("  Opacity: {:.2}", window.opacity);
+    }
+
+    println!(
+        "  Is fullscreen: {}",
+        if window.layout.is_fullscreen {
+            "yes"
+        } else {
+            "no"
+        }
+    );
+
+    if let Some((column, tile)) = window.layout.pos_in_scrolling_layout {
+        println!("  Scrolling position: column {column}, tile {tile}");
+    }
+
     if let Some(pid) = window.pid {
         println!("  PID: {pid}");
     } else {
@@ -613,3 +1082,199 @@ fn print_window(window: &Window) {
         println!("  Workspace ID: (none)");
     }
 }
+
+/// Prints rows under a header with the columns aligned.
+fn print_table<const N: usize>(header: [&str; N], rows: &[[String; N]]) {
+    let mut widths = header.map(|cell| cell.chars().count());
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let print_row = |row: [&str; N]| {
+        let mut line = String::new();
+        for (cell, width) in row.iter().zip(widths) {
+            line.push_str(&format!("{cell:<width$}  "));
+        }
+        println!("{}", line.trim_end());
+    };
+
+    print_row(header);
+    for row in rows {
+        print_row(row.each_ref().map(|cell| cell.as_str()));
+    }
+}
+
+/// Joins the flags that are set, or returns `-` if none are.
+fn flags<const N: usize>(flags: [(bool, &str); N]) -> String {
+    let set: Vec<_> = flags
+        .into_iter()
+        .filter_map(|(is_set, name)| is_set.then_some(name))
+        .collect();
+    if set.is_empty() {
+        String::from("-")
+    } else {
+        set.join(", ")
+    }
+}
+
+fn or_dash(value: Option<impl ToString>) -> String {
+    value.map_or_else(|| String::from("-"), |value| value.to_string())
+}
+
+fn print_outputs_table(outputs: impl Iterator<Item = Output>) -> anyhow::Result<()> {
+    let mut rows = Vec::new();
+    for output in outputs {
+        let mode = match output.current_mode {
+            Some(current) => {
+                let mode = output
+                    .modes
+                    .get(current)
+                    .context("invalid response: current mode does not exist")?;
+                let refresh = mode.refresh_rate as f64 / 1000.;
+                format!("{}x{}@{refresh:.3}", mode.width, mode.height)
+            }
+            None => String::from("disabled"),
+        };
+        let logical = output.logical.as_ref();
+
+        rows.push([
+            output.name,
+            format!("{} {}", output.make, output.model),
+            mode,
+            or_dash(logical.map(|l| l.scale)),
+            or_dash(logical.map(|l| format!("{}, {}", l.x, l.y))),
+            flags([
+                (output.vrr_enabled, "enabled"),
+                (output.vrr_supported && !output.vrr_enabled, "supported"),
+            ]),
+        ]);
+    }
+
+    print_table(
+        ["NAME", "MONITOR", "MODE", "SCALE", "POSITION", "VRR"],
+        &rows,
+    );
+    Ok(())
+}
+
+fn print_workspaces_table(workspaces: &[niri_ipc::Workspace]) {
+    let rows: Vec<_> = workspaces
+        .iter()
+        .map(|ws| {
+            [
+                ws.id.to_string(),
+                or_dash(ws.output.as_deref()),
+                ws.idx.to_string(),
+                or_dash(ws.name.as_deref()),
+                or_dash(ws.active_window_id),
+                flags([
+                    (ws.is_focused, "focused"),
+                    (ws.is_active && !ws.is_focused, "active"),
+                    (ws.is_urgent, "urgent"),
+                ]),
+            ]
+        })
+        .collect();
+
+    print_table(
+        ["ID", "OUTPUT", "IDX", "NAME", "ACTIVE WINDOW", "STATE"],
+        &rows,
+    );
+}
+
+fn print_windows_table(windows: &[Window]) {
+    let rows: Vec<_> = windows
+        .iter()
+        .map(|window| {
+            [
+                window.id.to_string(),
+                or_dash(window.app_id.as_deref()),
+                or_dash(window.title.as_deref()),
+                or_dash(window.pid),
+                or_dash(window.workspace_id),
+                flags([
+                    (window.is_focused, "focused"),
+                    (window.is_floating, "floating"),
+                    (window.layout.is_fullscreen, "fullscreen"),
+                    (window.is_urgent, "urgent"),
+                    (window.is_unresponsive, "unresponsive"),
+                    (window.is_suspended, "suspended"),
+                ]),
+            ]
+        })
+        .collect();
+
+    print_table(
+        ["ID", "APP ID", "TITLE", "PID", "WORKSPACE", "STATE"],
+        &rows,
+    );
+}
+
+fn print_layers_table(layers: &[niri_ipc::LayerSurface]) {
+    let rows: Vec<_> = layers
+        .iter()
+        .map(|surface| {
+            let layer = match surface.layer {
+                niri_ipc::Layer::Background => "background",
+                niri_ipc::Layer::Bottom => "bottom",
+                niri_ipc::Layer::Top => "top",
+                niri_ipc::Layer::Overlay => "overlay",
+            };
+            let interactivity = match surface.keyboard_interactivity {
+                niri_ipc::LayerSurfaceKeyboardInteractivity::None => "none",
+                niri_ipc::LayerSurfaceKeyboardInteractivity::Exclusive => "exclusive",
+                niri_ipc::LayerSurfaceKeyboardInteractivity::OnDemand => "on-demand",
+            };
+            [
+                surface.output.clone(),
+                String::from(layer),
+                surface.namespace.clone(),
+                String::from(interactivity),
+            ]
+        })
+        .collect();
+
+    print_table(["OUTPUT", "LAYER", "NAMESPACE", "KEYBOARD"], &rows);
+}
+
+fn print_gpu_table(gpus: &[Gpu]) {
+    let rows: Vec<_> = gpus
+        .iter()
+        .map(|gpu| {
+            [
+                gpu.path.clone(),
+                or_dash(gpu.render_node.as_deref()),
+                flags([(gpu.is_primary, "primary")]),
+                gpu.outputs.join(", "),
+            ]
+        })
+        .collect();
+
+    print_table(["PATH", "RENDER NODE", "STATE", "OUTPUTS"], &rows);
+}
+
+fn print_autostart_table(entries: &[AutostartEntry]) {
+    let rows: Vec<_> = entries
+        .iter()
+        .map(|entry| {
+            let status = if let Some(pid) = entry.pid {
+                format!("running, PID {pid}")
+            } else if let Some(code) = entry.exit_code {
+                format!("exited with code {code}")
+            } else {
+                String::from("not running")
+            };
+            [
+                entry.id.to_string(),
+                or_dash(entry.name.as_deref()),
+                status,
+                entry.restarts.to_string(),
+                entry.command.join(" "),
+            ]
+        })
+        .collect();
+
+    print_table(["ID", "NAME", "STATUS", "RESTARTS", "COMMAND"], &rows);
+}
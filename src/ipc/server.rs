@@ -1,5 +1,5 @@
 use std::cell::RefCell;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
 use std::os::unix::net::{UnixListener, UnixStream};
 use std::path::PathBuf;
@@ -17,7 +17,8 @@ use futures_util::{select_biased, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, Fu
 use niri_config::OutputName;
 use niri_ipc::state::{EventStreamState, EventStreamStatePart as _};
 use niri_ipc::{
-    Event, KeyboardLayouts, OutputConfigChanged, Overview, Reply, Request, Response, Workspace,
+    Event, KeyboardLayouts, OutputConfigChanged, Overview, Reply, Request, Response, WindowLayout,
+    Workspace,
 };
 use smithay::desktop::layer_map_for_output;
 use smithay::input::pointer::{
@@ -33,7 +34,7 @@ use crate::backend::IpcOutputMap;
 use crate::input::pick_window_grab::PickWindowGrab;
 use crate::layout::workspace::WorkspaceId;
 use crate::niri::State;
-use crate::utils::{version, with_toplevel_role};
+use crate::utils::{logging, version, with_toplevel_role};
 use crate::window::Mapped;
 
 // If an event stream client fails to read events fast enough that we accumulate more than this
@@ -163,16 +164,7 @@ fn on_new_ipc_client(state: &mut State, stream: UnixStream) {
         }
     };
 
-    let ipc_server = state.niri.ipc_server.as_ref().unwrap();
-
-    let ctx = ClientCtx {
-        event_loop: state.niri.event_loop.clone(),
-        scheduler: state.niri.scheduler.clone(),
-        ipc_outputs: state.backend.ipc_outputs(),
-        event_streams: ipc_server.event_streams.clone(),
-        event_stream_state: ipc_server.event_stream_state.clone(),
-    };
-
+    let ctx = ClientCtx::new(state);
     let future = async move {
         if let Err(err) = handle_client(ctx, stream).await {
             warn!("error handling IPC client: {err:?}");
@@ -183,6 +175,38 @@ fn on_new_ipc_client(state: &mut State, stream: UnixStream) {
     }
 }
 
+/// Processes an IPC request that didn't come through the socket, for example from a test.
+///
+/// The reply arrives on the returned channel once the event loop gets to the request.
+pub fn process_request(state: &mut State, request: Request) -> Receiver<Reply> {
+    let (tx, rx) = async_channel::bounded(1);
+
+    let ctx = ClientCtx::new(state);
+    let future = async move {
+        let reply = process(&ctx, request).await;
+        let _ = tx.send(reply).await;
+    };
+    if let Err(err) = state.niri.scheduler.schedule(future) {
+        warn!("error scheduling IPC request future: {err:?}");
+    }
+
+    rx
+}
+
+impl ClientCtx {
+    fn new(state: &State) -> Self {
+        let ipc_server = state.niri.ipc_server.as_ref().unwrap();
+
+        Self {
+            event_loop: state.niri.event_loop.clone(),
+            scheduler: state.niri.scheduler.clone(),
+            ipc_outputs: state.backend.ipc_outputs(),
+            event_streams: ipc_server.event_streams.clone(),
+            event_stream_state: ipc_server.event_stream_state.clone(),
+        }
+    }
+}
+
 async fn handle_client(ctx: ClientCtx, stream: Async<'static, UnixStream>) -> anyhow::Result<()> {
     let (read, mut write) = stream.split();
     let mut read = BufReader::new(read);
@@ -286,6 +310,124 @@ async fn process(ctx: &ClientCtx, request: Request) -> Reply {
             let windows = state.windows.windows.values().cloned().collect();
             Response::Windows(windows)
         }
+        Request::Layout => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let layout = state.niri.layout.ipc_layout(&|mapped| mapped.id().get());
+                let _ = tx.send_blocking(layout);
+            });
+            let result = rx.recv().await;
+            let layout = result.map_err(|_| String::from("error getting layout info"))?;
+            Response::Layout(layout)
+        }
+        Request::ValidateConfig => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let path = state.niri.config_path.clone();
+                let profile = state.niri.config_profile.clone();
+                let _ = tx.send_blocking((path, profile));
+            });
+            let result = rx.recv().await;
+            let (path, profile) = result.map_err(|_| String::from("error getting config path"))?;
+            let path = path.ok_or_else(|| String::from("niri is running without a config file"))?;
+
+            // Parsing the config is slow enough to not do it on the main thread.
+            let (tx, rx) = async_channel::bounded(1);
+            std::thread::spawn(move || {
+                let diagnostics = niri_config::Config::validate(&path, profile.as_deref());
+                let _ = tx.send_blocking(diagnostics);
+            });
+            let result = rx.recv().await;
+            let diagnostics = result.map_err(|_| String::from("error validating config"))?;
+            Response::ConfigDiagnostics(diagnostics)
+        }
+        Request::GetConfigValue { name } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let value = state.niri.config.borrow().get_value(&name);
+                let _ = tx.send_blocking(value);
+            });
+            let result = rx.recv().await;
+            let value = result.map_err(|_| String::from("error getting config value"))?;
+            Response::ConfigValue(value?)
+        }
+        Request::SetConfigValue {
+            name,
+            value,
+            persist,
+        } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let result = state.set_config_value(&name, &value, persist);
+                let _ = tx.send_blocking(result);
+            });
+            let result = rx.recv().await;
+            result.map_err(|_| String::from("error setting config value"))??;
+            Response::Handled
+        }
+        Request::Autostart => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let entries = state.niri.autostart.ipc_entries();
+                let _ = tx.send_blocking(entries);
+            });
+            let result = rx.recv().await;
+            let entries = result.map_err(|_| String::from("error getting autostart info"))?;
+            Response::Autostart(entries)
+        }
+        Request::RestartAutostart { id } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let result = state.niri.autostart.restart(&state.niri.event_loop, id);
+                let _ = tx.send_blocking(result);
+            });
+            let result = rx.recv().await;
+            result.map_err(|_| String::from("error restarting autostart entry"))??;
+            Response::Handled
+        }
+        Request::CreateVirtualOutput {
+            width,
+            height,
+            refresh_rate,
+            scale,
+        } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let result = state.create_virtual_output(width, height, refresh_rate, scale);
+                let _ = tx.send_blocking(result);
+            });
+            let result = rx.recv().await;
+            let name = result.map_err(|_| String::from("error creating virtual output"))??;
+            Response::VirtualOutputCreated(name)
+        }
+        Request::DestroyVirtualOutput { output } => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let result = state.destroy_virtual_output(&output);
+                let _ = tx.send_blocking(result);
+            });
+            let result = rx.recv().await;
+            result.map_err(|_| String::from("error destroying virtual output"))??;
+            Response::Handled
+        }
+        Request::Gpus => {
+            let (tx, rx) = async_channel::bounded(1);
+            ctx.event_loop.insert_idle(move |state| {
+                let _ = tx.send_blocking(state.backend.ipc_gpus());
+            });
+            let result = rx.recv().await;
+            let gpus = result.map_err(|_| String::from("error getting GPU info"))?;
+            Response::Gpus(gpus)
+        }
+        Request::Logs => Response::Logs(logging::recent_entries()),
+        Request::LogFilter => {
+            let filter = logging::filter().ok_or_else(|| String::from("logging is not set up"))?;
+            Response::LogFilter(filter)
+        }
+        Request::SetLogFilter { filter } => {
+            logging::set_filter(filter.as_deref()).map_err(|err| format!("{err:#}"))?;
+            Response::Handled
+        }
         Request::Layers => {
             let (tx, rx) = async_channel::bounded(1);
             ctx.event_loop.insert_idle(move |state| {
@@ -395,6 +537,23 @@ async fn process(ctx: &ClientCtx, request: Request) -> Reply {
             let _ = rx.recv().await;
             Response::Handled
         }
+        Request::Actions(actions) => {
+            let (tx, rx) = async_channel::bounded(1);
+
+            let actions: Vec<_> = actions.into_iter().map(niri_config::Action::from).collect();
+            ctx.event_loop.insert_idle(move |state| {
+                state.niri.advance_animations();
+                // Run all actions within the same idle callback, so that nothing gets rendered in
+                // between them.
+                for action in actions {
+                    state.do_action(action, false);
+                }
+                let _ = tx.send_blocking(());
+            });
+
+            let _ = rx.recv().await;
+            Response::Handled
+        }
         Request::Output { output, action } => {
             let ipc_outputs = ctx.ipc_outputs.lock().unwrap();
             let found = ipc_outputs
@@ -477,7 +636,11 @@ async fn handle_event_stream_client(client: EventStreamClient) -> anyhow::Result
     Ok(())
 }
 
-fn make_ipc_window(mapped: &Mapped, workspace_id: Option<WorkspaceId>) -> niri_ipc::Window {
+fn make_ipc_window(
+    mapped: &Mapped,
+    workspace_id: Option<WorkspaceId>,
+    layout: WindowLayout,
+) -> niri_ipc::Window {
     with_toplevel_role(mapped.toplevel(), |role| niri_ipc::Window {
         id: mapped.id().get(),
         title: role.title.clone(),
@@ -487,6 +650,10 @@ fn make_ipc_window(mapped: &Mapped, workspace_id: Option<WorkspaceId>) -> niri_i
         is_focused: mapped.is_focused(),
         is_floating: mapped.is_floating(),
         is_urgent: mapped.is_urgent(),
+        is_unresponsive: mapped.is_unresponsive(),
+        is_suspended: mapped.is_suspended(),
+        opacity: f64::from(mapped.opacity()),
+        layout,
     })
 }
 
@@ -660,7 +827,8 @@ impl State {
         // Check for window changes.
         let mut seen = HashSet::new();
         let mut focused_id = None;
-        layout.with_windows(|mapped, _, ws_id| {
+        let mut layout_changes = Vec::new();
+        layout.with_windows(|mapped, _, ws_id, win_layout| {
             let id = mapped.id().get();
             seen.insert(id);
 
@@ -669,21 +837,23 @@ impl State {
             }
 
             let Some(ipc_win) = state.windows.get(&id) else {
-                let window = make_ipc_window(mapped, ws_id);
+                let window = make_ipc_window(mapped, ws_id, win_layout);
                 events.push(Event::WindowOpenedOrChanged { window });
                 return;
             };
 
             let workspace_id = ws_id.map(|id| id.get());
-            let mut changed =
-                ipc_win.workspace_id != workspace_id || ipc_win.is_floating != mapped.is_floating();
+            let mut changed = ipc_win.workspace_id != workspace_id
+                || ipc_win.is_floating != mapped.is_floating()
+                || ipc_win.is_suspended != mapped.is_suspended()
+                || ipc_win.opacity != f64::from(mapped.opacity());
 
             changed |= with_toplevel_role(mapped.toplevel(), |role| {
                 ipc_win.title != role.title || ipc_win.app_id != role.app_id
             });
 
             if changed {
-                let window = make_ipc_window(mapped, ws_id);
+                let window = make_ipc_window(mapped, ws_id, win_layout);
                 events.push(Event::WindowOpenedOrChanged { window });
                 return;
             }
@@ -696,8 +866,23 @@ impl State {
             if urgent != ipc_win.is_urgent {
                 events.push(Event::WindowUrgencyChanged { id, urgent })
             }
+
+            let unresponsive = mapped.is_unresponsive();
+            if unresponsive != ipc_win.is_unresponsive {
+                events.push(Event::WindowResponsivenessChanged { id, unresponsive })
+            }
+
+            if win_layout != ipc_win.layout {
+                layout_changes.push((id, win_layout));
+            }
         });
 
+        if !layout_changes.is_empty() {
+            events.push(Event::WindowLayoutsChanged {
+                changes: layout_changes,
+            });
+        }
+
         // Check for closed windows.
         let mut ipc_focused_id = None;
         for (id, ipc_win) in &state.windows {
@@ -739,4 +924,26 @@ impl State {
         state.apply(event.clone());
         server.send_event(event);
     }
+
+    pub fn ipc_outputs_changed(&mut self) {
+        let Some(server) = &self.niri.ipc_server else {
+            return;
+        };
+
+        let ipc_outputs = self.backend.ipc_outputs();
+        let ipc_outputs = ipc_outputs.lock().unwrap();
+        let outputs: HashMap<_, _> = ipc_outputs
+            .values()
+            .cloned()
+            .map(|o| (o.name.clone(), o))
+            .collect();
+        drop(ipc_outputs);
+
+        let mut state = server.event_stream_state.borrow_mut();
+        let state = &mut state.outputs;
+
+        let event = Event::OutputsChanged { outputs };
+        state.apply(event.clone());
+        server.send_event(event);
+    }
 }
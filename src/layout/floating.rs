@@ -1,5 +1,6 @@
 use std::cmp::max;
-use std::iter::zip;
+use std::iter::{self, zip};
+use std::mem;
 use std::rc::Rc;
 
 use niri_config::{PresetSize, RelativeTo};
@@ -28,6 +29,9 @@ use crate::window::ResolvedWindowRules;
 /// By how many logical pixels the directional move commands move floating windows.
 pub const DIRECTIONAL_MOVE_PX: f64 = 50.;
 
+/// Widths that `switch_preset_pip_width()` switches between, as proportions of the working area.
+const PIP_PRESET_WIDTHS: [f64; 3] = [0.2, 0.3, 0.4];
+
 /// Space for floating windows.
 #[derive(Debug)]
 pub struct FloatingSpace<W: LayoutElement> {
@@ -90,6 +94,23 @@ struct Data {
 
     /// Working area used for conversions.
     working_area: Rectangle<f64, Logical>,
+
+    /// Corner of the working area the tile is snapped to, and the gap to keep to its edges.
+    ///
+    /// A snapped tile stays in its corner as its size or the working area change.
+    snapped_corner: Option<(Corner, f64)>,
+
+    /// Index of the picture-in-picture preset width the tile was last set to.
+    pip_preset_width_idx: Option<usize>,
+}
+
+/// Corner of the working area.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Corner {
+    TopLeft,
+    TopRight,
+    BottomRight,
+    BottomLeft,
 }
 
 impl Data {
@@ -103,6 +124,8 @@ impl Data {
             logical_pos: Point::default(),
             size: Size::default(),
             working_area,
+            snapped_corner: None,
+            pip_preset_width_idx: None,
         };
         rv.update(tile);
         rv.set_logical_pos(logical_pos);
@@ -132,6 +155,13 @@ impl Data {
     }
 
     fn recompute_logical_pos(&mut self) {
+        if let Some((corner, gap)) = self.snapped_corner {
+            self.logical_pos = corner.tile_pos(self.working_area, self.size, gap);
+            self.pos =
+                Self::logical_to_size_frac_in_working_area(self.working_area, self.logical_pos);
+            return;
+        }
+
         let mut logical_pos = Self::scale_by_working_area(self.working_area, self.pos);
 
         // Make sure the window doesn't go too much off-screen. Numbers taken from Mutter.
@@ -176,6 +206,7 @@ impl Data {
     }
 
     pub fn set_logical_pos(&mut self, logical_pos: Point<f64, Logical>) {
+        self.snapped_corner = None;
         self.pos = Self::logical_to_size_frac_in_working_area(self.working_area, logical_pos);
 
         // This will clamp the logical position to the current working area.
@@ -186,6 +217,15 @@ impl Data {
         self.logical_pos + self.size.downscale(2.)
     }
 
+    pub fn snap_to_corner(&mut self, corner: Corner, gap: f64) {
+        self.snapped_corner = Some((corner, gap));
+        self.recompute_logical_pos();
+    }
+
+    pub fn rect(&self) -> Rectangle<f64, Logical> {
+        Rectangle::new(self.logical_pos, self.size)
+    }
+
     #[cfg(test)]
     fn verify_invariants(&self) {
         let mut temp = *self;
@@ -197,6 +237,50 @@ impl Data {
     }
 }
 
+impl Corner {
+    /// Returns the next corner going clockwise.
+    fn next(self) -> Self {
+        match self {
+            Self::TopLeft => Self::TopRight,
+            Self::TopRight => Self::BottomRight,
+            Self::BottomRight => Self::BottomLeft,
+            Self::BottomLeft => Self::TopLeft,
+        }
+    }
+
+    /// Returns the corner of the area closest to the point.
+    fn nearest(area: Rectangle<f64, Logical>, point: Point<f64, Logical>) -> Self {
+        let center = area.loc + area.size.downscale(2.);
+        match (point.x < center.x, point.y < center.y) {
+            (true, true) => Self::TopLeft,
+            (false, true) => Self::TopRight,
+            (false, false) => Self::BottomRight,
+            (true, false) => Self::BottomLeft,
+        }
+    }
+
+    /// Returns the position of a tile of this size placed in this corner of the area.
+    fn tile_pos(
+        self,
+        area: Rectangle<f64, Logical>,
+        size: Size<f64, Logical>,
+        gap: f64,
+    ) -> Point<f64, Logical> {
+        let left = area.loc.x + gap;
+        let right = area.loc.x + area.size.w - size.w - gap;
+        let top = area.loc.y + gap;
+        let bottom = area.loc.y + area.size.h - size.h - gap;
+
+        let (x, y) = match self {
+            Self::TopLeft => (left, top),
+            Self::TopRight => (right, top),
+            Self::BottomRight => (right, bottom),
+            Self::BottomLeft => (left, bottom),
+        };
+        Point::from((x, y))
+    }
+}
+
 impl<W: LayoutElement> FloatingSpace<W> {
     pub fn new(
         view_size: Size<f64, Logical>,
@@ -574,6 +658,38 @@ impl<W: LayoutElement> FloatingSpace<W> {
         true
     }
 
+    /// Moves the always-on-top windows above the others, preserving their stacking order.
+    fn stack_always_on_top(&mut self) {
+        // Children go along with their always-on-top parents to stay stacked above them. Parents
+        // are always below children, so walk back to front.
+        let mut on_top = vec![false; self.tiles.len()];
+        for idx in (0..self.tiles.len()).rev() {
+            let win = self.tiles[idx].window();
+            on_top[idx] = win.is_always_on_top()
+                || (idx + 1..self.tiles.len())
+                    .any(|below| on_top[below] && win.is_child_of(self.tiles[below].window()));
+        }
+
+        if on_top.windows(2).all(|pair| pair[0] || !pair[1]) {
+            return;
+        }
+
+        let tiles = mem::take(&mut self.tiles);
+        let data = mem::take(&mut self.data);
+        let (on_top, rest): (Vec<_>, Vec<_>) =
+            zip(on_top, zip(tiles, data)).partition(|(on_top, _)| *on_top);
+        for (_, (tile, data)) in on_top.into_iter().chain(rest) {
+            self.tiles.push(tile);
+            self.data.push(data);
+        }
+    }
+
+    pub fn has_always_on_top(&self) -> bool {
+        self.tiles
+            .iter()
+            .any(|tile| tile.window().is_always_on_top())
+    }
+
     fn raise_window(&mut self, from_idx: usize, to_idx: usize) {
         assert!(to_idx <= from_idx);
 
@@ -948,6 +1064,99 @@ impl<W: LayoutElement> FloatingSpace<W> {
         self.move_to(idx, new_pos, true);
     }
 
+    /// Returns the index of the picture-in-picture window: the topmost always-on-top window.
+    fn pip_idx(&self) -> Option<usize> {
+        self.tiles
+            .iter()
+            .position(|tile| tile.window().is_always_on_top())
+    }
+
+    fn snap_to_corner(&mut self, idx: usize, corner: Corner) {
+        let gap = self.options.gaps;
+        let new_pos = corner.tile_pos(self.working_area, self.data[idx].size, gap);
+        self.move_and_animate(idx, new_pos);
+        self.data[idx].snap_to_corner(corner, gap);
+    }
+
+    pub fn move_pip_to_next_corner(&mut self) {
+        let Some(idx) = self.pip_idx() else {
+            return;
+        };
+
+        let data = &self.data[idx];
+        let corner = match data.snapped_corner {
+            Some((corner, _)) => corner.next(),
+            // Start from the closest corner so that the window doesn't jump across the screen.
+            None => Corner::nearest(self.working_area, data.center()),
+        };
+        self.snap_to_corner(idx, corner);
+    }
+
+    pub fn switch_preset_pip_width(&mut self) {
+        let Some(idx) = self.pip_idx() else {
+            return;
+        };
+
+        let data = &mut self.data[idx];
+        let preset_idx = data
+            .pip_preset_width_idx
+            .map_or(0, |idx| (idx + 1) % PIP_PRESET_WIDTHS.len());
+        data.pip_preset_width_idx = Some(preset_idx);
+
+        let tile = &mut self.tiles[idx];
+        tile.floating_preset_width_idx = None;
+
+        let tile_width = self.working_area.size.w * PIP_PRESET_WIDTHS[preset_idx];
+        let win_width = tile.window_width_for_tile_width(tile_width);
+
+        // Keep the aspect ratio, since picture-in-picture windows usually show a video.
+        let win = tile.window_mut();
+        let current = win.expected_size().unwrap_or_else(|| win.size());
+        let win_height = if current.w > 0 {
+            f64::from(current.h) * win_width / f64::from(current.w)
+        } else {
+            f64::from(current.h)
+        };
+
+        let min_size = win.min_size();
+        let max_size = win.max_size();
+        let win_width =
+            ensure_min_max_size(win_width.round().max(1.) as i32, min_size.w, max_size.w);
+        let win_height =
+            ensure_min_max_size(win_height.round().max(1.) as i32, min_size.h, max_size.h);
+
+        // The tile stays in its corner as it resizes, if it's snapped.
+        win.request_size_once(Size::from((win_width, win_height)), true);
+    }
+
+    /// Moves the snapped picture-in-picture window to a corner where it doesn't cover the area.
+    ///
+    /// Does nothing if it covers the area in every corner.
+    pub fn dodge_pip(&mut self, area: Rectangle<f64, Logical>) {
+        let Some(idx) = self.pip_idx() else {
+            return;
+        };
+
+        let data = &self.data[idx];
+        let Some((corner, gap)) = data.snapped_corner else {
+            return;
+        };
+        if !data.rect().overlaps(area) {
+            return;
+        }
+
+        let size = data.size;
+        let free = iter::successors(Some(corner.next()), |corner| Some(corner.next()))
+            .take(3)
+            .find(|corner| {
+                let pos = corner.tile_pos(self.working_area, size, gap);
+                !Rectangle::new(pos, size).overlaps(area)
+            });
+        if let Some(corner) = free {
+            self.snap_to_corner(idx, corner);
+        }
+    }
+
     pub fn descendants_added(&mut self, id: &W::Id) -> bool {
         let Some(idx) = self.idx_of(id) else {
             return false;
@@ -998,6 +1207,7 @@ impl<W: LayoutElement> FloatingSpace<W> {
         view_rect: Rectangle<f64, Logical>,
         target: RenderTarget,
         focus_ring: bool,
+        only_always_on_top: bool,
     ) -> Vec<FloatingSpaceRenderElement<R>> {
         let mut rv = Vec::new();
 
@@ -1006,13 +1216,19 @@ impl<W: LayoutElement> FloatingSpace<W> {
         // Draw the closing windows on top of the other windows.
         //
         // FIXME: I guess this should rather preserve the stacking order when the window is closed.
-        for closing in self.closing_windows.iter().rev() {
-            let elem = closing.render(renderer.as_gles_renderer(), view_rect, scale, target);
-            rv.push(elem.into());
+        if !only_always_on_top {
+            for closing in self.closing_windows.iter().rev() {
+                let elem = closing.render(renderer.as_gles_renderer(), view_rect, scale, target);
+                rv.push(elem.into());
+            }
         }
 
         let active = self.active_window_id.clone();
         for (tile, tile_pos) in self.tiles_with_render_positions() {
+            if only_always_on_top && !tile.window().is_always_on_top() {
+                continue;
+            }
+
             // For the active tile, draw the focus ring.
             let focus_ring = focus_ring && Some(tile.window().id()) == active.as_ref();
 
@@ -1101,7 +1317,9 @@ impl<W: LayoutElement> FloatingSpace<W> {
         self.interactive_resize = None;
     }
 
-    pub fn refresh(&mut self, is_active: bool, is_focused: bool) {
+    pub fn refresh(&mut self, is_active: bool, is_focused: bool, is_visible: bool) {
+        self.stack_always_on_top();
+
         let active = self.active_window_id.clone();
         for tile in &mut self.tiles {
             let win = tile.window_mut();
@@ -1114,6 +1332,7 @@ impl<W: LayoutElement> FloatingSpace<W> {
                 is_active &= is_focused;
             }
             win.set_activated(is_active);
+            win.set_suspended(!is_visible);
 
             let resize_data = self
                 .interactive_resize
@@ -16,6 +16,7 @@ impl InsertHintElement {
         Self {
             inner: FocusRing::new(niri_config::FocusRing {
                 off: config.off,
+                hide_when_single_window: false,
                 width: FloatOrInt(0.),
                 active_color: config.color,
                 inactive_color: config.color,
@@ -30,6 +31,7 @@ impl InsertHintElement {
     pub fn update_config(&mut self, config: niri_config::InsertHint) {
         self.inner.update_config(niri_config::FocusRing {
             off: config.off,
+            hide_when_single_window: false,
             width: FloatOrInt(0.),
             active_color: config.color,
             inactive_color: config.color,
@@ -42,7 +42,7 @@ use niri_config::{
     CenterFocusedColumn, Config, CornerRadius, FloatOrInt, PresetSize, Struts,
     Workspace as WorkspaceConfig, WorkspaceReference,
 };
-use niri_ipc::{ColumnDisplay, PositionChange, SizeChange};
+use niri_ipc::{ColumnDisplay, PositionChange, SizeChange, WindowLayout, WorkspaceLayout};
 use scrolling::{Column, ColumnWidth};
 use smithay::backend::renderer::element::surface::WaylandSurfaceRenderElement;
 use smithay::backend::renderer::element::utils::RescaleRenderElement;
@@ -81,6 +81,7 @@ pub mod floating;
 pub mod focus_ring;
 pub mod insert_hint_element;
 pub mod monitor;
+pub mod offscreen_column_indicator;
 pub mod opening_window;
 pub mod scrolling;
 pub mod shadow;
@@ -207,13 +208,24 @@ pub trait LayoutElement {
     fn output_leave(&self, output: &Output);
     fn set_offscreen_data(&self, data: Option<OffscreenData>);
     fn set_activated(&mut self, active: bool);
+    /// Sets whether the window isn't visible, so that it can stop rendering.
+    fn set_suspended(&mut self, suspended: bool);
     fn set_active_in_column(&mut self, active: bool);
     fn set_floating(&mut self, floating: bool);
     fn set_bounds(&self, bounds: Size<i32, Logical>);
-    fn is_ignoring_opacity_window_rule(&self) -> bool;
+    /// Opacity of the window, from 0 to 1, not counting fullscreen.
+    fn opacity(&self) -> f32;
+
+    /// Whether the window, when floating, stays above all other windows.
+    fn is_always_on_top(&self) -> bool;
 
     fn is_urgent(&self) -> bool;
 
+    /// Whether the client stopped answering pings.
+    fn is_unresponsive(&self) -> bool;
+    /// Whether a force kill of this window is waiting for a confirmation.
+    fn is_kill_pending(&self) -> bool;
+
     fn configure_intent(&self) -> ConfigureIntent;
     fn send_pending_configure(&mut self);
 
@@ -296,9 +308,12 @@ pub struct Layout<W: LayoutElement> {
     /// This data is stored upon monitor removal and is used to restore the active workspace when
     /// the monitor is reconnected.
     ///
+    /// The monitor is identified by make/model/serial when available, so that the active workspace
+    /// is restored even if the monitor is reconnected to a different connector.
+    ///
     /// The workspace id does not necessarily point to a valid workspace. If it doesn't, then it is
     /// simply ignored.
-    last_active_workspace_id: HashMap<String, WorkspaceId>,
+    last_active_workspace_id: HashMap<OutputId, WorkspaceId>,
     /// Ongoing interactive move.
     interactive_move: Option<InteractiveMoveState<W>>,
     /// Ongoing drag-and-drop operation.
@@ -314,6 +329,10 @@ pub struct Layout<W: LayoutElement> {
     overview_open: bool,
     /// The overview zoom progress.
     overview_progress: Option<OverviewProgress>,
+    /// Whether the device is in tablet mode.
+    is_tablet_mode: bool,
+    /// Whether effects are degraded to save power.
+    is_power_saving: bool,
     /// Configurable properties of the layout.
     options: Rc<Options>,
 }
@@ -342,11 +361,17 @@ pub struct Options {
     pub gaps: f64,
     /// Extra padding around the working area in logical pixels.
     pub struts: Struts,
+    /// Whether to remove the gaps on workspaces with a single column.
+    pub smart_gaps: Option<niri_config::SmartGaps>,
+    /// Maximum width of the column in reading mode in logical pixels.
+    pub reading_mode_max_width: f64,
     pub focus_ring: niri_config::FocusRing,
     pub border: niri_config::Border,
     pub shadow: niri_config::Shadow,
     pub tab_indicator: niri_config::TabIndicator,
     pub insert_hint: niri_config::InsertHint,
+    pub offscreen_column_indicator: niri_config::OffscreenColumnIndicator,
+    pub top_bar: niri_config::TopBar,
     pub center_focused_column: CenterFocusedColumn,
     pub always_center_single_column: bool,
     pub empty_workspace_above_first: bool,
@@ -360,6 +385,8 @@ pub struct Options {
     pub animations: niri_config::Animations,
     pub gestures: niri_config::Gestures,
     pub overview: niri_config::Overview,
+    /// Extra hit area around top bar buttons in logical pixels.
+    pub hit_target_padding: f64,
     // Debug flags.
     pub disable_resize_throttling: bool,
     pub disable_transactions: bool,
@@ -371,11 +398,15 @@ impl Default for Options {
         Self {
             gaps: 16.,
             struts: Default::default(),
+            smart_gaps: None,
+            reading_mode_max_width: 1200.,
             focus_ring: Default::default(),
             border: Default::default(),
             shadow: Default::default(),
             tab_indicator: Default::default(),
             insert_hint: Default::default(),
+            offscreen_column_indicator: Default::default(),
+            top_bar: Default::default(),
             center_focused_column: Default::default(),
             always_center_single_column: false,
             empty_workspace_above_first: false,
@@ -389,6 +420,7 @@ impl Default for Options {
             animations: Default::default(),
             gestures: Default::default(),
             overview: Default::default(),
+            hit_target_padding: 0.,
             disable_resize_throttling: false,
             disable_transactions: false,
             preset_window_heights: vec![
@@ -531,6 +563,8 @@ pub enum HitType {
     Activate {
         /// Whether the hit was on the tab indicator.
         is_tab_indicator: bool,
+        /// Whether the hit was on the top bar, outside of its buttons.
+        is_top_bar: bool,
     },
     /// The hit is on a button in the top bar.
     TopBarButton {
@@ -610,6 +644,7 @@ impl HitType {
         match self {
             HitType::Input { .. } => HitType::Activate {
                 is_tab_indicator: false,
+                is_top_bar: false,
             },
             HitType::Activate { .. } => self,
             HitType::TopBarButton { .. } => self,
@@ -653,11 +688,15 @@ impl Options {
         Self {
             gaps: layout.gaps.0,
             struts: layout.struts,
+            smart_gaps: layout.smart_gaps,
+            reading_mode_max_width: layout.reading_mode_max_width.0,
             focus_ring: layout.focus_ring,
             border: layout.border,
             shadow: layout.shadow,
             tab_indicator: layout.tab_indicator,
             insert_hint: layout.insert_hint,
+            offscreen_column_indicator: layout.offscreen_column_indicator,
+            top_bar: layout.top_bar,
             center_focused_column: layout.center_focused_column,
             always_center_single_column: layout.always_center_single_column,
             empty_workspace_above_first: layout.empty_workspace_above_first,
@@ -667,6 +706,8 @@ impl Options {
             animations: config.animations.clone(),
             gestures: config.gestures,
             overview: config.overview,
+            // Only applied in tablet mode, see Layout::update_config().
+            hit_target_padding: 0.,
             disable_resize_throttling: config.debug.disable_resize_throttling,
             disable_transactions: config.debug.disable_transactions,
             deactivate_unfocused_windows: config.debug.deactivate_unfocused_windows,
@@ -761,6 +802,8 @@ impl<W: LayoutElement> Layout<W> {
             update_render_elements_time: Duration::ZERO,
             overview_open: false,
             overview_progress: None,
+            is_tablet_mode: false,
+            is_power_saving: false,
             options: Rc::new(options),
         }
     }
@@ -786,6 +829,8 @@ impl<W: LayoutElement> Layout<W> {
             update_render_elements_time: Duration::ZERO,
             overview_open: false,
             overview_progress: None,
+            is_tablet_mode: false,
+            is_power_saving: false,
             options: opts,
         }
     }
@@ -799,7 +844,9 @@ impl<W: LayoutElement> Layout<W> {
             } => {
                 let primary = &mut monitors[primary_idx];
 
-                let ws_id_to_activate = self.last_active_workspace_id.remove(&output.name());
+                let ws_id_to_activate = self
+                    .last_active_workspace_id
+                    .remove(&OutputId::new(&output));
                 let mut active_workspace_idx = None;
 
                 let mut stopped_primary_ws_switch = false;
@@ -916,7 +963,9 @@ impl<W: LayoutElement> Layout<W> {
                     active_workspace_idx += 1;
                 }
 
-                let ws_id_to_activate = self.last_active_workspace_id.remove(&output.name());
+                let ws_id_to_activate = self
+                    .last_active_workspace_id
+                    .remove(&OutputId::new(&output));
 
                 for (i, workspace) in workspaces.iter_mut().enumerate() {
                     workspace.set_output(Some(output.clone()));
@@ -955,7 +1004,7 @@ impl<W: LayoutElement> Layout<W> {
                 let monitor = monitors.remove(idx);
 
                 self.last_active_workspace_id.insert(
-                    monitor.output_name().clone(),
+                    OutputId::new(&monitor.output),
                     monitor.workspaces[monitor.active_workspace_idx].id(),
                 );
 
@@ -1610,6 +1659,19 @@ impl<W: LayoutElement> Layout<W> {
         0.
     }
 
+    /// Returns whether the view of the workspace with this window is currently scrolling.
+    pub fn is_view_scrolling_on(&self, window: &W::Id) -> bool {
+        if let Some(InteractiveMoveState::Moving(move_)) = &self.interactive_move {
+            if move_.tile.window().id() == window {
+                return false;
+            }
+        }
+
+        self.workspaces()
+            .find(|(_, _, ws)| ws.has_window(window))
+            .is_some_and(|(_, _, ws)| ws.is_view_scrolling())
+    }
+
     pub fn should_trigger_focus_follows_mouse_on(&self, window: &W::Id) -> bool {
         // During an animation, it's easy to trigger focus-follows-mouse on the previous workspace,
         // especially when clicking to switch workspace on a bar of some kind. This cancels the
@@ -1799,29 +1861,67 @@ impl<W: LayoutElement> Layout<W> {
         moving_window.chain(mon_windows)
     }
 
-    pub fn with_windows(&self, mut f: impl FnMut(&W, Option<&Output>, Option<WorkspaceId>)) {
+    pub fn with_windows(
+        &self,
+        mut f: impl FnMut(&W, Option<&Output>, Option<WorkspaceId>, WindowLayout),
+    ) {
         if let Some(InteractiveMoveState::Moving(move_)) = &self.interactive_move {
-            f(move_.tile.window(), Some(&move_.output), None);
+            let window = move_.tile.window();
+            let layout = WindowLayout {
+                pos_in_scrolling_layout: None,
+                is_fullscreen: window.is_fullscreen(),
+            };
+            f(window, Some(&move_.output), None, layout);
         }
 
         match &self.monitor_set {
             MonitorSet::Normal { monitors, .. } => {
                 for mon in monitors {
                     for ws in &mon.workspaces {
-                        for win in ws.windows() {
-                            f(win, Some(&mon.output), Some(ws.id()));
+                        for (win, layout) in ws.windows_with_ipc_layouts() {
+                            f(win, Some(&mon.output), Some(ws.id()), layout);
                         }
                     }
                 }
             }
             MonitorSet::NoOutputs { workspaces } => {
                 for ws in workspaces {
-                    for win in ws.windows() {
-                        f(win, None, Some(ws.id()));
+                    for (win, layout) in ws.windows_with_ipc_layouts() {
+                        f(win, None, Some(ws.id()), layout);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the layout tree of every workspace for IPC.
+    ///
+    /// A window in the middle of an interactive move isn't on any workspace, so it's missing.
+    pub fn ipc_layout(&self, window_id: &dyn Fn(&W) -> u64) -> Vec<WorkspaceLayout> {
+        let mut rv = Vec::new();
+
+        match &self.monitor_set {
+            MonitorSet::Normal {
+                monitors,
+                active_monitor_idx,
+                ..
+            } => {
+                for (mon_idx, mon) in monitors.iter().enumerate() {
+                    for (ws_idx, ws) in mon.workspaces.iter().enumerate() {
+                        let is_active = ws_idx == mon.active_workspace_idx;
+                        let is_focused = is_active && mon_idx == *active_monitor_idx;
+                        rv.push(ws.ipc_layout(Some(&mon.output), is_active, is_focused, window_id));
                     }
                 }
             }
+            MonitorSet::NoOutputs { workspaces } => {
+                for ws in workspaces {
+                    rv.push(ws.ipc_layout(None, false, false, window_id));
+                }
+            }
         }
+
+        rv
     }
 
     pub fn with_windows_mut(&mut self, mut f: impl FnMut(&mut W, Option<&Output>)) {
@@ -1965,11 +2065,27 @@ impl<W: LayoutElement> Layout<W> {
         true
     }
 
-    pub fn move_column_to_index(&mut self, index: usize) {
-        let Some(workspace) = self.active_workspace_mut() else {
+    pub fn move_column_to_index(&mut self, window: Option<&W::Id>, index: usize) {
+        if let Some(InteractiveMoveState::Moving(move_)) = &self.interactive_move {
+            if window == Some(move_.tile.window().id()) {
+                return;
+            }
+        }
+
+        let workspace = if let Some(window) = window {
+            Some(
+                self.workspaces_mut()
+                    .find(|ws| ws.has_window(window))
+                    .unwrap(),
+            )
+        } else {
+            self.active_workspace_mut()
+        };
+
+        let Some(workspace) = workspace else {
             return;
         };
-        workspace.move_column_to_index(index);
+        workspace.move_column_to_index(window, index);
     }
 
     pub fn move_down(&mut self) {
@@ -2095,6 +2211,13 @@ impl<W: LayoutElement> Layout<W> {
         workspace.focus_column(index);
     }
 
+    pub fn focus_column_previous(&mut self) {
+        let Some(workspace) = self.active_workspace_mut() else {
+            return;
+        };
+        workspace.focus_column_previous();
+    }
+
     pub fn focus_window_up_or_output(&mut self, output: &Output) -> bool {
         if let Some(workspace) = self.active_workspace_mut() {
             if workspace.focus_up() {
@@ -2399,6 +2522,20 @@ impl<W: LayoutElement> Layout<W> {
         workspace.center_visible_columns();
     }
 
+    pub fn move_pip_to_next_corner(&mut self) {
+        let Some(workspace) = self.active_workspace_mut() else {
+            return;
+        };
+        workspace.move_pip_to_next_corner();
+    }
+
+    pub fn switch_preset_pip_width(&mut self) {
+        let Some(workspace) = self.active_workspace_mut() else {
+            return;
+        };
+        workspace.switch_preset_pip_width();
+    }
+
     pub fn focus(&self) -> Option<&W> {
         self.focus_with_output().map(|(win, _out)| win)
     }
@@ -2605,11 +2742,10 @@ impl<W: LayoutElement> Layout<W> {
                         "workspace base options must be synchronized with layout"
                     );
 
-                    let options = Options::clone(&workspace.base_options)
-                        .adjusted_for_scale(workspace.scale().fractional_scale());
                     assert_eq!(
-                        &*workspace.options, &options,
-                        "workspace options must be base options adjusted for workspace scale"
+                        &*workspace.options,
+                        &workspace.resolved_options(),
+                        "workspace options must be resolved from base options"
                     );
 
                     assert!(
@@ -2769,11 +2905,10 @@ impl<W: LayoutElement> Layout<W> {
                     "workspace options must be synchronized with layout"
                 );
 
-                let options = Options::clone(&workspace.base_options)
-                    .adjusted_for_scale(workspace.scale().fractional_scale());
                 assert_eq!(
-                    &*workspace.options, &options,
-                    "workspace options must be base options adjusted for workspace scale"
+                    &*workspace.options,
+                    &workspace.resolved_options(),
+                    "workspace options must be resolved from base options"
                 );
 
                 assert!(
@@ -3199,7 +3334,32 @@ impl<W: LayoutElement> Layout<W> {
     }
 
     pub fn update_config(&mut self, config: &Config) {
-        self.update_options(Options::from_config(config));
+        let mut options = Options::from_config(config);
+        if self.is_tablet_mode {
+            options.hit_target_padding = config.tablet_mode.hit_target_padding.0;
+        }
+        if self.is_power_saving && config.power_saving.disable_shadows {
+            options.shadow.on = false;
+        }
+        self.update_options(options);
+    }
+
+    pub fn set_power_saving(&mut self, config: &Config, is_power_saving: bool) {
+        if self.is_power_saving == is_power_saving {
+            return;
+        }
+
+        self.is_power_saving = is_power_saving;
+        self.update_config(config);
+    }
+
+    pub fn set_tablet_mode(&mut self, config: &Config, is_tablet_mode: bool) {
+        if self.is_tablet_mode == is_tablet_mode {
+            return;
+        }
+
+        self.is_tablet_mode = is_tablet_mode;
+        self.update_config(config);
     }
 
     fn update_options(&mut self, options: Options) {
@@ -3291,6 +3451,20 @@ impl<W: LayoutElement> Layout<W> {
         workspace.toggle_full_width();
     }
 
+    pub fn toggle_column_fullscreen(&mut self) {
+        let Some(workspace) = self.active_workspace_mut() else {
+            return;
+        };
+        workspace.toggle_column_fullscreen();
+    }
+
+    pub fn toggle_reading_mode(&mut self) {
+        let Some(workspace) = self.active_workspace_mut() else {
+            return;
+        };
+        workspace.toggle_reading_mode();
+    }
+
     pub fn set_column_width(&mut self, change: SizeChange) {
         let Some(workspace) = self.active_workspace_mut() else {
             return;
@@ -4012,6 +4186,45 @@ impl<W: LayoutElement> Layout<W> {
         None
     }
 
+    pub fn wheel_scroll_gesture_begin(&mut self, output: &Output, workspace_idx: usize) {
+        let monitors = match &mut self.monitor_set {
+            MonitorSet::Normal { monitors, .. } => monitors,
+            MonitorSet::NoOutputs { .. } => unreachable!(),
+        };
+
+        for monitor in monitors {
+            for (idx, ws) in monitor.workspaces.iter_mut().enumerate() {
+                if &monitor.output == output && idx == workspace_idx {
+                    ws.wheel_scroll_gesture_begin();
+                } else {
+                    // Cancel the gesture on other workspaces.
+                    ws.wheel_scroll_gesture_end();
+                }
+            }
+        }
+    }
+
+    pub fn wheel_scroll_gesture_update(&mut self, delta_x: f64, timestamp: Duration) -> bool {
+        let zoom = self.overview_zoom();
+        let delta_x = delta_x / zoom;
+
+        for ws in self.workspaces_mut() {
+            if ws.wheel_scroll_gesture_update(delta_x, timestamp) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    pub fn wheel_scroll_gesture_end(&mut self) -> bool {
+        let mut ended = false;
+        for ws in self.workspaces_mut() {
+            ended |= ws.wheel_scroll_gesture_end();
+        }
+        ended
+    }
+
     pub fn overview_gesture_begin(&mut self) {
         self.overview_open = true;
 
@@ -5143,6 +5356,7 @@ impl<W: LayoutElement> Layout<W> {
             win.set_active_in_column(true);
             win.set_floating(move_.is_floating);
             win.set_activated(true);
+            win.set_suspended(false);
 
             win.set_interactive_resize(None);
 
@@ -5182,9 +5396,10 @@ impl<W: LayoutElement> Layout<W> {
                         mon.dnd_scroll_gesture_end();
                     }
 
+                    let visible: Vec<_> = mon.workspaces_visibility().collect();
                     for (ws_idx, ws) in mon.workspaces.iter_mut().enumerate() {
                         let is_focused = is_active && ws_idx == mon.active_workspace_idx;
-                        ws.refresh(is_active, is_focused);
+                        ws.refresh(is_active, is_focused, visible[ws_idx]);
 
                         if let Some(is_scrolling) = ongoing_scrolling_dnd {
                             // Lock or unlock the view for scrolling interactive move.
@@ -5204,7 +5419,7 @@ impl<W: LayoutElement> Layout<W> {
             }
             MonitorSet::NoOutputs { workspaces, .. } => {
                 for ws in workspaces {
-                    ws.refresh(false, false);
+                    ws.refresh(false, false, false);
                     ws.view_offset_gesture_end(None);
                 }
             }
@@ -1276,6 +1276,21 @@ impl<W: LayoutElement> Monitor<W> {
         }
     }
 
+    /// Returns the workspace at the current render index and the one the view is moving towards.
+    ///
+    /// The second workspace comes with its blend factor: drawing it on top of the first one with
+    /// this alpha gives a crossfade that follows workspace switch animations and gestures.
+    pub fn workspaces_for_crossfade(&self) -> (&Workspace<W>, Option<(&Workspace<W>, f64)>) {
+        let last_idx = self.workspaces.len() - 1;
+        let render_idx = self.workspace_render_idx().clamp(0., last_idx as f64);
+
+        let idx = render_idx.floor() as usize;
+        let progress = render_idx - idx as f64;
+
+        let next = (progress > 0. && idx < last_idx).then(|| (&self.workspaces[idx + 1], progress));
+        (&self.workspaces[idx], next)
+    }
+
     pub fn workspaces_render_geo(&self) -> impl Iterator<Item = Rectangle<f64, Logical>> {
         let scale = self.scale.fractional_scale();
         let zoom = self.overview_zoom();
@@ -1300,6 +1315,15 @@ impl<W: LayoutElement> Monitor<W> {
         })
     }
 
+    /// Returns whether each workspace is at least partially on screen.
+    pub fn workspaces_visibility(&self) -> impl Iterator<Item = bool> {
+        let output_geo = Rectangle::from_size(self.view_size);
+
+        self.workspaces_render_geo()
+            .take(self.workspaces.len())
+            .map(move |geo| geo.intersection(output_geo).is_some())
+    }
+
     pub fn workspaces_with_render_geo(
         &self,
     ) -> impl Iterator<Item = (&Workspace<W>, Rectangle<f64, Logical>)> {
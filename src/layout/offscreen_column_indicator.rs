@@ -0,0 +1,204 @@
+//! Indicator of the columns past the edges of the view.
+//!
+//! It shows up at the left and right edges of the view while the view scrolls, and hides a while
+//! after the view comes to rest.
+
+use std::iter::zip;
+
+use niri_config::OffscreenColumnIndicatorStyle;
+use smithay::backend::renderer::element::Kind;
+use smithay::utils::{Logical, Point, Rectangle, Size};
+
+use crate::animation::{Animation, Clock, Curve};
+use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
+use crate::utils::round_logical_in_physical_max1;
+
+/// How long the indicator takes to fade out at the end of `hide-after-ms`.
+const FADE_DURATION_MS: u64 = 150;
+
+/// Maximum number of dots on one side, with the dots style.
+const MAX_DOTS: usize = 16;
+
+#[derive(Debug)]
+pub struct OffscreenColumnIndicator {
+    buffers: Vec<SolidColorBuffer>,
+    locations: Vec<Point<f64, Logical>>,
+    /// Whether the indicator is visible, either scrolling or waiting to hide.
+    is_shown: bool,
+    /// Countdown to hiding the indicator after the view stops scrolling.
+    ///
+    /// The value goes from zero to the total time in milliseconds.
+    hide_anim: Option<Animation>,
+    config: niri_config::OffscreenColumnIndicator,
+}
+
+pub type OffscreenColumnIndicatorRenderElement = SolidColorRenderElement;
+
+impl OffscreenColumnIndicator {
+    pub fn new(config: niri_config::OffscreenColumnIndicator) -> Self {
+        Self {
+            buffers: Vec::new(),
+            locations: Vec::new(),
+            is_shown: false,
+            hide_anim: None,
+            config,
+        }
+    }
+
+    pub fn update_config(&mut self, config: niri_config::OffscreenColumnIndicator) {
+        self.config = config;
+    }
+
+    pub fn advance_animations(&mut self) {
+        if let Some(anim) = &self.hide_anim {
+            if anim.is_done() {
+                self.hide_anim = None;
+                self.is_shown = false;
+            }
+        }
+    }
+
+    pub fn are_animations_ongoing(&self) -> bool {
+        self.hide_anim.is_some()
+    }
+
+    /// Updates the indicator for `left` and `right` columns past the view edges.
+    ///
+    /// `area` is the working area, which the indicator spans vertically.
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_render_elements(
+        &mut self,
+        is_scrolling: bool,
+        clock: &Clock,
+        left: usize,
+        right: usize,
+        view_width: f64,
+        area: Rectangle<f64, Logical>,
+        scale: f64,
+    ) {
+        if !self.config.on {
+            self.is_shown = false;
+            self.hide_anim = None;
+            return;
+        }
+
+        if is_scrolling {
+            self.is_shown = true;
+            self.hide_anim = None;
+        } else if self.is_shown && self.hide_anim.is_none() {
+            let duration = u64::from(self.config.hide_after_ms) + FADE_DURATION_MS;
+            self.hide_anim = Some(Animation::ease(
+                clock.clone(),
+                0.,
+                duration as f64,
+                0.,
+                duration,
+                Curve::Linear,
+            ));
+        }
+
+        if !self.is_shown {
+            return;
+        }
+
+        let width = round_logical_in_physical_max1(scale, self.config.width.0);
+
+        let mut rects = Vec::new();
+        for (count, x) in [(left, 0.), (right, view_width - width)] {
+            if count == 0 {
+                continue;
+            }
+
+            match self.config.style {
+                OffscreenColumnIndicatorStyle::Bar => {
+                    let loc = Point::from((x, area.loc.y));
+                    rects.push(Rectangle::new(loc, Size::from((width, area.size.h))));
+                }
+                OffscreenColumnIndicatorStyle::Dots => {
+                    // Dots are squares separated by their own size.
+                    let count = count.min(MAX_DOTS);
+                    let total = (count * 2 - 1) as f64 * width;
+                    let mut y = area.loc.y + (area.size.h - total) / 2.;
+                    for _ in 0..count {
+                        let loc = Point::from((x, y));
+                        rects.push(Rectangle::new(loc, Size::from((width, width))));
+                        y += width * 2.;
+                    }
+                }
+            }
+        }
+
+        let color = self.config.color.to_array_premul();
+        self.buffers.resize_with(rects.len(), Default::default);
+        self.locations.clear();
+        for (buffer, rect) in zip(&mut self.buffers, rects) {
+            buffer.update(rect.size, color);
+            self.locations.push(rect.loc);
+        }
+    }
+
+    pub fn render(
+        &self,
+        scale: f64,
+    ) -> impl Iterator<Item = OffscreenColumnIndicatorRenderElement> + '_ {
+        let alpha = self.hide_anim.as_ref().map_or(1., |anim| {
+            let remaining = anim.to() - anim.value();
+            (remaining / FADE_DURATION_MS as f64).clamp(0., 1.)
+        }) as f32;
+
+        let show = self.config.on && self.is_shown;
+        zip(&self.buffers, &self.locations)
+            .filter(move |_| show)
+            .map(move |(buffer, loc)| {
+                let loc = loc.to_physical_precise_round(scale).to_logical(scale);
+                SolidColorRenderElement::from_buffer(buffer, loc, alpha, Kind::Unspecified)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use niri_config::FloatOrInt;
+
+    use super::*;
+
+    fn update(indicator: &mut OffscreenColumnIndicator, is_scrolling: bool, clock: &Clock) {
+        let area = Rectangle::new(Point::from((0., 0.)), Size::from((1280., 720.)));
+        indicator.update_render_elements(is_scrolling, clock, 2, 20, 1280., area, 1.);
+        indicator.advance_animations();
+    }
+
+    #[test]
+    fn shows_while_scrolling_then_hides() {
+        let mut clock = Clock::with_time(Duration::ZERO);
+        let mut indicator = OffscreenColumnIndicator::new(niri_config::OffscreenColumnIndicator {
+            on: true,
+            width: FloatOrInt(4.),
+            hide_after_ms: 500,
+            ..Default::default()
+        });
+
+        update(&mut indicator, false, &clock);
+        assert_eq!(indicator.render(1.).count(), 0);
+
+        // One dot per column past the edge, up to the limit.
+        update(&mut indicator, true, &clock);
+        assert_eq!(indicator.render(1.).count(), 2 + MAX_DOTS);
+        let right = indicator.render(1.).last().unwrap().geo();
+        assert_eq!(right.loc.x, 1276.);
+
+        // It stays for a while after the scrolling stops.
+        update(&mut indicator, false, &clock);
+        clock.set_unadjusted(Duration::from_millis(400));
+        update(&mut indicator, false, &clock);
+        assert_eq!(indicator.render(1.).count(), 2 + MAX_DOTS);
+        assert!(indicator.are_animations_ongoing());
+
+        clock.set_unadjusted(Duration::from_millis(1000));
+        update(&mut indicator, false, &clock);
+        assert_eq!(indicator.render(1.).count(), 0);
+        assert!(!indicator.are_animations_ongoing());
+    }
+}
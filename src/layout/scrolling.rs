@@ -4,13 +4,16 @@ use std::rc::Rc;
 use std::time::Duration;
 
 use niri_config::{CenterFocusedColumn, PresetSize, Struts};
-use niri_ipc::{ColumnDisplay, SizeChange};
+use niri_ipc::{ColumnDisplay, ColumnLayout, SizeChange};
 use ordered_float::NotNan;
 use smithay::backend::renderer::gles::GlesRenderer;
 use smithay::utils::{Logical, Point, Rectangle, Scale, Serial, Size};
 
 use super::closing_window::{ClosingWindow, ClosingWindowRenderElement};
 use super::monitor::InsertPosition;
+use super::offscreen_column_indicator::{
+    OffscreenColumnIndicator, OffscreenColumnIndicatorRenderElement,
+};
 use super::tab_indicator::{TabIndicator, TabIndicatorRenderElement, TabInfo};
 use super::tile::{Tile, TileRenderElement, TileRenderSnapshot};
 use super::workspace::{InteractiveResize, ResolvedSize};
@@ -27,6 +30,10 @@ use crate::window::ResolvedWindowRules;
 /// Amount of touchpad movement to scroll the view for the width of one working area.
 const VIEW_GESTURE_WORKING_AREA_MOVEMENT: f64 = 1200.;
 
+/// How far the wheel scroll gesture can overscroll the ends of the strip, as a fraction of the
+/// working area width.
+const WHEEL_SCROLL_OVERSCROLL: f64 = 0.1;
+
 /// A scrollable-tiling space for windows.
 #[derive(Debug)]
 pub struct ScrollingSpace<W: LayoutElement> {
@@ -64,9 +71,18 @@ pub struct ScrollingSpace<W: LayoutElement> {
     /// View offset to restore after unfullscreening.
     view_offset_before_fullscreen: Option<f64>,
 
+    /// Active window of the previously focused column, for `focus_column_previous()`.
+    ///
+    /// A window rather than a column index, so that it keeps pointing to the same column as
+    /// columns are added, removed and moved around.
+    previous_column_window: Option<W::Id>,
+
     /// Windows in the closing animation.
     closing_windows: Vec<ClosingWindow>,
 
+    /// Indicator of the columns past the view edges.
+    offscreen_column_indicator: OffscreenColumnIndicator,
+
     /// View size for this space.
     view_size: Size<f64, Logical>,
 
@@ -96,6 +112,7 @@ niri_render_elements! {
         Tile = TileRenderElement<R>,
         ClosingWindow = ClosingWindowRenderElement,
         TabIndicator = TabIndicatorRenderElement,
+        OffscreenColumnIndicator = OffscreenColumnIndicatorRenderElement,
     }
 }
 
@@ -129,6 +146,8 @@ pub(super) struct ViewGesture {
     stationary_view_offset: f64,
     /// Whether the gesture is controlled by the touchpad.
     is_touchpad: bool,
+    /// Whether the gesture is controlled by the mouse wheel.
+    is_wheel: bool,
 
     // If this gesture is for drag-and-drop scrolling, this is the last event's unadjusted
     // timestamp.
@@ -169,9 +188,18 @@ pub struct Column<W: LayoutElement> {
     /// Whether this column contains a single full-screened window.
     is_fullscreen: bool,
 
+    /// Whether this whole column is fullscreen.
+    ///
+    /// Its windows are stacked over the whole view without gaps, keeping their relative heights,
+    /// but they aren't full-screened themselves.
+    is_column_fullscreen: bool,
+
     /// How this column displays and arranges windows.
     display_mode: ColumnDisplay,
 
+    /// Sizes to restore when switching back to other display modes.
+    saved_display_sizes: Vec<DisplayModeSizes<W::Id>>,
+
     /// Tab indicator for the tabbed display mode.
     tab_indicator: TabIndicator,
 
@@ -209,6 +237,16 @@ struct TileData {
     interactively_resizing_by_left_edge: bool,
 }
 
+/// Column sizes remembered for a display mode while the column is in another one.
+#[derive(Debug)]
+struct DisplayModeSizes<Id> {
+    display: ColumnDisplay,
+    width: ColumnWidth,
+    preset_width_idx: Option<usize>,
+    /// Window heights by window, so that they survive windows coming and going in the meantime.
+    heights: Vec<(Id, WindowHeight)>,
+}
+
 /// Width of a column.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ColumnWidth {
@@ -272,7 +310,11 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             view_offset: ViewOffset::Static(0.),
             activate_prev_column_on_removal: None,
             view_offset_before_fullscreen: None,
+            previous_column_window: None,
             closing_windows: Vec::new(),
+            offscreen_column_indicator: OffscreenColumnIndicator::new(
+                options.offscreen_column_indicator,
+            ),
             view_size,
             working_area,
             parent_area,
@@ -296,6 +338,9 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             data.update(column);
         }
 
+        self.offscreen_column_indicator
+            .update_config(options.offscreen_column_indicator);
+
         self.view_size = view_size;
         self.working_area = working_area;
         self.parent_area = parent_area;
@@ -354,12 +399,15 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             closing.advance_animations();
             closing.are_animations_ongoing()
         });
+
+        self.offscreen_column_indicator.advance_animations();
     }
 
     pub fn are_animations_ongoing(&self) -> bool {
         self.view_offset.is_animation_ongoing()
             || self.columns.iter().any(Column::are_animations_ongoing)
             || !self.closing_windows.is_empty()
+            || self.offscreen_column_indicator.are_animations_ongoing()
     }
 
     pub fn are_transitions_ongoing(&self) -> bool {
@@ -379,6 +427,34 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             let view_rect = Rectangle::new(col_pos, view_size);
             col.update_render_elements(is_active, view_rect);
         }
+
+        let (left, right) = self.offscreen_column_counts();
+        self.offscreen_column_indicator.update_render_elements(
+            self.is_view_scrolling(),
+            &self.clock,
+            left,
+            right,
+            view_size.w,
+            self.working_area,
+            self.scale,
+        );
+    }
+
+    /// Returns how many columns are entirely past the left and the right edges of the view.
+    fn offscreen_column_counts(&self) -> (usize, usize) {
+        let view_pos = self.view_pos();
+        let view_end = view_pos + self.view_size.w;
+
+        let mut left = 0;
+        let mut right = 0;
+        for (data, col_x) in zip(&self.data, self.column_xs(self.data.iter().copied())) {
+            if col_x + data.width <= view_pos {
+                left += 1;
+            } else if view_end <= col_x {
+                right += 1;
+            }
+        }
+        (left, right)
     }
 
     pub fn tiles(&self) -> impl Iterator<Item = &Tile<W>> + '_ {
@@ -389,10 +465,31 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         self.columns.iter_mut().flat_map(|col| col.tiles.iter_mut())
     }
 
+    /// Returns the tiles along with their 1-based column and tile indices.
+    pub fn tiles_with_ipc_positions(
+        &self,
+    ) -> impl Iterator<Item = (&Tile<W>, (usize, usize))> + '_ {
+        self.columns.iter().enumerate().flat_map(|(col_idx, col)| {
+            col.tiles
+                .iter()
+                .enumerate()
+                .map(move |(tile_idx, tile)| (tile, (col_idx + 1, tile_idx + 1)))
+        })
+    }
+
     pub fn is_empty(&self) -> bool {
         self.columns.is_empty()
     }
 
+    pub fn column_count(&self) -> usize {
+        self.columns.len()
+    }
+
+    /// Returns whether the view is moving, either from an animation or from a gesture.
+    pub fn is_view_scrolling(&self) -> bool {
+        !self.view_offset.is_static()
+    }
+
     pub fn active_window(&self) -> Option<&W> {
         if self.columns.is_empty() {
             return None;
@@ -426,7 +523,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         }
 
         let col = &self.columns[self.active_column_idx];
-        col.is_fullscreen
+        col.fills_view()
     }
 
     pub fn new_window_toplevel_bounds(&self, rules: &ResolvedWindowRules) -> Size<i32, Logical> {
@@ -564,7 +661,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             target_x,
             self.column_x(idx),
             col.width(),
-            col.is_fullscreen,
+            col.fills_view(),
         )
     }
 
@@ -578,7 +675,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             target_x,
             self.column_x(idx),
             col.width(),
-            col.is_fullscreen,
+            col.fills_view(),
         )
     }
 
@@ -1360,8 +1457,9 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         let Some(column_idx) = column_idx else {
             return false;
         };
-        let column = &mut self.columns[column_idx];
+        self.remember_previous_column(column_idx);
 
+        let column = &mut self.columns[column_idx];
         column.activate_window(window);
         self.activate_column(column_idx);
 
@@ -1471,11 +1569,30 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             .any(|col| col.start_open_animation(id))
     }
 
+    /// Remembers the active column as the previous one if the column at `idx` is about to be
+    /// focused instead.
+    fn remember_previous_column(&mut self, idx: usize) {
+        if idx == self.active_column_idx {
+            return;
+        }
+
+        if let Some(col) = self.columns.get(self.active_column_idx) {
+            let window = col.tiles[col.active_tile_idx].window().id().clone();
+            self.previous_column_window = Some(window);
+        }
+    }
+
+    /// Focuses the column at `idx`, remembering the current column as the previous one.
+    fn focus_column_idx(&mut self, idx: usize) {
+        self.remember_previous_column(idx);
+        self.activate_column(idx);
+    }
+
     pub fn focus_left(&mut self) -> bool {
         if self.active_column_idx == 0 {
             return false;
         }
-        self.activate_column(self.active_column_idx - 1);
+        self.focus_column_idx(self.active_column_idx - 1);
         true
     }
 
@@ -1484,12 +1601,12 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             return false;
         }
 
-        self.activate_column(self.active_column_idx + 1);
+        self.focus_column_idx(self.active_column_idx + 1);
         true
     }
 
     pub fn focus_column_first(&mut self) {
-        self.activate_column(0);
+        self.focus_column_idx(0);
     }
 
     pub fn focus_column_last(&mut self) {
@@ -1497,7 +1614,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             return;
         }
 
-        self.activate_column(self.columns.len() - 1);
+        self.focus_column_idx(self.columns.len() - 1);
     }
 
     pub fn focus_column(&mut self, index: usize) {
@@ -1505,7 +1622,18 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             return;
         }
 
-        self.activate_column(index.saturating_sub(1).min(self.columns.len() - 1));
+        self.focus_column_idx(index.saturating_sub(1).min(self.columns.len() - 1));
+    }
+
+    pub fn focus_column_previous(&mut self) {
+        let Some(window) = &self.previous_column_window else {
+            return;
+        };
+        let Some(idx) = self.columns.iter().position(|col| col.contains(window)) else {
+            return;
+        };
+
+        self.focus_column_idx(idx);
     }
 
     pub fn focus_window_in_column(&mut self, index: u8) {
@@ -1592,12 +1720,66 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         self.columns[self.active_column_idx].focus_bottom()
     }
 
-    pub fn move_column_to_index(&mut self, index: usize) {
+    pub fn move_column_to_index(&mut self, window: Option<&W::Id>, index: usize) {
         if self.columns.is_empty() {
             return;
         }
 
-        self.move_column_to(index.saturating_sub(1).min(self.columns.len() - 1));
+        let new_idx = index.saturating_sub(1).min(self.columns.len() - 1);
+
+        if let Some(window) = window {
+            let col_idx = self
+                .columns
+                .iter()
+                .position(|col| col.contains(window))
+                .unwrap();
+            if col_idx != self.active_column_idx {
+                self.move_inactive_column_to(col_idx, new_idx);
+                return;
+            }
+        }
+
+        self.move_column_to(new_idx);
+    }
+
+    /// Moves a column other than the active one, keeping the active column and the view.
+    fn move_inactive_column_to(&mut self, idx: usize, new_idx: usize) {
+        if idx == new_idx {
+            return;
+        }
+
+        let view_pos = self.view_pos();
+        let mut old_xs: Vec<_> = self
+            .column_xs(self.data.iter().copied())
+            .take(self.columns.len())
+            .map(|x| x - view_pos)
+            .collect();
+        let x = old_xs.remove(idx);
+        old_xs.insert(new_idx, x);
+
+        let mut column = self.columns.remove(idx);
+        let data = self.data.remove(idx);
+        cancel_resize_for_column(&mut self.interactive_resize, &mut column);
+        self.columns.insert(new_idx, column);
+        self.data.insert(new_idx, data);
+
+        // Keep pointing at the same active column. The view offset is relative to the active
+        // column, so the view stays on it.
+        if idx < self.active_column_idx && self.active_column_idx <= new_idx {
+            self.active_column_idx -= 1;
+        } else if new_idx <= self.active_column_idx && self.active_column_idx < idx {
+            self.active_column_idx += 1;
+        }
+
+        // Animate every column from its previous on-screen position.
+        let view_pos = self.view_pos();
+        let new_xs = self.column_xs(self.data.iter().copied());
+        for (col, (old_x, new_x)) in zip(&mut self.columns, zip(old_xs, new_xs)) {
+            let delta = old_x - (new_x - view_pos);
+            if delta != 0. {
+                col.animate_move_from(delta);
+            }
+        }
     }
 
     fn move_column_to(&mut self, new_idx: usize) {
@@ -2312,6 +2494,32 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             })
     }
 
+    /// Returns the columns for the IPC layout tree.
+    pub fn ipc_columns(&self, window_id: &dyn Fn(&W) -> u64) -> Vec<ColumnLayout> {
+        let view_off = Point::from((-self.view_pos(), 0.));
+        let col_xs = self.column_xs(self.data.iter().copied());
+        zip(&self.columns, col_xs)
+            .map(|(col, col_x)| {
+                let col_pos = view_off + Point::from((col_x, 0.)) + col.render_offset();
+                let tiles = zip(&col.tiles, col.tile_offsets())
+                    .map(|(tile, tile_off)| {
+                        let pos = col_pos + tile_off + tile.render_offset();
+                        tile.ipc_layout(pos, window_id(tile.window()))
+                    })
+                    .collect();
+
+                ColumnLayout {
+                    x: col_pos.x,
+                    width: col.width(),
+                    is_full_width: col.is_full_width,
+                    display: col.display_mode,
+                    tiles,
+                    active_tile_idx: col.active_tile_idx,
+                }
+            })
+            .collect()
+    }
+
     pub fn tiles_with_render_positions_mut(
         &mut self,
         round: bool,
@@ -2404,7 +2612,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
 
                 // Adjust for place-within-column tab indicator.
                 let origin_x = col.tiles_origin().x;
-                let extra_w = if is_tabbed && !col.is_fullscreen {
+                let extra_w = if is_tabbed && !col.fills_view() {
                     col.tab_indicator.extra_size(col.tiles.len(), col.scale).w
                 } else {
                     0.
@@ -2623,7 +2831,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         }
 
         let col = &mut self.columns[self.active_column_idx];
-        if col.is_fullscreen || col.is_full_width {
+        if col.fills_view() || col.is_full_width {
             return;
         }
 
@@ -2774,6 +2982,25 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         true
     }
 
+    pub fn toggle_column_fullscreen(&mut self) {
+        if self.columns.is_empty() {
+            return;
+        }
+
+        let col = &mut self.columns[self.active_column_idx];
+
+        // A full-screened window already takes up the whole view.
+        if col.is_fullscreen {
+            return;
+        }
+
+        cancel_resize_for_column(&mut self.interactive_resize, col);
+        col.set_column_fullscreen(!col.is_column_fullscreen);
+
+        // The tab indicator is hidden in a fullscreen column.
+        self.data[self.active_column_idx].update(col);
+    }
+
     pub fn render_above_top_layer(&self) -> bool {
         // Render above the top layer if we're on a fullscreen window and the view is stationary.
         if self.columns.is_empty() {
@@ -2784,7 +3011,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             return false;
         }
 
-        self.columns[self.active_column_idx].is_fullscreen
+        self.columns[self.active_column_idx].fills_view()
     }
 
     pub fn render_elements<R: NiriRenderer>(
@@ -2797,6 +3024,13 @@ impl<W: LayoutElement> ScrollingSpace<W> {
 
         let scale = Scale::from(self.scale);
 
+        // Draw the indicator on top of everything.
+        rv.extend(
+            self.offscreen_column_indicator
+                .render(self.scale)
+                .map(Into::into),
+        );
+
         // Draw the closing windows on top of the other windows.
         let view_rect = Rectangle::new(Point::from((self.view_pos(), 0.)), self.view_size);
         for closing in self.closing_windows.iter().rev() {
@@ -2864,7 +3098,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             let col_render_off = col.render_offset();
 
             // Hit the tab indicator.
-            if col.display_mode == ColumnDisplay::Tabbed && !col.is_fullscreen {
+            if col.display_mode == ColumnDisplay::Tabbed && !col.fills_view() {
                 let col_pos = view_off + col_off + col_render_off;
                 let col_pos = col_pos.to_physical_precise_round(scale).to_logical(scale);
 
@@ -2876,6 +3110,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
                 ) {
                     let hit = HitType::Activate {
                         is_tab_indicator: true,
+                        is_top_bar: false,
                     };
                     return Some((col.tiles[idx].window(), hit));
                 }
@@ -2929,6 +3164,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             delta_from_tracker: self.view_offset.current(),
             stationary_view_offset: self.view_offset.stationary(),
             is_touchpad,
+            is_wheel: false,
             dnd_last_event_time: None,
             dnd_nonzero_start_time: None,
         };
@@ -2952,6 +3188,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             delta_from_tracker: self.view_offset.current(),
             stationary_view_offset: self.view_offset.stationary(),
             is_touchpad: false,
+            is_wheel: false,
             dnd_last_event_time: Some(self.clock.now_unadjusted()),
             dnd_nonzero_start_time: None,
         };
@@ -2970,7 +3207,10 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             return None;
         };
 
-        if gesture.is_touchpad != is_touchpad || gesture.dnd_last_event_time.is_some() {
+        if gesture.is_touchpad != is_touchpad
+            || gesture.is_wheel
+            || gesture.dnd_last_event_time.is_some()
+        {
             return None;
         }
 
@@ -3062,6 +3302,74 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         true
     }
 
+    pub fn wheel_scroll_gesture_begin(&mut self) {
+        if self.view_offset.is_gesture() {
+            // Already active, or another gesture is in progress.
+            return;
+        }
+
+        if self.columns.is_empty() || self.interactive_resize.is_some() {
+            return;
+        }
+
+        let gesture = ViewGesture {
+            current_view_offset: self.view_offset.current(),
+            animation: None,
+            tracker: SwipeTracker::new(),
+            delta_from_tracker: self.view_offset.current(),
+            stationary_view_offset: self.view_offset.stationary(),
+            is_touchpad: false,
+            is_wheel: true,
+            dnd_last_event_time: None,
+            dnd_nonzero_start_time: None,
+        };
+        self.view_offset = ViewOffset::Gesture(gesture);
+    }
+
+    pub fn wheel_scroll_gesture_update(&mut self, delta_x: f64, timestamp: Duration) -> bool {
+        if !self.view_offset.is_wheel_scroll() {
+            return false;
+        }
+
+        // Let the view overscroll the ends of the strip a bit. Ending the gesture snaps it back
+        // with a spring, which makes the view bounce off the ends.
+        let gaps = self.options.gaps;
+        let overscroll = self.working_area.size.w * WHEEL_SCROLL_OVERSCROLL;
+        let left_strut = self.working_area.loc.x;
+        let right_strut = self.view_size.w - self.working_area.size.w - left_strut;
+
+        let last_col_idx = self.columns.len() - 1;
+        let strip_end = self.column_x(last_col_idx) + self.data[last_col_idx].width;
+        let active_col_x = self.column_x(self.active_column_idx);
+
+        let leftmost = -left_strut - gaps - overscroll - active_col_x;
+        let rightmost =
+            strip_end + gaps + right_strut - self.view_size.w + overscroll - active_col_x;
+        let min_offset = f64::min(leftmost, rightmost);
+        let max_offset = f64::max(leftmost, rightmost);
+
+        let ViewOffset::Gesture(gesture) = &mut self.view_offset else {
+            unreachable!()
+        };
+
+        gesture.tracker.push(delta_x, timestamp);
+
+        let view_offset = gesture.tracker.pos() + gesture.delta_from_tracker;
+        let clamped_offset = view_offset.clamp(min_offset, max_offset);
+
+        gesture.delta_from_tracker += clamped_offset - view_offset;
+        gesture.current_view_offset = clamped_offset;
+        true
+    }
+
+    pub fn wheel_scroll_gesture_end(&mut self) -> bool {
+        if !self.view_offset.is_wheel_scroll() {
+            return false;
+        }
+
+        self.view_offset_gesture_end(None)
+    }
+
     pub fn view_offset_gesture_end(&mut self, is_touchpad: Option<bool>) -> bool {
         let ViewOffset::Gesture(gesture) = &mut self.view_offset else {
             return false;
@@ -3117,7 +3425,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             for (col_idx, col) in self.columns.iter().enumerate() {
                 let col_w = col.width();
 
-                let view_pos = if col.is_fullscreen {
+                let view_pos = if col.fills_view() {
                     col_x
                 } else if self.working_area.size.w <= col_w {
                     col_x - left_strut
@@ -3144,7 +3452,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
 
                     // Normal columns align with the working area, but fullscreen columns align with
                     // the view size.
-                    if col.is_fullscreen {
+                    if col.fills_view() {
                         let left = col_x;
                         let right = col_x + col_w;
                         (left, right)
@@ -3271,7 +3579,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
                     let col_x = self.column_x(col_idx);
                     let col_w = col.width();
 
-                    if col.is_fullscreen {
+                    if col.fills_view() {
                         if target_snap.view_pos + self.view_size.w < col_x + col_w {
                             break;
                         }
@@ -3293,7 +3601,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
                     let col_x = self.column_x(col_idx);
                     let col_w = col.width();
 
-                    if col.is_fullscreen {
+                    if col.fills_view() {
                         if col_x < target_snap.view_pos {
                             break;
                         }
@@ -3367,7 +3675,7 @@ impl<W: LayoutElement> ScrollingSpace<W> {
             .find(|(_, col)| col.contains(&window))
             .unwrap();
 
-        if col.is_fullscreen {
+        if col.fills_view() {
             return false;
         }
 
@@ -3479,7 +3787,13 @@ impl<W: LayoutElement> ScrollingSpace<W> {
         self.interactive_resize = None;
     }
 
-    pub fn refresh(&mut self, is_active: bool, is_focused: bool) {
+    pub fn refresh(&mut self, is_active: bool, is_focused: bool, is_visible: bool) {
+        let view_pos = self.view_pos();
+        let view_end = view_pos + self.view_size.w;
+        let is_column_visible: Vec<_> = zip(&self.data, self.column_xs(self.data.iter().copied()))
+            .map(|(data, col_x)| is_visible && view_pos < col_x + data.width && col_x < view_end)
+            .collect();
+
         for (col_idx, col) in self.columns.iter_mut().enumerate() {
             let mut col_resize_data = None;
             if let Some(resize) = &self.interactive_resize {
@@ -3534,6 +3848,11 @@ impl<W: LayoutElement> ScrollingSpace<W> {
                 }
                 win.set_activated(active);
 
+                // Hidden tabs are covered by the active one.
+                let is_tile_visible =
+                    is_column_visible[col_idx] && (active_in_column || !is_tabbed);
+                win.set_suspended(!is_tile_visible);
+
                 win.set_interactive_resize(col_resize_data);
 
                 let border_config = win.rules().border.resolve_against(self.options.border);
@@ -3692,6 +4011,10 @@ impl ViewOffset {
         matches!(&self, ViewOffset::Gesture(gesture) if gesture.dnd_last_event_time.is_some())
     }
 
+    pub fn is_wheel_scroll(&self) -> bool {
+        matches!(&self, ViewOffset::Gesture(gesture) if gesture.is_wheel)
+    }
+
     pub fn is_animation_ongoing(&self) -> bool {
         match self {
             ViewOffset::Static(_) => false,
@@ -3804,7 +4127,9 @@ impl<W: LayoutElement> Column<W> {
             preset_width_idx: None,
             is_full_width,
             is_fullscreen: false,
+            is_column_fullscreen: false,
             display_mode,
+            saved_display_sizes: Vec::new(),
             tab_indicator: TabIndicator::new(options.tab_indicator),
             move_animation: None,
             view_size,
@@ -3852,11 +4177,13 @@ impl<W: LayoutElement> Column<W> {
         // If preset widths changed, clear our stored preset index.
         if self.options.preset_column_widths != options.preset_column_widths {
             self.preset_width_idx = None;
+            self.saved_display_sizes.clear();
         }
 
         // If preset heights changed, make our heights non-preset.
         if self.options.preset_window_heights != options.preset_window_heights {
             self.convert_heights_to_auto();
+            self.saved_display_sizes.clear();
             update_sizes = true;
         }
 
@@ -3880,6 +4207,16 @@ impl<W: LayoutElement> Column<W> {
         }
 
         self.tab_indicator.update_config(options.tab_indicator);
+
+        // Animate the tiles to their new position when the working area moves vertically, for
+        // example when a top bar appears or disappears.
+        let working_area_dy = self.working_area.loc.y - working_area.loc.y;
+        if working_area_dy != 0. && !self.fills_view() {
+            for tile in &mut self.tiles {
+                tile.animate_move_y_from(working_area_dy);
+            }
+        }
+
         self.view_size = view_size;
         self.working_area = working_area;
         self.scale = scale;
@@ -3941,7 +4278,7 @@ impl<W: LayoutElement> Column<W> {
         // you don't want that to happen in fullscreen. Also, laying things out correctly when the
         // tab indicator is within the column and the column goes fullscreen, would require too
         // many changes to the code for too little benefit (it's mostly invisible anyway).
-        let enabled = self.display_mode == ColumnDisplay::Tabbed && !self.is_fullscreen;
+        let enabled = self.display_mode == ColumnDisplay::Tabbed && !self.fills_view();
 
         self.tab_indicator.update_render_elements(
             enabled,
@@ -3976,6 +4313,12 @@ impl<W: LayoutElement> Column<W> {
         from_x_offset: f64,
         config: niri_config::Animation,
     ) {
+        // The column moves as a whole, so follow the window rules of its active window.
+        let config = self
+            .tiles
+            .get(self.active_tile_idx)
+            .and_then(|tile| tile.window().rules().window_movement_animation)
+            .unwrap_or(config);
         let current_offset = self.move_animation.as_ref().map_or(0., Animation::value);
 
         self.move_animation = Some(Animation::new(
@@ -4029,6 +4372,7 @@ impl<W: LayoutElement> Column<W> {
         if self.display_mode != ColumnDisplay::Tabbed {
             self.is_fullscreen = false;
         }
+        self.is_column_fullscreen = false;
 
         self.data
             .insert(idx, TileData::new(&tile, WindowHeight::auto_1()));
@@ -4387,11 +4731,35 @@ impl<W: LayoutElement> Column<W> {
             assert_eq!(auto_tiles_left, 0);
         }
 
-        for (tile_idx, (tile, h)) in zip(&mut self.tiles, heights).enumerate() {
+        let heights = heights.into_iter().map(|h| {
             let WindowHeight::Fixed(height) = h else {
                 unreachable!()
             };
+            height
+        });
+        let mut heights: Vec<_> = heights.collect();
+
+        // A fullscreen column stacks its tiles over the whole view without gaps, scaling their
+        // heights proportionally.
+        let width = if self.is_column_fullscreen {
+            let total_height = if is_tabbed {
+                heights[0]
+            } else {
+                heights.iter().sum()
+            };
+            let factor = self.view_size.h / f64::max(total_height, 1.);
+
+            for (height, tile) in zip(&mut heights, &self.tiles) {
+                let window_height = tile.window_height_for_tile_height(*height * factor);
+                *height = tile.tile_height_for_window_height(window_height.round().max(1.));
+            }
+
+            self.view_size.w
+        } else {
+            width
+        };
 
+        for (tile_idx, (tile, height)) in zip(&mut self.tiles, heights).enumerate() {
             let size = Size::from((width, height));
 
             // In tabbed mode, only the visible window participates in the transaction.
@@ -4415,7 +4783,7 @@ impl<W: LayoutElement> Column<W> {
             .map(NotNan::into_inner)
             .unwrap();
 
-        if self.display_mode == ColumnDisplay::Tabbed && !self.is_fullscreen {
+        if self.display_mode == ColumnDisplay::Tabbed && !self.fills_view() {
             let extra_size = self.tab_indicator.extra_size(self.tiles.len(), self.scale);
             tiles_width += extra_size.w;
         }
@@ -4746,12 +5114,27 @@ impl<W: LayoutElement> Column<W> {
 
         if is_fullscreen {
             assert!(self.tiles.len() == 1 || self.display_mode == ColumnDisplay::Tabbed);
+            self.is_column_fullscreen = false;
         }
 
         self.is_fullscreen = is_fullscreen;
         self.update_tile_sizes(false);
     }
 
+    fn set_column_fullscreen(&mut self, is_column_fullscreen: bool) {
+        if self.is_column_fullscreen == is_column_fullscreen {
+            return;
+        }
+
+        self.is_column_fullscreen = is_column_fullscreen;
+        self.update_tile_sizes(true);
+    }
+
+    /// Returns whether the column takes up the whole view.
+    fn fills_view(&self) -> bool {
+        self.is_fullscreen || self.is_column_fullscreen
+    }
+
     fn set_column_display(&mut self, display: ColumnDisplay) {
         if self.display_mode == display {
             return;
@@ -4764,6 +5147,7 @@ impl<W: LayoutElement> Column<W> {
         //
         // Doing it this way to avoid storing all tile positions in a vector. If more display modes
         // are added it might be simpler to just collect everything into a smallvec.
+        let prev_display = self.display_mode;
         let prev_origin = self.tiles_origin();
         self.display_mode = display;
         let new_origin = self.tiles_origin();
@@ -4807,13 +5191,56 @@ impl<W: LayoutElement> Column<W> {
 
         // Now switch the display mode for real.
         self.display_mode = display;
+        self.swap_display_mode_sizes(prev_display, display);
         self.update_tile_sizes(true);
     }
 
+    /// Saves the sizes of the `from` display mode and restores the ones of the `to` mode.
+    fn swap_display_mode_sizes(&mut self, from: ColumnDisplay, to: ColumnDisplay) {
+        self.saved_display_sizes
+            .retain(|sizes| sizes.display != from);
+        self.saved_display_sizes.push(DisplayModeSizes {
+            display: from,
+            width: self.width,
+            preset_width_idx: self.preset_width_idx,
+            heights: zip(&self.tiles, &self.data)
+                .map(|(tile, data)| (tile.window().id().clone(), data.height))
+                .collect(),
+        });
+
+        let Some(idx) = self
+            .saved_display_sizes
+            .iter()
+            .position(|sizes| sizes.display == to)
+        else {
+            return;
+        };
+        let sizes = self.saved_display_sizes.remove(idx);
+
+        self.width = sizes.width;
+        self.preset_width_idx = sizes.preset_width_idx;
+
+        for (tile, data) in zip(&self.tiles, &mut self.data) {
+            let id = tile.window().id();
+            data.height = sizes
+                .heights
+                .iter()
+                .find(|(saved_id, _)| saved_id == id)
+                .map_or(WindowHeight::auto_1(), |(_, height)| *height);
+        }
+
+        // Windows may have gone away since, leaving a single window with an auto weight.
+        if self.tiles.len() == 1 {
+            if let WindowHeight::Auto { .. } = self.data[0].height {
+                self.data[0].height = WindowHeight::auto_1();
+            }
+        }
+    }
+
     fn tiles_origin(&self) -> Point<f64, Logical> {
         let mut origin = Point::from((0., 0.));
 
-        if self.is_fullscreen {
+        if self.fills_view() {
             return origin;
         }
 
@@ -4838,7 +5265,11 @@ impl<W: LayoutElement> Column<W> {
         // Column should somehow know when it is being centered due to being the single column on
         // the workspace or some other reason.
         let center = self.options.center_focused_column == CenterFocusedColumn::Always;
-        let gaps = self.options.gaps;
+        let gaps = if self.is_column_fullscreen {
+            0.
+        } else {
+            self.options.gaps
+        };
         let tabbed = self.display_mode == ColumnDisplay::Tabbed;
 
         // Does not include extra size from the tab indicator.
@@ -5052,7 +5483,7 @@ impl<W: LayoutElement> Column<W> {
                 tile.tile_height_for_window_height(f64::from(requested_size.h));
             let min_tile_height = f64::max(1., tile.min_size_nonfullscreen().h);
 
-            if !self.is_fullscreen
+            if !self.fills_view()
                 && self.scale.round() == self.scale
                 && working_size.h.round() == working_size.h
                 && gaps.round() == gaps
@@ -5072,6 +5503,7 @@ impl<W: LayoutElement> Column<W> {
         }
 
         if !is_tabbed
+            && !self.is_column_fullscreen
             && tile_count > 1
             && self.scale.round() == self.scale
             && working_size.h.round() == working_size.h
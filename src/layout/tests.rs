@@ -1,7 +1,7 @@
 use std::cell::Cell;
 
 use niri_config::{
-    FloatOrInt, OutputName, TabIndicatorLength, TabIndicatorPosition, WorkspaceName,
+    FloatOrInt, OutputName, SmartGaps, TabIndicatorLength, TabIndicatorPosition, WorkspaceName,
     WorkspaceReference,
 };
 use proptest::prelude::*;
@@ -28,6 +28,7 @@ struct TestWindowInner {
     max_size: Size<i32, Logical>,
     pending_fullscreen: Cell<bool>,
     pending_activated: Cell<bool>,
+    pending_suspended: Cell<bool>,
     is_fullscreen: Cell<bool>,
     is_windowed_fullscreen: Cell<bool>,
     is_pending_windowed_fullscreen: Cell<bool>,
@@ -73,6 +74,7 @@ impl TestWindow {
             max_size: params.min_max_size.1,
             pending_fullscreen: Cell::new(false),
             pending_activated: Cell::new(false),
+            pending_suspended: Cell::new(false),
             is_fullscreen: Cell::new(false),
             is_windowed_fullscreen: Cell::new(false),
             is_pending_windowed_fullscreen: Cell::new(false),
@@ -189,9 +191,17 @@ impl LayoutElement for TestWindow {
         self.0.pending_activated.set(active);
     }
 
+    fn set_suspended(&mut self, suspended: bool) {
+        self.0.pending_suspended.set(suspended);
+    }
+
     fn set_bounds(&self, _bounds: Size<i32, Logical>) {}
 
-    fn is_ignoring_opacity_window_rule(&self) -> bool {
+    fn opacity(&self) -> f32 {
+        1.
+    }
+
+    fn is_always_on_top(&self) -> bool {
         false
     }
 
@@ -265,6 +275,14 @@ impl LayoutElement for TestWindow {
     fn is_urgent(&self) -> bool {
         false
     }
+
+    fn is_unresponsive(&self) -> bool {
+        false
+    }
+
+    fn is_kill_pending(&self) -> bool {
+        false
+    }
 }
 
 fn arbitrary_bbox() -> impl Strategy<Value = Rectangle<i32, Logical>> {
@@ -417,6 +435,7 @@ enum Op {
     FocusColumnRightOrFirst,
     FocusColumnLeftOrLast,
     FocusColumn(#[proptest(strategy = "1..=5usize")] usize),
+    FocusColumnPrevious,
     FocusWindowOrMonitorUp(#[proptest(strategy = "1..=2u8")] u8),
     FocusWindowOrMonitorDown(#[proptest(strategy = "1..=2u8")] u8),
     FocusColumnOrMonitorLeft(#[proptest(strategy = "1..=2u8")] u8),
@@ -441,7 +460,12 @@ enum Op {
     MoveColumnToLast,
     MoveColumnLeftOrToMonitorLeft(#[proptest(strategy = "1..=2u8")] u8),
     MoveColumnRightOrToMonitorRight(#[proptest(strategy = "1..=2u8")] u8),
-    MoveColumnToIndex(#[proptest(strategy = "1..=5usize")] usize),
+    MoveColumnToIndex {
+        #[proptest(strategy = "proptest::option::of(1..=5usize)")]
+        id: Option<usize>,
+        #[proptest(strategy = "1..=5usize")]
+        index: usize,
+    },
     MoveWindowDown,
     MoveWindowUp,
     MoveWindowDownOrToWorkspaceDown,
@@ -465,6 +489,8 @@ enum Op {
         id: Option<usize>,
     },
     CenterVisibleColumns,
+    MovePipToNextCorner,
+    SwitchPresetPipWidth,
     FocusWorkspaceDown,
     FocusWorkspaceUp,
     FocusWorkspace(#[proptest(strategy = "0..=4usize")] usize),
@@ -530,6 +556,8 @@ enum Op {
         id: Option<usize>,
     },
     MaximizeColumn,
+    FullscreenColumn,
+    ToggleReadingMode,
     SetColumnWidth(#[proptest(strategy = "arbitrary_size_change()")] SizeChange),
     SetWindowWidth {
         #[proptest(strategy = "proptest::option::of(1..=5usize)")]
@@ -600,6 +628,18 @@ enum Op {
     ViewOffsetGestureEnd {
         is_touchpad: Option<bool>,
     },
+    WheelScrollGestureBegin {
+        #[proptest(strategy = "1..=5usize")]
+        output_idx: usize,
+        #[proptest(strategy = "0..=4usize")]
+        workspace_idx: usize,
+    },
+    WheelScrollGestureUpdate {
+        #[proptest(strategy = "arbitrary_view_offset_gesture_delta()")]
+        delta: f64,
+        timestamp: Duration,
+    },
+    WheelScrollGestureEnd,
     WorkspaceSwitchGestureBegin {
         #[proptest(strategy = "1..=5usize")]
         output_idx: usize,
@@ -769,6 +809,7 @@ impl Op {
                 layout.ensure_named_workspace(&WorkspaceConfig {
                     name: WorkspaceName(format!("ws{ws_name}")),
                     open_on_output: output_name.map(|name| format!("output{name}")),
+                    wallpaper: None,
                 });
             }
             Op::UnnameWorkspace { ws_name } => {
@@ -980,6 +1021,7 @@ impl Op {
             Op::FocusColumnRightOrFirst => layout.focus_column_right_or_first(),
             Op::FocusColumnLeftOrLast => layout.focus_column_left_or_last(),
             Op::FocusColumn(index) => layout.focus_column(index),
+            Op::FocusColumnPrevious => layout.focus_column_previous(),
             Op::FocusWindowOrMonitorUp(id) => {
                 let name = format!("output{id}");
                 let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
@@ -1046,7 +1088,10 @@ impl Op {
 
                 layout.move_column_right_or_to_output(&output);
             }
-            Op::MoveColumnToIndex(index) => layout.move_column_to_index(index),
+            Op::MoveColumnToIndex { id, index } => {
+                let id = id.filter(|id| layout.has_window(id));
+                layout.move_column_to_index(id.as_ref(), index);
+            }
             Op::MoveWindowDown => layout.move_down(),
             Op::MoveWindowUp => layout.move_up(),
             Op::MoveWindowDownOrToWorkspaceDown => layout.move_down_or_to_workspace_down(),
@@ -1070,6 +1115,8 @@ impl Op {
                 layout.center_window(id.as_ref());
             }
             Op::CenterVisibleColumns => layout.center_visible_columns(),
+            Op::MovePipToNextCorner => layout.move_pip_to_next_corner(),
+            Op::SwitchPresetPipWidth => layout.switch_preset_pip_width(),
             Op::FocusWorkspaceDown => layout.switch_workspace_down(),
             Op::FocusWorkspaceUp => layout.switch_workspace_up(),
             Op::FocusWorkspace(idx) => layout.switch_workspace(idx),
@@ -1205,6 +1252,8 @@ impl Op {
                 layout.toggle_window_height(id.as_ref());
             }
             Op::MaximizeColumn => layout.toggle_full_width(),
+            Op::FullscreenColumn => layout.toggle_column_fullscreen(),
+            Op::ToggleReadingMode => layout.toggle_reading_mode(),
             Op::SetColumnWidth(change) => layout.set_column_width(change),
             Op::SetWindowWidth { id, change } => {
                 let id = id.filter(|id| layout.has_window(id));
@@ -1390,6 +1439,23 @@ impl Op {
             Op::ViewOffsetGestureEnd { is_touchpad } => {
                 layout.view_offset_gesture_end(is_touchpad);
             }
+            Op::WheelScrollGestureBegin {
+                output_idx: id,
+                workspace_idx,
+            } => {
+                let name = format!("output{id}");
+                let Some(output) = layout.outputs().find(|o| o.name() == name).cloned() else {
+                    return;
+                };
+
+                layout.wheel_scroll_gesture_begin(&output, workspace_idx);
+            }
+            Op::WheelScrollGestureUpdate { delta, timestamp } => {
+                layout.wheel_scroll_gesture_update(delta, timestamp);
+            }
+            Op::WheelScrollGestureEnd => {
+                layout.wheel_scroll_gesture_end();
+            }
             Op::WorkspaceSwitchGestureBegin {
                 output_idx: id,
                 is_touchpad,
@@ -1947,6 +2013,69 @@ fn workspaces_update_original_output_on_moving_to_same_output() {
     assert_eq!(mon.output_name(), "output2");
 }
 
+#[test]
+fn workspaces_restored_when_output_reconnects_to_other_connector() {
+    fn make_output(connector: &str) -> Output {
+        let output = Output::new(
+            connector.to_owned(),
+            PhysicalProperties {
+                size: Size::from((1280, 720)),
+                subpixel: Subpixel::Unknown,
+                make: String::from("Some Company"),
+                model: String::from("Some Monitor"),
+            },
+        );
+        output.change_current_state(
+            Some(Mode {
+                size: Size::from((1280, 720)),
+                refresh: 60000,
+            }),
+            None,
+            None,
+            None,
+        );
+        output.user_data().insert_if_missing(|| OutputName {
+            connector: connector.to_owned(),
+            make: Some(String::from("Some Company")),
+            model: Some(String::from("Some Monitor")),
+            serial: Some(String::from("1234")),
+        });
+        output
+    }
+
+    let mut layout = check_ops(&[Op::AddOutput(1)]);
+
+    let output = make_output("DP-1");
+    layout.add_output(output.clone());
+    layout.focus_output(&output);
+
+    let ops = [
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::FocusWorkspaceDown,
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+    ];
+    for op in ops {
+        op.apply(&mut layout);
+        layout.verify_invariants();
+    }
+
+    layout.remove_output(&output);
+    layout.verify_invariants();
+
+    // The same monitor comes back on a different connector.
+    let output = make_output("DP-2");
+    layout.add_output(output.clone());
+    layout.verify_invariants();
+
+    let mon = layout.monitor_for_output(&output).unwrap();
+    assert!(mon.workspaces.iter().any(|ws| ws.has_window(&1)));
+    assert!(mon.active_workspace_ref().has_window(&2));
+}
+
 #[test]
 fn workspaces_update_original_output_on_moving_to_same_monitor() {
     let ops = [
@@ -2146,6 +2275,67 @@ fn unfullscreen_window_in_column() {
     check_ops(&ops);
 }
 
+#[test]
+fn focus_column_previous_bounces_between_columns() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(3),
+        },
+        Op::FocusColumn(1),
+        Op::FocusColumnPrevious,
+    ];
+    let mut layout = check_ops(&ops);
+    assert_eq!(layout.focus().map(|win| *win.id()), Some(3));
+
+    // The previous column follows its window as columns move around.
+    let ops = [
+        Op::FocusColumnPrevious,
+        Op::MoveColumnToIndex {
+            id: Some(3),
+            index: 1,
+        },
+        Op::FocusColumnPrevious,
+    ];
+    for op in ops {
+        op.apply(&mut layout);
+    }
+    layout.verify_invariants();
+    assert_eq!(layout.focus().map(|win| *win.id()), Some(3));
+}
+
+#[test]
+fn fullscreen_column() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::ConsumeOrExpelWindowLeft { id: None },
+        Op::SetWindowHeight {
+            id: None,
+            change: SizeChange::SetFixed(200),
+        },
+        Op::FullscreenColumn,
+        Op::Communicate(1),
+        Op::Communicate(2),
+        Op::FullscreenWindow(2),
+        Op::FullscreenColumn,
+        Op::FullscreenColumn,
+    ];
+
+    check_ops(&ops);
+}
+
 #[test]
 fn open_right_of_on_different_workspace() {
     let ops = [
@@ -2768,6 +2958,38 @@ fn move_window_to_different_output() {
     check_ops_with_options(options, &ops);
 }
 
+#[test]
+fn move_inactive_column_to_index_keeps_focus() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(3),
+        },
+        Op::MoveColumnToIndex {
+            id: Some(1),
+            index: 3,
+        },
+    ];
+    let layout = check_ops(&ops);
+    assert_eq!(layout.focus().map(|win| *win.id()), Some(3));
+
+    let workspaces = layout.ipc_layout(&|win| *win.id() as u64);
+    let ws = workspaces.iter().find(|ws| ws.is_focused).unwrap();
+    let ids: Vec<_> = ws
+        .columns
+        .iter()
+        .map(|col| col.tiles[0].window_id)
+        .collect();
+    assert_eq!(ids, [2, 3, 1]);
+    assert_eq!(ws.active_column_idx, Some(1));
+}
+
 #[test]
 fn close_window_empty_ws_above_first() {
     let ops = [
@@ -2818,6 +3040,173 @@ fn switch_ewaf_on() {
     layout.verify_invariants();
 }
 
+#[test]
+fn smart_gaps() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::Refresh { is_active: true },
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::Refresh { is_active: true },
+        Op::ConsumeOrExpelWindowLeft { id: None },
+        Op::Refresh { is_active: true },
+        Op::CloseWindow(2),
+        Op::Refresh { is_active: true },
+    ];
+
+    let options = Options {
+        smart_gaps: Some(SmartGaps {
+            remove_borders: true,
+        }),
+        ..Default::default()
+    };
+    check_ops_with_options(options, &ops);
+}
+
+#[test]
+fn hide_border_and_focus_ring_when_single_window() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::Refresh { is_active: true },
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::Refresh { is_active: true },
+        Op::ToggleWindowFloating { id: Some(2) },
+        Op::Refresh { is_active: true },
+        Op::CloseWindow(1),
+        Op::Refresh { is_active: true },
+    ];
+
+    let options = Options {
+        focus_ring: niri_config::FocusRing {
+            hide_when_single_window: true,
+            ..Default::default()
+        },
+        border: niri_config::Border {
+            off: false,
+            hide_when_single_window: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    check_ops_with_options(options, &ops);
+}
+
+#[test]
+fn reading_mode() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::ToggleReadingMode,
+        Op::Refresh { is_active: true },
+    ];
+
+    let options = Options {
+        reading_mode_max_width: 600.,
+        ..Default::default()
+    };
+    let mut layout = check_ops_with_options(options, &ops);
+
+    let ws = layout.active_workspace().unwrap();
+    assert!(ws.is_reading_mode());
+    assert_eq!(ws.options.struts.left.0, 340.);
+    assert_eq!(ws.options.struts.right.0, 340.);
+    assert_eq!(
+        ws.options.center_focused_column,
+        CenterFocusedColumn::Always
+    );
+
+    // The reading mode only applies to a single column.
+    let ops = [
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::Refresh { is_active: true },
+    ];
+    for op in ops {
+        op.apply(&mut layout);
+    }
+    layout.verify_invariants();
+
+    let ws = layout.active_workspace().unwrap();
+    assert_eq!(ws.options.struts.left.0, 0.);
+
+    Op::ToggleReadingMode.apply(&mut layout);
+    assert!(!layout.active_workspace().unwrap().is_reading_mode());
+}
+
+#[test]
+fn wheel_scroll_gesture_bounces_off_strip_end() {
+    let ops = [
+        Op::AddOutput(1),
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::FocusColumnFirst,
+        Op::Communicate(1),
+        Op::Communicate(2),
+        Op::AdvanceAnimations { msec_delta: 1000 },
+    ];
+    let mut layout = check_ops(&ops);
+
+    let view_offset = |layout: &Layout<TestWindow>| {
+        let ws = layout.active_workspace().unwrap();
+        ws.scrolling().view_offset().current()
+    };
+    let resting_offset = view_offset(&layout);
+
+    // Scrolling far past the left end only overscrolls by a bit.
+    let ops = [
+        Op::WheelScrollGestureBegin {
+            output_idx: 1,
+            workspace_idx: 0,
+        },
+        Op::WheelScrollGestureUpdate {
+            delta: -10000.,
+            timestamp: Duration::from_millis(10),
+        },
+    ];
+    for op in ops {
+        op.apply(&mut layout);
+    }
+    layout.verify_invariants();
+
+    let overscrolled_offset = view_offset(&layout);
+    assert!(overscrolled_offset < resting_offset);
+    assert!(overscrolled_offset > resting_offset - 1280. * 0.2);
+
+    // Ending the gesture springs the view back.
+    let ops = [
+        Op::WheelScrollGestureEnd,
+        Op::AdvanceAnimations { msec_delta: 1000 },
+    ];
+    for op in ops {
+        op.apply(&mut layout);
+    }
+    layout.verify_invariants();
+
+    assert!(!layout
+        .active_workspace()
+        .unwrap()
+        .scrolling()
+        .view_offset()
+        .is_gesture());
+    assert_eq!(view_offset(&layout), resting_offset);
+}
+
 #[test]
 fn switch_ewaf_off() {
     let ops = [
@@ -2931,6 +3320,52 @@ fn windows_on_other_workspaces_remain_activated() {
     assert!(win.0.pending_activated.get());
 }
 
+#[test]
+fn windows_out_of_view_are_suspended() {
+    let ops = [
+        Op::AddOutput(0),
+        Op::AddWindow {
+            params: TestWindowParams::new(0),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(2),
+        },
+        Op::ConsumeOrExpelWindowLeft { id: None },
+        Op::SetColumnDisplay(ColumnDisplay::Tabbed),
+        Op::SetColumnWidth(SizeChange::SetFixed(1280)),
+        Op::FocusColumnLeft,
+        Op::SetColumnWidth(SizeChange::SetFixed(1280)),
+        Op::AdvanceAnimations { msec_delta: 1000 },
+        Op::Refresh { is_active: true },
+    ];
+
+    let mut layout = check_ops(&ops);
+    let is_suspended = |layout: &Layout<TestWindow>, id| {
+        let (_, win) = layout.windows().find(|(_, win)| win.0.id == id).unwrap();
+        win.0.pending_suspended.get()
+    };
+    // Window 2 is the active tab and window 1 is hidden behind it, both out of view.
+    assert!(!is_suspended(&layout, 0));
+    assert!(is_suspended(&layout, 1));
+    assert!(is_suspended(&layout, 2));
+
+    Op::FocusColumnRight.apply(&mut layout);
+    Op::AdvanceAnimations { msec_delta: 1000 }.apply(&mut layout);
+    Op::Refresh { is_active: true }.apply(&mut layout);
+    assert!(is_suspended(&layout, 0));
+    assert!(is_suspended(&layout, 1));
+    assert!(!is_suspended(&layout, 2));
+
+    // Windows on workspaces out of view are suspended too.
+    Op::FocusWorkspaceDown.apply(&mut layout);
+    Op::AdvanceAnimations { msec_delta: 1000 }.apply(&mut layout);
+    Op::Refresh { is_active: true }.apply(&mut layout);
+    assert!(is_suspended(&layout, 2));
+}
+
 #[test]
 fn stacking_add_parent_brings_up_child() {
     let ops = [
@@ -3249,6 +3684,36 @@ fn disable_tabbed_mode_in_fullscreen() {
     check_ops(&ops);
 }
 
+#[test]
+fn column_sizes_remembered_per_display_mode() {
+    let ops = [
+        Op::AddOutput(0),
+        Op::AddWindow {
+            params: TestWindowParams::new(0),
+        },
+        Op::AddWindow {
+            params: TestWindowParams::new(1),
+        },
+        Op::ConsumeOrExpelWindowLeft { id: None },
+        Op::SetColumnWidth(SizeChange::SetFixed(500)),
+        Op::SetWindowHeight {
+            id: Some(0),
+            change: SizeChange::SetFixed(200),
+        },
+        Op::SetColumnDisplay(ColumnDisplay::Tabbed),
+        Op::SetColumnWidth(SizeChange::SetFixed(800)),
+        Op::SetColumnDisplay(ColumnDisplay::Normal),
+    ];
+
+    let mut layout = check_ops(&ops);
+    let win = layout.windows().find(|(_, win)| win.0.id == 0).unwrap().1;
+    assert_eq!(win.requested_size().unwrap(), Size::from((500, 200)));
+
+    layout.set_column_display(ColumnDisplay::Tabbed);
+    let win = layout.windows().next().unwrap().1;
+    assert_eq!(win.requested_size().unwrap().w, 800);
+}
+
 #[test]
 fn unfullscreen_with_large_border() {
     let ops = [
@@ -3685,10 +4150,12 @@ prop_compose! {
         center_focused_column in arbitrary_center_focused_column(),
         always_center_single_column in any::<bool>(),
         empty_workspace_above_first in any::<bool>(),
+        smart_gaps in prop::option::of(any::<bool>()),
     ) -> Options {
         Options {
             gaps,
             struts,
+            smart_gaps: smart_gaps.map(|remove_borders| SmartGaps { remove_borders }),
             center_focused_column,
             always_center_single_column,
             empty_workspace_above_first,
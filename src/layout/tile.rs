@@ -2,6 +2,7 @@ use core::f64;
 use std::rc::Rc;
 
 use niri_config::{Color, CornerRadius, GradientInterpolation};
+use niri_ipc::TileLayout;
 use smithay::backend::renderer::element::{Element, Kind};
 use smithay::backend::renderer::gles::GlesRenderer;
 use smithay::utils::{Logical, Point, Rectangle, Scale, Size};
@@ -29,6 +30,9 @@ use crate::render_helpers::RenderTarget;
 use crate::utils::transaction::Transaction;
 use crate::utils::{baba_is_float_offset, round_logical_in_physical};
 
+/// Premultiplied color of the tint over windows of unresponsive clients.
+const UNRESPONSIVE_TINT_COLOR: [f32; 4] = [0., 0., 0., 0.5];
+
 /// Toplevel window with decorations.
 #[derive(Debug)]
 pub struct Tile<W: LayoutElement> {
@@ -56,6 +60,12 @@ pub struct Tile<W: LayoutElement> {
     /// The black backdrop for fullscreen windows.
     fullscreen_backdrop: SolidColorBuffer,
 
+    /// The fill behind the window, drawn when set through the window rules.
+    background: SolidColorBuffer,
+
+    /// The tint drawn over the window when its client is unresponsive.
+    unresponsive_tint: SolidColorBuffer,
+
     /// Whether the tile should float upon unfullscreening.
     pub(super) unfullscreen_to_floating: bool,
 
@@ -188,6 +198,8 @@ impl<W: LayoutElement> Tile<W> {
             top_bar,
             is_fullscreen,
             fullscreen_backdrop: SolidColorBuffer::new(view_size, [0., 0., 0., 1.]),
+            background: SolidColorBuffer::new(window_size, [0., 0., 0., 0.]),
+            unresponsive_tint: SolidColorBuffer::new(window_size, UNRESPONSIVE_TINT_COLOR),
             unfullscreen_to_floating: false,
             floating_window_size: None,
             floating_pos: None,
@@ -275,13 +287,12 @@ impl<W: LayoutElement> Tile<W> {
             let change = self.window.size().to_f64().to_point() - size_from.to_point();
             let change = f64::max(change.x.abs(), change.y.abs());
             if change > RESIZE_ANIMATION_THRESHOLD {
-                let anim = Animation::new(
-                    self.clock.clone(),
-                    0.,
-                    1.,
-                    0.,
-                    self.options.animations.window_resize.anim,
-                );
+                let config = self
+                    .window
+                    .rules()
+                    .window_resize_animation
+                    .unwrap_or(self.options.animations.window_resize.anim);
+                let anim = Animation::new(self.clock.clone(), 0., 1., 0., config);
                 self.resize_animation = Some(ResizeAnimation {
                     anim,
                     size_from,
@@ -423,12 +434,35 @@ impl<W: LayoutElement> Tile<W> {
             self.scale,
             1.,
         );
+
+        let urgent_color = self.options.top_bar.urgent_color;
+        self.top_bar.set_urgent_color(self.window.is_urgent().then_some(urgent_color));
+        self.top_bar.set_kill_pending(self.window.is_kill_pending());
+
+        self.unresponsive_tint.resize(self.window_size());
+
+        if let Some(color) = rules.background_color {
+            self.background.update(self.animated_window_size(), color.to_array_premul());
+        }
     }
 
     pub fn scale(&self) -> f64 {
         self.scale
     }
 
+    /// Returns the IPC layout info for this tile rendered at `pos`.
+    pub fn ipc_layout(&self, pos: Point<f64, Logical>, window_id: u64) -> TileLayout {
+        let tile_size = self.tile_size();
+        let window_size = self.window_size();
+        TileLayout {
+            window_id,
+            pos: (pos.x, pos.y),
+            tile_size: (tile_size.w, tile_size.h),
+            window_size: (window_size.w, window_size.h),
+            is_fullscreen: self.is_fullscreen,
+        }
+    }
+
     pub fn render_offset(&self) -> Point<f64, Logical> {
         let mut offset = Point::from((0., 0.));
 
@@ -468,6 +502,7 @@ impl<W: LayoutElement> Tile<W> {
     }
 
     pub fn animate_move_x_from_with_config(&mut self, from: f64, config: niri_config::Animation) {
+        let config = self.window.rules().window_movement_animation.unwrap_or(config);
         let current_offset = self.render_offset().x;
 
         // Preserve the previous config if ongoing.
@@ -487,6 +522,7 @@ impl<W: LayoutElement> Tile<W> {
     }
 
     pub fn animate_move_y_from_with_config(&mut self, from: f64, config: niri_config::Animation) {
+        let config = self.window.rules().window_movement_animation.unwrap_or(config);
         let current_offset = self.render_offset().y;
 
         // Preserve the previous config if ongoing.
@@ -709,7 +745,11 @@ impl<W: LayoutElement> Tile<W> {
         // First check if the point is in the top bar
         if !self.is_fullscreen && point.y < super::top_bar::TOP_BAR_HEIGHT {
             // If the point is in the top bar, check if it's in one of the buttons
-            if self.top_bar.hit_test(point).is_some() {
+            if self
+                .top_bar
+                .hit_test(point, self.options.hit_target_padding)
+                .is_some()
+            {
                 return false; // Not in input region, will be handled by hit_top_bar
             }
         }
@@ -730,7 +770,8 @@ impl<W: LayoutElement> Tile<W> {
             return None;
         }
         
-        self.top_bar.hit_test(point)
+        self.top_bar
+            .hit_test(point, self.options.hit_target_padding)
     }
 
     pub fn hit(&self, point: Point<f64, Logical>) -> Option<HitType> {
@@ -749,6 +790,7 @@ impl<W: LayoutElement> Tile<W> {
         } else if self.is_in_activation_region(point) {
             Some(HitType::Activate {
                 is_tab_indicator: false,
+                is_top_bar: !self.is_fullscreen && point.y < super::top_bar::TOP_BAR_HEIGHT,
             })
         } else {
             None
@@ -900,10 +942,10 @@ impl<W: LayoutElement> Tile<W> {
 
         let scale = Scale::from(self.scale);
 
-        let win_alpha = if self.is_fullscreen || self.window.is_ignoring_opacity_window_rule() {
+        let win_alpha = if self.is_fullscreen {
             1.
         } else {
-            self.window.rules().opacity.unwrap_or(1.).clamp(0., 1.)
+            self.window.opacity()
         };
 
         // This is here rather than in render_offset() because render_offset() is currently assumed
@@ -923,7 +965,14 @@ impl<W: LayoutElement> Tile<W> {
         let area = Rectangle::new(window_render_loc, animated_window_size);
 
         let rules = self.window.rules();
-        let clip_to_geometry = !self.is_fullscreen && rules.clip_to_geometry == Some(true);
+        // Clip forced windowed fullscreen windows to cut off what they draw past their size,
+        // windows with CSD margins to cut off the margins, and windows with a background to keep
+        // them within it.
+        let clip_to_geometry = !self.is_fullscreen
+            && (rules.clip_to_geometry == Some(true)
+                || rules.force_windowed_fullscreen == Some(true)
+                || rules.csd_margins.is_some()
+                || rules.background_color.is_some());
         let radius = rules.geometry_corner_radius.unwrap_or_default();
 
         // If we're resizing, try to render a shader, or a fallback.
@@ -1093,10 +1142,58 @@ impl<W: LayoutElement> Tile<W> {
             .flatten()
             .chain(resize_shader)
             .chain(resize_fallback)
-            .chain(window_popups.into_iter().flatten())
+            .chain(window_popups.into_iter().flatten());
+
+        // Tint the window, but not its popups, while its client doesn't respond to pings.
+        let elem = (self.window.is_unresponsive() && window_surface.is_some()).then(|| {
+            SolidColorRenderElement::from_buffer(
+                &self.unresponsive_tint,
+                window_render_loc,
+                win_alpha,
+                Kind::Unspecified,
+            )
+            .into()
+        });
+        let rv = rv
+            .chain(elem)
             .chain(rounded_corner_damage)
             .chain(window_surface.into_iter().flatten());
 
+        // Fill the window area behind the window, for windows that are transparent or don't cover
+        // their whole size, for example in the middle of a resize.
+        let has_background = !self.is_fullscreen && rules.background_color.is_some();
+        let has_border_shader = BorderRenderElement::has_shader(renderer);
+        let elem = has_background.then(|| {
+            let radius = radius.fit_to(area.size.w as f32, area.size.h as f32);
+            if radius != CornerRadius::default() && has_border_shader {
+                let color = Color::from_color32f(self.background.color());
+                return BorderRenderElement::new(
+                    area.size,
+                    Rectangle::from_size(area.size),
+                    GradientInterpolation::default(),
+                    color,
+                    color,
+                    0.,
+                    Rectangle::from_size(area.size),
+                    0.,
+                    radius,
+                    scale.x as f32,
+                    win_alpha,
+                )
+                .with_location(area.loc)
+                .into();
+            }
+
+            SolidColorRenderElement::from_buffer(
+                &self.background,
+                area.loc,
+                win_alpha,
+                Kind::Unspecified,
+            )
+            .into()
+        });
+        let rv = rv.chain(elem);
+
         let elem = self.is_fullscreen.then(|| {
             SolidColorRenderElement::from_buffer(
                 &self.fullscreen_backdrop,
@@ -53,10 +53,19 @@ pub struct TopBar {
     size: Size<f64, Logical>,
     /// The locations of the buttons
     button_locations: [Point<f64, Logical>; 5],
+    /// Whether each button fits in the top bar
+    button_visible: [bool; 5],
     /// The button colors
     button_colors: [Color; 5],
+    /// The background color to use instead of the default one, for urgent windows
+    urgent_color: Option<Color>,
+    /// Whether the close button is waiting for a click confirming a force kill
+    kill_pending: bool,
 }
 
+/// The close button color while waiting for a force kill confirmation - Yellow
+const KILL_PENDING_COLOR: [f32; 4] = [1.0, 0.85, 0.2, 1.0];
+
 niri_render_elements! {
     TopBarRenderElement => {
         SolidColor = SolidColorRenderElement,
@@ -93,7 +102,37 @@ impl TopBar {
             button_buffers,
             size: Default::default(),
             button_locations: Default::default(),
+            button_visible: [true; 5],
             button_colors,
+            urgent_color: None,
+            kill_pending: false,
+        }
+    }
+
+    /// Set the background color for urgent windows, or `None` for the default one
+    pub fn set_urgent_color(&mut self, color: Option<Color>) {
+        self.urgent_color = color;
+        self.background_buffer.set_color(self.background_color());
+    }
+
+    /// Set whether the close button should ask to confirm a force kill
+    pub fn set_kill_pending(&mut self, kill_pending: bool) {
+        self.kill_pending = kill_pending;
+        self.button_buffers[BUTTON_CLOSE].set_color(self.close_button_color());
+    }
+
+    fn close_button_color(&self) -> [f32; 4] {
+        if self.kill_pending {
+            KILL_PENDING_COLOR
+        } else {
+            [1.0, 0.3, 0.3, 1.0]
+        }
+    }
+
+    fn background_color(&self) -> [f32; 4] {
+        match self.urgent_color {
+            Some(color) => color.to_array_premul(),
+            None => [0.2, 0.2, 0.2, 0.9],
         }
     }
 
@@ -105,7 +144,7 @@ impl TopBar {
         
         // Update the background buffer
         self.background_buffer.resize(size);
-        self.background_buffer.set_color([0.2, 0.2, 0.2, 0.9]);
+        self.background_buffer.set_color(self.background_color());
         
         // Common Y position for all buttons
         let button_y = (TOP_BAR_HEIGHT - BUTTON_SIZE) / 2.0;
@@ -128,6 +167,16 @@ impl TopBar {
         // Preset column width button - to the left of window control buttons
         right_offset -= BUTTON_SIZE;
         self.button_locations[BUTTON_PRESET_WIDTH] = Point::from((right_offset, button_y));
+
+        // In narrow windows, hide the buttons that would overlap, starting from the left
+        let mut min_x = left_offset + BUTTON_SIZE + BUTTON_SPACING;
+        self.button_visible[BUTTON_SCREENSHOT] = min_x <= right_offset;
+        if !self.button_visible[BUTTON_SCREENSHOT] {
+            min_x = left_offset;
+        }
+        for i in [BUTTON_PRESET_WIDTH, BUTTON_CLOSE, BUTTON_MINIMIZE, BUTTON_MAXIMIZE] {
+            self.button_visible[i] = min_x <= self.button_locations[i].x;
+        }
         
         // Resize and color all button buffers
         let button_size = Size::from((BUTTON_SIZE, BUTTON_SIZE));
@@ -145,25 +194,40 @@ impl TopBar {
             self.button_buffers[i].resize(button_size);
             self.button_buffers[i].set_color(button_colors[i]);
         }
+        self.button_buffers[BUTTON_CLOSE].set_color(self.close_button_color());
     }
 
     /// Check if a point is inside one of the buttons
     /// Returns the button index if hit, None otherwise
-    pub fn hit_test(&self, point: Point<f64, Logical>) -> Option<usize> {
+    ///
+    /// The padding enlarges the hit area of every button by that many logical pixels on each side.
+    pub fn hit_test(&self, point: Point<f64, Logical>, padding: f64) -> Option<usize> {
         // First check if we're in the top bar area
         if point.y < 0.0 || point.y > TOP_BAR_HEIGHT {
             return None;
         }
         
-        // Check each button
+        // Check each button; enlarged hit areas can overlap, so prefer the closest button
+        let mut closest = None;
         for (i, loc) in self.button_locations.iter().enumerate() {
-            let button_rect = Rectangle::new(*loc, Size::from((BUTTON_SIZE, BUTTON_SIZE)));
+            if !self.button_visible[i] {
+                continue;
+            }
+
+            let button_rect = Rectangle::new(
+                *loc - Point::from((padding, padding)),
+                Size::from((BUTTON_SIZE + padding * 2., BUTTON_SIZE + padding * 2.)),
+            );
             if button_rect.contains(point) {
-                return Some(i);
+                let center_x = loc.x + BUTTON_SIZE / 2.;
+                let distance = (point.x - center_x).abs();
+                if closest.map_or(true, |(_, d)| distance < d) {
+                    closest = Some((i, distance));
+                }
             }
         }
         
-        None
+        closest.map(|(i, _)| i)
     }
 
     /// Render the top bar and its buttons
@@ -181,7 +245,9 @@ impl TopBar {
         );
         
         // Then render each button
-        let buttons = self.button_buffers.iter().enumerate().map(move |(i, buf)| {
+        let buttons = self.button_buffers.iter().enumerate();
+        let buttons = buttons.filter(move |(i, _)| self.button_visible[*i]);
+        let buttons = buttons.map(move |(i, buf)| {
             let button_loc = location + self.button_locations[i];
             SolidColorRenderElement::from_buffer(
                 buf,
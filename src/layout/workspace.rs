@@ -5,7 +5,7 @@ use std::time::Duration;
 use niri_config::{
     CenterFocusedColumn, CornerRadius, OutputName, PresetSize, Workspace as WorkspaceConfig,
 };
-use niri_ipc::{ColumnDisplay, PositionChange, SizeChange};
+use niri_ipc::{ColumnDisplay, PositionChange, SizeChange, WindowLayout, WorkspaceLayout};
 use smithay::backend::renderer::gles::GlesRenderer;
 use smithay::desktop::{layer_map_for_output, Window};
 use smithay::output::Output;
@@ -96,6 +96,12 @@ pub struct Workspace<W: LayoutElement> {
     /// Configurable properties of the layout with logical sizes adjusted for the current `scale`.
     pub(super) options: Rc<Options>,
 
+    /// What this workspace held when its options were last resolved.
+    occupancy: Occupancy,
+
+    /// Whether a single column is centered with at most the reading mode width.
+    reading_mode: bool,
+
     /// Optional name of this workspace.
     pub(super) name: Option<String>,
 
@@ -103,7 +109,7 @@ pub struct Workspace<W: LayoutElement> {
     id: WorkspaceId,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct OutputId(String);
 
 impl OutputId {
@@ -170,6 +176,15 @@ enum FloatingActive {
     Yes,
 }
 
+/// What a workspace holds, as far as resolving its options goes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Occupancy {
+    /// Whether the scrolling layout has a single column.
+    single_column: bool,
+    /// Whether the workspace has a single window, tiled or floating.
+    single_window: bool,
+}
+
 /// Where to put a newly added window.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum WorkspaceAddWindowTarget<'a, W: LayoutElement> {
@@ -252,6 +267,8 @@ impl<W: LayoutElement> Workspace<W> {
             clock,
             base_options,
             options,
+            occupancy: Occupancy::default(),
+            reading_mode: false,
             name: config.map(|c| c.name.0),
             id: WorkspaceId::next(),
         }
@@ -309,6 +326,8 @@ impl<W: LayoutElement> Workspace<W> {
             clock,
             base_options,
             options,
+            occupancy: Occupancy::default(),
+            reading_mode: false,
             name: config.map(|c| c.name.0),
             id: WorkspaceId::next(),
         }
@@ -370,7 +389,12 @@ impl<W: LayoutElement> Workspace<W> {
 
     pub fn update_config(&mut self, base_options: Rc<Options>) {
         let scale = self.scale.fractional_scale();
-        let options = Rc::new(Options::clone(&base_options).adjusted_for_scale(scale));
+        let options = Rc::new(resolve_options(
+            &base_options,
+            scale,
+            self.occupancy,
+            self.reading_mode_width(),
+        ));
 
         self.scrolling.update_config(
             self.view_size,
@@ -394,6 +418,39 @@ impl<W: LayoutElement> Workspace<W> {
         self.options = options;
     }
 
+    fn compute_occupancy(&self) -> Occupancy {
+        let window_count = self.scrolling.tiles().count() + self.floating.tiles().count();
+        Occupancy {
+            single_column: self.scrolling.column_count() == 1,
+            single_window: window_count == 1,
+        }
+    }
+
+    /// Returns the working area width to fit the reading mode into, if it's enabled.
+    fn reading_mode_width(&self) -> Option<f64> {
+        self.reading_mode.then_some(self.working_area.size.w)
+    }
+
+    /// Returns the base options resolved for this workspace.
+    #[cfg(test)]
+    pub(super) fn resolved_options(&self) -> Options {
+        resolve_options(
+            &self.base_options,
+            self.scale.fractional_scale(),
+            self.occupancy,
+            self.reading_mode_width(),
+        )
+    }
+
+    pub fn is_reading_mode(&self) -> bool {
+        self.reading_mode
+    }
+
+    pub fn toggle_reading_mode(&mut self) {
+        self.reading_mode = !self.reading_mode;
+        self.update_config(self.base_options.clone());
+    }
+
     pub fn update_shaders(&mut self) {
         self.scrolling.update_shaders();
         self.floating.update_shaders();
@@ -408,6 +465,51 @@ impl<W: LayoutElement> Workspace<W> {
         self.tiles_mut().map(Tile::window_mut)
     }
 
+    /// Returns the layout tree of this workspace for IPC.
+    pub fn ipc_layout(
+        &self,
+        output: Option<&Output>,
+        is_active: bool,
+        is_focused: bool,
+        window_id: &dyn Fn(&W) -> u64,
+    ) -> WorkspaceLayout {
+        let columns = self.scrolling.ipc_columns(window_id);
+        let active_column_idx = (!columns.is_empty()).then(|| self.scrolling.active_column_idx);
+        let floating = self
+            .floating
+            .tiles_with_render_positions()
+            .map(|(tile, pos)| tile.ipc_layout(pos, window_id(tile.window())))
+            .collect();
+
+        WorkspaceLayout {
+            id: self.id().get(),
+            output: output.map(|output| output.name()),
+            is_active,
+            is_focused,
+            view_size: (self.view_size.w, self.view_size.h),
+            columns,
+            active_column_idx,
+            floating,
+            floating_is_active: self.floating_is_active(),
+        }
+    }
+
+    /// Returns the windows along with their layout info for IPC.
+    pub fn windows_with_ipc_layouts(&self) -> impl Iterator<Item = (&W, WindowLayout)> + '_ {
+        let scrolling = self
+            .scrolling
+            .tiles_with_ipc_positions()
+            .map(|(tile, pos)| (tile.window(), Some(pos)));
+        let floating = self.floating.tiles().map(|tile| (tile.window(), None));
+        scrolling.chain(floating).map(|(win, pos)| {
+            let layout = WindowLayout {
+                pos_in_scrolling_layout: pos,
+                is_fullscreen: win.is_fullscreen(),
+            };
+            (win, layout)
+        })
+    }
+
     pub fn tiles(&self) -> impl Iterator<Item = &Tile<W>> + '_ {
         let scrolling = self.scrolling.tiles();
         let floating = self.floating.tiles();
@@ -512,8 +614,9 @@ impl<W: LayoutElement> Workspace<W> {
         self.view_size = size;
         self.working_area = working_area;
 
-        if fractional_scale_changed {
-            // Options need to be recomputed for the new scale.
+        if fractional_scale_changed || self.reading_mode {
+            // Options need to be recomputed for the new scale. Reading mode options also depend on
+            // the working area.
             self.update_config(self.base_options.clone());
         } else {
             // Pass our existing options as is.
@@ -821,12 +924,20 @@ impl<W: LayoutElement> Workspace<W> {
             let current = guard.current();
             (current.min_size, current.max_size)
         });
+        let min_size = rules.remove_csd_margins(min_size);
+        let max_size = rules.remove_csd_margins(max_size);
         toplevel.with_pending_state(|state| {
-            if state.states.contains(xdg_toplevel::State::Fullscreen) {
+            if state.states.contains(xdg_toplevel::State::Fullscreen)
+                && rules.force_windowed_fullscreen != Some(true)
+            {
                 state.size = Some(self.view_size.to_i32_round());
             } else {
-                let size =
+                let mut size =
                     self.new_window_size(width, height, is_floating, rules, (min_size, max_size));
+                // Windows don't draw their CSD margins in fullscreen.
+                if !state.states.contains(xdg_toplevel::State::Fullscreen) {
+                    size = rules.add_csd_margins(size);
+                }
                 state.size = Some(size);
             }
 
@@ -889,6 +1000,13 @@ impl<W: LayoutElement> Workspace<W> {
         self.scrolling.focus_column(index);
     }
 
+    pub fn focus_column_previous(&mut self) {
+        if self.floating_is_active.get() {
+            self.focus_tiling();
+        }
+        self.scrolling.focus_column_previous();
+    }
+
     pub fn focus_window_in_column(&mut self, index: u8) {
         if self.floating_is_active.get() {
             return;
@@ -1004,11 +1122,15 @@ impl<W: LayoutElement> Workspace<W> {
         self.scrolling.move_column_to_last();
     }
 
-    pub fn move_column_to_index(&mut self, index: usize) {
-        if self.floating_is_active.get() {
+    pub fn move_column_to_index(&mut self, window: Option<&W::Id>, index: usize) {
+        if let Some(window) = window {
+            if self.floating.has_window(window) {
+                return;
+            }
+        } else if self.floating_is_active.get() {
             return;
         }
-        self.scrolling.move_column_to_index(index);
+        self.scrolling.move_column_to_index(window, index);
     }
 
     pub fn move_down(&mut self) -> bool {
@@ -1107,6 +1229,14 @@ impl<W: LayoutElement> Workspace<W> {
         self.scrolling.center_visible_columns();
     }
 
+    pub fn move_pip_to_next_corner(&mut self) {
+        self.floating.move_pip_to_next_corner();
+    }
+
+    pub fn switch_preset_pip_width(&mut self) {
+        self.floating.switch_preset_pip_width();
+    }
+
     pub fn toggle_width(&mut self) {
         if self.floating_is_active.get() {
             self.floating.toggle_window_width(None);
@@ -1124,6 +1254,13 @@ impl<W: LayoutElement> Workspace<W> {
         self.scrolling.toggle_full_width();
     }
 
+    pub fn toggle_column_fullscreen(&mut self) {
+        if self.floating_is_active.get() {
+            return;
+        }
+        self.scrolling.toggle_column_fullscreen();
+    }
+
     pub fn set_column_width(&mut self, change: SizeChange) {
         if self.floating_is_active.get() {
             self.floating.set_window_width(None, change, true);
@@ -1413,7 +1550,10 @@ impl<W: LayoutElement> Workspace<W> {
 
         let floating = self.floating.tiles_with_render_positions();
         let visible = self.is_floating_visible();
-        let floating = floating.map(move |(tile, pos)| (tile, pos, visible));
+        let floating = floating.map(move |(tile, pos)| {
+            let visible = visible || tile.window().is_always_on_top();
+            (tile, pos, visible)
+        });
 
         floating.chain(scrolling)
     }
@@ -1459,11 +1599,16 @@ impl<W: LayoutElement> Workspace<W> {
         let scrolling = scrolling.into_iter().map(WorkspaceRenderElement::from);
 
         let floating_focus_ring = focus_ring && self.floating_is_active();
-        let floating = self.is_floating_visible().then(|| {
+        let floating_visible = self.is_floating_visible();
+        let floating = (floating_visible || self.floating.has_always_on_top()).then(|| {
             let view_rect = Rectangle::from_size(self.view_size);
-            let floating =
-                self.floating
-                    .render_elements(renderer, view_rect, target, floating_focus_ring);
+            let floating = self.floating.render_elements(
+                renderer,
+                view_rect,
+                target,
+                floating_focus_ring,
+                !floating_visible,
+            );
             floating.into_iter().map(WorkspaceRenderElement::from)
         });
         let floating = floating.into_iter().flatten();
@@ -1545,14 +1690,14 @@ impl<W: LayoutElement> Workspace<W> {
 
     pub fn window_under(&self, pos: Point<f64, Logical>) -> Option<(&W, HitType)> {
         // This logic is consistent with tiles_with_render_positions().
-        if self.is_floating_visible() {
-            if let Some(rv) = self
-                .floating
-                .tiles_with_render_positions()
-                .find_map(|(tile, tile_pos)| HitType::hit_tile(tile, tile_pos, pos))
-            {
-                return Some(rv);
-            }
+        let visible = self.is_floating_visible();
+        if let Some(rv) = self
+            .floating
+            .tiles_with_render_positions()
+            .filter(|(tile, _)| visible || tile.window().is_always_on_top())
+            .find_map(|(tile, tile_pos)| HitType::hit_tile(tile, tile_pos, pos))
+        {
+            return Some(rv);
         }
 
         self.scrolling.window_under(pos)
@@ -1631,11 +1776,30 @@ impl<W: LayoutElement> Workspace<W> {
         }
     }
 
-    pub fn refresh(&mut self, is_active: bool, is_focused: bool) {
-        self.scrolling
-            .refresh(is_active && !self.floating_is_active.get(), is_focused);
-        self.floating
-            .refresh(is_active && self.floating_is_active.get(), is_focused);
+    pub fn refresh(&mut self, is_active: bool, is_focused: bool, is_visible: bool) {
+        let occupancy = self.compute_occupancy();
+        if self.occupancy != occupancy {
+            self.occupancy = occupancy;
+            self.update_config(self.base_options.clone());
+        }
+
+        // Keep the picture-in-picture window out of the way of the focused tiled window.
+        if !self.floating_is_active.get() {
+            if let Some(rect) = self.scrolling.active_tile_visual_rectangle() {
+                self.floating.dodge_pip(rect);
+            }
+        }
+
+        self.scrolling.refresh(
+            is_active && !self.floating_is_active.get(),
+            is_focused,
+            is_visible,
+        );
+        self.floating.refresh(
+            is_active && self.floating_is_active.get(),
+            is_focused,
+            is_visible,
+        );
     }
 
     pub fn scroll_amount_to_activate(&self, window: &W::Id) -> f64 {
@@ -1646,6 +1810,11 @@ impl<W: LayoutElement> Workspace<W> {
         self.scrolling.scroll_amount_to_activate(window)
     }
 
+    /// Returns whether the scrolling layout view is currently moving.
+    pub fn is_view_scrolling(&self) -> bool {
+        self.scrolling.is_view_scrolling()
+    }
+
     pub fn is_urgent(&self) -> bool {
         self.windows().any(|win| win.is_urgent())
     }
@@ -1707,6 +1876,19 @@ impl<W: LayoutElement> Workspace<W> {
         self.scrolling.view_offset_gesture_end(is_touchpad)
     }
 
+    pub fn wheel_scroll_gesture_begin(&mut self) {
+        self.scrolling.wheel_scroll_gesture_begin();
+    }
+
+    pub fn wheel_scroll_gesture_update(&mut self, delta_x: f64, timestamp: Duration) -> bool {
+        self.scrolling
+            .wheel_scroll_gesture_update(delta_x, timestamp)
+    }
+
+    pub fn wheel_scroll_gesture_end(&mut self) -> bool {
+        self.scrolling.wheel_scroll_gesture_end()
+    }
+
     pub fn dnd_scroll_gesture_begin(&mut self) {
         self.scrolling.dnd_scroll_gesture_begin();
     }
@@ -1864,6 +2046,52 @@ impl<W: LayoutElement> Workspace<W> {
     }
 }
 
+/// Resolves the options of a workspace from the options of the layout.
+///
+/// `reading_mode_width` is the working area width when the reading mode is enabled.
+fn resolve_options(
+    base_options: &Options,
+    scale: f64,
+    occupancy: Occupancy,
+    reading_mode_width: Option<f64>,
+) -> Options {
+    let mut options = Options::clone(base_options);
+
+    if let Some(width) = reading_mode_width {
+        if occupancy.single_column {
+            // Narrow the working area down to the maximum width with struts on both sides, and
+            // keep the column centered in it.
+            let struts = &mut options.struts;
+            let width = width - struts.left.0 - struts.right.0;
+            let margin = f64::max(0., (width - options.reading_mode_max_width) / 2.);
+            struts.left.0 += margin;
+            struts.right.0 += margin;
+            options.center_focused_column = CenterFocusedColumn::Always;
+        }
+    }
+
+    if let Some(smart_gaps) = options.smart_gaps {
+        if occupancy.single_column {
+            options.gaps = 0.;
+
+            if smart_gaps.remove_borders && occupancy.single_window {
+                options.border.off = true;
+            }
+        }
+    }
+
+    if occupancy.single_window {
+        if options.border.hide_when_single_window {
+            options.border.off = true;
+        }
+        if options.focus_ring.hide_when_single_window {
+            options.focus_ring.off = true;
+        }
+    }
+
+    options.adjusted_for_scale(scale)
+}
+
 pub(super) fn compute_working_area(output: &Output) -> Rectangle<f64, Logical> {
     layer_map_for_output(output).non_exclusive_zone().to_f64()
 }
@@ -2,22 +2,31 @@
 extern crate tracing;
 
 pub mod animation;
+pub mod autostart;
 pub mod backend;
 pub mod cli;
+pub mod crash_dump;
 pub mod cursor;
 #[cfg(feature = "dbus")]
 pub mod dbus;
 pub mod frame_clock;
 pub mod handlers;
+#[cfg(any(test, feature = "test-harness"))]
+pub mod harness;
+pub mod hooks;
+pub mod idle;
+pub mod import_config;
 pub mod input;
 pub mod ipc;
 pub mod layer;
 pub mod layout;
 pub mod niri;
+pub mod power_saving;
 pub mod protocols;
 pub mod render_helpers;
 pub mod rubber_band;
 pub mod ui;
+pub mod unresponsive;
 pub mod utils;
 pub mod window;
 
@@ -5,15 +5,18 @@ use std::fmt::Write as _;
 use std::fs::{self, File};
 use std::io::{self, Write};
 use std::os::fd::FromRawFd;
+use std::panic::{self, AssertUnwindSafe};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{self, Command};
 use std::{env, mem};
 
 use clap::{CommandFactory, Parser};
 use directories::ProjectDirs;
 use niri::cli::{Cli, Sub};
+use niri::{crash_dump, import_config};
 #[cfg(feature = "dbus")]
 use niri::dbus;
+use niri::input::replay::InputRecorder;
 use niri::ipc::client::handle_msg;
 use niri::niri::State;
 use niri::utils::spawning::{
@@ -21,16 +24,17 @@ use niri::utils::spawning::{
     REMOVE_ENV_RUST_LIB_BACKTRACE,
 };
 use niri::utils::watcher::Watcher;
-use niri::utils::{cause_panic, version, xwayland, IS_SYSTEMD_SERVICE};
+use niri::utils::{
+    cause_panic, enable_software_rendering, logging, version, xwayland, IS_SYSTEMD_SERVICE,
+};
+use niri_config::schema::config_schema;
 use niri_config::Config;
 use niri_ipc::socket::SOCKET_PATH_ENV;
+use niri_ipc::DiagnosticSeverity;
 use portable_atomic::Ordering;
 use sd_notify::NotifyState;
 use smithay::reexports::calloop::EventLoop;
 use smithay::reexports::wayland_server::Display;
-use tracing_subscriber::EnvFilter;
-
-const DEFAULT_LOG_FILTER: &str = "niri=debug,smithay::backend::renderer::gles=error";
 
 #[cfg(feature = "profile-with-tracy-allocations")]
 #[global_allocator]
@@ -48,13 +52,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         REMOVE_ENV_RUST_LIB_BACKTRACE.store(true, Ordering::Relaxed);
     }
 
-    let directives = env::var("RUST_LOG").unwrap_or_else(|_| DEFAULT_LOG_FILTER.to_owned());
-    let env_filter = EnvFilter::builder().parse_lossy(directives);
-    tracing_subscriber::fmt()
-        .compact()
-        .with_writer(io::stderr)
-        .with_env_filter(env_filter)
-        .init();
+    logging::init();
 
     if env::var_os("NOTIFY_SOCKET").is_some() {
         IS_SYSTEMD_SERVICE.store(true, Ordering::Relaxed);
@@ -91,19 +89,63 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         env::set_var("XDG_SESSION_TYPE", "wayland");
     }
 
+    // This changes the environment, so it must happen before spawning any threads.
+    let is_nested = env::var_os("WAYLAND_DISPLAY").is_some()
+        || env::var_os("WAYLAND_SOCKET").is_some()
+        || env::var_os("DISPLAY").is_some();
+    if cli.software_rendering {
+        enable_software_rendering();
+    } else if !is_nested && has_no_render_nodes() {
+        warn!("no GPU can render, falling back to software rendering");
+        enable_software_rendering();
+    }
+
     // Handle subcommands.
     if let Some(subcommand) = cli.subcommand {
         match subcommand {
-            Sub::Validate { config } => {
+            Sub::Validate {
+                config,
+                profile,
+                json,
+            } => {
                 tracy_client::Client::start();
 
                 let (path, _, _) = config_path(config);
-                Config::load(&path)?;
+                let profile = profile.or_else(env_profile);
+
+                if json {
+                    let diagnostics = Config::validate(&path, profile.as_deref());
+                    println!("{}", serde_json::to_string(&diagnostics)?);
+
+                    let is_valid = diagnostics
+                        .iter()
+                        .all(|d| d.severity != DiagnosticSeverity::Error);
+                    if !is_valid {
+                        process::exit(1);
+                    }
+                    return Ok(());
+                }
+
+                Config::load(&path, profile.as_deref(), &mut Vec::new())?;
                 info!("config is valid");
                 return Ok(());
             }
-            Sub::Msg { msg, json } => {
-                handle_msg(msg, json)?;
+            Sub::Msg {
+                msg,
+                json,
+                table,
+                watch,
+            } => {
+                handle_msg(msg, json, table, watch)?;
+                return Ok(());
+            }
+            Sub::ConfigSchema => {
+                println!("{}", serde_json::to_string_pretty(&config_schema())?);
+                return Ok(());
+            }
+            Sub::ImportConfig { from, path } => {
+                let text = fs::read_to_string(&path)?;
+                print!("{}", import_config::convert(from, &text));
                 return Ok(());
             }
             Sub::Panic => cause_panic(),
@@ -124,6 +166,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut config_created = false;
     let (path, watch_path, create_default) = config_path(cli.config);
     env::remove_var("NIRI_CONFIG");
+    let profile = cli.profile.or_else(env_profile);
+    env::remove_var("NIRI_PROFILE");
     if create_default {
         let default_parent = path.parent().unwrap();
 
@@ -161,7 +205,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
-    let config_load_result = Config::load(&path);
+    let mut config_files = Vec::new();
+    let config_load_result = Config::load(&path, profile.as_deref(), &mut config_files);
     let config_errored = config_load_result.is_err();
     let mut config = config_load_result
         .map_err(|err| warn!("{err:?}"))
@@ -184,6 +229,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         true,
     )
     .unwrap();
+    state.niri.config_path = Some(path.clone());
+    state.niri.config_profile.clone_from(&profile);
 
     // Set WAYLAND_DISPLAY for children.
     let socket_name = state.niri.socket_name.as_deref().unwrap();
@@ -244,18 +291,29 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let _watcher = {
         // Parsing the config actually takes > 20 ms on my beefy machine, so let's do it on the
         // watcher thread.
-        let process = |path: &Path| {
-            Config::load(path).map_err(|err| {
+        let process = move |path: &Path, extra_paths: &mut Vec<PathBuf>| {
+            let mut files = Vec::new();
+            let rv = Config::load(path, profile.as_deref(), &mut files).map_err(|err| {
                 warn!("{:?}", err.context("error loading config"));
-            })
+            });
+            // Also watch the included files, skipping the main config file itself.
+            *extra_paths = files.into_iter().skip(1).collect();
+            rv
         };
 
+        let extra_paths = config_files.into_iter().skip(1).collect();
+
         let (tx, rx) = calloop::channel::sync_channel(1);
-        let watcher = Watcher::new(watch_path.clone(), process, tx);
+        let watcher = Watcher::new(watch_path.clone(), extra_paths, process, tx);
         event_loop
             .handle()
             .insert_source(rx, |event, _, state| match event {
-                calloop::channel::Event::Msg(config) => state.reload_config(config),
+                calloop::channel::Event::Msg(mut config) => {
+                    if let Ok(config) = &mut config {
+                        state.niri.apply_config_overrides(config);
+                    }
+                    state.reload_config(config);
+                }
                 calloop::channel::Event::Closed => (),
             })
             .unwrap();
@@ -264,9 +322,22 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Spawn commands from cli and auto-start.
     spawn(cli.command, None);
-
-    for elem in spawn_at_startup {
-        spawn(elem.command, None);
+    state.niri.autostart.start(spawn_at_startup);
+
+    // Start recording or replaying input if requested.
+    if let Some(path) = &cli.record_input {
+        match InputRecorder::create(path) {
+            Ok(recorder) => {
+                warn!("recording input to {path:?}, this includes everything typed");
+                state.niri.input_recorder = Some(recorder);
+            }
+            Err(err) => warn!("error starting the input recording: {err:?}"),
+        }
+    }
+    if let Some(path) = &cli.replay_input {
+        if let Err(err) = state.start_input_replay(path) {
+            warn!("error replaying input: {err:?}");
+        }
     }
 
     // Show the config error notification right away if needed.
@@ -277,9 +348,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // Run the compositor.
-    event_loop
-        .run(None, &mut state, |state| state.refresh_and_flush_clients())
-        .unwrap();
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        event_loop.run(None, &mut state, |state| state.refresh_and_flush_clients())
+    }));
+    match res {
+        Ok(res) => res.unwrap(),
+        Err(payload) => crash_dump::handle_crash(&state, payload),
+    }
 
     Ok(())
 }
@@ -341,6 +416,10 @@ fn env_config_path() -> Option<PathBuf> {
         .map(PathBuf::from)
 }
 
+fn env_profile() -> Option<String> {
+    env::var("NIRI_PROFILE").ok().filter(|x| !x.is_empty())
+}
+
 fn default_config_path() -> Option<PathBuf> {
     let Some(dirs) = ProjectDirs::from("", "", "niri") else {
         warn!("error retrieving home directory");
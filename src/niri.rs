@@ -1,8 +1,9 @@
 use std::cell::{Cell, OnceCell, RefCell};
 use std::collections::{HashMap, HashSet};
 use std::ffi::OsString;
+use std::iter::zip;
 use std::os::unix::net::UnixStream;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{self, Receiver, Sender};
@@ -14,7 +15,8 @@ use _server_decoration::server::org_kde_kwin_server_decoration_manager::Mode as
 use anyhow::{bail, ensure, Context};
 use calloop::futures::Scheduler;
 use niri_config::{
-    Config, FloatOrInt, Key, Modifiers, OutputName, PreviewRender, TrackLayout,
+    ClientPermission, Config, FloatOrInt, FocusFollowsMouse, Key, Modifiers, OutputName,
+    PowerMenuEntry, PreviewRender, TouchpadSwipeAction, TrackLayout, Wallpaper,
     WarpMouseToFocusMode, WorkspaceReference,
 };
 use smithay::backend::allocator::Fourcc;
@@ -58,7 +60,9 @@ use smithay::reexports::calloop::{
     Interest, LoopHandle, LoopSignal, Mode, PostAction, RegistrationToken,
 };
 use smithay::reexports::wayland_protocols::ext::session_lock::v1::server::ext_session_lock_v1::ExtSessionLockV1;
-use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel::WmCapabilities;
+use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel::{
+    self, WmCapabilities,
+};
 use smithay::reexports::wayland_protocols_misc::server_decoration as _server_decoration;
 use smithay::reexports::wayland_protocols_wlr::screencopy::v1::server::zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1;
 use smithay::reexports::wayland_server::backend::{
@@ -75,8 +79,10 @@ use smithay::wayland::compositor::{
     with_states, with_surface_tree_downward, CompositorClientState, CompositorHandler,
     CompositorState, HookId, SurfaceData, TraversalAction,
 };
+use smithay::wayland::content_type::ContentTypeState;
 use smithay::wayland::cursor_shape::CursorShapeManagerState;
 use smithay::wayland::dmabuf::DmabufState;
+use smithay::wayland::foreign_toplevel_list::ForeignToplevelListState;
 use smithay::wayland::fractional_scale::FractionalScaleManagerState;
 use smithay::wayland::idle_inhibit::IdleInhibitManagerState;
 use smithay::wayland::idle_notify::IdleNotifierState;
@@ -92,13 +98,16 @@ use smithay::wayland::relative_pointer::RelativePointerManagerState;
 use smithay::wayland::security_context::SecurityContextState;
 use smithay::wayland::selection::data_device::{set_data_device_selection, DataDeviceState};
 use smithay::wayland::selection::ext_data_control::DataControlState as ExtDataControlState;
-use smithay::wayland::selection::primary_selection::PrimarySelectionState;
+use smithay::wayland::selection::primary_selection::{
+    clear_primary_selection, set_primary_focus, PrimarySelectionState,
+};
 use smithay::wayland::selection::wlr_data_control::DataControlState as WlrDataControlState;
 use smithay::wayland::session_lock::{LockSurface, SessionLockManagerState, SessionLocker};
 use smithay::wayland::shell::kde::decoration::KdeDecorationState;
 use smithay::wayland::shell::wlr_layer::{self, Layer, WlrLayerShellState};
 use smithay::wayland::shell::xdg::decoration::XdgDecorationState;
-use smithay::wayland::shell::xdg::XdgShellState;
+use smithay::wayland::shell::xdg::dialog::XdgDialogState;
+use smithay::wayland::shell::xdg::{ToplevelSurface, XdgShellState};
 use smithay::wayland::shm::ShmState;
 #[cfg(test)]
 use smithay::wayland::single_pixel_buffer::SinglePixelBufferState;
@@ -111,10 +120,15 @@ use smithay::wayland::xdg_activation::XdgActivationState;
 use smithay::wayland::xdg_foreign::XdgForeignState;
 
 use crate::animation::Clock;
+use crate::autostart::Autostart;
 use crate::backend::tty::SurfaceDmabufFeedback;
+use crate::backend::virtual_output::VirtualOutput;
 use crate::backend::{Backend, Headless, RenderResult, Tty, Winit};
+use crate::crash_dump::InputHistory;
 use crate::cursor::{CursorManager, CursorTextureCache, RenderCursor, XCursor};
 #[cfg(feature = "dbus")]
+use crate::dbus::freedesktop_notifications::NotificationsToNiri;
+#[cfg(feature = "dbus")]
 use crate::dbus::gnome_shell_introspect::{self, IntrospectToNiri, NiriToIntrospect};
 #[cfg(feature = "dbus")]
 use crate::dbus::gnome_shell_screenshot::{NiriToScreenshot, ScreenshotToNiri};
@@ -122,9 +136,13 @@ use crate::dbus::gnome_shell_screenshot::{NiriToScreenshot, ScreenshotToNiri};
 use crate::dbus::mutter_screen_cast::{self, ScreenCastToNiri};
 use crate::frame_clock::FrameClock;
 use crate::handlers::{configure_lock_surface, XDG_ACTIVATION_TOKEN_TIMEOUT};
+use crate::idle::DIM_ALPHA;
+use crate::input::accessibility::KeyboardAccessibility;
 use crate::input::pick_color_grab::PickColorGrab;
+use crate::input::replay::InputRecorder;
 use crate::input::scroll_swipe_gesture::ScrollSwipeGesture;
 use crate::input::scroll_tracker::ScrollTracker;
+use crate::input::tap_tracker::TapTracker;
 use crate::input::{
     apply_libinput_settings, mods_with_finger_scroll_binds, mods_with_mouse_binds,
     mods_with_wheel_binds, TabletData,
@@ -136,6 +154,7 @@ use crate::layout::tile::TileRenderElement;
 use crate::layout::workspace::{Workspace, WorkspaceId};
 use crate::layout::{HitType, Layout, LayoutElement as _, MonitorRenderElement};
 use crate::niri_render_elements;
+use crate::power_saving::{PowerStatus, PowerStatusChange};
 use crate::protocols::foreign_toplevel::{self, ForeignToplevelManagerState};
 use crate::protocols::gamma_control::GammaControlManagerState;
 use crate::protocols::mutter_x11_interop::MutterX11InteropManagerState;
@@ -146,6 +165,7 @@ use crate::pw_utils::{Cast, PipeWire};
 #[cfg(feature = "xdp-gnome-screencast")]
 use crate::pw_utils::{CastSizeChange, PwToNiri};
 use crate::render_helpers::debug::draw_opaque_regions;
+use crate::render_helpers::offscreen::{OffscreenBuffer, OffscreenRenderElement};
 use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
 use crate::render_helpers::renderer::NiriRenderer;
 use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
@@ -154,18 +174,32 @@ use crate::render_helpers::{
     encompassing_geo, render_to_dmabuf, render_to_encompassing_texture, render_to_shm,
     render_to_texture, render_to_vec, shaders, RenderTarget, SplitElements,
 };
+use crate::ui::binds_mode_indicator::BindsModeIndicator;
 use crate::ui::config_error_notification::ConfigErrorNotification;
 use crate::ui::exit_confirm_dialog::ExitConfirmDialog;
 use crate::ui::hotkey_overlay::HotkeyOverlay;
+use crate::ui::layout_indicator::{self, LayoutIndicator};
+#[cfg(feature = "dbus")]
+use crate::ui::lock_screen::MediaControl;
+use crate::ui::lock_screen::{self, LockScreen};
+use crate::ui::notifications::{CloseReason, Notification, NotificationPopups};
+use crate::ui::osd::Osd;
 use crate::ui::screen_transition::{self, ScreenTransition};
 use crate::ui::screenshot_ui::{OutputScreenshot, ScreenshotUi, ScreenshotUiRenderElement};
+use crate::ui::wallpaper::Wallpapers;
+use crate::ui::window_switcher::WindowSwitcher;
+use crate::unresponsive::{PingedClient, PING_INTERVAL};
+use crate::utils::backlight::BacklightWorker;
+#[cfg(feature = "dbus")]
+use crate::utils::mpris::{MediaKey, Mpris};
 use crate::utils::scale::{closest_representable_scale, guess_monitor_scale};
 use crate::utils::spawning::{CHILD_DISPLAY, CHILD_ENV};
 use crate::utils::xwayland::satellite::Satellite;
 use crate::utils::{
-    center, center_f64, expand_home, get_monotonic_time, ipc_transform_to_smithay, is_mapped,
-    logical_output, make_screenshot_path, output_matches_name, output_size, send_scale_transform,
-    write_png_rgba8, xwayland,
+    center, center_f64, expand_home, get_executable, get_monotonic_time, get_peer_pid,
+    get_systemd_unit, ipc_transform_to_smithay, is_mapped, is_software_rendering, logical_output,
+    make_screenshot_path, output_matches_name, output_size, send_scale_transform, write_png_rgba8,
+    xwayland,
 };
 use crate::window::mapped::MappedId;
 use crate::window::{InitialConfigureState, Mapped, ResolvedWindowRules, Unmapped, WindowRef};
@@ -177,6 +211,9 @@ const CLEAR_COLOR_LOCKED: [f32; 4] = [0.3, 0.1, 0.1, 1.];
 // should be ~1.995 seconds.
 const FRAME_CALLBACK_THROTTLE: Option<Duration> = Some(Duration::from_millis(995));
 
+/// How long to wait for a suspended window to draw before taking its screenshot anyway.
+const WINDOW_SCREENSHOT_TIMEOUT: Duration = Duration::from_secs(1);
+
 pub struct Niri {
     pub config: Rc<RefCell<Config>>,
 
@@ -186,6 +223,16 @@ pub struct Niri {
     /// reloading the config from disk to determine if the output configuration should be reloaded
     /// (and transient changes dropped).
     pub config_file_output_config: niri_config::Outputs,
+    /// Path to the loaded config file, if running with one.
+    pub config_path: Option<PathBuf>,
+    /// Config profile given on startup.
+    pub config_profile: Option<String>,
+    /// Config values changed over IPC.
+    pub config_overrides: Vec<ConfigOverride>,
+    /// Commands from `spawn-at-startup`.
+    pub autostart: Autostart,
+    /// Name of the output profile applied for the currently connected outputs.
+    pub active_output_profile: Option<String>,
 
     pub event_loop: LoopHandle<'static, State>,
     pub scheduler: Scheduler<()>,
@@ -237,6 +284,8 @@ pub struct Niri {
     pub blocker_cleared_rx: Receiver<Client>,
 
     pub output_state: HashMap<Output, OutputState>,
+    /// Virtual outputs created over IPC, in creation order.
+    pub virtual_outputs: Vec<Output>,
 
     // When false, we're idling with monitors powered off.
     pub monitors_active: bool,
@@ -246,6 +295,7 @@ pub struct Niri {
     /// Libinput guarantees that the lid switch starts in open state, and if it was closed during
     /// startup, libinput will immediately send a closed event.
     pub is_lid_closed: bool,
+    pub is_tablet_mode: bool,
 
     pub devices: HashSet<input::Device>,
     pub tablets: HashMap<input::Device, TabletData>,
@@ -254,15 +304,20 @@ pub struct Niri {
     // Smithay state.
     pub compositor_state: CompositorState,
     pub xdg_shell_state: XdgShellState,
+    /// xdg-shell clients, pinged to detect unresponsive ones.
+    pub shell_clients: Vec<PingedClient>,
     pub xdg_decoration_state: XdgDecorationState,
     pub kde_decoration_state: KdeDecorationState,
     pub layer_shell_state: WlrLayerShellState,
     pub session_lock_state: SessionLockManagerState,
     pub foreign_toplevel_state: ForeignToplevelManagerState,
+    pub foreign_toplevel_list_state: ForeignToplevelListState,
     pub screencopy_state: ScreencopyManagerState,
     pub output_management_state: OutputManagementManagerState,
     pub viewporter_state: ViewporterState,
+    pub content_type_state: ContentTypeState,
     pub xdg_foreign_state: XdgForeignState,
+    pub xdg_dialog_state: XdgDialogState,
     pub shm_state: ShmState,
     pub output_manager_state: OutputManagerState,
     pub dmabuf_state: DmabufState,
@@ -303,16 +358,27 @@ pub struct Niri {
     pub seat: Seat<State>,
     /// Scancodes of the keys to suppress.
     pub suppressed_keys: HashSet<Keycode>,
+    pub keyboard_accessibility: KeyboardAccessibility,
+    pub tap_tracker: TapTracker,
     /// Button codes of the mouse buttons to suppress.
     pub suppressed_buttons: HashSet<u32>,
     pub bind_cooldown_timers: HashMap<Key, RegistrationToken>,
-    pub bind_repeat_timer: Option<RegistrationToken>,
+    /// Timer repeating the bind triggered by the held key, with the bind's modifiers.
+    pub bind_repeat_timer: Option<(Keycode, Modifiers, RegistrationToken)>,
     pub keyboard_focus: KeyboardFocus,
+    /// Keyboard layouts remembered for each workspace, with `track-layout "workspace"`.
+    pub workspace_keyboard_layouts: HashMap<WorkspaceId, KeyboardLayout>,
+    /// Workspace that the current keyboard layout belongs to, with `track-layout "workspace"`.
+    pub keyboard_layout_workspace: Option<WorkspaceId>,
     pub layer_shell_on_demand_focus: Option<LayerSurface>,
     pub previously_focused_window: Option<Window>,
     pub idle_inhibiting_surfaces: HashSet<WlSurface>,
     pub is_fdo_idle_inhibited: Arc<AtomicBool>,
     pub keyboard_shortcuts_inhibiting_surfaces: HashMap<WlSurface, KeyboardShortcutsInhibitor>,
+    /// Surface whose pointer constraint was released by the user.
+    ///
+    /// Its constraint won't activate again until the next click.
+    pub pointer_constraint_released: Option<WlSurface>,
 
     pub cursor_manager: CursorManager,
     pub cursor_texture_cache: CursorTextureCache,
@@ -345,11 +411,31 @@ pub struct Niri {
     /// resolution mice.
     pub notified_activity_this_iteration: bool,
     pub pointer_inside_hot_corner: bool,
+    /// Focused workspace as of the last check for the workspace switch hooks.
+    pub hooks_focused_workspace: Option<WorkspaceId>,
+    /// Timers for the idle stages and hooks, `None` for the ones that already ran.
+    pub idle_timers: Vec<Option<RegistrationToken>>,
+    /// Whether the outputs are dimmed because of inactivity.
+    pub is_idle_dimmed: bool,
+    /// Whether idle was inhibited as of the last idle inhibit refresh.
+    pub was_idle_inhibited: bool,
+    pub power_status: PowerStatus,
+    /// Recent input events for the crash dump.
+    pub input_history: InputHistory,
+    /// Input recording started with `--record-input`.
+    pub input_recorder: Option<InputRecorder>,
+    /// Window waiting for the focus-follows-mouse delay, with the timer.
+    pub focus_follows_mouse_timer: Option<(Window, RegistrationToken)>,
+    /// How far the pointer was pushed against an output barrier so far.
+    pub output_barrier_push: f64,
     pub tablet_cursor_location: Option<Point<f64, Logical>>,
-    pub gesture_swipe_3f_cumulative: Option<(f64, f64)>,
+    pub gesture_swipe_cumulative: Option<(TouchpadSwipeAction, f64, f64)>,
+    pub gesture_pinch_last_scale: Option<f64>,
     pub overview_scroll_swipe_gesture: ScrollSwipeGesture,
     pub vertical_wheel_tracker: ScrollTracker,
     pub horizontal_wheel_tracker: ScrollTracker,
+    /// Timer ending the kinetic wheel view scroll once the wheel stops.
+    pub wheel_view_scroll_timer: Option<RegistrationToken>,
     pub mods_with_mouse_binds: HashSet<Modifiers>,
     pub mods_with_wheel_binds: HashSet<Modifiers>,
     pub vertical_finger_scroll_tracker: ScrollTracker,
@@ -362,6 +448,24 @@ pub struct Niri {
     pub config_error_notification: ConfigErrorNotification,
     pub hotkey_overlay: HotkeyOverlay,
     pub exit_confirm_dialog: Option<ExitConfirmDialog>,
+    pub window_switcher: WindowSwitcher,
+    /// Name of the active binds mode, if any.
+    pub binds_mode: Option<String>,
+    pub binds_mode_indicator: BindsModeIndicator,
+    pub layout_indicator: LayoutIndicator,
+    /// Timer hiding the keyboard layout indicator.
+    pub layout_indicator_timer: Option<RegistrationToken>,
+    pub osd: Osd,
+    /// Timer hiding the on-screen display.
+    pub osd_timer: Option<RegistrationToken>,
+    pub backlight: BacklightWorker,
+    pub lock_screen: LockScreen,
+    /// Sources updating the built-in lock screen while the session is locked.
+    pub lock_screen_sources: Vec<RegistrationToken>,
+    pub notification_popups: NotificationPopups,
+    pub wallpapers: Wallpapers,
+    /// Windows shown as output backdrops instead of in the layout, in the order they were mapped.
+    pub backdrop_windows: Vec<BackdropWindow>,
 
     pub pick_window: Option<async_channel::Sender<Option<MappedId>>>,
     pub pick_color: Option<async_channel::Sender<Option<niri_ipc::PickedColor>>>,
@@ -373,9 +477,11 @@ pub struct Niri {
     pub dbus: Option<crate::dbus::DBusServers>,
     #[cfg(feature = "dbus")]
     pub inhibit_power_key_fd: Option<zbus::zvariant::OwnedFd>,
+    #[cfg(feature = "dbus")]
+    pub mpris: Mpris,
 
     pub ipc_server: Option<IpcServer>,
-    pub ipc_outputs_changed: bool,
+    pub ipc_outputs_dirty: bool,
 
     pub satellite: Option<Satellite>,
 
@@ -422,6 +528,27 @@ pub struct DndIcon {
     pub offset: Point<i32, Logical>,
 }
 
+/// Window that spans its output below everything else, like a wallpaper.
+///
+/// Backdrop windows live outside of the layout: they never get focus or pointer input, and they
+/// don't take part in window switching.
+pub struct BackdropWindow {
+    pub window: Window,
+    /// Output that the window spans, if any is connected.
+    pub output: Option<Output>,
+}
+
+/// Config value changed over IPC on top of the config file.
+#[derive(Debug)]
+pub struct ConfigOverride {
+    pub name: String,
+    pub value: String,
+    /// Value in the config file when the override was made.
+    ///
+    /// When the value in the file changes, the override is dropped.
+    pub file_value: String,
+}
+
 pub struct OutputState {
     pub global: GlobalId,
     pub frame_clock: FrameClock,
@@ -431,6 +558,8 @@ pub struct OutputState {
     pub unfinished_animations_remain: bool,
     /// Last sequence received in a vblank event.
     pub last_drm_sequence: Option<u32>,
+    /// Monotonic time of the last successful render, for the frame rate limit.
+    pub last_render_time: Option<Duration>,
     /// Sequence for frame callback throttling.
     ///
     /// We want to send frame callbacks for each surface at most once per monitor refresh cycle.
@@ -458,6 +587,19 @@ pub struct OutputState {
     pub lock_render_state: LockRenderState,
     pub lock_surface: Option<LockSurface>,
     pub lock_color_buffer: SolidColorBuffer,
+    /// Black overlay for dimming the output on inactivity.
+    pub dim_buffer: SolidColorBuffer,
+    /// Output being mirrored onto this output.
+    ///
+    /// Mirroring outputs are removed from the layout and the global space, and show the contents
+    /// of the source output instead.
+    pub mirror_source: Option<Output>,
+    /// Offscreen buffer with the contents of the mirrored output.
+    ///
+    /// Only the damaged parts are re-rendered into it every frame.
+    pub mirror_buffer: OffscreenBuffer,
+    /// Whether this output was powered off on its own, while the other outputs stay on.
+    pub is_powered_off: bool,
     screen_transition: Option<ScreenTransition>,
     /// Damage tracker used for the debug damage visualization.
     pub debug_damage_tracker: OutputDamageTracker,
@@ -660,6 +802,10 @@ impl State {
 
         let mut state = Self { backend, niri };
 
+        if is_software_rendering() {
+            state.on_power_status_change(PowerStatusChange::SoftwareRendering(true));
+        }
+
         // Load the xkb_file config option if set by the user.
         state.load_xkb_file();
         // Initialize some IPC server state.
@@ -728,11 +874,88 @@ impl State {
         self.niri.refresh_mapped_cast_window_rules();
 
         self.niri.refresh_window_rules();
+        self.niri.refresh_workspace_switch_hooks();
         self.refresh_ipc_outputs();
         self.ipc_refresh_layout();
+        self.refresh_layout_indicator();
         self.ipc_refresh_keyboard_layout_index();
     }
 
+    /// Briefly shows the keyboard layout indicator if the active layout changed.
+    fn refresh_layout_indicator(&mut self) {
+        let keyboard = self.niri.seat.get_keyboard().unwrap();
+        let idx = keyboard.with_xkb_state(self, |context| {
+            let xkb = context.xkb().lock().unwrap();
+            xkb.active_layout().0
+        });
+
+        if !self.niri.layout_indicator.update_layout_idx(idx) {
+            return;
+        }
+
+        let config = self.niri.config.borrow();
+        if !config.input.keyboard.layout_indicator {
+            return;
+        }
+
+        // Prefer the short layout from the config, like "us", and fall back to the full name.
+        let text = config
+            .input
+            .keyboard
+            .xkb
+            .layouts()
+            .nth(idx as usize)
+            .filter(|layout| !layout.is_empty())
+            .map(str::to_owned);
+        drop(config);
+        let text = text.unwrap_or_else(|| {
+            keyboard.with_xkb_state(self, |context| {
+                let xkb = context.xkb().lock().unwrap();
+                xkb.layout_name(xkb.active_layout()).to_owned()
+            })
+        });
+        self.niri.layout_indicator.show(text);
+
+        if let Some(token) = self.niri.layout_indicator_timer.take() {
+            self.niri.event_loop.remove(token);
+        }
+        let timer = Timer::from_duration(layout_indicator::SHOW_DURATION);
+        let token = self
+            .niri
+            .event_loop
+            .insert_source(timer, |_, _, state| {
+                state.niri.layout_indicator_timer = None;
+                state.niri.layout_indicator.hide();
+                state.niri.queue_redraw_all();
+                TimeoutAction::Drop
+            })
+            .unwrap();
+        self.niri.layout_indicator_timer = Some(token);
+
+        self.niri.queue_redraw_all();
+    }
+
+    /// Returns the index of the keyboard layout with the given full or xkb config name.
+    pub fn keyboard_layout_by_name(&mut self, name: &str) -> Option<u8> {
+        let keyboard = self.niri.seat.get_keyboard().unwrap();
+        let idx = keyboard.with_xkb_state(self, |context| {
+            let xkb = context.xkb().lock().unwrap();
+            xkb.layouts()
+                .position(|layout| xkb.layout_name(layout) == name)
+        });
+
+        let idx = idx.or_else(|| {
+            let config = self.niri.config.borrow();
+            config
+                .input
+                .keyboard
+                .xkb
+                .layouts()
+                .position(|layout| layout == name)
+        });
+        idx.and_then(|idx| u8::try_from(idx).ok())
+    }
+
     fn notify_blocker_cleared(&mut self) {
         let dh = self.niri.display_handle.clone();
         while let Ok(client) = self.niri.blocker_cleared_rx.try_recv() {
@@ -1145,7 +1368,12 @@ impl State {
         };
 
         let keyboard = self.niri.seat.get_keyboard().unwrap();
-        if self.niri.keyboard_focus != focus {
+        let focus_changed = self.niri.keyboard_focus != focus;
+        if self.niri.config.borrow().input.keyboard.track_layout == TrackLayout::Workspace {
+            self.update_workspace_keyboard_layout(!focus_changed);
+        }
+
+        if focus_changed {
             trace!(
                 "keyboard focus changed from {:?} to {:?}",
                 self.niri.keyboard_focus,
@@ -1259,6 +1487,51 @@ impl State {
         }
     }
 
+    /// Switches to the keyboard layout of the active workspace if it changed.
+    ///
+    /// The focused surface has to be re-entered to learn about the new layout. When `refocus` is
+    /// unset, the caller is expected to set the new keyboard focus right after.
+    fn update_workspace_keyboard_layout(&mut self, refocus: bool) {
+        let ws_id = self.niri.layout.active_workspace().map(|ws| ws.id());
+        if self.niri.keyboard_layout_workspace == ws_id {
+            return;
+        }
+
+        let keyboard = self.niri.seat.get_keyboard().unwrap();
+        let current_layout = keyboard.with_xkb_state(self, |context| {
+            let xkb = context.xkb().lock().unwrap();
+            xkb.active_layout()
+        });
+
+        // Store the currently active layout for the previous workspace.
+        let layouts = &mut self.niri.workspace_keyboard_layouts;
+        if let Some(prev_id) = self.niri.keyboard_layout_workspace {
+            layouts.insert(prev_id, current_layout);
+        }
+        layouts.retain(|id, _| self.niri.layout.find_workspace_by_id(*id).is_some());
+        self.niri.keyboard_layout_workspace = ws_id;
+
+        let Some(ws_id) = ws_id else {
+            return;
+        };
+
+        // The default layout is effectively the first layout in the keymap, so use it for new
+        // workspaces.
+        let new_layout = layouts.get(&ws_id).copied().unwrap_or_default();
+        if new_layout == current_layout {
+            return;
+        }
+
+        keyboard.set_focus(self, None, SERIAL_COUNTER.next_serial());
+        keyboard.with_xkb_state(self, |mut context| {
+            context.set_layout(new_layout);
+        });
+        if refocus {
+            let focus = self.niri.keyboard_focus.clone().into_surface();
+            keyboard.set_focus(self, focus, SERIAL_COUNTER.next_serial());
+        }
+    }
+
     /// Loads the xkb keymap from a file config setting.
     fn set_xkb_file(&mut self, xkb_file: String) -> anyhow::Result<()> {
         let xkb_file = PathBuf::from(xkb_file);
@@ -1298,6 +1571,9 @@ impl State {
 
         self.niri.config_error_notification.hide();
 
+        // Pick up both wallpaper config changes and changed image files.
+        self.niri.wallpapers.refresh(&config);
+
         // Find & orphan removed named workspaces.
         let mut removed_workspaces: Vec<String> = vec![];
         for ws in &self.niri.config.borrow().workspaces {
@@ -1319,23 +1595,20 @@ impl State {
             self.niri.layout.ensure_named_workspace(ws_config);
         }
 
-        let rate = 1.0 / config.animations.slowdown.0.max(0.001);
-        self.niri.clock.set_rate(rate);
-        self.niri
-            .clock
-            .set_complete_instantly(config.animations.off);
-
         *CHILD_ENV.write().unwrap() = mem::take(&mut config.environment);
 
         let mut reload_xkb = None;
         let mut libinput_config_changed = false;
         let mut output_config_changed = false;
+        let mut output_profiles_changed = false;
         let mut preserved_output_config = None;
         let mut window_rules_changed = false;
         let mut layer_rules_changed = false;
         let mut shaders_changed = false;
         let mut cursor_inactivity_timeout_changed = false;
+        let mut idle_changed = false;
         let mut xwls_changed = false;
+        let mut primary_selection_changed = false;
         let mut old_config = self.niri.config.borrow_mut();
 
         // Reload the cursor.
@@ -1371,6 +1644,7 @@ impl State {
 
         if config.outputs != self.niri.config_file_output_config {
             output_config_changed = true;
+            output_profiles_changed = true;
             self.niri
                 .config_file_output_config
                 .clone_from(&config.outputs);
@@ -1391,6 +1665,23 @@ impl State {
                 mods_with_finger_scroll_binds(new_mod_key, &config.binds);
         }
 
+        if config.osd != old_config.osd {
+            self.niri.osd.update_config(config.osd);
+        }
+
+        if config.power_menu != old_config.power_menu {
+            if let Some(dialog) = &mut self.niri.exit_confirm_dialog {
+                dialog.set_entries(config.power_menu.entries.clone());
+            }
+        }
+
+        // Leave the binds mode if it was removed from the config.
+        if let Some(mode) = &self.niri.binds_mode {
+            if !config.binds_modes.iter().any(|m| m.name == *mode) {
+                self.niri.set_binds_mode(None);
+            }
+        }
+
         if config.window_rules != old_config.window_rules {
             window_rules_changed = true;
         }
@@ -1433,12 +1724,20 @@ impl State {
             cursor_inactivity_timeout_changed = true;
         }
 
+        if config.idle != old_config.idle || config.hooks.idle != old_config.hooks.idle {
+            idle_changed = true;
+        }
+
         if config.debug.keep_laptop_panel_on_when_lid_is_closed
             != old_config.debug.keep_laptop_panel_on_when_lid_is_closed
         {
             output_config_changed = true;
         }
 
+        if config.lid_switch != old_config.lid_switch {
+            output_config_changed = true;
+        }
+
         // FIXME: move backdrop rendering into layout::Monitor, then this will become unnecessary.
         if config.overview.backdrop_color != old_config.overview.backdrop_color {
             output_config_changed = true;
@@ -1447,10 +1746,18 @@ impl State {
             output_config_changed = true;
         }
 
+        if config.output_profiles != old_config.output_profiles {
+            output_profiles_changed = true;
+        }
+
         if config.xwayland_satellite != old_config.xwayland_satellite {
             xwls_changed = true;
         }
 
+        if config.clipboard.disable_primary != old_config.clipboard.disable_primary {
+            primary_selection_changed = true;
+        }
+
         *old_config = config;
 
         if let Some(outputs) = preserved_output_config {
@@ -1460,6 +1767,13 @@ impl State {
         // Release the borrow.
         drop(old_config);
 
+        // Both the animations and the power saving state depend on the new config.
+        self.niri.update_animation_clock();
+        let is_power_saving = self.niri.is_power_saving();
+        let config = self.niri.config.borrow();
+        self.niri.layout.set_power_saving(&config, is_power_saving);
+        drop(config);
+
         // Now with a &mut self we can reload the xkb config.
         if let Some(mut xkb) = reload_xkb {
             let mut set_xkb_config = true;
@@ -1492,7 +1806,11 @@ impl State {
             }
         }
 
-        if output_config_changed {
+        // Re-apply the output profile on top of the new output config. The profiles themselves
+        // may have changed, so rebuild the output config even if the same profile still matches.
+        let output_profile_applied = self.refresh_output_profile(output_profiles_changed);
+
+        if output_config_changed && !output_profile_applied {
             self.reload_output_config();
         }
 
@@ -1514,6 +1832,28 @@ impl State {
             self.niri.reset_pointer_inactivity_timer();
         }
 
+        if idle_changed {
+            self.niri.reset_idle_timers();
+        }
+
+        if primary_selection_changed {
+            let disabled = self.niri.config.borrow().clipboard.disable_primary;
+            let dh = &self.niri.display_handle;
+            let seat = &self.niri.seat;
+
+            let client = if disabled {
+                // Drop the current primary selection so it can no longer be pasted.
+                clear_primary_selection(dh, seat);
+                None
+            } else {
+                self.niri
+                    .keyboard_focus
+                    .surface()
+                    .and_then(|surface| dh.get_client(surface.id()).ok())
+            };
+            set_primary_focus(dh, seat, client);
+        }
+
         if xwls_changed {
             // If xwl-s was previously working and is now off, we don't try to kill it or stop
             // watching the sockets, for simplicity's sake.
@@ -1550,7 +1890,9 @@ impl State {
         let mut resized_outputs = vec![];
         let mut recolored_outputs = vec![];
 
-        for output in self.niri.global_space.outputs() {
+        // Go through all outputs, including the mirrors which aren't in the global space.
+        let outputs: Vec<_> = self.niri.output_state.keys().cloned().collect();
+        for output in &outputs {
             let name = output.user_data().get::<OutputName>().unwrap();
             let full_config = self.niri.config.borrow_mut();
             let config = full_config.outputs.find(name);
@@ -1558,18 +1900,14 @@ impl State {
             let scale = config
                 .and_then(|c| c.scale)
                 .map(|s| s.0)
-                .unwrap_or_else(|| {
-                    let size_mm = output.physical_properties().size;
-                    let resolution = output.current_mode().unwrap().size;
-                    guess_monitor_scale(size_mm, resolution)
-                });
+                .unwrap_or_else(|| default_output_scale(output));
             let scale = closest_representable_scale(scale.clamp(0.1, 10.));
 
             let mut transform = config
                 .map(|c| ipc_transform_to_smithay(c.transform))
                 .unwrap_or(Transform::Normal);
             // FIXME: fix winit damage on other transforms.
-            if name.connector == "winit" {
+            if name.connector.starts_with("winit") {
                 transform = Transform::Flipped180;
             }
 
@@ -1582,7 +1920,7 @@ impl State {
                     Some(output::Scale::Fractional(scale)),
                     None,
                 );
-                self.niri.ipc_outputs_changed = true;
+                self.niri.ipc_outputs_dirty = true;
                 resized_outputs.push(output.clone());
             }
 
@@ -1621,6 +1959,7 @@ impl State {
 
         self.backend.on_output_config_changed(&mut self.niri);
 
+        self.niri.refresh_output_mirrors();
         self.niri.reposition_outputs(None);
 
         if let Some(touch) = self.niri.seat.get_touch() {
@@ -1709,19 +2048,135 @@ impl State {
                     None
                 }
             }
+            niri_ipc::OutputAction::Mirror { mirror } => {
+                config.mirror = match mirror {
+                    niri_ipc::MirrorToSet::Off => None,
+                    niri_ipc::MirrorToSet::Output(name) => Some(name),
+                }
+            }
+        });
+
+        self.reload_output_config();
+    }
+
+    /// Changes a config value, optionally writing it into the config file.
+    pub fn set_config_value(
+        &mut self,
+        name: &str,
+        value: &str,
+        persist: bool,
+    ) -> Result<(), String> {
+        let mut config = self.niri.config.borrow().clone();
+        let file_value = match self.niri.config_overrides.iter().find(|o| o.name == name) {
+            Some(o) => o.file_value.clone(),
+            None => config.get_value(name)?,
+        };
+        config.set_value(name, value)?;
+
+        if persist {
+            let path = self
+                .niri
+                .config_path
+                .as_ref()
+                .ok_or_else(|| String::from("niri is running without a config file"))?;
+            let profile = self.niri.config_profile.as_deref();
+            niri_config::persist_value(path, profile, name, value)?;
+        }
+
+        // Also apply the value right away, since the file watcher picks up the change later.
+        self.niri.config_overrides.retain(|o| o.name != name);
+        self.niri.config_overrides.push(ConfigOverride {
+            name: name.to_owned(),
+            value: config.get_value(name)?,
+            file_value,
         });
 
+        // Preserve the transient output config changes.
+        config
+            .outputs
+            .clone_from(&self.niri.config_file_output_config);
+        self.reload_config(Ok(config));
+
+        Ok(())
+    }
+
+    /// Applies the first output profile that matches the connected outputs.
+    ///
+    /// With `force`, the output config is rebuilt even if the active profile stays the same, which
+    /// is needed when the profiles changed in the config.
+    ///
+    /// Returns `true` if the output config changed and was reloaded.
+    pub fn refresh_output_profile(&mut self, force: bool) -> bool {
+        let connected: Vec<_> = self
+            .backend
+            .ipc_outputs()
+            .lock()
+            .unwrap()
+            .values()
+            .map(OutputName::from_ipc_output)
+            .collect();
+
+        let config = self.niri.config.borrow();
+        let profile = config
+            .output_profiles
+            .iter()
+            .find(|profile| profile.matches(&connected));
+
+        let name = profile.map(|profile| profile.name.clone());
+        if !force && name == self.niri.active_output_profile {
+            return false;
+        }
+
+        // Profile outputs come first so that they take precedence over the top-level ones.
+        let mut outputs = self.niri.config_file_output_config.clone();
+        let mut workspaces = vec![];
+        if let Some(profile) = profile {
+            outputs.0.splice(0..0, profile.outputs.0.iter().cloned());
+            workspaces = profile.workspaces.clone();
+        }
+        drop(config);
+
+        match &name {
+            Some(name) => info!("applying output profile {name:?}"),
+            None => info!("no output profile matches, using the top-level output config"),
+        }
+        self.niri.active_output_profile = name;
+
+        self.niri.config.borrow_mut().outputs = outputs;
         self.reload_output_config();
+
+        for ws in workspaces {
+            let Some(output) = ws
+                .open_on_output
+                .and_then(|name| self.niri.output_by_name_match(&name).cloned())
+            else {
+                continue;
+            };
+
+            let reference = WorkspaceReference::Name(ws.name.0);
+            if let Some((Some(old_output), old_idx)) =
+                self.niri.find_output_and_workspace_index(reference)
+            {
+                self.niri
+                    .layout
+                    .move_workspace_to_output_by_id(old_idx, Some(old_output), output);
+            }
+        }
+
+        true
     }
 
     pub fn refresh_ipc_outputs(&mut self) {
-        if !self.niri.ipc_outputs_changed {
+        if !self.niri.ipc_outputs_dirty {
             return;
         }
-        self.niri.ipc_outputs_changed = false;
+        self.niri.ipc_outputs_dirty = false;
 
         let _span = tracy_client::span!("State::refresh_ipc_outputs");
 
+        // The set of connected outputs may have changed.
+        self.refresh_output_profile(false);
+
         for ipc_output in self.backend.ipc_outputs().lock().unwrap().values_mut() {
             let logical = self
                 .niri
@@ -1735,6 +2190,8 @@ impl State {
         #[cfg(feature = "dbus")]
         self.niri.on_ipc_outputs_changed();
 
+        self.ipc_outputs_changed();
+
         let new_config = self.backend.ipc_outputs().lock().unwrap().clone();
         self.niri.output_management_state.notify_changes(new_config);
     }
@@ -2109,6 +2566,44 @@ impl State {
         }
     }
 
+    /// Runs the entry confirmed in the exit dialog, `None` being the plain exit confirmation.
+    pub fn activate_power_menu_entry(&mut self, entry: Option<PowerMenuEntry>) {
+        let method = match entry {
+            None | Some(PowerMenuEntry::Logout) => {
+                info!("quitting after confirming exit dialog");
+                self.niri.stop_signal.stop();
+                return;
+            }
+            Some(PowerMenuEntry::Suspend) => {
+                self.backend.suspend();
+                // Suspend may not deliver the key releases, so clear the state.
+                self.niri.suppressed_keys.clear();
+                return;
+            }
+            Some(PowerMenuEntry::Hibernate) => "Hibernate",
+            Some(PowerMenuEntry::Reboot) => "Reboot",
+            Some(PowerMenuEntry::PowerOff) => "PowerOff",
+        };
+
+        #[cfg(feature = "dbus")]
+        {
+            // Authorization may need a polkit agent, which needs the compositor running.
+            let res = std::thread::Builder::new()
+                .name("Power Menu".to_owned())
+                .spawn(move || {
+                    if let Err(err) = crate::utils::call_logind(method) {
+                        warn!("error calling logind {method}: {err:?}");
+                    }
+                });
+            if let Err(err) = res {
+                warn!("error spawning a thread to call logind: {err:?}");
+            }
+        }
+
+        #[cfg(not(feature = "dbus"))]
+        warn!("cannot call logind {method} without the dbus feature");
+    }
+
     #[cfg(feature = "dbus")]
     pub fn on_screen_shot_msg(
         &mut self,
@@ -2125,6 +2620,113 @@ impl State {
         }
     }
 
+    #[cfg(feature = "dbus")]
+    pub fn on_notifications_msg(&mut self, msg: NotificationsToNiri) {
+        match msg {
+            NotificationsToNiri::Notify {
+                id,
+                summary,
+                body,
+                is_critical,
+                expire_timeout,
+            } => {
+                let niri = &mut self.niri;
+
+                // Critical notifications show up even with do-not-disturb.
+                if niri.notification_popups.do_not_disturb() && !is_critical {
+                    niri.close_notification(id, CloseReason::Dismissed);
+                    return;
+                }
+
+                let default_timeout = niri.config.borrow().notifications.timeout_ms;
+                let timeout_ms = match expire_timeout {
+                    0 => None,
+                    timeout if timeout > 0 => Some(timeout.unsigned_abs()),
+                    // Critical notifications stay until dismissed by default.
+                    _ if is_critical => None,
+                    _ => Some(default_timeout),
+                };
+
+                let timer = timeout_ms.map(|timeout_ms| {
+                    let timer = Timer::from_duration(Duration::from_millis(u64::from(timeout_ms)));
+                    niri.event_loop
+                        .insert_source(timer, move |_, _, state| {
+                            state.niri.notification_popups.forget_timer(id);
+                            state.niri.close_notification(id, CloseReason::Expired);
+                            TimeoutAction::Drop
+                        })
+                        .unwrap()
+                });
+
+                let notification = Notification {
+                    id,
+                    summary,
+                    body,
+                    is_critical,
+                };
+                if let Some(old_timer) = niri.notification_popups.show(notification, timer) {
+                    niri.event_loop.remove(old_timer);
+                }
+                for (id, timer) in niri.notification_popups.close_overflow() {
+                    if let Some(timer) = timer {
+                        niri.event_loop.remove(timer);
+                    }
+                    niri.emit_notification_closed(id, CloseReason::Expired);
+                }
+                niri.queue_redraw_all();
+            }
+            NotificationsToNiri::Close(id) => {
+                self.niri.close_notification(id, CloseReason::Closed);
+            }
+        }
+    }
+
+    /// Takes a screenshot of a suspended window once it wakes up and draws again.
+    ///
+    /// A suspended window may not have drawn its latest contents.
+    pub fn request_window_screenshot(&mut self, surface: &WlSurface, write_to_disk: bool) {
+        let Some((mapped, _)) = self.niri.layout.find_window_and_output_mut(surface) else {
+            return;
+        };
+        // The next layout refresh wakes the window up.
+        mapped.request_screenshot(write_to_disk);
+
+        let surface = surface.clone();
+        let timer = Timer::from_duration(WINDOW_SCREENSHOT_TIMEOUT);
+        self.niri
+            .event_loop
+            .insert_source(timer, move |_, _, state| {
+                state.take_pending_window_screenshot(&surface);
+                TimeoutAction::Drop
+            })
+            .unwrap();
+    }
+
+    /// Takes the screenshot requested for the window, if it's still pending.
+    pub fn take_pending_window_screenshot(&mut self, surface: &WlSurface) {
+        let Some((mapped, output)) = self.niri.layout.find_window_and_output_mut(surface) else {
+            return;
+        };
+        let Some(write_to_disk) = mapped.take_pending_screenshot() else {
+            return;
+        };
+        let Some(output) = output.cloned() else {
+            return;
+        };
+
+        let Some((mapped, _)) = self.niri.layout.find_window_and_output(surface) else {
+            return;
+        };
+        self.backend.with_primary_renderer(|renderer| {
+            if let Err(err) = self
+                .niri
+                .screenshot_window(renderer, &output, mapped, write_to_disk)
+            {
+                warn!("error taking screenshot: {err:?}");
+            }
+        });
+    }
+
     #[cfg(feature = "dbus")]
     fn handle_take_screenshot(
         &mut self,
@@ -2188,7 +2790,7 @@ impl State {
             },
         );
 
-        self.niri.layout.with_windows(|mapped, _, _| {
+        self.niri.layout.with_windows(|mapped, _, _, _| {
             let id = mapped.id().get();
             let props = with_toplevel_role(mapped.toplevel(), |role| {
                 gnome_shell_introspect::WindowProperties {
@@ -2251,13 +2853,9 @@ impl Niri {
             &display_handle,
             [WmCapabilities::Fullscreen],
         );
-        let xdg_decoration_state =
-            XdgDecorationState::new_with_filter::<State, _>(&display_handle, |client| {
-                client
-                    .get_data::<ClientState>()
-                    .unwrap()
-                    .can_view_decoration_globals
-            });
+        // The xdg-decoration global is always visible so that window rules can force server-side
+        // decorations. Without prefer-no-csd, the preferred mode is client-side.
+        let xdg_decoration_state = XdgDecorationState::new::<State>(&display_handle);
         let kde_decoration_state = KdeDecorationState::new_with_filter::<State, _>(
             &display_handle,
             // If we want CSD we will hide the global.
@@ -2299,15 +2897,19 @@ impl Niri {
                     .unwrap()
                     .primary_selection_disabled
             });
+        fn client_can_use_data_control(client: &Client) -> bool {
+            let data = client.get_data::<ClientState>().unwrap();
+            !data.restricted && data.data_control_allowed
+        }
         let wlr_data_control_state = WlrDataControlState::new::<State, _>(
             &display_handle,
             Some(&primary_selection_state),
-            client_is_unrestricted,
+            client_can_use_data_control,
         );
         let ext_data_control_state = ExtDataControlState::new::<State, _>(
             &display_handle,
             Some(&primary_selection_state),
-            client_is_unrestricted,
+            client_can_use_data_control,
         );
         let presentation_state =
             PresentationState::new::<State>(&display_handle, Monotonic::ID as u32);
@@ -2320,18 +2922,35 @@ impl Niri {
         let keyboard_shortcuts_inhibit_state =
             KeyboardShortcutsInhibitState::new::<State>(&display_handle);
         let virtual_keyboard_state =
-            VirtualKeyboardManagerState::new::<State, _>(&display_handle, client_is_unrestricted);
+            VirtualKeyboardManagerState::new::<State, _>(&display_handle, |client| {
+                let data = client.get_data::<ClientState>().unwrap();
+                !data.restricted && data.virtual_keyboard_allowed
+            });
         let virtual_pointer_state =
-            VirtualPointerManagerState::new::<State, _>(&display_handle, client_is_unrestricted);
+            VirtualPointerManagerState::new::<State, _>(&display_handle, |client| {
+                let data = client.get_data::<ClientState>().unwrap();
+                !data.restricted && data.virtual_pointer_allowed
+            });
         let foreign_toplevel_state =
             ForeignToplevelManagerState::new::<State, _>(&display_handle, client_is_unrestricted);
+        let foreign_toplevel_list_state = ForeignToplevelListState::new_with_filter::<State>(
+            &display_handle,
+            client_is_unrestricted,
+        );
         let mut output_management_state =
-            OutputManagementManagerState::new::<State, _>(&display_handle, client_is_unrestricted);
+            OutputManagementManagerState::new::<State, _>(&display_handle, |client| {
+                let data = client.get_data::<ClientState>().unwrap();
+                !data.restricted && data.output_management_allowed
+            });
         output_management_state.on_config_changed(config_.outputs.clone());
-        let screencopy_state =
-            ScreencopyManagerState::new::<State, _>(&display_handle, client_is_unrestricted);
+        let screencopy_state = ScreencopyManagerState::new::<State, _>(&display_handle, |client| {
+            let data = client.get_data::<ClientState>().unwrap();
+            !data.restricted && data.screencopy_allowed
+        });
         let viewporter_state = ViewporterState::new::<State>(&display_handle);
+        let content_type_state = ContentTypeState::new::<State>(&display_handle);
         let xdg_foreign_state = XdgForeignState::new::<State>(&display_handle);
+        let xdg_dialog_state = XdgDialogState::new::<State>(&display_handle);
 
         let is_tty = matches!(backend, Backend::Tty(_));
         let gamma_control_manager_state =
@@ -2351,6 +2970,13 @@ impl Niri {
             )
             .unwrap();
 
+        event_loop
+            .insert_source(Timer::from_duration(PING_INTERVAL), |_, _, state| {
+                state.ping_shell_clients();
+                TimeoutAction::ToDuration(PING_INTERVAL)
+            })
+            .unwrap();
+
         let mutter_x11_interop_state =
             MutterX11InteropManagerState::new::<State, _>(&display_handle, move |_| true);
 
@@ -2403,7 +3029,9 @@ impl Niri {
             hotkey_overlay.show();
         }
 
-        let exit_confirm_dialog = match ExitConfirmDialog::new() {
+        let osd = Osd::new(config_.osd);
+
+        let exit_confirm_dialog = match ExitConfirmDialog::new(config_.power_menu.entries.clone()) {
             Ok(x) => Some(x),
             Err(err) => {
                 warn!("error creating the exit confirm dialog: {err:?}");
@@ -2416,7 +3044,7 @@ impl Niri {
                 Timer::from_duration(Duration::from_secs(1)),
                 |_, _, state| {
                     state.niri.send_frame_callbacks_on_fallback_timer();
-                    TimeoutAction::ToDuration(Duration::from_secs(1))
+                    TimeoutAction::ToDuration(state.niri.frame_callback_fallback_interval())
                 },
             )
             .unwrap();
@@ -2430,12 +3058,15 @@ impl Niri {
                         client,
                         restricted: false,
                         credentials_unknown: false,
+                        sandbox_app_id: None,
                     });
                 })
                 .unwrap();
             socket_name
         });
 
+        let autostart = Autostart::new(&event_loop);
+
         let ipc_server = match IpcServer::start(&event_loop, socket_name.as_deref()) {
             Ok(server) => Some(server),
             Err(err) => {
@@ -2444,6 +3075,25 @@ impl Niri {
             }
         };
 
+        let (wallpaper_to_niri, from_wallpapers) = calloop::channel::channel();
+        event_loop
+            .insert_source(from_wallpapers, move |event, _, state| {
+                if let calloop::channel::Event::Msg(prepared) = event {
+                    state.niri.wallpapers.finish(prepared);
+                    state.niri.queue_redraw_all();
+                }
+            })
+            .unwrap();
+
+        let (backlight_to_niri, from_backlight) = calloop::channel::channel();
+        event_loop
+            .insert_source(from_backlight, move |event, _, state| {
+                if let calloop::channel::Event::Msg(value) = event {
+                    state.niri.show_osd(niri_ipc::OsdKind::Brightness, value, false);
+                }
+            })
+            .unwrap();
+
         #[cfg(feature = "xdp-gnome-screencast")]
         let pw_to_niri = {
             let (pw_to_niri, from_pipewire) = calloop::channel::channel();
@@ -2484,6 +3134,11 @@ impl Niri {
         let mut niri = Self {
             config,
             config_file_output_config,
+            config_path: None,
+            config_profile: None,
+            config_overrides: Vec::new(),
+            autostart,
+            active_output_profile: None,
 
             event_loop,
             scheduler,
@@ -2507,6 +3162,7 @@ impl Niri {
             blocker_cleared_rx,
             monitors_active: true,
             is_lid_closed: false,
+            is_tablet_mode: false,
 
             devices: HashSet::new(),
             tablets: HashMap::new(),
@@ -2514,15 +3170,19 @@ impl Niri {
 
             compositor_state,
             xdg_shell_state,
+            shell_clients: Vec::new(),
             xdg_decoration_state,
             kde_decoration_state,
             layer_shell_state,
             session_lock_state,
             foreign_toplevel_state,
+            foreign_toplevel_list_state,
             output_management_state,
             screencopy_state,
             viewporter_state,
+            content_type_state,
             xdg_foreign_state,
+            xdg_dialog_state,
             text_input_state,
             input_method_state,
             keyboard_shortcuts_inhibit_state,
@@ -2546,6 +3206,8 @@ impl Niri {
             popups: PopupManager::default(),
             popup_grab: None,
             suppressed_keys: HashSet::new(),
+            keyboard_accessibility: KeyboardAccessibility::default(),
+            tap_tracker: TapTracker::default(),
             suppressed_buttons: HashSet::new(),
             bind_cooldown_timers: HashMap::new(),
             bind_repeat_timer: Option::default(),
@@ -2559,11 +3221,14 @@ impl Niri {
 
             seat,
             keyboard_focus: KeyboardFocus::Layout { surface: None },
+            workspace_keyboard_layouts: HashMap::new(),
+            keyboard_layout_workspace: None,
             layer_shell_on_demand_focus: None,
             previously_focused_window: None,
             idle_inhibiting_surfaces: HashSet::new(),
             is_fdo_idle_inhibited: Arc::new(AtomicBool::new(false)),
             keyboard_shortcuts_inhibiting_surfaces: HashMap::new(),
+            pointer_constraint_released: None,
             cursor_manager,
             cursor_texture_cache: Default::default(),
             cursor_shape_manager_state,
@@ -2574,11 +3239,22 @@ impl Niri {
             pointer_inactivity_timer_got_reset: false,
             notified_activity_this_iteration: false,
             pointer_inside_hot_corner: false,
+            hooks_focused_workspace: None,
+            idle_timers: Vec::new(),
+            is_idle_dimmed: false,
+            was_idle_inhibited: false,
+            power_status: PowerStatus::default(),
+            input_history: InputHistory::default(),
+            input_recorder: None,
+            focus_follows_mouse_timer: None,
+            output_barrier_push: 0.,
             tablet_cursor_location: None,
-            gesture_swipe_3f_cumulative: None,
+            gesture_swipe_cumulative: None,
+            gesture_pinch_last_scale: None,
             overview_scroll_swipe_gesture: ScrollSwipeGesture::new(),
             vertical_wheel_tracker: ScrollTracker::new(120),
             horizontal_wheel_tracker: ScrollTracker::new(120),
+            wheel_view_scroll_timer: None,
             mods_with_mouse_binds,
             mods_with_wheel_binds,
 
@@ -2593,6 +3269,20 @@ impl Niri {
             config_error_notification,
             hotkey_overlay,
             exit_confirm_dialog,
+            window_switcher: WindowSwitcher::new(),
+            binds_mode: None,
+            binds_mode_indicator: BindsModeIndicator::new(),
+            layout_indicator: LayoutIndicator::new(),
+            layout_indicator_timer: None,
+            osd,
+            osd_timer: None,
+            backlight: BacklightWorker::new(backlight_to_niri),
+            lock_screen: LockScreen::new(),
+            lock_screen_sources: Vec::new(),
+            notification_popups: NotificationPopups::new(),
+            wallpapers: Wallpapers::new(wallpaper_to_niri),
+            backdrop_windows: Vec::new(),
+            virtual_outputs: Vec::new(),
 
             pick_window: None,
             pick_color: None,
@@ -2604,9 +3294,11 @@ impl Niri {
             dbus: None,
             #[cfg(feature = "dbus")]
             inhibit_power_key_fd: None,
+            #[cfg(feature = "dbus")]
+            mpris: Mpris::new(),
 
             ipc_server,
-            ipc_outputs_changed: false,
+            ipc_outputs_dirty: false,
 
             satellite: None,
 
@@ -2623,6 +3315,7 @@ impl Niri {
         };
 
         niri.reset_pointer_inactivity_timer();
+        niri.reset_idle_timers();
 
         niri
     }
@@ -2632,15 +3325,74 @@ impl Niri {
             client,
             restricted,
             credentials_unknown,
+            sandbox_app_id,
         } = client;
 
         let config = self.config.borrow();
-        let data = Arc::new(ClientState {
-            compositor_state: Default::default(),
-            can_view_decoration_globals: config.prefer_no_csd,
+
+        let pid = if credentials_unknown {
+            None
+        } else {
+            get_peer_pid(&client)
+        };
+        let executable = pid.and_then(get_executable);
+        let unit = pid.and_then(get_systemd_unit);
+        let is_xwayland = executable
+            .as_deref()
+            .is_some_and(|exe| is_xwayland_satellite(&config, exe));
+
+        let security = &config.security;
+        let allows = |permission: &ClientPermission| {
+            permission.allows(
+                executable.as_deref(),
+                unit.as_deref(),
+                security.deny_by_default,
+            )
+        };
+        let mut virtual_keyboard_allowed = allows(&security.virtual_keyboard);
+        let mut virtual_pointer_allowed = allows(&security.virtual_pointer);
+        let mut data_control_allowed = allows(&security.data_control);
+        let mut screencopy_allowed = allows(&security.screencopy);
+        let mut output_management_allowed = allows(&security.output_management);
+
+        let denied: Vec<_> = [
+            (virtual_keyboard_allowed, "virtual keyboard"),
+            (virtual_pointer_allowed, "virtual pointer"),
+            (data_control_allowed, "data control"),
+            (screencopy_allowed, "screencopy"),
+            (output_management_allowed, "output management"),
+        ]
+        .into_iter()
+        .filter(|(allowed, _)| !allowed)
+        .map(|(_, name)| name)
+        .collect();
+        if !denied.is_empty() {
+            let denied = denied.join(", ");
+            if security.log_only {
+                info!("would deny new client {executable:?} in unit {unit:?}: {denied}");
+                virtual_keyboard_allowed = true;
+                virtual_pointer_allowed = true;
+                data_control_allowed = true;
+                screencopy_allowed = true;
+                output_management_allowed = true;
+            } else {
+                info!("denying new client {executable:?} in unit {unit:?}: {denied}");
+            }
+        }
+
+        let data = Arc::new(ClientState {
+            compositor_state: Default::default(),
+            can_view_decoration_globals: config.prefer_no_csd,
             primary_selection_disabled: config.clipboard.disable_primary,
             restricted,
             credentials_unknown,
+            virtual_keyboard_allowed,
+            virtual_pointer_allowed,
+            data_control_allowed,
+            screencopy_allowed,
+            output_management_allowed,
+            is_xwayland,
+            sandbox_app_id,
         });
 
         if let Err(err) = self.display_handle.insert_client(client, data) {
@@ -2795,7 +3547,7 @@ impl Niri {
                     name.connector, new_position.x, new_position.y
                 );
                 output.change_current_state(None, None, None, Some(new_position));
-                self.ipc_outputs_changed = true;
+                self.ipc_outputs_dirty = true;
                 self.queue_redraw(&output);
             }
         }
@@ -2808,11 +3560,10 @@ impl Niri {
 
         let config = self.config.borrow();
         let c = config.outputs.find(name);
-        let scale = c.and_then(|c| c.scale).map(|s| s.0).unwrap_or_else(|| {
-            let size_mm = output.physical_properties().size;
-            let resolution = output.current_mode().unwrap().size;
-            guess_monitor_scale(size_mm, resolution)
-        });
+        let scale = c
+            .and_then(|c| c.scale)
+            .map(|s| s.0)
+            .unwrap_or_else(|| default_output_scale(&output));
         let scale = closest_representable_scale(scale.clamp(0.1, 10.));
 
         let mut transform = c
@@ -2831,7 +3582,7 @@ impl Niri {
         backdrop_color[3] = 1.;
 
         // FIXME: fix winit damage on other transforms.
-        if name.connector == "winit" {
+        if name.connector.starts_with("winit") {
             transform = Transform::Flipped180;
         }
         drop(config);
@@ -2861,20 +3612,37 @@ impl Niri {
             unfinished_animations_remain: false,
             frame_clock: FrameClock::new(refresh_interval, vrr),
             last_drm_sequence: None,
+            last_render_time: None,
             frame_callback_sequence: 0,
             background_buffer: SolidColorBuffer::new(size, background_color),
             backdrop_buffer: SolidColorBuffer::new(size, backdrop_color),
             lock_render_state,
             lock_surface: None,
             lock_color_buffer: SolidColorBuffer::new(size, CLEAR_COLOR_LOCKED),
+            dim_buffer: SolidColorBuffer::new(size, [0., 0., 0., 1.]),
+            mirror_source: None,
+            mirror_buffer: OffscreenBuffer::default(),
+            is_powered_off: false,
             screen_transition: None,
             debug_damage_tracker: OutputDamageTracker::from_output(&output),
         };
         let rv = self.output_state.insert(output.clone(), state);
         assert!(rv.is_none(), "output was already tracked");
 
-        // Must be last since it will call queue_redraw(output) which needs things to be filled-in.
+        // Must be after the output state is filled-in since it will call queue_redraw(output).
         self.reposition_outputs(Some(&output));
+
+        // The new output may be a mirror, or a source for an existing mirror.
+        self.refresh_output_mirrors();
+
+        // Backdrop windows left without an output go to the new one.
+        for backdrop in &mut self.backdrop_windows {
+            if backdrop.output.is_none() {
+                backdrop.set_output(Some(output.clone()));
+            }
+        }
+
+        self.run_output_connect_hooks(&output);
     }
 
     pub fn remove_output(&mut self, output: &Output) {
@@ -2882,7 +3650,10 @@ impl Niri {
             layer.layer_surface().send_close();
         }
 
-        self.layout.remove_output(output);
+        // Mirrors aren't in the layout.
+        if !self.is_mirror(output) {
+            self.layout.remove_output(output);
+        }
         self.global_space.unmap_output(output);
         self.reposition_outputs(None);
         self.gamma_control_manager_state.output_removed(output);
@@ -2901,6 +3672,18 @@ impl Niri {
         self.stop_casts_for_target(CastTarget::Output(output.downgrade()));
 
         self.remove_screencopy_output(output);
+        self.wallpapers.remove_output(output);
+
+        // Mirrors of this output go back to showing their own workspaces.
+        self.refresh_output_mirrors();
+
+        // Backdrop windows move over to the active output, like the workspaces.
+        let new_output = self.layout.active_output().cloned();
+        for backdrop in &mut self.backdrop_windows {
+            if backdrop.output.as_ref() == Some(output) {
+                backdrop.set_output(new_output.clone());
+            }
+        }
 
         // Disable the output global and remove some time later to give the clients some time to
         // process it.
@@ -2968,13 +3751,22 @@ impl Niri {
             layer_map.arrange();
         }
 
-        self.layout.update_output_size(output);
+        if !self.is_mirror(output) {
+            self.layout.update_output_size(output);
+        }
+
+        for backdrop in &self.backdrop_windows {
+            if backdrop.output.as_ref() == Some(output) {
+                backdrop.configure();
+            }
+        }
 
         if let Some(state) = self.output_state.get_mut(output) {
             state.background_buffer.resize(output_size);
             state.backdrop_buffer.resize(output_size);
 
             state.lock_color_buffer.resize(output_size);
+            state.dim_buffer.resize(output_size);
             if let Some(lock_surface) = &state.lock_surface {
                 configure_lock_surface(lock_surface, output);
             }
@@ -3000,6 +3792,91 @@ impl Niri {
         self.queue_redraw(output);
     }
 
+    /// Returns whether this output is currently mirroring another output.
+    pub fn is_mirror(&self, output: &Output) -> bool {
+        self.output_state
+            .get(output)
+            .is_some_and(|state| state.mirror_source.is_some())
+    }
+
+    /// Adds outputs to or removes them from the layout according to their mirror config.
+    ///
+    /// An output only mirrors when its configured source is connected and is not a mirror itself;
+    /// otherwise it shows its own workspaces as usual.
+    pub fn refresh_output_mirrors(&mut self) {
+        let _span = tracy_client::span!("Niri::refresh_output_mirrors");
+
+        let config = self.config.borrow();
+        let configured_source = |output: &Output| {
+            let name = output.user_data().get::<OutputName>().unwrap();
+            config.outputs.find(name).and_then(|c| c.mirror.clone())
+        };
+
+        let outputs: Vec<_> = self.output_state.keys().cloned().collect();
+        let mut changes = vec![];
+        for output in &outputs {
+            let source = configured_source(output).and_then(|target| {
+                let source = outputs.iter().find(|source| {
+                    *source != output
+                        && output_matches_name(source, &target)
+                        && configured_source(source).is_none()
+                });
+
+                if source.is_none() {
+                    debug!(
+                        "output {} cannot mirror {target}: no such output, \
+                         or it is a mirror itself",
+                        output.name()
+                    );
+                }
+
+                source.cloned()
+            });
+
+            let state = &self.output_state[output];
+            if state.mirror_source != source {
+                changes.push((output.clone(), source));
+            }
+        }
+        drop(config);
+
+        if changes.is_empty() {
+            return;
+        }
+
+        for (output, source) in changes {
+            let state = self.output_state.get_mut(&output).unwrap();
+            let was_mirror = state.mirror_source.is_some();
+            let is_mirror = source.is_some();
+            state.mirror_source = source;
+            // Don't keep the old contents around.
+            state.mirror_buffer = OffscreenBuffer::default();
+
+            if let Some(source) = &state.mirror_source {
+                debug!(
+                    "output {} is now mirroring {}",
+                    output.name(),
+                    source.name()
+                );
+            } else {
+                debug!("output {} stopped mirroring", output.name());
+            }
+
+            if !was_mirror && is_mirror {
+                self.layout.remove_output(&output);
+                self.global_space.unmap_output(&output);
+            } else if was_mirror && !is_mirror {
+                self.layout.add_output(output.clone());
+                self.reposition_outputs(Some(&output));
+            }
+
+            self.queue_redraw(&output);
+        }
+
+        self.reposition_outputs(None);
+        self.ipc_outputs_dirty = true;
+    }
+
     pub fn deactivate_monitors(&mut self, backend: &mut Backend) {
         if !self.monitors_active {
             return;
@@ -3010,6 +3887,16 @@ impl Niri {
     }
 
     pub fn activate_monitors(&mut self, backend: &mut Backend) {
+        let powered_off: Vec<_> = self
+            .output_state
+            .iter()
+            .filter(|(_, state)| state.is_powered_off)
+            .map(|(output, _)| output.clone())
+            .collect();
+        for output in powered_off {
+            self.set_output_powered(backend, &output, true);
+        }
+
         if self.monitors_active {
             return;
         }
@@ -3020,6 +3907,23 @@ impl Niri {
         self.queue_redraw_all();
     }
 
+    /// Powers a single output off or on, independently of the other outputs.
+    pub fn set_output_powered(&mut self, backend: &mut Backend, output: &Output, powered: bool) {
+        let Some(state) = self.output_state.get_mut(output) else {
+            return;
+        };
+        if state.is_powered_off != powered {
+            return;
+        }
+
+        state.is_powered_off = !powered;
+        backend.set_output_active(output, powered);
+
+        if powered {
+            self.queue_redraw(output);
+        }
+    }
+
     pub fn output_under(&self, pos: Point<f64, Logical>) -> Option<(&Output, Point<f64, Logical>)> {
         let output = self.global_space.output_under(pos).next()?;
         let pos_within_output = pos
@@ -3533,6 +4437,24 @@ impl Niri {
         Some((target_output.cloned(), target_workspace_index))
     }
 
+    /// Returns the global geometry and the output of the focused tile, if the tablet must be
+    /// mapped to it.
+    pub fn focused_tile_for_tablet(&self) -> Option<(Rectangle<i32, Logical>, &Output)> {
+        if !self.config.borrow().input.tablet.map_to_focused_window {
+            return None;
+        }
+
+        if !self.keyboard_focus.is_layout() {
+            return None;
+        }
+
+        let output = self.layout.active_output()?;
+        let monitor = self.layout.monitor_for_output(output)?;
+        let mut rect = monitor.active_tile_visual_rectangle()?;
+        rect.loc += self.global_space.output_geometry(output)?.loc.to_f64();
+        Some((rect.to_i32_round(), output))
+    }
+
     pub fn output_for_tablet(&self) -> Option<&Output> {
         let config = self.config.borrow();
         let map_to_output = config.input.tablet.map_to_output.as_ref();
@@ -3561,6 +4483,11 @@ impl Niri {
             return output;
         }
 
+        // Check backdrop windows.
+        if let Some(idx) = self.backdrop_window_idx(root) {
+            return self.backdrop_windows[idx].output.as_ref();
+        }
+
         // Check layer-shell.
         let has_layer_surface = |o: &&Output| {
             layer_map_for_output(o)
@@ -3570,6 +4497,12 @@ impl Niri {
         self.layout.outputs().find(has_layer_surface)
     }
 
+    pub fn backdrop_window_idx(&self, surface: &WlSurface) -> Option<usize> {
+        self.backdrop_windows
+            .iter()
+            .position(|backdrop| backdrop.is_wl_surface(surface))
+    }
+
     pub fn lock_surface_focus(&self) -> Option<WlSurface> {
         let output_under_cursor = self.output_under_cursor();
         let output = output_under_cursor
@@ -3581,6 +4514,41 @@ impl Niri {
         state.lock_surface.as_ref().map(|s| s.wl_surface()).cloned()
     }
 
+    /// Enters the binds mode with the given name, or returns to the regular binds with `None`.
+    pub fn set_binds_mode(&mut self, mode: Option<String>) {
+        if self.binds_mode == mode {
+            return;
+        }
+
+        self.binds_mode_indicator.set_mode(mode.clone());
+        self.binds_mode = mode;
+        self.queue_redraw_all();
+    }
+
+    /// Shows a level from 0 to 1 in the on-screen display, hiding it after a while.
+    pub fn show_osd(&mut self, kind: niri_ipc::OsdKind, value: f64, muted: bool) {
+        if !self.osd.show(kind, value, muted) {
+            return;
+        }
+
+        if let Some(token) = self.osd_timer.take() {
+            self.event_loop.remove(token);
+        }
+        let timer = Timer::from_duration(self.osd.hide_after());
+        let token = self
+            .event_loop
+            .insert_source(timer, |_, _, state| {
+                state.niri.osd_timer = None;
+                state.niri.osd.hide();
+                state.niri.queue_redraw_all();
+                TimeoutAction::Drop
+            })
+            .unwrap();
+        self.osd_timer = Some(token);
+
+        self.queue_redraw_all();
+    }
+
     /// Schedules an immediate redraw on all outputs if one is not already scheduled.
     pub fn queue_redraw_all(&mut self) {
         for state in self.output_state.values_mut() {
@@ -3592,6 +4560,13 @@ impl Niri {
     pub fn queue_redraw(&mut self, output: &Output) {
         let state = self.output_state.get_mut(output).unwrap();
         state.redraw_state = mem::take(&mut state.redraw_state).queue_redraw();
+
+        // Mirrors show the contents of their source, so they need to redraw along with it.
+        for state in self.output_state.values_mut() {
+            if state.mirror_source.as_ref() == Some(output) {
+                state.redraw_state = mem::take(&mut state.redraw_state).queue_redraw();
+            }
+        }
     }
 
     pub fn redraw_queued_outputs(&mut self, backend: &mut Backend) {
@@ -3609,6 +4584,32 @@ impl Niri {
         }
     }
 
+    /// Waits before redrawing the output, like the estimated VBlank timer does.
+    fn delay_redraw(&mut self, output: &Output, delay: Duration) {
+        let state = self.output_state.get_mut(output).unwrap();
+        if let RedrawState::WaitingForEstimatedVBlankAndQueued(token) = state.redraw_state {
+            self.event_loop.remove(token);
+        }
+
+        let output_ = output.clone();
+        let timer = Timer::from_duration(delay);
+        let token = self
+            .event_loop
+            .insert_source(timer, move |_, _, state| {
+                let output = &output_;
+                if let Some(output_state) = state.niri.output_state.get_mut(output) {
+                    // We waited for the timer, now we can send frame callbacks again.
+                    output_state.frame_callback_sequence =
+                        output_state.frame_callback_sequence.wrapping_add(1);
+                    output_state.redraw_state = RedrawState::Idle;
+                    state.niri.queue_redraw(output);
+                }
+                TimeoutAction::Drop
+            })
+            .unwrap();
+        state.redraw_state = RedrawState::WaitingForEstimatedVBlank(token);
+    }
+
     pub fn pointer_element<R: NiriRenderer>(
         &self,
         renderer: &mut R,
@@ -3869,13 +4870,76 @@ impl Niri {
 
         self.idle_inhibiting_surfaces.retain(|s| s.is_alive());
 
-        let is_inhibited = self.is_fdo_idle_inhibited.load(Ordering::SeqCst)
-            || self.idle_inhibiting_surfaces.iter().any(|surface| {
-                with_states(surface, |states| {
-                    surface_primary_scanout_output(surface, states).is_some()
-                })
-            });
+        let is_inhibited = self.is_idle_inhibited();
         self.idle_notifier_state.set_is_inhibited(is_inhibited);
+
+        // The idle stages that came up while inhibited were skipped, so start them over.
+        if mem::replace(&mut self.was_idle_inhibited, is_inhibited) && !is_inhibited {
+            self.reset_idle_timers();
+        }
+    }
+
+    /// Applies the config values changed over IPC on top of a config loaded from the file.
+    ///
+    /// Values that have since changed in the file are forgotten, so that the file takes
+    /// precedence.
+    pub fn apply_config_overrides(&mut self, config: &mut Config) {
+        self.config_overrides
+            .retain(|o| config.get_value(&o.name).is_ok_and(|v| v == o.file_value));
+
+        for o in &self.config_overrides {
+            if let Err(err) = config.set_value(&o.name, &o.value) {
+                warn!("error applying config override: {err}");
+            }
+        }
+    }
+
+    pub fn is_idle_inhibited(&self) -> bool {
+        self.is_fdo_idle_inhibited.load(Ordering::SeqCst)
+            || self.idle_inhibiting_surfaces.iter().any(|surface| {
+                surface.is_alive()
+                    && with_states(surface, |states| {
+                        surface_primary_scanout_output(surface, states).is_some()
+                    })
+            })
+            || (self.config.borrow().idle.inhibit_when_fullscreen
+                && self.has_visible_fullscreen_window())
+    }
+
+    /// Returns whether any output currently shows a window with video or game content.
+    pub fn has_visible_video_or_game(&self) -> bool {
+        if !self.monitors_active {
+            return false;
+        }
+
+        self.global_space.outputs().any(|output| {
+            self.layout
+                .monitor_for_output(output)
+                .and_then(|mon| mon.active_window())
+                .is_some_and(|mapped| mapped.is_video_or_game())
+        })
+    }
+
+    /// Returns whether the output shows a fullscreen window with video or game content.
+    pub fn has_fullscreen_video_or_game(&self, output: &Output) -> bool {
+        self.layout
+            .monitor_for_output(output)
+            .and_then(|mon| mon.active_window())
+            .is_some_and(|mapped| mapped.is_fullscreen() && mapped.is_video_or_game())
+    }
+
+    /// Returns whether any output currently shows a fullscreen window.
+    fn has_visible_fullscreen_window(&self) -> bool {
+        if !self.monitors_active {
+            return false;
+        }
+
+        self.global_space.outputs().any(|output| {
+            self.layout
+                .monitor_for_output(output)
+                .and_then(|mon| mon.active_window())
+                .is_some_and(|mapped| mapped.is_fullscreen())
+        })
     }
 
     pub fn refresh_window_states(&mut self) {
@@ -3905,8 +4969,9 @@ impl Niri {
                 }
 
                 // Since refresh_window_rules() is called after refresh_layout(), we need to update
-                // the tiled state right here, so that it's picked up by the following
-                // send_pending_configure().
+                // the decoration mode and the tiled state right here, so that they're picked up by
+                // the following send_pending_configure().
+                mapped.update_decoration_mode();
                 mapped.update_tiled_state(config.prefer_no_csd);
             }
         });
@@ -3944,7 +5009,7 @@ impl Niri {
         let mut seen = HashSet::new();
         let mut output_changed = vec![];
 
-        self.layout.with_windows(|mapped, output, _| {
+        self.layout.with_windows(|mapped, output, _, _| {
             seen.insert(mapped.window.clone());
 
             let Some(output) = output else {
@@ -4052,6 +5117,10 @@ impl Niri {
             }
         }
 
+        if let Some(source) = &self.output_state.get(output).unwrap().mirror_source {
+            return self.render_mirror(renderer, output, source, include_pointer, target);
+        }
+
         let output_scale = Scale::from(output.current_scale().fractional_scale());
 
         // The pointer goes on the top.
@@ -4060,6 +5129,20 @@ impl Niri {
             elements = self.pointer_element(renderer, output);
         }
 
+        // Next, the idle dimming, which only applies to the screen itself.
+        if self.is_idle_dimmed && target == RenderTarget::Output {
+            let state = self.output_state.get(output).unwrap();
+            elements.push(
+                SolidColorRenderElement::from_buffer(
+                    &state.dim_buffer,
+                    (0., 0.),
+                    DIM_ALPHA,
+                    Kind::Unspecified,
+                )
+                .into(),
+            );
+        }
+
         // Next, the screen transition texture.
         {
             let state = self.output_state.get(output).unwrap();
@@ -4075,15 +5158,57 @@ impl Niri {
             }
         }
 
+        // Next, the window switcher, on the active output only.
+        if self.layout.active_output() == Some(output) {
+            if let Some(element) = self.window_switcher.render(renderer, output) {
+                elements.push(element.into());
+            }
+        }
+
         // Next, the config error notification too.
         if let Some(element) = self.config_error_notification.render(renderer, output) {
             elements.push(element.into());
         }
 
-        // If the session is locked, draw the lock surface.
-        if self.is_locked() {
+        // Next, the binds mode indicator.
+        if let Some(element) = self.binds_mode_indicator.render(renderer, output) {
+            elements.push(element.into());
+        }
+
+        // Next, the keyboard layout indicator, on the active output only.
+        if self.layout.active_output() == Some(output) {
+            if let Some(element) = self.layout_indicator.render(renderer, output) {
+                elements.push(element.into());
+            }
+        }
+
+        // Next, the on-screen display, on the active output only.
+        if self.layout.active_output() == Some(output) {
+            if let Some(element) = self.osd.render(renderer, output) {
+                elements.push(element.into());
+            }
+        }
+
+        // Next, the notification popups, on the active output only.
+        if self.layout.active_output() == Some(output) {
+            let position = self.config.borrow().notifications.position;
+            for element in self.notification_popups.render(renderer, output, position) {
+                elements.push(element.into());
+            }
+        }
+
+        // If the session is locked, draw the lock surface. While waiting for the lock surfaces,
+        // blank the outputs right away so that nothing sensitive remains visible.
+        //
+        // The lock surface itself is only shown on the screen; screen captures of a locked output
+        // get just the solid color.
+        if self.is_locking_or_locked() {
             let state = self.output_state.get(output).unwrap();
-            if let Some(surface) = state.lock_surface.as_ref() {
+            let lock_surface = state
+                .lock_surface
+                .as_ref()
+                .filter(|_| target == RenderTarget::Output);
+            if let Some(surface) = lock_surface {
                 elements.extend(render_elements_from_surface_tree(
                     renderer,
                     surface.wl_surface(),
@@ -4094,6 +5219,12 @@ impl Niri {
                 ));
             }
 
+            if target == RenderTarget::Output && self.shows_builtin_lock_screen(output) {
+                if let Some(element) = self.lock_screen.render(renderer, output) {
+                    elements.push(element.into());
+                }
+            }
+
             // Draw the solid color background.
             elements.push(
                 SolidColorRenderElement::from_buffer(
@@ -4163,6 +5294,9 @@ impl Niri {
                 .map(|(geo, iter)| (geo, Vec::from_iter(iter))),
         );
         let workspace_shadow_elements = Vec::from_iter(mon.render_workspace_shadows(renderer));
+        let wallpaper_elements = self.render_wallpaper(renderer, output);
+        let backdrop_window_elements = self.render_backdrop_windows(renderer, output);
+        let has_backdrop_window = !backdrop_window_elements.is_empty();
         let insert_hint_elements = mon.render_insert_hint_between_workspaces(renderer);
         let int_move_elements: Vec<_> = self
             .layout
@@ -4214,7 +5348,22 @@ impl Niri {
             elements.extend(top_layer.into_iter().map(OutputRenderElements::from));
             elements.extend(layer_elems.into_iter().map(OutputRenderElements::from));
 
-            elements.push(OutputRenderElements::from(background));
+            // The solid background stays underneath the wallpaper, which can be crossfading or
+            // still loading.
+            let has_wallpaper = !wallpaper_elements.is_empty();
+            elements.extend(
+                backdrop_window_elements
+                    .into_iter()
+                    .map(OutputRenderElements::from),
+            );
+            elements.extend(
+                wallpaper_elements
+                    .into_iter()
+                    .map(OutputRenderElements::from),
+            );
+            if has_wallpaper || !has_backdrop_window {
+                elements.push(OutputRenderElements::from(background));
+            }
 
             elements.extend(
                 workspace_shadow_elements
@@ -4236,7 +5385,20 @@ impl Niri {
                     .map(OutputRenderElements::from),
             );
 
-            for (ws_geo, ws_elements) in monitor_elements {
+            // Workspaces with a wallpaper or a backdrop window show it through instead of the
+            // solid background.
+            let has_wallpaper = {
+                let config = self.config.borrow();
+                let name = output.user_data().get::<OutputName>().unwrap();
+                let output_config = config.outputs.find(name);
+                Vec::from_iter(
+                    mon.workspaces_with_render_geo()
+                        .map(|(ws, _)| workspace_wallpaper(&config, output_config, ws).is_some()),
+                )
+            };
+            let any_wallpaper = has_wallpaper.contains(&true);
+
+            for ((ws_geo, ws_elements), has_wallpaper) in zip(monitor_elements, has_wallpaper) {
                 // Collect all other layer-shell elements.
                 let mut layer_elems = SplitElements::default();
                 extend_from_layer(&mut layer_elems, Layer::Bottom, false);
@@ -4260,6 +5422,10 @@ impl Niri {
                         .map(OutputRenderElements::from),
                 );
 
+                if has_wallpaper || has_backdrop_window {
+                    continue;
+                }
+
                 if let Some(elem) =
                     scale_relocate_crop(background.clone(), output_scale, zoom, ws_geo)
                 {
@@ -4267,6 +5433,24 @@ impl Niri {
                 }
             }
 
+            // Backdrop windows and the wallpaper stay in place as the workspaces move.
+            elements.extend(
+                backdrop_window_elements
+                    .into_iter()
+                    .map(OutputRenderElements::from),
+            );
+            elements.extend(
+                wallpaper_elements
+                    .into_iter()
+                    .map(OutputRenderElements::from),
+            );
+
+            // The solid background stays underneath the wallpaper, which can be crossfading or
+            // still loading.
+            if any_wallpaper {
+                elements.push(OutputRenderElements::from(background));
+            }
+
             elements.extend(
                 workspace_shadow_elements
                     .into_iter()
@@ -4288,42 +5472,204 @@ impl Niri {
         elements
     }
 
-    #[allow(clippy::too_many_arguments)]
-    fn render_layer<R: NiriRenderer>(
+    /// Renders the backdrop windows of an output, spanning the whole output.
+    fn render_backdrop_windows<R: NiriRenderer>(
         &self,
         renderer: &mut R,
-        target: RenderTarget,
-        layer_map: &LayerMap,
-        layer: Layer,
-        elements: &mut SplitElements<LayerSurfaceRenderElement<R>>,
-        for_backdrop: bool,
-    ) {
-        // LayerMap returns layers in reverse stacking order.
-        let iter = layer_map.layers_on(layer).rev().filter_map(|surface| {
-            let mapped = self.mapped_layer_surfaces.get(surface)?;
+        output: &Output,
+    ) -> Vec<WaylandSurfaceRenderElement<R>> {
+        let scale = Scale::from(output.current_scale().fractional_scale());
 
-            if for_backdrop != mapped.place_within_backdrop() {
-                return None;
+        let mut elements = vec![];
+
+        // Windows mapped later go on top.
+        for backdrop in self.backdrop_windows.iter().rev() {
+            if backdrop.output.as_ref() != Some(output) {
+                continue;
             }
 
-            let geo = layer_map.layer_geometry(surface)?;
-            Some((mapped, geo))
-        });
-        for (mapped, geo) in iter {
-            elements.extend(mapped.render(renderer, geo.loc.to_f64(), target));
+            let buf_pos = Point::from((0., 0.)) - backdrop.window.geometry().loc.to_f64();
+            elements.extend(render_elements_from_surface_tree(
+                renderer,
+                backdrop.toplevel().wl_surface(),
+                buf_pos.to_physical_precise_round(scale),
+                scale,
+                1.,
+                Kind::Unspecified,
+            ));
         }
-    }
 
-    fn redraw(&mut self, backend: &mut Backend, output: &Output) {
-        let _span = tracy_client::span!("Niri::redraw");
+        elements
+    }
 
-        // Verify our invariant.
+    /// Renders the wallpapers of the workspaces in view, crossfading between them.
+    fn render_wallpaper<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+    ) -> Vec<PrimaryGpuTextureRenderElement> {
+        let mut elements = vec![];
+
+        let Some(mon) = self.layout.monitor_for_output(output) else {
+            return elements;
+        };
+
+        let config = self.config.borrow();
+        let name = output.user_data().get::<OutputName>().unwrap();
+        let output_config = config.outputs.find(name);
+        let background_color = output_config
+            .and_then(|c| c.background_color)
+            .unwrap_or(config.layout.background_color);
+
+        let (ws, next) = mon.workspaces_for_crossfade();
+        let wallpaper = workspace_wallpaper(&config, output_config, ws);
+
+        if let Some((next, progress)) = next {
+            let next_wallpaper = workspace_wallpaper(&config, output_config, next);
+            if let Some(next_wallpaper) = next_wallpaper.filter(|w| Some(*w) != wallpaper) {
+                elements.extend(self.wallpapers.render(
+                    renderer,
+                    output,
+                    next_wallpaper,
+                    background_color,
+                    progress as f32,
+                ));
+            }
+        }
+
+        if let Some(wallpaper) = wallpaper {
+            elements.extend(self.wallpapers.render(
+                renderer,
+                output,
+                wallpaper,
+                background_color,
+                1.,
+            ));
+        }
+
+        elements
+    }
+
+    /// Renders the source output scaled to fit the mirror output, letterboxing the rest.
+    fn render_mirror<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+        source: &Output,
+        include_pointer: bool,
+        target: RenderTarget,
+    ) -> Vec<OutputRenderElements<R>> {
+        let _span = tracy_client::span!("Niri::render_mirror");
+
+        let mut elements = vec![];
+        let state = self.output_state.get(output).unwrap();
+
+        // Render the source without its transform, so that the buffer is upright in logical
+        // coordinates.
+        let source_size = source
+            .current_transform()
+            .transform_size(source.current_mode().unwrap().size);
+        let source_scale = Scale::from(source.current_scale().fractional_scale());
+        let output_scale = Scale::from(output.current_scale().fractional_scale());
+
+        let renderer = renderer.as_gles_renderer();
+        let source_elements =
+            self.render::<GlesRenderer>(renderer, source, include_pointer, target);
+        // Elements can stick out of the source, so crop them to keep the buffer at its size.
+        let source_rect = Rectangle::from_size(source_size);
+        let source_elements: Vec<_> = source_elements
+            .iter()
+            .filter_map(|elem| CropRenderElement::from_element(elem, source_scale, source_rect))
+            .collect();
+        let res = state
+            .mirror_buffer
+            .render(renderer, source_scale, &source_elements);
+
+        match res {
+            Ok((elem, _sync_point, _data)) => {
+                // Scale to fit while keeping the aspect ratio, and center.
+                let from = output_size(source);
+                let to = output_size(output);
+                let zoom = f64::min(to.w / from.w, to.h / from.h);
+                let size = from.upscale(zoom);
+                let loc = Point::from(((to.w - size.w) / 2., (to.h - size.h) / 2.));
+
+                let elem = RescaleRenderElement::from_element(elem, Point::from((0, 0)), zoom);
+                let elem = RelocateRenderElement::from_element(
+                    elem,
+                    loc.to_physical_precise_round(output_scale),
+                    Relocate::Relative,
+                );
+                elements.push(elem.into());
+            }
+            Err(err) => {
+                warn!("error rendering {} for mirroring: {err:?}", source.name());
+            }
+        }
+
+        // The background shows through as letterboxing.
+        elements.push(
+            SolidColorRenderElement::from_buffer(
+                &state.background_buffer,
+                (0., 0.),
+                1.,
+                Kind::Unspecified,
+            )
+            .into(),
+        );
+
+        elements
+    }
+
+    fn render_layer<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        target: RenderTarget,
+        layer_map: &LayerMap,
+        layer: Layer,
+        elements: &mut SplitElements<LayerSurfaceRenderElement<R>>,
+        for_backdrop: bool,
+    ) {
+        // LayerMap returns layers in reverse stacking order.
+        let iter = layer_map.layers_on(layer).rev().filter_map(|surface| {
+            let mapped = self.mapped_layer_surfaces.get(surface)?;
+
+            if for_backdrop != mapped.place_within_backdrop() {
+                return None;
+            }
+
+            let geo = layer_map.layer_geometry(surface)?;
+            Some((mapped, geo))
+        });
+        for (mapped, geo) in iter {
+            elements.extend(mapped.render(renderer, geo.loc.to_f64(), target));
+        }
+    }
+
+    fn redraw(&mut self, backend: &mut Backend, output: &Output) {
+        let _span = tracy_client::span!("Niri::redraw");
+
+        // Verify our invariant.
         let state = self.output_state.get_mut(output).unwrap();
         assert!(matches!(
             state.redraw_state,
             RedrawState::Queued | RedrawState::WaitingForEstimatedVBlankAndQueued(_)
         ));
 
+        // Limit the frame rate while saving power.
+        let last_render_time = state.last_render_time;
+        if let Some(min_interval) = self.min_frame_interval() {
+            let next_render_time = last_render_time.map(|time| time + min_interval);
+            let now = get_monotonic_time();
+            if let Some(delay) = next_render_time.and_then(|time| time.checked_sub(now)) {
+                if !delay.is_zero() {
+                    self.delay_redraw(output, delay);
+                    return;
+                }
+            }
+        }
+
+        let state = self.output_state.get_mut(output).unwrap();
         let target_presentation_time = state.frame_clock.next_presentation_time();
 
         // Freeze the clock at the target time.
@@ -4331,8 +5677,11 @@ impl Niri {
 
         self.update_render_elements(Some(output));
 
+        let state = self.output_state.get_mut(output).unwrap();
+        let is_powered_on = self.monitors_active && !state.is_powered_off;
+
         let mut res = RenderResult::Skipped;
-        if self.monitors_active {
+        if is_powered_on {
             let state = self.output_state.get_mut(output).unwrap();
             state.unfinished_animations_remain = self.layout.are_animations_ongoing(Some(output));
             state.unfinished_animations_remain |=
@@ -4360,6 +5709,10 @@ impl Niri {
         let is_locked = self.is_locked();
         let state = self.output_state.get_mut(output).unwrap();
 
+        if res != RenderResult::Skipped {
+            state.last_render_time = Some(get_monotonic_time());
+        }
+
         if res == RenderResult::Skipped {
             // Update the redraw state on failed render.
             state.redraw_state = if let RedrawState::WaitingForEstimatedVBlank(token)
@@ -4375,7 +5728,7 @@ impl Niri {
         // Update the lock render state on successful render, or if monitors are inactive. When
         // monitors are inactive on a TTY, they have no framebuffer attached, so no sensitive data
         // from a last render will be visible.
-        if res != RenderResult::Skipped || !self.monitors_active {
+        if res != RenderResult::Skipped || !is_powered_on {
             state.lock_render_state = if is_locked {
                 LockRenderState::Locked
             } else {
@@ -4456,7 +5809,12 @@ impl Niri {
         }
 
         let current = self.layout.windows_for_output(output).any(|mapped| {
-            mapped.rules().variable_refresh_rate == Some(true) && {
+            // Without a window rule, videos and games turn VRR on by their content type.
+            let wants_vrr = mapped
+                .rules()
+                .variable_refresh_rate
+                .unwrap_or_else(|| mapped.is_video_or_game());
+            wants_vrr && {
                 let mut visible = false;
                 mapped.window.with_surfaces(|surface, states| {
                     if !visible
@@ -4525,6 +5883,23 @@ impl Niri {
         // The reason to do this at all is that it keeps track of whether the surface is visible or
         // not in a unified way with the pointer surfaces, which makes the logic elsewhere simpler.
 
+        for backdrop in &self.backdrop_windows {
+            if backdrop.output.as_ref() != Some(output) {
+                continue;
+            }
+
+            backdrop.window.with_surfaces(|surface, states| {
+                update_surface_primary_scanout_output(
+                    surface,
+                    output,
+                    states,
+                    render_element_states,
+                    // Backdrop windows are shown only on one output at a time.
+                    |_, _, output, _| output,
+                );
+            });
+        }
+
         for mapped in self.layout.windows_for_output(output) {
             let win = &mapped.window;
             let offscreen_data = mapped.offscreen_data();
@@ -4631,6 +6006,25 @@ impl Niri {
             );
         }
 
+        for backdrop in &self.backdrop_windows {
+            if backdrop.output.as_ref() != Some(output) {
+                continue;
+            }
+
+            backdrop.window.send_dmabuf_feedback(
+                output,
+                |_, _| Some(output.clone()),
+                |surface, _| {
+                    select_dmabuf_feedback(
+                        surface,
+                        render_element_states,
+                        &feedback.render,
+                        &feedback.scanout,
+                    )
+                },
+            );
+        }
+
         for surface in layer_map_for_output(output).layers() {
             surface.send_dmabuf_feedback(
                 output,
@@ -4695,9 +6089,29 @@ impl Niri {
         }
     }
 
+    /// Returns how often to send frame callbacks to the hidden windows, if at all.
+    fn hidden_window_frame_interval(&self) -> Option<Duration> {
+        let max_fps = self.config.borrow().hidden_windows.max_fps;
+        (max_fps > 0).then(|| Duration::from_secs(1) / u32::from(max_fps))
+    }
+
+    /// Returns the throttling for frame callbacks to windows not visible on the output.
+    fn hidden_window_frame_throttle(&self) -> Option<Duration> {
+        // Leave some leeway for the timer, like with FRAME_CALLBACK_THROTTLE.
+        self.hidden_window_frame_interval()
+            .map(|interval| interval * 199 / 200)
+    }
+
+    fn frame_callback_fallback_interval(&self) -> Duration {
+        let interval = Duration::from_secs(1);
+        self.hidden_window_frame_interval()
+            .map_or(interval, |x| x.min(interval))
+    }
+
     pub fn send_frame_callbacks(&mut self, output: &Output) {
         let _span = tracy_client::span!("Niri::send_frame_callbacks");
 
+        let window_throttle = self.hidden_window_frame_throttle();
         let state = self.output_state.get(output).unwrap();
         let sequence = state.frame_callback_sequence;
 
@@ -4737,7 +6151,15 @@ impl Niri {
         let frame_callback_time = get_monotonic_time();
 
         for mapped in self.layout.windows_for_output_mut(output) {
-            mapped.send_frame(
+            mapped.send_frame(output, frame_callback_time, window_throttle, should_send);
+        }
+
+        for backdrop in &self.backdrop_windows {
+            if backdrop.output.as_ref() != Some(output) {
+                continue;
+            }
+
+            backdrop.window.send_frame(
                 output,
                 frame_callback_time,
                 FRAME_CALLBACK_THROTTLE,
@@ -4802,14 +6224,19 @@ impl Niri {
 
         let frame_callback_time = get_monotonic_time();
 
+        let window_throttle = self.hidden_window_frame_throttle();
         self.layout.with_windows_mut(|mapped, _| {
-            mapped.send_frame(
+            mapped.send_frame(output, frame_callback_time, window_throttle, |_, _| None);
+        });
+
+        for backdrop in &self.backdrop_windows {
+            backdrop.window.send_frame(
                 output,
                 frame_callback_time,
                 FRAME_CALLBACK_THROTTLE,
                 |_, _| None,
             );
-        });
+        }
 
         for (output, state) in self.output_state.iter() {
             for surface in layer_map_for_output(output).layers() {
@@ -4892,6 +6319,20 @@ impl Niri {
             )
         }
 
+        for backdrop in &self.backdrop_windows {
+            if backdrop.output.as_ref() != Some(output) {
+                continue;
+            }
+
+            backdrop.window.take_presentation_feedback(
+                &mut feedback,
+                surface_primary_scanout_output,
+                |surface, _| {
+                    surface_presentation_feedback_flags_from_states(surface, render_element_states)
+                },
+            );
+        }
+
         for surface in layer_map_for_output(output).layers() {
             surface.take_presentation_feedback(
                 &mut feedback,
@@ -5006,6 +6447,11 @@ impl Niri {
                 continue;
             };
 
+            // Don't leak window contents while the session is locked, or about to be.
+            if self.is_locking_or_locked() {
+                continue;
+            }
+
             let mut windows = self.layout.windows_for_output(output);
             let Some(mapped) = windows.find(|win| win.id().get() == id) else {
                 continue;
@@ -5416,15 +6862,15 @@ impl Niri {
         let _span = tracy_client::span!("Niri::screenshot_window");
 
         let scale = Scale::from(output.current_scale().fractional_scale());
-        let alpha = if mapped.is_fullscreen() || mapped.is_ignoring_opacity_window_rule() {
+        let alpha = if mapped.is_fullscreen() {
             1.
         } else {
-            mapped.rules().opacity.unwrap_or(1.).clamp(0., 1.)
+            mapped.opacity()
         };
         // FIXME: pointer.
         let elements = mapped.render(
             renderer,
-            mapped.window.geometry().loc.to_f64(),
+            mapped.geometry().loc.to_f64(),
             scale,
             alpha,
             RenderTarget::ScreenCapture,
@@ -5606,6 +7052,14 @@ impl Niri {
         }
     }
 
+    /// Returns whether the session is locked or in the process of locking.
+    ///
+    /// Unlike [`Self::is_locked()`], this includes waiting for the lock surfaces, when the outputs
+    /// are already blanked.
+    pub fn is_locking_or_locked(&self) -> bool {
+        !matches!(self.lock_state, LockState::Unlocked)
+    }
+
     pub fn lock(&mut self, confirmation: SessionLocker) {
         // Check if another client is in the process of locking.
         if matches!(
@@ -5636,6 +7090,8 @@ impl Niri {
         }
 
         info!("locking session");
+        self.run_lock_hooks();
+        self.start_lock_screen();
 
         if self.output_state.is_empty() {
             // There are no outputs, lock the session right away.
@@ -5665,6 +7121,9 @@ impl Niri {
                 confirmation,
                 deadline_token,
             };
+
+            // Blank the outputs immediately.
+            self.queue_redraw_all();
         }
     }
 
@@ -5723,6 +7182,7 @@ impl Niri {
 
     pub fn unlock(&mut self) {
         info!("unlocking session");
+        self.stop_lock_screen();
 
         let prev = mem::take(&mut self.lock_state);
         if let LockState::WaitingForSurfaces { deadline_token, .. } = prev {
@@ -5735,6 +7195,95 @@ impl Niri {
         self.queue_redraw_all();
     }
 
+    /// Starts keeping the built-in lock screen up to date.
+    fn start_lock_screen(&mut self) {
+        if !self.lock_screen_sources.is_empty() {
+            return;
+        }
+
+        self.lock_screen.update_clock();
+        let timer = Timer::from_duration(lock_screen::CLOCK_INTERVAL);
+        let token = self
+            .event_loop
+            .insert_source(timer, |_, _, state| {
+                if state.niri.lock_screen.update_clock() {
+                    state.niri.queue_redraw_all();
+                }
+                TimeoutAction::ToDuration(lock_screen::CLOCK_INTERVAL)
+            })
+            .unwrap();
+        self.lock_screen_sources.push(token);
+
+        #[cfg(feature = "dbus")]
+        if self.config.borrow().lock_screen.show_media {
+            let (to_niri, from_media) = calloop::channel::channel();
+            let token = self
+                .event_loop
+                .insert_source(from_media, |event, _, state| {
+                    if let calloop::channel::Event::Msg(media) = event {
+                        if state.niri.lock_screen.set_media(media) {
+                            state.niri.queue_redraw_all();
+                        }
+                    }
+                })
+                .unwrap();
+            self.lock_screen_sources.push(token);
+            self.mpris.watch_media(to_niri);
+        }
+    }
+
+    fn stop_lock_screen(&mut self) {
+        for token in self.lock_screen_sources.drain(..) {
+            self.event_loop.remove(token);
+        }
+        #[cfg(feature = "dbus")]
+        self.mpris.unwatch_media();
+        self.lock_screen.set_media(None);
+    }
+
+    /// Whether to draw the built-in lock screen on the output.
+    ///
+    /// It's shown while locked without a lock surface, for example if the lock screen client
+    /// crashed.
+    fn shows_builtin_lock_screen(&self, output: &Output) -> bool {
+        if !self.is_locked() || self.config.borrow().lock_screen.off {
+            return false;
+        }
+
+        let state = &self.output_state[output];
+        !state
+            .lock_surface
+            .as_ref()
+            .is_some_and(|surface| is_mapped(surface.wl_surface()))
+    }
+
+    /// Whether some output shows the media controls of the built-in lock screen.
+    #[cfg(feature = "dbus")]
+    pub fn builtin_lock_screen_shows_media(&self) -> bool {
+        self.lock_screen.has_media()
+            && self
+                .global_space
+                .outputs()
+                .any(|output| self.shows_builtin_lock_screen(output))
+    }
+
+    /// Returns the built-in lock screen media control under the pointer, if any.
+    #[cfg(feature = "dbus")]
+    pub fn lock_screen_media_key_under_pointer(&self) -> Option<MediaKey> {
+        let pos = self.seat.get_pointer().unwrap().current_location();
+        let (output, pos_within_output) = self.output_under(pos)?;
+        if !self.shows_builtin_lock_screen(output) {
+            return None;
+        }
+
+        let key = match self.lock_screen.control_at(output, pos_within_output)? {
+            MediaControl::Previous => MediaKey::Previous,
+            MediaControl::PlayPause => MediaKey::PlayPause,
+            MediaControl::Next => MediaKey::Next,
+        };
+        Some(key)
+    }
+
     pub fn new_lock_surface(&mut self, surface: LockSurface, output: &Output) {
         let lock = match &self.lock_state {
             LockState::Unlocked => {
@@ -5773,6 +7322,10 @@ impl Niri {
             return;
         }
 
+        if self.pointer_constraint_released.as_ref() == Some(surface) {
+            return;
+        }
+
         let pointer = &self.seat.get_pointer().unwrap();
         with_pointer_constraint(surface, pointer, |constraint| {
             let Some(constraint) = constraint else { return };
@@ -5793,6 +7346,28 @@ impl Niri {
         });
     }
 
+    /// Deactivates the pointer constraint of the surface under the pointer, if any.
+    pub fn release_pointer_constraint(&mut self) {
+        let Some((surface, _)) = &self.pointer_contents.surface else {
+            return;
+        };
+
+        let pointer = &self.seat.get_pointer().unwrap();
+        let mut released = false;
+        with_pointer_constraint(surface, pointer, |constraint| {
+            let Some(constraint) = constraint else { return };
+
+            if constraint.is_active() {
+                constraint.deactivate();
+                released = true;
+            }
+        });
+
+        if released {
+            self.pointer_constraint_released = Some(surface.clone());
+        }
+    }
+
     pub fn focus_layer_surface_if_on_demand(&mut self, surface: Option<LayerSurface>) {
         if let Some(surface) = surface {
             if surface.cached_state().keyboard_interactivity
@@ -5834,6 +7409,80 @@ impl Niri {
         root.clone()
     }
 
+    /// Closes the notification popup, if it's shown.
+    pub fn close_notification(&mut self, id: u32, reason: CloseReason) {
+        let Some(timer) = self.notification_popups.close(id) else {
+            return;
+        };
+
+        if let Some(timer) = timer {
+            self.event_loop.remove(timer);
+        }
+        self.queue_redraw_all();
+
+        #[cfg(feature = "dbus")]
+        self.emit_notification_closed(id, reason);
+        #[cfg(not(feature = "dbus"))]
+        let _ = reason;
+    }
+
+    /// Closes all notification popups.
+    pub fn dismiss_notifications(&mut self) {
+        let popups = self.notification_popups.close_all();
+        if popups.is_empty() {
+            return;
+        }
+
+        for (_id, timer) in popups {
+            if let Some(timer) = timer {
+                self.event_loop.remove(timer);
+            }
+
+            #[cfg(feature = "dbus")]
+            self.emit_notification_closed(_id, CloseReason::Dismissed);
+        }
+        self.queue_redraw_all();
+    }
+
+    #[cfg(feature = "dbus")]
+    fn emit_notification_closed(&self, id: u32, reason: CloseReason) {
+        let Some(dbus) = &self.dbus else { return };
+        let Some(conn_notifications) = dbus.conn_notifications.clone() else {
+            return;
+        };
+
+        let res = thread::Builder::new()
+            .name("Notifications NotificationClosed Emitter".to_owned())
+            .spawn(move || {
+                use crate::dbus::freedesktop_notifications::Notifications;
+                let _span = tracy_client::span!("NotificationClosed");
+                let iface = match conn_notifications
+                    .object_server()
+                    .interface::<_, Notifications>("/org/freedesktop/Notifications")
+                {
+                    Ok(iface) => iface,
+                    Err(err) => {
+                        warn!("error getting Notifications interface: {err:?}");
+                        return;
+                    }
+                };
+
+                async_io::block_on(async move {
+                    let signal_emitter = iface.signal_emitter();
+                    let reason = reason as u32;
+                    if let Err(err) =
+                        Notifications::notification_closed(signal_emitter, id, reason).await
+                    {
+                        warn!("error emitting NotificationClosed: {err:?}");
+                    }
+                });
+            });
+
+        if let Err(err) = res {
+            warn!("error spawning a thread to send NotificationClosed: {err:?}");
+        }
+    }
+
     #[cfg(feature = "dbus")]
     pub fn on_ipc_outputs_changed(&self) {
         let _span = tracy_client::span!("Niri::on_ipc_outputs_changed");
@@ -5873,6 +7522,14 @@ impl Niri {
     }
 
     pub fn handle_focus_follows_mouse(&mut self, new_focus: &PointContents) {
+        // Moving away from the window cancels its delayed focus.
+        if let Some((pending, token)) = &self.focus_follows_mouse_timer {
+            if new_focus.window.as_ref().map(|(w, _)| w) != Some(pending) {
+                self.event_loop.remove(*token);
+                self.focus_follows_mouse_timer = None;
+            }
+        }
+
         let Some(ffm) = self.config.borrow().input.focus_follows_mouse else {
             return;
         };
@@ -5895,26 +7552,15 @@ impl Niri {
             if !self.layout.is_overview_open() && current_focus.window.as_ref() != Some(window) {
                 let (window, hit) = window;
 
-                // Don't trigger focus-follows-mouse over the tab indicator.
-                if matches!(
-                    hit,
-                    HitType::Activate {
-                        is_tab_indicator: true
-                    }
-                ) {
+                if !self.should_focus_follows_mouse(ffm, window, hit) {
                     return;
                 }
 
-                if !self.layout.should_trigger_focus_follows_mouse_on(window) {
+                if let Some(delay) = ffm.delay_ms.filter(|delay| *delay > 0) {
+                    self.schedule_focus_follows_mouse(window.clone(), delay);
                     return;
                 }
 
-                if let Some(threshold) = ffm.max_scroll_amount {
-                    if self.layout.scroll_amount_to_activate(window) > threshold.0 {
-                        return;
-                    }
-                }
-
                 self.layout.activate_window_without_raising(window);
                 self.layer_shell_on_demand_focus = None;
             }
@@ -5927,6 +7573,90 @@ impl Niri {
         }
     }
 
+    fn should_focus_follows_mouse(
+        &self,
+        ffm: FocusFollowsMouse,
+        window: &Window,
+        hit: &HitType,
+    ) -> bool {
+        // Don't trigger focus-follows-mouse over the tab indicator.
+        if matches!(
+            hit,
+            HitType::Activate {
+                is_tab_indicator: true,
+                ..
+            }
+        ) {
+            return false;
+        }
+
+        if ffm.content_only && !matches!(hit, HitType::Input { .. }) {
+            return false;
+        }
+
+        if !self.layout.should_trigger_focus_follows_mouse_on(window) {
+            return false;
+        }
+
+        if ffm.ignore_while_scrolling && self.layout.is_view_scrolling_on(window) {
+            return false;
+        }
+
+        if let Some(threshold) = ffm.max_scroll_amount {
+            if self.layout.scroll_amount_to_activate(window) > threshold.0 {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn schedule_focus_follows_mouse(&mut self, window: Window, delay_ms: u16) {
+        if let Some((pending, token)) = &self.focus_follows_mouse_timer {
+            if *pending == window {
+                return;
+            }
+            self.event_loop.remove(*token);
+        }
+
+        let timer = Timer::from_duration(Duration::from_millis(u64::from(delay_ms)));
+        let token = self
+            .event_loop
+            .insert_source(timer, |_, _, state| {
+                let niri = &mut state.niri;
+                if let Some((window, _)) = niri.focus_follows_mouse_timer.take() {
+                    niri.focus_follows_mouse_delayed(&window);
+                }
+                TimeoutAction::Drop
+            })
+            .unwrap();
+        self.focus_follows_mouse_timer = Some((window, token));
+    }
+
+    fn focus_follows_mouse_delayed(&mut self, window: &Window) {
+        let Some(ffm) = self.config.borrow().input.focus_follows_mouse else {
+            return;
+        };
+
+        let pointer = self.seat.get_pointer().unwrap();
+        if pointer.is_grabbed() || self.layout.is_overview_open() {
+            return;
+        }
+
+        // Check that the pointer is still over the window, and things didn't change under it.
+        let contents = self.contents_under(pointer.current_location());
+        let Some((under, hit)) = &contents.window else {
+            return;
+        };
+        if under != window || !self.should_focus_follows_mouse(ffm, window, hit) {
+            return;
+        }
+
+        self.layout.activate_window_without_raising(window);
+        self.layer_shell_on_demand_focus = None;
+        self.queue_redraw_all();
+    }
+
     pub fn do_screen_transition(&mut self, renderer: &mut GlesRenderer, delay_ms: Option<u16>) {
         let _span = tracy_client::span!("Niri::do_screen_transition");
 
@@ -6105,6 +7835,7 @@ impl Niri {
         let _span = tracy_client::span!("Niri::notify_activity");
 
         self.idle_notifier_state.notify_activity(&self.seat);
+        self.reset_idle_timers();
 
         self.notified_activity_this_iteration = true;
     }
@@ -6114,6 +7845,8 @@ pub struct NewClient {
     pub client: UnixStream,
     pub restricted: bool,
     pub credentials_unknown: bool,
+    /// App id of the sandboxed app, from the security context.
+    pub sandbox_app_id: Option<String>,
 }
 
 pub struct ClientState {
@@ -6124,6 +7857,20 @@ pub struct ClientState {
     pub restricted: bool,
     /// We cannot retrieve this client's socket credentials.
     pub credentials_unknown: bool,
+    /// Whether this client may bind the virtual keyboard global.
+    pub virtual_keyboard_allowed: bool,
+    /// Whether this client may bind the virtual pointer global.
+    pub virtual_pointer_allowed: bool,
+    /// Whether this client may bind the data control globals.
+    pub data_control_allowed: bool,
+    /// Whether this client may bind the screencopy global.
+    pub screencopy_allowed: bool,
+    /// Whether this client may bind the output management global.
+    pub output_management_allowed: bool,
+    /// Whether this client is xwayland-satellite, so its windows are X11 windows.
+    pub is_xwayland: bool,
+    /// App id of the sandboxed app, like a Flatpak id, from the security context.
+    pub sandbox_app_id: Option<String>,
 }
 
 impl ClientData for ClientState {
@@ -6131,6 +7878,108 @@ impl ClientData for ClientState {
     fn disconnected(&self, _client_id: ClientId, _reason: DisconnectReason) {}
 }
 
+impl BackdropWindow {
+    pub fn new(window: Window, output: Option<Output>) -> Self {
+        let mut rv = Self {
+            window,
+            output: None,
+        };
+        rv.set_output(output);
+        rv
+    }
+
+    pub fn toplevel(&self) -> &ToplevelSurface {
+        self.window.toplevel().expect("no X11 support")
+    }
+
+    pub fn is_wl_surface(&self, surface: &WlSurface) -> bool {
+        self.toplevel().wl_surface() == surface
+    }
+
+    /// Moves the window over to span a different output.
+    pub fn set_output(&mut self, output: Option<Output>) {
+        if let Some(old) = &self.output {
+            self.window.output_leave(old);
+        }
+
+        self.output = output;
+        self.configure();
+    }
+
+    /// Updates the window for the current size and scale of its output.
+    pub fn configure(&self) {
+        if let Some(output) = &self.output {
+            let overlap = Rectangle::from_size(Size::from((i32::MAX, i32::MAX)));
+            self.window.output_enter(output, overlap);
+
+            let scale = output.current_scale();
+            let transform = output.current_transform();
+            self.window.with_surfaces(|surface, data| {
+                send_scale_transform(surface, data, scale, transform);
+            });
+        }
+
+        let toplevel = self.toplevel();
+        set_backdrop_window_pending_state(toplevel, self.output.as_ref());
+        toplevel.send_pending_configure();
+    }
+}
+
+/// Sets the pending state of a backdrop window to span the output.
+pub fn set_backdrop_window_pending_state(toplevel: &ToplevelSurface, output: Option<&Output>) {
+    toplevel.with_pending_state(|state| {
+        state.size = output.map(|output| output_size(output).to_i32_round());
+        // Fullscreen makes clients drop their decorations and fill the size exactly.
+        state.states.set(xdg_toplevel::State::Fullscreen);
+    });
+}
+
+/// Returns the scale for an output that has none set in the config.
+fn default_output_scale(output: &Output) -> f64 {
+    if let Some(scale) = output
+        .user_data()
+        .get::<VirtualOutput>()
+        .and_then(|v| v.scale)
+    {
+        return scale;
+    }
+
+    let size_mm = output.physical_properties().size;
+    let resolution = output.current_mode().unwrap().size;
+    guess_monitor_scale(size_mm, resolution)
+}
+
+/// Returns whether the executable is the configured xwayland-satellite.
+fn is_xwayland_satellite(config: &Config, executable: &Path) -> bool {
+    let xwls = &config.xwayland_satellite;
+    if xwls.off {
+        return false;
+    }
+
+    // Compare just the file names since the config path may be relative or go through symlinks.
+    let path = Path::new(&xwls.path);
+    path.file_name().is_some() && executable.file_name() == path.file_name()
+}
+
+/// Returns the wallpaper for a workspace.
+///
+/// Named workspaces can set their own wallpaper, otherwise the output wallpaper is used.
+fn workspace_wallpaper<'a>(
+    config: &'a Config,
+    output_config: Option<&'a niri_config::Output>,
+    ws: &Workspace<Mapped>,
+) -> Option<&'a Wallpaper> {
+    ws.name()
+        .and_then(|name| {
+            config
+                .workspaces
+                .iter()
+                .find(|w| w.name.0.eq_ignore_ascii_case(name))
+        })
+        .and_then(|w| w.wallpaper.as_ref())
+        .or_else(|| output_config.and_then(|c| c.wallpaper.as_ref()))
+}
+
 fn scale_relocate_crop<E: Element>(
     elem: E,
     output_scale: Scale<f64>,
@@ -6159,6 +8008,7 @@ niri_render_elements! {
         >>>,
         ScreenshotUi = ScreenshotUiRenderElement,
         Texture = PrimaryGpuTextureRenderElement,
+        Mirror = RelocateRenderElement<RescaleRenderElement<OffscreenRenderElement>>,
         // Used for the CPU-rendered panels.
         RelocatedMemoryBuffer = RelocateRenderElement<MemoryRenderBufferRenderElement<R>>,
     }
@@ -0,0 +1,164 @@
+//! Degrading effects to save power while on battery or in the power-saver profile.
+
+use std::time::Duration;
+
+use niri_config::Config;
+
+use crate::niri::{Niri, State};
+
+/// Power status reported by the system services.
+#[derive(Debug, Default)]
+pub struct PowerStatus {
+    pub on_battery: bool,
+    pub power_saver_profile: bool,
+    /// Whether niri renders on the CPU.
+    pub software_rendering: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum PowerStatusChange {
+    OnBattery(bool),
+    PowerSaverProfile(bool),
+    SoftwareRendering(bool),
+}
+
+impl PowerStatus {
+    /// Returns whether the config asks to save power in this status.
+    pub fn is_power_saving(&self, config: &Config) -> bool {
+        let config = &config.power_saving;
+        (config.on_battery && self.on_battery)
+            || (config.power_saver_profile && self.power_saver_profile)
+            || (config.software_rendering && self.software_rendering)
+    }
+}
+
+impl Niri {
+    pub fn is_power_saving(&self) -> bool {
+        self.power_status.is_power_saving(&self.config.borrow())
+    }
+
+    /// Applies the animation settings, taking power saving into account.
+    pub fn update_animation_clock(&mut self) {
+        let config = self.config.borrow();
+        let is_power_saving = self.power_status.is_power_saving(&config);
+
+        let mut slowdown = config.animations.slowdown.0;
+        let mut off = config.animations.off;
+        if is_power_saving {
+            if let Some(x) = config.power_saving.animation_slowdown {
+                slowdown *= x.0;
+            }
+            off |= config.power_saving.disable_animations;
+        }
+
+        let rate = 1.0 / slowdown.max(0.001);
+        self.clock.set_rate(rate);
+        self.clock.set_complete_instantly(off);
+    }
+
+    /// Returns the shortest time between frames on an output, if the frame rate is limited.
+    pub fn min_frame_interval(&self) -> Option<Duration> {
+        let config = self.config.borrow();
+        if !self.power_status.is_power_saving(&config) {
+            return None;
+        }
+
+        let max_fps = config.power_saving.max_fps.filter(|fps| *fps > 0)?;
+        Some(Duration::from_secs(1) / u32::from(max_fps))
+    }
+}
+
+impl State {
+    pub fn on_power_status_change(&mut self, change: PowerStatusChange) {
+        debug!("power status changed: {change:?}");
+
+        let was_power_saving = self.niri.is_power_saving();
+        match change {
+            PowerStatusChange::OnBattery(x) => self.niri.power_status.on_battery = x,
+            PowerStatusChange::PowerSaverProfile(x) => {
+                self.niri.power_status.power_saver_profile = x
+            }
+            PowerStatusChange::SoftwareRendering(x) => {
+                self.niri.power_status.software_rendering = x
+            }
+        }
+
+        let is_power_saving = self.niri.is_power_saving();
+        if was_power_saving == is_power_saving {
+            return;
+        }
+
+        self.niri.update_animation_clock();
+
+        let config = self.niri.config.borrow();
+        self.niri.layout.set_power_saving(&config, is_power_saving);
+        drop(config);
+
+        self.niri.queue_redraw_all();
+    }
+}
+
+/// Watches UPower and power-profiles-daemon for power status changes.
+#[cfg(feature = "dbus")]
+pub fn watch_power_status(to_niri: calloop::channel::Sender<PowerStatusChange>) {
+    let to_niri_ = to_niri.clone();
+    watch_property(
+        "UPower",
+        ("org.freedesktop.UPower", "/org/freedesktop/UPower"),
+        "OnBattery",
+        move |on_battery: bool| to_niri_.send(PowerStatusChange::OnBattery(on_battery)),
+    );
+
+    watch_property(
+        "PowerProfiles",
+        ("net.hadess.PowerProfiles", "/net/hadess/PowerProfiles"),
+        "ActiveProfile",
+        move |profile: String| {
+            let is_power_saver = profile == "power-saver";
+            to_niri.send(PowerStatusChange::PowerSaverProfile(is_power_saver))
+        },
+    );
+}
+
+/// Sends the property value, and then every change to it, from a thread.
+#[cfg(feature = "dbus")]
+fn watch_property<T, E>(
+    name: &str,
+    (destination, path): (&'static str, &'static str),
+    property: &'static str,
+    send: impl Fn(T) -> Result<(), E> + Send + 'static,
+) where
+    T: TryFrom<zbus::zvariant::OwnedValue> + Unpin + Send + 'static,
+    T::Error: Into<zbus::Error>,
+{
+    let res = std::thread::Builder::new()
+        .name(format!("{name} Watcher"))
+        .spawn(move || {
+            let run = || -> anyhow::Result<()> {
+                let conn = zbus::blocking::Connection::system()?;
+                // The services use their bus name for the interface name too.
+                let proxy = zbus::blocking::Proxy::new(&conn, destination, path, destination)?;
+
+                let changes = proxy.receive_property_changed::<T>(property);
+                if send(proxy.get_property(property)?).is_err() {
+                    return Ok(());
+                }
+
+                for change in changes {
+                    if send(change.get()?).is_err() {
+                        break;
+                    }
+                }
+
+                Ok(())
+            };
+
+            if let Err(err) = run() {
+                debug!("error watching {destination} {property}: {err:?}");
+            }
+        });
+
+    if let Err(err) = res {
+        warn!("error spawning a thread to watch {destination}: {err:?}");
+    }
+}
@@ -11,6 +11,7 @@ use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::reexports::wayland_server::{
     Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
 };
+use smithay::wayland::foreign_toplevel_list::{ForeignToplevelHandle, ForeignToplevelListState};
 use smithay::wayland::shell::xdg::{ToplevelStateSet, XdgToplevelSurfaceRoleAttributes};
 use wayland_protocols_wlr::foreign_toplevel::v1::server::{
     zwlr_foreign_toplevel_handle_v1, zwlr_foreign_toplevel_manager_v1,
@@ -43,6 +44,8 @@ struct ToplevelData {
     states: ArrayVec<u32, 3>,
     output: Option<Output>,
     instances: HashMap<ZwlrForeignToplevelHandleV1, Vec<WlOutput>>,
+    /// Handle in the ext-foreign-toplevel-list protocol.
+    list_handle: ForeignToplevelHandle,
     // FIXME: parent.
 }
 
@@ -74,6 +77,7 @@ pub fn refresh(state: &mut State) {
     let _span = tracy_client::span!("foreign_toplevel::refresh");
 
     let protocol_state = &mut state.niri.foreign_toplevel_state;
+    let list_state = &mut state.niri.foreign_toplevel_list_state;
 
     // Handle closed windows.
     protocol_state.toplevels.retain(|surface, data| {
@@ -84,6 +88,7 @@ pub fn refresh(state: &mut State) {
         for instance in data.instances.keys() {
             instance.closed();
         }
+        list_state.remove_toplevel(&data.list_handle);
 
         false
     });
@@ -93,14 +98,14 @@ pub fn refresh(state: &mut State) {
     // Save the focused window for last, this way when the focus changes, we will first deactivate
     // the previous window and only then activate the newly focused window.
     let mut focused = None;
-    state.niri.layout.with_windows(|mapped, output, _| {
+    state.niri.layout.with_windows(|mapped, output, _, _| {
         let toplevel = mapped.toplevel();
         let wl_surface = toplevel.wl_surface();
         with_toplevel_role(toplevel, |role| {
             if state.niri.keyboard_focus.surface() == Some(wl_surface) {
                 focused = Some((mapped.window.clone(), output.cloned()));
             } else {
-                refresh_toplevel(protocol_state, wl_surface, role, output, false);
+                refresh_toplevel(protocol_state, list_state, wl_surface, role, output, false);
             }
         });
     });
@@ -110,7 +115,14 @@ pub fn refresh(state: &mut State) {
         let toplevel = window.toplevel().expect("no X11 support");
         let wl_surface = toplevel.wl_surface();
         with_toplevel_role(toplevel, |role| {
-            refresh_toplevel(protocol_state, wl_surface, role, output.as_ref(), true);
+            refresh_toplevel(
+                protocol_state,
+                list_state,
+                wl_surface,
+                role,
+                output.as_ref(),
+                true,
+            );
         });
     }
 }
@@ -142,6 +154,7 @@ pub fn on_output_bound(state: &mut State, output: &Output, wl_output: &WlOutput)
 
 fn refresh_toplevel(
     protocol_state: &mut ForeignToplevelManagerState,
+    list_state: &mut ForeignToplevelListState,
     wl_surface: &WlSurface,
     role: &XdgToplevelSurfaceRoleAttributes,
     output: Option<&Output>,
@@ -215,6 +228,16 @@ fn refresh_toplevel(
                     }
                     instance.done();
                 }
+
+                if new_title.is_some() || new_app_id.is_some() {
+                    if let Some(new_title) = new_title {
+                        data.list_handle.send_title(new_title);
+                    }
+                    if let Some(new_app_id) = new_app_id {
+                        data.list_handle.send_app_id(new_app_id);
+                    }
+                    data.list_handle.send_done();
+                }
             }
 
             for outputs in data.instances.values_mut() {
@@ -224,12 +247,18 @@ fn refresh_toplevel(
         }
         Entry::Vacant(entry) => {
             // New window, start tracking it.
+            let list_handle = list_state.new_toplevel::<State>(
+                role.title.clone().unwrap_or_default(),
+                role.app_id.clone().unwrap_or_default(),
+            );
+
             let mut data = ToplevelData {
                 title: role.title.clone(),
                 app_id: role.app_id.clone(),
                 states,
                 output: output.cloned(),
                 instances: HashMap::new(),
+                list_handle,
             };
 
             for manager in &protocol_state.instances {
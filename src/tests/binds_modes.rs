@@ -0,0 +1,111 @@
+use niri_config::Config;
+use niri_ipc::Action;
+
+use super::*;
+
+// XKB key codes.
+const ESCAPE: u32 = 9;
+const RETURN: u32 = 36;
+
+const CONFIG: &str = r##"
+binds-mode "resize" {
+    Return { exit-mode; }
+}
+
+binds-mode "escape" {
+    Escape { focus-column-left; }
+}
+"##;
+
+fn fixture() -> Fixture {
+    let config = Config::parse("test.kdl", CONFIG).unwrap();
+    Fixture::with_config(config)
+}
+
+fn tap(f: &mut Fixture, key_code: u32) {
+    f.key(key_code, true);
+    f.key(key_code, false);
+}
+
+fn enter_mode(f: &mut Fixture, name: &str) {
+    f.action(Action::EnterMode {
+        name: String::from(name),
+    });
+}
+
+fn binds_mode(f: &mut Fixture) -> Option<String> {
+    f.niri().binds_mode.clone()
+}
+
+#[test]
+fn enter_and_exit_mode() {
+    let mut f = fixture();
+    assert_eq!(binds_mode(&mut f), None);
+
+    enter_mode(&mut f, "resize");
+    assert_eq!(binds_mode(&mut f).as_deref(), Some("resize"));
+
+    // The mode binds Return to exit-mode.
+    tap(&mut f, RETURN);
+    assert_eq!(binds_mode(&mut f), None);
+}
+
+#[test]
+fn unknown_mode_is_ignored() {
+    let mut f = fixture();
+
+    enter_mode(&mut f, "nonexistent");
+    assert_eq!(binds_mode(&mut f), None);
+
+    enter_mode(&mut f, "resize");
+    enter_mode(&mut f, "nonexistent");
+    assert_eq!(binds_mode(&mut f).as_deref(), Some("resize"));
+}
+
+#[test]
+fn escape_exits_mode_without_escape_bind() {
+    let mut f = fixture();
+
+    enter_mode(&mut f, "resize");
+    tap(&mut f, ESCAPE);
+    assert_eq!(binds_mode(&mut f), None);
+}
+
+#[test]
+fn escape_bound_in_mode_does_not_exit() {
+    let mut f = fixture();
+
+    enter_mode(&mut f, "escape");
+    tap(&mut f, ESCAPE);
+    assert_eq!(binds_mode(&mut f).as_deref(), Some("escape"));
+
+    f.action(Action::ExitMode {});
+    assert_eq!(binds_mode(&mut f), None);
+}
+
+#[test]
+fn reload_keeps_existing_mode() {
+    let mut f = fixture();
+
+    enter_mode(&mut f, "resize");
+
+    let config = Config::parse("test.kdl", CONFIG).unwrap();
+    f.niri_state().reload_config(Ok(config));
+    assert_eq!(binds_mode(&mut f).as_deref(), Some("resize"));
+}
+
+#[test]
+fn reload_removing_active_mode_exits_it() {
+    let mut f = fixture();
+
+    enter_mode(&mut f, "resize");
+
+    let config = r##"
+binds-mode "escape" {
+    Escape { focus-column-left; }
+}
+"##;
+    let config = Config::parse("test.kdl", config).unwrap();
+    f.niri_state().reload_config(Ok(config));
+    assert_eq!(binds_mode(&mut f), None);
+}
@@ -1,5 +1,7 @@
 use client::ClientId;
 use insta::assert_snapshot;
+use niri_config::Config;
+use smithay::utils::Size;
 use wayland_client::protocol::wl_surface::WlSurface;
 
 use super::*;
@@ -153,3 +155,51 @@ fn windowed_fullscreen_chain() {
     "
     );
 }
+
+#[test]
+fn force_windowed_fullscreen_rule() {
+    let config = r##"
+window-rule {
+    force-windowed-fullscreen true
+}
+"##;
+    let config = Config::parse("test.kdl", config).unwrap();
+    let mut f = Fixture::with_config(config);
+    f.add_output(1, (1920, 1080));
+
+    let id = f.add_client();
+    let window = f.client(id).create_window();
+    let surface = window.surface.clone();
+    window.commit();
+    f.roundtrip(id);
+
+    let window = f.client(id).window(&surface);
+    window.attach_new_buffer();
+    window.set_size(100, 100);
+    window.ack_last_and_commit();
+    f.double_roundtrip(id);
+
+    let _ = f.client(id).window(&surface).recent_configures();
+
+    // Fullscreen requests turn into windowed fullscreen.
+    f.client(id).window(&surface).set_fullscreen(None);
+    f.double_roundtrip(id);
+
+    let window = f.client(id).window(&surface);
+    assert_snapshot!(
+        window.format_recent_configures(),
+        @"size: 936 × 1048, bounds: 1888 × 1048, states: [Activated, Fullscreen]"
+    );
+
+    // Commit in response with the output size.
+    let window = f.client(id).window(&surface);
+    window.set_size(1920, 1080);
+    window.ack_last_and_commit();
+    f.roundtrip(id);
+
+    let mapped = f.niri().layout.windows().next().unwrap().1;
+    assert!(!mapped.is_fullscreen());
+    assert!(mapped.is_windowed_fullscreen());
+    // The window is kept to its tile size.
+    assert_eq!(mapped.size(), Size::from((936, 1048)));
+}
@@ -0,0 +1,78 @@
+use insta::assert_snapshot;
+use niri_config::Config;
+use niri_ipc::{Action, SizeChange};
+
+use super::*;
+
+// The snapshots come from hit-testing, not from rendering.
+//
+// Tiles fill the whole output with nothing around them, so the snapshots show only the tiles.
+const CONFIG: &str = r##"
+layout {
+    gaps 0
+    focus-ring {
+        off
+    }
+    default-column-width { proportion 1.0; }
+}
+
+animations {
+    off
+}
+"##;
+
+#[test]
+fn top_bar_buttons() {
+    let config = Config::parse("test.kdl", CONFIG).unwrap();
+    let mut f = Fixture::with_config(config);
+    f.add_output(1, (200, 100));
+
+    let id = f.add_client();
+    f.open_window(id);
+
+    assert_snapshot!(f.format_hit_map(1, 8), @r"
+    =00===========1122233=44=
+    =00===========1122233=44=
+    =00===========1122233=44=
+    aaaaaaaaaaaaaaaaaaaaaaaaa
+    aaaaaaaaaaaaaaaaaaaaaaaaa
+    aaaaaaaaaaaaaaaaaaaaaaaaa
+    aaaaaaaaaaaaaaaaaaaaaaaaa
+    aaaaaaaaaaaaaaaaaaaaaaaaa
+    aaaaaaaaaaaaaaaaaaaaaaaaa
+    aaaaaaaaaaaaaaaaaaaaaaaaa
+    aaaaaaaaaaaaaaaaaaaaaaaaa
+    aaaaaaaaaaaaaaaaaaaaaaaaa
+    ");
+}
+
+#[test]
+fn top_bar_buttons_in_narrow_window() {
+    let config = Config::parse("test.kdl", CONFIG).unwrap();
+    let mut f = Fixture::with_config(config);
+    f.add_output(1, (200, 100));
+
+    let id = f.add_client();
+    let surface = f.open_window(id);
+
+    f.action(Action::SetColumnWidth {
+        change: SizeChange::SetProportion(50.),
+    });
+    f.commit_configured(id, &surface);
+
+    // The screenshot button doesn't fit next to the other buttons, so it's hidden.
+    assert_snapshot!(f.format_hit_map(1, 8), @r"
+    =11=22=33444.............
+    =11=22=33444.............
+    =11=22=33444.............
+    aaaaaaaaaaaa.............
+    aaaaaaaaaaaa.............
+    aaaaaaaaaaaa.............
+    aaaaaaaaaaaa.............
+    aaaaaaaaaaaa.............
+    aaaaaaaaaaaa.............
+    aaaaaaaaaaaa.............
+    aaaaaaaaaaaa.............
+    aaaaaaaaaaaa.............
+    ");
+}
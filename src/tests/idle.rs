@@ -0,0 +1,57 @@
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+
+use niri_config::Config;
+
+use super::*;
+
+fn wait_for_timers(f: &mut Fixture) {
+    thread::sleep(Duration::from_millis(20));
+    f.dispatch_server();
+}
+
+#[test]
+fn dims_after_timeout() {
+    let config = r##"
+idle {
+    dim-timeout-ms 1
+}
+"##;
+    let config = Config::parse("test.kdl", config).unwrap();
+    let mut f = Fixture::with_config(config);
+    assert!(!f.niri().is_idle_dimmed);
+
+    wait_for_timers(&mut f);
+    assert!(f.niri().is_idle_dimmed);
+
+    f.niri().notify_activity();
+    assert!(!f.niri().is_idle_dimmed);
+}
+
+#[test]
+fn rearms_stages_after_inhibitor_goes_away() {
+    let config = r##"
+idle {
+    dim-timeout-ms 1
+}
+"##;
+    let config = Config::parse("test.kdl", config).unwrap();
+    let mut f = Fixture::with_config(config);
+
+    let inhibited = f.niri().is_fdo_idle_inhibited.clone();
+    inhibited.store(true, Ordering::SeqCst);
+    f.niri().refresh_idle_inhibit();
+
+    // The stage comes up while inhibited and gets skipped.
+    wait_for_timers(&mut f);
+    assert!(!f.niri().is_idle_dimmed);
+    assert!(f.niri().idle_timers.iter().all(Option::is_none));
+
+    inhibited.store(false, Ordering::SeqCst);
+    f.niri().refresh_idle_inhibit();
+    assert!(f.niri().idle_timers.iter().any(Option::is_some));
+
+    wait_for_timers(&mut f);
+    assert!(f.niri().is_idle_dimmed);
+}
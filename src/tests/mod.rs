@@ -1,10 +1,12 @@
-use fixture::Fixture;
-
-mod client;
-mod fixture;
-mod server;
+use crate::harness::{client, Fixture};
 
+mod binds_modes;
 mod floating;
 mod fullscreen;
+mod hit_areas;
+mod idle;
+mod output_profiles;
+mod suspended;
 mod transactions;
 mod window_opening;
+mod window_rules;
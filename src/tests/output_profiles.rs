@@ -0,0 +1,95 @@
+use niri_config::Config;
+
+use super::*;
+
+fn output_scale(f: &mut Fixture, name: &str) -> Option<f64> {
+    let config = f.niri().config.borrow();
+    let output = config.outputs.0.iter().find(|output| output.name == name)?;
+    output.scale.map(|scale| scale.0)
+}
+
+fn reload(f: &mut Fixture, config: &str) {
+    let config = Config::parse("test.kdl", config).unwrap();
+    f.niri_state().reload_config(Ok(config));
+}
+
+#[test]
+fn selects_matching_profile() {
+    let config = r##"
+output-profile "docked" {
+    output "headless-1" {
+        scale 1
+    }
+    output "headless-2" {
+        scale 1.5
+    }
+}
+
+output-profile "single" {
+    output "headless-1" {
+        scale 2
+    }
+}
+"##;
+    let config = Config::parse("test.kdl", config).unwrap();
+    let mut f = Fixture::with_config(config);
+
+    f.add_output(1, (1920, 1080));
+    f.niri_state().refresh_output_profile(false);
+    assert_eq!(f.niri().active_output_profile.as_deref(), Some("single"));
+    assert_eq!(output_scale(&mut f, "headless-1"), Some(2.));
+
+    f.add_output(2, (1920, 1080));
+    f.niri_state().refresh_output_profile(false);
+    assert_eq!(f.niri().active_output_profile.as_deref(), Some("docked"));
+    assert_eq!(output_scale(&mut f, "headless-1"), Some(1.));
+    assert_eq!(output_scale(&mut f, "headless-2"), Some(1.5));
+}
+
+#[test]
+fn reload_reapplies_edited_profile() {
+    let config = r##"
+output-profile "single" {
+    output "headless-1" {
+        scale 2
+    }
+}
+"##;
+    let config = Config::parse("test.kdl", config).unwrap();
+    let mut f = Fixture::with_config(config);
+    f.add_output(1, (1920, 1080));
+    f.niri_state().refresh_output_profile(false);
+    assert_eq!(output_scale(&mut f, "headless-1"), Some(2.));
+
+    let config = r##"
+output-profile "single" {
+    output "headless-1" {
+        scale 1.5
+    }
+}
+"##;
+    reload(&mut f, config);
+    assert_eq!(f.niri().active_output_profile.as_deref(), Some("single"));
+    assert_eq!(output_scale(&mut f, "headless-1"), Some(1.5));
+}
+
+#[test]
+fn reload_drops_removed_profile() {
+    let config = r##"
+output-profile "single" {
+    output "headless-1" {
+        scale 2
+    }
+}
+"##;
+    let config = Config::parse("test.kdl", config).unwrap();
+    let mut f = Fixture::with_config(config);
+    f.add_output(1, (1920, 1080));
+    f.niri_state().refresh_output_profile(false);
+    assert_eq!(output_scale(&mut f, "headless-1"), Some(2.));
+
+    // No profile matches anymore, and the profile's output settings must go away.
+    reload(&mut f, "");
+    assert_eq!(f.niri().active_output_profile, None);
+    assert_eq!(output_scale(&mut f, "headless-1"), None);
+}
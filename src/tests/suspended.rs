@@ -0,0 +1,82 @@
+use client::ClientId;
+use niri_config::Config;
+use niri_ipc::Action;
+use wayland_client::protocol::wl_surface::WlSurface;
+
+use super::*;
+
+// Every column fills the output, so opening a second window scrolls the first one out of view.
+const CONFIG: &str = r##"
+layout {
+    gaps 0
+    default-column-width { proportion 1.0; }
+}
+
+animations {
+    off
+}
+"##;
+
+// Returns whether the first and the second window are suspended.
+fn suspended(f: &mut Fixture) -> (bool, bool) {
+    let mut windows = f.niri().layout.windows().map(|(_, mapped)| mapped.is_suspended());
+    (windows.next().unwrap(), windows.next().unwrap())
+}
+
+// Opens two windows and returns the surface of the first one, which ends up out of view.
+fn set_up() -> (Fixture, ClientId, WlSurface) {
+    let config = Config::parse("test.kdl", CONFIG).unwrap();
+    let mut f = Fixture::with_config(config);
+    f.add_output(1, (200, 100));
+
+    let id = f.add_client();
+    let surface = f.open_window(id);
+    f.open_window(id);
+    f.double_roundtrip(id);
+
+    (f, id, surface)
+}
+
+#[test]
+fn window_out_of_view_is_suspended() {
+    let (mut f, _, _) = set_up();
+    assert_eq!(suspended(&mut f), (true, false));
+}
+
+#[test]
+fn window_cast_target_is_not_suspended() {
+    let (mut f, id, _) = set_up();
+
+    f.niri()
+        .layout
+        .with_windows_mut(|mapped, _| mapped.set_is_window_cast_target(true));
+    f.double_roundtrip(id);
+    assert_eq!(suspended(&mut f), (false, false));
+
+    // Once the cast stops, the window out of view goes back to being suspended.
+    f.niri()
+        .layout
+        .with_windows_mut(|mapped, _| mapped.set_is_window_cast_target(false));
+    f.double_roundtrip(id);
+    assert_eq!(suspended(&mut f), (true, false));
+}
+
+#[test]
+fn window_screenshot_wakes_suspended_window() {
+    let (mut f, id, surface) = set_up();
+
+    let (_, mapped) = f.niri().layout.windows().next().unwrap();
+    let window_id = mapped.id().get();
+
+    f.action(Action::ScreenshotWindow {
+        id: Some(window_id),
+        write_to_disk: false,
+    });
+    f.double_roundtrip(id);
+    assert_eq!(suspended(&mut f), (false, false));
+
+    // The screenshot is taken once the window draws again, then it's suspended again.
+    f.commit_configured(id, &surface);
+    f.double_roundtrip(id);
+    assert_eq!(suspended(&mut f), (true, false));
+}
@@ -403,6 +403,40 @@ post-map configures:
     assert_snapshot!(snapshot);
 }
 
+#[test]
+fn open_on_output_fallback() {
+    let config = r##"
+window-rule {
+    open-on-output "headless-3" "headless-2" "headless-1"
+}
+"##;
+    let config = Config::parse("config.kdl", config).unwrap();
+
+    let mut f = Fixture::with_config(config);
+    f.add_output(1, (1280, 720));
+    f.add_output(2, (1920, 1080));
+
+    let id = f.add_client();
+    let window = f.client(id).create_window();
+    let surface = window.surface.clone();
+    window.commit();
+    f.roundtrip(id);
+
+    let window = f.client(id).window(&surface);
+    window.attach_new_buffer();
+    window.ack_last_and_commit();
+    f.double_roundtrip(id);
+
+    // headless-3 doesn't exist, so the window opens on the next output in the list.
+    let (mon, _, _) = f
+        .niri()
+        .layout
+        .workspaces()
+        .find(|(_, _, ws)| ws.windows().next().is_some())
+        .unwrap();
+    assert_eq!(mon.unwrap().output_name(), "headless-2");
+}
+
 #[test]
 fn target_size() {
     if std::env::var_os("RUN_SLOW_TESTS").is_none() {
@@ -0,0 +1,89 @@
+use client::ClientId;
+use niri_config::{Color, Config};
+use wayland_client::protocol::wl_surface::WlSurface;
+
+use super::*;
+use crate::layout::LayoutElement;
+
+const CONFIG: &str = r##"
+window-rule {
+    match title="^red$"
+    background-color "#ff0000"
+}
+
+window-rule {
+    match title="^translucent$"
+    background-color "#0000ff80"
+}
+
+animations {
+    off
+}
+"##;
+
+// Opens a window with the given title, so that title rules apply from the start.
+fn open_window_with_title(f: &mut Fixture, id: ClientId, title: &str) -> WlSurface {
+    let window = f.client(id).create_window();
+    let surface = window.surface.clone();
+    window.set_title(title);
+    window.commit();
+
+    f.commit_configured(id, &surface);
+    surface
+}
+
+fn background_colors(f: &mut Fixture) -> Vec<Option<Color>> {
+    f.niri()
+        .layout
+        .windows()
+        .map(|(_, mapped)| mapped.rules().background_color)
+        .collect()
+}
+
+#[test]
+fn background_color_rule() {
+    let config = Config::parse("test.kdl", CONFIG).unwrap();
+    let mut f = Fixture::with_config(config);
+    f.add_output(1, (1920, 1080));
+
+    let id = f.add_client();
+    open_window_with_title(&mut f, id, "red");
+    open_window_with_title(&mut f, id, "translucent");
+    open_window_with_title(&mut f, id, "other");
+
+    assert_eq!(
+        background_colors(&mut f),
+        [
+            Some(Color::from_rgba8_unpremul(255, 0, 0, 255)),
+            Some(Color::from_rgba8_unpremul(0, 0, 255, 128)),
+            None,
+        ]
+    );
+}
+
+#[test]
+fn background_color_rule_follows_title() {
+    let config = Config::parse("test.kdl", CONFIG).unwrap();
+    let mut f = Fixture::with_config(config);
+    f.add_output(1, (1920, 1080));
+
+    let id = f.add_client();
+    let surface = open_window_with_title(&mut f, id, "other");
+    assert_eq!(background_colors(&mut f), [None]);
+
+    let window = f.client(id).window(&surface);
+    window.set_title("red");
+    window.commit();
+    f.double_roundtrip(id);
+    assert_eq!(
+        background_colors(&mut f),
+        [Some(Color::from_rgba8_unpremul(255, 0, 0, 255))]
+    );
+
+    // Once the title stops matching, the window goes back to having no background.
+    let window = f.client(id).window(&surface);
+    window.set_title("other");
+    window.commit();
+    f.double_roundtrip(id);
+    assert_eq!(background_colors(&mut f), [None]);
+}
@@ -1,6 +1,7 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
 
+use niri_config::PowerMenuEntry;
 use ordered_float::NotNan;
 use pangocairo::cairo::{self, ImageSurface};
 use pangocairo::pango::{Alignment, FontDescription};
@@ -17,29 +18,45 @@ use crate::utils::{output_size, to_physical_precise_round};
 
 const TEXT: &str = "Are you sure you want to exit niri?\n\n\
                     Press <span face='mono' bgcolor='#2C2C2C'> Enter </span> to confirm.";
+const MENU_TITLE: &str = "What do you want to do?";
+const MENU_HINT: &str = "Select with <span face='mono' bgcolor='#2C2C2C'> ↑ </span> \
+                         <span face='mono' bgcolor='#2C2C2C'> ↓ </span>, \
+                         confirm with <span face='mono' bgcolor='#2C2C2C'> Enter </span>.";
 const PADDING: i32 = 16;
 const FONT: &str = "sans 14px";
 const BORDER: i32 = 8;
 
+/// Dialog confirming the exit, which turns into a power menu when it has entries.
 pub struct ExitConfirmDialog {
     is_open: bool,
+    entries: Vec<PowerMenuEntry>,
+    selected: usize,
     buffers: RefCell<HashMap<NotNan<f64>, Option<MemoryBuffer>>>,
 }
 
 impl ExitConfirmDialog {
-    pub fn new() -> anyhow::Result<Self> {
+    pub fn new(entries: Vec<PowerMenuEntry>) -> anyhow::Result<Self> {
+        let text = text(&entries, 0);
         Ok(Self {
             is_open: false,
+            entries,
+            selected: 0,
             buffers: RefCell::new(HashMap::from([(
                 NotNan::new(1.).unwrap(),
-                Some(render(1.)?),
+                Some(render(1., &text)?),
             )])),
         })
     }
 
+    pub fn set_entries(&mut self, entries: Vec<PowerMenuEntry>) {
+        self.entries = entries;
+        self.select(0);
+    }
+
     pub fn show(&mut self) -> bool {
         if !self.is_open {
             self.is_open = true;
+            self.select(0);
             true
         } else {
             false
@@ -59,6 +76,40 @@ impl ExitConfirmDialog {
         self.is_open
     }
 
+    pub fn has_entries(&self) -> bool {
+        !self.entries.is_empty()
+    }
+
+    /// Returns the selected entry, or `None` for the plain exit confirmation.
+    pub fn selected(&self) -> Option<PowerMenuEntry> {
+        self.entries.get(self.selected).copied()
+    }
+
+    pub fn select_next(&mut self) {
+        if !self.entries.is_empty() {
+            self.select((self.selected + 1) % self.entries.len());
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        if !self.entries.is_empty() {
+            let len = self.entries.len();
+            self.select((self.selected + len - 1) % len);
+        }
+    }
+
+    fn select(&mut self, idx: usize) {
+        self.selected = idx;
+
+        // Re-render the fallback right away so that it's always available.
+        let text = text(&self.entries, self.selected);
+        let mut buffers = self.buffers.borrow_mut();
+        let fallback = buffers.remove(&NotNan::new(1.).unwrap()).flatten();
+        buffers.clear();
+        let fallback = render(1., &text).ok().or(fallback);
+        buffers.insert(NotNan::new(1.).unwrap(), fallback);
+    }
+
     pub fn render<R: NiriRenderer>(
         &self,
         renderer: &mut R,
@@ -75,7 +126,7 @@ impl ExitConfirmDialog {
         let fallback = buffers[&NotNan::new(1.).unwrap()].clone().unwrap();
         let buffer = buffers
             .entry(NotNan::new(scale).unwrap())
-            .or_insert_with(|| render(scale).ok());
+            .or_insert_with(|| render(scale, &text(&self.entries, self.selected)).ok());
         let buffer = buffer.as_ref().unwrap_or(&fallback);
 
         let size = buffer.logical_size();
@@ -98,7 +149,35 @@ impl ExitConfirmDialog {
     }
 }
 
-fn render(scale: f64) -> anyhow::Result<MemoryBuffer> {
+fn entry_name(entry: PowerMenuEntry) -> &'static str {
+    match entry {
+        PowerMenuEntry::Logout => "Log Out",
+        PowerMenuEntry::Suspend => "Suspend",
+        PowerMenuEntry::Hibernate => "Hibernate",
+        PowerMenuEntry::Reboot => "Reboot",
+        PowerMenuEntry::PowerOff => "Power Off",
+    }
+}
+
+fn text(entries: &[PowerMenuEntry], selected: usize) -> String {
+    if entries.is_empty() {
+        return String::from(TEXT);
+    }
+
+    let mut text = format!("{MENU_TITLE}\n");
+    for (idx, entry) in entries.iter().enumerate() {
+        let name = entry_name(*entry);
+        if idx == selected {
+            text.push_str(&format!("\n<span bgcolor='#4C4C4C'> <b>{name}</b> </span>"));
+        } else {
+            text.push_str(&format!("\n {name} "));
+        }
+    }
+    text.push_str(&format!("\n\n{MENU_HINT}"));
+    text
+}
+
+fn render(scale: f64, text: &str) -> anyhow::Result<MemoryBuffer> {
     let _span = tracy_client::span!("exit_confirm_dialog::render");
 
     let padding: i32 = to_physical_precise_round(scale, PADDING);
@@ -112,7 +191,7 @@ fn render(scale: f64) -> anyhow::Result<MemoryBuffer> {
     layout.context().set_round_glyph_positions(false);
     layout.set_font_description(Some(&font));
     layout.set_alignment(Alignment::Center);
-    layout.set_markup(TEXT);
+    layout.set_markup(text);
 
     let (mut width, mut height) = layout.pixel_size();
     width += padding * 2;
@@ -128,7 +207,7 @@ fn render(scale: f64) -> anyhow::Result<MemoryBuffer> {
     layout.context().set_round_glyph_positions(false);
     layout.set_font_description(Some(&font));
     layout.set_alignment(Alignment::Center);
-    layout.set_markup(TEXT);
+    layout.set_markup(text);
 
     cr.set_source_rgb(1., 1., 1.);
     pangocairo::functions::show_layout(&cr, &layout);
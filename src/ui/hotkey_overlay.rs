@@ -25,9 +25,25 @@ const FONT: &str = "sans 14px";
 const BORDER: i32 = 4;
 const LINE_INTERVAL: i32 = 2;
 const TITLE: &str = "Important Hotkeys";
+const TITLE_ALL: &str = "All Hotkeys";
+const UNKNOWN_ACTION: &str = "FIXME: Unknown";
+
+/// Categories of the cheatsheet, in the order they are shown.
+const CATEGORIES: [&str; 8] = [
+    "Focus",
+    "Moving Windows",
+    "Sizing",
+    "Workspaces",
+    "Monitors",
+    "Applications",
+    "Screenshots",
+    "Other",
+];
 
 pub struct HotkeyOverlay {
     is_open: bool,
+    /// Whether this shows the cheatsheet with all binds rather than the important ones.
+    show_all: bool,
     config: Rc<RefCell<Config>>,
     mod_key: ModKey,
     buffers: RefCell<HashMap<WeakOutput, RenderedOverlay>>,
@@ -41,6 +57,7 @@ impl HotkeyOverlay {
     pub fn new(config: Rc<RefCell<Config>>, mod_key: ModKey) -> Self {
         Self {
             is_open: false,
+            show_all: false,
             config,
             mod_key,
             buffers: RefCell::new(HashMap::new()),
@@ -48,12 +65,26 @@ impl HotkeyOverlay {
     }
 
     pub fn show(&mut self) -> bool {
-        if !self.is_open {
-            self.is_open = true;
-            true
-        } else {
-            false
+        self.open(false)
+    }
+
+    /// Shows the cheatsheet with all binds.
+    pub fn show_all(&mut self) -> bool {
+        self.open(true)
+    }
+
+    fn open(&mut self, show_all: bool) -> bool {
+        if self.is_open && self.show_all == show_all {
+            return false;
         }
+
+        if self.show_all != show_all {
+            self.show_all = show_all;
+            self.buffers.borrow_mut().clear();
+        }
+
+        self.is_open = true;
+        true
     }
 
     pub fn hide(&mut self) -> bool {
@@ -101,8 +132,14 @@ impl HotkeyOverlay {
 
         let rendered = buffers.entry(weak).or_insert_with(|| {
             let renderer = renderer.as_gles_renderer();
-            render(renderer, &self.config.borrow(), self.mod_key, scale)
-                .unwrap_or_else(|_| RenderedOverlay { buffer: None })
+            let config = self.config.borrow();
+            let rv = if self.show_all {
+                let max_height = to_physical_precise_round(scale, output_size.h);
+                render_all(renderer, &config, self.mod_key, scale, max_height)
+            } else {
+                render(renderer, &config, self.mod_key, scale)
+            };
+            rv.unwrap_or_else(|_| RenderedOverlay { buffer: None })
         });
         let buffer = rendered.buffer.as_ref()?;
 
@@ -422,6 +459,7 @@ fn action_name(action: &Action) -> String {
     match action {
         Action::Quit(_) => String::from("Exit niri"),
         Action::ShowHotkeyOverlay => String::from("Show Important Hotkeys"),
+        Action::ShowCheatsheet => String::from("Show All Hotkeys"),
         Action::CloseWindow => String::from("Close Focused Window"),
         Action::FocusColumnLeft => String::from("Focus Column to the Left"),
         Action::FocusColumnRight => String::from("Focus Column to the Right"),
@@ -447,8 +485,300 @@ fn action_name(action: &Action) -> String {
             "Spawn <span face='monospace' bgcolor='#000000'>{}</span>",
             args.first().unwrap_or(&String::new())
         ),
-        _ => String::from("FIXME: Unknown"),
+        _ => String::from(UNKNOWN_ACTION),
+    }
+}
+
+/// Describes any action, also the ones that the important hotkeys don't show.
+fn describe_action(action: &Action) -> String {
+    let name = action_name(action);
+    if name != UNKNOWN_ACTION {
+        return name;
+    }
+
+    // Make something readable out of the debug representation, for example
+    // "FocusWorkspace(Index(3))" becomes "Focus Workspace Index 3".
+    let debug = format!("{action:?}");
+    let split = debug
+        .find(|c: char| !c.is_alphanumeric())
+        .unwrap_or(debug.len());
+    let (variant, args) = debug.split_at(split);
+
+    let mut name = String::new();
+    for c in variant.chars() {
+        if c.is_uppercase() && !name.is_empty() {
+            name.push(' ');
+        }
+        name.push(c);
+    }
+
+    let args = args.replace(['(', ')', '{', '}', ','], " ");
+    for arg in args.split_whitespace() {
+        name.push(' ');
+        name.push_str(arg);
+    }
+
+    pango::glib::markup_escape_text(&name).into()
+}
+
+fn action_category(action: &Action) -> &'static str {
+    let debug = format!("{action:?}");
+    let variant = debug.split(|c: char| !c.is_alphanumeric()).next().unwrap();
+
+    let starts_with_any = |prefixes: &[&str]| prefixes.iter().any(|p| variant.starts_with(p));
+    let contains_any = |parts: &[&str]| parts.iter().any(|p| variant.contains(p));
+
+    if variant.starts_with("Spawn") {
+        "Applications"
+    } else if variant.starts_with("Screenshot") {
+        "Screenshots"
+    } else if variant.contains("Workspace") {
+        "Workspaces"
+    } else if variant.contains("Monitor") {
+        "Monitors"
+    } else if variant.starts_with("Focus") {
+        "Focus"
+    } else if starts_with_any(&["Move", "Consume", "Expel", "Swap", "Center"]) {
+        "Moving Windows"
+    } else if contains_any(&["Width", "Height", "Maximize", "Fullscreen", "Size"]) {
+        "Sizing"
+    } else {
+        "Other"
+    }
+}
+
+struct MeasuredRow<'a> {
+    key: &'a str,
+    key_size: (i32, i32),
+    attrs: Option<AttrList>,
+    text: String,
+    text_size: (i32, i32),
+}
+
+impl MeasuredRow<'_> {
+    fn height(&self) -> i32 {
+        max(self.key_size.1, self.text_size.1)
+    }
+}
+
+struct MeasuredSection<'a> {
+    name: &'a str,
+    name_size: (i32, i32),
+    rows: Vec<MeasuredRow<'a>>,
+}
+
+fn render_all(
+    renderer: &mut GlesRenderer,
+    config: &Config,
+    mod_key: ModKey,
+    scale: f64,
+    max_height: i32,
+) -> anyhow::Result<RenderedOverlay> {
+    let _span = tracy_client::span!("hotkey_overlay::render_all");
+
+    let padding: i32 = to_physical_precise_round(scale, PADDING);
+    let line_interval: i32 = to_physical_precise_round(scale, LINE_INTERVAL);
+
+    let mut sections: Vec<(&str, Vec<(String, String)>)> =
+        CATEGORIES.iter().map(|name| (*name, Vec::new())).collect();
+    for bind in &config.binds.0 {
+        let title = match &bind.hotkey_overlay_title {
+            Some(None) => continue,
+            Some(Some(title)) => title.clone(),
+            None => describe_action(&bind.action),
+        };
+
+        let key = format!(" {} ", key_name(mod_key, &bind.key));
+        let category = action_category(&bind.action);
+        let (_, rows) = sections
+            .iter_mut()
+            .find(|(name, _)| *name == category)
+            .unwrap();
+        rows.push((key, title));
+    }
+    sections.retain(|(_, rows)| !rows.is_empty());
+    anyhow::ensure!(!sections.is_empty(), "no binds to show");
+
+    let mut font = FontDescription::from_string(FONT);
+    font.set_absolute_size(to_physical_precise_round(scale, font.size()));
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, 0, 0)?;
+    let cr = cairo::Context::new(&surface)?;
+    let layout = pangocairo::functions::create_layout(&cr);
+    layout.context().set_round_glyph_positions(false);
+    layout.set_font_description(Some(&font));
+
+    let bold = AttrList::new();
+    bold.insert(AttrInt::new_weight(Weight::Bold));
+
+    let key_attrs = AttrList::new();
+    key_attrs.insert(AttrString::new_family("Monospace"));
+    key_attrs.insert(AttrColor::new_background(12000, 12000, 12000));
+
+    layout.set_attributes(Some(&bold));
+    layout.set_text(TITLE_ALL);
+    let title_size = layout.pixel_size();
+
+    let sections = sections
+        .iter()
+        .map(|(name, rows)| {
+            layout.set_attributes(Some(&bold));
+            layout.set_text(name);
+            let name_size = layout.pixel_size();
+
+            let rows = rows
+                .iter()
+                .map(|(key, action)| {
+                    layout.set_attributes(Some(&key_attrs));
+                    layout.set_text(key);
+                    let key_size = layout.pixel_size();
+
+                    let (attrs, text) = match pango::parse_markup(action, '\0') {
+                        Ok((attrs, text, _accel)) => (Some(attrs), text.into()),
+                        Err(err) => {
+                            warn!("error parsing markup for key {key}: {err}");
+                            (None, action.clone())
+                        }
+                    };
+                    layout.set_attributes(attrs.as_ref());
+                    layout.set_text(&text);
+                    let text_size = layout.pixel_size();
+
+                    MeasuredRow {
+                        key,
+                        key_size,
+                        attrs,
+                        text,
+                        text_size,
+                    }
+                })
+                .collect();
+
+            MeasuredSection {
+                name,
+                name_size,
+                rows,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let section_height = |section: &MeasuredSection| {
+        section.name_size.1
+            + section
+                .rows
+                .iter()
+                .map(|row| line_interval + row.height())
+                .sum::<i32>()
+    };
+
+    // Split the sections into columns that fit into the output height.
+    let available_height = max_height - padding * 4 - title_size.1 - padding;
+    let mut columns: Vec<(Vec<&MeasuredSection>, i32)> = vec![(Vec::new(), 0)];
+    for section in &sections {
+        let height = section_height(section);
+        let (column, column_height) = columns.last_mut().unwrap();
+        if column.is_empty() {
+            column.push(section);
+            *column_height = height;
+        } else if *column_height + padding * 2 + height <= available_height {
+            column.push(section);
+            *column_height += padding * 2 + height;
+        } else {
+            columns.push((vec![section], height));
+        }
+    }
+
+    let column_widths = columns
+        .iter()
+        .map(|(column, _)| {
+            let rows = || column.iter().flat_map(|section| &section.rows);
+            let key_width = rows().map(|row| row.key_size.0).max().unwrap_or(0);
+            let text_width = rows().map(|row| row.text_size.0).max().unwrap_or(0);
+            let name_width = column.iter().map(|s| s.name_size.0).max().unwrap_or(0);
+            (key_width, max(key_width + padding + text_width, name_width))
+        })
+        .collect::<Vec<_>>();
+
+    let columns_width = column_widths.iter().map(|(_, w)| w).sum::<i32>()
+        + (columns.len() as i32 - 1) * padding * 3;
+    let width = max(columns_width, title_size.0) + padding * 2;
+    let height = columns.iter().map(|(_, h)| *h).max().unwrap() + title_size.1 + padding * 3;
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, width, height)?;
+    let cr = cairo::Context::new(&surface)?;
+    cr.set_source_rgb(0.1, 0.1, 0.1);
+    cr.paint()?;
+
+    let layout = pangocairo::functions::create_layout(&cr);
+    layout.context().set_round_glyph_positions(false);
+    layout.set_font_description(Some(&font));
+
+    cr.set_source_rgb(1., 1., 1.);
+
+    cr.move_to(((width - title_size.0) / 2).into(), padding.into());
+    layout.set_attributes(Some(&bold));
+    layout.set_text(TITLE_ALL);
+    pangocairo::functions::show_layout(&cr, &layout);
+
+    let mut x = padding;
+    for ((column, _), (key_width, column_width)) in zip(&columns, &column_widths) {
+        let mut y = padding + title_size.1 + padding;
+
+        for section in column {
+            cr.move_to(x.into(), y.into());
+            layout.set_attributes(Some(&bold));
+            layout.set_text(section.name);
+            pangocairo::functions::show_layout(&cr, &layout);
+            y += section.name_size.1;
+
+            for row in &section.rows {
+                y += line_interval;
+
+                cr.move_to(x.into(), y.into());
+                layout.set_attributes(Some(&key_attrs));
+                layout.set_text(row.key);
+                pangocairo::functions::show_layout(&cr, &layout);
+
+                cr.move_to((x + key_width + padding).into(), y.into());
+                layout.set_attributes(row.attrs.as_ref());
+                layout.set_text(&row.text);
+                pangocairo::functions::show_layout(&cr, &layout);
+
+                y += row.height();
+            }
+
+            y += padding * 2;
+        }
+
+        x += column_width + padding * 3;
     }
+
+    cr.move_to(0., 0.);
+    cr.line_to(width.into(), 0.);
+    cr.line_to(width.into(), height.into());
+    cr.line_to(0., height.into());
+    cr.line_to(0., 0.);
+    cr.set_source_rgb(0.5, 0.8, 1.0);
+    // Keep the border width even to avoid blurry edges.
+    cr.set_line_width((f64::from(BORDER) / 2. * scale).round() * 2.);
+    cr.stroke()?;
+    drop(cr);
+
+    let data = surface.take_data().unwrap();
+    let buffer = TextureBuffer::from_memory(
+        renderer,
+        &data,
+        Fourcc::Argb8888,
+        (width, height),
+        false,
+        scale,
+        Transform::Normal,
+        Vec::new(),
+    )?;
+
+    Ok(RenderedOverlay {
+        buffer: Some(buffer),
+    })
 }
 
 fn key_name(mod_key: ModKey, key: &Key) -> String {
@@ -0,0 +1,277 @@
+//! Built-in lock screen, shown while the session is locked without a lock screen client.
+//!
+//! It only shows the clock and, optionally, the playing media with buttons to control it. There's
+//! no way to unlock from it: unlocking is left to the lock screen client, which takes over as soon
+//! as it shows up.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use ordered_float::NotNan;
+use pangocairo::cairo::{self, ImageSurface};
+use pangocairo::pango::{glib, FontDescription};
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::output::Output;
+use smithay::reexports::gbm::Format as Fourcc;
+use smithay::utils::{Logical, Physical, Point, Rectangle, Size, Transform};
+
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::utils::{output_size, to_physical_precise_round};
+
+/// How often the clock is checked for a new minute.
+pub const CLOCK_INTERVAL: Duration = Duration::from_secs(1);
+
+const CLOCK_FONT: &str = "sans light 96px";
+const LABEL_FONT: &str = "sans 24px";
+const MEDIA_FONT: &str = "sans 18px";
+const CONTROL_FONT: &str = "sans 32px";
+const SPACING: i32 = 12;
+/// Gap between the media control buttons.
+const CONTROL_GAP: i32 = 48;
+
+pub struct LockScreen {
+    clock: String,
+    media: Option<String>,
+    buffers: RefCell<HashMap<NotNan<f64>, Option<Rendered>>>,
+}
+
+/// Button on the lock screen that controls the media player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaControl {
+    Previous,
+    PlayPause,
+    Next,
+}
+
+struct Rendered {
+    buffer: TextureBuffer<GlesTexture>,
+    /// Media control buttons, relative to the buffer.
+    controls: Vec<(MediaControl, Rectangle<f64, Logical>)>,
+}
+
+impl MediaControl {
+    const ALL: [Self; 3] = [Self::Previous, Self::PlayPause, Self::Next];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Previous => "⏮",
+            Self::PlayPause => "⏯",
+            Self::Next => "⏭",
+        }
+    }
+}
+
+impl LockScreen {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            clock: String::new(),
+            media: None,
+            buffers: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Updates the clock to the current time and returns whether it changed.
+    pub fn update_clock(&mut self) -> bool {
+        let clock = glib::DateTime::now_local()
+            .and_then(|now| now.format("%H:%M"))
+            .map(String::from)
+            .unwrap_or_default();
+
+        if self.clock == clock {
+            return false;
+        }
+
+        self.clock = clock;
+        self.buffers.get_mut().clear();
+        true
+    }
+
+    /// Sets the media line and returns whether it changed.
+    pub fn set_media(&mut self, media: Option<String>) -> bool {
+        if self.media == media {
+            return false;
+        }
+
+        self.media = media;
+        self.buffers.get_mut().clear();
+        true
+    }
+
+    pub fn has_media(&self) -> bool {
+        self.media.is_some()
+    }
+
+    pub fn render<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+    ) -> Option<PrimaryGpuTextureRenderElement> {
+        let scale = output.current_scale().fractional_scale();
+
+        let mut buffers = self.buffers.borrow_mut();
+        let rendered = buffers
+            .entry(NotNan::new(scale).unwrap())
+            .or_insert_with(|| {
+                render(
+                    renderer.as_gles_renderer(),
+                    scale,
+                    &self.clock,
+                    self.media.as_deref(),
+                )
+                .ok()
+            });
+        let buffer = rendered.as_ref()?.buffer.clone();
+        let location = location(output, buffer.logical_size());
+
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer,
+            location,
+            1.,
+            None,
+            None,
+            Kind::Unspecified,
+        );
+        Some(PrimaryGpuTextureRenderElement(elem))
+    }
+
+    /// Returns the media control button at the position within the output, if any.
+    pub fn control_at(&self, output: &Output, pos: Point<f64, Logical>) -> Option<MediaControl> {
+        let scale = output.current_scale().fractional_scale();
+        let buffers = self.buffers.borrow();
+        let rendered = buffers.get(&NotNan::new(scale).unwrap())?.as_ref()?;
+
+        let pos = pos - location(output, rendered.buffer.logical_size());
+        rendered
+            .controls
+            .iter()
+            .find(|(_, rect)| rect.contains(pos))
+            .map(|(control, _)| *control)
+    }
+}
+
+/// Returns the location of the lock screen contents of the given size, centered in the output.
+fn location(output: &Output, size: Size<f64, Logical>) -> Point<f64, Logical> {
+    let scale = output.current_scale().fractional_scale();
+    let output_size = output_size(output);
+
+    let x = (output_size.w - size.w).max(0.) / 2.;
+    let y = (output_size.h - size.h).max(0.) / 2.;
+
+    let location = Point::from((x, y));
+    location.to_physical_precise_round(scale).to_logical(scale)
+}
+
+fn render(
+    renderer: &mut GlesRenderer,
+    scale: f64,
+    clock: &str,
+    media: Option<&str>,
+) -> anyhow::Result<Rendered> {
+    let _span = tracy_client::span!("lock_screen::render");
+
+    let spacing: i32 = to_physical_precise_round(scale, SPACING);
+    let control_gap: i32 = to_physical_precise_round(scale, CONTROL_GAP);
+
+    let mut lines = vec![(CLOCK_FONT, clock), (LABEL_FONT, "Locked")];
+    if let Some(media) = media {
+        lines.push((MEDIA_FONT, media));
+    }
+
+    let fonts: Vec<_> = lines
+        .iter()
+        .map(|(font, _)| {
+            let mut font = FontDescription::from_string(font);
+            font.set_absolute_size(to_physical_precise_round(scale, font.size()));
+            font
+        })
+        .collect();
+
+    // Measure the lines first to size the surface.
+    let surface = ImageSurface::create(cairo::Format::ARgb32, 0, 0)?;
+    let cr = cairo::Context::new(&surface)?;
+    let mut sizes = Vec::with_capacity(lines.len());
+    for ((_, text), font) in lines.iter().zip(&fonts) {
+        let layout = pangocairo::functions::create_layout(&cr);
+        layout.context().set_round_glyph_positions(false);
+        layout.set_font_description(Some(font));
+        layout.set_text(text);
+        sizes.push(layout.pixel_size());
+    }
+
+    let mut control_font = FontDescription::from_string(CONTROL_FONT);
+    control_font.set_absolute_size(to_physical_precise_round(scale, control_font.size()));
+
+    // The media controls go in a row under the media line.
+    let mut control_sizes = Vec::new();
+    if media.is_some() {
+        for control in MediaControl::ALL {
+            let layout = pangocairo::functions::create_layout(&cr);
+            layout.context().set_round_glyph_positions(false);
+            layout.set_font_description(Some(&control_font));
+            layout.set_text(control.label());
+            control_sizes.push((control, layout.pixel_size()));
+        }
+    }
+    let controls_width = control_sizes.iter().map(|(_, (w, _))| *w).sum::<i32>()
+        + control_gap * (control_sizes.len() as i32 - 1).max(0);
+    let controls_height = control_sizes.iter().map(|(_, (_, h))| *h).max();
+
+    let width = sizes.iter().map(|(w, _)| *w).max().unwrap_or(0);
+    let width = width.max(controls_width);
+    let mut height =
+        sizes.iter().map(|(_, h)| *h).sum::<i32>() + spacing * (sizes.len() as i32 - 1).max(0);
+    if let Some(controls_height) = controls_height {
+        height += spacing + controls_height;
+    }
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, width, height)?;
+    let cr = cairo::Context::new(&surface)?;
+    cr.set_source_rgb(1., 1., 1.);
+
+    let mut y = 0;
+    for (((_, text), font), (w, h)) in lines.iter().zip(&fonts).zip(&sizes) {
+        cr.move_to(f64::from((width - w) / 2), f64::from(y));
+        let layout = pangocairo::functions::create_layout(&cr);
+        layout.context().set_round_glyph_positions(false);
+        layout.set_font_description(Some(font));
+        layout.set_text(text);
+        pangocairo::functions::show_layout(&cr, &layout);
+        y += h + spacing;
+    }
+
+    let mut controls = Vec::new();
+    let mut x = (width - controls_width) / 2;
+    for (control, (w, h)) in control_sizes {
+        cr.move_to(f64::from(x), f64::from(y));
+        let layout = pangocairo::functions::create_layout(&cr);
+        layout.context().set_round_glyph_positions(false);
+        layout.set_font_description(Some(&control_font));
+        layout.set_text(control.label());
+        pangocairo::functions::show_layout(&cr, &layout);
+
+        let rect: Rectangle<i32, Physical> =
+            Rectangle::new(Point::from((x, y)), Size::from((w, h)));
+        controls.push((control, rect.to_f64().to_logical(scale)));
+        x += w + control_gap;
+    }
+    drop(cr);
+
+    let data = surface.take_data().unwrap();
+    let buffer = TextureBuffer::from_memory(
+        renderer,
+        &data,
+        Fourcc::Argb8888,
+        (width, height),
+        false,
+        scale,
+        Transform::Normal,
+        Vec::new(),
+    )?;
+
+    Ok(Rendered { buffer, controls })
+}
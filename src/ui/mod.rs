@@ -1,5 +1,12 @@
+pub mod binds_mode_indicator;
 pub mod config_error_notification;
 pub mod exit_confirm_dialog;
 pub mod hotkey_overlay;
+pub mod layout_indicator;
+pub mod lock_screen;
+pub mod notifications;
+pub mod osd;
 pub mod screen_transition;
 pub mod screenshot_ui;
+pub mod wallpaper;
+pub mod window_switcher;
@@ -0,0 +1,380 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use niri_config::NotificationPosition;
+use ordered_float::NotNan;
+use pangocairo::cairo::{self, ImageSurface};
+use pangocairo::pango::{AttrInt, AttrList, FontDescription, Weight, WrapMode};
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::output::Output;
+use smithay::reexports::calloop::RegistrationToken;
+use smithay::reexports::gbm::Format as Fourcc;
+use smithay::utils::{Point, Transform};
+
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::utils::{output_size, to_physical_precise_round};
+
+const PADDING: i32 = 8;
+/// Gap between the popups, and between the popups and the screen edges.
+const GAP: f64 = 8.;
+const FONT: &str = "sans 14px";
+const BORDER: i32 = 2;
+const WIDTH: i32 = 360;
+/// Only this many of the newest popups are shown at once.
+const MAX_VISIBLE: usize = 5;
+/// Popups beyond this many are closed, oldest first, so that popups without a timeout can't pile
+/// up forever.
+const MAX_POPUPS: usize = 20;
+
+/// Popups of the built-in notification daemon.
+pub struct NotificationPopups {
+    popups: Vec<Popup>,
+    do_not_disturb: bool,
+}
+
+pub struct Notification {
+    pub id: u32,
+    pub summary: String,
+    pub body: String,
+    pub is_critical: bool,
+}
+
+/// Reason for closing a notification, as sent in the `NotificationClosed` signal.
+#[derive(Debug, Clone, Copy)]
+pub enum CloseReason {
+    Expired = 1,
+    Dismissed = 2,
+    Closed = 3,
+}
+
+struct Popup {
+    notification: Notification,
+    /// Timer closing the popup, unless it stays until dismissed.
+    timer: Option<RegistrationToken>,
+    buffers: RefCell<HashMap<NotNan<f64>, Option<TextureBuffer<GlesTexture>>>>,
+}
+
+impl NotificationPopups {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            popups: Vec::new(),
+            do_not_disturb: false,
+        }
+    }
+
+    pub fn do_not_disturb(&self) -> bool {
+        self.do_not_disturb
+    }
+
+    pub fn set_do_not_disturb(&mut self, value: bool) {
+        self.do_not_disturb = value;
+    }
+
+    /// Adds the popup, or replaces the one with the same id.
+    ///
+    /// Returns the timer of the replaced popup, if any.
+    pub fn show(
+        &mut self,
+        notification: Notification,
+        timer: Option<RegistrationToken>,
+    ) -> Option<RegistrationToken> {
+        let popup = Popup {
+            notification,
+            timer,
+            buffers: RefCell::new(HashMap::new()),
+        };
+
+        let id = popup.notification.id;
+        if let Some(existing) = self.popups.iter_mut().find(|p| p.notification.id == id) {
+            let old = std::mem::replace(existing, popup);
+            old.timer
+        } else {
+            self.popups.push(popup);
+            None
+        }
+    }
+
+    /// Removes the oldest popups above the limit, returning their ids and timers.
+    pub fn close_overflow(&mut self) -> Vec<(u32, Option<RegistrationToken>)> {
+        let excess = self.popups.len().saturating_sub(MAX_POPUPS);
+        self.popups
+            .drain(..excess)
+            .map(|p| (p.notification.id, p.timer))
+            .collect()
+    }
+
+    /// Forgets the timer of the popup, for when the timer fires.
+    pub fn forget_timer(&mut self, id: u32) {
+        if let Some(popup) = self.popups.iter_mut().find(|p| p.notification.id == id) {
+            popup.timer = None;
+        }
+    }
+
+    /// Removes the popup, returning its timer, or `None` if there's no such popup.
+    pub fn close(&mut self, id: u32) -> Option<Option<RegistrationToken>> {
+        let idx = self.popups.iter().position(|p| p.notification.id == id)?;
+        Some(self.popups.remove(idx).timer)
+    }
+
+    /// Removes all popups, returning their ids and timers.
+    pub fn close_all(&mut self) -> Vec<(u32, Option<RegistrationToken>)> {
+        self.popups
+            .drain(..)
+            .map(|p| (p.notification.id, p.timer))
+            .collect()
+    }
+
+    pub fn render<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+        position: NotificationPosition,
+    ) -> Vec<PrimaryGpuTextureRenderElement> {
+        let scale = output.current_scale().fractional_scale();
+        let output_size = output_size(output);
+
+        let mut rv = Vec::new();
+        let mut y = GAP;
+        // The newest popup goes closest to the corner.
+        for popup in self.popups.iter().rev().take(MAX_VISIBLE) {
+            let mut buffers = popup.buffers.borrow_mut();
+            let buffer = buffers
+                .entry(NotNan::new(scale).unwrap())
+                .or_insert_with(|| {
+                    render(renderer.as_gles_renderer(), scale, &popup.notification).ok()
+                });
+            let Some(buffer) = buffer.clone() else {
+                continue;
+            };
+
+            let size = buffer.logical_size();
+            let x = match position {
+                NotificationPosition::TopLeft | NotificationPosition::BottomLeft => GAP,
+                NotificationPosition::TopRight | NotificationPosition::BottomRight => {
+                    output_size.w - size.w - GAP
+                }
+            };
+            let loc_y = match position {
+                NotificationPosition::TopLeft | NotificationPosition::TopRight => y,
+                NotificationPosition::BottomLeft | NotificationPosition::BottomRight => {
+                    output_size.h - size.h - y
+                }
+            };
+            y += size.h + GAP;
+
+            let location = Point::from((x, loc_y));
+            let location = location.to_physical_precise_round(scale).to_logical(scale);
+
+            let elem = TextureRenderElement::from_texture_buffer(
+                buffer,
+                location,
+                1.,
+                None,
+                None,
+                Kind::Unspecified,
+            );
+            rv.push(PrimaryGpuTextureRenderElement(elem));
+        }
+
+        rv
+    }
+}
+
+fn render(
+    renderer: &mut GlesRenderer,
+    scale: f64,
+    notification: &Notification,
+) -> anyhow::Result<TextureBuffer<GlesTexture>> {
+    let _span = tracy_client::span!("notifications::render");
+
+    let padding: i32 = to_physical_precise_round(scale, PADDING);
+    let width: i32 = to_physical_precise_round(scale, WIDTH);
+    let text_width = width - padding * 2;
+
+    let mut font = FontDescription::from_string(FONT);
+    font.set_absolute_size(to_physical_precise_round(scale, font.size()));
+
+    let bold = AttrList::new();
+    bold.insert(AttrInt::new_weight(Weight::Bold));
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, 0, 0)?;
+    let cr = cairo::Context::new(&surface)?;
+    let layout = pangocairo::functions::create_layout(&cr);
+    layout.context().set_round_glyph_positions(false);
+    layout.set_font_description(Some(&font));
+    layout.set_width(text_width * pangocairo::pango::SCALE);
+    layout.set_wrap(WrapMode::WordChar);
+
+    layout.set_attributes(Some(&bold));
+    layout.set_text(&notification.summary);
+    let summary_height = layout.pixel_size().1;
+
+    layout.set_attributes(None);
+    layout.set_text(&notification.body);
+    let body_height = if notification.body.is_empty() {
+        0
+    } else {
+        layout.pixel_size().1
+    };
+
+    let height = summary_height + body_height + padding * 2;
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, width, height)?;
+    let cr = cairo::Context::new(&surface)?;
+    cr.set_source_rgb(0.1, 0.1, 0.1);
+    cr.paint()?;
+
+    let layout = pangocairo::functions::create_layout(&cr);
+    layout.context().set_round_glyph_positions(false);
+    layout.set_font_description(Some(&font));
+    layout.set_width(text_width * pangocairo::pango::SCALE);
+    layout.set_wrap(WrapMode::WordChar);
+    cr.set_source_rgb(1., 1., 1.);
+
+    cr.move_to(padding.into(), padding.into());
+    layout.set_attributes(Some(&bold));
+    layout.set_text(&notification.summary);
+    pangocairo::functions::show_layout(&cr, &layout);
+
+    if !notification.body.is_empty() {
+        cr.move_to(padding.into(), (padding + summary_height).into());
+        layout.set_attributes(None);
+        layout.set_text(&notification.body);
+        pangocairo::functions::show_layout(&cr, &layout);
+    }
+
+    cr.move_to(0., 0.);
+    cr.line_to(width.into(), 0.);
+    cr.line_to(width.into(), height.into());
+    cr.line_to(0., height.into());
+    cr.line_to(0., 0.);
+    if notification.is_critical {
+        cr.set_source_rgb(1., 0.3, 0.3);
+    } else {
+        cr.set_source_rgb(0.5, 0.5, 0.5);
+    }
+    // Keep the border width even to avoid blurry edges.
+    cr.set_line_width((f64::from(BORDER) / 2. * scale).round() * 2.);
+    cr.stroke()?;
+    drop(cr);
+
+    let data = surface.take_data().unwrap();
+    let buffer = TextureBuffer::from_memory(
+        renderer,
+        &data,
+        Fourcc::Argb8888,
+        (width, height),
+        false,
+        scale,
+        Transform::Normal,
+        Vec::new(),
+    )?;
+
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
+    use smithay::reexports::calloop::EventLoop;
+
+    use super::*;
+
+    fn notification(id: u32, summary: &str) -> Notification {
+        Notification {
+            id,
+            summary: String::from(summary),
+            body: String::new(),
+            is_critical: false,
+        }
+    }
+
+    fn ids(popups: &NotificationPopups) -> Vec<u32> {
+        popups.popups.iter().map(|p| p.notification.id).collect()
+    }
+
+    fn timer(event_loop: &EventLoop<()>) -> RegistrationToken {
+        event_loop
+            .handle()
+            .insert_source(Timer::immediate(), |_, _, _| TimeoutAction::Drop)
+            .unwrap()
+    }
+
+    #[test]
+    fn add_and_close() {
+        let mut popups = NotificationPopups::new();
+        assert_eq!(popups.show(notification(1, "a"), None), None);
+        assert_eq!(popups.show(notification(2, "b"), None), None);
+        assert_eq!(ids(&popups), [1, 2]);
+
+        assert_eq!(popups.close(1), Some(None));
+        assert_eq!(ids(&popups), [2]);
+
+        // Closing twice does nothing.
+        assert_eq!(popups.close(1), None);
+    }
+
+    #[test]
+    fn replace_keeps_position_and_returns_old_timer() {
+        let event_loop = EventLoop::try_new().unwrap();
+        let old_timer = timer(&event_loop);
+        let new_timer = timer(&event_loop);
+
+        let mut popups = NotificationPopups::new();
+        popups.show(notification(1, "a"), Some(old_timer));
+        popups.show(notification(2, "b"), None);
+
+        assert_eq!(
+            popups.show(notification(1, "c"), Some(new_timer)),
+            Some(old_timer)
+        );
+        assert_eq!(ids(&popups), [1, 2]);
+        assert_eq!(popups.popups[0].notification.summary, "c");
+        assert_eq!(popups.close(1), Some(Some(new_timer)));
+    }
+
+    #[test]
+    fn expire_forgets_timer() {
+        let event_loop = EventLoop::try_new().unwrap();
+        let timer = timer(&event_loop);
+
+        let mut popups = NotificationPopups::new();
+        popups.show(notification(1, "a"), Some(timer));
+
+        // The timer fired, so it's already gone from the event loop.
+        popups.forget_timer(1);
+        assert_eq!(popups.close(1), Some(None));
+    }
+
+    #[test]
+    fn close_all() {
+        let mut popups = NotificationPopups::new();
+        popups.show(notification(1, "a"), None);
+        popups.show(notification(2, "b"), None);
+
+        assert_eq!(popups.close_all(), [(1, None), (2, None)]);
+        assert!(popups.popups.is_empty());
+    }
+
+    #[test]
+    fn overflow_closes_oldest() {
+        let mut popups = NotificationPopups::new();
+        for id in 0..MAX_POPUPS as u32 {
+            let mut notification = notification(id, "a");
+            // Critical ones without a timeout are capped too.
+            notification.is_critical = true;
+            popups.show(notification, None);
+        }
+        assert!(popups.close_overflow().is_empty());
+
+        popups.show(notification(100, "b"), None);
+        popups.show(notification(101, "c"), None);
+        assert_eq!(popups.close_overflow(), [(0, None), (1, None)]);
+        assert_eq!(popups.popups.len(), MAX_POPUPS);
+        assert_eq!(ids(&popups).last(), Some(&101));
+    }
+}
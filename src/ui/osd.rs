@@ -0,0 +1,221 @@
+//! On-screen display briefly showing brightness and volume levels.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use niri_config::OsdPosition;
+use niri_ipc::OsdKind;
+use ordered_float::NotNan;
+use pangocairo::cairo::{self, ImageSurface};
+use pangocairo::pango::FontDescription;
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::output::Output;
+use smithay::reexports::gbm::Format as Fourcc;
+use smithay::utils::{Point, Transform};
+
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::utils::{output_size, to_physical_precise_round};
+
+const PADDING: i32 = 16;
+const BAR_HEIGHT: i32 = 8;
+/// Distance from the output edge for the top and bottom positions.
+const MARGIN: f64 = 64.;
+const FONT: &str = "sans 16px";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Level {
+    kind: OsdKind,
+    /// Level from 0 to 1.
+    value: f64,
+    muted: bool,
+}
+
+pub struct Osd {
+    level: Option<Level>,
+    config: niri_config::Osd,
+    buffers: RefCell<HashMap<NotNan<f64>, Option<TextureBuffer<GlesTexture>>>>,
+}
+
+impl Osd {
+    pub fn new(config: niri_config::Osd) -> Self {
+        Self {
+            level: None,
+            config,
+            buffers: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn update_config(&mut self, config: niri_config::Osd) {
+        self.config = config;
+        if config.off {
+            self.level = None;
+        }
+        self.buffers.get_mut().clear();
+    }
+
+    /// How long the display stays on screen after the last change.
+    pub fn hide_after(&self) -> Duration {
+        Duration::from_millis(u64::from(self.config.hide_after_ms))
+    }
+
+    /// Shows a level from 0 to 1 and returns whether the display is on.
+    pub fn show(&mut self, kind: OsdKind, value: f64, muted: bool) -> bool {
+        if self.config.off {
+            return false;
+        }
+
+        let level = Level {
+            kind,
+            value: value.clamp(0., 1.),
+            muted,
+        };
+        if self.level != Some(level) {
+            self.level = Some(level);
+            self.buffers.get_mut().clear();
+        }
+        true
+    }
+
+    pub fn hide(&mut self) {
+        self.level = None;
+        self.buffers.get_mut().clear();
+    }
+
+    pub fn render<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+    ) -> Option<PrimaryGpuTextureRenderElement> {
+        let level = self.level?;
+
+        let scale = output.current_scale().fractional_scale();
+        let output_size = output_size(output);
+
+        let mut buffers = self.buffers.borrow_mut();
+        let buffer = buffers
+            .entry(NotNan::new(scale).unwrap())
+            .or_insert_with(|| {
+                render(renderer.as_gles_renderer(), scale, &self.config, level).ok()
+            });
+        let buffer = buffer.clone()?;
+
+        let size = buffer.logical_size();
+        let x = (output_size.w - size.w).max(0.) / 2.;
+        let y = match self.config.position {
+            OsdPosition::Top => MARGIN,
+            OsdPosition::Center => (output_size.h - size.h) / 2.,
+            OsdPosition::Bottom => output_size.h - size.h - MARGIN,
+        };
+        let y = y.max(0.);
+
+        let location = Point::from((x, y));
+        let location = location.to_physical_precise_round(scale).to_logical(scale);
+
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer,
+            location,
+            1.,
+            None,
+            None,
+            Kind::Unspecified,
+        );
+        Some(PrimaryGpuTextureRenderElement(elem))
+    }
+}
+
+fn render(
+    renderer: &mut GlesRenderer,
+    scale: f64,
+    config: &niri_config::Osd,
+    level: Level,
+) -> anyhow::Result<TextureBuffer<GlesTexture>> {
+    let _span = tracy_client::span!("osd::render");
+
+    let padding: i32 = to_physical_precise_round(scale, PADDING);
+    let bar_height: i32 = to_physical_precise_round(scale, BAR_HEIGHT);
+    let width: i32 = to_physical_precise_round(scale, config.width.0);
+
+    let mut font = FontDescription::from_string(FONT);
+    font.set_absolute_size(to_physical_precise_round(scale, font.size()));
+
+    let label = match level.kind {
+        OsdKind::Brightness => "Brightness",
+        OsdKind::Volume => "Volume",
+    };
+    let percent = if level.muted {
+        String::from("Muted")
+    } else {
+        format!("{:.0}%", level.value * 100.)
+    };
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, 0, 0)?;
+    let cr = cairo::Context::new(&surface)?;
+    let layout = pangocairo::functions::create_layout(&cr);
+    layout.context().set_round_glyph_positions(false);
+    layout.set_font_description(Some(&font));
+    layout.set_text(label);
+    let (_, text_height) = layout.pixel_size();
+
+    let height = padding * 3 + text_height + bar_height;
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, width, height)?;
+    let cr = cairo::Context::new(&surface)?;
+    cr.set_source_rgba(0.1, 0.1, 0.1, 0.9);
+    cr.paint()?;
+
+    // The label on the left and the percentage on the right.
+    cr.set_source_rgb(1., 1., 1.);
+    cr.move_to(padding.into(), padding.into());
+    let layout = pangocairo::functions::create_layout(&cr);
+    layout.context().set_round_glyph_positions(false);
+    layout.set_font_description(Some(&font));
+    layout.set_text(label);
+    pangocairo::functions::show_layout(&cr, &layout);
+
+    layout.set_text(&percent);
+    let (percent_width, _) = layout.pixel_size();
+    cr.move_to((width - padding - percent_width).into(), padding.into());
+    pangocairo::functions::show_layout(&cr, &layout);
+
+    // The bar under them.
+    let bar_width = f64::from(width - padding * 2);
+    let bar_y = f64::from(padding * 2 + text_height);
+    cr.set_source_rgb(0.3, 0.3, 0.3);
+    cr.rectangle(padding.into(), bar_y, bar_width, bar_height.into());
+    cr.fill()?;
+
+    let color = config.bar_color;
+    let alpha = if level.muted { 0.4 } else { 1. };
+    cr.set_source_rgba(
+        color.r.into(),
+        color.g.into(),
+        color.b.into(),
+        f64::from(color.a) * alpha,
+    );
+    cr.rectangle(
+        padding.into(),
+        bar_y,
+        (bar_width * level.value).round(),
+        bar_height.into(),
+    );
+    cr.fill()?;
+    drop(cr);
+
+    let data = surface.take_data().unwrap();
+    let buffer = TextureBuffer::from_memory(
+        renderer,
+        &data,
+        Fourcc::Argb8888,
+        (width, height),
+        false,
+        scale,
+        Transform::Normal,
+        Vec::new(),
+    )?;
+
+    Ok(buffer)
+}
@@ -0,0 +1,430 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::SystemTime;
+
+use anyhow::{bail, Context as _};
+use niri_config::{Color, Config, Wallpaper, WallpaperMode};
+use pangocairo::cairo::{self, Extend, ImageSurface, SurfacePattern};
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::GlesTexture;
+use smithay::output::{Output, WeakOutput};
+use smithay::reexports::calloop::channel::Sender;
+use smithay::reexports::gbm::Format as Fourcc;
+use smithay::utils::{Physical, Rectangle, Size, Transform};
+
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::utils::expand_home;
+
+/// Built-in wallpapers, prepared once per output in a thread and cached as textures.
+pub struct Wallpapers {
+    buffers: RefCell<HashMap<WeakOutput, Vec<RenderedWallpaper>>>,
+    /// Decoded images by path, shared between the preparing threads.
+    images: Arc<Mutex<HashMap<PathBuf, DecodedImage>>>,
+    to_niri: Sender<PreparedWallpaper>,
+}
+
+struct RenderedWallpaper {
+    key: WallpaperKey,
+    /// Modification time of the image file that the wallpaper was prepared from.
+    mtime: Option<SystemTime>,
+    state: RenderState,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct WallpaperKey {
+    wallpaper: Wallpaper,
+    background_color: Color,
+    size: Size<i32, Physical>,
+    scale: f64,
+}
+
+enum RenderState {
+    /// Being prepared in a thread.
+    Pending,
+    /// Prepared pixels waiting for the upload.
+    Prepared(Vec<u8>),
+    /// Uploaded, or `None` if preparing failed.
+    Done(Option<TextureBuffer<GlesTexture>>),
+}
+
+/// Wallpaper pixels prepared in a thread, to be sent over to the main thread.
+pub struct PreparedWallpaper {
+    output: WeakOutput,
+    key: WallpaperKey,
+    result: anyhow::Result<(Vec<u8>, Option<SystemTime>)>,
+}
+
+struct DecodedImage {
+    mtime: Option<SystemTime>,
+    data: Vec<u8>,
+    width: i32,
+    height: i32,
+    stride: i32,
+}
+
+impl Wallpapers {
+    pub fn new(to_niri: Sender<PreparedWallpaper>) -> Self {
+        Self {
+            buffers: RefCell::new(HashMap::new()),
+            images: Arc::new(Mutex::new(HashMap::new())),
+            to_niri,
+        }
+    }
+
+    /// Drops the wallpapers that are no longer configured or whose image files changed, so that
+    /// they are loaded from disk again.
+    pub fn refresh(&mut self, config: &Config) {
+        let outputs = Vec::from_iter(
+            config
+                .outputs
+                .0
+                .iter()
+                .chain(config.output_profiles.iter().flat_map(|p| &p.outputs.0)),
+        );
+        let configured = Vec::from_iter(
+            outputs
+                .iter()
+                .filter_map(|output| output.wallpaper.as_ref())
+                .chain(
+                    config
+                        .workspaces
+                        .iter()
+                        .filter_map(|ws| ws.wallpaper.as_ref()),
+                ),
+        );
+        let background_colors = Vec::from_iter(
+            outputs
+                .iter()
+                .filter_map(|output| output.background_color)
+                .chain([config.layout.background_color]),
+        );
+
+        let buffers = self.buffers.get_mut();
+        buffers.retain(|output, _| output.is_alive());
+        for rendered in buffers.values_mut() {
+            rendered.retain(|r| {
+                if !configured.contains(&&r.key.wallpaper)
+                    || !background_colors.contains(&r.key.background_color)
+                {
+                    return false;
+                }
+
+                match r.state {
+                    // The thread will pick up the current file.
+                    RenderState::Pending => true,
+                    // Try again in case the file got fixed.
+                    RenderState::Done(None) => false,
+                    _ => r.mtime.is_some() && image_mtime(&r.key.wallpaper) == r.mtime,
+                }
+            });
+        }
+
+        let paths = Vec::from_iter(configured.iter().filter_map(|w| wallpaper_path(w).ok()));
+        self.images
+            .lock()
+            .unwrap()
+            .retain(|path, image| paths.contains(path) && image_mtime_at(path) == image.mtime);
+    }
+
+    /// Drops the wallpapers of a removed output.
+    pub fn remove_output(&mut self, output: &Output) {
+        self.buffers.get_mut().remove(&output.downgrade());
+    }
+
+    /// Stores a wallpaper prepared in a thread.
+    pub fn finish(&mut self, prepared: PreparedWallpaper) {
+        let PreparedWallpaper {
+            output,
+            key,
+            result,
+        } = prepared;
+
+        let Some(rendered) = self.buffers.get_mut().get_mut(&output) else {
+            return;
+        };
+        let Some(rendered) = rendered
+            .iter_mut()
+            .find(|r| r.key == key && matches!(r.state, RenderState::Pending))
+        else {
+            return;
+        };
+
+        match result {
+            Ok((data, mtime)) => {
+                rendered.mtime = mtime;
+                rendered.state = RenderState::Prepared(data);
+            }
+            Err(err) => {
+                warn!(
+                    "error rendering wallpaper {:?}: {err:?}",
+                    key.wallpaper.path
+                );
+                rendered.state = RenderState::Done(None);
+            }
+        }
+    }
+
+    pub fn render<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+        wallpaper: &Wallpaper,
+        background_color: Color,
+        alpha: f32,
+    ) -> Option<PrimaryGpuTextureRenderElement> {
+        let scale = output.current_scale().fractional_scale();
+        let size = output
+            .current_transform()
+            .transform_size(output.current_mode()?.size);
+
+        let mut buffers = self.buffers.borrow_mut();
+        buffers.retain(|output, _| output.is_alive());
+
+        let rendered = buffers.entry(output.downgrade()).or_default();
+
+        // Outdated wallpapers for this output will never match again.
+        rendered.retain(|r| r.key.size == size && r.key.scale == scale);
+
+        let key = WallpaperKey {
+            wallpaper: wallpaper.clone(),
+            background_color,
+            size,
+            scale,
+        };
+        let Some(rendered) = rendered.iter_mut().find(|r| r.key == key) else {
+            self.prepare(output, key.clone());
+            rendered.push(RenderedWallpaper {
+                key,
+                mtime: None,
+                state: RenderState::Pending,
+            });
+            return None;
+        };
+
+        if let RenderState::Prepared(data) = &rendered.state {
+            let _span = tracy_client::span!("wallpaper upload");
+
+            let renderer = renderer.as_gles_renderer();
+            let buffer = TextureBuffer::from_memory(
+                renderer,
+                data,
+                Fourcc::Argb8888,
+                (size.w, size.h),
+                false,
+                scale,
+                Transform::Normal,
+                vec![Rectangle::from_size((size.w, size.h).into())],
+            );
+            let buffer = match buffer {
+                Ok(buffer) => Some(buffer),
+                Err(err) => {
+                    warn!("error uploading wallpaper {:?}: {err:?}", wallpaper.path);
+                    None
+                }
+            };
+            rendered.state = RenderState::Done(buffer);
+        }
+
+        let RenderState::Done(Some(buffer)) = &rendered.state else {
+            return None;
+        };
+
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer.clone(),
+            (0., 0.),
+            alpha,
+            None,
+            None,
+            Kind::Unspecified,
+        );
+        Some(PrimaryGpuTextureRenderElement(elem))
+    }
+
+    /// Decodes and scales the wallpaper in a thread, since both are slow for big images.
+    fn prepare(&self, output: &Output, key: WallpaperKey) {
+        let output = output.downgrade();
+        let images = self.images.clone();
+        let to_niri = self.to_niri.clone();
+
+        let res = thread::Builder::new()
+            .name("Wallpaper Loader".to_owned())
+            .spawn(move || {
+                let result = prepare_pixels(&images, &key);
+                let _ = to_niri.send(PreparedWallpaper {
+                    output,
+                    key,
+                    result,
+                });
+            });
+
+        if let Err(err) = res {
+            warn!("error spawning a thread to load the wallpaper: {err:?}");
+        }
+    }
+}
+
+fn wallpaper_path(wallpaper: &Wallpaper) -> anyhow::Result<PathBuf> {
+    let mut path = PathBuf::from(&wallpaper.path);
+    if let Some(expanded) = expand_home(&path).context("error expanding ~")? {
+        path = expanded;
+    }
+    Ok(path)
+}
+
+fn image_mtime(wallpaper: &Wallpaper) -> Option<SystemTime> {
+    let path = wallpaper_path(wallpaper).ok()?;
+    image_mtime_at(&path)
+}
+
+fn image_mtime_at(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+fn prepare_pixels(
+    images: &Mutex<HashMap<PathBuf, DecodedImage>>,
+    key: &WallpaperKey,
+) -> anyhow::Result<(Vec<u8>, Option<SystemTime>)> {
+    let _span = tracy_client::span!("wallpaper::prepare_pixels");
+
+    let path = wallpaper_path(&key.wallpaper)?;
+    let mtime = image_mtime_at(&path);
+
+    // Reuse the image decoded for another output, unless the file changed since.
+    let cached = images
+        .lock()
+        .unwrap()
+        .get(&path)
+        .filter(|image| image.mtime.is_some() && image.mtime == mtime)
+        .map(|image| (image.data.clone(), image.width, image.height, image.stride));
+    let (data, width, height, stride) = match cached {
+        Some(image) => image,
+        None => {
+            let mut image = load_png(&path).with_context(|| format!("error loading {path:?}"))?;
+            image.mtime = mtime;
+            let res = (image.data.clone(), image.width, image.height, image.stride);
+            images.lock().unwrap().insert(path, image);
+            res
+        }
+    };
+
+    let image = ImageSurface::create_for_data(data, cairo::Format::ARgb32, width, height, stride)?;
+    let data = render(&image, key)?;
+    Ok((data, mtime))
+}
+
+fn render(image: &ImageSurface, key: &WallpaperKey) -> anyhow::Result<Vec<u8>> {
+    let WallpaperKey {
+        wallpaper,
+        background_color,
+        size,
+        scale,
+    } = key;
+
+    let image_w = f64::from(image.width());
+    let image_h = f64::from(image.height());
+
+    let (width, height) = (size.w, size.h);
+    let surface = ImageSurface::create(cairo::Format::ARgb32, width, height)?;
+    let cr = cairo::Context::new(&surface)?;
+
+    // The background color shows through the letterboxing in fit mode and through transparent
+    // parts of the image. The wallpaper itself is always opaque.
+    let [r, g, b, _] = background_color.to_array_unpremul();
+    cr.set_source_rgb(r.into(), g.into(), b.into());
+    cr.paint()?;
+
+    match wallpaper.mode {
+        WallpaperMode::Fill | WallpaperMode::Fit => {
+            let scale_x = f64::from(width) / image_w;
+            let scale_y = f64::from(height) / image_h;
+            let image_scale = if wallpaper.mode == WallpaperMode::Fill {
+                f64::max(scale_x, scale_y)
+            } else {
+                f64::min(scale_x, scale_y)
+            };
+
+            cr.translate(
+                ((f64::from(width) - image_w * image_scale) / 2.).round(),
+                ((f64::from(height) - image_h * image_scale) / 2.).round(),
+            );
+            cr.scale(image_scale, image_scale);
+            cr.set_source_surface(image, 0., 0.)?;
+        }
+        WallpaperMode::Tile => {
+            // Tile in logical pixels so that the pattern looks the same regardless of scale.
+            cr.scale(*scale, *scale);
+            let pattern = SurfacePattern::create(image);
+            pattern.set_extend(Extend::Repeat);
+            cr.set_source(&pattern)?;
+        }
+    }
+    cr.paint()?;
+    drop(cr);
+
+    let data = surface.take_data().unwrap();
+    Ok(data.to_vec())
+}
+
+/// Loads a PNG file into pixels for a Cairo surface.
+fn load_png(path: &Path) -> anyhow::Result<DecodedImage> {
+    let file = File::open(path).context("error opening file")?;
+    let mut decoder = png::Decoder::new(BufReader::new(file));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+    let mut reader = decoder.read_info().context("error reading PNG header")?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buf)
+        .context("error decoding PNG image")?;
+
+    let channels = match info.color_type {
+        png::ColorType::Grayscale => 1,
+        png::ColorType::GrayscaleAlpha => 2,
+        png::ColorType::Rgb => 3,
+        png::ColorType::Rgba => 4,
+        png::ColorType::Indexed => bail!("unexpected indexed color after expansion"),
+    };
+
+    let width = i32::try_from(info.width).context("image is too wide")?;
+    let height = i32::try_from(info.height).context("image is too tall")?;
+    let stride = cairo::Format::ARgb32.stride_for_width(info.width)?;
+
+    // Cairo wants premultiplied alpha in native-endian 32-bit pixels.
+    let mut data = vec![0; stride as usize * height as usize];
+    for (src_row, dst_row) in buf
+        .chunks(info.line_size)
+        .zip(data.chunks_mut(stride as usize))
+    {
+        for (src, dst) in src_row
+            .chunks_exact(channels)
+            .zip(dst_row.chunks_exact_mut(4))
+        {
+            let (r, g, b, a) = match *src {
+                [v] => (v, v, v, 255),
+                [v, a] => (v, v, v, a),
+                [r, g, b] => (r, g, b, 255),
+                [r, g, b, a] => (r, g, b, a),
+                _ => unreachable!(),
+            };
+
+            let premul = |c: u8| (u16::from(c) * u16::from(a) / 255) as u32;
+            let pixel = (u32::from(a) << 24) | (premul(r) << 16) | (premul(g) << 8) | premul(b);
+            dst.copy_from_slice(&pixel.to_ne_bytes());
+        }
+    }
+
+    Ok(DecodedImage {
+        mtime: None,
+        data,
+        width,
+        height,
+        stride,
+    })
+}
@@ -0,0 +1,358 @@
+//! Window switcher dialog.
+//!
+//! Lists the windows of all workspaces and filters them by fuzzy matching the typed text against
+//! their titles and app IDs.
+
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use ordered_float::NotNan;
+use pangocairo::cairo::{self, ImageSurface};
+use pangocairo::pango::{glib, EllipsizeMode, FontDescription};
+use smithay::backend::renderer::element::Kind;
+use smithay::backend::renderer::gles::{GlesRenderer, GlesTexture};
+use smithay::output::Output;
+use smithay::reexports::gbm::Format as Fourcc;
+use smithay::utils::Transform;
+
+use crate::render_helpers::primary_gpu_texture::PrimaryGpuTextureRenderElement;
+use crate::render_helpers::renderer::NiriRenderer;
+use crate::render_helpers::texture::{TextureBuffer, TextureRenderElement};
+use crate::utils::{output_size, to_physical_precise_round};
+
+const TITLE: &str = "Switch to window";
+const HINT: &str = "<span face='mono' bgcolor='#2C2C2C'> Enter </span> focus, \
+                    <span face='mono' bgcolor='#2C2C2C'> Shift+Enter </span> bring here, \
+                    <span face='mono' bgcolor='#2C2C2C'> Esc </span> cancel";
+const MAX_SHOWN: usize = 10;
+const WIDTH: i32 = 640;
+const PADDING: i32 = 16;
+const FONT: &str = "sans 14px";
+const BORDER: i32 = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowSwitcherEntry {
+    /// ID of the window, as used in IPC.
+    pub id: u64,
+    pub title: String,
+    pub app_id: String,
+}
+
+pub struct WindowSwitcher {
+    is_open: bool,
+    entries: Vec<WindowSwitcherEntry>,
+    query: String,
+    /// Indices of the entries matching the query, best match first.
+    matches: Vec<usize>,
+    /// Index into `matches` of the selected entry.
+    selected: usize,
+    buffers: RefCell<HashMap<NotNan<f64>, Option<TextureBuffer<GlesTexture>>>>,
+}
+
+impl WindowSwitcher {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            is_open: false,
+            entries: Vec::new(),
+            query: String::new(),
+            matches: Vec::new(),
+            selected: 0,
+            buffers: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn show(&mut self, entries: Vec<WindowSwitcherEntry>) -> bool {
+        if self.is_open {
+            return false;
+        }
+
+        self.is_open = true;
+        self.entries = entries;
+        self.query.clear();
+        self.update_matches();
+        true
+    }
+
+    pub fn hide(&mut self) -> bool {
+        if !self.is_open {
+            return false;
+        }
+
+        self.is_open = false;
+        self.entries.clear();
+        self.matches.clear();
+        self.buffers.get_mut().clear();
+        true
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.is_open
+    }
+
+    /// Returns the window ID of the selected entry.
+    pub fn selected(&self) -> Option<u64> {
+        let idx = *self.matches.get(self.selected)?;
+        Some(self.entries[idx].id)
+    }
+
+    pub fn push_char(&mut self, c: char) {
+        self.query.push(c);
+        self.update_matches();
+    }
+
+    pub fn pop_char(&mut self) {
+        if self.query.pop().is_some() {
+            self.update_matches();
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        if !self.matches.is_empty() {
+            self.selected = (self.selected + 1) % self.matches.len();
+            self.buffers.get_mut().clear();
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        if !self.matches.is_empty() {
+            let len = self.matches.len();
+            self.selected = (self.selected + len - 1) % len;
+            self.buffers.get_mut().clear();
+        }
+    }
+
+    fn update_matches(&mut self) {
+        let mut scored: Vec<_> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, entry)| {
+                let title = fuzzy_score(&self.query, &entry.title);
+                let app_id = fuzzy_score(&self.query, &entry.app_id);
+                Some((idx, title.max(app_id)?))
+            })
+            .collect();
+        // The sort is stable, so equally good matches keep the layout order.
+        scored.sort_by_key(|(_, score)| Reverse(*score));
+
+        self.matches = scored.into_iter().map(|(idx, _)| idx).collect();
+        self.selected = 0;
+        self.buffers.get_mut().clear();
+    }
+
+    pub fn render<R: NiriRenderer>(
+        &self,
+        renderer: &mut R,
+        output: &Output,
+    ) -> Option<PrimaryGpuTextureRenderElement> {
+        if !self.is_open {
+            return None;
+        }
+
+        let scale = output.current_scale().fractional_scale();
+        let output_size = output_size(output);
+
+        let mut buffers = self.buffers.borrow_mut();
+        let buffer = buffers
+            .entry(NotNan::new(scale).unwrap())
+            .or_insert_with(|| render(renderer.as_gles_renderer(), scale, &self.text()).ok());
+        let buffer = buffer.clone()?;
+
+        let size = buffer.logical_size();
+        let location = (output_size.to_point() - size.to_point()).downscale(2.);
+        let mut location = location.to_physical_precise_round(scale).to_logical(scale);
+        location.x = f64::max(0., location.x);
+        location.y = f64::max(0., location.y);
+
+        let elem = TextureRenderElement::from_texture_buffer(
+            buffer,
+            location,
+            1.,
+            None,
+            None,
+            Kind::Unspecified,
+        );
+        Some(PrimaryGpuTextureRenderElement(elem))
+    }
+
+    fn text(&self) -> String {
+        let query = glib::markup_escape_text(&self.query);
+        let mut text = format!("<b>{TITLE}</b>\n\n<span bgcolor='#2C2C2C'> {query}▏</span>\n");
+
+        if self.matches.is_empty() {
+            text.push_str("\n<i>No matching windows</i>\n");
+        }
+
+        // Scroll the list to keep the selected entry visible.
+        let start = self.selected.saturating_sub(MAX_SHOWN - 1);
+        for (pos, idx) in self.matches.iter().enumerate().skip(start).take(MAX_SHOWN) {
+            let entry = &self.entries[*idx];
+            let title = glib::markup_escape_text(&entry.title);
+            let app_id = glib::markup_escape_text(&entry.app_id);
+            let line = format!("{title}  <span alpha='60%'>{app_id}</span>");
+            if pos == self.selected {
+                text.push_str(&format!("\n<span bgcolor='#4C4C4C'><b>{line}</b></span>"));
+            } else {
+                text.push_str(&format!("\n{line}"));
+            }
+        }
+
+        text.push_str(&format!("\n\n<span size='small'>{HINT}</span>"));
+        text
+    }
+}
+
+/// Returns how well `text` fuzzy matches `query`, or `None` if it doesn't match at all.
+///
+/// The query matches if its characters appear in the text in order, ignoring case. Characters
+/// following the previous match or starting a word score higher.
+fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+
+    let mut score = 0;
+    let mut start = 0;
+    let mut prev = None;
+    for c in query.chars().flat_map(char::to_lowercase) {
+        let idx = start + text[start..].iter().position(|x| *x == c)?;
+
+        score += 1;
+        if idx > 0 && prev == Some(idx - 1) {
+            score += 4;
+        }
+        if idx == 0 || !text[idx - 1].is_alphanumeric() {
+            score += 2;
+        }
+
+        prev = Some(idx);
+        start = idx + 1;
+    }
+
+    Some(score)
+}
+
+fn render(
+    renderer: &mut GlesRenderer,
+    scale: f64,
+    text: &str,
+) -> anyhow::Result<TextureBuffer<GlesTexture>> {
+    let _span = tracy_client::span!("window_switcher::render");
+
+    let padding: i32 = to_physical_precise_round(scale, PADDING);
+    let width: i32 = to_physical_precise_round(scale, WIDTH);
+
+    let mut font = FontDescription::from_string(FONT);
+    font.set_absolute_size(to_physical_precise_round(scale, font.size()));
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, 0, 0)?;
+    let cr = cairo::Context::new(&surface)?;
+    let layout = pangocairo::functions::create_layout(&cr);
+    layout.context().set_round_glyph_positions(false);
+    layout.set_font_description(Some(&font));
+    layout.set_width(width * pangocairo::pango::SCALE);
+    layout.set_ellipsize(EllipsizeMode::End);
+    layout.set_markup(text);
+
+    let (_, mut height) = layout.pixel_size();
+    let width = width + padding * 2;
+    height += padding * 2;
+
+    let surface = ImageSurface::create(cairo::Format::ARgb32, width, height)?;
+    let cr = cairo::Context::new(&surface)?;
+    cr.set_source_rgb(0.1, 0.1, 0.1);
+    cr.paint()?;
+
+    cr.move_to(padding.into(), padding.into());
+    let layout = pangocairo::functions::create_layout(&cr);
+    layout.context().set_round_glyph_positions(false);
+    layout.set_font_description(Some(&font));
+    layout.set_width((width - padding * 2) * pangocairo::pango::SCALE);
+    layout.set_ellipsize(EllipsizeMode::End);
+    layout.set_markup(text);
+
+    cr.set_source_rgb(1., 1., 1.);
+    pangocairo::functions::show_layout(&cr, &layout);
+
+    cr.move_to(0., 0.);
+    cr.line_to(width.into(), 0.);
+    cr.line_to(width.into(), height.into());
+    cr.line_to(0., height.into());
+    cr.line_to(0., 0.);
+    cr.set_source_rgb(0.5, 0.8, 1.);
+    // Keep the border width even to avoid blurry edges.
+    cr.set_line_width((f64::from(BORDER) / 2. * scale).round() * 2.);
+    cr.stroke()?;
+    drop(cr);
+
+    let data = surface.take_data().unwrap();
+    let buffer = TextureBuffer::from_memory(
+        renderer,
+        &data,
+        Fourcc::Argb8888,
+        (width, height),
+        false,
+        scale,
+        Transform::Normal,
+        Vec::new(),
+    )?;
+
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: u64, title: &str, app_id: &str) -> WindowSwitcherEntry {
+        WindowSwitcherEntry {
+            id,
+            title: title.to_owned(),
+            app_id: app_id.to_owned(),
+        }
+    }
+
+    #[test]
+    fn fuzzy_score_matches_in_order() {
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        assert!(fuzzy_score("ffx", "Firefox").is_some());
+        assert!(fuzzy_score("FIRE", "firefox").is_some());
+        assert_eq!(fuzzy_score("xf", "Firefox"), None);
+        assert_eq!(fuzzy_score("firefoxx", "Firefox"), None);
+
+        // Consecutive and word-start matches score higher.
+        assert!(fuzzy_score("ire", "Firefox") > fuzzy_score("ire", "ixrxe"));
+        assert!(fuzzy_score("ms", "Mail Settings") > fuzzy_score("ms", "mass"));
+    }
+
+    #[test]
+    fn filter_and_select() {
+        let mut switcher = WindowSwitcher::new();
+        assert!(switcher.show(vec![
+            entry(1, "Inbox - Mail", "org.gnome.Evolution"),
+            entry(2, "~/code", "Alacritty"),
+            entry(3, "Mozilla Firefox", "firefox"),
+        ]));
+        assert!(!switcher.show(Vec::new()));
+        assert_eq!(switcher.selected(), Some(1));
+
+        switcher.select_prev();
+        assert_eq!(switcher.selected(), Some(3));
+
+        // The app ID matches too, and typing resets the selection.
+        switcher.push_char('a');
+        switcher.push_char('l');
+        switcher.push_char('a');
+        assert_eq!(switcher.matches, [1]);
+        assert_eq!(switcher.selected(), Some(2));
+
+        switcher.push_char('z');
+        assert_eq!(switcher.selected(), None);
+        switcher.pop_char();
+        assert_eq!(switcher.selected(), Some(2));
+
+        assert!(switcher.hide());
+        assert!(!switcher.is_open());
+        assert_eq!(switcher.selected(), None);
+    }
+}
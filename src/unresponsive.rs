@@ -0,0 +1,193 @@
+//! Detecting unresponsive clients with xdg-shell pings.
+//!
+//! Every xdg-shell client is pinged periodically. A client that hasn't answered by the time the
+//! next ping is due counts as unresponsive: its windows get tinted, the close button in their top
+//! bar force kills the client after a confirmation, and IPC clients are notified.
+
+use std::ffi::OsStr;
+use std::time::Duration;
+
+use smithay::desktop::Window;
+use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
+use smithay::reexports::wayland_server::backend::ClientId;
+use smithay::reexports::wayland_server::Resource;
+use smithay::utils::SERIAL_COUNTER;
+use smithay::wayland::shell::xdg::ShellClient;
+
+use crate::niri::{ClientState, Niri, State};
+use crate::utils::get_executable;
+use crate::window::Mapped;
+
+/// How often clients are pinged, and how long they have to answer.
+pub const PING_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long a force kill request waits for the confirming second click.
+pub const KILL_CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Executables of clients that show windows on behalf of other processes, possibly on other
+/// machines, so killing them would take down more than the unresponsive app.
+const PROXY_EXECUTABLES: &[&str] = &["waypipe", "xwayland-satellite"];
+
+#[derive(Debug)]
+pub struct PingedClient {
+    client: ShellClient,
+    /// Whether a ping was sent and not answered yet.
+    ping_pending: bool,
+    /// Whether the client missed the last ping.
+    is_unresponsive: bool,
+}
+
+impl PingedClient {
+    pub fn new(client: ShellClient) -> Self {
+        Self {
+            client,
+            ping_pending: false,
+            is_unresponsive: false,
+        }
+    }
+
+    fn client_id(&self) -> Option<ClientId> {
+        self.client.xdg_wm_base().client().map(|client| client.id())
+    }
+}
+
+impl Niri {
+    /// Returns whether the client missed the last ping.
+    pub fn is_client_unresponsive(&self, client_id: &ClientId) -> bool {
+        self.shell_clients
+            .iter()
+            .any(|pinged| pinged.is_unresponsive && pinged.client_id().as_ref() == Some(client_id))
+    }
+}
+
+impl State {
+    /// Pings all xdg-shell clients, marking the ones that didn't answer the previous ping.
+    pub fn ping_shell_clients(&mut self) {
+        self.niri
+            .shell_clients
+            .retain(|pinged| pinged.client.alive());
+
+        let mut newly_unresponsive = Vec::new();
+        for pinged in &mut self.niri.shell_clients {
+            if pinged.ping_pending {
+                if !pinged.is_unresponsive {
+                    pinged.is_unresponsive = true;
+                    newly_unresponsive.extend(pinged.client_id());
+                }
+                continue;
+            }
+
+            match pinged.client.send_ping(SERIAL_COUNTER.next_serial()) {
+                Ok(()) => pinged.ping_pending = true,
+                Err(err) => debug!("error pinging client: {err:?}"),
+            }
+        }
+
+        for client_id in newly_unresponsive {
+            self.set_client_unresponsive(&client_id, true);
+        }
+    }
+
+    pub fn shell_client_pong(&mut self, client: &ShellClient) {
+        let Some(pinged) = self
+            .niri
+            .shell_clients
+            .iter_mut()
+            .find(|pinged| pinged.client == *client)
+        else {
+            return;
+        };
+
+        pinged.ping_pending = false;
+        if pinged.is_unresponsive {
+            pinged.is_unresponsive = false;
+            if let Some(client_id) = pinged.client_id() {
+                self.set_client_unresponsive(&client_id, false);
+            }
+        }
+    }
+
+    fn set_client_unresponsive(&mut self, client_id: &ClientId, unresponsive: bool) {
+        if unresponsive {
+            info!("client {client_id:?} stopped responding to pings");
+        } else {
+            info!("client {client_id:?} is responding again");
+        }
+
+        self.niri.layout.with_windows_mut(|mapped, _| {
+            let client = mapped.toplevel().wl_surface().client();
+            if client.is_some_and(|client| client.id() == *client_id) {
+                mapped.set_unresponsive(unresponsive);
+            }
+        });
+
+        // FIXME: granular.
+        self.niri.queue_redraw_all();
+    }
+
+    /// Handles a request to close an unresponsive window.
+    ///
+    /// The first request asks for a confirmation, the second one kills the client process.
+    pub fn force_kill_window(&mut self, window: &Window) {
+        let surface = window.toplevel().expect("no X11 support").wl_surface();
+        let Some((mapped, _)) = self.niri.layout.find_window_and_output_mut(surface) else {
+            return;
+        };
+
+        if !mapped.request_kill() {
+            // Reset the close button once the confirmation times out.
+            let timer = Timer::from_duration(KILL_CONFIRMATION_TIMEOUT);
+            self.niri
+                .event_loop
+                .insert_source(timer, |_, _, state| {
+                    state.niri.queue_redraw_all();
+                    TimeoutAction::Drop
+                })
+                .unwrap();
+
+            // FIXME: granular.
+            self.niri.queue_redraw_all();
+            return;
+        }
+
+        let Some(pid) = killable_pid(mapped) else {
+            info!("not killing the client of the unresponsive window, closing the window instead");
+            mapped.toplevel().send_close();
+            return;
+        };
+
+        info!("killing unresponsive process {pid}");
+        if unsafe { libc::kill(pid, libc::SIGKILL) } != 0 {
+            let err = std::io::Error::last_os_error();
+            warn!("error killing process {pid}: {err:?}");
+        }
+    }
+}
+
+/// Returns the pid of the client of the window, if it's safe to kill.
+///
+/// That's only the case for a client that is the app itself, which rules out xwayland-satellite,
+/// proxies like waypipe, and sandboxed clients, whose pid may not be the app.
+fn killable_pid(mapped: &Mapped) -> Option<i32> {
+    let client = mapped.toplevel().wl_surface().client()?;
+    let data = client.get_data::<ClientState>()?;
+    if data.is_xwayland
+        || data.restricted
+        || data.credentials_unknown
+        || data.sandbox_app_id.is_some()
+    {
+        return None;
+    }
+
+    let pid = mapped.credentials()?.pid;
+    let executable = get_executable(pid)?;
+    let name = executable.file_name()?;
+    if PROXY_EXECUTABLES
+        .iter()
+        .any(|proxy| name == OsStr::new(proxy))
+    {
+        return None;
+    }
+
+    Some(pid)
+}
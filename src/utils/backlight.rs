@@ -0,0 +1,192 @@
+//! Changing the screen brightness through the sysfs backlight devices.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+
+use anyhow::{bail, Context};
+use calloop::channel::Sender;
+
+const BACKLIGHT_DIR: &str = "/sys/class/backlight";
+
+/// Connection used to set the brightness.
+#[cfg(feature = "dbus")]
+type Connection = zbus::blocking::Connection;
+#[cfg(not(feature = "dbus"))]
+type Connection = ();
+
+/// Changes the brightness from one long-lived worker thread, since going through logind can
+/// block.
+///
+/// The new brightness, as a fraction of the maximum, goes back to niri for the on-screen display.
+pub struct BacklightWorker {
+    worker: Option<mpsc::Sender<i8>>,
+    to_niri: Sender<f64>,
+}
+
+#[derive(Debug)]
+pub struct Backlight {
+    /// Name of the device in the backlight sysfs directory.
+    name: String,
+    brightness: u32,
+    max_brightness: u32,
+}
+
+impl Backlight {
+    /// Finds the first backlight device, in name order.
+    pub fn find() -> anyhow::Result<Self> {
+        let mut names = fs::read_dir(BACKLIGHT_DIR)
+            .context("error reading the backlight directory")?
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+
+        let Some(name) = names.into_iter().next() else {
+            bail!("no backlight devices");
+        };
+
+        let dir = Path::new(BACKLIGHT_DIR).join(&name);
+        let brightness = read_value(dir.join("brightness"))?;
+        let max_brightness = read_value(dir.join("max_brightness"))?;
+        if max_brightness == 0 {
+            bail!("backlight {name} has zero max brightness");
+        }
+
+        Ok(Self {
+            name,
+            brightness,
+            max_brightness,
+        })
+    }
+
+    /// Returns the brightness as a fraction of the maximum brightness.
+    pub fn fraction(&self) -> f64 {
+        f64::from(self.brightness) / f64::from(self.max_brightness)
+    }
+
+    /// Changes the brightness by `step` percent of the maximum brightness.
+    pub fn adjust(&mut self, conn: &Connection, step: i8) -> anyhow::Result<()> {
+        let brightness = adjusted_brightness(self.brightness, self.max_brightness, step);
+        if brightness == self.brightness {
+            return Ok(());
+        }
+
+        self.set(conn, brightness)?;
+        self.brightness = brightness;
+        Ok(())
+    }
+
+    /// Sets the brightness through logind, which doesn't need write access to sysfs.
+    #[cfg(feature = "dbus")]
+    fn set(&self, conn: &Connection, brightness: u32) -> anyhow::Result<()> {
+        conn.call_method(
+            Some("org.freedesktop.login1"),
+            "/org/freedesktop/login1/session/auto",
+            Some("org.freedesktop.login1.Session"),
+            "SetBrightness",
+            &("backlight", &self.name, brightness),
+        )?;
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "dbus"))]
+    fn set(&self, _conn: &Connection, brightness: u32) -> anyhow::Result<()> {
+        let path = Path::new(BACKLIGHT_DIR).join(&self.name).join("brightness");
+        fs::write(&path, brightness.to_string()).with_context(|| format!("error writing {path:?}"))
+    }
+}
+
+impl BacklightWorker {
+    pub fn new(to_niri: Sender<f64>) -> Self {
+        Self {
+            worker: None,
+            to_niri,
+        }
+    }
+
+    /// Changes the brightness by `step` percent of the maximum brightness.
+    pub fn adjust(&mut self, step: i8) {
+        if let Some(worker) = &self.worker {
+            match worker.send(step) {
+                Ok(()) => return,
+                // The worker is gone, for example because it couldn't connect to the bus.
+                Err(mpsc::SendError(_)) => self.worker = None,
+            }
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let to_niri = self.to_niri.clone();
+        let res = thread::Builder::new()
+            .name("Backlight".to_owned())
+            .spawn(move || run_worker(rx, to_niri));
+        if let Err(err) = res {
+            warn!("error spawning the backlight thread: {err:?}");
+            return;
+        }
+
+        let _ = tx.send(step);
+        self.worker = Some(tx);
+    }
+}
+
+fn run_worker(rx: mpsc::Receiver<i8>, to_niri: Sender<f64>) {
+    #[cfg(feature = "dbus")]
+    let conn = match Connection::system() {
+        Ok(conn) => conn,
+        Err(err) => {
+            warn!("error connecting to the system bus: {err:?}");
+            return;
+        }
+    };
+    #[cfg(not(feature = "dbus"))]
+    let conn = ();
+
+    while let Ok(step) = rx.recv() {
+        // Look the device up every time since the brightness may have changed elsewhere.
+        let res = Backlight::find().and_then(|mut backlight| {
+            backlight.adjust(&conn, step)?;
+            Ok(backlight.fraction())
+        });
+        match res {
+            Ok(value) => {
+                if to_niri.send(value).is_err() {
+                    break;
+                }
+            }
+            Err(err) => warn!("error adjusting brightness: {err:?}"),
+        }
+    }
+}
+
+fn read_value(path: PathBuf) -> anyhow::Result<u32> {
+    let value = fs::read_to_string(&path).with_context(|| format!("error reading {path:?}"))?;
+    value
+        .trim()
+        .parse()
+        .with_context(|| format!("error parsing {path:?}"))
+}
+
+/// Returns the brightness after a step in percent of the maximum.
+///
+/// Dimming stops at the lowest non-zero brightness, so that the screen doesn't go fully dark.
+fn adjusted_brightness(brightness: u32, max_brightness: u32, step: i8) -> u32 {
+    let step = f64::from(max_brightness) * f64::from(step) / 100.;
+    let brightness = (f64::from(brightness) + step).round();
+    brightness.clamp(1., f64::from(max_brightness)) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adjusted_brightness_clamps() {
+        assert_eq!(adjusted_brightness(500, 1000, 5), 550);
+        assert_eq!(adjusted_brightness(500, 1000, -10), 400);
+        assert_eq!(adjusted_brightness(980, 1000, 5), 1000);
+        assert_eq!(adjusted_brightness(30, 1000, -5), 1);
+        assert_eq!(adjusted_brightness(3, 7, 10), 4);
+    }
+}
@@ -0,0 +1,155 @@
+//! Logging setup with a filter that can change at runtime and a buffer of recent messages.
+
+use std::collections::VecDeque;
+use std::fmt::{self, Write as _};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+use std::{env, io};
+
+use anyhow::Context as _;
+use niri_ipc::{LogEntry, LogLevel};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt as _};
+use tracing_subscriber::util::SubscriberInitExt as _;
+use tracing_subscriber::{fmt, reload, EnvFilter, Layer, Registry};
+
+const DEFAULT_LOG_FILTER: &str = "niri=debug,smithay::backend::renderer::gles=error";
+
+/// How many recent messages to keep for `niri msg logs`.
+const BUFFER_LEN: usize = 1000;
+
+struct Logging {
+    filter_handle: reload::Handle<EnvFilter, Registry>,
+    /// Filter from `RUST_LOG` or the default one, to reset to.
+    initial_filter: String,
+    filter: Mutex<String>,
+    buffer: Mutex<VecDeque<LogEntry>>,
+    start: Instant,
+}
+
+static LOGGING: OnceLock<Logging> = OnceLock::new();
+
+/// Sets up logging to stderr.
+///
+/// The filter comes from `RUST_LOG`. Setting `NIRI_LOG_FORMAT=json` switches to one JSON object
+/// per message.
+pub fn init() {
+    let directives = env::var("RUST_LOG").unwrap_or_else(|_| DEFAULT_LOG_FILTER.to_owned());
+    let env_filter = EnvFilter::builder().parse_lossy(&directives);
+    let (filter_layer, filter_handle) = reload::Layer::new(env_filter);
+
+    let json = env::var_os("NIRI_LOG_FORMAT").is_some_and(|x| x == "json");
+    let compact_layer = (!json).then(|| fmt::layer().compact().with_writer(io::stderr));
+    let json_layer = json.then(|| fmt::layer().json().with_writer(io::stderr));
+
+    let logging = Logging {
+        filter_handle,
+        initial_filter: directives.clone(),
+        filter: Mutex::new(directives),
+        buffer: Mutex::new(VecDeque::with_capacity(BUFFER_LEN)),
+        start: Instant::now(),
+    };
+    if LOGGING.set(logging).is_err() {
+        return;
+    }
+
+    tracing_subscriber::registry()
+        .with(filter_layer)
+        .with(compact_layer)
+        .with(json_layer)
+        .with(BufferLayer)
+        .init();
+}
+
+/// Returns the current log filter.
+pub fn filter() -> Option<String> {
+    let logging = LOGGING.get()?;
+    let filter = logging.filter.lock().unwrap().clone();
+    Some(filter)
+}
+
+/// Changes the log filter, or resets it to the initial one with `None`.
+pub fn set_filter(directives: Option<&str>) -> anyhow::Result<()> {
+    let logging = LOGGING.get().context("logging is not set up")?;
+    let directives = directives.unwrap_or(&logging.initial_filter);
+
+    let env_filter = EnvFilter::builder()
+        .parse(directives)
+        .context("error parsing the log filter")?;
+    logging
+        .filter_handle
+        .reload(env_filter)
+        .context("error changing the log filter")?;
+
+    info!("changed the log filter to {directives:?}");
+    *logging.filter.lock().unwrap() = directives.to_owned();
+    Ok(())
+}
+
+/// Returns the recent log messages, oldest first.
+pub fn recent_entries() -> Vec<LogEntry> {
+    let Some(logging) = LOGGING.get() else {
+        return Vec::new();
+    };
+    let buffer = logging.buffer.lock().unwrap();
+    buffer.iter().cloned().collect()
+}
+
+/// Layer that keeps the recent messages that passed the filter.
+struct BufferLayer;
+
+impl<S: Subscriber> Layer<S> for BufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let Some(logging) = LOGGING.get() else {
+            return;
+        };
+
+        let metadata = event.metadata();
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        let entry = LogEntry {
+            time_ms: logging.start.elapsed().as_millis() as u64,
+            level: log_level(*metadata.level()),
+            target: metadata.target().to_owned(),
+            message: visitor.message,
+        };
+
+        let mut buffer = logging.buffer.lock().unwrap();
+        if buffer.len() == BUFFER_LEN {
+            buffer.pop_front();
+        }
+        buffer.push_back(entry);
+    }
+}
+
+/// Formats the message and the fields of an event like the stderr output does.
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if !self.message.is_empty() {
+            self.message.push(' ');
+        }
+
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            let _ = write!(self.message, "{}={value:?}", field.name());
+        }
+    }
+}
+
+fn log_level(level: Level) -> LogLevel {
+    match level {
+        Level::ERROR => LogLevel::Error,
+        Level::WARN => LogLevel::Warn,
+        Level::INFO => LogLevel::Info,
+        Level::DEBUG => LogLevel::Debug,
+        _ => LogLevel::Trace,
+    }
+}
@@ -1,18 +1,23 @@
 use std::cmp::{max, min};
+use std::env;
 use std::f64;
 use std::ffi::{CString, OsStr};
+use std::fs;
 use std::io::Write;
+use std::mem::size_of;
+use std::os::fd::AsRawFd;
+use std::os::unix::net::UnixStream;
 use std::os::unix::prelude::OsStrExt;
 use std::path::{Path, PathBuf};
 use std::ptr::null_mut;
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
 use anyhow::{ensure, Context};
 use bitflags::bitflags;
 use directories::UserDirs;
 use git_version::git_version;
-use niri_config::{Config, OutputName};
+use niri_config::{Config, DecorationMode, OutputName};
 use smithay::backend::renderer::utils::with_renderer_surface_state;
 use smithay::input::pointer::CursorIcon;
 use smithay::output::{self, Output};
@@ -31,8 +36,13 @@ use wayland_backend::server::Credentials;
 
 use crate::handlers::KdeDecorationsModeState;
 use crate::niri::ClientState;
+use crate::utils::spawning::REMOVE_ENV_LIBGL_ALWAYS_SOFTWARE;
 
+pub mod backlight;
 pub mod id;
+pub mod logging;
+#[cfg(feature = "dbus")]
+pub mod mpris;
 pub mod scale;
 pub mod spawning;
 pub mod transaction;
@@ -102,6 +112,40 @@ pub fn version() -> String {
     }
 }
 
+/// Returns whether Mesa renders on the CPU instead of the GPU.
+pub fn is_software_rendering() -> bool {
+    env::var_os("LIBGL_ALWAYS_SOFTWARE").is_some_and(|value| value != "0" && value != "false")
+}
+
+/// Makes Mesa render on the CPU instead of the GPU.
+///
+/// Changes the environment, so it must be called before spawning any threads.
+pub fn enable_software_rendering() {
+    if is_software_rendering() {
+        return;
+    }
+
+    if env::var_os("LIBGL_ALWAYS_SOFTWARE").is_none() {
+        REMOVE_ENV_LIBGL_ALWAYS_SOFTWARE.store(true, Ordering::Relaxed);
+    }
+    env::set_var("LIBGL_ALWAYS_SOFTWARE", "1");
+}
+
+/// Returns whether the DRM devices can only display and not render.
+///
+/// Display-only drivers, like the ones of virtual machines without 3D acceleration, don't have
+/// render nodes.
+pub fn has_no_render_nodes() -> bool {
+    let Ok(entries) = fs::read_dir("/dev/dri") else {
+        // No DRM devices at all, software rendering won't help.
+        return false;
+    };
+
+    !entries
+        .filter_map(|entry| entry.ok())
+        .any(|entry| entry.file_name().as_bytes().starts_with(b"renderD"))
+}
+
 pub fn get_monotonic_time() -> Duration {
     let ts = clock_gettime(ClockId::Monotonic);
     Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
@@ -341,6 +385,28 @@ pub fn update_tiled_state(
     });
 }
 
+pub fn xdg_decoration_mode(mode: DecorationMode) -> zxdg_toplevel_decoration_v1::Mode {
+    match mode {
+        DecorationMode::ServerSide => zxdg_toplevel_decoration_v1::Mode::ServerSide,
+        DecorationMode::ClientSide => zxdg_toplevel_decoration_v1::Mode::ClientSide,
+    }
+}
+
+/// Applies the decoration mode forced by the window rules, if any.
+pub fn update_decoration_mode(toplevel: &ToplevelSurface, force: Option<DecorationMode>) {
+    let Some(force) = force else {
+        return;
+    };
+
+    toplevel.with_pending_state(|state| {
+        // The pending mode is only set when the client bound xdg-decoration for this window, and
+        // we mustn't send decoration configures otherwise.
+        if state.decoration_mode.is_some() {
+            state.decoration_mode = Some(xdg_decoration_mode(force));
+        }
+    });
+}
+
 pub fn get_credentials_for_surface(surface: &WlSurface) -> Option<Credentials> {
     let handle = surface.handle().upgrade()?;
     let dh = DisplayHandle::from(handle);
@@ -354,6 +420,46 @@ pub fn get_credentials_for_surface(surface: &WlSurface) -> Option<Credentials> {
     client.get_credentials(&dh).ok()
 }
 
+/// Returns the pid of the process on the other end of the socket.
+pub fn get_peer_pid(stream: &UnixStream) -> Option<i32> {
+    let mut cred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = size_of::<libc::ucred>() as libc::socklen_t;
+
+    let rv = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            (&mut cred as *mut libc::ucred).cast(),
+            &mut len,
+        )
+    };
+    if rv != 0 || cred.pid <= 0 {
+        return None;
+    }
+
+    Some(cred.pid)
+}
+
+/// Returns the path to the executable of the process.
+pub fn get_executable(pid: i32) -> Option<PathBuf> {
+    std::fs::read_link(format!("/proc/{pid}/exe")).ok()
+}
+
+/// Returns the name of the systemd unit, like a scope or a service, that the process runs in.
+pub fn get_systemd_unit(pid: i32) -> Option<String> {
+    let cgroup = std::fs::read_to_string(format!("/proc/{pid}/cgroup")).ok()?;
+    // With the unified hierarchy, the line looks like "0::/user.slice/.../app-foo-123.scope".
+    let path = cgroup.lines().find_map(|line| line.strip_prefix("0::"))?;
+    path.rsplit('/')
+        .find(|name| name.ends_with(".scope") || name.ends_with(".service"))
+        .map(String::from)
+}
+
 pub fn ensure_min_max_size(mut x: i32, min_size: i32, max_size: i32) -> i32 {
     if max_size > 0 {
         x = min(x, max_size);
@@ -404,6 +510,22 @@ pub fn baba_is_float_offset(now: Duration, view_height: f64) -> f64 {
     amplitude * ((f64::consts::TAU * now / 3.6).sin() - 1.)
 }
 
+/// Calls a logind manager method such as `Reboot`, allowing interactive authorization.
+#[cfg(feature = "dbus")]
+pub fn call_logind(method: &str) -> anyhow::Result<()> {
+    let conn = zbus::blocking::Connection::system()?;
+
+    conn.call_method(
+        Some("org.freedesktop.login1"),
+        "/org/freedesktop/login1",
+        Some("org.freedesktop.login1.Manager"),
+        method,
+        &(true),
+    )?;
+
+    Ok(())
+}
+
 #[cfg(feature = "dbus")]
 pub fn show_screenshot_notification(image_path: Option<PathBuf>) -> anyhow::Result<()> {
     use std::collections::HashMap;
@@ -0,0 +1,311 @@
+//! Sending the media keys to MPRIS media players and watching what they play.
+
+use std::collections::HashMap;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use smithay::input::keyboard::Keysym;
+use zbus::zvariant::OwnedValue;
+
+const PREFIX: &str = "org.mpris.MediaPlayer2.";
+
+/// How often the players are checked for what they play, while watching.
+const MEDIA_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKey {
+    PlayPause,
+    Pause,
+    Stop,
+    Next,
+    Previous,
+}
+
+impl MediaKey {
+    pub fn from_keysym(keysym: Keysym) -> Option<Self> {
+        let key = match keysym {
+            Keysym::XF86_AudioPlay => Self::PlayPause,
+            Keysym::XF86_AudioPause => Self::Pause,
+            Keysym::XF86_AudioStop => Self::Stop,
+            Keysym::XF86_AudioNext => Self::Next,
+            Keysym::XF86_AudioPrev => Self::Previous,
+            _ => return None,
+        };
+        Some(key)
+    }
+
+    fn method(self) -> &'static str {
+        match self {
+            Self::PlayPause => "PlayPause",
+            Self::Pause => "Pause",
+            Self::Stop => "Stop",
+            Self::Next => "Next",
+            Self::Previous => "Previous",
+        }
+    }
+}
+
+/// Connection to the MPRIS media players, kept in one long-lived worker thread since D-Bus calls
+/// can block.
+pub struct Mpris {
+    worker: Option<mpsc::Sender<Request>>,
+}
+
+enum Request {
+    Key(MediaKey),
+    /// Starts or stops sending what the players play.
+    WatchMedia(Option<calloop::channel::Sender<Option<String>>>),
+}
+
+struct Worker {
+    conn: zbus::blocking::Connection,
+    /// Bus name of the player that last got a key, which wins over other players unless one of
+    /// them is playing.
+    last_player: Option<String>,
+    media_watcher: Option<calloop::channel::Sender<Option<String>>>,
+}
+
+impl Mpris {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self { worker: None }
+    }
+
+    pub fn send_key(&mut self, key: MediaKey) {
+        self.send(Request::Key(key));
+    }
+
+    /// Starts sending a line describing the playing media every few seconds.
+    pub fn watch_media(&mut self, to_niri: calloop::channel::Sender<Option<String>>) {
+        self.send(Request::WatchMedia(Some(to_niri)));
+    }
+
+    pub fn unwatch_media(&mut self) {
+        if self.worker.is_some() {
+            self.send(Request::WatchMedia(None));
+        }
+    }
+
+    fn send(&mut self, request: Request) {
+        let request = match &self.worker {
+            Some(worker) => match worker.send(request) {
+                Ok(()) => return,
+                // The worker is gone, for example because it couldn't connect to the bus.
+                Err(mpsc::SendError(request)) => request,
+            },
+            None => request,
+        };
+
+        let (tx, rx) = mpsc::channel();
+        let res = thread::Builder::new()
+            .name("MPRIS".to_owned())
+            .spawn(move || run_worker(rx));
+        if let Err(err) = res {
+            warn!("error spawning the MPRIS thread: {err:?}");
+            self.worker = None;
+            return;
+        }
+
+        let _ = tx.send(request);
+        self.worker = Some(tx);
+    }
+}
+
+fn run_worker(rx: mpsc::Receiver<Request>) {
+    let conn = match zbus::blocking::Connection::session() {
+        Ok(conn) => conn,
+        Err(err) => {
+            debug!("error connecting to the session bus: {err:?}");
+            return;
+        }
+    };
+
+    let mut worker = Worker {
+        conn,
+        last_player: None,
+        media_watcher: None,
+    };
+
+    loop {
+        let request = if worker.media_watcher.is_some() {
+            match rx.recv_timeout(MEDIA_INTERVAL) {
+                Ok(request) => Some(request),
+                Err(mpsc::RecvTimeoutError::Timeout) => None,
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+        } else {
+            match rx.recv() {
+                Ok(request) => Some(request),
+                Err(mpsc::RecvError) => break,
+            }
+        };
+
+        match request {
+            Some(Request::Key(key)) => {
+                if let Err(err) = worker.send_key(key) {
+                    debug!("error sending {key:?} to a media player: {err:?}");
+                }
+            }
+            Some(Request::WatchMedia(watcher)) => worker.media_watcher = watcher,
+            None => (),
+        }
+
+        worker.update_media();
+    }
+}
+
+impl Worker {
+    fn send_key(&mut self, key: MediaKey) -> anyhow::Result<()> {
+        let players = list_players(&self.conn)?;
+        let Some(name) = pick_player(&players, self.last_player.as_deref()) else {
+            debug!("no MPRIS players to send {key:?} to");
+            return Ok(());
+        };
+        let name = name.to_owned();
+
+        player_proxy(&self.conn, &name)?.call_method(key.method(), &())?;
+        self.last_player = Some(name);
+
+        Ok(())
+    }
+
+    fn update_media(&mut self) {
+        let Some(watcher) = &self.media_watcher else {
+            return;
+        };
+
+        let media = match now_playing(&self.conn) {
+            Ok(media) => media,
+            Err(err) => {
+                debug!("error getting the playing media: {err:?}");
+                None
+            }
+        };
+
+        if watcher.send(media).is_err() {
+            self.media_watcher = None;
+        }
+    }
+}
+
+/// Returns the bus names of the MPRIS players with their playback statuses.
+fn list_players(conn: &zbus::blocking::Connection) -> anyhow::Result<Vec<(String, String)>> {
+    let dbus = zbus::blocking::fdo::DBusProxy::new(conn)?;
+
+    let mut players = Vec::new();
+    for name in dbus.list_names()? {
+        if !name.starts_with(PREFIX) {
+            continue;
+        }
+
+        let proxy = player_proxy(conn, name.as_str())?;
+        let status: String = proxy.get_property("PlaybackStatus").unwrap_or_default();
+        players.push((name.to_string(), status));
+    }
+
+    Ok(players)
+}
+
+/// Returns a line describing the media of a playing, or else a paused, player.
+fn now_playing(conn: &zbus::blocking::Connection) -> anyhow::Result<Option<String>> {
+    let mut paused = None;
+    for (name, status) in list_players(conn)? {
+        let is_playing = match &*status {
+            "Playing" => true,
+            "Paused" => false,
+            _ => continue,
+        };
+
+        let proxy = player_proxy(conn, &name)?;
+        let metadata: HashMap<String, OwnedValue> = proxy.get_property("Metadata")?;
+        let get = |key: &str| metadata.get(key).and_then(|value| value.try_clone().ok());
+        let title = get("xesam:title").and_then(|value| String::try_from(value).ok());
+        let artists = get("xesam:artist").and_then(|value| Vec::<String>::try_from(value).ok());
+
+        let Some(title) = title.filter(|title| !title.is_empty()) else {
+            continue;
+        };
+        let text = match artists.filter(|artists| !artists.is_empty()) {
+            Some(artists) => format!("{} – {title}", artists.join(", ")),
+            None => title,
+        };
+
+        if is_playing {
+            return Ok(Some(format!("▶ {text}")));
+        }
+        paused.get_or_insert_with(|| format!("⏸ {text}"));
+    }
+
+    Ok(paused)
+}
+
+fn player_proxy<'a>(
+    conn: &zbus::blocking::Connection,
+    name: &'a str,
+) -> zbus::Result<zbus::blocking::Proxy<'a>> {
+    zbus::blocking::Proxy::new(
+        conn,
+        name,
+        "/org/mpris/MediaPlayer2",
+        "org.mpris.MediaPlayer2.Player",
+    )
+}
+
+/// Picks the player to get a media key out of bus names and their playback statuses.
+///
+/// A playing player wins, then the last player that got a key, then a paused player, then any.
+fn pick_player<'a>(players: &'a [(String, String)], last: Option<&str>) -> Option<&'a str> {
+    let with_status = |status: &str| {
+        players
+            .iter()
+            .find(|(_, s)| s == status)
+            .map(|(name, _)| name.as_str())
+    };
+
+    with_status("Playing")
+        .or_else(|| {
+            players
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .find(|name| Some(*name) == last)
+        })
+        .or_else(|| with_status("Paused"))
+        .or_else(|| players.first().map(|(name, _)| name.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pick_player_order() {
+        let player = |name: &str, status: &str| (format!("{PREFIX}{name}"), status.to_owned());
+
+        let players = [
+            player("mpv", "Stopped"),
+            player("firefox", "Paused"),
+            player("spotify", "Playing"),
+        ];
+        let last = format!("{PREFIX}mpv");
+        assert_eq!(
+            pick_player(&players, Some(&last)),
+            Some("org.mpris.MediaPlayer2.spotify")
+        );
+
+        let players = &players[..2];
+        assert_eq!(
+            pick_player(players, Some(&last)),
+            Some("org.mpris.MediaPlayer2.mpv")
+        );
+        assert_eq!(
+            pick_player(players, None),
+            Some("org.mpris.MediaPlayer2.firefox")
+        );
+        assert_eq!(
+            pick_player(&players[..1], None),
+            Some("org.mpris.MediaPlayer2.mpv")
+        );
+        assert_eq!(pick_player(&[], None), None);
+    }
+}
@@ -15,6 +15,7 @@ use crate::utils::expand_home;
 
 pub static REMOVE_ENV_RUST_BACKTRACE: AtomicBool = AtomicBool::new(false);
 pub static REMOVE_ENV_RUST_LIB_BACKTRACE: AtomicBool = AtomicBool::new(false);
+pub static REMOVE_ENV_LIBGL_ALWAYS_SOFTWARE: AtomicBool = AtomicBool::new(false);
 pub static CHILD_ENV: RwLock<Environment> = RwLock::new(Environment(Vec::new()));
 pub static CHILD_DISPLAY: RwLock<Option<String>> = RwLock::new(None);
 
@@ -64,6 +65,22 @@ pub fn restore_nofile_rlimit() {
 
 /// Spawns the command to run independently of the compositor.
 pub fn spawn<T: AsRef<OsStr> + Send + 'static>(command: Vec<T>, token: Option<XdgActivationToken>) {
+    spawn_inner(command, token, Vec::new());
+}
+
+/// Spawns the command with extra environment variables to run independently of the compositor.
+pub fn spawn_with_env<T: AsRef<OsStr> + Send + 'static>(
+    command: Vec<T>,
+    env: Vec<(&'static str, String)>,
+) {
+    spawn_inner(command, None, env);
+}
+
+fn spawn_inner<T: AsRef<OsStr> + Send + 'static>(
+    command: Vec<T>,
+    token: Option<XdgActivationToken>,
+    env: Vec<(&'static str, String)>,
+) {
     let _span = tracy_client::span!();
 
     if command.is_empty() {
@@ -75,7 +92,7 @@ pub fn spawn<T: AsRef<OsStr> + Send + 'static>(command: Vec<T>, token: Option<Xd
         .name("Command Spawner".to_owned())
         .spawn(move || {
             let (command, args) = command.split_first().unwrap();
-            spawn_sync(command, args, token);
+            spawn_sync(command, args, token, &env);
         });
 
     if let Err(err) = res {
@@ -83,15 +100,99 @@ pub fn spawn<T: AsRef<OsStr> + Send + 'static>(command: Vec<T>, token: Option<Xd
     }
 }
 
+/// Spawns the command as a direct child of the compositor, to keep track of it.
+///
+/// Unlike [`spawn`], this runs synchronously and returns the child for the caller to wait on.
+pub fn spawn_child(
+    command: &[String],
+    environment: &Environment,
+    working_dir: Option<&str>,
+) -> Option<Child> {
+    let _span = tracy_client::span!();
+
+    let (name, args) = command.split_first()?;
+    let mut process = make_command(OsStr::new(name), args, None, &[]);
+
+    for var in &environment.0 {
+        if let Some(value) = &var.value {
+            process.env(&var.name, value);
+        } else {
+            process.env_remove(&var.name);
+        }
+    }
+
+    if let Some(dir) = working_dir {
+        let dir = Path::new(dir);
+        match expand_home(dir) {
+            Ok(Some(expanded)) => {
+                process.current_dir(expanded);
+            }
+            Ok(None) => {
+                process.current_dir(dir);
+            }
+            Err(err) => warn!("error expanding ~: {err:?}"),
+        }
+    }
+
+    unsafe {
+        process.pre_exec(|| {
+            restore_nofile_rlimit();
+            Ok(())
+        });
+    }
+
+    let child = match process.spawn() {
+        Ok(child) => child,
+        Err(err) => {
+            warn!("error spawning {name:?}: {err:?}");
+            return None;
+        }
+    };
+
+    // There's no intermediate child here to keep the scope from ending up empty (see do_spawn()),
+    // but the child is still alive in the common case, and we need its exit status anyway.
+    #[cfg(feature = "systemd")]
+    if let Err(err) = systemd::start_systemd_scope(OsStr::new(name), &[child.id()]) {
+        trace!("error starting systemd scope for spawned command: {err:?}");
+    }
+
+    Some(child)
+}
+
 fn spawn_sync(
     command: impl AsRef<OsStr>,
     args: impl IntoIterator<Item = impl AsRef<OsStr>>,
     token: Option<XdgActivationToken>,
+    extra_env: &[(&'static str, String)],
 ) {
     let _span = tracy_client::span!();
 
-    let mut command = command.as_ref();
+    let command = command.as_ref();
+    let process = make_command(command, args, token, extra_env);
+
+    let Some(mut child) = do_spawn(command, process) else {
+        return;
+    };
+
+    match child.wait() {
+        Ok(status) => {
+            if !status.success() {
+                warn!("child did not exit successfully: {status:?}");
+            }
+        }
+        Err(err) => {
+            warn!("error waiting for child: {err:?}");
+        }
+    }
+}
 
+/// Creates the command with the compositor's environment for children.
+fn make_command(
+    mut command: &OsStr,
+    args: impl IntoIterator<Item = impl AsRef<OsStr>>,
+    token: Option<XdgActivationToken>,
+    extra_env: &[(&'static str, String)],
+) -> Command {
     // Expand `~` at the start.
     let expanded = expand_home(Path::new(command));
     match &expanded {
@@ -109,13 +210,17 @@ fn spawn_sync(
         .stdout(Stdio::null())
         .stderr(Stdio::null());
 
-    // Remove RUST_BACKTRACE and RUST_LIB_BACKTRACE from the environment if needed.
+    // Remove the variables that niri set for itself from the environment if needed.
     if REMOVE_ENV_RUST_BACKTRACE.load(Ordering::Relaxed) {
         process.env_remove("RUST_BACKTRACE");
     }
     if REMOVE_ENV_RUST_LIB_BACKTRACE.load(Ordering::Relaxed) {
         process.env_remove("RUST_LIB_BACKTRACE");
     }
+    // Clients shouldn't render in software just because niri does.
+    if REMOVE_ENV_LIBGL_ALWAYS_SOFTWARE.load(Ordering::Relaxed) {
+        process.env_remove("LIBGL_ALWAYS_SOFTWARE");
+    }
 
     // Set DISPLAY if needed.
     let display = CHILD_DISPLAY.read().unwrap();
@@ -141,20 +246,11 @@ fn spawn_sync(
         process.env("DESKTOP_STARTUP_ID", token.as_str());
     }
 
-    let Some(mut child) = do_spawn(command, process) else {
-        return;
-    };
-
-    match child.wait() {
-        Ok(status) => {
-            if !status.success() {
-                warn!("child did not exit successfully: {status:?}");
-            }
-        }
-        Err(err) => {
-            warn!("error waiting for child: {err:?}");
-        }
+    for (name, value) in extra_env {
+        process.env(name, value);
     }
+
+    process
 }
 
 #[cfg(not(feature = "systemd"))]
@@ -308,7 +404,7 @@ mod systemd {
 
                     // Start a systemd scope for the grandchild.
                     #[cfg(feature = "systemd")]
-                    if let Err(err) = start_systemd_scope(command, child.id(), pid as u32) {
+                    if let Err(err) = start_systemd_scope(command, &[child.id(), pid as u32]) {
                         trace!("error starting systemd scope for spawned command: {err:?}");
                     }
                 }
@@ -362,12 +458,10 @@ mod systemd {
     ///
     /// This separates the pid from the compositor scope, which for example prevents the OOM killer
     /// from bringing down the compositor together with a misbehaving client.
+    ///
+    /// The scope is named after the last pid.
     #[cfg(feature = "systemd")]
-    fn start_systemd_scope(
-        name: &OsStr,
-        intermediate_pid: u32,
-        child_pid: u32,
-    ) -> anyhow::Result<()> {
+    pub fn start_systemd_scope(name: &OsStr, pids: &[u32]) -> anyhow::Result<()> {
         use std::fmt::Write as _;
         use std::os::unix::ffi::OsStrExt;
         use std::sync::OnceLock;
@@ -399,7 +493,7 @@ mod systemd {
             }
         }
 
-        let _ = write!(scope_name, "-{child_pid}.scope");
+        let _ = write!(scope_name, "-{}.scope", pids.last().unwrap());
 
         // Ask systemd to start a transient scope.
         static CONNECTION: OnceLock<zbus::Result<zbus::blocking::Connection>> = OnceLock::new();
@@ -420,7 +514,6 @@ mod systemd {
             .receive_signal("JobRemoved")
             .context("error creating a signal iterator")?;
 
-        let pids: &[_] = &[intermediate_pid, child_pid];
         let properties: &[_] = &[
             ("PIDs", Value::new(pids)),
             ("CollectMode", Value::new("inactive-or-failed")),
@@ -4,7 +4,7 @@ use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{mpsc, Arc};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 use smithay::reexports::calloop::channel::SyncSender;
 
@@ -19,17 +19,23 @@ impl Drop for Watcher {
 }
 
 impl Watcher {
+    /// Watches `path` and `extra_paths` for changes, calling `process` on every change.
+    ///
+    /// `process` receives the extra paths and can update them, for example when the set of files
+    /// included from the main file changes.
     pub fn new<T: Send + 'static>(
         path: PathBuf,
-        process: impl FnMut(&Path) -> T + Send + 'static,
+        extra_paths: Vec<PathBuf>,
+        process: impl FnMut(&Path, &mut Vec<PathBuf>) -> T + Send + 'static,
         changed: SyncSender<T>,
     ) -> Self {
-        Self::with_start_notification(path, process, changed, None)
+        Self::with_start_notification(path, extra_paths, process, changed, None)
     }
 
     pub fn with_start_notification<T: Send + 'static>(
         path: PathBuf,
-        mut process: impl FnMut(&Path) -> T + Send + 'static,
+        mut extra_paths: Vec<PathBuf>,
+        mut process: impl FnMut(&Path, &mut Vec<PathBuf>) -> T + Send + 'static,
         changed: SyncSender<T>,
         started: Option<mpsc::SyncSender<()>>,
     ) -> Self {
@@ -48,10 +54,9 @@ impl Watcher {
                     // because, on nix practically everything is a symlink to /nix/store
                     // and due to reproducibility, /nix/store keeps no mtime (= 1970-01-01)
                     // so, symlink targets change frequently when mtime doesn't.
-                    let mut last_props = path
-                        .canonicalize()
-                        .and_then(|canon| Ok((canon.metadata()?.modified()?, canon)))
-                        .ok();
+                    let mut last_props = file_props(&path);
+                    let mut last_extra_props: Vec<_> =
+                        extra_paths.iter().map(|p| file_props(p)).collect();
 
                     if let Some(started) = started {
                         let _ = started.send(());
@@ -64,14 +69,16 @@ impl Watcher {
                             break;
                         }
 
-                        if let Ok(new_props) = path
-                            .canonicalize()
-                            .and_then(|canon| Ok((canon.metadata()?.modified()?, canon)))
-                        {
-                            if last_props.as_ref() != Some(&new_props) {
+                        if let Some(new_props) = file_props(&path) {
+                            let new_extra_props: Vec<_> =
+                                extra_paths.iter().map(|p| file_props(p)).collect();
+
+                            if last_props.as_ref() != Some(&new_props)
+                                || last_extra_props != new_extra_props
+                            {
                                 trace!("file changed: {}", path.to_string_lossy());
 
-                                let rv = process(&path);
+                                let rv = process(&path, &mut extra_paths);
 
                                 if let Err(err) = changed.send(rv) {
                                     warn!("error sending change notification: {err:?}");
@@ -79,6 +86,9 @@ impl Watcher {
                                 }
 
                                 last_props = Some(new_props);
+                                // The extra paths could've changed during processing.
+                                last_extra_props =
+                                    extra_paths.iter().map(|p| file_props(p)).collect();
                             }
                         }
                     }
@@ -92,6 +102,12 @@ impl Watcher {
     }
 }
 
+fn file_props(path: &Path) -> Option<(SystemTime, PathBuf)> {
+    path.canonicalize()
+        .and_then(|canon| Ok((canon.metadata()?.modified()?, canon)))
+        .ok()
+}
+
 #[cfg(test)]
 mod tests {
     use std::error::Error;
@@ -130,8 +146,13 @@ mod tests {
 
         let (tx, rx) = sync_channel(1);
         let (started_tx, started_rx) = mpsc::sync_channel(1);
-        let _watcher =
-            Watcher::with_start_notification(config_path.clone(), |_| (), tx, Some(started_tx));
+        let _watcher = Watcher::with_start_notification(
+            config_path.clone(),
+            Vec::new(),
+            |_, _| (),
+            tx,
+            Some(started_tx),
+        );
         loop_handle
             .insert_source(rx, |_, _, _| {
                 changed.fetch_add(1, Ordering::SeqCst);
@@ -353,4 +374,58 @@ mod tests {
             },
         );
     }
+
+    #[test]
+    fn change_extra_file() {
+        let sh = Shell::new().unwrap();
+        let temp_dir = sh.create_temp_dir().unwrap();
+        sh.change_dir(temp_dir.path());
+
+        sh.write_file("niri/config.kdl", "a").unwrap();
+        sh.write_file("niri/extra.kdl", "a").unwrap();
+        let config_path = sh.current_dir().join("niri/config.kdl");
+        let extra_path = sh.current_dir().join("niri/extra.kdl");
+
+        let changed = AtomicU8::new(0);
+
+        let mut event_loop = EventLoop::try_new().unwrap();
+        let loop_handle = event_loop.handle();
+
+        let (tx, rx) = sync_channel(1);
+        let (started_tx, started_rx) = mpsc::sync_channel(1);
+        let _watcher = Watcher::with_start_notification(
+            config_path,
+            vec![extra_path.clone()],
+            // Stop watching the extra file after the first change.
+            |_, extra_paths| extra_paths.clear(),
+            tx,
+            Some(started_tx),
+        );
+        loop_handle
+            .insert_source(rx, |_, _, _| {
+                changed.fetch_add(1, Ordering::SeqCst);
+            })
+            .unwrap();
+        started_rx.recv().unwrap();
+
+        // HACK: if we don't sleep, files might have the same mtime.
+        thread::sleep(Duration::from_millis(100));
+
+        sh.write_file(&extra_path, "b").unwrap();
+
+        event_loop
+            .dispatch(Duration::from_millis(750), &mut ())
+            .unwrap();
+
+        assert_eq!(changed.load(Ordering::SeqCst), 1);
+
+        thread::sleep(Duration::from_millis(100));
+        sh.write_file(&extra_path, "c").unwrap();
+
+        event_loop
+            .dispatch(Duration::from_millis(750), &mut ())
+            .unwrap();
+
+        assert_eq!(changed.load(Ordering::SeqCst), 1);
+    }
 }
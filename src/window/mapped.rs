@@ -1,19 +1,22 @@
 use std::cell::{Cell, Ref, RefCell};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use niri_config::{Color, CornerRadius, GradientInterpolation, WindowRule};
+use niri_ipc::OpacityChange;
 use smithay::backend::renderer::element::surface::render_elements_from_surface_tree;
 use smithay::backend::renderer::element::Kind;
 use smithay::backend::renderer::gles::GlesRenderer;
 use smithay::desktop::space::SpaceElement as _;
 use smithay::desktop::{PopupManager, Window};
 use smithay::output::{self, Output};
+use smithay::reexports::wayland_protocols::wp::content_type::v1::server::wp_content_type_v1;
 use smithay::reexports::wayland_protocols::xdg::decoration::zv1::server::zxdg_toplevel_decoration_v1;
 use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel;
 use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::reexports::wayland_server::Resource as _;
 use smithay::utils::{Logical, Point, Rectangle, Scale, Serial, Size, Transform};
 use smithay::wayland::compositor::{remove_pre_commit_hook, with_states, HookId, SurfaceData};
+use smithay::wayland::content_type::ContentTypeSurfaceCachedState;
 use smithay::wayland::seat::WaylandFocus;
 use smithay::wayland::shell::xdg::{SurfaceCachedState, ToplevelSurface};
 use wayland_backend::server::Credentials;
@@ -32,11 +35,12 @@ use crate::render_helpers::snapshot::RenderSnapshot;
 use crate::render_helpers::solid_color::{SolidColorBuffer, SolidColorRenderElement};
 use crate::render_helpers::surface::render_snapshot_from_surface_tree;
 use crate::render_helpers::{BakedBuffer, RenderTarget, SplitElements};
+use crate::unresponsive::KILL_CONFIRMATION_TIMEOUT;
 use crate::utils::id::IdCounter;
 use crate::utils::transaction::Transaction;
 use crate::utils::{
-    get_credentials_for_surface, send_scale_transform, update_tiled_state, with_toplevel_role,
-    ResizeEdge,
+    get_credentials_for_surface, send_scale_transform, update_decoration_mode, update_tiled_state,
+    with_toplevel_role, ResizeEdge,
 };
 
 #[derive(Debug)]
@@ -80,6 +84,15 @@ pub struct Mapped {
     /// Whether this has an urgent indicator.
     is_urgent: bool,
 
+    /// When this last became urgent.
+    urgent_since: Option<Instant>,
+
+    /// Whether the client of this window stopped answering pings.
+    is_unresponsive: bool,
+
+    /// When the user asked to force kill this unresponsive window, waiting for a confirmation.
+    kill_requested_at: Option<Instant>,
+
     /// Whether this window has the keyboard focus.
     is_focused: bool,
 
@@ -92,9 +105,20 @@ pub struct Mapped {
     /// Whether this window is a target of a window cast.
     is_window_cast_target: bool,
 
+    /// Screenshot waiting for the window to draw after being suspended.
+    ///
+    /// Contains whether to write the screenshot to disk.
+    pending_screenshot: Option<bool>,
+
     /// Whether this window should ignore opacity set through window rules.
     ignore_opacity_window_rule: bool,
 
+    /// Opacity set through actions, kept until the window closes.
+    opacity_override: Option<f32>,
+
+    /// Always-on-top state toggled through actions, overriding the window rules.
+    always_on_top_override: Option<bool>,
+
     /// Buffer to draw instead of the window when it should be blocked out.
     block_out_buffer: RefCell<SolidColorBuffer>,
 
@@ -236,11 +260,17 @@ impl Mapped {
             needs_frame_callback: false,
             offscreen_data: RefCell::new(None),
             is_urgent: false,
+            is_unresponsive: false,
+            kill_requested_at: None,
+            urgent_since: None,
             is_focused: false,
             is_active_in_column: true,
             is_floating: false,
             is_window_cast_target: false,
+            pending_screenshot: None,
             ignore_opacity_window_rule: false,
+            opacity_override: None,
+            always_on_top_override: None,
             block_out_buffer: RefCell::new(SolidColorBuffer::new((0., 0.), [0., 0., 0., 1.])),
             animate_next_configure: false,
             animate_serials: Vec::new(),
@@ -260,6 +290,40 @@ impl Mapped {
         self.window.toplevel().expect("no X11 support")
     }
 
+    /// Returns the window geometry with the CSD margins from the window rules cut off.
+    pub fn geometry(&self) -> Rectangle<i32, Logical> {
+        let mut geo = self.window.geometry();
+
+        if let Some(margins) = self.rules.csd_margins {
+            if !self.has_fullscreen_state() {
+                geo.loc += Point::from((i32::from(margins.left), i32::from(margins.top)));
+                geo.size = self.rules.remove_csd_margins(geo.size);
+            }
+        }
+
+        geo
+    }
+
+    /// Returns whether the window has committed the Fullscreen state, including windowed
+    /// fullscreen.
+    ///
+    /// Windows don't draw their CSD margins in this state.
+    fn has_fullscreen_state(&self) -> bool {
+        with_toplevel_role(self.toplevel(), |role| {
+            role.current
+                .states
+                .contains(xdg_toplevel::State::Fullscreen)
+        })
+    }
+
+    fn remove_csd_margins(&self, size: Size<i32, Logical>) -> Size<i32, Logical> {
+        if self.has_fullscreen_state() {
+            size
+        } else {
+            self.rules.remove_csd_margins(size)
+        }
+    }
+
     /// Recomputes the resolved window rules and returns whether they changed.
     pub fn recompute_window_rules(&mut self, rules: &[WindowRule], is_at_startup: bool) -> bool {
         self.need_to_recompute_rules = false;
@@ -303,6 +367,22 @@ impl Mapped {
         self.credentials.as_ref()
     }
 
+    /// Returns the content type that the window set through the content-type protocol.
+    pub fn content_type(&self) -> wp_content_type_v1::Type {
+        with_states(self.toplevel().wl_surface(), |states| {
+            let mut guard = states.cached_state.get::<ContentTypeSurfaceCachedState>();
+            *guard.current().content_type()
+        })
+    }
+
+    /// Whether the window shows a video or a game, according to its content type.
+    pub fn is_video_or_game(&self) -> bool {
+        matches!(
+            self.content_type(),
+            wp_content_type_v1::Type::Video | wp_content_type_v1::Type::Game
+        )
+    }
+
     pub fn offscreen_data(&self) -> Ref<Option<OffscreenData>> {
         self.offscreen_data.borrow()
     }
@@ -315,6 +395,12 @@ impl Mapped {
         self.is_active_in_column
     }
 
+    /// Whether the window is told that it isn't visible.
+    pub fn is_suspended(&self) -> bool {
+        self.toplevel()
+            .with_pending_state(|state| state.states.contains(xdg_toplevel::State::Suspended))
+    }
+
     pub fn is_floating(&self) -> bool {
         self.is_floating
     }
@@ -323,10 +409,31 @@ impl Mapped {
         self.is_window_cast_target
     }
 
+    /// Keeps the window from being suspended until the requested screenshot is taken.
+    pub fn request_screenshot(&mut self, write_to_disk: bool) {
+        self.pending_screenshot = Some(write_to_disk);
+    }
+
+    pub fn take_pending_screenshot(&mut self) -> Option<bool> {
+        self.pending_screenshot.take()
+    }
+
     pub fn toggle_ignore_opacity_window_rule(&mut self) {
         self.ignore_opacity_window_rule = !self.ignore_opacity_window_rule;
     }
 
+    pub fn toggle_always_on_top(&mut self) {
+        self.always_on_top_override = Some(!self.is_always_on_top());
+    }
+
+    pub fn set_opacity(&mut self, change: OpacityChange) {
+        let opacity = match change {
+            OpacityChange::SetOpacity(opacity) => opacity,
+            OpacityChange::AdjustOpacity(delta) => f64::from(self.opacity()) + delta,
+        };
+        self.opacity_override = Some(opacity.clamp(0., 1.) as f32);
+    }
+
     pub fn set_is_focused(&mut self, is_focused: bool) {
         if self.is_focused == is_focused {
             return;
@@ -334,6 +441,7 @@ impl Mapped {
 
         self.is_focused = is_focused;
         self.is_urgent = false;
+        self.urgent_since = None;
         self.need_to_recompute_rules = true;
     }
 
@@ -361,7 +469,7 @@ impl Mapped {
             dst: None,
         }];
 
-        let buf_pos = self.window.geometry().loc.upscale(-1).to_f64();
+        let buf_pos = self.geometry().loc.upscale(-1).to_f64();
 
         let mut contents = vec![];
 
@@ -450,7 +558,7 @@ impl Mapped {
             .to_logical(scale);
         let radius = radius.fit_to(window_size.w as f32, window_size.h as f32);
 
-        let location = self.window.geometry().loc.to_f64() - bbox.loc.to_logical(scale);
+        let location = self.geometry().loc.to_f64() - bbox.loc.to_logical(scale);
         let elements = self.render(renderer, location, scale, 1., RenderTarget::Screencast);
 
         elements.into_iter().map(move |elem| {
@@ -513,6 +621,10 @@ impl Mapped {
         update_tiled_state(self.toplevel(), prefer_no_csd, self.rules.tiled_state);
     }
 
+    pub fn update_decoration_mode(&self) {
+        update_decoration_mode(self.toplevel(), self.rules.decoration_mode);
+    }
+
     pub fn is_windowed_fullscreen(&self) -> bool {
         self.is_windowed_fullscreen
     }
@@ -524,12 +636,47 @@ impl Mapped {
 
         let changed = self.is_urgent != urgent;
         self.is_urgent = urgent;
+        // Every new request for attention makes the window the most recently urgent one.
+        self.urgent_since = urgent.then(Instant::now);
         self.need_to_recompute_rules |= changed;
     }
 
     pub fn is_urgent(&self) -> bool {
         self.is_urgent
     }
+
+    /// When the window last became urgent, if it's urgent.
+    pub fn urgent_since(&self) -> Option<Instant> {
+        self.urgent_since
+    }
+
+    pub fn set_unresponsive(&mut self, unresponsive: bool) {
+        self.is_unresponsive = unresponsive;
+        if !unresponsive {
+            self.kill_requested_at = None;
+        }
+    }
+
+    pub fn is_unresponsive(&self) -> bool {
+        self.is_unresponsive
+    }
+
+    /// Records a force kill request, returns `true` if it confirms an earlier request.
+    pub fn request_kill(&mut self) -> bool {
+        if self.is_kill_pending() {
+            self.kill_requested_at = None;
+            return true;
+        }
+
+        self.kill_requested_at = Some(Instant::now());
+        false
+    }
+
+    /// Whether a force kill request is waiting for a confirmation.
+    pub fn is_kill_pending(&self) -> bool {
+        self.kill_requested_at
+            .is_some_and(|at| at.elapsed() < KILL_CONFIRMATION_TIMEOUT)
+    }
 }
 
 impl Drop for Mapped {
@@ -546,15 +693,31 @@ impl LayoutElement for Mapped {
     }
 
     fn size(&self) -> Size<i32, Logical> {
-        self.window.geometry().size
+        let mut size = self.geometry().size;
+
+        // Windows in forced windowed fullscreen tend to ignore the configured size and draw for the
+        // whole output. Keep them to the size of their tile.
+        if self.is_windowed_fullscreen && self.rules.force_windowed_fullscreen == Some(true) {
+            if let Some(configured) = with_toplevel_role(self.toplevel(), |role| role.current.size)
+            {
+                if configured.w > 0 {
+                    size.w = size.w.min(configured.w);
+                }
+                if configured.h > 0 {
+                    size.h = size.h.min(configured.h);
+                }
+            }
+        }
+
+        size
     }
 
     fn buf_loc(&self) -> Point<i32, Logical> {
-        Point::from((0, 0)) - self.window.geometry().loc
+        Point::from((0, 0)) - self.geometry().loc
     }
 
     fn is_in_input_region(&self, point: Point<f64, Logical>) -> bool {
-        let surface_local = point + self.window.geometry().loc.to_f64();
+        let surface_local = point + self.geometry().loc.to_f64();
         self.window.is_in_input_region(&surface_local)
     }
 
@@ -570,12 +733,12 @@ impl LayoutElement for Mapped {
 
         if target.should_block_out(self.rules.block_out_from) {
             let mut buffer = self.block_out_buffer.borrow_mut();
-            buffer.resize(self.window.geometry().size.to_f64());
+            buffer.resize(self.geometry().size.to_f64());
             let elem =
                 SolidColorRenderElement::from_buffer(&buffer, location, alpha, Kind::Unspecified);
             rv.normal.push(elem.into());
         } else {
-            let buf_pos = location - self.window.geometry().loc.to_f64();
+            let buf_pos = location - self.geometry().loc.to_f64();
 
             let surface = self.toplevel().wl_surface();
             for (popup, popup_offset) in PopupManager::popups_for_surface(surface) {
@@ -614,12 +777,12 @@ impl LayoutElement for Mapped {
     ) -> Vec<LayoutElementRenderElement<R>> {
         if target.should_block_out(self.rules.block_out_from) {
             let mut buffer = self.block_out_buffer.borrow_mut();
-            buffer.resize(self.window.geometry().size.to_f64());
+            buffer.resize(self.geometry().size.to_f64());
             let elem =
                 SolidColorRenderElement::from_buffer(&buffer, location, alpha, Kind::Unspecified);
             vec![elem.into()]
         } else {
-            let buf_pos = location - self.window.geometry().loc.to_f64();
+            let buf_pos = location - self.geometry().loc.to_f64();
             let surface = self.toplevel().wl_surface();
             render_elements_from_surface_tree(
                 renderer,
@@ -645,7 +808,7 @@ impl LayoutElement for Mapped {
         } else {
             let mut rv = vec![];
 
-            let buf_pos = location - self.window.geometry().loc.to_f64();
+            let buf_pos = location - self.geometry().loc.to_f64();
             let surface = self.toplevel().wl_surface();
             for (popup, popup_offset) in PopupManager::popups_for_surface(surface) {
                 let offset = self.window.geometry().loc + popup_offset - popup.geometry().loc;
@@ -682,6 +845,12 @@ impl LayoutElement for Mapped {
             }
         }
 
+        let size = if is_fullscreen || self.is_pending_windowed_fullscreen {
+            size
+        } else {
+            self.rules.add_csd_margins(size)
+        };
+
         let changed = self.toplevel().with_pending_state(|state| {
             let changed = state.size != Some(size);
             state.size = Some(size);
@@ -714,6 +883,12 @@ impl LayoutElement for Mapped {
         // longer participate in any transactions with other windows.
         self.transaction_for_next_configure = None;
 
+        let size = if self.is_pending_windowed_fullscreen {
+            size
+        } else {
+            self.rules.add_csd_margins(size)
+        };
+
         // If our last requested size already matches the size we want to request-once, clear the
         // size request right away. However, we must also check if we're unfullscreening, because
         // in that case the window itself will restore its previous size upon receiving a (0, 0)
@@ -780,6 +955,7 @@ impl LayoutElement for Mapped {
             let mut guard = state.cached_state.get::<SurfaceCachedState>();
             guard.current().min_size
         });
+        let min_size = self.remove_csd_margins(min_size);
 
         self.rules.apply_min_size(min_size)
     }
@@ -789,6 +965,7 @@ impl LayoutElement for Mapped {
             let mut guard = state.cached_state.get::<SurfaceCachedState>();
             guard.current().max_size
         });
+        let max_size = self.remove_csd_margins(max_size);
 
         self.rules.apply_max_size(max_size)
     }
@@ -854,6 +1031,14 @@ impl LayoutElement for Mapped {
         self.is_urgent
     }
 
+    fn is_unresponsive(&self) -> bool {
+        self.is_unresponsive
+    }
+
+    fn is_kill_pending(&self) -> bool {
+        Mapped::is_kill_pending(self)
+    }
+
     fn set_activated(&mut self, active: bool) {
         let changed = self.toplevel().with_pending_state(|state| {
             if active {
@@ -865,6 +1050,20 @@ impl LayoutElement for Mapped {
         self.need_to_recompute_rules |= changed;
     }
 
+    fn set_suspended(&mut self, suspended: bool) {
+        // Casts and screenshots need the window to keep drawing.
+        let suspended =
+            suspended && !self.is_window_cast_target && self.pending_screenshot.is_none();
+
+        self.toplevel().with_pending_state(|state| {
+            if suspended {
+                state.states.set(xdg_toplevel::State::Suspended);
+            } else {
+                state.states.unset(xdg_toplevel::State::Suspended);
+            }
+        });
+    }
+
     fn set_active_in_column(&mut self, active: bool) {
         let changed = self.is_active_in_column != active;
         self.is_active_in_column = active;
@@ -1047,17 +1246,37 @@ impl LayoutElement for Mapped {
             .with_pending_state(|state| state.states.contains(xdg_toplevel::State::Fullscreen))
     }
 
-    fn is_ignoring_opacity_window_rule(&self) -> bool {
-        self.ignore_opacity_window_rule
+    fn is_always_on_top(&self) -> bool {
+        self.always_on_top_override
+            .unwrap_or(self.rules.always_on_top == Some(true))
+    }
+
+    fn opacity(&self) -> f32 {
+        if let Some(opacity) = self.opacity_override {
+            return opacity;
+        }
+
+        if self.ignore_opacity_window_rule {
+            return 1.;
+        }
+
+        self.rules.opacity.unwrap_or(1.).clamp(0., 1.)
     }
 
     fn requested_size(&self) -> Option<Size<i32, Logical>> {
-        self.toplevel().with_pending_state(|state| state.size)
+        self.toplevel().with_pending_state(|state| {
+            let size = state.size?;
+            if state.states.contains(xdg_toplevel::State::Fullscreen) {
+                Some(size)
+            } else {
+                Some(self.rules.remove_csd_margins(size))
+            }
+        })
     }
 
     fn expected_size(&self) -> Option<Size<i32, Logical>> {
         // We can only use current size if it's not fullscreen.
-        let current_size = (!self.is_fullscreen()).then(|| self.window.geometry().size);
+        let current_size = (!self.is_fullscreen()).then(|| self.geometry().size);
 
         // Check if we should be using the current window size.
         //
@@ -1119,6 +1338,10 @@ impl LayoutElement for Mapped {
                 return None;
             }
 
+            if !fullscreen {
+                size = self.rules.remove_csd_margins(size);
+            }
+
             // If some component of the pending size is zero, substitute it with the current window
             // size. But only if the current size is not fullscreen.
             if size.w == 0 {
@@ -1,8 +1,9 @@
+use std::cell::Cell;
 use std::cmp::{max, min};
 
 use niri_config::{
-    BlockOutFrom, BorderRule, CornerRadius, FloatingPosition, Match, PresetSize, ShadowRule,
-    TabIndicatorRule, WindowRule,
+    ActivationPolicy, Animation, BlockOutFrom, BorderRule, Color, CornerRadius, CsdMargins,
+    DecorationMode, FloatingPosition, Match, PresetSize, ShadowRule, TabIndicatorRule, WindowRule,
 };
 use niri_ipc::ColumnDisplay;
 use smithay::reexports::wayland_protocols::xdg::shell::server::xdg_toplevel;
@@ -12,6 +13,7 @@ use smithay::wayland::shell::xdg::{
     SurfaceCachedState, ToplevelSurface, XdgToplevelSurfaceRoleAttributes,
 };
 
+use crate::niri::ClientState;
 use crate::utils::with_toplevel_role;
 
 pub mod mapped;
@@ -20,6 +22,23 @@ pub use mapped::Mapped;
 pub mod unmapped;
 pub use unmapped::{InitialConfigureState, Unmapped};
 
+/// Whether a toplevel is a modal dialog, set through xdg-dialog.
+#[derive(Debug, Default)]
+pub struct ModalState(pub Cell<bool>);
+
+/// App id that the activation token used by a new toplevel was made for.
+#[derive(Debug)]
+pub struct ActivationAppId(pub String);
+
+/// Matcher inputs that live outside the toplevel role.
+///
+/// Reading them locks the surface states, so they are read before locking the role.
+struct ExtraMatchData {
+    is_modal: bool,
+    activation_app_id: Option<String>,
+    sandbox_app_id: Option<String>,
+}
+
 /// Reference to a mapped or unmapped window.
 #[derive(Debug, Clone, Copy)]
 pub enum WindowRef<'a> {
@@ -50,8 +69,8 @@ pub struct ResolvedWindowRules {
     /// Default floating position for this window.
     pub default_floating_position: Option<FloatingPosition>,
 
-    /// Output to open this window on.
-    pub open_on_output: Option<String>,
+    /// Outputs to open this window on, in the order of preference.
+    pub open_on_output: Vec<String>,
 
     /// Workspace to open this window on.
     pub open_on_workspace: Option<String>,
@@ -68,6 +87,9 @@ pub struct ResolvedWindowRules {
     /// Whether the window should open focused.
     pub open_focused: Option<bool>,
 
+    /// Whether the window should open as the backdrop of its output instead of in the layout.
+    pub open_as_backdrop: Option<bool>,
+
     /// Extra bound on the minimum window width.
     pub min_width: Option<u16>,
     /// Extra bound on the minimum window height.
@@ -100,6 +122,12 @@ pub struct ResolvedWindowRules {
     /// Whether to clip this window to its geometry, including the corner radius.
     pub clip_to_geometry: Option<bool>,
 
+    /// Margins that the window draws around its geometry without reporting them.
+    pub csd_margins: Option<CsdMargins>,
+
+    /// Color to fill the window area with behind the window.
+    pub background_color: Option<Color>,
+
     /// Whether to bob this window up and down.
     pub baba_is_float: Option<bool>,
 
@@ -114,6 +142,27 @@ pub struct ResolvedWindowRules {
 
     /// Override whether to set the Tiled xdg-toplevel state on the window.
     pub tiled_state: Option<bool>,
+
+    /// Override the policy for activation requests without recent user interaction.
+    pub activation_policy: Option<ActivationPolicy>,
+
+    /// Decoration mode to force regardless of what the client requests.
+    pub decoration_mode: Option<DecorationMode>,
+
+    /// Override for the window movement animation.
+    pub window_movement_animation: Option<Animation>,
+
+    /// Override for the window resize animation.
+    pub window_resize_animation: Option<Animation>,
+
+    /// Whether the window, when floating, stays above all other windows.
+    pub always_on_top: Option<bool>,
+
+    /// Whether fullscreen requests put the window in windowed fullscreen instead.
+    pub force_windowed_fullscreen: Option<bool>,
+
+    /// Whether to pass compositor shortcuts through to the window while it is fullscreen.
+    pub inhibit_shortcuts_when_fullscreen: Option<bool>,
 }
 
 impl<'a> WindowRef<'a> {
@@ -166,6 +215,43 @@ impl<'a> WindowRef<'a> {
             WindowRef::Mapped(mapped) => mapped.is_window_cast_target(),
         }
     }
+
+    /// Whether this is an X11 window, shown through xwayland-satellite.
+    pub fn is_xwayland(self) -> bool {
+        let Some(client) = self.toplevel().wl_surface().client() else {
+            return false;
+        };
+        client
+            .get_data::<ClientState>()
+            .is_some_and(|data| data.is_xwayland)
+    }
+
+    /// App id of the sandboxed app, like a Flatpak id, from the security context.
+    pub fn sandbox_app_id(self) -> Option<String> {
+        let client = self.toplevel().wl_surface().client()?;
+        let data = client.get_data::<ClientState>()?;
+        data.sandbox_app_id.clone()
+    }
+
+    fn extra_match_data(self) -> ExtraMatchData {
+        let (is_modal, activation_app_id) = with_states(self.toplevel().wl_surface(), |states| {
+            let is_modal = states
+                .data_map
+                .get::<ModalState>()
+                .is_some_and(|state| state.0.get());
+            let activation_app_id = states
+                .data_map
+                .get::<ActivationAppId>()
+                .map(|app_id| app_id.0.clone());
+            (is_modal, activation_app_id)
+        });
+
+        ExtraMatchData {
+            is_modal,
+            activation_app_id,
+            sandbox_app_id: self.sandbox_app_id(),
+        }
+    }
 }
 
 impl ResolvedWindowRules {
@@ -175,12 +261,13 @@ impl ResolvedWindowRules {
             default_height: None,
             default_column_display: None,
             default_floating_position: None,
-            open_on_output: None,
+            open_on_output: Vec::new(),
             open_on_workspace: None,
             open_maximized: None,
             open_fullscreen: None,
             open_floating: None,
             open_focused: None,
+            open_as_backdrop: None,
             min_width: None,
             min_height: None,
             max_width: None,
@@ -229,11 +316,20 @@ impl ResolvedWindowRules {
             opacity: None,
             geometry_corner_radius: None,
             clip_to_geometry: None,
+            csd_margins: None,
+            background_color: None,
             baba_is_float: None,
             block_out_from: None,
             variable_refresh_rate: None,
             scroll_factor: None,
             tiled_state: None,
+            activation_policy: None,
+            decoration_mode: None,
+            window_movement_animation: None,
+            window_resize_animation: None,
+            always_on_top: None,
+            force_windowed_fullscreen: None,
+            inhibit_shortcuts_when_fullscreen: None,
         }
     }
 
@@ -241,6 +337,7 @@ impl ResolvedWindowRules {
         let _span = tracy_client::span!("ResolvedWindowRules::compute");
 
         let mut resolved = ResolvedWindowRules::empty();
+        let extra = window.extra_match_data();
 
         with_toplevel_role(window.toplevel(), |role| {
             // Ensure server_pending like in Smithay's with_pending_state().
@@ -259,7 +356,7 @@ impl ResolvedWindowRules {
                         }
                     }
 
-                    window_matches(window, role, m)
+                    window_matches(window, role, &extra, m)
                 };
 
                 if !(rule.matches.is_empty() || rule.matches.iter().any(matches)) {
@@ -310,6 +407,10 @@ impl ResolvedWindowRules {
                     resolved.open_focused = Some(x);
                 }
 
+                if let Some(x) = rule.open_as_backdrop {
+                    resolved.open_as_backdrop = Some(x);
+                }
+
                 if let Some(x) = rule.min_width {
                     resolved.min_width = Some(x);
                 }
@@ -340,6 +441,12 @@ impl ResolvedWindowRules {
                 if let Some(x) = rule.clip_to_geometry {
                     resolved.clip_to_geometry = Some(x);
                 }
+                if let Some(x) = rule.csd_margins {
+                    resolved.csd_margins = Some(x);
+                }
+                if let Some(x) = rule.background_color {
+                    resolved.background_color = Some(x);
+                }
                 if let Some(x) = rule.baba_is_float {
                     resolved.baba_is_float = Some(x);
                 }
@@ -355,9 +462,30 @@ impl ResolvedWindowRules {
                 if let Some(x) = rule.tiled_state {
                     resolved.tiled_state = Some(x);
                 }
+                if let Some(x) = rule.activation_policy {
+                    resolved.activation_policy = Some(x);
+                }
+                if let Some(x) = rule.decoration_mode {
+                    resolved.decoration_mode = Some(x);
+                }
+                if let Some(x) = &rule.window_movement_animation {
+                    resolved.window_movement_animation = Some(x.0);
+                }
+                if let Some(x) = &rule.window_resize_animation {
+                    resolved.window_resize_animation = Some(x.anim);
+                }
+                if let Some(x) = rule.always_on_top {
+                    resolved.always_on_top = Some(x);
+                }
+                if let Some(x) = rule.force_windowed_fullscreen {
+                    resolved.force_windowed_fullscreen = Some(x);
+                }
+                if let Some(x) = rule.inhibit_shortcuts_when_fullscreen {
+                    resolved.inhibit_shortcuts_when_fullscreen = Some(x);
+                }
             }
 
-            resolved.open_on_output = open_on_output.map(|x| x.to_owned());
+            resolved.open_on_output = open_on_output.map(|x| x.to_vec()).unwrap_or_default();
             resolved.open_on_workspace = open_on_workspace.map(|x| x.to_owned());
         });
 
@@ -408,6 +536,48 @@ impl ResolvedWindowRules {
         (min_size, max_size)
     }
 
+    /// Grows a size that we request from the window by its CSD margins.
+    ///
+    /// Zero components are left as is, since they let the window pick its own size.
+    pub fn add_csd_margins(&self, size: Size<i32, Logical>) -> Size<i32, Logical> {
+        let Some(margins) = self.csd_margins else {
+            return size;
+        };
+
+        let mut size = size;
+        if size.w > 0 {
+            size.w += i32::from(margins.left) + i32::from(margins.right);
+        }
+        if size.h > 0 {
+            size.h += i32::from(margins.top) + i32::from(margins.bottom);
+        }
+        size
+    }
+
+    /// Shrinks a size that the window reports by its CSD margins.
+    ///
+    /// Zero components are left as is, since they mean that the size is unset.
+    pub fn remove_csd_margins(&self, size: Size<i32, Logical>) -> Size<i32, Logical> {
+        let Some(margins) = self.csd_margins else {
+            return size;
+        };
+
+        let mut size = size;
+        if size.w > 0 {
+            size.w = max(
+                size.w - i32::from(margins.left) - i32::from(margins.right),
+                1,
+            );
+        }
+        if size.h > 0 {
+            size.h = max(
+                size.h - i32::from(margins.top) - i32::from(margins.bottom),
+                1,
+            );
+        }
+        size
+    }
+
     pub fn compute_open_floating(&self, toplevel: &ToplevelSurface) -> bool {
         if let Some(res) = self.open_floating {
             return res;
@@ -430,7 +600,12 @@ impl ResolvedWindowRules {
     }
 }
 
-fn window_matches(window: WindowRef, role: &XdgToplevelSurfaceRoleAttributes, m: &Match) -> bool {
+fn window_matches(
+    window: WindowRef,
+    role: &XdgToplevelSurfaceRoleAttributes,
+    extra: &ExtraMatchData,
+    m: &Match,
+) -> bool {
     // Must be ensured by the caller.
     let server_pending = role.server_pending.as_ref().unwrap();
 
@@ -492,5 +667,41 @@ fn window_matches(window: WindowRef, role: &XdgToplevelSurfaceRoleAttributes, m:
         }
     }
 
+    if let Some(is_xwayland) = m.is_xwayland {
+        if window.is_xwayland() != is_xwayland {
+            return false;
+        }
+    }
+
+    if let Some(has_parent) = m.has_parent {
+        if role.parent.is_some() != has_parent {
+            return false;
+        }
+    }
+
+    if let Some(is_modal) = m.is_modal {
+        if extra.is_modal != is_modal {
+            return false;
+        }
+    }
+
+    if let Some(sandbox_app_id_re) = &m.sandbox_app_id {
+        let Some(sandbox_app_id) = &extra.sandbox_app_id else {
+            return false;
+        };
+        if !sandbox_app_id_re.0.is_match(sandbox_app_id) {
+            return false;
+        }
+    }
+
+    if let Some(activation_app_id_re) = &m.activation_app_id {
+        let Some(activation_app_id) = &extra.activation_app_id else {
+            return false;
+        };
+        if !activation_app_id_re.0.is_match(activation_app_id) {
+            return false;
+        }
+    }
+
     true
 }